@@ -88,6 +88,69 @@ pub enum UnOp {
     Not,
 }
 
+/// The result of evaluating a constant expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Evaluation {
+    /// A string.
+    String(String),
+    /// A number.
+    Number(f64),
+    /// A boolean.
+    Boolean(bool),
+    /// `null`.
+    Null,
+}
+
+impl Evaluation {
+    /// Returns whether this evaluation is "truthy," per GitHub Actions'
+    /// coercion rules: everything except `false`, `0`, `-0`, `NaN`,
+    /// `null` and the empty string is truthy.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Evaluation::String(s) => !s.is_empty(),
+            Evaluation::Number(n) => *n != 0.0 && !n.is_nan(),
+            Evaluation::Boolean(b) => *b,
+            Evaluation::Null => false,
+        }
+    }
+
+    /// Coerces this evaluation to a number, for comparisons between
+    /// evaluations of different types.
+    fn as_number(&self) -> f64 {
+        match self {
+            Evaluation::String(s) if s.trim().is_empty() => 0.0,
+            Evaluation::String(s) => s.trim().parse().unwrap_or(f64::NAN),
+            Evaluation::Number(n) => *n,
+            Evaluation::Boolean(b) => f64::from(u8::from(*b)),
+            Evaluation::Null => 0.0,
+        }
+    }
+
+    /// Renders this evaluation as a string, as `format()` would.
+    fn as_string(&self) -> String {
+        match self {
+            Evaluation::String(s) => s.clone(),
+            Evaluation::Number(n) => n.to_string(),
+            Evaluation::Boolean(b) => b.to_string(),
+            Evaluation::Null => String::new(),
+        }
+    }
+
+    /// Compares two evaluations with GitHub Actions' loose semantics:
+    /// strings are compared case-insensitively, and evaluations of
+    /// different types are compared as numbers.
+    ///
+    /// Returns `None` if the evaluations are incomparable, e.g. `NaN`.
+    fn compare(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Evaluation::String(lhs), Evaluation::String(rhs)) => {
+                Some(lhs.to_lowercase().cmp(&rhs.to_lowercase()))
+            }
+            (lhs, rhs) => lhs.as_number().partial_cmp(&rhs.as_number()),
+        }
+    }
+}
+
 /// Represents a GitHub Actions expression.
 #[derive(Debug, PartialEq)]
 pub enum Expr<'src> {
@@ -182,24 +245,129 @@ impl<'src> Expr<'src> {
             Expr::BinOp { lhs, op: _, rhs } => lhs.constant_reducible() && rhs.constant_reducible(),
             // Unops are reducible if their interior expression is reducible.
             Expr::UnOp { op: _, expr } => expr.constant_reducible(),
-            Expr::Call { func, args } => {
-                // These functions are reducible if their arguments are reducible.
+            // These functions are reducible if their arguments are reducible.
+            Expr::Call { func, args }
                 if func == "format"
                     || func == "contains"
                     || func == "startsWith"
-                    || func == "endsWith"
-                {
-                    args.iter().all(Expr::constant_reducible)
-                } else {
-                    // TODO: fromJSON(toJSON(...)) and vice versa.
-                    false
-                }
+                    || func == "endsWith" =>
+            {
+                args.iter().all(Expr::constant_reducible)
             }
             // Everything else is presumed non-reducible.
+            // TODO: fromJSON(toJSON(...)) and vice versa.
             _ => false,
         }
     }
 
+    /// Evaluates the expression, if it's [constant reducible].
+    ///
+    /// Returns `None` if the expression isn't constant reducible, or
+    /// if its evaluation isn't well-defined (e.g. a malformed `format()`
+    /// string).
+    ///
+    /// [constant reducible]: Self::constant_reducible
+    pub fn consteval(&self) -> Option<Evaluation> {
+        use std::cmp::Ordering;
+
+        match self {
+            Expr::Number(n) => Some(Evaluation::Number(*n)),
+            Expr::String(s) => Some(Evaluation::String(s.clone())),
+            Expr::Boolean(b) => Some(Evaluation::Boolean(*b)),
+            Expr::Null => Some(Evaluation::Null),
+            Expr::BinOp { lhs, op, rhs } => {
+                let lhs = lhs.consteval()?;
+
+                // `&&` and `||` evaluate to one of their operands.
+                match op {
+                    BinOp::And if !lhs.is_truthy() => return Some(lhs),
+                    BinOp::Or if lhs.is_truthy() => return Some(lhs),
+                    BinOp::And | BinOp::Or => return rhs.consteval(),
+                    _ => {}
+                }
+
+                let ordering = lhs.compare(&rhs.consteval()?);
+                let result = match op {
+                    BinOp::Eq => ordering == Some(Ordering::Equal),
+                    BinOp::Neq => ordering != Some(Ordering::Equal),
+                    BinOp::Gt => ordering == Some(Ordering::Greater),
+                    BinOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                    BinOp::Lt => ordering == Some(Ordering::Less),
+                    BinOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                    BinOp::And | BinOp::Or => unreachable!(),
+                };
+
+                Some(Evaluation::Boolean(result))
+            }
+            Expr::UnOp {
+                op: UnOp::Not,
+                expr,
+            } => Some(Evaluation::Boolean(!expr.consteval()?.is_truthy())),
+            Expr::Call { func, args } => {
+                let args = args
+                    .iter()
+                    .map(Expr::consteval)
+                    .collect::<Option<Vec<_>>>()?;
+
+                let string_test = |test: fn(&str, &str) -> bool| match args.as_slice() {
+                    [haystack, needle] => Some(Evaluation::Boolean(test(
+                        &haystack.as_string().to_lowercase(),
+                        &needle.as_string().to_lowercase(),
+                    ))),
+                    _ => None,
+                };
+
+                if func == "contains" {
+                    string_test(|haystack, needle| haystack.contains(needle))
+                } else if func == "startsWith" {
+                    string_test(|haystack, prefix| haystack.starts_with(prefix))
+                } else if func == "endsWith" {
+                    string_test(|haystack, suffix| haystack.ends_with(suffix))
+                } else if func == "format" {
+                    let (template, args) = args.split_first()?;
+                    Self::format(&template.as_string(), args).map(Evaluation::String)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Evaluates `format(template, args...)`, returning `None` if
+    /// `template` is malformed or refers to a missing argument.
+    fn format(template: &str, args: &[Evaluation]) -> Option<String> {
+        let mut result = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    result.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    result.push('}');
+                }
+                '{' => {
+                    let mut index = String::new();
+                    loop {
+                        match chars.next()? {
+                            '}' => break,
+                            c => index.push(c),
+                        }
+                    }
+                    result.push_str(&args.get(index.parse::<usize>().ok()?)?.as_string());
+                }
+                '}' => return None,
+                c => result.push(c),
+            }
+        }
+
+        Some(result)
+    }
+
     /// Like [`Self::constant_reducible`], but for all subexpressions
     /// rather than the top-level expression.
     ///
@@ -237,18 +405,16 @@ impl<'src> Expr<'src> {
     /// `${{ foo.bar == 'abc' }}` returns no expanded contexts,
     /// since the value of `foo.bar` flows into a boolean evaluation
     /// that gets expanded.
-    pub fn dataflow_contexts(&self) -> Vec<&Context> {
+    pub fn dataflow_contexts(&self) -> Vec<&Context<'_>> {
         let mut contexts = vec![];
 
         match self {
-            Expr::Call { func, args } => {
-                // These functions, when evaluated, produce an evaluation
-                // that includes some or all of the contexts listed in
-                // their arguments.
-                if func == "toJSON" || func == "format" || func == "join" {
-                    for arg in args {
-                        contexts.extend(arg.dataflow_contexts());
-                    }
+            // These functions, when evaluated, produce an evaluation
+            // that includes some or all of the contexts listed in
+            // their arguments.
+            Expr::Call { func, args } if func == "toJSON" || func == "format" || func == "join" => {
+                for arg in args {
+                    contexts.extend(arg.dataflow_contexts());
                 }
             }
             // NOTE: We intentionally don't handle the `func(...).foo.bar`
@@ -278,7 +444,7 @@ impl<'src> Expr<'src> {
     }
 
     /// Parses the given string into an expression.
-    pub fn parse(expr: &str) -> Result<Expr> {
+    pub fn parse(expr: &str) -> Result<Expr<'_>> {
         // Top level `expression` is a single `or_expr`.
         let or_expr = ExprParser::parse(Rule::expression, expr)?
             .next()
@@ -287,7 +453,7 @@ impl<'src> Expr<'src> {
             .next()
             .unwrap();
 
-        fn parse_pair(pair: Pair<'_, Rule>) -> Result<Box<Expr>> {
+        fn parse_pair(pair: Pair<'_, Rule>) -> Result<Box<Expr<'_>>> {
             // We're parsing a pest grammar, which isn't left-recursive.
             // As a result, we have constructions like
             // `or_expr = { and_expr ~ ("||" ~ and_expr)* }`, which
@@ -455,7 +621,7 @@ mod tests {
     use pest::Parser as _;
     use pretty_assertions::assert_eq;
 
-    use super::{BinOp, Context, Expr, ExprParser, Function, Rule, UnOp};
+    use super::{BinOp, Context, Evaluation, Expr, ExprParser, Function, Rule, UnOp};
 
     #[test]
     fn test_function_eq() {
@@ -828,6 +994,54 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_expr_consteval() -> Result<()> {
+        for (expr, evaluation) in &[
+            ("'foo'", Some(Evaluation::String("foo".into()))),
+            ("1", Some(Evaluation::Number(1.0))),
+            ("null", Some(Evaluation::Null)),
+            // Every non-empty string is truthy, including 'false'.
+            ("!'false'", Some(Evaluation::Boolean(false))),
+            ("!''", Some(Evaluation::Boolean(true))),
+            // && and || evaluate to one of their operands.
+            ("'a' && 'b'", Some(Evaluation::String("b".into()))),
+            ("'' && 'b'", Some(Evaluation::String("".into()))),
+            ("0 || 'b'", Some(Evaluation::String("b".into()))),
+            ("null || false", Some(Evaluation::Boolean(false))),
+            // Strings are compared case-insensitively.
+            ("'FOO' == 'foo'", Some(Evaluation::Boolean(true))),
+            ("'a' < 'B'", Some(Evaluation::Boolean(true))),
+            // Mixed types are compared as numbers.
+            ("'1' == 1", Some(Evaluation::Boolean(true))),
+            ("true == 1", Some(Evaluation::Boolean(true))),
+            ("null == 0", Some(Evaluation::Boolean(true))),
+            ("'' == 0", Some(Evaluation::Boolean(true))),
+            ("'false' == false", Some(Evaluation::Boolean(false))),
+            ("'abc' != 0", Some(Evaluation::Boolean(true))),
+            ("2 >= 1", Some(Evaluation::Boolean(true))),
+            ("1 <= 1", Some(Evaluation::Boolean(true))),
+            // Functions.
+            ("contains('FooBar', 'bar')", Some(Evaluation::Boolean(true))),
+            ("startsWith('foo', 'bar')", Some(Evaluation::Boolean(false))),
+            ("endsWith('foobar', 'BAR')", Some(Evaluation::Boolean(true))),
+            (
+                "format('{0}-{{1}}-{1}', 'a', 2)",
+                Some(Evaluation::String("a-{1}-2".into())),
+            ),
+            ("format('{1}', 'a')", None),
+            ("format('{0', 'a')", None),
+            // Non-constant expressions.
+            ("foo.bar", None),
+            ("foo.bar == 'bar'", None),
+            ("'a' && foo.bar", None),
+            ("fromJSON('true')", None),
+        ] {
+            assert_eq!(Expr::parse(expr)?.consteval(), *evaluation, "{expr}");
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_expr_has_constant_reducible_subexpr() -> Result<()> {
         for (expr, reducible) in &[
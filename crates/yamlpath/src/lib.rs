@@ -47,6 +47,10 @@ pub enum QueryError {
     /// the given field name.
     #[error("syntax node `{0}` is missing child field `{1}`")]
     MissingChildField(String, &'static str),
+    /// The query passes through an alias whose anchor isn't defined
+    /// before it.
+    #[error("undefined alias `{0}`")]
+    UndefinedAlias(String),
     /// Any other query error that doesn't fit cleanly above.
     #[error("query error: {0}")]
     Other(String),
@@ -179,11 +183,18 @@ impl From<Node<'_>> for Location {
 }
 
 /// Represents the result of a successful query.
+///
+/// Queries pass through aliases (`*name`) and merge keys (`<<: *name`)
+/// into the anchored nodes that they refer to, so a feature can be
+/// reached from several places in the document; see [`Feature::alias`].
 #[derive(Debug)]
 pub struct Feature<'tree> {
     /// The tree-sitter node that this feature was extracted from.
     _node: Node<'tree>,
 
+    /// The outermost alias that the query passed through, if any.
+    alias: Option<Node<'tree>>,
+
     /// The exact location of the query result.
     pub location: Location,
 
@@ -196,12 +207,25 @@ impl<'tree> From<Node<'tree>> for Feature<'tree> {
     fn from(node: Node<'tree>) -> Self {
         Feature {
             _node: node,
+            alias: None,
             location: Location::from(node),
             context: node.parent().map(Location::from),
         }
     }
 }
 
+impl<'tree> Feature<'tree> {
+    /// Returns the outermost alias that the query passed through to reach
+    /// this feature, i.e. where the feature is used for the queried route,
+    /// if that isn't where it's defined.
+    ///
+    /// For example, querying `[b, x]` in `{a: &a {x: 1}, b: *a}` yields
+    /// `x: 1` (in `a`), whose alias is `*a` (in `b`).
+    pub fn alias(&self) -> Option<Feature<'tree>> {
+        self.alias.map(Feature::from)
+    }
+}
+
 /// Represents a queryable YAML document.
 pub struct Document {
     source: String,
@@ -221,6 +245,9 @@ pub struct Document {
     flow_pair_id: u16,
     block_sequence_item_id: u16,
     comment_id: u16,
+    anchor_id: u16,
+    tag_id: u16,
+    alias_id: u16,
 }
 
 impl Document {
@@ -253,6 +280,9 @@ impl Document {
             flow_pair_id: language.id_for_node_kind("flow_pair", true),
             block_sequence_item_id: language.id_for_node_kind("block_sequence_item", true),
             comment_id: language.id_for_node_kind("comment", true),
+            anchor_id: language.id_for_node_kind("anchor", true),
+            tag_id: language.id_for_node_kind("tag", true),
+            alias_id: language.id_for_node_kind("alias", true),
         })
     }
 
@@ -266,7 +296,7 @@ impl Document {
     ///
     /// This is typically useful as a "fallback" feature, e.g. for capturing
     /// a span of the entire document.
-    pub fn root(&self) -> Feature {
+    pub fn root(&self) -> Feature<'_> {
         self.tree.root_node().into()
     }
 
@@ -291,13 +321,19 @@ impl Document {
 
     /// Perform a query on the current document, returning a `Feature`
     /// if the query succeeds.
-    pub fn query(&self, query: &Query) -> Result<Feature, QueryError> {
+    pub fn query(&self, query: &Query) -> Result<Feature<'_>, QueryError> {
         // TODO: Figure out comment extraction. This is made annoying
         // by the fact that comments aren't parented in obvious places on
         // the tree, e.g. `[a, b, [c]] # foo` has a comment adjacent to the
         // top sequence when we may be querying for the `c` in the innermost
         // sequence.
-        self.query_node(query).map(|n| n.into())
+        let mut alias = None;
+        let node = self.query_node(query, &mut alias)?;
+
+        Ok(Feature {
+            alias,
+            ..node.into()
+        })
     }
 
     /// Returns a string slice of the original document corresponding to
@@ -410,7 +446,11 @@ impl Document {
         )
     }
 
-    fn query_node(&self, query: &Query) -> Result<Node, QueryError> {
+    fn query_node<'b>(
+        &'b self,
+        query: &Query,
+        alias: &mut Option<Node<'b>>,
+    ) -> Result<Node<'b>, QueryError> {
         // All tree-sitter-yaml trees start with a `stream` node.
         let stream = self.tree.root_node();
 
@@ -433,7 +473,7 @@ impl Document {
 
         let mut key_node = top_node;
         for component in &query.route {
-            match self.descend(&key_node, component) {
+            match self.descend(&key_node, component, alias) {
                 Ok(next) => key_node = next,
                 Err(e) => return Err(e),
             }
@@ -455,17 +495,84 @@ impl Document {
         Ok(key_node)
     }
 
-    fn descend<'b>(&self, node: &Node<'b>, component: &Component) -> Result<Node<'b>, QueryError> {
+    /// Returns the content of the given `block_node` or `flow_node`, i.e.
+    /// its child other than any anchor or tag, resolving it if it's an
+    /// alias. An alias is recorded in `alias`, unless one already is.
+    fn content<'b>(
+        &'b self,
+        node: &Node<'b>,
+        alias: &mut Option<Node<'b>>,
+    ) -> Result<Node<'b>, QueryError> {
+        let mut cur = node.walk();
+        let child = node
+            .named_children(&mut cur)
+            .find(|c| c.kind_id() != self.anchor_id && c.kind_id() != self.tag_id)
+            .ok_or_else(|| QueryError::MissingChild(node.kind().into(), "content".into()))?;
+
+        if child.kind_id() != self.alias_id {
+            return Ok(child);
+        }
+
+        let anchored = self.resolve_alias(&child)?;
+        alias.get_or_insert(child);
+
+        // NOTE: An anchored node can't itself be an alias, so this
+        // can't recurse more than once.
+        self.content(&anchored, alias)
+    }
+
+    /// Resolves the given `alias` node to the `block_node` or `flow_node`
+    /// that its anchor is attached to, i.e. the closest preceding anchor
+    /// with the same name (since YAML allows anchors to be redefined).
+    fn resolve_alias<'b>(&'b self, alias: &Node<'b>) -> Result<Node<'b>, QueryError> {
+        // NOTE: text unwraps are infallible, since our document is UTF-8.
+        let name = alias.utf8_text(self.source.as_bytes()).unwrap();
+        let name = name.strip_prefix('*').unwrap_or(name);
+
+        let mut anchored = None;
+        let mut cur = self.tree.root_node().walk();
+        'walk: loop {
+            let node = cur.node();
+            if node.start_byte() >= alias.start_byte() {
+                break;
+            }
+
+            if node.kind_id() == self.anchor_id {
+                let anchor = node.utf8_text(self.source.as_bytes()).unwrap();
+                if anchor.strip_prefix('&') == Some(name) {
+                    anchored = node.parent();
+                }
+            }
+
+            if cur.goto_first_child() {
+                continue;
+            }
+            while !cur.goto_next_sibling() {
+                if !cur.goto_parent() {
+                    break 'walk;
+                }
+            }
+        }
+
+        anchored.ok_or_else(|| QueryError::UndefinedAlias(name.into()))
+    }
+
+    fn descend<'b>(
+        &'b self,
+        node: &Node<'b>,
+        component: &Component,
+        alias: &mut Option<Node<'b>>,
+    ) -> Result<Node<'b>, QueryError> {
         // The cursor is assumed to start on a block_node or flow_node,
-        // which has a single child containing the inner scalar/vector
-        // type we're descending through.
-        let child = node.child(0).unwrap();
+        // whose content is the inner scalar/vector type we're descending
+        // through (possibly via an alias).
+        let child = self.content(node, alias)?;
 
         // We expect the child to be a sequence or mapping of either
         // flow or block type.
         if child.kind_id() == self.block_mapping_id || child.kind_id() == self.flow_mapping_id {
             match component {
-                Component::Key(key) => self.descend_mapping(&child, key),
+                Component::Key(key) => self.descend_mapping(&child, key, alias),
                 Component::Index(idx) => Err(QueryError::ExpectedList(*idx)),
             }
         } else if child.kind_id() == self.block_sequence_id
@@ -480,7 +587,13 @@ impl Document {
         }
     }
 
-    fn descend_mapping<'b>(&self, node: &Node<'b>, expected: &str) -> Result<Node<'b>, QueryError> {
+    fn descend_mapping<'b>(
+        &'b self,
+        node: &Node<'b>,
+        expected: &str,
+        alias: &mut Option<Node<'b>>,
+    ) -> Result<Node<'b>, QueryError> {
+        let mut merges = vec![];
         let mut cur = node.walk();
         for child in node.named_children(&mut cur) {
             // Skip over any unexpected children, e.g. comments.
@@ -524,9 +637,51 @@ impl Document {
                 // in a way that matters (since an empty value is terminal anyways).
                 return Ok(child.child_by_field_name("value").unwrap_or(child));
             }
+
+            if key_value == "<<" {
+                merges.extend(child.child_by_field_name("value"));
+            }
+        }
+
+        // None of the keys in the mapping matched, but it might be merged
+        // in from another mapping. Explicit keys take precedence over
+        // merged ones, as do earlier merged mappings over later ones.
+        for merge in merges {
+            let mut merge_alias = None;
+            let Ok(merged) = self.content(&merge, &mut merge_alias) else {
+                continue;
+            };
+
+            let mappings = if merged.kind_id() == self.flow_sequence_id {
+                let mut cur = merged.walk();
+                merged
+                    .named_children(&mut cur)
+                    .filter(|c| c.kind_id() == self.flow_node_id)
+                    .collect()
+            } else {
+                vec![merge]
+            };
+
+            for mapping in mappings {
+                let mut mapping_alias = merge_alias;
+                let Ok(content) = self.content(&mapping, &mut mapping_alias) else {
+                    continue;
+                };
+                if content.kind_id() != self.block_mapping_id
+                    && content.kind_id() != self.flow_mapping_id
+                {
+                    continue;
+                }
+
+                if let Ok(found) = self.descend_mapping(&content, expected, &mut mapping_alias) {
+                    if alias.is_none() {
+                        *alias = mapping_alias;
+                    }
+                    return Ok(found);
+                }
+            }
         }
 
-        // None of the keys in the mapping matched.
         Err(QueryError::ExhaustedMapping(expected.into()))
     }
 
@@ -712,4 +867,47 @@ bar: # outside
         let feature = doc.query(&query).unwrap();
         assert_eq!(doc.feature_comments(&feature), &["# quux"]);
     }
+
+    #[test]
+    fn test_alias() {
+        let doc = r#"
+a: &a
+  x: 1
+b: *a
+c:
+  <<: *a
+  y: 2
+d: *missing
+"#;
+
+        let doc = Document::new(doc).unwrap();
+        let query = |route: &[&str]| {
+            doc.query(&Query {
+                route: route.iter().map(|&k| Component::Key(k.into())).collect(),
+            })
+        };
+
+        // Defined in place, so there's no alias.
+        let feature = query(&["a", "x"]).unwrap();
+        assert_eq!(doc.extract(&feature), "x: 1");
+        assert!(feature.alias().is_none());
+
+        // Reached through an alias, or a merge key.
+        let feature = query(&["b", "x"]).unwrap();
+        assert_eq!(doc.extract(&feature), "x: 1");
+        assert_eq!(doc.extract(&feature.alias().unwrap()), "*a");
+
+        let feature = query(&["c", "x"]).unwrap();
+        assert_eq!(doc.extract(&feature), "x: 1");
+        assert_eq!(doc.extract(&feature.alias().unwrap()), "*a");
+
+        let feature = query(&["c", "y"]).unwrap();
+        assert_eq!(doc.extract(&feature), "y: 2");
+        assert!(feature.alias().is_none());
+
+        assert!(matches!(
+            query(&["d", "x"]),
+            Err(crate::QueryError::UndefinedAlias(name)) if name == "missing"
+        ));
+    }
 }
@@ -0,0 +1,147 @@
+//! Audits `workflow_run` workflows for artifacts downloaded from
+//! the triggering workflow, which may be an untrusted pull request
+//! workflow, and then executed or published.
+
+use std::ops::Deref;
+
+use github_actions_models::common::{EnvValue, Uses};
+use github_actions_models::workflow::job::StepBody;
+use indexmap::IndexMap;
+
+use super::cache_poisoning::KNOWN_PUBLISHER_ACTIONS;
+use super::{Audit, AuditLoadError, audit_meta};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::models::{JobExt as _, NormalJob, Step, StepCommon as _, uses::RepositoryUsesExt as _};
+use crate::state::AuditState;
+
+pub(crate) struct ArtifactPoisoning;
+
+audit_meta!(
+    ArtifactPoisoning,
+    "artifact-poisoning",
+    "artifact downloaded from an untrusted workflow",
+    Severity::High
+);
+
+impl ArtifactPoisoning {
+    /// Returns the download path if `step` downloads an artifact from
+    /// another workflow run, or `None` otherwise.
+    ///
+    /// The path is `None` when the artifact is extracted into the
+    /// workspace root, which is the default.
+    fn cross_workflow_download(step: &Step) -> Option<Option<String>> {
+        let StepBody::Uses {
+            uses: Uses::Repository(uses),
+            with,
+        } = &step.deref().body
+        else {
+            return None;
+        };
+
+        // `actions/download-artifact` only downloads from other workflow
+        // runs when given a `run-id` (and a token to access it), while
+        // `dawidd6/action-download-artifact` exists to do exactly that.
+        let cross_workflow = (uses.matches("actions/download-artifact")
+            && (with.contains_key("run-id") || with.contains_key("github-token")))
+            || uses.matches("dawidd6/action-download-artifact");
+
+        cross_workflow.then(|| Self::download_path(with))
+    }
+
+    fn download_path(with: &IndexMap<String, EnvValue>) -> Option<String> {
+        let path = with.get("path")?.to_string();
+        let path = path.trim_start_matches("./").trim_end_matches('/');
+
+        match path {
+            "" | "." | "${{ github.workspace }}" => None,
+            path => Some(path.into()),
+        }
+    }
+
+    /// Whether `step` plausibly consumes an artifact downloaded to `path`.
+    ///
+    /// This is a heuristic: a `run:` step consumes the artifact if it
+    /// mentions its path, or unconditionally if the artifact was extracted
+    /// into the workspace root, since that's the step's working directory.
+    /// Publishing steps are always considered consumers.
+    ///
+    /// Returns the consuming key and an annotation for it.
+    fn consumes(step: &Step, path: Option<&str>) -> Option<(&'static str, &'static str)> {
+        match &step.deref().body {
+            StepBody::Run { run, .. } => match path {
+                None => Some((
+                    "run",
+                    "runs in the workspace the artifact was extracted into",
+                )),
+                Some(path) if run.contains(path) => Some(("run", "artifact contents used here")),
+                Some(_) => None,
+            },
+            StepBody::Uses { .. } => KNOWN_PUBLISHER_ACTIONS
+                .iter()
+                .any(|publisher| publisher.usage(step).is_some())
+                .then_some(("uses", "artifact contents may be published here")),
+        }
+    }
+}
+
+impl Audit for ArtifactPoisoning {
+    fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+
+    fn audit_normal_job<'doc>(&self, job: &NormalJob<'doc>) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        if !job.parent().has_workflow_run() {
+            return Ok(findings);
+        }
+
+        let steps = job.steps().collect::<Vec<_>>();
+        for (idx, step) in steps.iter().enumerate() {
+            let Some(path) = Self::cross_workflow_download(step) else {
+                continue;
+            };
+
+            let consumer = steps[idx + 1..].iter().find_map(|later| {
+                Self::consumes(later, path.as_deref()).map(|consumed| (later, consumed))
+            });
+
+            let mut finding = Self::finding()
+                .add_location(
+                    job.parent()
+                        .location()
+                        .with_keys(&["on".into()])
+                        .annotated("workflow_run may be triggered by an untrusted workflow"),
+                )
+                .add_location(step.location().hidden())
+                .add_location(
+                    step.location()
+                        .primary()
+                        .with_keys(&["uses".into()])
+                        .annotated("downloads an artifact from the triggering workflow"),
+                );
+
+            finding = match consumer {
+                Some((consumer, (key, annotation))) => finding
+                    .severity(Severity::High)
+                    .confidence(Confidence::Medium)
+                    .add_location(
+                        consumer
+                            .location()
+                            .with_keys(&[key.into()])
+                            .annotated(annotation),
+                    ),
+                None => finding
+                    .severity(Severity::Medium)
+                    .confidence(Confidence::Low),
+            };
+
+            findings.push(finding.build(job.parent())?);
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,271 @@
+//! Detects artifacts uploaded from paths that are likely to contain
+//! credentials, e.g. `~/.ssh`, `.git/config`, or `.npmrc`.
+//!
+//! Artifacts can be downloaded by anybody with read access to the
+//! repository, so credentials in them are effectively public in public
+//! repositories.
+
+use std::ops::Deref as _;
+use std::sync::LazyLock;
+
+use anyhow::Result;
+use github_actions_models::common::{EnvValue, Uses};
+use github_actions_models::workflow::job::StepBody;
+use regex::Regex;
+
+use super::{Audit, AuditLoadError, audit_meta};
+use crate::finding::{Confidence, Finding, Severity, Subfeature};
+use crate::models::{JobExt as _, Step, StepCommon as _, uses::RepositoryUsesExt as _};
+use crate::state::AuditState;
+use crate::utils::split_patterns;
+
+/// Actions that upload artifacts from a `path:` input.
+const UPLOAD_ACTIONS: &[&str] = &["actions/upload-artifact", "actions/upload-pages-artifact"];
+
+/// Paths that refer to the runner's home directory.
+const HOME_DIRS: &[&str] = &[
+    "~",
+    "$HOME",
+    "${HOME}",
+    "${{ env.HOME }}",
+    "/home/runner",
+    "/Users/runner",
+    "/root",
+];
+
+/// Paths that refer to the workspace root.
+const WORKSPACE_ROOTS: &[&str] = &[
+    ".",
+    "..",
+    "$GITHUB_WORKSPACE",
+    "${GITHUB_WORKSPACE}",
+    "${{ github.workspace }}",
+];
+
+/// Files that conventionally contain credentials.
+const CREDENTIAL_FILES: &[&str] = &[
+    ".npmrc",
+    ".netrc",
+    ".pypirc",
+    ".git-credentials",
+    ".dockercfg",
+    "credentials.json",
+];
+
+/// Redirections (and `tee`s) into a file, e.g. `>> .npmrc`.
+static FILE_WRITE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?:>>?|\btee(?:\s+-a)?)\s*["']?(?P<file>[^\s"'|;&<>]+)"#).unwrap()
+});
+
+pub(crate) struct ArtifactSensitivePaths;
+
+audit_meta!(
+    ArtifactSensitivePaths,
+    "artifact-sensitive-paths",
+    "artifact uploaded from a sensitive path",
+    Severity::High
+);
+
+/// Whether the given file name conventionally contains credentials.
+fn is_credential_file(name: &str) -> bool {
+    CREDENTIAL_FILES.contains(&name)
+        || name.ends_with("-credentials.json")
+        || (name.starts_with("gha-creds-") && name.ends_with(".json"))
+}
+
+/// Whether the given file name is a dotenv file, e.g. `.env` or `.env.local`.
+fn is_env_file(name: &str) -> bool {
+    name == ".env" || name.starts_with(".env.")
+}
+
+impl ArtifactSensitivePaths {
+    /// Returns why uploading `pattern` is dangerous, if it is.
+    fn sensitive_pattern(pattern: &str) -> Option<&'static str> {
+        let path = pattern.trim_end_matches('/');
+        let path = path.strip_prefix("./").unwrap_or(path);
+
+        if HOME_DIRS.contains(&path) {
+            return Some("uploads the runner's entire home directory");
+        }
+
+        let components = path.split('/').collect::<Vec<_>>();
+        let last = components.last().copied().unwrap_or_default();
+
+        if components.contains(&".ssh") {
+            Some("uploads SSH keys and configuration")
+        } else if last == ".git" || components.windows(2).any(|w| w == [".git", "config"]) {
+            Some("uploads Git configuration, which may contain credentials")
+        } else if components
+            .windows(2)
+            .any(|w| w == [".docker", "config.json"])
+            || is_credential_file(last)
+        {
+            Some("uploads a credentials file")
+        } else if is_env_file(last) {
+            Some("uploads a dotenv file, which may contain secrets")
+        } else {
+            None
+        }
+    }
+
+    /// Whether `step` writes a credentials file into the workspace.
+    fn writes_credentials(step: &Step<'_>) -> bool {
+        match &step.deref().body {
+            StepBody::Run { run, .. } => FILE_WRITE.captures_iter(run).any(|cap| {
+                let file = &cap["file"];
+                // Files outside of the workspace aren't uploaded with it.
+                if file.starts_with(['/', '~']) || file.starts_with("$HOME") {
+                    return false;
+                }
+
+                let name = file.rsplit('/').next().unwrap_or(file);
+                is_credential_file(name) || is_env_file(name)
+            }),
+            // `google-github-actions/auth` writes a `gha-creds-*.json`
+            // file into the workspace unless told not to.
+            StepBody::Uses {
+                uses: Uses::Repository(uses),
+                with,
+            } if uses.matches("google-github-actions/auth") => with
+                .get("create_credentials_file")
+                .is_none_or(|v| v.to_string() != "false"),
+            _ => false,
+        }
+    }
+
+    /// Returns the `path:` input of `step` if it uploads an artifact.
+    fn upload_path<'doc>(step: &Step<'doc>) -> Option<&'doc str> {
+        let StepBody::Uses {
+            uses: Uses::Repository(uses),
+            with,
+        } = &step.deref().body
+        else {
+            return None;
+        };
+
+        if !UPLOAD_ACTIONS.iter().any(|action| uses.matches(action)) {
+            return None;
+        }
+
+        match with.get("path") {
+            Some(EnvValue::String(path)) => Some(path),
+            _ => None,
+        }
+    }
+}
+
+impl Audit for ArtifactSensitivePaths {
+    fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError> {
+        Ok(Self)
+    }
+
+    fn audit_normal_job<'doc>(&self, job: &super::NormalJob<'doc>) -> Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        // Artifacts uploaded by pull request workflows can be triggered by
+        // anybody who can open a pull request.
+        let severity = if job.parent().has_pull_request() || job.parent().has_pull_request_target()
+        {
+            Severity::High
+        } else {
+            Severity::Medium
+        };
+
+        let mut credentials_writer = None;
+        for step in job.steps() {
+            if credentials_writer.is_none() && Self::writes_credentials(&step) {
+                credentials_writer = Some(step.clone());
+            }
+
+            let Some(path) = Self::upload_path(&step) else {
+                continue;
+            };
+
+            for pattern in split_patterns(path) {
+                // Exclusions can only make an upload safer.
+                if pattern.starts_with('!') {
+                    continue;
+                }
+
+                // NOTE: `pattern` is a subslice of `path`, so we can recover
+                // its offset to pin the finding to the offending line.
+                let offset = pattern.as_ptr() as usize - path.as_ptr() as usize;
+                let subfeature = Subfeature::new(path[..offset].matches(pattern).count(), pattern);
+
+                let location = step
+                    .location()
+                    .primary()
+                    .with_keys(&["with".into(), "path".into()])
+                    .with_subfeature(subfeature);
+
+                if let Some(reason) = Self::sensitive_pattern(pattern) {
+                    findings.push(
+                        Self::finding()
+                            .severity(severity)
+                            .confidence(Confidence::High)
+                            .add_location(step.location_with_name())
+                            .add_location(location.annotated(reason))
+                            .build(job.parent())?,
+                    );
+                } else if let Some(writer) = &credentials_writer {
+                    if WORKSPACE_ROOTS.contains(&pattern.trim_end_matches('/')) {
+                        findings.push(
+                            Self::finding()
+                                .severity(severity)
+                                .confidence(Confidence::Medium)
+                                .add_location(
+                                    writer
+                                        .location_with_name()
+                                        .annotated("writes a credentials file into the workspace"),
+                                )
+                                .add_location(location.annotated(
+                                    "uploads the workspace, including the credentials file",
+                                ))
+                                .build(job.parent())?,
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArtifactSensitivePaths;
+
+    #[test]
+    fn test_sensitive_pattern() {
+        for (pattern, sensitive) in [
+            ("~", true),
+            ("~/", true),
+            ("/home/runner", true),
+            ("/home/runner/", true),
+            ("${{ env.HOME }}", true),
+            ("~/.ssh", true),
+            ("~/.ssh/id_rsa", true),
+            (".git/config", true),
+            ("./.git", true),
+            ("**/.env", true),
+            (".env.production", true),
+            (".npmrc", true),
+            ("packages/foo/.npmrc", true),
+            ("~/.docker/config.json", true),
+            ("gha-creds-1234.json", true),
+            (".", false),
+            ("dist/", false),
+            ("/home/runner/work/dist", false),
+            (".environment", false),
+            ("config.json", false),
+            (".github/workflows", false),
+        ] {
+            assert_eq!(
+                ArtifactSensitivePaths::sensitive_pattern(pattern).is_some(),
+                sensitive,
+                "{pattern}"
+            );
+        }
+    }
+}
@@ -20,7 +20,8 @@ pub(crate) struct Artipacked;
 audit_meta!(
     Artipacked,
     "artipacked",
-    "credential persistence through GitHub Actions artifacts"
+    "credential persistence through GitHub Actions artifacts",
+    Severity::Medium
 );
 
 impl Artipacked {
@@ -118,7 +119,7 @@ impl Audit for Artipacked {
             // a cartesian product, but this way is simple.
             for ((checkout, persona), upload) in vulnerable_checkouts
                 .into_iter()
-                .cartesian_product(vulnerable_uploads.into_iter())
+                .cartesian_product(vulnerable_uploads)
             {
                 if checkout.index < upload.index {
                     findings.push(
@@ -1,6 +1,7 @@
 use github_actions_expressions::{BinOp, Expr, UnOp, context::Context};
 use github_actions_models::common::{If, expr::ExplicitExpr};
 
+use super::dangerous_triggers::DangerousTriggers;
 use super::{Audit, AuditLoadError, AuditState, audit_meta};
 use crate::{
     finding::{Confidence, Severity},
@@ -9,7 +10,12 @@ use crate::{
 
 pub(crate) struct BotConditions;
 
-audit_meta!(BotConditions, "bot-conditions", "spoofable bot actor check");
+audit_meta!(
+    BotConditions,
+    "bot-conditions",
+    "spoofable bot actor check",
+    Severity::Medium
+);
 
 const SPOOFABLE_ACTOR_CONTEXTS: &[&str] = &["github.actor", "github.triggering_actor"];
 
@@ -34,6 +40,14 @@ impl Audit for BotConditions {
             return Ok(vec![]);
         }
 
+        // A spoofed actor check only matters if the job it gates is
+        // privileged, i.e. has secrets or a write-capable token.
+        if DangerousTriggers::is_read_only(&job.permissions, &job.parent().permissions)
+            && !DangerousTriggers::references_secrets(job.parent(), job)?
+        {
+            return Ok(vec![]);
+        }
+
         let mut conds = vec![];
         if let Some(If::Expr(expr)) = &job.r#if {
             conds.push((expr, job.location()));
@@ -49,13 +63,12 @@ impl Audit for BotConditions {
             if let Some(confidence) = Self::bot_condition(expr) {
                 findings.push(
                     Self::finding()
-                        .severity(Severity::High)
+                        .severity(Severity::Medium)
                         .confidence(confidence)
-                        .add_location(
-                            loc.with_keys(&["if".into()])
-                                .primary()
-                                .annotated("actor context may be spoofable"),
-                        )
+                        .add_location(loc.with_keys(&["if".into()]).primary().annotated(
+                            "actor context may be spoofable; check \
+                                 `github.event.pull_request.user.login` instead",
+                        ))
                         .build(job.parent())?,
                 );
             }
@@ -134,6 +147,29 @@ impl BotConditions {
         }
     }
 
+    /// Whether the expression is gated on `github.event_name`, i.e. contains
+    /// an event name comparison that must hold for the whole expression to hold.
+    fn gated_by_event_name(expr: &Expr) -> bool {
+        match expr {
+            Expr::BinOp {
+                lhs,
+                op: BinOp::And,
+                rhs,
+            } => Self::gated_by_event_name(lhs) || Self::gated_by_event_name(rhs),
+            Expr::BinOp {
+                lhs,
+                op: BinOp::Eq,
+                rhs,
+            } => match (lhs.as_ref(), rhs.as_ref()) {
+                (Expr::Context(ctx), Expr::String(_)) | (Expr::String(_), Expr::Context(ctx)) => {
+                    ctx == "github.event_name"
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
     fn bot_condition(expr: &str) -> Option<Confidence> {
         // TODO: Remove clones here.
         let bare = match ExplicitExpr::from_curly(expr) {
@@ -146,6 +182,12 @@ impl BotConditions {
             return None;
         };
 
+        // An actor check that's combined with an event name check can't be
+        // satisfied by an arbitrary (attacker-initiated) event.
+        if Self::gated_by_event_name(&expr) {
+            return None;
+        }
+
         // We're looking for `github.actor == *[bot]` anywhere in the expression tree.
         // The bot condition is said to "dominate" if controls the entire
         // expression truth value. For example, `github.actor == 'dependabot[bot]' || foo`
@@ -208,4 +250,25 @@ mod tests {
             assert_eq!(BotConditions::bot_condition(cond).unwrap(), *confidence);
         }
     }
+
+    #[test]
+    fn test_bot_condition_event_name() {
+        for cond in &[
+            "github.event_name == 'pull_request' && github.actor == 'dependabot[bot]'",
+            "github.actor == 'dependabot[bot]' && github.event_name == 'pull_request'",
+            "github.actor == 'dependabot[bot]' && (github.event_name == 'pull_request' && true)",
+            "'pull_request' == GitHub.EVENT_NAME && github.actor == 'dependabot[bot]'",
+        ] {
+            assert_eq!(BotConditions::bot_condition(cond), None, "{cond}");
+        }
+
+        // An event name check that doesn't gate the whole expression
+        // doesn't help.
+        for cond in &[
+            "github.event_name == 'pull_request' || github.actor == 'dependabot[bot]'",
+            "github.actor == 'dependabot[bot]' || github.event_name == 'push'",
+        ] {
+            assert!(BotConditions::bot_condition(cond).is_some(), "{cond}");
+        }
+    }
 }
@@ -1,13 +1,18 @@
+use std::ops::Deref;
 use std::sync::LazyLock;
 
-use github_actions_models::workflow::Trigger;
+use github_actions_expressions::{Expr, context::ContextPattern};
+use github_actions_models::common::Uses;
 use github_actions_models::workflow::event::{BareEvent, BranchFilters, OptionalBody};
+use github_actions_models::workflow::job::StepBody;
+use github_actions_models::workflow::{Trigger, Workflow};
 
 use crate::audit::{Audit, audit_meta};
 use crate::finding::{Confidence, Finding, Severity};
 use crate::models::coordinate::{ActionCoordinate, ControlExpr, ControlFieldType, Toggle, Usage};
 use crate::models::{JobExt as _, NormalJob, Step, StepCommon, Steps};
 use crate::state::AuditState;
+use crate::utils::extract_expressions;
 
 use super::AuditLoadError;
 
@@ -168,7 +173,7 @@ static KNOWN_CACHE_AWARE_ACTIONS: LazyLock<Vec<ActionCoordinate>> = LazyLock::ne
 
 /// A list of well-know publisher actions
 /// In the future we can retrieve this list from the static API
-static KNOWN_PUBLISHER_ACTIONS: LazyLock<Vec<ActionCoordinate>> = LazyLock::new(|| {
+pub(crate) static KNOWN_PUBLISHER_ACTIONS: LazyLock<Vec<ActionCoordinate>> = LazyLock::new(|| {
     vec![
         // Public packages and/or binary distribution channels
         ActionCoordinate::NotConfigurable("pypa/gh-action-pypi-publish".parse().unwrap()),
@@ -213,6 +218,15 @@ static KNOWN_PUBLISHER_ACTIONS: LazyLock<Vec<ActionCoordinate>> = LazyLock::new(
     ]
 });
 
+/// Contexts that identify the triggering pull request, which scope
+/// a cache entry to that pull request when used in its key.
+static PR_NUMBER_CONTEXTS: LazyLock<Vec<ContextPattern>> = LazyLock::new(|| {
+    ["github.event.pull_request.number", "github.event.number"]
+        .iter()
+        .map(|s| ContextPattern::new(s).unwrap())
+        .collect()
+});
+
 enum PublishingArtifactsScenario<'doc> {
     UsingTypicalWorkflowTrigger,
     UsingWellKnowPublisherAction(Step<'doc>),
@@ -223,7 +237,8 @@ pub(crate) struct CachePoisoning;
 audit_meta!(
     CachePoisoning,
     "cache-poisoning",
-    "runtime artifacts potentially vulnerable to a cache poisoning attack"
+    "runtime artifacts potentially vulnerable to a cache poisoning attack",
+    Severity::High
 );
 
 impl CachePoisoning {
@@ -276,6 +291,91 @@ impl CachePoisoning {
         ))
     }
 
+    /// Returns the privileged trigger of the given workflow, if any.
+    ///
+    /// Cache entries written from these triggers run in the context of the
+    /// default branch, making them readable from subsequent default
+    /// branch runs.
+    fn privileged_trigger(workflow: &Workflow) -> Option<&'static str> {
+        let (pull_request_target, workflow_run) = match &workflow.on {
+            Trigger::BareEvent(event) => (
+                *event == BareEvent::PullRequestTarget,
+                *event == BareEvent::WorkflowRun,
+            ),
+            Trigger::BareEvents(events) => (
+                events.contains(&BareEvent::PullRequestTarget),
+                events.contains(&BareEvent::WorkflowRun),
+            ),
+            Trigger::Events(events) => (
+                !matches!(events.pull_request_target, OptionalBody::Missing),
+                !matches!(events.workflow_run, OptionalBody::Missing),
+            ),
+        };
+
+        if pull_request_target {
+            Some("pull_request_target")
+        } else if workflow_run {
+            Some("workflow_run")
+        } else {
+            None
+        }
+    }
+
+    /// Whether the step's cache `key` is scoped to the triggering pull request.
+    fn cache_key_scoped_to_pr(step: &Step) -> bool {
+        let StepBody::Uses {
+            uses: Uses::Repository(_),
+            with,
+        } = &step.deref().body
+        else {
+            return false;
+        };
+
+        let Some(key) = with.get("key").map(|key| key.to_string()) else {
+            return false;
+        };
+
+        extract_expressions(&key).iter().any(|(expr, _)| {
+            Expr::parse(expr.as_bare()).is_ok_and(|expr| {
+                expr.dataflow_contexts()
+                    .iter()
+                    .any(|ctx| PR_NUMBER_CONTEXTS.iter().any(|pat| pat.matches(ctx)))
+            })
+        })
+    }
+
+    fn uses_cache_in_privileged_context<'doc>(
+        &self,
+        step: &Step<'doc>,
+        trigger: &str,
+    ) -> Option<Finding<'doc>> {
+        self.evaluate_cache_usage(step)?;
+
+        if Self::cache_key_scoped_to_pr(step) {
+            return None;
+        }
+
+        Self::finding()
+            .confidence(Confidence::Medium)
+            .severity(Severity::Medium)
+            .add_location(
+                step.workflow()
+                    .location()
+                    .with_keys(&["on".into()])
+                    .annotated(format!(
+                        "{trigger} runs in the default branch's cache scope"
+                    )),
+            )
+            .add_location(
+                step.location()
+                    .primary()
+                    .with_keys(&["uses".into()])
+                    .annotated("cache entries written here are readable by default branch runs"),
+            )
+            .build(step.workflow())
+            .ok()
+    }
+
     fn evaluate_cache_usage<'doc>(&self, step: &impl StepCommon<'doc>) -> Option<Usage> {
         KNOWN_CACHE_AWARE_ACTIONS
             .iter()
@@ -348,12 +448,20 @@ impl Audit for CachePoisoning {
         let steps = job.steps();
         let trigger = &job.parent().on;
 
-        let Some(scenario) = self.is_job_publishing_artifacts(trigger, steps) else {
-            return Ok(findings);
-        };
+        let scenario = self.is_job_publishing_artifacts(trigger, steps);
+        let privileged_trigger = Self::privileged_trigger(job.parent());
 
         for step in job.steps() {
-            if let Some(finding) = self.uses_cache_aware_step(&step, &scenario) {
+            // Publishing takes precedence, since a poisoned cache that
+            // makes it into published artifacts is strictly worse.
+            if let Some(finding) = scenario
+                .as_ref()
+                .and_then(|scenario| self.uses_cache_aware_step(&step, scenario))
+            {
+                findings.push(finding);
+            } else if let Some(finding) = privileged_trigger
+                .and_then(|trigger| self.uses_cache_in_privileged_context(&step, trigger))
+            {
                 findings.push(finding);
             }
         }
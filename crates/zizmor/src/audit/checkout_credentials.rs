@@ -0,0 +1,151 @@
+//! Detects `actions/checkout` steps that persist their credentials in jobs
+//! that go on to run untrusted or third-party code.
+//!
+//! By default, `actions/checkout` persists the job's `GITHUB_TOKEN` into the
+//! checkout's `.git/config`, where any later step in the job can read it.
+
+use std::ops::Deref as _;
+
+use anyhow::Result;
+use github_actions_models::common::Uses;
+use github_actions_models::workflow::job::StepBody;
+
+use super::unpinned_uses::AllowedOrgs;
+use super::{Audit, AuditLoadError, NormalJob, audit_meta};
+use crate::finding::{Confidence, Finding, Persona, Severity};
+use crate::models::{
+    JobExt as _, Step, StepCommon as _,
+    uses::{RepositoryUsesExt as _, UsesExt as _},
+};
+use crate::state::AuditState;
+
+pub(crate) struct CheckoutCredentials {
+    allowed_orgs: AllowedOrgs,
+}
+
+audit_meta!(
+    CheckoutCredentials,
+    "checkout-credentials",
+    "checkout persists credentials for later steps",
+    Severity::High
+);
+
+impl CheckoutCredentials {
+    /// Returns the persona that `step` should be reported with, if it's
+    /// an `actions/checkout` step that persists its credentials.
+    fn persists_credentials(step: &Step<'_>) -> Option<Persona> {
+        let StepBody::Uses {
+            uses: Uses::Repository(uses),
+            with,
+        } = &step.deref().body
+        else {
+            return None;
+        };
+
+        if !uses.matches("actions/checkout") {
+            return None;
+        }
+
+        match with
+            .get("persist-credentials")
+            .map(|v| v.to_string())
+            .as_deref()
+        {
+            Some("false") => None,
+            // An explicit `persist-credentials: true` is probably deliberate.
+            Some("true") => Some(Persona::Auditor),
+            // persist-credentials is true by default.
+            _ => Some(Persona::default()),
+        }
+    }
+
+    /// Whether `step` uses a third-party action that isn't pinned to a commit.
+    fn unpinned_third_party(&self, step: &Step<'_>) -> bool {
+        match step.uses() {
+            Some(uses @ Uses::Repository(repo_uses)) => {
+                uses.unhashed()
+                    && !self
+                        .allowed_orgs
+                        .contains(&repo_uses.owner, &repo_uses.repo)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Audit for CheckoutCredentials {
+    fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError> {
+        let allowed_orgs = AllowedOrgs::from_state(state).map_err(AuditLoadError::Fail)?;
+
+        Ok(Self { allowed_orgs })
+    }
+
+    fn audit_normal_job<'doc>(&self, job: &NormalJob<'doc>) -> Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        // `pull_request_target` jobs run in the context of the target
+        // repository, and usually check out (and run) the pull request's code.
+        let risky_trigger = job.parent().has_pull_request_target();
+        let steps = job.steps().collect::<Vec<_>>();
+
+        for (index, checkout) in steps.iter().enumerate() {
+            let Some(persona) = Self::persists_credentials(checkout) else {
+                continue;
+            };
+
+            let third_party = steps[index + 1..]
+                .iter()
+                .find(|step| self.unpinned_third_party(step));
+
+            let (severity, confidence, minimum_persona) = match (risky_trigger, third_party) {
+                (true, _) => (Severity::High, Confidence::High, Persona::Regular),
+                (false, Some(_)) => (Severity::Medium, Confidence::Medium, Persona::Pedantic),
+                (false, None) => (Severity::Low, Confidence::Low, Persona::Pedantic),
+            };
+            let persona = persona.min(minimum_persona);
+
+            let location = match &checkout.deref().body {
+                StepBody::Uses { with, .. } if with.contains_key("persist-credentials") => checkout
+                    .location()
+                    .primary()
+                    .with_keys(&["with".into(), "persist-credentials".into()])
+                    .annotated("set this to `persist-credentials: false`"),
+                StepBody::Uses { with, .. } if !with.is_empty() => checkout
+                    .location()
+                    .primary()
+                    .with_keys(&["with".into()])
+                    .annotated("add `persist-credentials: false` to this block"),
+                _ => checkout
+                    .location()
+                    .primary()
+                    .with_keys(&["uses".into()])
+                    .annotated("add `with: { persist-credentials: false }` to this step"),
+            };
+
+            let mut finding = Self::finding()
+                .severity(severity)
+                .confidence(confidence)
+                .persona(persona)
+                .add_location(location);
+
+            if risky_trigger {
+                finding = finding.add_location(
+                    job.parent()
+                        .location()
+                        .with_keys(&["on".into()])
+                        .annotated("pull_request_target runs with the target repository's token"),
+                );
+            } else if let Some(step) = third_party {
+                finding = finding.add_location(
+                    step.location()
+                        .with_keys(&["uses".into()])
+                        .annotated("unpinned third-party action can read the credentials"),
+                );
+            }
+
+            findings.push(finding.build(job.parent())?);
+        }
+
+        Ok(findings)
+    }
+}
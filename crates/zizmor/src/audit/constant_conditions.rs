@@ -0,0 +1,230 @@
+//! Detects `if:` conditions that don't actually gate anything, because
+//! they're always true or always false.
+//!
+//! The most common form of this is a condition that mixes template
+//! expansions with other text, e.g. `if: ${{ a }} && ${{ b }}`: GitHub
+//! Actions evaluates this to a non-empty string, which is always truthy.
+
+use github_actions_expressions::{BinOp, Expr, UnOp, context::Context};
+use github_actions_models::common::If;
+
+use super::{Audit, AuditLoadError, AuditState, NormalJob, audit_meta};
+use crate::finding::{Confidence, Finding, Severity, SymbolicLocation};
+use crate::models::{AsDocument as _, JobExt as _, StepCommon as _};
+use crate::utils::extract_expressions;
+
+pub(crate) struct ConstantConditions;
+
+audit_meta!(
+    ConstantConditions,
+    "constant-conditions",
+    "condition is constant",
+    Severity::Low
+);
+
+/// The contexts that are available to `if:` conditions.
+const KNOWN_CONTEXTS: &[&str] = &[
+    "env", "github", "inputs", "job", "jobs", "matrix", "needs", "runner", "secrets", "steps",
+    "strategy", "vars",
+];
+
+/// Why a condition doesn't gate anything.
+enum Problem {
+    /// The condition expands expressions into a larger string.
+    Interpolated,
+    /// The condition always evaluates to this truthiness.
+    Constant(bool),
+    /// The condition references a context that doesn't exist.
+    UnknownContext(String),
+}
+
+impl Problem {
+    fn annotation(&self) -> String {
+        match self {
+            Problem::Interpolated => "expressions expanded into a string are always true; \
+                 put the entire condition inside one ${{ }}"
+                .into(),
+            Problem::Constant(truthy) => format!("this condition is always {truthy}"),
+            Problem::UnknownContext(context) => format!("{context} isn't a known context"),
+        }
+    }
+}
+
+/// Collects every context referenced anywhere in `expr`.
+fn contexts<'a, 'src>(expr: &'a Expr<'src>, found: &mut Vec<&'a Context<'src>>) {
+    match expr {
+        Expr::Context(context) => {
+            found.push(context);
+            for part in &context.parts {
+                contexts(part, found);
+            }
+        }
+        Expr::Call { args, .. } => args.iter().for_each(|arg| contexts(arg, found)),
+        Expr::Index(expr) | Expr::UnOp { expr, .. } => contexts(expr, found),
+        Expr::BinOp { lhs, rhs, .. } => {
+            contexts(lhs, found);
+            contexts(rhs, found);
+        }
+        _ => {}
+    }
+}
+
+/// Whether `expr` is always truthy (or always falsy), if it's either.
+///
+/// This goes slightly beyond constant evaluation, by also treating
+/// comparisons of an expression with itself as tautological.
+fn constant_truth(expr: &Expr) -> Option<bool> {
+    if let Some(evaluation) = expr.consteval() {
+        return Some(evaluation.is_truthy());
+    }
+
+    match expr {
+        Expr::BinOp { lhs, op, rhs } => match op {
+            BinOp::And => match (constant_truth(lhs), constant_truth(rhs)) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            },
+            BinOp::Or => match (constant_truth(lhs), constant_truth(rhs)) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            },
+            BinOp::Eq | BinOp::Ge | BinOp::Le if lhs == rhs => Some(true),
+            BinOp::Neq | BinOp::Gt | BinOp::Lt if lhs == rhs => Some(false),
+            _ => None,
+        },
+        Expr::UnOp {
+            op: UnOp::Not,
+            expr,
+        } => constant_truth(expr).map(|truthy| !truthy),
+        _ => None,
+    }
+}
+
+/// Whether `feature` (a job or step's source) uses any secrets.
+fn references_secrets(feature: &str) -> bool {
+    extract_expressions(feature)
+        .iter()
+        .filter_map(|(expr, _)| Expr::parse(expr.as_bare()).ok())
+        .any(|expr| {
+            let mut found = vec![];
+            contexts(&expr, &mut found);
+            found.iter().any(|context| context.child_of("secrets"))
+        })
+}
+
+impl ConstantConditions {
+    /// Returns the problem with `cond`, if it has one.
+    fn problem(cond: &If) -> Option<Problem> {
+        // A YAML boolean is as constant as its `${{ }}` spelling.
+        let cond = match cond {
+            If::Bool(truthy) => return Some(Problem::Constant(*truthy)),
+            If::Expr(cond) => cond,
+        };
+
+        let trimmed = cond.trim();
+        let exprs = extract_expressions(trimmed);
+        let bare = match exprs.as_slice() {
+            [] => trimmed,
+            [(expr, span)] if *span == (0..trimmed.len()) => expr.as_bare(),
+            _ => return Some(Problem::Interpolated),
+        };
+
+        let Ok(expr) = Expr::parse(bare) else {
+            tracing::warn!("couldn't parse expression: {bare}");
+            return None;
+        };
+
+        let mut found = vec![];
+        contexts(&expr, &mut found);
+        let unknown = found.iter().find(|context| {
+            matches!(
+                context.parts.first(),
+                Some(Expr::Identifier(ident)) if !KNOWN_CONTEXTS.iter().any(|known| ident == *known)
+            )
+        });
+        if let Some(context) = unknown {
+            return Some(Problem::UnknownContext(context.as_str().into()));
+        }
+
+        constant_truth(&expr).map(Problem::Constant)
+    }
+
+    fn build_finding<'doc>(
+        job: &NormalJob<'doc>,
+        problem: Problem,
+        privileged: bool,
+        owner: SymbolicLocation<'doc>,
+        location: SymbolicLocation<'doc>,
+    ) -> anyhow::Result<Finding<'doc>> {
+        // A condition that's never true doesn't expose anything.
+        let severity = match problem {
+            Problem::Constant(false) => Severity::Low,
+            _ if privileged => Severity::High,
+            _ => Severity::Low,
+        };
+
+        Self::finding()
+            .severity(severity)
+            .confidence(Confidence::High)
+            .add_location(owner)
+            .add_location(
+                location
+                    .with_keys(&["if".into()])
+                    .primary()
+                    .annotated(problem.annotation()),
+            )
+            .build(job.parent())
+    }
+}
+
+impl Audit for ConstantConditions {
+    fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+
+    fn audit_normal_job<'doc>(&self, job: &NormalJob<'doc>) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+        let document = job.parent().as_document();
+
+        // A condition guarding a deployment or secrets is presumably
+        // there to keep them from being used unconditionally.
+        let deploys = job.environment.is_some();
+
+        if let Some(problem) = job.r#if.as_ref().and_then(Self::problem) {
+            let privileged = deploys
+                || references_secrets(job.location().concretize(document)?.concrete.feature);
+
+            findings.push(Self::build_finding(
+                job,
+                problem,
+                privileged,
+                job.location(),
+                job.location(),
+            )?);
+        }
+
+        for step in job.steps() {
+            let Some(problem) = step.r#if.as_ref().and_then(Self::problem) else {
+                continue;
+            };
+
+            let privileged = deploys
+                || references_secrets(step.location().concretize(document)?.concrete.feature);
+
+            findings.push(Self::build_finding(
+                job,
+                problem,
+                privileged,
+                step.location_with_name(),
+                step.location(),
+            )?);
+        }
+
+        Ok(findings)
+    }
+}
@@ -0,0 +1,644 @@
+//! Detects remote scripts that are fetched and immediately executed,
+//! e.g. `curl ... | sh`.
+
+use std::sync::{LazyLock, Mutex};
+
+use anyhow::{Context as _, Result};
+use github_actions_models::action;
+use github_actions_models::workflow::job::StepBody;
+use regex::Regex;
+use tree_sitter::{Language, Node, Parser};
+
+use super::{Audit, AuditLoadError, AuditState, audit_meta};
+use crate::finding::{Confidence, Finding, Severity, Subfeature};
+use crate::models::{CompositeStep, Step, StepCommon};
+use crate::utils;
+
+static URL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"https?://[^\s'"`()<>|;]+"#).unwrap());
+
+/// Commands that execute a script passed via stdin or as an argument.
+const BASH_INTERPRETERS: &[&str] = &["sh", "bash", "dash", "ksh", "zsh", "source", "."];
+
+/// Commands that run another command, e.g. `sudo bash`.
+const BASH_WRAPPERS: &[&str] = &["sudo", "doas", "env"];
+
+/// Cmdlets (and their aliases) that fetch a remote resource.
+const PWSH_DOWNLOADERS: &[&str] = &[
+    "iwr",
+    "irm",
+    "invoke-webrequest",
+    "invoke-restmethod",
+    "curl",
+    "wget",
+];
+
+/// Cmdlets (and their aliases) that evaluate a string as code.
+const PWSH_EVALUATORS: &[&str] = &["iex", "invoke-expression"];
+
+/// Refs that are conventionally branches, i.e. mutable.
+const BRANCH_REFS: &[&str] = &["main", "master", "head", "develop", "dev", "trunk"];
+
+pub(crate) struct CurlPipeSh {
+    // NOTE: interior mutability used since Parser::parse requires &mut self,
+    // and audits are shared between threads
+    bash_parser: Mutex<Parser>,
+    pwsh_parser: Mutex<Parser>,
+}
+
+audit_meta!(
+    CurlPipeSh,
+    "curl-pipe-sh",
+    "remote script piped into a shell",
+    Severity::High
+);
+
+/// A remote script that's fetched and executed within a `run:` block.
+#[derive(Debug, PartialEq)]
+struct RemoteScript<'src> {
+    /// The command that fetches and executes the script.
+    command: &'src str,
+    /// The command's offset within the `run:` block.
+    offset: usize,
+    /// The literal URL that the script is fetched from, and its offset
+    /// within the `run:` block, if present.
+    url: Option<(&'src str, usize)>,
+}
+
+impl<'src> RemoteScript<'src> {
+    /// Builds a [`RemoteScript`] for `command`, taking the URL from
+    /// `downloader`.
+    fn new(command: Node<'_>, downloader: Node<'_>, source: &'src str) -> Self {
+        let url = URL
+            .find(&source[downloader.byte_range()])
+            .map(|url| (url.as_str(), downloader.start_byte() + url.start()));
+
+        Self {
+            command: &source[command.byte_range()],
+            offset: command.start_byte(),
+            url,
+        }
+    }
+
+    /// Whether the script's URL refers to mutable content, e.g. a `latest`
+    /// release or a branch.
+    fn is_mutable(&self) -> bool {
+        let Some((url, _)) = self.url else {
+            return false;
+        };
+
+        let Some((_, rest)) = url.split_once("://") else {
+            return false;
+        };
+
+        let mut segments = rest.split(['/', '?', '#']);
+        let host = segments.next().unwrap_or_default().to_lowercase();
+        let segments = segments.collect::<Vec<_>>();
+
+        if segments
+            .iter()
+            .any(|segment| segment.eq_ignore_ascii_case("latest"))
+        {
+            return true;
+        }
+
+        // Raw file content is addressed by `{owner}/{repo}/{ref}/{path}` on
+        // raw.githubusercontent.com and by `{owner}/{repo}/raw/{ref}/{path}`
+        // on github.com.
+        let git_ref = match host.as_str() {
+            "raw.githubusercontent.com" => segments.get(2..),
+            "github.com" if segments.get(2).is_some_and(|s| *s == "raw") => segments.get(3..),
+            _ => None,
+        };
+
+        match git_ref {
+            Some(["refs", "heads", ..]) => true,
+            Some([git_ref, ..]) => BRANCH_REFS.iter().any(|b| git_ref.eq_ignore_ascii_case(b)),
+            _ => false,
+        }
+    }
+
+    /// The command, with any line continuations and repeated whitespace
+    /// collapsed, for use in annotations.
+    fn display_command(&self) -> String {
+        self.command
+            .split_whitespace()
+            .filter(|part| *part != "\\")
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Calls `visit` on `node` and each of its named descendants.
+fn walk<'t>(node: Node<'t>, visit: &mut impl FnMut(Node<'t>)) {
+    visit(node);
+
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        walk(child, visit);
+    }
+}
+
+/// Returns the first of `node` and its named descendants that satisfies
+/// `pred`.
+fn find<'t>(node: Node<'t>, pred: &impl Fn(Node<'t>) -> bool) -> Option<Node<'t>> {
+    if pred(node) {
+        return Some(node);
+    }
+
+    let mut cursor = node.walk();
+    let children = node.named_children(&mut cursor).collect::<Vec<_>>();
+    children.into_iter().find_map(|child| find(child, pred))
+}
+
+impl CurlPipeSh {
+    /// Returns the effective name and arguments of a bash `command` node,
+    /// looking through wrappers like `sudo`.
+    fn bash_command<'t, 'src>(
+        node: Node<'t>,
+        source: &'src str,
+    ) -> Option<(&'src str, Vec<&'src str>)> {
+        if node.kind() != "command" {
+            return None;
+        }
+
+        let name = &source[node.child_by_field_name("name")?.byte_range()];
+        let mut cursor = node.walk();
+        let mut args = node
+            .children_by_field_name("argument", &mut cursor)
+            .map(|arg| &source[arg.byte_range()])
+            .collect::<Vec<_>>();
+
+        let mut name = name.rsplit('/').next().unwrap_or(name);
+        while BASH_WRAPPERS.contains(&name) {
+            // Skip the wrapper's own flags and any `env`-style assignments.
+            let idx = args
+                .iter()
+                .position(|arg| !arg.starts_with('-') && !arg.contains('='))?;
+            let wrapped = args[idx];
+            args.drain(..=idx);
+            name = wrapped.rsplit('/').next().unwrap_or(wrapped);
+        }
+
+        Some((name, args))
+    }
+
+    /// Whether the given bash `command` node downloads something to stdout.
+    fn bash_is_download(node: Node<'_>, source: &str) -> bool {
+        match Self::bash_command(node, source) {
+            Some(("curl", _)) => true,
+            // `wget` writes to a file unless told to write to stdout,
+            // e.g. with `-O-` or `-qO -`.
+            Some(("wget", args)) => args.iter().enumerate().any(|(idx, arg)| {
+                let next_is_stdout = args.get(idx + 1) == Some(&"-");
+                *arg == "--output-document=-"
+                    || (!arg.starts_with("--") && arg.starts_with('-') && arg.ends_with("O-"))
+                    || ((*arg == "--output-document"
+                        || (!arg.starts_with("--") && arg.starts_with('-') && arg.ends_with('O')))
+                        && next_is_stdout)
+            }),
+            _ => false,
+        }
+    }
+
+    fn bash_is_interpreter(node: Node<'_>, source: &str) -> bool {
+        Self::bash_command(node, source).is_some_and(|(name, _)| BASH_INTERPRETERS.contains(&name))
+    }
+
+    fn bash_remote_scripts<'src>(&self, script: &'src str) -> Result<Vec<RemoteScript<'src>>> {
+        let masked = utils::mask_expressions(script);
+        let tree = self
+            .bash_parser
+            .lock()
+            .expect("parser lock poisoned")
+            .parse(&masked, None)
+            .context("failed to parse `run:` body as bash")?;
+
+        let mut scripts = vec![];
+
+        // NOTE: Comments and heredoc bodies aren't parsed as commands,
+        // so they're skipped naturally.
+        walk(tree.root_node(), &mut |node| match node.kind() {
+            // `curl ... | sh`, `wget -O- ... | sudo bash`, etc.
+            "pipeline" => {
+                let mut cursor = node.walk();
+                let commands = node
+                    .named_children(&mut cursor)
+                    .filter(|child| child.kind() == "command")
+                    .collect::<Vec<_>>();
+
+                let Some(download) = commands
+                    .iter()
+                    .position(|cmd| Self::bash_is_download(*cmd, &masked))
+                else {
+                    return;
+                };
+
+                if commands[download + 1..]
+                    .iter()
+                    .any(|cmd| Self::bash_is_interpreter(*cmd, &masked))
+                {
+                    scripts.push(RemoteScript::new(node, commands[download], script));
+                }
+            }
+            // `bash <(curl ...)`, `sh -c "$(curl ...)"`, etc.
+            "command" if Self::bash_is_interpreter(node, &masked) => {
+                let mut cursor = node.walk();
+                let args = node
+                    .children_by_field_name("argument", &mut cursor)
+                    .collect::<Vec<_>>();
+
+                let download = args.into_iter().find_map(|arg| {
+                    let substitution = find(arg, &|n| {
+                        matches!(n.kind(), "process_substitution" | "command_substitution")
+                    })?;
+                    find(substitution, &|n| Self::bash_is_download(n, &masked))
+                });
+
+                if let Some(download) = download {
+                    scripts.push(RemoteScript::new(node, download, script));
+                }
+            }
+            _ => (),
+        });
+
+        Ok(scripts)
+    }
+
+    fn pwsh_command_name<'src>(node: Node<'_>, source: &'src str) -> Option<&'src str> {
+        if node.kind() != "command" {
+            return None;
+        }
+
+        Some(&source[node.child_by_field_name("command_name")?.byte_range()])
+    }
+
+    fn pwsh_is_download(node: Node<'_>, source: &str) -> bool {
+        match node.kind() {
+            "command" => Self::pwsh_command_name(node, source).is_some_and(|name| {
+                PWSH_DOWNLOADERS
+                    .iter()
+                    .any(|d| name.eq_ignore_ascii_case(d))
+            }),
+            // `(New-Object System.Net.WebClient).DownloadString(...)`
+            "invokation_expression" => {
+                let mut cursor = node.walk();
+                node.named_children(&mut cursor).any(|child| {
+                    child.kind() == "member_name"
+                        && source[child.byte_range()].eq_ignore_ascii_case("DownloadString")
+                })
+            }
+            _ => false,
+        }
+    }
+
+    fn pwsh_is_evaluator(node: Node<'_>, source: &str) -> bool {
+        Self::pwsh_command_name(node, source)
+            .is_some_and(|name| PWSH_EVALUATORS.iter().any(|e| name.eq_ignore_ascii_case(e)))
+    }
+
+    fn pwsh_remote_scripts<'src>(&self, script: &'src str) -> Result<Vec<RemoteScript<'src>>> {
+        let masked = utils::mask_expressions(script);
+        let tree = self
+            .pwsh_parser
+            .lock()
+            .expect("parser lock poisoned")
+            .parse(&masked, None)
+            .context("failed to parse `run:` body as pwsh")?;
+
+        let mut scripts = vec![];
+
+        walk(tree.root_node(), &mut |node| match node.kind() {
+            // `iwr ... | iex`, `irm ... | Invoke-Expression`, etc.
+            "pipeline" => {
+                let mut cursor = node.walk();
+                let commands = node
+                    .named_children(&mut cursor)
+                    .filter(|child| child.kind() == "command")
+                    .collect::<Vec<_>>();
+
+                let Some(download) = commands
+                    .iter()
+                    .position(|cmd| Self::pwsh_is_download(*cmd, &masked))
+                else {
+                    return;
+                };
+
+                if commands[download + 1..]
+                    .iter()
+                    .any(|cmd| Self::pwsh_is_evaluator(*cmd, &masked))
+                {
+                    scripts.push(RemoteScript::new(node, commands[download], script));
+                }
+            }
+            // `iex (irm ...)`, `iex ((New-Object ...).DownloadString(...))`, etc.
+            "command" if Self::pwsh_is_evaluator(node, &masked) => {
+                let download = node
+                    .child_by_field_name("command_elements")
+                    .and_then(|elements| find(elements, &|n| Self::pwsh_is_download(n, &masked)));
+
+                if let Some(download) = download {
+                    scripts.push(RemoteScript::new(node, download, script));
+                }
+            }
+            _ => (),
+        });
+
+        Ok(scripts)
+    }
+
+    fn remote_scripts<'src>(
+        &self,
+        script: &'src str,
+        shell: &str,
+    ) -> Result<Vec<RemoteScript<'src>>> {
+        // The `shell:` stanza can contain a path and/or multiple arguments,
+        // which we need to normalize out before comparing.
+        match utils::normalize_shell(shell) {
+            "bash" | "sh" => self.bash_remote_scripts(script),
+            "pwsh" | "powershell" => self.pwsh_remote_scripts(script),
+            // TODO: handle cmd and python.
+            _ => Ok(vec![]),
+        }
+    }
+
+    fn process_run<'doc>(
+        &self,
+        step: &impl StepCommon<'doc>,
+        run: &str,
+        shell: &str,
+    ) -> Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        for script in self.remote_scripts(run, shell)? {
+            let command = script.display_command();
+
+            let (subfeature, annotation) = match script.url {
+                Some((url, offset)) => (
+                    Subfeature::new(run[..offset].matches(url).count(), url),
+                    format!("`{command}` runs a script fetched from this URL"),
+                ),
+                None => {
+                    // NOTE: Only the command's first line is used, since
+                    // later lines may be indented differently in the YAML.
+                    let fragment = script.command.lines().next().unwrap_or(script.command);
+                    (
+                        Subfeature::new(run[..script.offset].matches(fragment).count(), fragment),
+                        format!("`{command}` runs a remotely fetched script"),
+                    )
+                }
+            };
+
+            let severity = if script.is_mutable() {
+                Severity::High
+            } else {
+                Severity::Medium
+            };
+
+            findings.push(
+                Self::finding()
+                    .severity(severity)
+                    .confidence(Confidence::High)
+                    .add_location(step.location().hidden())
+                    .add_location(step.location_with_name())
+                    .add_location(
+                        step.location()
+                            .primary()
+                            .with_keys(&["run".into()])
+                            .with_subfeature(subfeature)
+                            .annotated(annotation),
+                    )
+                    .build(step)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
+
+impl Audit for CurlPipeSh {
+    fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        let bash: Language = tree_sitter_bash::LANGUAGE.into();
+        let mut bash_parser = Parser::new();
+        bash_parser
+            .set_language(&bash)
+            .context("failed to load bash parser")
+            .map_err(AuditLoadError::Broken)?;
+
+        let pwsh: Language = tree_sitter_powershell::LANGUAGE.into();
+        let mut pwsh_parser = Parser::new();
+        pwsh_parser
+            .set_language(&pwsh)
+            .context("failed to load powershell parser")
+            .map_err(AuditLoadError::Broken)?;
+
+        Ok(Self {
+            bash_parser: Mutex::new(bash_parser),
+            pwsh_parser: Mutex::new(pwsh_parser),
+        })
+    }
+
+    fn audit_step<'doc>(&self, step: &Step<'doc>) -> Result<Vec<Finding<'doc>>> {
+        let StepBody::Run { run, .. } = &step.body else {
+            return Ok(vec![]);
+        };
+
+        // If we can't infer a shell for this `run:`, assume that it's bash,
+        // like the runner itself does on non-Windows hosts.
+        let shell = step.shell().unwrap_or("bash");
+
+        self.process_run(step, run, shell)
+    }
+
+    fn audit_composite_step<'doc>(&self, step: &CompositeStep<'doc>) -> Result<Vec<Finding<'doc>>> {
+        let action::StepBody::Run { run, shell, .. } = &step.body else {
+            return Ok(vec![]);
+        };
+
+        self.process_run(step, run, shell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CurlPipeSh, RemoteScript};
+    use crate::audit::Audit;
+    use crate::github_api::GitHubHost;
+    use crate::state::AuditState;
+
+    fn audit() -> CurlPipeSh {
+        let audit_state = AuditState {
+            config: &Default::default(),
+            online: false,
+            cache_dir: "/tmp/zizmor".into(),
+            gh_token: None,
+            gh_hostname: GitHubHost::Standard("github.com".into()),
+            tpa_allowlist_file: None,
+            tpa_allowed_org: None,
+            lenient_allowlist: false,
+            advisories_file: None,
+            osv: false,
+            lockfile: None,
+            resolver: Default::default(),
+        };
+
+        CurlPipeSh::new(&audit_state).expect("failed to create audit")
+    }
+
+    #[test]
+    fn test_bash_patterns() {
+        let sut = audit();
+
+        for (case, expected) in &[
+            ("curl -fsSL https://example.com/install.sh | sh", true),
+            (
+                "curl -fsSL https://example.com/install.sh | bash -s -- --yes",
+                true,
+            ),
+            (
+                "curl -fsSL https://example.com/install.sh | sudo -E bash",
+                true,
+            ),
+            ("curl https://example.com/install.sh | /bin/bash", true),
+            ("curl -s https://example.com | tee log | sh", true),
+            ("wget -O- https://example.com/install.sh | sh", true),
+            ("wget -qO- https://example.com/install.sh | sh", true),
+            ("wget -qO - https://example.com/install.sh | sh", true),
+            ("wget --output-document=- https://example.com | bash", true),
+            ("bash <(curl -s https://example.com/install.sh)", true),
+            ("source <(curl -s https://example.com/env.sh)", true),
+            (
+                "sh -c \"$(curl -fsSL https://example.com/install.sh)\"",
+                true,
+            ),
+            (
+                "if true; then\n  curl -s https://example.com | sh\nfi",
+                true,
+            ),
+            ("curl -s \"$INSTALLER_URL\" | sh", true),
+            ("curl -s ${{ inputs.url }} | sh", true),
+            // negative cases
+            (
+                "curl -fsSL https://example.com/install.sh -o install.sh",
+                false,
+            ),
+            ("curl -fsSL https://example.com/data.json | jq .", false),
+            ("wget https://example.com/install.sh | sh", false), // writes to a file
+            ("sh ./install.sh", false),
+            ("echo 'curl https://example.com | sh'", false),
+            ("# curl https://example.com | sh", false),
+            ("cat <<EOF\ncurl https://example.com | sh\nEOF", false),
+            ("sh | curl https://example.com", false), // curl doesn't feed the shell
+        ] {
+            let scripts = sut.remote_scripts(case, "bash").unwrap();
+            assert_eq!(!scripts.is_empty(), *expected, "failed: {case}");
+        }
+
+        // Expressions don't confuse the parser, and are kept in the command.
+        let scripts = sut
+            .remote_scripts(
+                "sudo bash -c \"$(wget -O - https://example.com)\" ${{ matrix.v }}\nls",
+                "bash",
+            )
+            .unwrap();
+        assert_eq!(
+            scripts[0].command,
+            "sudo bash -c \"$(wget -O - https://example.com)\" ${{ matrix.v }}"
+        );
+
+        // Non-bash shells don't get checked for bash patterns.
+        assert!(
+            sut.remote_scripts("curl https://example.com | sh", "pwsh")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_pwsh_patterns() {
+        let sut = audit();
+
+        for (case, expected) in &[
+            ("iwr https://example.com/install.ps1 | iex", true),
+            ("iwr -useb https://example.com/install.ps1 | iex", true),
+            (
+                "irm https://example.com/install.ps1 | Invoke-Expression",
+                true,
+            ),
+            (
+                "Invoke-WebRequest https://example.com/install.ps1 | IEX",
+                true,
+            ),
+            ("iex (irm https://example.com/install.ps1)", true),
+            (
+                "Invoke-Expression ((New-Object System.Net.WebClient).DownloadString('https://example.com/install.ps1'))",
+                true,
+            ),
+            // negative cases
+            (
+                "iwr https://example.com/install.ps1 -OutFile install.ps1",
+                false,
+            ),
+            (
+                "irm https://example.com/data.json | ConvertFrom-Json",
+                false,
+            ),
+            ("# iwr https://example.com/install.ps1 | iex", false),
+            ("iex $command", false),
+        ] {
+            let scripts = sut.remote_scripts(case, "pwsh").unwrap();
+            assert_eq!(!scripts.is_empty(), *expected, "failed: {case}");
+        }
+
+        // pwsh patterns are only checked under pwsh.
+        assert!(
+            sut.remote_scripts("iwr https://example.com/install.ps1 | iex", "bash")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_is_mutable() {
+        for (url, expected) in &[
+            ("https://example.com/install.sh", false),
+            ("https://example.com/releases/v1.2.3/install.sh", false),
+            ("https://example.com/releases/latest/install.sh", true),
+            (
+                "https://github.com/owner/repo/releases/latest/download/install.sh",
+                true,
+            ),
+            (
+                "https://raw.githubusercontent.com/owner/repo/main/install.sh",
+                true,
+            ),
+            (
+                "https://raw.githubusercontent.com/owner/repo/HEAD/install.sh",
+                true,
+            ),
+            (
+                "https://raw.githubusercontent.com/owner/repo/refs/heads/release/install.sh",
+                true,
+            ),
+            (
+                "https://raw.githubusercontent.com/owner/repo/v1.2.3/install.sh",
+                false,
+            ),
+            (
+                "https://raw.githubusercontent.com/owner/repo/0123456789abcdef0123456789abcdef01234567/install.sh",
+                false,
+            ),
+            ("https://github.com/owner/repo/raw/master/install.sh", true),
+            ("https://github.com/owner/repo/raw/v1/install.sh", false),
+        ] {
+            let script = RemoteScript {
+                command: "",
+                offset: 0,
+                url: Some((url, 0)),
+            };
+            assert_eq!(script.is_mutable(), *expected, "failed: {url}");
+        }
+    }
+}
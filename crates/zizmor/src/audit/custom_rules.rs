@@ -0,0 +1,359 @@
+//! Runs the user-defined rules in the configuration's `custom-rules`.
+//!
+//! Each rule is a regular expression that's matched against one kind of
+//! value, e.g. every `run:` script or every `env:` value. Each match is
+//! a finding, identified by the rule's own ID.
+
+use std::collections::HashSet;
+
+use anyhow::{Result, anyhow};
+use github_actions_models::common::expr::LoE;
+use github_actions_models::common::{Env, If, Uses};
+use github_actions_models::{action, workflow};
+use regex::Regex;
+
+use super::{Audit, AuditLoadError, AuditState, Job, audit_meta};
+use crate::config::{CustomRule, CustomRuleTarget};
+use crate::finding::{
+    Confidence, ExternalLocation, Finding, Severity, Subfeature, SymbolicLocation,
+};
+use crate::models::{
+    AsDocument, CompositeStep, JobExt as _, NormalJob, ReusableWorkflowCallJob, Step, StepCommon,
+    Workflow,
+};
+use crate::output::tpa_list;
+
+/// A [`CustomRule`], with its pattern compiled.
+struct CompiledRule {
+    rule: CustomRule,
+    regex: Regex,
+    /// Where the rule is defined in the configuration.
+    source: ExternalLocation,
+}
+
+pub(crate) struct CustomRules {
+    rules: Vec<CompiledRule>,
+}
+
+audit_meta!(
+    CustomRules,
+    "custom-rules",
+    "matched a user-defined rule",
+    Severity::Medium
+);
+
+/// Whether `id` is a valid custom rule ID, i.e. is made of lowercase
+/// letters, digits, and dashes.
+fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && !id.starts_with('-')
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+/// Renders a `uses:` clause back into the form it's written in.
+fn uses_text(uses: &Uses) -> String {
+    match uses {
+        Uses::Local(local) => local.path.clone(),
+        Uses::Repository(repo) => tpa_list::reference(repo),
+        Uses::Docker(docker) => tpa_list::docker_reference(docker),
+    }
+}
+
+impl CustomRules {
+    /// Matches each of this audit's `target` rules against `value`,
+    /// which is at `location`.
+    fn check<'a, 'doc>(
+        &self,
+        doc: &'a impl AsDocument<'a, 'doc>,
+        target: CustomRuleTarget,
+        location: SymbolicLocation<'doc>,
+        value: &str,
+        findings: &mut Vec<Finding<'doc>>,
+    ) -> Result<()> {
+        for CompiledRule {
+            rule,
+            regex,
+            source,
+        } in self.rules.iter().filter(|r| r.rule.target == target)
+        {
+            let Some(m) = regex.find(value) else {
+                continue;
+            };
+
+            let mut location = location.clone().primary().annotated(&rule.description);
+            if !m.is_empty() {
+                location = location.with_subfeature(Subfeature::new(
+                    value[..m.start()].matches(m.as_str()).count(),
+                    m.as_str(),
+                ));
+            }
+
+            findings.push(
+                Self::finding()
+                    .subident(&rule.id)
+                    .severity(rule.severity)
+                    .confidence(Confidence::High)
+                    .add_location(location)
+                    .add_external_location(source.clone())
+                    .build(doc)?,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Matches the `env-values` rules against each value in `env`, which
+    /// is the `env:` block under `parent`.
+    fn check_env<'a, 'doc>(
+        &self,
+        doc: &'a impl AsDocument<'a, 'doc>,
+        parent: &SymbolicLocation<'doc>,
+        env: &'doc LoE<Env>,
+        findings: &mut Vec<Finding<'doc>>,
+    ) -> Result<()> {
+        // NOTE: An `env:` that's an expression has no values to match.
+        let LoE::Literal(env) = env else {
+            return Ok(());
+        };
+
+        for (key, value) in env {
+            self.check(
+                doc,
+                CustomRuleTarget::EnvValues,
+                parent.with_keys(&["env".into(), key.as_str().into()]),
+                &value.to_string(),
+                findings,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Matches the `uses` and `with-values` rules against a `uses:`
+    /// clause and its `with:` block, both under `parent`.
+    fn check_uses<'a, 'doc>(
+        &self,
+        doc: &'a impl AsDocument<'a, 'doc>,
+        parent: &SymbolicLocation<'doc>,
+        uses: &Uses,
+        with: &'doc Env,
+        findings: &mut Vec<Finding<'doc>>,
+    ) -> Result<()> {
+        self.check(
+            doc,
+            CustomRuleTarget::Uses,
+            parent.with_keys(&["uses".into()]),
+            &uses_text(uses),
+            findings,
+        )?;
+
+        for (key, value) in with {
+            self.check(
+                doc,
+                CustomRuleTarget::WithValues,
+                parent.with_keys(&["with".into(), key.as_str().into()]),
+                &value.to_string(),
+                findings,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Matches the `if` rules against the `if:` condition under `parent`,
+    /// if there is one.
+    fn check_if<'a, 'doc>(
+        &self,
+        doc: &'a impl AsDocument<'a, 'doc>,
+        parent: &SymbolicLocation<'doc>,
+        cond: Option<&If>,
+        findings: &mut Vec<Finding<'doc>>,
+    ) -> Result<()> {
+        // NOTE: Boolean conditions have no text to match.
+        let Some(If::Expr(cond)) = cond else {
+            return Ok(());
+        };
+
+        self.check(
+            doc,
+            CustomRuleTarget::If,
+            parent.with_keys(&["if".into()]),
+            cond,
+            findings,
+        )
+    }
+}
+
+impl Audit for CustomRules {
+    fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        let config = state.config;
+        if config.custom_rules.is_empty() {
+            return Err(AuditLoadError::Skip(anyhow!("no custom rules configured")));
+        }
+
+        let mut ids = HashSet::new();
+        let mut rules = vec![];
+        for (idx, rule) in config.custom_rules.iter().enumerate() {
+            if !valid_id(&rule.id) {
+                return Err(AuditLoadError::Fail(anyhow!(
+                    "invalid custom rule ID: {id} (must be lowercase letters, digits, and dashes)",
+                    id = rule.id
+                )));
+            }
+            if !ids.insert(rule.id.as_str()) {
+                return Err(AuditLoadError::Fail(anyhow!(
+                    "duplicate custom rule ID: {id}",
+                    id = rule.id
+                )));
+            }
+
+            let regex = Regex::new(&rule.regex).map_err(|e| {
+                AuditLoadError::Fail(anyhow!(
+                    "invalid regex for custom rule {id}: `{regex}`: {e}",
+                    id = rule.id,
+                    regex = rule.regex
+                ))
+            })?;
+
+            let source = ExternalLocation::new(format!("{id} rule defined", id = rule.id));
+            let source = match config.origin() {
+                Some(origin) => source.with_path(origin, config.custom_rule_point(idx)),
+                None => source,
+            };
+
+            rules.push(CompiledRule {
+                rule: rule.clone(),
+                regex,
+                source,
+            });
+        }
+
+        Ok(Self { rules })
+    }
+
+    fn audit_workflow<'doc>(&self, workflow: &'doc Workflow) -> Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        self.check_env(workflow, &workflow.location(), &workflow.env, &mut findings)?;
+
+        for job in workflow.jobs() {
+            match job {
+                Job::NormalJob(normal) => findings.extend(self.audit_normal_job(&normal)?),
+                Job::ReusableWorkflowCallJob(reusable) => {
+                    findings.extend(self.audit_reusable_job(&reusable)?)
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+
+    fn audit_normal_job<'doc>(&self, job: &NormalJob<'doc>) -> Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+        let workflow = job.parent();
+        let location = job.location();
+
+        self.check_if(workflow, &location, job.r#if.as_ref(), &mut findings)?;
+        self.check_env(workflow, &location, &job.env, &mut findings)?;
+
+        for step in job.steps() {
+            findings.extend(self.audit_step(&step)?);
+        }
+
+        Ok(findings)
+    }
+
+    fn audit_reusable_job<'doc>(
+        &self,
+        job: &ReusableWorkflowCallJob<'doc>,
+    ) -> Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+        let workflow = job.parent();
+        let location = job.location();
+
+        self.check_if(workflow, &location, job.r#if.as_ref(), &mut findings)?;
+        self.check_uses(workflow, &location, &job.uses, &job.with, &mut findings)?;
+
+        Ok(findings)
+    }
+
+    fn audit_step<'doc>(&self, step: &Step<'doc>) -> Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+        let workflow = step.workflow();
+        let location = step.location();
+
+        self.check_if(workflow, &location, step.r#if.as_ref(), &mut findings)?;
+
+        match &step.body {
+            workflow::job::StepBody::Uses { uses, with } => {
+                self.check_uses(workflow, &location, uses, with, &mut findings)?;
+            }
+            workflow::job::StepBody::Run { run, env, .. } => {
+                self.check(
+                    workflow,
+                    CustomRuleTarget::Run,
+                    location.with_keys(&["run".into()]),
+                    run,
+                    &mut findings,
+                )?;
+                self.check_env(workflow, &location, env, &mut findings)?;
+            }
+        }
+
+        Ok(findings)
+    }
+
+    fn audit_composite_step<'doc>(&self, step: &CompositeStep<'doc>) -> Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+        let action = step.action();
+        let location = step.location();
+
+        self.check_if(action, &location, step.r#if.as_ref(), &mut findings)?;
+
+        match &step.body {
+            action::StepBody::Uses { uses, with } => {
+                self.check_uses(action, &location, uses, with, &mut findings)?;
+            }
+            action::StepBody::Run { run, env, .. } => {
+                self.check(
+                    action,
+                    CustomRuleTarget::Run,
+                    location.with_keys(&["run".into()]),
+                    run,
+                    &mut findings,
+                )?;
+                self.check_env(action, &location, env, &mut findings)?;
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::valid_id;
+
+    #[test]
+    fn test_valid_id() {
+        for id in ["no-legacy-key", "rule1", "a"] {
+            assert!(valid_id(id), "{id} should be valid");
+        }
+
+        for id in [
+            "",
+            "-leading",
+            "Upper",
+            "has space",
+            "under_score",
+            "slash/y",
+        ] {
+            assert!(!valid_id(id), "{id} should be invalid");
+        }
+    }
+}
@@ -1,16 +1,47 @@
+use std::ops::Deref;
+use std::sync::LazyLock;
+
 use anyhow::Result;
+use github_actions_expressions::{Expr, context::ContextPattern};
+use github_actions_models::common::{BasePermission, If, Permission, Permissions, Uses};
+use github_actions_models::workflow::job::StepBody;
+use regex::Regex;
 
-use super::{Audit, AuditLoadError, audit_meta};
-use crate::finding::{Confidence, Finding, Severity};
-use crate::models::Workflow;
+use super::{Audit, AuditLoadError, Job, audit_meta};
+use crate::finding::{Confidence, Finding, Persona, Severity};
+use crate::models::{
+    AsDocument as _, JobExt as _, NormalJob, StepCommon as _, Workflow,
+    uses::RepositoryUsesExt as _,
+};
 use crate::state::AuditState;
+use crate::utils::extract_expressions;
+
+/// Contexts that refer to the attacker-controlled head of a pull request
+/// (or of the workflow run that triggered a `workflow_run` workflow).
+static ATTACKER_REF_CONTEXTS: LazyLock<Vec<ContextPattern>> = LazyLock::new(|| {
+    [
+        "github.event.pull_request.head.sha",
+        "github.event.pull_request.head.ref",
+        "github.head_ref",
+        "github.event.workflow_run.head_sha",
+        "github.event.workflow_run.head_branch",
+    ]
+    .iter()
+    .map(|s| ContextPattern::new(s).unwrap())
+    .collect()
+});
+
+/// Matches `if:` conditions that gate a job (or step) on a pull request label.
+static LABEL_CHECK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"github\.event\.(label\.name|pull_request\.labels)").unwrap());
 
 pub(crate) struct DangerousTriggers;
 
 audit_meta!(
     DangerousTriggers,
     "dangerous-triggers",
-    "use of fundamentally insecure workflow trigger"
+    "use of fundamentally insecure workflow trigger",
+    Severity::High
 );
 
 impl Audit for DangerousTriggers {
@@ -51,6 +82,146 @@ impl Audit for DangerousTriggers {
             );
         }
 
+        if workflow.has_pull_request_target() || workflow.has_workflow_run() {
+            for job in workflow.jobs() {
+                let Job::NormalJob(job) = job else {
+                    continue;
+                };
+
+                findings.extend(self.privileged_checkouts(workflow, &job)?);
+            }
+        }
+
         Ok(findings)
     }
 }
+
+impl DangerousTriggers {
+    /// Finds `actions/checkout` steps in `job` that check out the
+    /// attacker-controlled head of the triggering pull request.
+    fn privileged_checkouts<'doc>(
+        &self,
+        workflow: &'doc Workflow,
+        job: &NormalJob<'doc>,
+    ) -> Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        // A checkout is harmless (or at least much less harmful) if the job
+        // has neither a write token nor any secrets to steal.
+        if Self::is_read_only(&job.permissions, &workflow.permissions)
+            && !Self::references_secrets(workflow, job)?
+        {
+            return Ok(findings);
+        }
+
+        let trigger = if workflow.has_pull_request_target() {
+            "pull_request_target"
+        } else {
+            "workflow_run"
+        };
+
+        for step in job.steps() {
+            let StepBody::Uses {
+                uses: Uses::Repository(uses),
+                with,
+            } = &step.deref().body
+            else {
+                continue;
+            };
+
+            if !uses.matches("actions/checkout") {
+                continue;
+            }
+
+            let Some(git_ref) = with.get("ref").map(|r| r.to_string()) else {
+                continue;
+            };
+
+            if !Self::is_attacker_ref(&git_ref) {
+                continue;
+            }
+
+            // Label checks limit who can trigger the job, but a label can be
+            // applied before the attacker pushes new commits to the PR.
+            let persona = if Self::is_label_gated(&job.r#if) || Self::is_label_gated(&step.r#if) {
+                Persona::Pedantic
+            } else {
+                Persona::Regular
+            };
+
+            findings.push(
+                Self::finding()
+                    .confidence(Confidence::High)
+                    .severity(Severity::High)
+                    .persona(persona)
+                    .add_location(
+                        workflow
+                            .location()
+                            .with_keys(&["on".into()])
+                            .annotated(format!(
+                                "{trigger} runs with a privileged token and secrets"
+                            )),
+                    )
+                    .add_location(step.location().hidden())
+                    .add_location(step.location_with_name())
+                    .add_location(
+                        step.location()
+                            .with_keys(&["with".into(), "ref".into()])
+                            .primary()
+                            .annotated("checks out attacker-controlled code"),
+                    )
+                    .build(workflow)?,
+            );
+        }
+
+        Ok(findings)
+    }
+
+    /// Whether a job's effective permissions are read-only.
+    ///
+    /// Default permissions aren't read-only, since privileged triggers
+    /// may receive a write token depending on the repository's settings.
+    pub(crate) fn is_read_only(job: &Permissions, workflow: &Permissions) -> bool {
+        let effective = match job {
+            Permissions::Base(BasePermission::Default) => workflow,
+            _ => job,
+        };
+
+        match effective {
+            Permissions::Base(BasePermission::ReadAll) => true,
+            Permissions::Base(_) => false,
+            Permissions::Explicit(perms) => perms.values().all(|p| *p != Permission::Write),
+        }
+    }
+
+    /// Whether a job expands any secrets into its steps.
+    pub(crate) fn references_secrets(workflow: &Workflow, job: &NormalJob) -> Result<bool> {
+        let job = job.location().concretize(workflow.as_document())?;
+
+        Ok(extract_expressions(job.concrete.feature)
+            .iter()
+            .filter_map(|(expr, _)| Expr::parse(expr.as_bare()).ok())
+            .any(|expr| {
+                expr.dataflow_contexts()
+                    .iter()
+                    .any(|ctx| ctx.child_of("secrets"))
+            }))
+    }
+
+    fn is_attacker_ref(git_ref: &str) -> bool {
+        extract_expressions(git_ref).iter().any(|(expr, _)| {
+            let Ok(parsed) = Expr::parse(expr.as_bare()) else {
+                return false;
+            };
+
+            parsed
+                .dataflow_contexts()
+                .iter()
+                .any(|ctx| ATTACKER_REF_CONTEXTS.iter().any(|pat| pat.matches(ctx)))
+        })
+    }
+
+    fn is_label_gated(cond: &Option<If>) -> bool {
+        matches!(cond, Some(If::Expr(expr)) if LABEL_CHECK.is_match(expr))
+    }
+}
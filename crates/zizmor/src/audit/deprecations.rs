@@ -0,0 +1,124 @@
+//! Detects deprecated (or outright disabled) GitHub Actions features:
+//! the old stdout-based workflow commands, and end-of-life Node.js
+//! action runtimes.
+
+use github_actions_models::action;
+
+use super::{Audit, AuditLoadError, audit_meta};
+use crate::finding::{Confidence, Finding, Persona, Severity, Subfeature};
+use crate::models::{Action, CompositeStep, Step, StepBodyCommon, StepCommon};
+use crate::state::AuditState;
+
+/// Deprecated workflow commands, and the environment files that replace them.
+const WORKFLOW_COMMANDS: &[(&str, &str)] = &[
+    ("::set-output", "$GITHUB_OUTPUT"),
+    ("::save-state", "$GITHUB_STATE"),
+    ("::set-env", "$GITHUB_ENV"),
+    ("::add-path", "$GITHUB_PATH"),
+];
+
+/// End-of-life Node.js runtimes for JavaScript actions.
+const DEPRECATED_RUNTIMES: &[&str] = &["node12", "node16"];
+
+/// The runtime that deprecated runtimes should be replaced with.
+const CURRENT_RUNTIME: &str = "node20";
+
+pub(crate) struct Deprecations;
+
+audit_meta!(
+    Deprecations,
+    "deprecations",
+    "deprecated workflow commands or runtimes",
+    Severity::Low
+);
+
+impl Deprecations {
+    fn process_step<'doc>(
+        &self,
+        step: &impl StepCommon<'doc>,
+    ) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        let StepBodyCommon::Run { run, .. } = step.body() else {
+            return Ok(findings);
+        };
+
+        for (command, replacement) in WORKFLOW_COMMANDS {
+            for (occurrence, _) in run.match_indices(command).enumerate() {
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::Low)
+                        .confidence(Confidence::High)
+                        .add_location(step.location().hidden())
+                        .add_location(step.location_with_name())
+                        .add_location(
+                            step.location()
+                                .with_keys(&["run".into()])
+                                .with_subfeature(Subfeature::new(occurrence, *command))
+                                .primary()
+                                .annotated(format!(
+                                    "{command} is deprecated; write to {replacement} instead"
+                                )),
+                        )
+                        .build(step)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+impl Audit for Deprecations {
+    fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+
+    fn audit_step<'doc>(&self, step: &Step<'doc>) -> anyhow::Result<Vec<Finding<'doc>>> {
+        self.process_step(step)
+    }
+
+    fn audit_composite_step<'a>(
+        &self,
+        step: &CompositeStep<'a>,
+    ) -> anyhow::Result<Vec<Finding<'a>>> {
+        self.process_step(step)
+    }
+
+    fn audit_action<'doc>(&self, action: &'doc Action) -> anyhow::Result<Vec<Finding<'doc>>> {
+        match &action.runs {
+            action::Runs::Composite(_) => {
+                let mut findings = vec![];
+                for step in action.steps() {
+                    findings.extend(self.audit_composite_step(&step)?);
+                }
+                Ok(findings)
+            }
+            action::Runs::JavaScript(javascript)
+                if DEPRECATED_RUNTIMES.contains(&javascript.using.as_str()) =>
+            {
+                Ok(vec![
+                    Self::finding()
+                        .severity(Severity::Low)
+                        .confidence(Confidence::High)
+                        .persona(Persona::Pedantic)
+                        .add_location(
+                            action
+                                .location()
+                                .primary()
+                                .with_keys(&["runs".into(), "using".into()])
+                                .annotated(format!(
+                                    "{using} is end-of-life; use {CURRENT_RUNTIME} instead",
+                                    using = javascript.using
+                                )),
+                        )
+                        .build(action)?,
+                ])
+            }
+            _ => Ok(vec![]),
+        }
+    }
+}
@@ -0,0 +1,310 @@
+//! Detects free-form `workflow_dispatch` inputs that are interpolated into
+//! privileged operations.
+//!
+//! Anyone who can trigger a workflow manually (typically anyone with write
+//! access to the repository, or any automation holding such a token) can
+//! give its `workflow_dispatch` inputs arbitrary values. When a free-form
+//! input is expanded directly into the inputs of a deployment or publishing
+//! action, or into the name of an environment, whoever dispatches the
+//! workflow controls that operation.
+//!
+//! Inputs with `type: choice` or `type: boolean` aren't flagged, since
+//! GitHub constrains their values. Inputs expanded into `run:` scripts
+//! aren't flagged either, since `template-injection` already reports them.
+
+use github_actions_expressions::{Expr, context::Context};
+use github_actions_models::{
+    common::{BasePermission, EnvValue, Permission, Permissions, Uses},
+    workflow::{
+        Trigger,
+        event::{OptionalBody, WorkflowDispatchInput},
+        job::{DeploymentEnvironment, StepBody},
+    },
+};
+
+use super::{Audit, AuditLoadError, audit_meta, dangerous_triggers::DangerousTriggers};
+use crate::{
+    finding::{Confidence, Finding, RouteComponent, Severity, Subfeature, SymbolicLocation},
+    models::{JobExt as _, NormalJob, StepCommon as _, Workflow, uses::RepositoryUsesPattern},
+    state::AuditState,
+    utils::extract_expressions,
+};
+
+/// The well-known actions that deploy or publish something, and whose
+/// inputs therefore choose what gets deployed or published.
+const KNOWN_DEPLOY_ACTIONS: &[&str] = &[
+    "actions/deploy-pages",
+    "aws-actions/amazon-ecs-deploy-task-definition",
+    "azure/webapps-deploy",
+    "cloudflare/wrangler-action",
+    "docker/build-push-action",
+    "google-github-actions/deploy-cloudrun",
+    "JamesIves/github-pages-deploy-action",
+    "ncipollo/release-action",
+    "peaceiris/actions-gh-pages",
+    "pypa/gh-action-pypi-publish",
+    "rubygems/release-gem",
+    "softprops/action-gh-release",
+];
+
+/// The input types whose values GitHub constrains.
+const CONSTRAINED_TYPES: &[&str] = &["boolean", "choice"];
+
+pub(crate) struct DispatchInputs {
+    deploy_actions: Vec<RepositoryUsesPattern>,
+}
+
+audit_meta!(
+    DispatchInputs,
+    "dispatch-inputs",
+    "free-form workflow_dispatch input used in a privileged operation",
+    Severity::Medium
+);
+
+/// A place within a job where a dispatch input's value can be used
+/// in a privileged way.
+struct Sink<'doc> {
+    text: &'doc str,
+    location: SymbolicLocation<'doc>,
+    what: &'static str,
+}
+
+impl DispatchInputs {
+    /// Returns the workflow's `workflow_dispatch` inputs, if any.
+    fn dispatch_inputs(
+        workflow: &Workflow,
+    ) -> impl Iterator<Item = (&String, &WorkflowDispatchInput)> {
+        let inputs = match &workflow.on {
+            Trigger::Events(events) => match &events.workflow_dispatch {
+                OptionalBody::Body(body) => Some(&body.inputs),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        inputs.into_iter().flatten()
+    }
+
+    /// Returns the name of the input that `context` refers to, for
+    /// `inputs.<name>` and `github.event.inputs.<name>` contexts
+    /// (including their `[...]` forms).
+    fn input_name<'a>(context: &'a Context<'a>) -> Option<&'a str> {
+        let raw = context.as_str();
+        let tail = ["github.event.inputs", "inputs"].iter().find_map(|head| {
+            raw.get(..head.len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case(head))
+                .map(|_| &raw[head.len()..])
+        })?;
+
+        let name = if let Some(tail) = tail.strip_prefix('.') {
+            tail.split(['.', '[']).next()
+        } else if let Some(tail) = tail.strip_prefix('[') {
+            tail.strip_prefix('\'')?.split('\'').next()
+        } else {
+            None
+        };
+
+        name.filter(|name| !name.is_empty() && *name != "*")
+    }
+
+    /// Whether a job's effective permissions explicitly include any writes.
+    fn has_write_permissions(job: &Permissions, workflow: &Permissions) -> bool {
+        let effective = match job {
+            Permissions::Base(BasePermission::Default) => workflow,
+            _ => job,
+        };
+
+        match effective {
+            Permissions::Base(base) => *base == BasePermission::WriteAll,
+            Permissions::Explicit(perms) => perms.values().any(|p| *p == Permission::Write),
+        }
+    }
+
+    /// Returns every place in `job` that a dispatch input can be used
+    /// in a privileged way.
+    fn sinks<'doc>(&self, job: &NormalJob<'doc>) -> Vec<Sink<'doc>> {
+        let mut sinks = vec![];
+
+        match &job.environment {
+            Some(DeploymentEnvironment::Name(name)) => sinks.push(Sink {
+                text: name,
+                location: job.location().with_keys(&["environment".into()]),
+                what: "the environment that this job deploys to",
+            }),
+            Some(DeploymentEnvironment::NameURL { name, .. }) => sinks.push(Sink {
+                text: name,
+                location: job
+                    .location()
+                    .with_keys(&["environment".into(), "name".into()]),
+                what: "the environment that this job deploys to",
+            }),
+            None => {}
+        }
+
+        for step in job.steps() {
+            let body: &'doc StepBody = &step.body;
+            match body {
+                StepBody::Uses {
+                    uses: Uses::Repository(uses),
+                    with,
+                } if self
+                    .deploy_actions
+                    .iter()
+                    .any(|action| action.matches(uses)) =>
+                {
+                    sinks.extend(with.iter().filter_map(|(key, value)| {
+                        let EnvValue::String(text) = value else {
+                            return None;
+                        };
+
+                        Some(Sink {
+                            text,
+                            location: step
+                                .location()
+                                .with_keys(&["with".into(), RouteComponent::Key(key.into())]),
+                            what: "what this step deploys or publishes",
+                        })
+                    }));
+                }
+                // NOTE: `run:` scripts are left to `template-injection`,
+                // which flags every input expanded into them.
+                StepBody::Uses { .. } | StepBody::Run { .. } => {}
+            }
+        }
+
+        sinks
+    }
+}
+
+impl Audit for DispatchInputs {
+    fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            // NOTE: Safe unwrap, since these are all valid patterns.
+            deploy_actions: KNOWN_DEPLOY_ACTIONS
+                .iter()
+                .map(|action| action.parse().unwrap())
+                .collect(),
+        })
+    }
+
+    fn audit_normal_job<'doc>(&self, job: &NormalJob<'doc>) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+        let workflow = job.parent();
+
+        let free_form = Self::dispatch_inputs(workflow)
+            .filter(|(_, input)| {
+                !input
+                    .r#type
+                    .as_deref()
+                    .is_some_and(|ty| CONSTRAINED_TYPES.contains(&ty))
+            })
+            .collect::<Vec<_>>();
+        if free_form.is_empty() {
+            return Ok(findings);
+        }
+
+        let severity = if Self::has_write_permissions(&job.permissions, &workflow.permissions)
+            || DangerousTriggers::references_secrets(workflow, job)?
+        {
+            Severity::High
+        } else {
+            Severity::Medium
+        };
+
+        for sink in self.sinks(job) {
+            for (expr, span) in extract_expressions(sink.text) {
+                let Ok(parsed) = Expr::parse(expr.as_bare()) else {
+                    continue;
+                };
+
+                for context in parsed.dataflow_contexts() {
+                    let Some((name, _)) = Self::input_name(context).and_then(|name| {
+                        free_form
+                            .iter()
+                            .find(|(input, _)| input.eq_ignore_ascii_case(name))
+                    }) else {
+                        continue;
+                    };
+
+                    let subfeature = Subfeature::new(
+                        sink.text[..span.start].matches(expr.as_raw()).count(),
+                        expr.as_raw(),
+                    );
+
+                    findings.push(
+                        Self::finding()
+                            .severity(severity)
+                            .confidence(Confidence::Medium)
+                            .add_location(
+                                sink.location
+                                    .clone()
+                                    .with_subfeature(subfeature)
+                                    .primary()
+                                    .annotated(format!(
+                                        "{context} may let whoever dispatches this workflow \
+                                         choose {what}",
+                                        context = context.as_str(),
+                                        what = sink.what,
+                                    )),
+                            )
+                            .add_location(
+                                workflow
+                                    .location()
+                                    .with_keys(&[
+                                        "on".into(),
+                                        "workflow_dispatch".into(),
+                                        "inputs".into(),
+                                        name.as_str().into(),
+                                    ])
+                                    .annotated(format!("input {name} accepts any string")),
+                            )
+                            .build(workflow)?,
+                    );
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use github_actions_expressions::Expr;
+
+    use super::{DispatchInputs, KNOWN_DEPLOY_ACTIONS};
+    use crate::models::uses::RepositoryUsesPattern;
+
+    #[test]
+    fn test_known_patterns() {
+        for uses in KNOWN_DEPLOY_ACTIONS {
+            assert!(uses.parse::<RepositoryUsesPattern>().is_ok(), "{uses}");
+        }
+    }
+
+    #[test]
+    fn test_input_name() {
+        for (expr, name) in [
+            ("inputs.version", Some("version")),
+            ("inputs.version.major", Some("version")),
+            ("INPUTS.Version", Some("Version")),
+            ("inputs['version']", Some("version")),
+            ("github.event.inputs.version", Some("version")),
+            ("github.event.inputs['version']", Some("version")),
+            ("inputs", None),
+            ("inputs.*", None),
+            ("github.event.issue.title", None),
+            ("inputsx.version", None),
+        ] {
+            let parsed = Expr::parse(expr).unwrap();
+            let Expr::Context(context) = &parsed else {
+                panic!("{expr} isn't a context");
+            };
+
+            assert_eq!(DispatchInputs::input_name(context), name, "{expr}");
+        }
+    }
+}
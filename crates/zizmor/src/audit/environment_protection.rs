@@ -0,0 +1,243 @@
+//! Detects jobs that deploy to environments without any protection rules,
+//! and jobs that deploy to environments that don't exist.
+//!
+//! An environment only gates a job's secrets when it has required
+//! reviewers or a deployment branch policy; otherwise any workflow run
+//! on any branch can deploy to it.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use github_actions_expressions::Expr;
+use github_actions_models::workflow::job::DeploymentEnvironment;
+
+use super::{Audit, AuditLoadError, AuditMeta, Network, audit_meta};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::github_api::{self, RateLimited, RepositoryStatus};
+use crate::models::{AsDocument as _, JobExt as _, NormalJob};
+use crate::state::AuditState;
+use crate::utils::extract_expressions;
+
+/// The protection state of a single environment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum EnvironmentStatus {
+    Protected,
+    Unprotected,
+    /// The environment doesn't exist in the job's repository.
+    Missing,
+}
+
+pub(crate) struct EnvironmentProtection {
+    client: github_api::Client,
+    /// The `owner/repo` that local inputs are in, from `GITHUB_REPOSITORY`.
+    local_repository: Option<String>,
+    /// Environment statuses, keyed by lowercased `owner/repo/name`, or
+    /// `None` for environments whose repository isn't visible to our token.
+    environments: Mutex<HashMap<String, Option<EnvironmentStatus>>>,
+}
+
+audit_meta!(
+    EnvironmentProtection,
+    "environment-protection",
+    "deployment to an unprotected environment",
+    Severity::Medium
+);
+
+impl EnvironmentProtection {
+    /// Whether the given job uses any secrets besides `GITHUB_TOKEN`.
+    fn uses_secrets(job: &NormalJob<'_>) -> bool {
+        let Ok(feature) = job.location().concretize(job.parent().as_document()) else {
+            return false;
+        };
+
+        extract_expressions(feature.concrete.feature)
+            .iter()
+            .filter_map(|(expr, _)| Expr::parse(expr.as_bare()).ok())
+            .any(|parsed| {
+                parsed.dataflow_contexts().iter().any(|context| {
+                    context.child_of("secrets")
+                        && !context
+                            .as_str()
+                            .eq_ignore_ascii_case("secrets.GITHUB_TOKEN")
+                })
+            })
+    }
+
+    /// Returns the status of the given environment, or `None` if it
+    /// couldn't be determined.
+    fn environment_status(&self, slug: &str, name: &str) -> Result<Option<EnvironmentStatus>> {
+        let Some((owner, repo)) = slug.split_once('/') else {
+            return Ok(None);
+        };
+
+        let key = format!("{slug}/{name}").to_lowercase();
+        if let Some(status) = self.environments.lock().unwrap().get(&key) {
+            return Ok(*status);
+        }
+
+        let status = match self.client.environment(owner, repo, name) {
+            Ok(Some(environment)) if environment.is_protected() => {
+                Some(EnvironmentStatus::Protected)
+            }
+            Ok(Some(_)) => Some(EnvironmentStatus::Unprotected),
+            // GitHub returns a 404 for both missing environments and
+            // repositories that our token can't see, so we only report
+            // a missing environment if its repository is visible.
+            Ok(None) => match self.client.repository_status(owner, repo) {
+                Ok(RepositoryStatus::Missing) => {
+                    tracing::warn!("{slug}: repository isn't visible; skipping its environments");
+                    None
+                }
+                Ok(_) => Some(EnvironmentStatus::Missing),
+                Err(e) => return self.handle_error(e),
+            },
+            Err(e) => return self.handle_error(e),
+        };
+
+        self.environments.lock().unwrap().insert(key, status);
+        Ok(status)
+    }
+
+    /// Skips the environment if `e` is a rate limit, and propagates
+    /// it otherwise.
+    fn handle_error(&self, e: anyhow::Error) -> Result<Option<EnvironmentStatus>> {
+        RateLimited::skip(Err(e), "environment protection checks")
+    }
+}
+
+impl Audit for EnvironmentProtection {
+    fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        let client = state.online_client()?;
+
+        Ok(Self {
+            client,
+            local_repository: std::env::var("GITHUB_REPOSITORY")
+                .ok()
+                .filter(|slug| slug.contains('/')),
+            environments: Default::default(),
+        })
+    }
+
+    fn meta() -> AuditMeta {
+        AuditMeta {
+            network: Network::Online,
+            ..Default::default()
+        }
+    }
+
+    fn audit_normal_job<'doc>(&self, job: &NormalJob<'doc>) -> Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        let name = match &job.environment {
+            Some(DeploymentEnvironment::Name(name)) => name,
+            Some(DeploymentEnvironment::NameURL { name, .. }) => name,
+            None => return Ok(findings),
+        };
+
+        // Environments chosen by an expression can't be checked statically.
+        if !extract_expressions(name).is_empty() {
+            return Ok(findings);
+        }
+
+        let Some(slug) = job
+            .parent()
+            .key
+            .repository()
+            .or_else(|| self.local_repository.clone())
+        else {
+            tracing::debug!(
+                "{key}: no repository to check environments in",
+                key = job.parent().key
+            );
+            return Ok(findings);
+        };
+
+        let location = match job.environment {
+            Some(DeploymentEnvironment::NameURL { .. }) => job
+                .location()
+                .with_keys(&["environment".into(), "name".into()]),
+            _ => job.location().with_keys(&["environment".into()]),
+        };
+
+        match self.environment_status(&slug, name)? {
+            Some(EnvironmentStatus::Missing) => findings.push(
+                Self::finding()
+                    .confidence(Confidence::High)
+                    .severity(Severity::Low)
+                    .add_location(
+                        location
+                            .primary()
+                            .annotated(format!("environment doesn't exist in {slug}")),
+                    )
+                    .build(job.parent())?,
+            ),
+            Some(EnvironmentStatus::Unprotected) if Self::uses_secrets(job) => findings.push(
+                Self::finding()
+                    .confidence(Confidence::High)
+                    .severity(Severity::Medium)
+                    .add_location(
+                        location
+                            .primary()
+                            .annotated("environment has no required reviewers or branch policy"),
+                    )
+                    .add_location(job.location().annotated("this job uses secrets"))
+                    .build(job.parent())?,
+            ),
+            _ => {}
+        }
+
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EnvironmentProtection;
+    use crate::models::{Job, Workflow};
+    use crate::registry::InputKey;
+
+    #[test]
+    fn test_uses_secrets() {
+        let workflow = Workflow::from_string(
+            r#"
+on: push
+
+jobs:
+  token-only:
+    runs-on: ubuntu-latest
+    environment: production
+    steps:
+      - run: echo ${{ secrets.GITHUB_TOKEN }}
+
+  secret:
+    runs-on: ubuntu-latest
+    environment: production
+    steps:
+      - run: ./deploy
+        env:
+          KEY: ${{ secrets.DEPLOY_KEY }}
+
+  none:
+    runs-on: ubuntu-latest
+    steps:
+      - run: echo hello
+"#
+            .into(),
+            InputKey::local("fake", None).unwrap(),
+        )
+        .unwrap();
+
+        let uses_secrets = workflow
+            .jobs()
+            .map(|job| match job {
+                Job::NormalJob(job) => EnvironmentProtection::uses_secrets(&job),
+                Job::ReusableWorkflowCallJob(_) => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(uses_secrets, [false, true, false]);
+    }
+}
@@ -1,16 +1,20 @@
 use std::{collections::HashMap, sync::LazyLock};
 
+use anyhow::Context as _;
 use github_actions_models::common::{BasePermission, Permission, Permissions};
+use globset::{Glob, GlobMatcher};
+use indexmap::IndexMap;
+use serde::Deserialize;
 
-use super::{Audit, AuditLoadError, Job, audit_meta};
-use crate::models::JobExt as _;
+use super::{Audit, AuditLoadError, AuditMeta, Job, audit_meta};
+use crate::models::{JobExt as _, Workflow};
 use crate::{
     AuditState,
     finding::{Confidence, Persona, Severity, SymbolicLocation},
 };
 
 // Subjective mapping of permissions to severities, when given `write` access.
-static KNOWN_PERMISSIONS: LazyLock<HashMap<&str, Severity>> = LazyLock::new(|| {
+pub(crate) static KNOWN_PERMISSIONS: LazyLock<HashMap<&str, Severity>> = LazyLock::new(|| {
     [
         ("actions", Severity::High),
         ("attestations", Severity::High),
@@ -34,17 +38,57 @@ static KNOWN_PERMISSIONS: LazyLock<HashMap<&str, Severity>> = LazyLock::new(|| {
 audit_meta!(
     ExcessivePermissions,
     "excessive-permissions",
-    "overly broad permissions"
+    "overly broad permissions",
+    Severity::Medium
 );
 
-pub(crate) struct ExcessivePermissions;
+/// The raw `excessive-permissions` configuration.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ExcessivePermissionsConfig {
+    /// Maps workflow path patterns to the `write` scopes that are
+    /// acceptable at the workflow level for matching workflows.
+    #[serde(default)]
+    allowed_permissions: IndexMap<String, Vec<String>>,
+}
+
+pub(crate) struct ExcessivePermissions {
+    allowed_permissions: Vec<(GlobMatcher, Vec<String>)>,
+}
 
 impl Audit for ExcessivePermissions {
-    fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError>
     where
         Self: Sized,
     {
-        Ok(Self)
+        let config = state
+            .config
+            .rule_config::<ExcessivePermissionsConfig>(Self::ident())
+            .map_err(AuditLoadError::Fail)?;
+
+        let allowed_permissions = config
+            .map(|config| config.allowed_permissions)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(pattern, scopes)| {
+                Glob::new(&pattern)
+                    .map(|glob| (glob.compile_matcher(), scopes))
+                    .with_context(|| format!("invalid workflow pattern: {pattern}"))
+            })
+            .collect::<anyhow::Result<_>>()
+            .with_context(|| state.config.rule_error_context(Self::ident()))
+            .map_err(AuditLoadError::Fail)?;
+
+        Ok(Self {
+            allowed_permissions,
+        })
+    }
+
+    fn meta() -> AuditMeta {
+        AuditMeta {
+            config_keys: &["allowed-permissions"],
+            ..Default::default()
+        }
     }
 
     fn audit_workflow<'doc>(
@@ -86,7 +130,7 @@ impl Audit for ExcessivePermissions {
         let location = workflow.location().primary();
 
         for (severity, confidence, perm_location) in
-            self.check_workflow_permissions(&workflow.permissions, location)
+            self.check_workflow_permissions(workflow, location)
         {
             findings.push(
                 Self::finding()
@@ -141,20 +185,40 @@ impl Audit for ExcessivePermissions {
 }
 
 impl ExcessivePermissions {
+    /// Returns whether `name: write` is explicitly allowed for `workflow`
+    /// by the user's configuration.
+    fn write_allowed(&self, workflow: &Workflow, name: &str) -> bool {
+        let key = &workflow.key;
+
+        self.allowed_permissions.iter().any(|(pattern, scopes)| {
+            (pattern.is_match(key.sarif_path()) || pattern.is_match(key.filename()))
+                && scopes.iter().any(|scope| scope == name)
+        })
+    }
+
     fn check_workflow_permissions<'a>(
         &self,
-        permissions: &'a Permissions,
+        workflow: &'a Workflow,
         location: SymbolicLocation<'a>,
     ) -> Vec<(Severity, Confidence, SymbolicLocation<'a>)> {
         let mut results = vec![];
 
-        match &permissions {
+        match &workflow.permissions {
             Permissions::Base(base) => match base {
-                BasePermission::Default => results.push((
-                    Severity::Medium,
-                    Confidence::Medium,
-                    location.annotated("default permissions used due to no permissions: block"),
-                )),
+                BasePermission::Default => {
+                    // There's no permissions: block to point at, so we point
+                    // at the workflow's name instead (if it has one).
+                    let location = match workflow.name {
+                        Some(_) => location.with_keys(&["name".into()]),
+                        None => location,
+                    };
+
+                    results.push((
+                        Severity::Medium,
+                        Confidence::Medium,
+                        location.annotated("default permissions used due to no permissions: block"),
+                    ))
+                }
                 BasePermission::ReadAll => results.push((
                     Severity::Medium,
                     Confidence::High,
@@ -172,7 +236,7 @@ impl ExcessivePermissions {
             },
             Permissions::Explicit(perms) => {
                 for (name, perm) in perms {
-                    if *perm != Permission::Write {
+                    if *perm != Permission::Write || self.write_allowed(workflow, name) {
                         continue;
                     }
 
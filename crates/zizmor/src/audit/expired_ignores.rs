@@ -0,0 +1,28 @@
+//! Reports entries in the configuration's `ignores` that have expired.
+//!
+//! Unlike other audits, this one doesn't inspect inputs itself: the
+//! [`FindingRegistry`](crate::registry::FindingRegistry) produces its
+//! findings as it matches other findings against the configured ignores,
+//! since only then is it known which findings an expired entry covers.
+
+use super::{Audit, AuditLoadError, audit_meta};
+use crate::finding::Severity;
+use crate::state::AuditState;
+
+pub(crate) struct ExpiredIgnores;
+
+audit_meta!(
+    ExpiredIgnores,
+    "expired-ignores",
+    "configured ignore has expired",
+    Severity::Low
+);
+
+impl Audit for ExpiredIgnores {
+    fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+}
@@ -1,7 +1,7 @@
-use anyhow::{Context, anyhow};
+use anyhow::anyhow;
 use github_actions_models::common::Uses;
 
-use super::{Audit, AuditLoadError, AuditState, audit_meta};
+use super::{Audit, AuditLoadError, AuditMeta, AuditState, audit_meta};
 use crate::finding::{Confidence, Finding, Persona, Severity};
 use crate::models::uses::RepositoryUsesPattern;
 use crate::models::{CompositeStep, Step, StepCommon};
@@ -11,7 +11,12 @@ pub(crate) struct ForbiddenUses {
     config: ForbiddenUsesConfig,
 }
 
-audit_meta!(ForbiddenUses, "forbidden-uses", "forbidden action used");
+audit_meta!(
+    ForbiddenUses,
+    "forbidden-uses",
+    "forbidden action used",
+    Severity::High
+);
 
 impl ForbiddenUses {
     fn use_denied(&self, uses: &Uses) -> bool {
@@ -74,7 +79,6 @@ impl Audit for ForbiddenUses {
         let Some(config) = state
             .config
             .rule_config(Self::ident())
-            .context("invalid configuration")
             .map_err(AuditLoadError::Fail)?
         else {
             return Err(AuditLoadError::Skip(anyhow!("audit not configured")));
@@ -83,6 +87,13 @@ impl Audit for ForbiddenUses {
         Ok(Self { config })
     }
 
+    fn meta() -> AuditMeta {
+        AuditMeta {
+            config_keys: &["allow", "deny"],
+            ..Default::default()
+        }
+    }
+
     fn audit_step<'doc>(&self, step: &Step<'doc>) -> anyhow::Result<Vec<Finding<'doc>>> {
         self.process_step(step)
     }
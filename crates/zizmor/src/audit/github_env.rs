@@ -1,8 +1,8 @@
-use std::cell::RefCell;
 use std::ops::{Deref, Range};
-use std::sync::LazyLock;
+use std::sync::{LazyLock, Mutex};
 
 use anyhow::{Context, Result};
+use github_actions_expressions::{Expr, context::ContextPattern};
 use github_actions_models::action;
 use github_actions_models::workflow::job::StepBody;
 use regex::Regex;
@@ -11,19 +11,80 @@ use tree_sitter::{
 };
 
 use super::{Audit, AuditLoadError, audit_meta};
-use crate::finding::{Confidence, Finding, Severity};
+use crate::finding::{Confidence, Finding, Severity, Subfeature};
 use crate::models::{JobExt as _, Step, StepCommon};
 use crate::state::AuditState;
 use crate::utils;
 
 static GITHUB_ENV_WRITE_CMD: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r#"(?mi)^.+\s*>>?\s*"?%(?<destination>GITHUB_ENV|GITHUB_PATH)%"?.*$"#).unwrap()
+    Regex::new(r#"(?mi)^.+\s*>>?\s*"?%(?<destination>GITHUB_ENV|GITHUB_PATH|GITHUB_OUTPUT)%"?.*$"#)
+        .unwrap()
 });
 
+/// Contexts containing free-form text that's fully attacker-controlled,
+/// e.g. an issue's title.
+static ATTACKER_TEXT_CONTEXTS: LazyLock<Vec<ContextPattern>> = LazyLock::new(|| {
+    [
+        "github.event.issue.title",
+        "github.event.issue.body",
+        "github.event.pull_request.title",
+        "github.event.pull_request.body",
+        "github.event.pull_request.head.label",
+        "github.event.comment.body",
+        "github.event.review.body",
+        "github.event.review_comment.body",
+        "github.event.discussion.title",
+        "github.event.discussion.body",
+        "github.event.head_commit.message",
+        "github.event.head_commit.author.email",
+        "github.event.head_commit.author.name",
+        "github.event.commits.*.message",
+        "github.event.commits.*.author.email",
+        "github.event.commits.*.author.name",
+        "github.event.pages.*.page_name",
+        "github.event.workflow_run.display_title",
+        "github.event.workflow_run.head_commit.message",
+    ]
+    .iter()
+    .map(|s| ContextPattern::new(s).unwrap())
+    .collect()
+});
+
+/// Contexts containing attacker-controlled branch names, which are
+/// constrained to Git's ref name rules.
+static ATTACKER_REF_CONTEXTS: LazyLock<Vec<ContextPattern>> = LazyLock::new(|| {
+    [
+        "github.head_ref",
+        "github.event.pull_request.head.ref",
+        "github.event.workflow_run.head_branch",
+    ]
+    .iter()
+    .map(|s| ContextPattern::new(s).unwrap())
+    .collect()
+});
+
+/// A single write to an environment file within a `run:` block.
+struct EnvWrite<'hay> {
+    /// The environment file written to, e.g. `GITHUB_ENV`.
+    destination: &'hay str,
+    /// The span of the writing command within the `run:` block.
+    span: Range<usize>,
+    /// Whether the written value is trivially static, e.g.
+    /// `echo "FOO=bar" >> $GITHUB_ENV`.
+    is_static: bool,
+}
+
+impl EnvWrite<'_> {
+    fn is_output(&self) -> bool {
+        self.destination.to_uppercase().ends_with("GITHUB_OUTPUT")
+    }
+}
+
 pub(crate) struct GitHubEnv {
-    // NOTE: interior mutability used since Parser::parse requires &mut self
-    bash_parser: RefCell<Parser>,
-    pwsh_parser: RefCell<Parser>,
+    // NOTE: interior mutability used since Parser::parse requires &mut self,
+    // and audits are shared between threads
+    bash_parser: Mutex<Parser>,
+    pwsh_parser: Mutex<Parser>,
 
     // cached queries
     bash_redirect_query: SpannedQuery,
@@ -32,7 +93,12 @@ pub(crate) struct GitHubEnv {
     pwsh_pipeline_query: SpannedQuery,
 }
 
-audit_meta!(GitHubEnv, "github-env", "dangerous use of environment file");
+audit_meta!(
+    GitHubEnv,
+    "github-env",
+    "dangerous use of environment file",
+    Severity::High
+);
 
 /// Holds a tree-sitter query that contains a `@span` capture that
 /// covers the entire range of the query.
@@ -76,7 +142,7 @@ const BASH_REDIRECT_QUERY: &str = r#"
      (simple_expansion (variable_name) @destination)
    ]
  ))
- (#match? @destination "^(GITHUB_ENV|GITHUB_PATH)$")
+ (#match? @destination "^(GITHUB_ENV|GITHUB_PATH|GITHUB_OUTPUT)$")
 ) @span
 "#;
 
@@ -91,7 +157,7 @@ const BASH_PIPELINE_QUERY: &str = r#"
     ]
   )
   (#match? @cmd "tee")
-  (#match? @destination "^(GITHUB_ENV|GITHUB_PATH)$")
+  (#match? @destination "^(GITHUB_ENV|GITHUB_PATH|GITHUB_OUTPUT)$")
 ) @span
 "#;
 
@@ -110,7 +176,7 @@ const PWSH_REDIRECT_QUERY: &str = r#"
       )
     (_)*
   )
-  (#match? @destination "(?i)ENV:GITHUB_ENV|ENV:GITHUB_PATH")
+  (#match? @destination "(?i)ENV:GITHUB_ENV|ENV:GITHUB_PATH|ENV:GITHUB_OUTPUT")
 )) @span
 "#;
 
@@ -129,7 +195,7 @@ const PWSH_PIPELINE_QUERY: &str = r#"
       )
       (_)*))
   (#match? @cmd "(?i)out-file|add-content|set-content|tee-object")
-  (#match? @destination "(?i)ENV:GITHUB_ENV|ENV:GITHUB_PATH")
+  (#match? @destination "(?i)ENV:GITHUB_ENV|ENV:GITHUB_PATH|ENV:GITHUB_OUTPUT")
 ) @span
 "#;
 
@@ -173,25 +239,27 @@ impl GitHubEnv {
         cursor.matches(query, tree.root_node(), source.as_bytes())
     }
 
-    fn bash_uses_github_env<'hay>(
-        &self,
-        script_body: &'hay str,
-    ) -> Result<Vec<(&'hay str, Range<usize>)>> {
+    fn bash_uses_github_env<'hay>(&self, script_body: &'hay str) -> Result<Vec<EnvWrite<'hay>>> {
         let mut cursor = QueryCursor::new();
 
+        // Expressions are masked out before parsing, since they otherwise
+        // confuse the parser. This preserves offsets into the original body.
+        let masked = utils::mask_expressions(script_body);
+
         let tree = self
             .bash_parser
-            .borrow_mut()
-            .parse(script_body, None)
+            .lock()
+            .expect("parser lock poisoned")
+            .parse(&masked, None)
             .context("failed to parse `run:` body as bash")?;
 
         // Look for redirect patterns, e.g. `... >> $GITHUB_ENV`.
         //
-        // This requires a bit of extra work, since we want to filter
-        // out false positives like `echo "foo" >> $GITHUB_ENV`, where
-        // the LHS is something trivial like `echo` with only string
-        // literal arguments (no variable expansions).
-        let matches = self.query(&self.bash_redirect_query, &mut cursor, &tree, script_body);
+        // This requires a bit of extra work, since we want to distinguish
+        // trivial writes like `echo "foo" >> $GITHUB_ENV`, where the LHS
+        // is something like `echo` with only string literal arguments
+        // (no variable expansions).
+        let matches = self.query(&self.bash_redirect_query, &mut cursor, &tree, &masked);
         let cmd = self
             .bash_redirect_query
             .capture_index_for_name("cmd")
@@ -201,35 +269,44 @@ impl GitHubEnv {
             .capture_index_for_name("args")
             .unwrap();
 
-        let mut matching_spans = vec![];
+        let mut writes = vec![];
 
         matches.for_each(|mat| {
             let cmd = {
                 let cap = mat.captures.iter().find(|cap| cap.index == cmd).unwrap();
-                cap.node.utf8_text(script_body.as_bytes()).unwrap()
+                &script_body[cap.node.byte_range()]
             };
 
             let args = mat.captures.iter().filter(|cap| cap.index == args);
 
-            // Filter matches down to those where the command isn't `echo`
-            // *or* at least one argument isn't a string literal.
-            if cmd != "echo" || !self.bash_echo_args_are_safe(args) {
-                let span = mat
+            let span = mat
+                .captures
+                .iter()
+                .find(|cap| cap.index == self.bash_redirect_query.span_idx)
+                .unwrap()
+                .node
+                .byte_range();
+
+            let destination = {
+                let cap = mat
                     .captures
                     .iter()
-                    .find(|cap| cap.index == self.bash_redirect_query.span_idx)
+                    .find(|cap| cap.index == self.bash_redirect_query.destination_idx)
                     .unwrap();
+                &script_body[cap.node.byte_range()]
+            };
 
-                let destination = {
-                    let cap = mat
-                        .captures
-                        .iter()
-                        .find(|cap| cap.index == self.bash_redirect_query.destination_idx)
-                        .unwrap();
-                    cap.node.utf8_text(script_body.as_bytes()).unwrap()
-                };
-                matching_spans.push((destination, span.node.byte_range()));
-            }
+            // The write is static if the command is `echo` and every
+            // argument is a string literal without any expressions.
+            let is_static = cmd == "echo"
+                && self.bash_echo_args_are_safe(args)
+                && utils::extract_expressions(&script_body[span.clone()]).is_empty();
+
+            writes.push(EnvWrite {
+                destination,
+                span,
+                is_static,
+            });
         });
 
         let queries = [
@@ -238,7 +315,7 @@ impl GitHubEnv {
         ];
 
         for query in queries {
-            let matches = self.query(query, &mut cursor, &tree, script_body);
+            let matches = self.query(query, &mut cursor, &tree, &masked);
 
             matches.for_each(|mat| {
                 let span = mat
@@ -253,44 +330,47 @@ impl GitHubEnv {
                         .iter()
                         .find(|cap| cap.index == query.destination_idx)
                         .unwrap();
-                    cap.node.utf8_text(script_body.as_bytes()).unwrap()
+                    &script_body[cap.node.byte_range()]
                 };
 
-                matching_spans.push((destination, span.node.byte_range()));
+                writes.push(EnvWrite {
+                    destination,
+                    span: span.node.byte_range(),
+                    is_static: false,
+                });
             });
         }
 
-        Ok(matching_spans)
+        Ok(writes)
     }
 
-    fn cmd_uses_github_env<'hay>(&self, script_body: &'hay str) -> Vec<(&'hay str, Range<usize>)> {
+    fn cmd_uses_github_env<'hay>(&self, script_body: &'hay str) -> Vec<EnvWrite<'hay>> {
         GITHUB_ENV_WRITE_CMD
             .captures_iter(script_body)
-            .map(|c| {
-                let name = c.name("destination").unwrap().as_str();
-                let span = c.name("destination").unwrap().range();
-
-                (name, span)
+            .map(|c| EnvWrite {
+                destination: c.name("destination").unwrap().as_str(),
+                span: c.get(0).unwrap().range(),
+                is_static: false,
             })
             .collect()
     }
 
-    fn pwsh_uses_github_env<'hay>(
-        &self,
-        script_body: &'hay str,
-    ) -> Result<Vec<(&'hay str, Range<usize>)>> {
+    fn pwsh_uses_github_env<'hay>(&self, script_body: &'hay str) -> Result<Vec<EnvWrite<'hay>>> {
+        let masked = utils::mask_expressions(script_body);
+
         let tree = &self
             .pwsh_parser
-            .borrow_mut()
-            .parse(script_body, None)
+            .lock()
+            .expect("parser lock poisoned")
+            .parse(&masked, None)
             .context("failed to parse `run:` body as pwsh")?;
 
         let mut cursor = QueryCursor::new();
         let queries = [&self.pwsh_redirect_query, &self.pwsh_pipeline_query];
-        let mut matching_spans = vec![];
+        let mut writes = vec![];
 
         for query in queries {
-            let matches = self.query(query, &mut cursor, tree, script_body);
+            let matches = self.query(query, &mut cursor, tree, &masked);
             matches.for_each(|mat| {
                 let span = mat
                     .captures
@@ -304,21 +384,26 @@ impl GitHubEnv {
                         .iter()
                         .find(|cap| cap.index == query.destination_idx)
                         .unwrap();
-                    cap.node.utf8_text(script_body.as_bytes()).unwrap()
+                    &script_body[cap.node.byte_range()]
                 };
 
-                matching_spans.push((destination, span.node.byte_range()));
+                writes.push(EnvWrite {
+                    destination,
+                    span: span.node.byte_range(),
+                    is_static: false,
+                });
             });
         }
 
-        Ok(matching_spans)
+        Ok(writes)
     }
 
-    fn uses_github_env<'hay>(
+    /// Returns every write to an environment file in the given `run:` body.
+    fn env_writes<'hay>(
         &self,
         run_step_body: &'hay str,
         shell: &str,
-    ) -> Result<Vec<(&'hay str, Range<usize>)>> {
+    ) -> Result<Vec<EnvWrite<'hay>>> {
         // The `shell:` stanza can contain a path and/or multiple arguments,
         // which we need to normalize out before comparing.
         // For example, `shell: /bin/bash -e {0}` becomes `bash`.
@@ -337,6 +422,109 @@ impl GitHubEnv {
             }
         }
     }
+
+    /// Returns every non-trivial write to `GITHUB_ENV` or `GITHUB_PATH`
+    /// in the given `run:` body.
+    #[cfg(test)]
+    fn uses_github_env<'hay>(
+        &self,
+        run_step_body: &'hay str,
+        shell: &str,
+    ) -> Result<Vec<EnvWrite<'hay>>> {
+        Ok(self
+            .env_writes(run_step_body, shell)?
+            .into_iter()
+            .filter(|write| !write.is_static && !write.is_output())
+            .collect())
+    }
+
+    /// Returns the most attacker-controllable context (and its severity)
+    /// that's expanded into the given write, if any.
+    fn attacker_controlled_context(
+        run_step_body: &str,
+        write: &EnvWrite<'_>,
+    ) -> Option<(String, Severity)> {
+        let mut worst: Option<(String, Severity)> = None;
+
+        for (expr, _) in utils::extract_expressions(&run_step_body[write.span.clone()]) {
+            let Ok(parsed) = Expr::parse(expr.as_bare()) else {
+                continue;
+            };
+
+            for context in parsed.dataflow_contexts() {
+                let severity = if ATTACKER_TEXT_CONTEXTS.iter().any(|p| p.matches(context)) {
+                    Severity::High
+                } else if ATTACKER_REF_CONTEXTS.iter().any(|p| p.matches(context)) {
+                    Severity::Medium
+                } else {
+                    continue;
+                };
+
+                if worst.as_ref().is_none_or(|(_, worst)| severity > *worst) {
+                    worst = Some((context.as_str().to_string(), severity));
+                }
+            }
+        }
+
+        worst
+    }
+
+    fn process_run<'doc>(
+        &self,
+        step: &impl StepCommon<'doc>,
+        run: &str,
+        shell: &str,
+        dangerous_triggers: bool,
+    ) -> Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        for write in self.env_writes(run, shell)? {
+            if let Some((context, severity)) = Self::attacker_controlled_context(run, &write) {
+                // NOTE: Only the command's first line is used, since
+                // later lines may be indented differently in the YAML.
+                let command = &run[write.span.clone()];
+                let fragment = command.lines().next().unwrap_or(command);
+
+                findings.push(
+                    Self::finding()
+                        .severity(severity)
+                        .confidence(Confidence::High)
+                        .add_location(
+                            step.location()
+                                .primary()
+                                .with_keys(&["run".into()])
+                                .with_subfeature(Subfeature::new(
+                                    run[..write.span.start].matches(fragment).count(),
+                                    fragment,
+                                ))
+                                .annotated(format!(
+                                    "writes attacker-controlled `{context}` to {dest}",
+                                    dest = write.destination
+                                )),
+                        )
+                        .build(step)?,
+                );
+            } else if dangerous_triggers && !write.is_static && !write.is_output() {
+                findings.push(
+                    Self::finding()
+                        .severity(Severity::High)
+                        .confidence(Confidence::Low)
+                        .add_location(
+                            step.location()
+                                .primary()
+                                .with_keys(&["run".into()])
+                                .annotated(format!(
+                                    "write to {dest} may allow code execution",
+                                    dest = write.destination
+                                )),
+                        )
+                        .build(step)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
 }
 
 impl Audit for GitHubEnv {
@@ -349,18 +537,18 @@ impl Audit for GitHubEnv {
         bash_parser
             .set_language(&bash)
             .context("failed to load bash parser")
-            .map_err(AuditLoadError::Skip)?;
+            .map_err(AuditLoadError::Broken)?;
 
         let pwsh = tree_sitter_powershell::LANGUAGE.into();
         let mut pwsh_parser = Parser::new();
         pwsh_parser
             .set_language(&pwsh)
             .context("failed to load powershell parser")
-            .map_err(AuditLoadError::Skip)?;
+            .map_err(AuditLoadError::Broken)?;
 
         Ok(Self {
-            bash_parser: RefCell::new(bash_parser),
-            pwsh_parser: RefCell::new(pwsh_parser),
+            bash_parser: Mutex::new(bash_parser),
+            pwsh_parser: Mutex::new(pwsh_parser),
             bash_redirect_query: SpannedQuery::new(BASH_REDIRECT_QUERY, &bash),
             bash_pipeline_query: SpannedQuery::new(BASH_PIPELINE_QUERY, &bash),
             pwsh_redirect_query: SpannedQuery::new(PWSH_REDIRECT_QUERY, &pwsh),
@@ -369,80 +557,43 @@ impl Audit for GitHubEnv {
     }
 
     fn audit_step<'doc>(&self, step: &Step<'doc>) -> anyhow::Result<Vec<Finding<'doc>>> {
-        let mut findings = vec![];
-
         let workflow = step.workflow();
 
-        let has_dangerous_triggers =
-            workflow.has_workflow_run() || workflow.has_pull_request_target();
-
-        if !has_dangerous_triggers {
-            return Ok(findings);
-        }
-
-        if let StepBody::Run { run, .. } = &step.deref().body {
-            let shell = step.shell().unwrap_or_else(|| {
-                tracing::warn!(
-                    "github-env: couldn't determine shell type for {workflow}:{job} step {stepno}",
-                    workflow = step.workflow().key.filename(),
-                    job = step.parent.id(),
-                    stepno = step.index
-                );
+        let StepBody::Run { run, .. } = &step.deref().body else {
+            return Ok(vec![]);
+        };
 
-                // If we can't infer a shell for this `run:`, assume that it's
-                // bash. This won't be correct on self-hosted Windows runners
-                // that don't use the default routing labels, but there's
-                // nothing we can do about that.
-                "bash"
-            });
+        let shell = step.shell().unwrap_or_else(|| {
+            tracing::warn!(
+                "github-env: couldn't determine shell type for {workflow}:{job} step {stepno}",
+                workflow = workflow.key.filename(),
+                job = step.parent.id(),
+                stepno = step.index
+            );
+
+            // If we can't infer a shell for this `run:`, assume that it's
+            // bash. This won't be correct on self-hosted Windows runners
+            // that don't use the default routing labels, but there's
+            // nothing we can do about that.
+            "bash"
+        });
 
-            // TODO: actually use the spanning information here.
-            for (dest, _span) in self.uses_github_env(run, shell)? {
-                findings.push(
-                    Self::finding()
-                        .severity(Severity::High)
-                        .confidence(Confidence::Low)
-                        .add_location(
-                            step.location()
-                                .primary()
-                                .with_keys(&["run".into()])
-                                .annotated(format!("write to {dest} may allow code execution")),
-                        )
-                        .build(step.workflow())?,
-                )
-            }
-        }
+        let dangerous_triggers = workflow.has_workflow_run() || workflow.has_pull_request_target();
 
-        Ok(findings)
+        self.process_run(step, run, shell, dangerous_triggers)
     }
 
     fn audit_composite_step<'doc>(
         &self,
         step: &super::CompositeStep<'doc>,
     ) -> Result<Vec<Finding<'doc>>> {
-        let mut findings = vec![];
-
         let action::StepBody::Run { run, shell, .. } = &step.body else {
-            return Ok(findings);
+            return Ok(vec![]);
         };
 
-        // TODO: actually use the spanning information here.
-        for (dest, _span) in self.uses_github_env(run, shell)? {
-            findings.push(
-                Self::finding()
-                    .severity(Severity::High)
-                    .confidence(Confidence::Low)
-                    .add_location(
-                        step.location()
-                            .primary()
-                            .with_keys(&["run".into()])
-                            .annotated(format!("write to {dest} may allow code execution")),
-                    )
-                    .build(step.action())?,
-            )
-        }
-
-        Ok(findings)
+        // We can't know how a composite action is triggered, so we
+        // conservatively assume that it's triggered dangerously.
+        self.process_run(step, run, shell, true)
     }
 }
 
@@ -450,6 +601,7 @@ impl Audit for GitHubEnv {
 mod tests {
     use crate::audit::Audit;
     use crate::audit::github_env::{GITHUB_ENV_WRITE_CMD, GitHubEnv};
+    use crate::finding::Severity;
     use crate::github_api::GitHubHost;
     use crate::state::AuditState;
 
@@ -517,10 +669,17 @@ mod tests {
         ] {
             let audit_state = AuditState {
                 config: &Default::default(),
-                no_online_audits: false,
+                online: false,
                 cache_dir: "/tmp/zizmor".into(),
                 gh_token: None,
                 gh_hostname: GitHubHost::Standard("github.com".into()),
+                tpa_allowlist_file: None,
+                tpa_allowed_org: None,
+                lenient_allowlist: false,
+                advisories_file: None,
+                osv: false,
+                lockfile: None,
+                resolver: Default::default(),
             };
 
             let sut = GitHubEnv::new(&audit_state).expect("failed to create audit");
@@ -634,10 +793,17 @@ mod tests {
         ] {
             let audit_state = AuditState {
                 config: &Default::default(),
-                no_online_audits: false,
+                online: false,
                 cache_dir: "/tmp/zizmor".into(),
                 gh_token: None,
                 gh_hostname: GitHubHost::Standard("github.com".into()),
+                tpa_allowlist_file: None,
+                tpa_allowed_org: None,
+                lenient_allowlist: false,
+                advisories_file: None,
+                osv: false,
+                lockfile: None,
+                resolver: Default::default(),
             };
 
             let sut = GitHubEnv::new(&audit_state).expect("failed to create audit");
@@ -647,4 +813,89 @@ mod tests {
             assert!(uses_github_env.is_empty() != *expected, "failed: {case}");
         }
     }
+
+    #[test]
+    fn test_attacker_controlled_writes() {
+        for (case, shell, expected) in &[
+            (
+                "echo \"TITLE=${{ github.event.issue.title }}\" >> $GITHUB_ENV",
+                "bash",
+                Some(("github.event.issue.title", Severity::High)),
+            ),
+            (
+                "echo \"${{ github.event.pull_request.body }}\" >> \"$GITHUB_PATH\"",
+                "bash",
+                Some(("github.event.pull_request.body", Severity::High)),
+            ),
+            (
+                "echo \"BRANCH=${{ github.head_ref }}\" | tee -a $GITHUB_ENV",
+                "bash",
+                Some(("github.head_ref", Severity::Medium)),
+            ),
+            (
+                "echo \"msg=${{ github.event.head_commit.message }}\" >> $GITHUB_OUTPUT",
+                "bash",
+                Some(("github.event.head_commit.message", Severity::High)),
+            ),
+            (
+                "echo \"REF=${{ github.head_ref }} ${{ github.event.comment.body }}\" >> $GITHUB_ENV",
+                "bash",
+                Some(("github.event.comment.body", Severity::High)),
+            ),
+            (
+                "\"TITLE=${{ github.event.issue.title }}\" | Out-File -FilePath $env:GITHUB_ENV -Append",
+                "pwsh",
+                Some(("github.event.issue.title", Severity::High)),
+            ),
+            (
+                "echo TITLE=${{ github.event.issue.title }}>> %GITHUB_ENV%",
+                "cmd",
+                Some(("github.event.issue.title", Severity::High)),
+            ),
+            // negative cases
+            (
+                "echo \"SHA=${{ github.sha }}\" >> $GITHUB_ENV",
+                "bash",
+                None,
+            ),
+            (
+                "echo \"${{ github.event.issue.title }}\" >> $OTHER_ENV",
+                "bash",
+                None,
+            ),
+        ] {
+            let audit_state = AuditState {
+                config: &Default::default(),
+                online: false,
+                cache_dir: "/tmp/zizmor".into(),
+                gh_token: None,
+                gh_hostname: GitHubHost::Standard("github.com".into()),
+                tpa_allowlist_file: None,
+                tpa_allowed_org: None,
+                lenient_allowlist: false,
+                advisories_file: None,
+                osv: false,
+                lockfile: None,
+                resolver: Default::default(),
+            };
+
+            let sut = GitHubEnv::new(&audit_state).expect("failed to create audit");
+
+            let contexts = sut
+                .env_writes(case, shell)
+                .unwrap()
+                .iter()
+                .filter_map(|write| GitHubEnv::attacker_controlled_context(case, write))
+                .collect::<Vec<_>>();
+
+            match expected {
+                Some((context, severity)) => {
+                    assert_eq!(contexts.len(), 1, "failed: {case}");
+                    assert_eq!(contexts[0].0, *context, "failed: {case}");
+                    assert_eq!(contexts[0].1, *severity, "failed: {case}");
+                }
+                None => assert!(contexts.is_empty(), "failed: {case}"),
+            }
+        }
+    }
 }
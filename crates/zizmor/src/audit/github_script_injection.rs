@@ -0,0 +1,108 @@
+//! Detects template expansions of attacker-controllable values into
+//! `actions/github-script` scripts.
+//!
+//! `actions/github-script` evaluates its `script:` input as JavaScript,
+//! with an authenticated GitHub API client in scope, so any template
+//! expansion into the script is a direct code injection. Scripts can
+//! read the same values from `context.payload` without expanding them.
+
+use github_actions_models::common::Uses;
+
+use super::template_injection::TemplateInjection;
+use super::{Audit, AuditLoadError, audit_meta};
+use crate::{
+    finding::{Confidence, Finding, Persona, Severity},
+    models::{self, CompositeStep, Step, StepCommon, uses::RepositoryUsesExt as _},
+    state::AuditState,
+};
+
+pub(crate) struct GitHubScriptInjection;
+
+audit_meta!(
+    GitHubScriptInjection,
+    "github-script-injection",
+    "code injection via actions/github-script",
+    Severity::High
+);
+
+/// Contexts that are attacker-controllable, but constrained to the
+/// characters that GitHub allows in branch names or usernames.
+const CONSTRAINED_CONTEXTS: &[&str] =
+    &["github.head_ref", "github.actor", "github.triggering_actor"];
+
+impl GitHubScriptInjection {
+    fn process_step<'doc>(
+        &self,
+        step: &impl StepCommon<'doc>,
+    ) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        let models::StepBodyCommon::Uses {
+            uses: Uses::Repository(uses),
+            with,
+        } = step.body()
+        else {
+            return Ok(findings);
+        };
+
+        if !uses.matches("actions/github-script") {
+            return Ok(findings);
+        }
+
+        let Some(script) = with.get("script").map(|script| script.to_string()) else {
+            return Ok(findings);
+        };
+
+        for (expr, subfeature, severity, confidence, persona) in
+            TemplateInjection::injectable_template_expressions(&script, step)
+        {
+            let (severity, confidence, persona) = if CONSTRAINED_CONTEXTS
+                .iter()
+                .any(|context| expr.eq_ignore_ascii_case(context))
+            {
+                (Severity::Medium, Confidence::High, Persona::default())
+            } else {
+                (severity, confidence, persona)
+            };
+
+            findings.push(
+                Self::finding()
+                    .severity(severity)
+                    .confidence(confidence)
+                    .persona(persona)
+                    .add_location(step.location().hidden())
+                    .add_location(step.location_with_name())
+                    .add_location(
+                        step.location()
+                            .with_keys(&["with".into(), "script".into()])
+                            .with_subfeature(subfeature)
+                            .primary()
+                            .annotated(format!("{expr} is expanded into JavaScript")),
+                    )
+                    .build(step)?,
+            )
+        }
+
+        Ok(findings)
+    }
+}
+
+impl Audit for GitHubScriptInjection {
+    fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+
+    fn audit_step<'doc>(&self, step: &Step<'doc>) -> anyhow::Result<Vec<Finding<'doc>>> {
+        self.process_step(step)
+    }
+
+    fn audit_composite_step<'a>(
+        &self,
+        step: &CompositeStep<'a>,
+    ) -> anyhow::Result<Vec<Finding<'a>>> {
+        self.process_step(step)
+    }
+}
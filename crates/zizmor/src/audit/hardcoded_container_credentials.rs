@@ -1,12 +1,14 @@
+use std::ops::Deref;
+
 use github_actions_models::{
-    common::expr::ExplicitExpr,
-    workflow::job::{Container, DockerCredentials},
+    common::{Uses, expr::ExplicitExpr},
+    workflow::job::{Container, DockerCredentials, StepBody},
 };
 
 use super::{Audit, AuditLoadError, Job, audit_meta};
 use crate::{
     finding::{Confidence, Severity},
-    models::JobExt as _,
+    models::{JobExt as _, StepCommon as _, uses::RepositoryUsesExt as _},
     state::AuditState,
 };
 
@@ -15,9 +17,21 @@ pub(crate) struct HardcodedContainerCredentials;
 audit_meta!(
     HardcodedContainerCredentials,
     "hardcoded-container-credentials",
-    "hardcoded credential in GitHub Actions container configurations"
+    "hardcoded credential in GitHub Actions container configurations",
+    Severity::High
 );
 
+impl HardcodedContainerCredentials {
+    /// Redacts a hard-coded password, so that it isn't repeated in the
+    /// finding's annotation.
+    fn redacted(password: &str) -> String {
+        match password.chars().next() {
+            Some(first) => format!("{first}{}", "*".repeat(7)),
+            None => String::new(),
+        }
+    }
+}
+
 impl Audit for HardcodedContainerCredentials {
     fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError>
     where
@@ -56,8 +70,15 @@ impl Audit for HardcodedContainerCredentials {
                             .add_location(
                                 job.location()
                                     .primary()
-                                    .with_keys(&["container".into(), "credentials".into()])
-                                    .annotated("container registry password is hard-coded"),
+                                    .with_keys(&[
+                                        "container".into(),
+                                        "credentials".into(),
+                                        "password".into(),
+                                    ])
+                                    .annotated(format!(
+                                        "container registry password is hard-coded: {redacted}",
+                                        redacted = Self::redacted(password)
+                                    )),
                             )
                             .build(workflow)?,
                     )
@@ -87,10 +108,12 @@ impl Audit for HardcodedContainerCredentials {
                                             "services".into(),
                                             service.as_str().into(),
                                             "credentials".into(),
+                                            "password".into(),
                                         ])
                                         .annotated(format!(
                                             "service {service}: container registry password is \
-                                         hard-coded"
+                                             hard-coded: {redacted}",
+                                            redacted = Self::redacted(password)
                                         )),
                                 )
                                 .build(workflow)?,
@@ -98,6 +121,43 @@ impl Audit for HardcodedContainerCredentials {
                     }
                 }
             }
+
+            for step in job.steps() {
+                let StepBody::Uses {
+                    uses: Uses::Repository(uses),
+                    with,
+                } = &step.deref().body
+                else {
+                    continue;
+                };
+
+                if !uses.matches("docker/login-action") {
+                    continue;
+                }
+
+                let Some(password) = with.get("password").map(|p| p.to_string()) else {
+                    continue;
+                };
+
+                if ExplicitExpr::from_curly(&password).is_none() {
+                    findings.push(
+                        Self::finding()
+                            .severity(Severity::High)
+                            .confidence(Confidence::High)
+                            .add_location(step.location().hidden())
+                            .add_location(
+                                step.location()
+                                    .primary()
+                                    .with_keys(&["with".into(), "password".into()])
+                                    .annotated(format!(
+                                        "registry password is hard-coded: {redacted}",
+                                        redacted = Self::redacted(&password)
+                                    )),
+                            )
+                            .build(workflow)?,
+                    )
+                }
+            }
         }
 
         Ok(findings)
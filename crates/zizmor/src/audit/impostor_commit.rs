@@ -5,14 +5,17 @@
 //!
 //! [`clank`]: https://github.com/chainguard-dev/clank
 
-use anyhow::{Result, anyhow};
+use std::sync::Arc;
+
+use anyhow::Result;
 use github_actions_models::common::{RepositoryUses, Uses};
 
-use super::{Audit, AuditLoadError, Job, audit_meta};
+use super::{Audit, AuditLoadError, AuditMeta, Job, Network, audit_meta};
 use crate::{
     finding::{Confidence, Finding, Severity},
     github_api::{self, ComparisonStatus},
     models::{JobExt as _, StepCommon, Workflow, uses::RepositoryUsesExt as _},
+    resolver::UsesResolver,
     state::AuditState,
 };
 
@@ -20,12 +23,14 @@ pub const IMPOSTOR_ANNOTATION: &str = "uses a commit that doesn't belong to the
 
 pub(crate) struct ImpostorCommit {
     pub(crate) client: github_api::Client,
+    resolver: Arc<UsesResolver>,
 }
 
 audit_meta!(
     ImpostorCommit,
     "impostor-commit",
-    "commit with no history in referenced repository"
+    "commit with no history in referenced repository",
+    Severity::High
 );
 
 impl ImpostorCommit {
@@ -56,6 +61,13 @@ impl ImpostorCommit {
     /// i.e. resolves due to presence in GitHub's fork network but is not actually
     /// present in any of the specified `owner/repo`'s tags or branches.
     fn impostor(&self, uses: &RepositoryUses) -> Result<bool> {
+        // Only commits on the run's GitHub host can be looked up.
+        let (host, uses) = self.resolver.on_host(uses);
+        if !self.resolver.is_local_host(&host) {
+            return Ok(false);
+        }
+        let uses = &uses;
+
         // If there's no ref or the ref is not a commit, there's nothing to impersonate.
         let Some(head_ref) = uses.commit_ref() else {
             return Ok(false);
@@ -114,19 +126,19 @@ impl ImpostorCommit {
 
 impl Audit for ImpostorCommit {
     fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError> {
-        if state.no_online_audits {
-            return Err(AuditLoadError::Skip(anyhow!(
-                "offline audits only requested"
-            )));
-        }
+        let client = state.online_client()?;
 
-        let Some(client) = state.github_client() else {
-            return Err(AuditLoadError::Skip(anyhow!(
-                "can't run without a GitHub API token"
-            )));
-        };
+        Ok(ImpostorCommit {
+            client,
+            resolver: state.resolver.clone(),
+        })
+    }
 
-        Ok(ImpostorCommit { client })
+    fn meta() -> AuditMeta {
+        AuditMeta {
+            network: Network::Online,
+            ..Default::default()
+        }
     }
 
     fn audit_workflow<'doc>(&self, workflow: &'doc Workflow) -> Result<Vec<Finding<'doc>>> {
@@ -17,7 +17,8 @@ pub(crate) struct InsecureCommands;
 audit_meta!(
     InsecureCommands,
     "insecure-commands",
-    "execution of insecure workflow commands is enabled"
+    "execution of insecure workflow commands is enabled",
+    Severity::High
 );
 
 impl InsecureCommands {
@@ -1,36 +1,274 @@
 //! Detects publicly disclosed action vulnerabilities.
 //!
-//! This audit uses GitHub's security advisories API as a source of
-//! ground truth.
+//! This audit uses a bundled dataset of action advisories, optionally
+//! extended with a user-supplied dataset (`--advisories`). When online,
+//! it also uses GitHub's security advisories API as a source of
+//! ground truth, and OSV with `--osv`.
 //!
 //! See: <https://docs.github.com/en/rest/security-advisories/global-advisories?apiVersion=2022-11-28>
 
-use anyhow::{Result, anyhow};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Context as _, Result, anyhow};
+use camino::Utf8Path;
+use github_actions_models::action;
 use github_actions_models::common::{RepositoryUses, Uses};
+use serde::Deserialize;
 
-use super::{Audit, AuditLoadError, audit_meta};
+use super::{Audit, AuditLoadError, AuditMeta, Network, audit_meta};
 use crate::{
     finding::{Confidence, Finding, Severity},
     github_api,
-    models::{CompositeStep, Step, StepCommon, uses::RepositoryUsesExt as _},
+    models::{
+        Action, Job, StepCommon, Workflow,
+        uses::{RepositoryUsesExt as _, RepositoryUsesPattern},
+    },
+    osv,
     state::AuditState,
 };
 
+/// The bundled advisory dataset.
+const BUNDLED_ADVISORIES: &str = include_str!("../data/action-advisories.json");
+
 pub(crate) struct KnownVulnerableActions {
-    client: github_api::Client,
+    client: Option<github_api::Client>,
+    advisories: Vec<ActionAdvisory>,
+    osv: Option<osv::Client>,
+    /// Whether querying OSV has failed, in which case it isn't retried.
+    osv_failed: AtomicBool,
 }
 
 audit_meta!(
     KnownVulnerableActions,
     "known-vulnerable-actions",
-    "action has a known vulnerability"
+    "action has a known vulnerability",
+    Severity::High
 );
 
+/// A version number, e.g. `1.2.3` in `v1.2.3`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Version(u64, u64, u64);
+
+impl Version {
+    /// Parses a version-like ref such as `v1`, `v1.2` or `1.2.3` into the
+    /// (inclusive) lowest and (exclusive) highest versions it can refer to.
+    ///
+    /// Full versions like `v1.2.3` refer to only themselves, and so are
+    /// returned as `(v1.2.3, None)`.
+    fn parse_ref(git_ref: &str) -> Option<(Version, Option<Version>)> {
+        let version = git_ref
+            .strip_prefix(['v', 'V'])
+            .unwrap_or(git_ref)
+            .split('.')
+            .map(u64::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+
+        match version[..] {
+            [major] => Some((Version(major, 0, 0), Some(Version(major + 1, 0, 0)))),
+            [major, minor] => Some((Version(major, minor, 0), Some(Version(major, minor + 1, 0)))),
+            [major, minor, patch] => Some((Version(major, minor, patch), None)),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match Self::parse_ref(s) {
+            Some((version, None)) => Ok(version),
+            _ => Err(anyhow!("invalid version: {s} (expected X.Y.Z)")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Comparator {
+    Lt(Version),
+    Le(Version),
+    Gt(Version),
+    Ge(Version),
+    Eq(Version),
+}
+
+/// A range of affected versions, e.g. `>= 1.0.0, < 1.2.3`.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(try_from = "String")]
+struct VersionRange(Vec<Comparator>);
+
+impl VersionRange {
+    /// Whether any version between `lower` (inclusive) and `upper`
+    /// (exclusive, or exactly `lower` if absent) is within this range.
+    fn intersects(&self, lower: Version, upper: Option<Version>) -> bool {
+        // Each bound is a version and whether it's inclusive.
+        let mut lower = (lower, true);
+        let mut upper = match upper {
+            Some(upper) => (upper, false),
+            None => (lower.0, true),
+        };
+
+        let raise = |bound: &mut (Version, bool), new: (Version, bool)| {
+            if new.0 > bound.0 || (new.0 == bound.0 && !new.1) {
+                *bound = new;
+            }
+        };
+        let lower_to = |bound: &mut (Version, bool), new: (Version, bool)| {
+            if new.0 < bound.0 || (new.0 == bound.0 && !new.1) {
+                *bound = new;
+            }
+        };
+
+        for comparator in &self.0 {
+            match *comparator {
+                Comparator::Lt(v) => lower_to(&mut upper, (v, false)),
+                Comparator::Le(v) => lower_to(&mut upper, (v, true)),
+                Comparator::Gt(v) => raise(&mut lower, (v, false)),
+                Comparator::Ge(v) => raise(&mut lower, (v, true)),
+                Comparator::Eq(v) => {
+                    raise(&mut lower, (v, true));
+                    lower_to(&mut upper, (v, true));
+                }
+            }
+        }
+
+        lower.0 < upper.0 || (lower.0 == upper.0 && lower.1 && upper.1)
+    }
+}
+
+impl TryFrom<String> for VersionRange {
+    type Error = anyhow::Error;
+
+    fn try_from(range: String) -> Result<Self> {
+        let comparators = range
+            .split(',')
+            .map(|comparator| {
+                let comparator = comparator.trim();
+                let (op, version) = comparator
+                    .find(|c: char| !"<>=".contains(c))
+                    .map(|idx| comparator.split_at(idx))
+                    .with_context(|| format!("invalid version comparator: {comparator}"))?;
+                let version = version.trim().parse()?;
+
+                Ok(match op {
+                    "<" => Comparator::Lt(version),
+                    "<=" => Comparator::Le(version),
+                    ">" => Comparator::Gt(version),
+                    ">=" => Comparator::Ge(version),
+                    "=" | "" => Comparator::Eq(version),
+                    _ => return Err(anyhow!("invalid version comparator: {comparator}")),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self(comparators))
+    }
+}
+
+/// An advisory for a GitHub Action, as loaded from the bundled
+/// or a user-supplied advisory dataset.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ActionAdvisory {
+    /// The advisory's identifier, e.g. a GHSA ID.
+    id: String,
+    url: String,
+    /// The affected actions, as a repository pattern.
+    uses: RepositoryUsesPattern,
+    summary: String,
+    /// The affected versions, as matched against version-like refs.
+    #[serde(default)]
+    affected_versions: Vec<VersionRange>,
+    /// The affected commits, as matched against SHA refs.
+    #[serde(default)]
+    affected_commits: Vec<String>,
+}
+
+impl ActionAdvisory {
+    fn load(contents: &str) -> Result<Vec<Self>> {
+        Ok(serde_json::from_str(contents)?)
+    }
+
+    fn load_file(path: &Utf8Path) -> Result<Vec<Self>> {
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("couldn't read advisories {path}"))?;
+
+        Self::load(&contents).with_context(|| format!("invalid advisories {path}"))
+    }
+
+    /// Returns the confidence with which `uses` is affected by this
+    /// advisory, if it may be affected at all.
+    ///
+    /// `version` is the version that `uses` resolves to, if known.
+    fn affects(&self, uses: &RepositoryUses, version: Option<&str>) -> Option<Confidence> {
+        if !self.uses.matches(uses) {
+            return None;
+        }
+
+        if let Some(commit) = uses.commit_ref() {
+            if self
+                .affected_commits
+                .iter()
+                .any(|affected| affected.eq_ignore_ascii_case(commit))
+            {
+                return Some(Confidence::High);
+            }
+        }
+
+        match version.and_then(Version::parse_ref) {
+            // A full version is either affected or not.
+            Some((version, None)) => self
+                .affected_versions
+                .iter()
+                .any(|range| range.intersects(version, None))
+                .then_some(Confidence::High),
+            // A partial version like `v1` may refer to an affected version.
+            Some((lower, upper)) => self
+                .affected_versions
+                .iter()
+                .any(|range| range.intersects(lower, upper))
+                .then_some(Confidence::Low),
+            // We couldn't figure out what the ref is, so it may or
+            // may not be affected. The exception is an unaffected commit,
+            // when only commits are affected.
+            None if uses.ref_is_commit() && self.affected_versions.is_empty() => None,
+            None => Some(Confidence::Low),
+        }
+    }
+}
+
+/// The OSV query for `uses` at `version` (if known), and the confidence
+/// with which its results affect `uses`.
+fn osv_query(uses: &RepositoryUses, version: Option<&str>) -> Option<(osv::Query, Confidence)> {
+    let name = format!("{owner}/{repo}", owner = uses.owner, repo = uses.repo);
+
+    match version.map(|version| (version, Version::parse_ref(version))) {
+        // OSV's GitHub Actions versions don't have a `v` prefix.
+        Some((version, Some((_, upper)))) => {
+            let version = version.strip_prefix(['v', 'V']).unwrap_or(version);
+            let confidence = match upper {
+                None => Confidence::High,
+                Some(_) => Confidence::Low,
+            };
+            Some((osv::Query::version(&name, version), confidence))
+        }
+        // OSV can't tell us anything about branches, but it can about commits.
+        _ => uses
+            .commit_ref()
+            .map(|commit| (osv::Query::commit(commit), Confidence::High)),
+    }
+}
+
 impl KnownVulnerableActions {
-    fn action_known_vulnerabilities(
-        &self,
+    /// Resolve the version that `uses` refers to, e.g. `v1.2.3`.
+    fn resolve_version(
+        client: &github_api::Client,
         uses: &RepositoryUses,
-    ) -> Result<Vec<(Severity, String)>> {
+    ) -> Result<Option<String>> {
         let version = match &uses.git_ref {
             // If `uses` is pinned to a symbolic ref, we need to perform
             // feats of heroism to figure out what's going on.
@@ -49,19 +287,14 @@ impl KnownVulnerableActions {
             // To handle all of the above, we convert the ref into a commit
             // and then find the longest tag for that commit.
             Some(version) if !uses.ref_is_commit() => {
-                let Some(commit_ref) =
-                    self.client
-                        .commit_for_ref(&uses.owner, &uses.repo, version)?
+                let Some(commit_ref) = client.commit_for_ref(&uses.owner, &uses.repo, version)?
                 else {
                     // No `ref -> commit` means that the action's version
                     // is probably just outright invalid.
-                    return Ok(vec![]);
+                    return Ok(None);
                 };
 
-                match self
-                    .client
-                    .longest_tag_for_commit(&uses.owner, &uses.repo, &commit_ref)?
-                {
+                match client.longest_tag_for_commit(&uses.owner, &uses.repo, &commit_ref)? {
                     Some(tag) => tag.name,
                     // Somehow we've round-tripped through a commit and ended
                     // up without a tag, which suggests we went
@@ -75,16 +308,13 @@ impl KnownVulnerableActions {
             // something annoying like use branches for versions instead,
             // which we should also probably support.
             Some(commit_ref) => {
-                match self
-                    .client
-                    .longest_tag_for_commit(&uses.owner, &uses.repo, commit_ref)?
-                {
+                match client.longest_tag_for_commit(&uses.owner, &uses.repo, commit_ref)? {
                     Some(tag) => tag.name,
                     // No corresponding tag means the user is maybe doing something
                     // weird, like using a commit ref off of a branch that isn't
                     // also tagged. Probably not good, but also not something
                     // we can easily discover known vulns for.
-                    None => return Ok(vec![]),
+                    None => return Ok(None),
                 }
             }
             // No version means the action runs the latest default branch
@@ -92,12 +322,18 @@ impl KnownVulnerableActions {
             // unlikely to be meaningful.
             // TODO: Maybe we need a separate (low-sev) audit for actions usage
             // on @master/@main/etc?
-            None => return Ok(vec![]),
+            None => return Ok(None),
         };
 
-        let vulns = self
-            .client
-            .gha_advisories(&uses.owner, &uses.repo, &version)?;
+        Ok(Some(version))
+    }
+
+    fn action_known_vulnerabilities(
+        client: &github_api::Client,
+        uses: &RepositoryUses,
+        version: &str,
+    ) -> Result<Vec<(Severity, String)>> {
+        let vulns = client.gha_advisories(&uses.owner, &uses.repo, version)?;
 
         let mut results = vec![];
 
@@ -116,24 +352,164 @@ impl KnownVulnerableActions {
         Ok(results)
     }
 
-    fn process_step<'doc>(&self, step: &impl StepCommon<'doc>) -> Result<Vec<Finding<'doc>>> {
+    /// Queries OSV for the advisories that affect each of `queries`, if
+    /// enabled. Failures are reported once, and OSV isn't queried again.
+    fn query_osv(&self, queries: Vec<osv::Query>) -> HashMap<osv::Query, Vec<String>> {
+        let Some(osv) = &self.osv else {
+            return HashMap::new();
+        };
+
+        if queries.is_empty() || self.osv_failed.load(Ordering::Relaxed) {
+            return HashMap::new();
+        }
+
+        osv.advisories(&queries).unwrap_or_else(|err| {
+            if !self.osv_failed.swap(true, Ordering::Relaxed) {
+                tracing::warn!("skipping OSV advisories: {err:#}");
+            }
+            HashMap::new()
+        })
+    }
+
+    /// Audits `steps` together, so that OSV is queried once for all of them.
+    fn process_steps<'doc, S: StepCommon<'doc>>(
+        &self,
+        steps: impl IntoIterator<Item = S>,
+    ) -> Result<Vec<Finding<'doc>>> {
+        let mut resolved = vec![];
+        for step in steps {
+            let Some(Uses::Repository(uses)) = step.uses() else {
+                continue;
+            };
+
+            // Without a client, we can only use the ref itself as the version.
+            let version = match &self.client {
+                Some(client) => Self::resolve_version(client, uses)?,
+                None => uses.symbolic_ref().map(str::to_string),
+            };
+
+            let query = osv_query(uses, version.as_deref());
+            resolved.push((step, version, query));
+        }
+
+        let osv = self.query_osv(
+            resolved
+                .iter()
+                .filter_map(|(_, _, query)| query.as_ref().map(|(query, _)| query.clone()))
+                .collect(),
+        );
+
+        let mut findings = vec![];
+        for (step, version, query) in &resolved {
+            let osv_ids = query.as_ref().and_then(|(query, confidence)| {
+                osv.get(query).map(|ids| (ids.as_slice(), *confidence))
+            });
+            findings.extend(self.process_step(step, version.as_deref(), osv_ids)?);
+        }
+
+        Ok(findings)
+    }
+
+    /// Audits a single step, given the version its action resolves to
+    /// and the IDs of any OSV advisories affecting it.
+    fn process_step<'doc>(
+        &self,
+        step: &impl StepCommon<'doc>,
+        version: Option<&str>,
+        osv_ids: Option<(&[String], Confidence)>,
+    ) -> Result<Vec<Finding<'doc>>> {
         let mut findings = vec![];
 
         let Some(Uses::Repository(uses)) = step.uses() else {
             return Ok(findings);
         };
 
-        for (severity, id) in self.action_known_vulnerabilities(uses)? {
+        let mut reported = HashSet::new();
+
+        for advisory in &self.advisories {
+            let Some(confidence) = advisory.affects(uses, version) else {
+                continue;
+            };
+
+            reported.insert(advisory.id.clone());
+
+            let annotation = match confidence {
+                Confidence::High => format!(
+                    "{id}: {summary}",
+                    id = advisory.id,
+                    summary = advisory.summary
+                ),
+                _ => format!(
+                    "{id}: {summary} (couldn't determine whether this ref is affected)",
+                    id = advisory.id,
+                    summary = advisory.summary
+                ),
+            };
+
+            findings.push(
+                Self::finding()
+                    .confidence(confidence)
+                    .severity(Severity::High)
+                    .add_location(
+                        step.location()
+                            .primary()
+                            .with_keys(&["uses".into()])
+                            .annotated(annotation)
+                            .with_url(&advisory.url),
+                    )
+                    .build(step)?,
+            );
+        }
+
+        if let (Some(client), Some(version)) = (&self.client, version) {
+            for (severity, id) in Self::action_known_vulnerabilities(client, uses, version)? {
+                // Don't report the same advisory from both datasets.
+                if !reported.insert(id.clone()) {
+                    continue;
+                }
+
+                findings.push(
+                    Self::finding()
+                        .confidence(Confidence::High)
+                        .severity(severity)
+                        .add_location(
+                            step.location()
+                                .primary()
+                                .with_keys(&["uses".into()])
+                                .annotated(&id)
+                                .with_url(format!("https://github.com/advisories/{id}")),
+                        )
+                        .build(step)?,
+                );
+            }
+        }
+
+        for (id, confidence) in osv_ids
+            .into_iter()
+            .flat_map(|(ids, confidence)| ids.iter().map(move |id| (id, confidence)))
+        {
+            // OSV includes GHSA, so its advisories may already be reported.
+            if !reported.insert(id.clone()) {
+                continue;
+            }
+
+            let annotation = match confidence {
+                Confidence::High => format!("{id}: reported by OSV"),
+                _ => format!(
+                    "{id}: reported by OSV (couldn't determine whether this ref is affected)"
+                ),
+            };
+
             findings.push(
                 Self::finding()
-                    .confidence(Confidence::High)
-                    .severity(severity)
+                    .confidence(confidence)
+                    .severity(Severity::High)
                     .add_location(
                         step.location()
                             .primary()
                             .with_keys(&["uses".into()])
-                            .annotated(&id)
-                            .with_url(format!("https://github.com/advisories/{id}")),
+                            .annotated(annotation)
+                            .with_url(format!("https://osv.dev/vulnerability/{id}")),
                     )
                     .build(step)?,
             );
@@ -148,26 +524,219 @@ impl Audit for KnownVulnerableActions {
     where
         Self: Sized,
     {
-        if state.no_online_audits {
-            return Err(AuditLoadError::Skip(anyhow!(
-                "offline audits only requested"
-            )));
+        // NOTE: Safe unwrap, since the bundled dataset is tested below.
+        let mut advisories = ActionAdvisory::load(BUNDLED_ADVISORIES).unwrap();
+
+        if let Some(path) = &state.advisories_file {
+            advisories.extend(ActionAdvisory::load_file(path).map_err(AuditLoadError::Fail)?);
         }
 
-        let Some(client) = state.github_client() else {
-            return Err(AuditLoadError::Skip(anyhow!(
-                "can't run without a GitHub API token"
-            )));
-        };
+        // Without a client, we can still check the advisory datasets,
+        // just without GHSA or resolving refs to versions.
+        let client = state.online_client().ok();
+
+        // OSV doesn't need a GitHub token, just the network.
+        let osv = (state.osv && state.online).then(|| osv::Client::new(&state.cache_dir));
+
+        Ok(Self {
+            client,
+            advisories,
+            osv,
+            osv_failed: AtomicBool::new(false),
+        })
+    }
+
+    fn meta() -> AuditMeta {
+        AuditMeta {
+            network: Network::Optional,
+            ..Default::default()
+        }
+    }
+
+    fn audit_workflow<'doc>(&self, workflow: &'doc Workflow) -> Result<Vec<Finding<'doc>>> {
+        self.process_steps(
+            workflow
+                .jobs()
+                .flat_map(|job| match job {
+                    Job::NormalJob(job) => Some(job.steps()),
+                    Job::ReusableWorkflowCallJob(_) => None,
+                })
+                .flatten(),
+        )
+    }
+
+    fn audit_action<'doc>(&self, action: &'doc Action) -> Result<Vec<Finding<'doc>>> {
+        if !matches!(action.runs, action::Runs::Composite(_)) {
+            return Ok(vec![]);
+        }
+
+        self.process_steps(action.steps())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use github_actions_models::common::{RepositoryUses, Uses};
+
+    use super::{ActionAdvisory, BUNDLED_ADVISORIES, Version, VersionRange, osv_query};
+    use crate::finding::Confidence;
+    use crate::osv::Query;
+
+    #[test]
+    fn test_bundled_advisories() {
+        let advisories = ActionAdvisory::load(BUNDLED_ADVISORIES).unwrap();
+        assert!(!advisories.is_empty());
+    }
 
-        Ok(Self { client })
+    #[test]
+    fn test_parse_ref() {
+        for (git_ref, expected) in [
+            ("v1", Some((Version(1, 0, 0), Some(Version(2, 0, 0))))),
+            ("1.2", Some((Version(1, 2, 0), Some(Version(1, 3, 0))))),
+            ("v1.2.3", Some((Version(1, 2, 3), None))),
+            ("V45.0.7", Some((Version(45, 0, 7), None))),
+            ("main", None),
+            ("release/v1", None),
+            ("v1.2.3-rc1", None),
+            ("v1.2.3.4", None),
+        ] {
+            assert_eq!(Version::parse_ref(git_ref), expected, "{git_ref}");
+        }
     }
 
-    fn audit_step<'doc>(&self, step: &Step<'doc>) -> Result<Vec<Finding<'doc>>> {
-        self.process_step(step)
+    #[test]
+    fn test_version_range() {
+        let range = VersionRange::try_from(">= 4.0.0, < 4.1.3".to_string()).unwrap();
+
+        assert!(range.intersects(Version(4, 0, 0), None));
+        assert!(range.intersects(Version(4, 1, 2), None));
+        assert!(!range.intersects(Version(4, 1, 3), None));
+        assert!(!range.intersects(Version(3, 9, 9), None));
+        // v4 and v4.1 may be affected; v4.2 and v5 aren't.
+        assert!(range.intersects(Version(4, 0, 0), Some(Version(5, 0, 0))));
+        assert!(range.intersects(Version(4, 1, 0), Some(Version(4, 2, 0))));
+        assert!(!range.intersects(Version(4, 2, 0), Some(Version(4, 3, 0))));
+        assert!(!range.intersects(Version(5, 0, 0), Some(Version(6, 0, 0))));
+
+        let range = VersionRange::try_from("1.2.3".to_string()).unwrap();
+        assert!(range.intersects(Version(1, 2, 3), None));
+        assert!(!range.intersects(Version(1, 2, 4), None));
+
+        for invalid in ["", "< main", "=> 1.2.3", "< 1.2"] {
+            assert!(
+                VersionRange::try_from(invalid.to_string()).is_err(),
+                "{invalid}"
+            );
+        }
     }
 
-    fn audit_composite_step<'doc>(&self, step: &CompositeStep<'doc>) -> Result<Vec<Finding<'doc>>> {
-        self.process_step(step)
+    #[test]
+    fn test_affects() {
+        let advisories = ActionAdvisory::load(
+            r#"[{
+                "id": "TEST-1",
+                "url": "https://example.com/TEST-1",
+                "uses": "example/action",
+                "summary": "bad",
+                "affected-versions": ["<= 45.0.7"],
+                "affected-commits": ["0e58ed8671d6b60d0890c21b07f8835ace038e67"]
+            }]"#,
+        )
+        .unwrap();
+        let advisory = &advisories[0];
+
+        let uses = |uses: &str| -> RepositoryUses {
+            match Uses::from_str(uses).unwrap() {
+                Uses::Repository(uses) => uses,
+                _ => unreachable!(),
+            }
+        };
+
+        for (step_uses, version, expected) in [
+            (
+                "example/action@0e58ed8671d6b60d0890c21b07f8835ace038e67",
+                None,
+                Some(Confidence::High),
+            ),
+            (
+                "example/action@v45.0.7",
+                Some("v45.0.7"),
+                Some(Confidence::High),
+            ),
+            ("example/action@v46.0.1", Some("v46.0.1"), None),
+            ("example/action@v45", Some("v45"), Some(Confidence::Low)),
+            ("example/action@v46", Some("v46"), None),
+            ("example/action@main", Some("main"), Some(Confidence::Low)),
+            (
+                "example/action@ffffffffffffffffffffffffffffffffffffffff",
+                None,
+                Some(Confidence::Low),
+            ),
+            (
+                "example/action@ffffffffffffffffffffffffffffffffffffffff",
+                Some("v46.0.1"),
+                None,
+            ),
+            (
+                "Example/Action@v1.0.0",
+                Some("v1.0.0"),
+                Some(Confidence::High),
+            ),
+            ("example/other-action@v1.0.0", Some("v1.0.0"), None),
+        ] {
+            assert_eq!(
+                advisory.affects(&uses(step_uses), version),
+                expected,
+                "{step_uses}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_osv_query() {
+        let uses = |uses: &str| -> RepositoryUses {
+            match Uses::from_str(uses).unwrap() {
+                Uses::Repository(uses) => uses,
+                _ => unreachable!(),
+            }
+        };
+
+        let commit = "0e58ed8671d6b60d0890c21b07f8835ace038e67";
+        for (step_uses, version, expected) in [
+            (
+                "example/action@v1.2.3",
+                Some("v1.2.3"),
+                Some((Query::version("example/action", "1.2.3"), Confidence::High)),
+            ),
+            (
+                "example/action@v1",
+                Some("v1"),
+                Some((Query::version("example/action", "1"), Confidence::Low)),
+            ),
+            (
+                "example/action/subpath@v1",
+                Some("v1.0.0"),
+                Some((Query::version("example/action", "1.0.0"), Confidence::High)),
+            ),
+            ("example/action@main", Some("main"), None),
+            (
+                "example/action@0e58ed8671d6b60d0890c21b07f8835ace038e67",
+                None,
+                Some((Query::commit(commit), Confidence::High)),
+            ),
+            (
+                "example/action@0e58ed8671d6b60d0890c21b07f8835ace038e67",
+                Some("v2.0.0"),
+                Some((Query::version("example/action", "2.0.0"), Confidence::High)),
+            ),
+        ] {
+            assert_eq!(
+                osv_query(&uses(step_uses), version),
+                expected,
+                "{step_uses}"
+            );
+        }
     }
 }
@@ -0,0 +1,89 @@
+//! Detects local `uses: ./path` actions that don't exist, or whose paths
+//! escape the repository that uses them.
+//!
+//! A local action's path is relative to the root of the repository, not
+//! to the workflow or action that uses it. A path that has no `action.yml`
+//! (or `action.yaml`) fails the step at runtime, which is often "fixed" in
+//! a hurry by replacing the step, and a path that climbs out of the root
+//! (e.g. `./../other`) runs whatever happens to be next to the checkout.
+
+use github_actions_models::common::Uses;
+
+use super::{Audit, AuditLoadError, AuditState, audit_meta};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::models::{CompositeStep, Step, StepBodyCommon, StepCommon};
+use crate::registry::LocalAction;
+
+pub(crate) struct LocalUses;
+
+audit_meta!(
+    LocalUses,
+    "local-uses",
+    "local action is missing or outside the repository",
+    Severity::Medium
+);
+
+impl LocalUses {
+    fn process_step<'doc>(
+        &self,
+        step: &impl StepCommon<'doc>,
+    ) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        let StepBodyCommon::Uses {
+            uses: Uses::Local(uses),
+            ..
+        } = step.body()
+        else {
+            return Ok(findings);
+        };
+
+        let location = step.location();
+        let (severity, annotation) = match location.key.local_action(uses) {
+            Some(LocalAction::Escapes) => (
+                Severity::High,
+                "local action path escapes the repository root".to_string(),
+            ),
+            Some(LocalAction::Missing(dir)) => (
+                Severity::Medium,
+                format!("no action.yml or action.yaml in {dir}"),
+            ),
+            Some(LocalAction::Manifest(_)) | None => return Ok(findings),
+        };
+
+        findings.push(
+            Self::finding()
+                .severity(severity)
+                .confidence(Confidence::High)
+                .add_location(
+                    location
+                        .with_keys(&["uses".into()])
+                        .primary()
+                        .annotated(annotation),
+                )
+                .build(step)?,
+        );
+
+        Ok(findings)
+    }
+}
+
+impl Audit for LocalUses {
+    fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+
+    fn audit_step<'doc>(&self, step: &Step<'doc>) -> anyhow::Result<Vec<Finding<'doc>>> {
+        self.process_step(step)
+    }
+
+    fn audit_composite_step<'doc>(
+        &self,
+        step: &CompositeStep<'doc>,
+    ) -> anyhow::Result<Vec<Finding<'doc>>> {
+        self.process_step(step)
+    }
+}
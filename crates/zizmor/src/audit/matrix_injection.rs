@@ -0,0 +1,392 @@
+//! Detects matrices whose values come from earlier jobs' outputs or from
+//! the triggering event, and which then flow into privileged contexts.
+//!
+//! A matrix like `${{ fromJSON(needs.setup.outputs.matrix) }}` lets the
+//! `setup` job (and anything that can influence it) choose the values of
+//! every `matrix.*` variable. When those variables pick the job's runner,
+//! container, or actions, the job effectively runs whatever the earlier
+//! job says it should.
+//!
+//! Static matrices, and matrices built from `vars.*` (or other contexts
+//! that attackers can't control), aren't flagged. Neither are matrix values
+//! interpolated into `run:` scripts, since `template-injection` already
+//! reports every non-static matrix expansion in a script.
+
+use github_actions_expressions::{Expr, context::Context};
+use github_actions_models::{
+    common::{Uses, expr::LoE},
+    workflow::job::{self, Container, RunsOn, StepBody},
+};
+use serde_yaml::Value;
+
+use super::{Audit, AuditLoadError, audit_meta};
+use crate::{
+    finding::{Confidence, Finding, Severity, Subfeature, SymbolicLocation},
+    models::{JobExt as _, NeedsOutput, NormalJob, StepCommon as _},
+    state::AuditState,
+    utils::extract_expressions,
+};
+
+pub(crate) struct MatrixInjection;
+
+audit_meta!(
+    MatrixInjection,
+    "matrix-injection",
+    "untrusted matrix values reach privileged contexts",
+    Severity::High
+);
+
+/// An untrusted source of some (or all) of a matrix's values.
+struct Source<'doc> {
+    /// The matrix key whose values come from this source, or `None` if
+    /// the source can supply every key (e.g. `include: ${{ ... }}`).
+    key: Option<String>,
+    /// The untrusted context, e.g. `needs.setup.outputs.matrix`.
+    context: String,
+    /// The job output that `context` refers to, if it's a `needs` context.
+    output: Option<NeedsOutput<'doc>>,
+}
+
+/// A place within a job where matrix values are used, along with how
+/// dangerous it is for them to be attacker-controlled.
+struct Sink<'doc> {
+    text: &'doc str,
+    location: SymbolicLocation<'doc>,
+    severity: Severity,
+    what: &'static str,
+}
+
+impl MatrixInjection {
+    /// Returns the contexts whose values flow into the matrix value
+    /// computed by `expr`, including through `fromJSON(...)`.
+    fn value_contexts<'a>(expr: &'a Expr<'a>) -> Vec<&'a Context<'a>> {
+        match expr {
+            Expr::Call { func, args } if func == "fromJSON" => {
+                args.iter().flat_map(Self::value_contexts).collect()
+            }
+            Expr::Context(ctx) => match ctx.parts.first() {
+                // e.g. `fromJSON(needs.setup.outputs.matrix).include`
+                Some(head @ Expr::Call { .. }) => Self::value_contexts(head),
+                _ => vec![ctx],
+            },
+            _ => expr.dataflow_contexts(),
+        }
+    }
+
+    /// Returns the untrusted sources of the matrix values in `text`,
+    /// for the given matrix `key` (if any).
+    fn sources_in<'doc>(job: &NormalJob<'doc>, key: Option<&str>, text: &str) -> Vec<Source<'doc>> {
+        let mut sources = vec![];
+
+        for (expr, _) in extract_expressions(text) {
+            let Ok(parsed) = Expr::parse(expr.as_bare()) else {
+                tracing::warn!("couldn't parse expression: {expr}", expr = expr.as_bare());
+                continue;
+            };
+
+            for context in Self::value_contexts(&parsed) {
+                let source = if context.child_of("github.event") {
+                    Source {
+                        key: key.map(Into::into),
+                        context: context.as_str().into(),
+                        output: None,
+                    }
+                } else if context.child_of("needs.*.outputs") {
+                    let output = job.needs_output(context.as_str());
+
+                    // An output that's defined without any expressions
+                    // is as static as the matrix itself.
+                    if output
+                        .as_ref()
+                        .and_then(|output| output.value())
+                        .is_some_and(|value| extract_expressions(value).is_empty())
+                    {
+                        continue;
+                    }
+
+                    Source {
+                        key: key.map(Into::into),
+                        context: context.as_str().into(),
+                        output,
+                    }
+                } else {
+                    continue;
+                };
+
+                sources.push(source);
+            }
+        }
+
+        sources
+    }
+
+    /// Returns the untrusted sources of each of `value`'s strings.
+    fn sources_in_value<'doc>(
+        job: &NormalJob<'doc>,
+        key: &str,
+        value: &Value,
+    ) -> Vec<Source<'doc>> {
+        match value {
+            Value::String(text) => Self::sources_in(job, Some(key), text),
+            Value::Sequence(values) => values
+                .iter()
+                .flat_map(|value| Self::sources_in_value(job, key, value))
+                .collect(),
+            Value::Mapping(values) => values
+                .values()
+                .flat_map(|value| Self::sources_in_value(job, key, value))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Returns every untrusted source of `job`'s matrix values.
+    fn matrix_sources<'doc>(job: &NormalJob<'doc>) -> Vec<Source<'doc>> {
+        let Some(matrix) = job.strategy.as_ref().and_then(|s| s.matrix.as_ref()) else {
+            return vec![];
+        };
+
+        let matrix = match matrix {
+            LoE::Expr(expr) => return Self::sources_in(job, None, expr.as_curly()),
+            LoE::Literal(matrix) => matrix,
+        };
+
+        let mut sources = vec![];
+
+        match &matrix.dimensions {
+            LoE::Expr(expr) => sources.extend(Self::sources_in(job, None, expr.as_curly())),
+            LoE::Literal(dimensions) => {
+                for (key, values) in dimensions {
+                    match values {
+                        LoE::Expr(expr) => {
+                            sources.extend(Self::sources_in(job, Some(key), expr.as_curly()));
+                        }
+                        LoE::Literal(values) => sources.extend(
+                            values
+                                .iter()
+                                .flat_map(|value| Self::sources_in_value(job, key, value)),
+                        ),
+                    }
+                }
+            }
+        }
+
+        // `include:` can add any key to the matrix, so an entirely
+        // computed `include:` can supply every key.
+        match &matrix.include {
+            LoE::Expr(expr) => sources.extend(Self::sources_in(job, None, expr.as_curly())),
+            LoE::Literal(rows) => {
+                for (key, value) in rows.iter().flatten() {
+                    sources.extend(Self::sources_in_value(job, key, value));
+                }
+            }
+        }
+
+        sources
+    }
+
+    /// Returns every place in `job` that matrix values can be used in
+    /// a privileged way.
+    fn sinks<'doc>(job: &NormalJob<'doc>) -> Vec<Sink<'doc>> {
+        let mut sinks = vec![];
+        let inner: &'doc job::NormalJob = **job;
+
+        let runs_on = job.location().with_keys(&["runs-on".into()]);
+        let mut runner = |text| {
+            sinks.push(Sink {
+                text,
+                location: runs_on.clone(),
+                severity: Severity::Medium,
+                what: "the job's runner",
+            })
+        };
+        match &inner.runs_on {
+            LoE::Expr(expr) => runner(expr.as_curly()),
+            LoE::Literal(RunsOn::Target(labels)) => labels.iter().for_each(|l| runner(l)),
+            LoE::Literal(RunsOn::Group { group, labels }) => {
+                group.iter().chain(labels).for_each(|l| runner(l))
+            }
+        }
+
+        match &inner.container {
+            Some(Container::Name(image)) => sinks.push(Sink {
+                text: image,
+                location: job.location().with_keys(&["container".into()]),
+                severity: Severity::Medium,
+                what: "the job's container image",
+            }),
+            Some(Container::Container { image, .. }) => sinks.push(Sink {
+                text: image,
+                location: job
+                    .location()
+                    .with_keys(&["container".into(), "image".into()]),
+                severity: Severity::Medium,
+                what: "the job's container image",
+            }),
+            None => {}
+        }
+
+        for step in job.steps() {
+            let body: &'doc StepBody = &step.body;
+            match body {
+                // NOTE: `run:` scripts are left to `template-injection`.
+                StepBody::Run { .. } => {}
+                StepBody::Uses { uses, .. } => {
+                    let location = step.location().with_keys(&["uses".into()]);
+                    let components = match uses {
+                        Uses::Local(local) => vec![Some(&local.path)],
+                        Uses::Repository(repo) => vec![
+                            Some(&repo.owner),
+                            Some(&repo.repo),
+                            repo.subpath.as_ref(),
+                            repo.git_ref.as_ref(),
+                        ],
+                        Uses::Docker(docker) => vec![
+                            docker.registry.as_ref(),
+                            Some(&docker.image),
+                            docker.tag.as_ref(),
+                            docker.hash.as_ref(),
+                        ],
+                    };
+
+                    sinks.extend(components.into_iter().flatten().map(|text| Sink {
+                        text,
+                        location: location.clone(),
+                        severity: Severity::High,
+                        what: "the action that this step uses",
+                    }));
+                }
+            }
+        }
+
+        sinks
+    }
+
+    /// Returns the matrix key that `context` (a `matrix` context) refers
+    /// to, or `None` if it refers to the entire matrix.
+    fn matrix_key<'a>(context: &'a Context<'a>) -> Option<&'a str> {
+        context
+            .pop_if("matrix")
+            .map(|tail| tail.split(['.', '[']).next().unwrap_or(tail))
+    }
+}
+
+impl Audit for MatrixInjection {
+    fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+
+    fn audit_normal_job<'doc>(&self, job: &NormalJob<'doc>) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        let sources = Self::matrix_sources(job);
+        if sources.is_empty() {
+            return Ok(findings);
+        }
+
+        for sink in Self::sinks(job) {
+            for (expr, span) in extract_expressions(sink.text) {
+                let Ok(parsed) = Expr::parse(expr.as_bare()) else {
+                    continue;
+                };
+
+                let Some(context) = parsed
+                    .dataflow_contexts()
+                    .into_iter()
+                    .find(|ctx| ctx.child_of("matrix"))
+                else {
+                    continue;
+                };
+
+                let key = Self::matrix_key(context);
+                let Some(source) = sources.iter().find(|source| {
+                    source.key.is_none()
+                        || key.is_none()
+                        || source.key.as_deref().is_some_and(|k| Some(k) == key)
+                }) else {
+                    continue;
+                };
+
+                // The event's payload is attacker-controlled outright, but
+                // a job that produces a matrix may well validate it.
+                let confidence = if source.context.starts_with("github.") {
+                    Confidence::High
+                } else {
+                    Confidence::Medium
+                };
+
+                let subfeature = Subfeature::new(
+                    sink.text[..span.start].matches(expr.as_raw()).count(),
+                    expr.as_raw(),
+                );
+
+                let mut builder = Self::finding()
+                    .severity(sink.severity)
+                    .confidence(confidence)
+                    .add_location(
+                        sink.location
+                            .clone()
+                            .with_subfeature(subfeature)
+                            .primary()
+                            .annotated(format!(
+                                "{context} may let an attacker choose {what}",
+                                context = context.as_str(),
+                                what = sink.what,
+                            )),
+                    )
+                    .add_location(
+                        job.location()
+                            .with_keys(&["strategy".into(), "matrix".into()])
+                            .annotated(format!("matrix is computed from {}", source.context)),
+                    );
+
+                if let Some(output) = &source.output {
+                    builder = builder.add_location(output.location().annotated(format!(
+                        "output {name} is produced by job {id}",
+                        name = output.name,
+                        id = output.job.id(),
+                    )));
+                }
+
+                findings.push(builder.build(job.parent())?);
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use github_actions_expressions::Expr;
+
+    use super::MatrixInjection;
+
+    #[test]
+    fn test_value_contexts() {
+        for (expr, contexts) in [
+            (
+                "fromJSON(needs.setup.outputs.matrix)",
+                &["needs.setup.outputs.matrix"][..],
+            ),
+            (
+                "fromJSON(needs.setup.outputs.matrix).include",
+                &["needs.setup.outputs.matrix"],
+            ),
+            ("github.event.inputs.os", &["github.event.inputs.os"]),
+            ("fromJSON(vars.MATRIX)", &["vars.MATRIX"]),
+            ("format('{0}-x', inputs.os)", &["inputs.os"]),
+            ("needs.setup.outputs.os == 'linux'", &[]),
+        ] {
+            let parsed = Expr::parse(expr).unwrap();
+            let actual = MatrixInjection::value_contexts(&parsed)
+                .into_iter()
+                .map(|ctx| ctx.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(actual, contexts, "{expr}");
+        }
+    }
+}
@@ -1,38 +1,68 @@
 //! Core namespace for zizmor's audits.
 
-use github_actions_models::action;
+use std::sync::LazyLock;
+
+use github_actions_models::{
+    action,
+    common::{LocalUses, Uses},
+    workflow::job::{Step as WorkflowStep, StepBody as WorkflowStepBody},
+};
 use line_index::LineIndex;
+use serde::Serialize;
 use thiserror::Error;
 use tracing::instrument;
 use yamlpath::Document;
 
 use crate::{
-    finding::{Finding, FindingBuilder, SymbolicLocation},
+    finding::{Finding, FindingBuilder, Severity, SymbolicLocation},
     models::{
-        Action, AsDocument, CompositeStep, Job, NormalJob, ReusableWorkflowCallJob, Step, Workflow,
+        Action, AsDocument, CompositeStep, Job, NormalJob, ReusableWorkflowCallJob, Step,
+        StepCommon as _, Workflow,
     },
     registry::InputKey,
     state::AuditState,
 };
 
+pub(crate) mod artifact_poisoning;
+pub(crate) mod artifact_sensitive_paths;
 pub(crate) mod artipacked;
 pub(crate) mod bot_conditions;
 pub(crate) mod cache_poisoning;
+pub(crate) mod checkout_credentials;
+pub(crate) mod constant_conditions;
+pub(crate) mod curl_pipe_sh;
+pub(crate) mod custom_rules;
 pub(crate) mod dangerous_triggers;
+pub(crate) mod deprecations;
+pub(crate) mod dispatch_inputs;
+pub(crate) mod environment_protection;
 pub(crate) mod excessive_permissions;
+pub(crate) mod expired_ignores;
 pub(crate) mod forbidden_uses;
 pub(crate) mod github_env;
+pub(crate) mod github_script_injection;
 pub(crate) mod hardcoded_container_credentials;
 pub(crate) mod impostor_commit;
 pub(crate) mod insecure_commands;
 pub(crate) mod known_vulnerable_actions;
+pub(crate) mod local_uses;
+pub(crate) mod matrix_injection;
 pub(crate) mod obfuscation;
+pub(crate) mod official_forks;
+pub(crate) mod oidc_permissions;
 pub(crate) mod overprovisioned_secrets;
 pub(crate) mod ref_confusion;
+pub(crate) mod reusable_permissions;
+pub(crate) mod secret_exposure;
 pub(crate) mod secrets_inherit;
+pub(crate) mod secrets_to_third_party;
 pub(crate) mod self_hosted_runner;
 pub(crate) mod stale_action_refs;
+pub(crate) mod stale_pin_comment;
+pub(crate) mod static_cloud_credentials;
+pub(crate) mod tag_moved;
 pub(crate) mod template_injection;
+pub(crate) mod typosquatting;
 pub(crate) mod unpinned_images;
 pub(crate) mod unpinned_uses;
 pub(crate) mod unredacted_secrets;
@@ -67,12 +97,57 @@ impl AuditInput {
         }
     }
 
-    pub(crate) fn location(&self) -> SymbolicLocation {
+    pub(crate) fn location(&self) -> SymbolicLocation<'_> {
         match self {
             AuditInput::Workflow(workflow) => workflow.location(),
             AuditInput::Action(action) => action.location(),
         }
     }
+
+    /// Every step in this input that uses a local action, i.e.
+    /// `uses: ./path`, along with the location of its `uses:`.
+    pub(crate) fn local_action_uses(&self) -> Vec<(SymbolicLocation<'_>, &LocalUses)> {
+        let mut uses = vec![];
+
+        match self {
+            AuditInput::Workflow(workflow) => {
+                for job in workflow.jobs() {
+                    let Job::NormalJob(job) = job else {
+                        continue;
+                    };
+
+                    for step in job.steps() {
+                        let inner: &WorkflowStep = *step;
+                        if let WorkflowStepBody::Uses {
+                            uses: Uses::Local(local),
+                            ..
+                        } = &inner.body
+                        {
+                            uses.push((step.location().with_keys(&["uses".into()]), local));
+                        }
+                    }
+                }
+            }
+            AuditInput::Action(action) => {
+                if !matches!(action.runs, action::Runs::Composite(_)) {
+                    return uses;
+                }
+
+                for step in action.steps() {
+                    let inner: &action::Step = *step;
+                    if let action::StepBody::Uses {
+                        uses: Uses::Local(local),
+                        ..
+                    } = &inner.body
+                    {
+                        uses.push((step.location().with_keys(&["uses".into()]), local));
+                    }
+                }
+            }
+        }
+
+        uses
+    }
 }
 
 impl<'a> AsDocument<'a, 'a> for AuditInput {
@@ -114,6 +189,17 @@ pub(crate) trait AuditCore {
     where
         Self: Sized;
 
+    /// The severity that the audit's findings typically have, e.g. for
+    /// the SARIF output's per-rule default level.
+    fn default_severity() -> Severity
+    where
+        Self: Sized;
+
+    /// A short Markdown blurb on how to fix the audit's findings.
+    fn remediation() -> &'static str
+    where
+        Self: Sized;
+
     fn finding<'doc>() -> FindingBuilder<'doc>
     where
         Self: Sized,
@@ -124,15 +210,17 @@ pub(crate) trait AuditCore {
 
 /// A convenience macro for implementing [`Audit`] on a type.
 ///
+/// Each audit's remediation blurb is read from `remediation/<id>.md`.
+///
 /// Example use:
 ///
 /// ```no_run
 /// struct SomeAudit;
 ///
-/// audit_meta!(SomeAudit, "some-audit", "brief description");
+/// audit_meta!(SomeAudit, "some-audit", "brief description", Severity::Medium);
 /// ```
 macro_rules! audit_meta {
-    ($t:ty, $id:literal, $desc:expr_2021) => {
+    ($t:ty, $id:literal, $desc:expr_2021, $severity:expr_2021) => {
         use crate::audit::AuditCore;
 
         impl AuditCore for $t {
@@ -150,6 +238,14 @@ macro_rules! audit_meta {
             fn url() -> &'static str {
                 concat!("https://docs.zizmor.sh/audits/#", $id)
             }
+
+            fn default_severity() -> crate::finding::Severity {
+                $severity
+            }
+
+            fn remediation() -> &'static str {
+                include_str!(concat!("remediation/", $id, ".md"))
+            }
         }
     };
 }
@@ -166,8 +262,126 @@ pub(crate) enum AuditLoadError {
     /// entire run should be aborted.
     #[error("{0}")]
     Fail(anyhow::Error),
+    /// The audit's initialization failed unexpectedly, e.g. because a
+    /// bundled parser couldn't be loaded. The run continues without the
+    /// audit, unless `--strict` is given.
+    #[error("{0}")]
+    Broken(anyhow::Error),
 }
 
+/// Whether an audit uses the GitHub API.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Network {
+    /// The audit never uses the GitHub API.
+    #[default]
+    Offline,
+    /// The audit uses the GitHub API when it's available, and runs
+    /// in a reduced form otherwise.
+    Optional,
+    /// The audit is skipped without the GitHub API.
+    Online,
+}
+
+impl Network {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Network::Offline => "offline",
+            Network::Optional => "optional",
+            Network::Online => "online",
+        }
+    }
+}
+
+/// Static metadata about an audit, beyond its ident and description.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub(crate) struct AuditMeta {
+    pub(crate) network: Network,
+    /// The keys the audit accepts under `rules.<id>.config`.
+    pub(crate) config_keys: &'static [&'static str],
+}
+
+/// An audit type, as known before any audit is loaded.
+#[derive(Serialize)]
+pub(crate) struct Rule {
+    pub(crate) ident: &'static str,
+    pub(crate) desc: &'static str,
+    pub(crate) url: &'static str,
+    #[serde(skip)]
+    pub(crate) default_severity: Severity,
+    #[serde(skip)]
+    pub(crate) remediation: &'static str,
+    #[serde(flatten)]
+    pub(crate) meta: AuditMeta,
+    #[serde(skip)]
+    pub(crate) new: fn(&AuditState<'_>) -> Result<Box<dyn Audit>, AuditLoadError>,
+}
+
+impl Rule {
+    fn of<A: Audit + 'static>() -> Self {
+        Self {
+            ident: A::ident(),
+            desc: A::desc(),
+            url: A::url(),
+            default_severity: A::default_severity(),
+            remediation: A::remediation(),
+            meta: A::meta(),
+            new: |state| A::new(state).map(|audit| Box::new(audit) as Box<dyn Audit>),
+        }
+    }
+}
+
+/// Every audit zizmor knows about, in the order they're run.
+pub(crate) static RULES: LazyLock<Vec<Rule>> = LazyLock::new(|| {
+    vec![
+        Rule::of::<artipacked::Artipacked>(),
+        Rule::of::<unsound_contains::UnsoundContains>(),
+        Rule::of::<excessive_permissions::ExcessivePermissions>(),
+        Rule::of::<dangerous_triggers::DangerousTriggers>(),
+        Rule::of::<impostor_commit::ImpostorCommit>(),
+        Rule::of::<ref_confusion::RefConfusion>(),
+        Rule::of::<use_trusted_publishing::UseTrustedPublishing>(),
+        Rule::of::<template_injection::TemplateInjection>(),
+        Rule::of::<github_script_injection::GitHubScriptInjection>(),
+        Rule::of::<hardcoded_container_credentials::HardcodedContainerCredentials>(),
+        Rule::of::<self_hosted_runner::SelfHostedRunner>(),
+        Rule::of::<known_vulnerable_actions::KnownVulnerableActions>(),
+        Rule::of::<unpinned_uses::UnpinnedUses>(),
+        Rule::of::<checkout_credentials::CheckoutCredentials>(),
+        Rule::of::<secrets_to_third_party::SecretsToThirdParty>(),
+        Rule::of::<typosquatting::Typosquatting>(),
+        Rule::of::<official_forks::OfficialForks>(),
+        Rule::of::<insecure_commands::InsecureCommands>(),
+        Rule::of::<github_env::GitHubEnv>(),
+        Rule::of::<curl_pipe_sh::CurlPipeSh>(),
+        Rule::of::<cache_poisoning::CachePoisoning>(),
+        Rule::of::<artifact_poisoning::ArtifactPoisoning>(),
+        Rule::of::<artifact_sensitive_paths::ArtifactSensitivePaths>(),
+        Rule::of::<secrets_inherit::SecretsInherit>(),
+        Rule::of::<secret_exposure::SecretExposure>(),
+        Rule::of::<bot_conditions::BotConditions>(),
+        Rule::of::<overprovisioned_secrets::OverprovisionedSecrets>(),
+        Rule::of::<unredacted_secrets::UnredactedSecrets>(),
+        Rule::of::<forbidden_uses::ForbiddenUses>(),
+        Rule::of::<obfuscation::Obfuscation>(),
+        Rule::of::<stale_action_refs::StaleActionRefs>(),
+        Rule::of::<unpinned_images::UnpinnedImages>(),
+        Rule::of::<oidc_permissions::OidcPermissions>(),
+        Rule::of::<deprecations::Deprecations>(),
+        Rule::of::<constant_conditions::ConstantConditions>(),
+        Rule::of::<expired_ignores::ExpiredIgnores>(),
+        Rule::of::<custom_rules::CustomRules>(),
+        Rule::of::<environment_protection::EnvironmentProtection>(),
+        Rule::of::<tag_moved::TagMoved>(),
+        Rule::of::<stale_pin_comment::StalePinComment>(),
+        Rule::of::<matrix_injection::MatrixInjection>(),
+        Rule::of::<reusable_permissions::ReusablePermissions>(),
+        Rule::of::<static_cloud_credentials::StaticCloudCredentials>(),
+        Rule::of::<dispatch_inputs::DispatchInputs>(),
+        Rule::of::<local_uses::LocalUses>(),
+    ]
+});
+
 /// Auditing trait.
 ///
 /// Implementors of this trait can choose the level of specificity/context
@@ -194,11 +408,32 @@ pub(crate) enum AuditLoadError {
 /// In other words, if an audit chooses to implement [`Audit::audit`], it should implement
 /// **only** [`Audit::audit`] and not [`Audit::audit_normal_job`] or
 /// [`Audit::audit_step`].
-pub(crate) trait Audit: AuditCore {
+///
+/// Audits are shared between the threads that audit each input, so they
+/// must be `Send` and `Sync`; use locks or atomics for any interior mutability.
+pub(crate) trait Audit: AuditCore + Send + Sync {
     fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError>
     where
         Self: Sized;
 
+    /// Static metadata about this audit, e.g. for `--list-rules`.
+    ///
+    /// Audits that use the GitHub API or accept configuration should
+    /// override this.
+    fn meta() -> AuditMeta
+    where
+        Self: Sized,
+    {
+        AuditMeta::default()
+    }
+
+    /// Problems with this audit's configuration that don't prevent it
+    /// from running, e.g. policies that can never apply. These are
+    /// listed at the end of the run.
+    fn config_warnings(&self) -> Vec<String> {
+        vec![]
+    }
+
     fn audit_step<'doc>(&self, _step: &Step<'doc>) -> anyhow::Result<Vec<Finding<'doc>>> {
         Ok(vec![])
     }
@@ -258,6 +493,19 @@ pub(crate) trait Audit: AuditCore {
         Ok(vec![])
     }
 
+    /// Runs once every input has been audited, with the inputs that
+    /// this audit was run on, for audits that report on the run as a
+    /// whole (e.g. roll-ups of what every input uses).
+    ///
+    /// Since cached results are replayed without running any audits,
+    /// implementors should derive their findings from `inputs` alone.
+    fn audit_finish<'doc>(
+        &self,
+        _inputs: &[&'doc AuditInput],
+    ) -> anyhow::Result<Vec<Finding<'doc>>> {
+        Ok(vec![])
+    }
+
     /// The top-level auditing function for both workflows and actions.
     ///
     /// Implementors **should not** override this blanket implementation,
@@ -2,12 +2,13 @@ use github_actions_expressions::Expr;
 use github_actions_models::common::{RepositoryUses, Uses};
 
 use crate::{
-    Confidence, Severity,
+    Confidence, Persona, Severity,
     finding::{Feature, Finding, Location},
-    models::{CompositeStep, Step, StepCommon},
+    models::{CompositeStep, Step, StepCommon, uses::UsesExt as _},
     utils::parse_expressions_from_input,
 };
 
+use super::unpinned_uses::DEFAULT_OFFICIAL_ORGS;
 use super::{Audit, AuditInput, AuditLoadError, AuditState, audit_meta};
 
 pub(crate) struct Obfuscation;
@@ -15,11 +16,12 @@ pub(crate) struct Obfuscation;
 audit_meta!(
     Obfuscation,
     "obfuscation",
-    "obfuscated usage of GitHub Actions features"
+    "obfuscated usage of GitHub Actions features",
+    Severity::Low
 );
 
 impl Obfuscation {
-    fn obfuscated_repo_uses(&self, uses: &RepositoryUses) -> Vec<&str> {
+    fn obfuscated_repo_uses(&self, uses: &RepositoryUses) -> Vec<(&str, Severity, Persona)> {
         let mut annotations = vec![];
 
         // Users can put all kinds of nonsense in `uses:` clauses, which
@@ -32,25 +34,91 @@ impl Obfuscation {
                     // . and .. are valid in uses subpaths, but are impossible to
                     // analyze or match with full generality.
                     "." => {
-                        annotations.push("actions reference contains '.'");
+                        annotations.push((
+                            "actions reference contains '.'",
+                            Severity::Low,
+                            Persona::Regular,
+                        ));
                     }
                     ".." => {
-                        annotations.push("actions reference contains '..'");
+                        annotations.push((
+                            "actions reference contains '..'",
+                            Severity::Low,
+                            Persona::Regular,
+                        ));
                     }
                     // `uses: foo/bar////baz` and similar is valid, but
                     // only serves to mess up pattern matching.
                     // This also catches `uses: foo/bar/@v1`.
                     _ if component.is_empty() => {
-                        annotations.push("actions reference contains empty component");
+                        annotations.push((
+                            "actions reference contains empty component",
+                            Severity::Low,
+                            Persona::Regular,
+                        ));
                     }
                     _ => {}
                 }
             }
         }
 
+        let components = [
+            Some(uses.owner.as_str()),
+            Some(uses.repo.as_str()),
+            uses.subpath.as_deref(),
+            uses.git_ref.as_deref(),
+        ];
+
+        // `Actions/checkout` resolves to `actions/checkout`, but looks like
+        // an attempt to imitate an official org (or to dodge case-sensitive
+        // pattern matching).
+        if uses.owner.chars().any(|c| c.is_ascii_uppercase())
+            && DEFAULT_OFFICIAL_ORGS.contains(&uses.owner.to_lowercase().as_str())
+        {
+            annotations.push((
+                "actions reference uses unusual casing for an official org",
+                Severity::Low,
+                Persona::Pedantic,
+            ));
+        }
+
+        // e.g. `actions/cache/save%2F..%2Fsave@v4`.
+        if components
+            .iter()
+            .flatten()
+            .any(|component| Self::has_percent_encoding(component))
+        {
+            annotations.push((
+                "actions reference contains URL-encoded characters",
+                Severity::Low,
+                Persona::Pedantic,
+            ));
+        }
+
+        // Invisible and confusable characters make a reference look like
+        // a different one when reviewed, e.g. `actions/checkout@v4\u{200b}`.
+        if components
+            .iter()
+            .flatten()
+            .any(|component| !component.chars().all(|c| c.is_ascii_graphic()))
+        {
+            annotations.push((
+                "actions reference contains non-ASCII or invisible characters",
+                Severity::Medium,
+                Persona::Regular,
+            ));
+        }
+
         annotations
     }
 
+    /// Whether the given `uses:` component contains a `%XX` escape.
+    fn has_percent_encoding(component: &str) -> bool {
+        component.as_bytes().windows(3).any(|window| {
+            window[0] == b'%' && window[1].is_ascii_hexdigit() && window[2].is_ascii_hexdigit()
+        })
+    }
+
     fn obfuscated_exprs(&self, expr: &Expr) -> Vec<&str> {
         let mut annotations = vec![];
 
@@ -75,12 +143,24 @@ impl Obfuscation {
     ) -> anyhow::Result<Vec<Finding<'doc>>> {
         let mut findings = vec![];
 
-        if let Some(Uses::Repository(uses)) = step.uses() {
-            for annotation in self.obfuscated_repo_uses(uses) {
+        let Some(uses) = step.uses() else {
+            return Ok(findings);
+        };
+
+        // Expressions in `uses:` mean that the action can't be determined
+        // statically. These are flagged by unpinned-uses, which owns them
+        // so that each one gets exactly one finding.
+        if uses.dynamic() {
+            return Ok(findings);
+        }
+
+        if let Uses::Repository(uses) = uses {
+            for (annotation, severity, persona) in self.obfuscated_repo_uses(uses) {
                 findings.push(
                     Self::finding()
                         .confidence(Confidence::High)
-                        .severity(Severity::Low)
+                        .severity(severity)
+                        .persona(persona)
                         .add_location(
                             step.location()
                                 .primary()
@@ -0,0 +1,211 @@
+//! Detects third-party actions whose repository name matches an official
+//! action's, e.g. `my-actions/checkout` instead of `actions/checkout`.
+//!
+//! These are usually forks of the official action, which don't receive the
+//! official action's fixes and can be changed at any time by their owner.
+
+use anyhow::Result;
+use github_actions_models::common::Uses;
+
+use super::stale_action_refs::StatusCache;
+use super::unpinned_uses::{AllowedOrgs, DEFAULT_OFFICIAL_ORGS};
+use super::{Audit, AuditLoadError, AuditMeta, Network, audit_meta};
+use crate::finding::{Confidence, Finding, Persona, Severity};
+use crate::github_api::{self, RateLimited, RepositoryStatus};
+use crate::models::{CompositeStep, Step, StepCommon, uses::UsesExt as _};
+use crate::state::AuditState;
+
+/// Well-known actions under the official organizations.
+///
+/// Repositories that aren't listed here are looked up with the GitHub API
+/// instead, when it's available.
+const OFFICIAL_ACTIONS: &[&str] = &[
+    "actions/add-to-project",
+    "actions/attest",
+    "actions/attest-build-provenance",
+    "actions/attest-sbom",
+    "actions/cache",
+    "actions/checkout",
+    "actions/configure-pages",
+    "actions/create-github-app-token",
+    "actions/create-release",
+    "actions/delete-package-versions",
+    "actions/dependency-review-action",
+    "actions/deploy-pages",
+    "actions/download-artifact",
+    "actions/first-interaction",
+    "actions/github-script",
+    "actions/jekyll-build-pages",
+    "actions/labeler",
+    "actions/setup-dotnet",
+    "actions/setup-go",
+    "actions/setup-java",
+    "actions/setup-node",
+    "actions/setup-python",
+    "actions/stale",
+    "actions/upload-artifact",
+    "actions/upload-pages-artifact",
+    "actions/upload-release-asset",
+    "dependabot/fetch-metadata",
+    "github/codeql-action",
+    "github/issue-labeler",
+    "github/super-linter",
+];
+
+pub(crate) struct OfficialForks {
+    client: Option<github_api::Client>,
+    allowed_orgs: AllowedOrgs,
+    statuses: StatusCache,
+}
+
+audit_meta!(
+    OfficialForks,
+    "official-forks",
+    "possible fork of an official action",
+    Severity::Medium
+);
+
+impl OfficialForks {
+    /// Returns the official action whose repository is named `repo`, if any.
+    fn official_action(&self, repo: &str) -> Result<Option<String>> {
+        let repo = repo.to_lowercase();
+
+        if let Some(action) = OFFICIAL_ACTIONS
+            .iter()
+            .find(|action| action.split_once('/').is_some_and(|(_, r)| r == repo))
+        {
+            return Ok(Some(action.to_string()));
+        }
+
+        let Some(client) = &self.client else {
+            return Ok(None);
+        };
+
+        for org in DEFAULT_OFFICIAL_ORGS {
+            let key = format!("{org}/{repo}");
+            let status = match self.statuses.get(&key) {
+                Some(status) => status,
+                None => {
+                    // Once we're rate limited, we only check against the
+                    // bundled list for the rest of the run.
+                    let Some(status) = RateLimited::skip(
+                        client.repository_status(org, &repo),
+                        "checks for forks of unbundled actions",
+                    )?
+                    else {
+                        return Ok(None);
+                    };
+                    self.statuses.insert(key.clone(), status);
+                    status
+                }
+            };
+
+            if !matches!(status, RepositoryStatus::Missing) {
+                return Ok(Some(key));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn process_step<'doc>(&self, step: &impl StepCommon<'doc>) -> Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        let Some(uses @ Uses::Repository(repo_uses)) = step.uses() else {
+            return Ok(findings);
+        };
+
+        if uses.dynamic()
+            || self
+                .allowed_orgs
+                .contains(&repo_uses.owner, &repo_uses.repo)
+        {
+            return Ok(findings);
+        }
+
+        let Some(official) = self.official_action(&repo_uses.repo)? else {
+            return Ok(findings);
+        };
+
+        // A hash-pinned fork can't change underneath the workflow,
+        // but it still isn't the official action.
+        let (severity, persona) = if uses.unhashed() {
+            (Severity::Medium, Persona::default())
+        } else {
+            (Severity::Low, Persona::Pedantic)
+        };
+
+        findings.push(
+            Self::finding()
+                .confidence(Confidence::Medium)
+                .severity(severity)
+                .persona(persona)
+                .add_location(
+                    step.location()
+                        .primary()
+                        .with_keys(&["uses".into()])
+                        .annotated(format!("possible fork of {official} — verify provenance")),
+                )
+                .build(step)?,
+        );
+
+        Ok(findings)
+    }
+}
+
+impl Audit for OfficialForks {
+    fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        // Without a client, we can still check against the bundled list.
+        let client = state.online_client().ok();
+
+        let allowed_orgs = AllowedOrgs::from_state(state).map_err(AuditLoadError::Fail)?;
+
+        Ok(Self {
+            client,
+            allowed_orgs,
+            statuses: StatusCache::load(&state.cache_dir),
+        })
+    }
+
+    fn meta() -> AuditMeta {
+        AuditMeta {
+            network: Network::Optional,
+            ..Default::default()
+        }
+    }
+
+    fn audit_step<'doc>(&self, step: &Step<'doc>) -> Result<Vec<Finding<'doc>>> {
+        self.process_step(step)
+    }
+
+    fn audit_composite_step<'doc>(&self, step: &CompositeStep<'doc>) -> Result<Vec<Finding<'doc>>> {
+        self.process_step(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OFFICIAL_ACTIONS;
+    use crate::audit::unpinned_uses::DEFAULT_OFFICIAL_ORGS;
+
+    #[test]
+    fn test_official_actions() {
+        for action in OFFICIAL_ACTIONS {
+            let (owner, repo) = action.split_once('/').unwrap();
+            assert!(DEFAULT_OFFICIAL_ORGS.contains(&owner), "{action}");
+            assert_eq!(repo, repo.to_lowercase(), "{action}");
+        }
+
+        // Each repository name maps to exactly one official action.
+        let mut repos = OFFICIAL_ACTIONS
+            .iter()
+            .map(|action| action.split_once('/').unwrap().1)
+            .collect::<Vec<_>>();
+        repos.sort();
+        repos.dedup();
+        assert_eq!(repos.len(), OFFICIAL_ACTIONS.len());
+    }
+}
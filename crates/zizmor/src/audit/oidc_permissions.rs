@@ -0,0 +1,190 @@
+//! Detects `id-token: write` permissions that are broader than the
+//! jobs that actually use OIDC.
+//!
+//! `id-token: write` lets a job mint OIDC tokens, which are commonly
+//! exchanged for cloud credentials. Granting it at the workflow level
+//! hands it to every job that doesn't set its own permissions, including
+//! jobs that have no use for it.
+
+use anyhow::Context as _;
+use github_actions_models::common::{Permission, Permissions, Uses};
+use serde::Deserialize;
+
+use super::{Audit, AuditLoadError, AuditMeta, AuditState, Job, audit_meta};
+use crate::finding::{Confidence, Finding, Persona, Severity};
+use crate::models::uses::RepositoryUsesPattern;
+use crate::models::{JobExt as _, NormalJob, StepCommon as _, Workflow};
+
+/// Actions that consume the job's OIDC token.
+const OIDC_ACTIONS: &[&str] = &[
+    "actions/attest",
+    "actions/attest-build-provenance",
+    "aws-actions/configure-aws-credentials",
+    "azure/login",
+    "google-github-actions/auth",
+    "hashicorp/vault-action",
+    "pypa/gh-action-pypi-publish",
+    "rubygems/release-gem",
+    "sigstore/cosign-installer",
+    "sigstore/gh-action-sigstore-python",
+];
+
+audit_meta!(
+    OidcPermissions,
+    "oidc-permissions",
+    "overly broad id-token permission",
+    Severity::Medium
+);
+
+/// The raw `oidc-permissions` configuration.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct OidcPermissionsConfig {
+    /// Additional `uses:` patterns for actions that consume OIDC tokens,
+    /// e.g. internal deployment actions.
+    #[serde(default)]
+    oidc_actions: Vec<RepositoryUsesPattern>,
+}
+
+pub(crate) struct OidcPermissions {
+    oidc_actions: Vec<RepositoryUsesPattern>,
+}
+
+/// Whether `permissions` explicitly grants `id-token: write`.
+fn grants_id_token(permissions: &Permissions) -> bool {
+    match permissions {
+        Permissions::Explicit(perms) => perms
+            .get("id-token")
+            .is_some_and(|perm| *perm == Permission::Write),
+        Permissions::Base(_) => false,
+    }
+}
+
+impl OidcPermissions {
+    /// Whether any of `job`'s steps uses a known OIDC-consuming action.
+    fn uses_oidc(&self, job: &NormalJob<'_>) -> bool {
+        job.steps().any(|step| match step.uses() {
+            Some(Uses::Repository(uses)) => self
+                .oidc_actions
+                .iter()
+                .any(|pattern| pattern.matches(uses)),
+            _ => false,
+        })
+    }
+}
+
+impl Audit for OidcPermissions {
+    fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        let config = state
+            .config
+            .rule_config::<OidcPermissionsConfig>(Self::ident())
+            .map_err(AuditLoadError::Fail)?;
+
+        let oidc_actions = OIDC_ACTIONS
+            .iter()
+            .map(|action| {
+                action
+                    .parse()
+                    .with_context(|| format!("invalid OIDC action pattern: {action}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(AuditLoadError::Fail)?
+            .into_iter()
+            .chain(config.into_iter().flat_map(|config| config.oidc_actions))
+            .collect();
+
+        Ok(Self { oidc_actions })
+    }
+
+    fn meta() -> AuditMeta {
+        AuditMeta {
+            config_keys: &["oidc-actions"],
+            ..Default::default()
+        }
+    }
+
+    fn audit_workflow<'doc>(&self, workflow: &'doc Workflow) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        // Jobs that don't set their own permissions inherit the workflow's.
+        let mut consumers = vec![];
+        let mut non_consumers = 0;
+
+        for job in workflow.jobs() {
+            match job {
+                Job::NormalJob(job)
+                    if grants_id_token(&job.permissions) && !self.uses_oidc(&job) =>
+                {
+                    findings.push(
+                        Self::finding()
+                            .severity(Severity::Low)
+                            .confidence(Confidence::Low)
+                            .persona(Persona::Pedantic)
+                            .add_location(job.location())
+                            .add_location(
+                                job.location()
+                                    .primary()
+                                    .with_keys(&["permissions".into(), "id-token".into()])
+                                    .annotated("no recognized OIDC action uses this"),
+                            )
+                            .build(workflow)?,
+                    );
+                }
+                Job::NormalJob(job) if matches!(job.permissions, Permissions::Base(_)) => {
+                    if self.uses_oidc(&job) {
+                        consumers.push(job.location().annotated("this job uses OIDC"));
+                    } else {
+                        non_consumers += 1;
+                    }
+                }
+                // A reusable workflow may need the token, but we can't tell.
+                Job::ReusableWorkflowCallJob(job)
+                    if matches!(job.permissions, Permissions::Base(_)) =>
+                {
+                    consumers.push(job.location().annotated("this job may use OIDC"));
+                }
+                _ => {}
+            }
+        }
+
+        if grants_id_token(&workflow.permissions) && non_consumers > 0 {
+            let location = workflow
+                .location()
+                .primary()
+                .with_keys(&["permissions".into(), "id-token".into()]);
+
+            let mut finding = if consumers.is_empty() {
+                Self::finding()
+                    .severity(Severity::Low)
+                    .confidence(Confidence::Low)
+                    .persona(Persona::Pedantic)
+                    .add_location(location.annotated("no job uses a recognized OIDC action"))
+            } else {
+                Self::finding()
+                    .severity(Severity::Medium)
+                    .confidence(Confidence::High)
+                    .add_location(
+                        location.annotated(match non_consumers {
+                            1 => "granted to 1 job that doesn't use OIDC; \
+                              move this to the jobs that need it"
+                                .to_string(),
+                            n => format!(
+                                "granted to {n} jobs that don't use OIDC; \
+                             move this to the jobs that need it"
+                            ),
+                        }),
+                    )
+            };
+            for consumer in consumers {
+                finding = finding.add_location(consumer);
+            }
+
+            findings.push(finding.build(workflow)?);
+        }
+
+        Ok(findings)
+    }
+}
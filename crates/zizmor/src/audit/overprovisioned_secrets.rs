@@ -12,7 +12,8 @@ pub(crate) struct OverprovisionedSecrets;
 audit_meta!(
     OverprovisionedSecrets,
     "overprovisioned-secrets",
-    "excessively provisioned secrets"
+    "excessively provisioned secrets",
+    Severity::Medium
 );
 
 impl Audit for OverprovisionedSecrets {
@@ -6,10 +6,10 @@
 //! but the upstream repository may host *both* a branch and a tag named
 //! `foo`, making it unclear to the end user which is selected.
 
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use github_actions_models::common::{RepositoryUses, Uses};
 
-use super::{Audit, AuditLoadError, Job, audit_meta};
+use super::{Audit, AuditLoadError, AuditMeta, Job, Network, audit_meta};
 use crate::finding::Finding;
 use crate::models::{CompositeStep, JobExt as _, StepCommon};
 use crate::{
@@ -29,7 +29,8 @@ pub(crate) struct RefConfusion {
 audit_meta!(
     RefConfusion,
     "ref-confusion",
-    "git ref for action with ambiguous ref type"
+    "git ref for action with ambiguous ref type",
+    Severity::Medium
 );
 
 impl RefConfusion {
@@ -52,21 +53,18 @@ impl Audit for RefConfusion {
     where
         Self: Sized,
     {
-        if state.no_online_audits {
-            return Err(AuditLoadError::Skip(anyhow!(
-                "offline audits only requested"
-            )));
-        }
-
-        let Some(client) = state.github_client() else {
-            return Err(AuditLoadError::Skip(anyhow!(
-                "can't run without a GitHub API token"
-            )));
-        };
+        let client = state.online_client()?;
 
         Ok(Self { client })
     }
 
+    fn meta() -> AuditMeta {
+        AuditMeta {
+            network: Network::Online,
+            ..Default::default()
+        }
+    }
+
     fn audit_workflow<'doc>(
         &self,
         workflow: &'doc crate::models::Workflow,
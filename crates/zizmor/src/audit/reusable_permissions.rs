@@ -0,0 +1,317 @@
+//! Detects reusable workflow calls that grant the called workflow more
+//! permissions than its jobs declare.
+//!
+//! A called workflow's `GITHUB_TOKEN` gets the calling job's permissions
+//! (which may be inherited from the caller's workflow-level `permissions:`),
+//! and its own `permissions:` blocks can only take permissions away. A
+//! caller that grants `write-all` to a workflow that only needs
+//! `contents: read` hands every one of its jobs (and anything that can
+//! influence them) far more than they need.
+//!
+//! This can only be determined when the called workflow is audited in
+//! the same run, so it's done once every input has been audited. Calls to
+//! workflows that aren't available are only flagged (pedantically) when
+//! the caller grants `write-all`.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use github_actions_models::common::{BasePermission, Permission, Permissions};
+use itertools::Itertools as _;
+
+use super::excessive_permissions::KNOWN_PERMISSIONS;
+use super::{Audit, AuditInput, AuditLoadError, AuditState, audit_meta};
+use crate::finding::{Confidence, Finding, Persona, Severity, SymbolicLocation};
+use crate::models::{AsDocument as _, Job, JobExt as _, ReusableWorkflowCallJob, Workflow};
+
+pub(crate) struct ReusablePermissions;
+
+audit_meta!(
+    ReusablePermissions,
+    "reusable-permissions",
+    "called workflow is granted more permissions than it declares",
+    Severity::Medium
+);
+
+/// How much a permission allows, from least to most.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Access {
+    None,
+    Read,
+    Write,
+}
+
+impl Display for Access {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Access::None => write!(f, "none"),
+            Access::Read => write!(f, "read"),
+            Access::Write => write!(f, "write"),
+        }
+    }
+}
+
+/// Each scope's access under some permissions, or `None` if they're the
+/// default (and therefore unknown) permissions.
+fn access(permissions: &Permissions) -> Option<BTreeMap<&str, Access>> {
+    let all = |access| {
+        KNOWN_PERMISSIONS
+            .keys()
+            .map(|scope| (*scope, access))
+            .collect()
+    };
+
+    match permissions {
+        Permissions::Base(BasePermission::Default) => None,
+        Permissions::Base(BasePermission::ReadAll) => Some(all(Access::Read)),
+        Permissions::Base(BasePermission::WriteAll) => Some(all(Access::Write)),
+        Permissions::Explicit(perms) => Some(
+            perms
+                .iter()
+                .map(|(scope, perm)| {
+                    let access = match perm {
+                        Permission::Read => Access::Read,
+                        Permission::Write => Access::Write,
+                        Permission::None => Access::None,
+                    };
+                    (scope.as_str(), access)
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// A job's own permissions (i.e. ignoring its workflow's), and where
+/// they're set.
+fn job_permissions<'doc>(job: &Job<'doc>) -> (&'doc Permissions, SymbolicLocation<'doc>) {
+    match job {
+        Job::NormalJob(job) => {
+            let inner: &'doc _ = **job;
+            (&inner.permissions, job.location())
+        }
+        Job::ReusableWorkflowCallJob(job) => {
+            let inner: &'doc _ = **job;
+            (&inner.permissions, job.location())
+        }
+    }
+}
+
+impl ReusablePermissions {
+    /// Returns the permissions that `job` grants to the workflow it calls,
+    /// along with where they're set, unless they're the default ones.
+    fn granted<'doc>(
+        job: &ReusableWorkflowCallJob<'doc>,
+    ) -> Option<(&'doc Permissions, SymbolicLocation<'doc>)> {
+        let inner: &'doc _ = **job;
+        let workflow = job.parent();
+
+        if access(&inner.permissions).is_some() {
+            Some((
+                &inner.permissions,
+                job.location().with_keys(&["permissions".into()]),
+            ))
+        } else if access(&workflow.permissions).is_some() {
+            Some((
+                &workflow.permissions,
+                workflow.location().with_keys(&["permissions".into()]),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the most access to each scope that any of `callee`'s jobs
+    /// declares, along with each `permissions:` block that declares it.
+    ///
+    /// Returns `None` if any job has the default permissions, since it
+    /// then uses whatever the caller grants.
+    fn declared<'doc>(
+        callee: &'doc Workflow,
+    ) -> Option<(BTreeMap<&'doc str, Access>, Vec<SymbolicLocation<'doc>>)> {
+        let mut declared = BTreeMap::new();
+        let mut locations = vec![];
+        let mut inherited = false;
+
+        for job in callee.jobs() {
+            let (permissions, job_location) = job_permissions(&job);
+            let permissions = match access(permissions) {
+                Some(permissions) => {
+                    locations.push(
+                        job_location
+                            .with_keys(&["permissions".into()])
+                            .annotated("this job declares these permissions"),
+                    );
+                    permissions
+                }
+                None => {
+                    inherited = true;
+                    access(&callee.permissions)?
+                }
+            };
+
+            for (scope, access) in permissions {
+                let declared = declared.entry(scope).or_insert(Access::None);
+                *declared = (*declared).max(access);
+            }
+        }
+
+        if inherited {
+            locations.push(
+                callee
+                    .location()
+                    .with_keys(&["permissions".into()])
+                    .annotated("the called workflow's jobs inherit these permissions"),
+            );
+        }
+
+        Some((declared, locations))
+    }
+
+    fn audit_call<'doc>(
+        job: &ReusableWorkflowCallJob<'doc>,
+        inputs: &[&'doc AuditInput],
+    ) -> anyhow::Result<Option<Finding<'doc>>> {
+        let Some((granted, granted_location)) = Self::granted(job) else {
+            return Ok(None);
+        };
+        // NOTE: Safe unwrap, since `granted` only returns known permissions.
+        let granted_access = access(granted).unwrap();
+
+        let inner: &'doc _ = **job;
+        let callee = job
+            .parent()
+            .key
+            .reusable_workflow_key(&inner.uses)
+            .and_then(|key| inputs.iter().find(|input| *input.key() == key))
+            .and_then(|input| match input {
+                AuditInput::Workflow(workflow) => Some(workflow),
+                AuditInput::Action(_) => None,
+            });
+
+        let uses = job.location().with_keys(&["uses".into()]);
+
+        let Some(callee) = callee else {
+            // Without the called workflow, only the broadest grant is
+            // clearly more than it needs.
+            if !matches!(granted, Permissions::Base(BasePermission::WriteAll)) {
+                return Ok(None);
+            }
+
+            return Self::finding()
+                .severity(Severity::Medium)
+                .confidence(Confidence::Low)
+                .persona(Persona::Pedantic)
+                .add_location(granted_location.annotated("write-all is granted here"))
+                .add_location(
+                    uses.primary()
+                        .annotated("called workflow is granted write-all permissions"),
+                )
+                .build(job.parent())
+                .map(Some);
+        };
+
+        let Some((declared, declared_locations)) = Self::declared(callee) else {
+            return Ok(None);
+        };
+
+        let excess = granted_access
+            .into_iter()
+            .filter(|(scope, access)| {
+                *access > declared.get(scope).copied().unwrap_or(Access::None)
+            })
+            .collect::<Vec<_>>();
+        if excess.is_empty() {
+            return Ok(None);
+        }
+
+        let annotation = match granted {
+            Permissions::Base(BasePermission::ReadAll) => {
+                "read-all grants more than the called workflow declares".into()
+            }
+            Permissions::Base(BasePermission::WriteAll) => {
+                "write-all grants more than the called workflow declares".into()
+            }
+            _ => format!(
+                "grants {excess} beyond what the called workflow declares",
+                excess = excess
+                    .iter()
+                    .map(|(scope, access)| format!("{scope}: {access}"))
+                    .join(", ")
+            ),
+        };
+
+        let mut finding = Self::finding()
+            .severity(Severity::Medium)
+            .confidence(Confidence::High)
+            .add_location(granted_location.annotated("permissions are granted here"))
+            .add_location(uses.primary().annotated(annotation));
+
+        // The called workflow's locations are in another input, and so
+        // are concretized against it rather than the caller.
+        for location in declared_locations {
+            finding = finding.add_raw_location(location.concretize(callee.as_document())?);
+        }
+
+        finding.build(job.parent()).map(Some)
+    }
+}
+
+impl Audit for ReusablePermissions {
+    fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        Ok(Self)
+    }
+
+    fn audit_finish<'doc>(
+        &self,
+        inputs: &[&'doc AuditInput],
+    ) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        for input in inputs {
+            let AuditInput::Workflow(workflow) = input else {
+                continue;
+            };
+
+            for job in workflow.jobs() {
+                if let Job::ReusableWorkflowCallJob(job) = job {
+                    findings.extend(Self::audit_call(&job, inputs)?);
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use github_actions_models::common::Permissions;
+
+    use super::{Access, access};
+
+    #[test]
+    fn test_access() {
+        let parse = |permissions| serde_yaml::from_str::<Permissions>(permissions).unwrap();
+
+        assert_eq!(access(&Permissions::default()), None);
+
+        let write_all = parse("write-all");
+        let write_all = access(&write_all).unwrap();
+        assert_eq!(write_all["contents"], Access::Write);
+        assert_eq!(write_all["id-token"], Access::Write);
+
+        let explicit = parse("{contents: read, issues: write, packages: none}");
+        let explicit = access(&explicit).unwrap();
+        assert_eq!(
+            explicit.into_iter().collect::<Vec<_>>(),
+            [
+                ("contents", Access::Read),
+                ("issues", Access::Write),
+                ("packages", Access::None)
+            ]
+        );
+    }
+}
@@ -0,0 +1,471 @@
+//! Detects `run:` steps that expose secrets in their log output, e.g. by
+//! interpolating `${{ secrets.* }}` into an `echo` or by passing it as a
+//! command line argument while `set -x` is in effect.
+//!
+//! GitHub redacts registered secrets in logs, but redaction is a best-effort
+//! string match: transformed or partial values (e.g. base64-encoded, split
+//! across lines, or quoted differently by `set -x`) slip through it.
+
+use std::ops::Range;
+use std::sync::Mutex;
+
+use anyhow::{Context as _, Result};
+use github_actions_expressions::Expr;
+use github_actions_models::action;
+use github_actions_models::workflow::job::StepBody;
+use tree_sitter::{Language, Node, Parser};
+
+use super::{Audit, AuditLoadError, AuditState, audit_meta};
+use crate::finding::{Confidence, Finding, Severity, Subfeature};
+use crate::models::{CompositeStep, Step, StepCommon};
+use crate::utils;
+
+/// Commands whose purpose is to write their arguments to stdout.
+const OUTPUT_COMMANDS: &[&str] = &["echo", "printf"];
+
+/// Commands with a `login` subcommand that logs or warns about passwords
+/// passed on the command line.
+const LOGIN_TOOLS: &[&str] = &["docker", "podman", "buildah", "helm", "skopeo"];
+
+/// How a secret ends up in a step's log output.
+#[derive(Debug, PartialEq)]
+enum Exposure<'src> {
+    /// The secret is written to stdout by the given command.
+    Printed(&'src str),
+    /// The secret is passed as a password argument to `<tool> login`.
+    Argument(&'src str),
+    /// The secret appears in a command traced by `set -x`.
+    Traced,
+}
+
+/// A secret that's exposed within a `run:` block.
+#[derive(Debug, PartialEq)]
+struct ExposedSecret<'src> {
+    /// The secret's context, e.g. `secrets.API_TOKEN`.
+    context: String,
+    /// The span of the `${{ ... }}` expression within the `run:` block.
+    span: Range<usize>,
+    exposure: Exposure<'src>,
+}
+
+pub(crate) struct SecretExposure {
+    // NOTE: interior mutability used since Parser::parse requires &mut self,
+    // and audits are shared between threads
+    bash_parser: Mutex<Parser>,
+}
+
+audit_meta!(
+    SecretExposure,
+    "secret-exposure",
+    "secret exposed in step output",
+    Severity::Medium
+);
+
+/// Calls `visit` on `node` and each of its named descendants.
+fn walk<'t>(node: Node<'t>, visit: &mut impl FnMut(Node<'t>)) {
+    visit(node);
+
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        walk(child, visit);
+    }
+}
+
+impl SecretExposure {
+    /// Returns the name and arguments of a bash `command` node.
+    fn bash_command<'src>(
+        node: Node<'_>,
+        source: &'src str,
+    ) -> Option<(&'src str, Vec<&'src str>)> {
+        let name = &source[node.child_by_field_name("name")?.byte_range()];
+        let name = name.rsplit('/').next().unwrap_or(name);
+
+        let mut cursor = node.walk();
+        let args = node
+            .children_by_field_name("argument", &mut cursor)
+            .map(|arg| &source[arg.byte_range()])
+            .collect();
+
+        Some((name, args))
+    }
+
+    /// Returns the offsets at which `set` enables (`true`) or disables
+    /// (`false`) command tracing, in order.
+    fn xtrace_toggles(root: Node<'_>, source: &str) -> Vec<(usize, bool)> {
+        let mut toggles = vec![];
+
+        walk(root, &mut |node| {
+            if node.kind() != "command" {
+                return;
+            }
+
+            let Some(("set", args)) = Self::bash_command(node, source) else {
+                return;
+            };
+
+            for (idx, arg) in args.iter().enumerate() {
+                let next = args.get(idx + 1).copied();
+                let toggle = match arg.chars().next() {
+                    Some(c @ ('-' | '+')) if *arg == format!("{c}o") => {
+                        (next == Some("xtrace")).then_some(c == '-')
+                    }
+                    Some(c @ ('-' | '+')) if !arg.starts_with("--") && arg.contains('x') => {
+                        Some(c == '-')
+                    }
+                    _ => None,
+                };
+
+                if let Some(enabled) = toggle {
+                    toggles.push((node.start_byte(), enabled));
+                }
+            }
+        });
+
+        toggles.sort_by_key(|(offset, _)| *offset);
+        toggles
+    }
+
+    /// Whether the output of the given `command` node goes to the log,
+    /// i.e. isn't piped into another command or redirected into a file.
+    fn output_is_logged(command: Node<'_>, source: &str) -> bool {
+        let Some(parent) = command.parent() else {
+            return true;
+        };
+
+        match parent.kind() {
+            // `echo $X | docker login --password-stdin` is the right way
+            // to pass a secret to a command; only the last command in a
+            // pipeline writes to the log.
+            "pipeline" => {
+                parent.named_child(parent.named_child_count().saturating_sub(1)) == Some(command)
+            }
+            "redirected_statement" => !Self::redirects_to_file(parent, source),
+            _ => true,
+        }
+    }
+
+    /// Whether any of the given node's redirects (including those nested
+    /// within a heredoc, e.g. `cat <<EOF > file`) writes to a file.
+    fn redirects_to_file(node: Node<'_>, source: &str) -> bool {
+        let mut cursor = node.walk();
+        let redirects = node
+            .children_by_field_name("redirect", &mut cursor)
+            .collect::<Vec<_>>();
+
+        redirects.into_iter().any(|redirect| match redirect.kind() {
+            // NOTE: `>&2` and friends write to the log, not to a file.
+            "file_redirect" => {
+                let redirect = &source[redirect.byte_range()];
+                !redirect.contains('&') && !redirect.contains("/dev/std")
+            }
+            "heredoc_redirect" => Self::redirects_to_file(redirect, source),
+            _ => false,
+        })
+    }
+
+    /// Classifies how the secret at `span` is exposed, if at all.
+    fn exposure<'src>(
+        root: Node<'_>,
+        span: &Range<usize>,
+        source: &'src str,
+        traced: bool,
+    ) -> Option<Exposure<'src>> {
+        let mut node = root.descendant_for_byte_range(span.start, span.end)?;
+
+        loop {
+            match node.kind() {
+                // `cat <<EOF` with the secret in the heredoc's body.
+                "heredoc_body" => {
+                    let statement = node.parent()?.parent()?;
+                    let command = statement.child_by_field_name("body")?;
+                    return match Self::bash_command(command, source) {
+                        Some((name @ "cat", _)) if Self::output_is_logged(command, source) => {
+                            Some(Exposure::Printed(name))
+                        }
+                        _ => traced.then_some(Exposure::Traced),
+                    };
+                }
+                "command" => {
+                    let (name, args) = Self::bash_command(node, source)?;
+
+                    if OUTPUT_COMMANDS.contains(&name) && Self::output_is_logged(node, source) {
+                        return Some(Exposure::Printed(name));
+                    }
+
+                    if LOGIN_TOOLS.contains(&name)
+                        && args.contains(&"login")
+                        && args.iter().any(|arg| {
+                            *arg == "-p" || *arg == "--password" || arg.starts_with("--password=")
+                        })
+                    {
+                        return Some(Exposure::Argument(name));
+                    }
+
+                    return traced.then_some(Exposure::Traced);
+                }
+                _ => node = node.parent()?,
+            }
+        }
+    }
+
+    fn exposed_secrets<'src>(&self, script: &'src str) -> Result<Vec<ExposedSecret<'src>>> {
+        let secrets = utils::extract_expressions(script)
+            .into_iter()
+            .filter_map(|(expr, span)| {
+                let parsed = Expr::parse(expr.as_bare()).ok()?;
+                let context = parsed
+                    .dataflow_contexts()
+                    .into_iter()
+                    .find(|ctx| ctx.child_of("secrets"))
+                    .map(|ctx| ctx.as_str().to_string())?;
+                Some((context, span))
+            })
+            .collect::<Vec<_>>();
+
+        if secrets.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // NOTE: We parse the masked script (so that expressions don't
+        // confuse the parser) and slice into the original, which has
+        // the same offsets.
+        let masked = utils::mask_expressions(script);
+        let tree = self
+            .bash_parser
+            .lock()
+            .expect("parser lock poisoned")
+            .parse(&masked, None)
+            .context("failed to parse `run:` body as bash")?;
+        let root = tree.root_node();
+
+        let toggles = Self::xtrace_toggles(root, &masked);
+
+        let mut exposed = vec![];
+        for (context, span) in secrets {
+            // A secret that was explicitly masked earlier in the script is
+            // redacted from the log regardless of how it's transformed.
+            // NOTE: This also covers the `::add-mask::` command itself.
+            let masked_earlier = script[..span.end]
+                .lines()
+                .any(|line| line.contains("::add-mask::") && line.contains(context.as_str()));
+            if masked_earlier {
+                continue;
+            }
+
+            let traced = toggles
+                .iter()
+                .rev()
+                .find(|(offset, _)| *offset < span.start)
+                .is_some_and(|(_, enabled)| *enabled);
+
+            if let Some(exposure) = Self::exposure(root, &span, script, traced) {
+                exposed.push(ExposedSecret {
+                    context,
+                    span,
+                    exposure,
+                });
+            }
+        }
+
+        Ok(exposed)
+    }
+
+    fn process_run<'doc>(
+        &self,
+        step: &impl StepCommon<'doc>,
+        run: &str,
+        shell: &str,
+    ) -> Result<Vec<Finding<'doc>>> {
+        // TODO: handle pwsh and cmd.
+        if !matches!(utils::normalize_shell(shell), "bash" | "sh") {
+            return Ok(vec![]);
+        }
+
+        let mut findings = vec![];
+
+        for secret in self.exposed_secrets(run)? {
+            let context = &secret.context;
+            let (confidence, annotation) = match secret.exposure {
+                Exposure::Printed(command) => (
+                    Confidence::High,
+                    format!("`{context}` is printed by `{command}`; pass it via `env:` instead"),
+                ),
+                Exposure::Argument(command) => (
+                    Confidence::High,
+                    format!(
+                        "`{context}` is passed to `{command} login` on the command line; \
+                         use `--password-stdin` with `env:` instead"
+                    ),
+                ),
+                Exposure::Traced => (
+                    Confidence::Medium,
+                    format!("`{context}` is traced by `set -x`; pass it via `env:` instead"),
+                ),
+            };
+
+            let fragment = &run[secret.span.clone()];
+            let subfeature =
+                Subfeature::new(run[..secret.span.start].matches(fragment).count(), fragment);
+
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Medium)
+                    .confidence(confidence)
+                    .add_location(step.location().hidden())
+                    .add_location(step.location_with_name())
+                    .add_location(
+                        step.location()
+                            .primary()
+                            .with_keys(&["run".into()])
+                            .with_subfeature(subfeature)
+                            .annotated(annotation),
+                    )
+                    .build(step)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
+
+impl Audit for SecretExposure {
+    fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        let bash: Language = tree_sitter_bash::LANGUAGE.into();
+        let mut bash_parser = Parser::new();
+        bash_parser
+            .set_language(&bash)
+            .context("failed to load bash parser")
+            .map_err(AuditLoadError::Broken)?;
+
+        Ok(Self {
+            bash_parser: Mutex::new(bash_parser),
+        })
+    }
+
+    fn audit_step<'doc>(&self, step: &Step<'doc>) -> Result<Vec<Finding<'doc>>> {
+        let StepBody::Run { run, .. } = &step.body else {
+            return Ok(vec![]);
+        };
+
+        // If we can't infer a shell for this `run:`, assume that it's bash,
+        // like the runner itself does on non-Windows hosts.
+        let shell = step.shell().unwrap_or("bash");
+
+        self.process_run(step, run, shell)
+    }
+
+    fn audit_composite_step<'doc>(&self, step: &CompositeStep<'doc>) -> Result<Vec<Finding<'doc>>> {
+        let action::StepBody::Run { run, shell, .. } = &step.body else {
+            return Ok(vec![]);
+        };
+
+        self.process_run(step, run, shell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Exposure, SecretExposure};
+    use crate::audit::Audit;
+    use crate::github_api::GitHubHost;
+    use crate::state::AuditState;
+
+    fn audit() -> SecretExposure {
+        let audit_state = AuditState {
+            config: &Default::default(),
+            online: false,
+            cache_dir: "/tmp/zizmor".into(),
+            gh_token: None,
+            gh_hostname: GitHubHost::Standard("github.com".into()),
+            tpa_allowlist_file: None,
+            tpa_allowed_org: None,
+            lenient_allowlist: false,
+            advisories_file: None,
+            osv: false,
+            lockfile: None,
+            resolver: Default::default(),
+        };
+
+        SecretExposure::new(&audit_state).unwrap()
+    }
+
+    #[test]
+    fn test_exposed_secrets() {
+        let audit = audit();
+
+        for (script, expected) in [
+            // Printed.
+            (
+                "echo ${{ secrets.TOKEN }}",
+                vec![("secrets.TOKEN", Exposure::Printed("echo"))],
+            ),
+            (
+                "printf '%s\\n' \"${{ secrets.TOKEN }}\"",
+                vec![("secrets.TOKEN", Exposure::Printed("printf"))],
+            ),
+            (
+                "echo \"token: ${{ secrets.TOKEN }}\" >&2",
+                vec![("secrets.TOKEN", Exposure::Printed("echo"))],
+            ),
+            (
+                "cat <<EOF\ntoken: ${{ secrets.TOKEN }}\nEOF",
+                vec![("secrets.TOKEN", Exposure::Printed("cat"))],
+            ),
+            // Passed as an argument.
+            (
+                "docker login -u me -p ${{ secrets.TOKEN }} ghcr.io",
+                vec![("secrets.TOKEN", Exposure::Argument("docker"))],
+            ),
+            (
+                "helm registry login --password=${{ secrets.TOKEN }} example.com",
+                vec![("secrets.TOKEN", Exposure::Argument("helm"))],
+            ),
+            // Traced.
+            (
+                "set -ex\n./deploy --token ${{ secrets.TOKEN }}",
+                vec![("secrets.TOKEN", Exposure::Traced)],
+            ),
+            (
+                "set -o xtrace\n./deploy --token ${{ secrets.TOKEN }}",
+                vec![("secrets.TOKEN", Exposure::Traced)],
+            ),
+            // Not exposed.
+            ("./deploy --token ${{ secrets.TOKEN }}", vec![]),
+            (
+                "set -x\nset +x\n./deploy --token ${{ secrets.TOKEN }}",
+                vec![],
+            ),
+            ("echo ${{ github.sha }}", vec![]),
+            (
+                "echo ${{ secrets.TOKEN }} | docker login -u me --password-stdin",
+                vec![],
+            ),
+            ("echo ${{ secrets.TOKEN }} > token.txt", vec![]),
+            ("cat <<EOF > .npmrc\n${{ secrets.TOKEN }}\nEOF", vec![]),
+            (
+                "echo \"::add-mask::${{ secrets.TOKEN }}\"\necho ${{ secrets.TOKEN }}",
+                vec![],
+            ),
+            (
+                "echo \"::add-mask::${{ secrets.OTHER }}\"\necho ${{ secrets.TOKEN }}",
+                vec![("secrets.TOKEN", Exposure::Printed("echo"))],
+            ),
+        ] {
+            let exposed = audit
+                .exposed_secrets(script)
+                .unwrap()
+                .into_iter()
+                .map(|secret| (secret.context, secret.exposure))
+                .collect::<Vec<_>>();
+            let expected = expected
+                .into_iter()
+                .map(|(context, exposure)| (context.to_string(), exposure))
+                .collect::<Vec<_>>();
+
+            assert_eq!(exposed, expected, "{script}");
+        }
+    }
+}
@@ -1,22 +1,100 @@
+use github_actions_expressions::Expr;
+use github_actions_models::common::Uses;
 use github_actions_models::workflow::job::Secrets;
+use indexmap::IndexSet;
 
+use super::unpinned_uses::AllowedOrgs;
 use super::{Audit, AuditLoadError, AuditState, audit_meta};
-use crate::{finding::Confidence, models::JobExt as _};
+use crate::{
+    finding::{Confidence, Persona, Severity},
+    models::{AsDocument as _, JobExt as _},
+    utils::extract_expressions,
+};
 
-pub(crate) struct SecretsInherit;
+/// Components of a secret's name that suggest a broadly scoped credential,
+/// e.g. `ADMIN_TOKEN` or `ORG_PAT`.
+const BROAD_SECRET_MARKERS: &[&str] = &["admin", "org", "owner", "pat", "root", "super"];
+
+pub(crate) struct SecretsInherit {
+    allowed_orgs: AllowedOrgs,
+}
 
 audit_meta!(
     SecretsInherit,
     "secrets-inherit",
-    "secrets unconditionally inherited by called workflow"
+    "excessive secrets passed to called workflow",
+    Severity::Medium
 );
 
+impl SecretsInherit {
+    /// Whether the given reusable workflow is maintained by the same
+    /// organization as the calling workflow.
+    ///
+    /// Local workflows are always in the same repository. For remote
+    /// workflows, we only know the calling organization if the input was
+    /// itself fetched from GitHub; otherwise, we fall back on the orgs
+    /// that are considered first-party for `unpinned-uses`.
+    fn is_same_org(&self, job: &super::ReusableWorkflowCallJob<'_>) -> bool {
+        match &job.uses {
+            Uses::Repository(uses) => {
+                self.allowed_orgs.contains(&uses.owner, &uses.repo)
+                    || job
+                        .parent()
+                        .key
+                        .remote_owner()
+                        .is_some_and(|owner| owner.eq_ignore_ascii_case(&uses.owner))
+            }
+            _ => true,
+        }
+    }
+
+    /// Whether the given secret's name suggests a broadly scoped credential.
+    fn is_broad_secret(name: &str) -> bool {
+        name.split(['_', '-'])
+            .any(|part| BROAD_SECRET_MARKERS.contains(&part.to_lowercase().as_str()))
+    }
+
+    /// Collects every broadly scoped secret explicitly forwarded via the
+    /// job's `secrets:` block.
+    fn broad_secrets(job: &super::ReusableWorkflowCallJob<'_>) -> IndexSet<String> {
+        let mut secrets = IndexSet::new();
+
+        let Ok(block) = job
+            .location()
+            .with_keys(&["secrets".into()])
+            .concretize(job.parent().as_document())
+        else {
+            return secrets;
+        };
+
+        for (expr, _) in extract_expressions(block.concrete.feature) {
+            let Ok(parsed) = Expr::parse(expr.as_bare()) else {
+                continue;
+            };
+
+            for context in parsed.dataflow_contexts() {
+                let Some(name) = context.as_str().strip_prefix("secrets.") else {
+                    continue;
+                };
+
+                if Self::is_broad_secret(name) {
+                    secrets.insert(context.as_str().to_string());
+                }
+            }
+        }
+
+        secrets
+    }
+}
+
 impl Audit for SecretsInherit {
-    fn new(_state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError>
     where
         Self: Sized,
     {
-        Ok(Self)
+        let allowed_orgs = AllowedOrgs::from_state(state).map_err(AuditLoadError::Fail)?;
+
+        Ok(Self { allowed_orgs })
     }
 
     fn audit_reusable_job<'doc>(
@@ -25,26 +103,91 @@ impl Audit for SecretsInherit {
     ) -> anyhow::Result<Vec<super::Finding<'doc>>> {
         let mut findings = vec![];
 
-        if matches!(job.secrets, Some(Secrets::Inherit)) {
-            findings.push(
-                Self::finding()
+        let same_org = self.is_same_org(job);
+
+        match &job.secrets {
+            Some(Secrets::Inherit) => {
+                let finding = Self::finding()
                     .add_location(
                         job.location()
                             .primary()
                             .with_keys(&["uses".into()])
-                            .annotated("this reusable workflow"),
+                            .annotated(if same_org {
+                                "this reusable workflow"
+                            } else {
+                                "this third-party reusable workflow"
+                            }),
                     )
                     .add_location(
                         job.location()
                             .with_keys(&["secrets".into()])
                             .annotated("inherits all parent secrets"),
                     )
-                    .confidence(Confidence::High)
-                    .severity(crate::finding::Severity::Medium)
-                    .build(job.parent())?,
-            );
+                    .confidence(Confidence::High);
+
+                findings.push(if same_org {
+                    finding
+                        .severity(Severity::Low)
+                        .persona(Persona::Pedantic)
+                        .build(job.parent())?
+                } else {
+                    finding.severity(Severity::High).build(job.parent())?
+                });
+            }
+            Some(Secrets::Env(_)) if !same_org => {
+                let secrets = Self::broad_secrets(job);
+                if !secrets.is_empty() {
+                    let secrets = secrets
+                        .iter()
+                        .map(|secret| format!("`{secret}`"))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    findings.push(
+                        Self::finding()
+                            .add_location(
+                                job.location()
+                                    .primary()
+                                    .with_keys(&["uses".into()])
+                                    .annotated("this third-party reusable workflow"),
+                            )
+                            .add_location(
+                                job.location()
+                                    .with_keys(&["secrets".into()])
+                                    .annotated(format!("receives broadly scoped {secrets}")),
+                            )
+                            .confidence(Confidence::Medium)
+                            .severity(Severity::Medium)
+                            .build(job.parent())?,
+                    );
+                }
+            }
+            _ => {}
         }
 
         Ok(findings)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::SecretsInherit;
+
+    #[test]
+    fn test_is_broad_secret() {
+        for (name, expected) in [
+            ("ADMIN_TOKEN", true),
+            ("admin-token", true),
+            ("ORG_PAT", true),
+            ("PAT", true),
+            ("REPO_OWNER_KEY", true),
+            ("special-secret", false),
+            ("NPM_TOKEN", false),
+            ("PATH_TOKEN", false),
+            ("ADMINISTRATOR", false),
+            ("GITHUB_TOKEN", false),
+        ] {
+            assert_eq!(SecretsInherit::is_broad_secret(name), expected, "{name}");
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Detects secrets that are passed to third-party actions, especially
+//! actions that aren't pinned to a commit SHA.
+//!
+//! An action that receives a secret via `with:` or `env:` can exfiltrate
+//! it. When the action is pinned to a mutable ref, whoever controls the
+//! action's repository can change its code to do so at any time.
+
+use github_actions_expressions::Expr;
+use github_actions_models::common::Uses;
+use indexmap::IndexSet;
+
+use super::unpinned_uses::AllowedOrgs;
+use super::{Audit, AuditLoadError, AuditState, audit_meta};
+use crate::finding::{Confidence, Finding, Persona, Severity};
+use crate::models::{CompositeStep, Step, StepCommon, uses::UsesExt as _};
+use crate::utils::extract_expressions;
+
+pub(crate) struct SecretsToThirdParty {
+    allowed_orgs: AllowedOrgs,
+}
+
+audit_meta!(
+    SecretsToThirdParty,
+    "secrets-to-third-party",
+    "secrets passed to a third-party action",
+    Severity::Medium
+);
+
+impl SecretsToThirdParty {
+    /// Whether the given secret context refers to the automatic
+    /// `GITHUB_TOKEN`, rather than a user-defined secret.
+    fn is_github_token(secret: &str) -> bool {
+        secret.eq_ignore_ascii_case("secrets.GITHUB_TOKEN")
+            || secret.eq_ignore_ascii_case("github.token")
+    }
+
+    /// Collects every secret (including `github.token`) that flows into
+    /// the given step's `with:` and `env:` blocks.
+    fn passed_secrets<'doc>(step: &impl StepCommon<'doc>) -> IndexSet<String> {
+        let mut secrets = IndexSet::new();
+
+        for key in ["with", "env"] {
+            // Not every step has both blocks, so a missing key isn't an error.
+            let Ok(block) = step
+                .location()
+                .with_keys(&[key.into()])
+                .concretize(step.document())
+            else {
+                continue;
+            };
+
+            for (expr, _) in extract_expressions(block.concrete.feature) {
+                let Ok(parsed) = Expr::parse(expr.as_bare()) else {
+                    continue;
+                };
+
+                for context in parsed.dataflow_contexts() {
+                    if context.child_of("secrets") || context == "github.token" {
+                        secrets.insert(context.as_str().to_string());
+                    }
+                }
+            }
+        }
+
+        secrets
+    }
+
+    fn process_step<'doc>(
+        &self,
+        step: &impl StepCommon<'doc>,
+    ) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        let Some(uses) = step.uses() else {
+            return Ok(findings);
+        };
+
+        let Uses::Repository(repo_uses) = uses else {
+            return Ok(findings);
+        };
+
+        if self
+            .allowed_orgs
+            .contains(&repo_uses.owner, &repo_uses.repo)
+        {
+            return Ok(findings);
+        }
+
+        let (tokens, custom): (Vec<_>, Vec<_>) = Self::passed_secrets(step)
+            .into_iter()
+            .partition(|secret| Self::is_github_token(secret));
+
+        if tokens.is_empty() && custom.is_empty() {
+            return Ok(findings);
+        }
+
+        if !uses.unhashed() {
+            // Hash-pinned actions can't change underneath the workflow,
+            // so passing them secrets is only a concern for auditors.
+            let all = custom.iter().chain(tokens.iter());
+            findings.push(
+                Self::finding()
+                    .confidence(Confidence::High)
+                    .severity(Severity::Low)
+                    .persona(Persona::Pedantic)
+                    .add_location(
+                        step.location()
+                            .primary()
+                            .with_keys(&["uses".into()])
+                            .annotated(format!(
+                                "pinned third-party action receives {secrets}",
+                                secrets = Self::render(all)
+                            )),
+                    )
+                    .build(step)?,
+            );
+
+            return Ok(findings);
+        }
+
+        if !custom.is_empty() {
+            findings.push(
+                Self::finding()
+                    .confidence(Confidence::High)
+                    .severity(Severity::High)
+                    .add_location(
+                        step.location()
+                            .primary()
+                            .with_keys(&["uses".into()])
+                            .annotated(format!(
+                                "unpinned third-party action receives {secrets}",
+                                secrets = Self::render(custom.iter())
+                            )),
+                    )
+                    .build(step)?,
+            );
+        }
+
+        if !tokens.is_empty() {
+            findings.push(
+                Self::finding()
+                    .confidence(Confidence::High)
+                    .severity(Severity::Medium)
+                    .add_location(
+                        step.location()
+                            .primary()
+                            .with_keys(&["uses".into()])
+                            .annotated(format!(
+                                "unpinned third-party action receives the GITHUB_TOKEN via {secrets}",
+                                secrets = Self::render(tokens.iter())
+                            )),
+                    )
+                    .build(step)?,
+            );
+        }
+
+        Ok(findings)
+    }
+
+    fn render<'a>(secrets: impl Iterator<Item = &'a String>) -> String {
+        secrets
+            .map(|secret| format!("`{secret}`"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl Audit for SecretsToThirdParty {
+    fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        let allowed_orgs = AllowedOrgs::from_state(state).map_err(AuditLoadError::Fail)?;
+
+        Ok(Self { allowed_orgs })
+    }
+
+    fn audit_step<'doc>(&self, step: &Step<'doc>) -> anyhow::Result<Vec<Finding<'doc>>> {
+        self.process_step(step)
+    }
+
+    fn audit_composite_step<'a>(
+        &self,
+        step: &CompositeStep<'a>,
+    ) -> anyhow::Result<Vec<Finding<'a>>> {
+        self.process_step(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SecretsToThirdParty;
+
+    #[test]
+    fn test_is_github_token() {
+        for (secret, expected) in [
+            ("secrets.GITHUB_TOKEN", true),
+            ("secrets.github_token", true),
+            ("github.token", true),
+            ("GITHUB.TOKEN", true),
+            ("secrets.GITHUB_TOKEN_2", false),
+            ("secrets.NPM_TOKEN", false),
+            ("secrets", false),
+        ] {
+            assert_eq!(SecretsToThirdParty::is_github_token(secret), expected);
+        }
+    }
+}
@@ -3,7 +3,9 @@
 //! due to the potential for persistence between workflow runs.
 //!
 //! This audit is "auditor" only, since zizmor can't detect
-//! whether self-hosted runners are ephemeral or not.
+//! whether self-hosted runners are ephemeral or not. The exception
+//! is workflows triggered by pull requests, since these can run
+//! code from forks on the self-hosted runner.
 
 use anyhow::Result;
 use github_actions_models::{
@@ -24,7 +26,8 @@ pub(crate) struct SelfHostedRunner;
 audit_meta!(
     SelfHostedRunner,
     "self-hosted-runner",
-    "runs on a self-hosted runner"
+    "runs on a self-hosted runner",
+    Severity::Unknown
 );
 
 impl Audit for SelfHostedRunner {
@@ -41,6 +44,15 @@ impl Audit for SelfHostedRunner {
     ) -> Result<Vec<crate::finding::Finding<'doc>>> {
         let mut results = vec![];
 
+        // Pull requests from forks can run arbitrary code on the runner,
+        // regardless of whether it's ephemeral.
+        let pr_triggered = workflow.has_pull_request() || workflow.has_pull_request_target();
+        let (severity, persona) = if pr_triggered {
+            (Severity::High, Persona::Regular)
+        } else {
+            (Severity::Unknown, Persona::Auditor)
+        };
+
         for job in workflow.jobs() {
             let Job::NormalJob(job) = job else {
                 continue;
@@ -49,18 +61,14 @@ impl Audit for SelfHostedRunner {
             match &job.runs_on {
                 LoE::Literal(RunsOn::Target(labels)) => {
                     {
-                        let Some(label) = labels.first() else {
-                            continue;
-                        };
-
-                        if label == "self-hosted" {
-                            // All self-hosted runners start with the 'self-hosted'
-                            // label followed by any specifiers.
+                        // All self-hosted runners have the 'self-hosted'
+                        // label, typically (but not necessarily) first.
+                        if labels.iter().any(|label| label == "self-hosted") {
                             results.push(
                                 Self::finding()
                                     .confidence(Confidence::High)
-                                    .severity(Severity::Unknown)
-                                    .persona(Persona::Auditor)
+                                    .severity(severity)
+                                    .persona(persona)
                                     .add_location(
                                         job.location()
                                             .primary()
@@ -69,7 +77,10 @@ impl Audit for SelfHostedRunner {
                                     )
                                     .build(workflow)?,
                             );
-                        } else if ExplicitExpr::from_curly(label).is_some() {
+                        } else if labels
+                            .iter()
+                            .any(|label| ExplicitExpr::from_curly(label).is_some())
+                        {
                             // The job might also have its runner expanded via an
                             // expression. Long-term we should perform this evaluation
                             // to increase our confidence, but for now we flag it as
@@ -77,8 +88,8 @@ impl Audit for SelfHostedRunner {
                             results.push(
                                 Self::finding()
                                     .confidence(Confidence::Low)
-                                    .severity(Severity::Unknown)
-                                    .persona(Persona::Auditor)
+                                    .severity(severity)
+                                    .persona(persona)
                                     .add_location(
                                         job.location()
                                             .primary()
@@ -100,8 +111,8 @@ impl Audit for SelfHostedRunner {
                 LoE::Literal(RunsOn::Group { .. }) => results.push(
                     Self::finding()
                         .confidence(Confidence::Low)
-                        .severity(Severity::Unknown)
-                        .persona(Persona::Auditor)
+                        .severity(severity)
+                        .persona(persona)
                         .add_location(
                             job.location()
                                 .primary()
@@ -113,7 +124,33 @@ impl Audit for SelfHostedRunner {
                 // The entire `runs-on:` is an expression, which may or may
                 // not be a self-hosted runner when expanded, like above.
                 LoE::Expr(exp) => {
-                    let Ok(matrix) = Matrix::try_from(&job) else {
+                    let matrix = exp
+                        .as_bare()
+                        .starts_with("matrix.")
+                        .then(|| Matrix::try_from(&job).ok())
+                        .flatten();
+
+                    let Some(matrix) = matrix else {
+                        // Expressions that don't come from the matrix can't be
+                        // resolved statically, so we only flag them (with low
+                        // confidence) when fork PRs could reach the runner.
+                        if pr_triggered {
+                            results.push(
+                                Self::finding()
+                                    .confidence(Confidence::Low)
+                                    .severity(severity)
+                                    .persona(persona)
+                                    .add_location(
+                                        job.location()
+                                            .primary()
+                                            .with_keys(&["runs-on".into()])
+                                            .annotated(
+                                                "expression may expand into a self-hosted runner",
+                                            ),
+                                    )
+                                    .build(workflow)?,
+                            );
+                        }
                         continue;
                     };
 
@@ -127,8 +164,8 @@ impl Audit for SelfHostedRunner {
                         results.push(
                             Self::finding()
                                 .confidence(Confidence::High)
-                                .severity(Severity::Unknown)
-                                .persona(Persona::Auditor)
+                                .severity(severity)
+                                .persona(persona)
                                 .add_location(
                                     job.location()
                                         .with_keys(&["strategy".into()])
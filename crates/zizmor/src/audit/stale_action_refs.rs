@@ -1,25 +1,145 @@
-//! Detects actions pinned by commit hash, which doesn't point to a Git tag.
+//! Detects stale action references: actions pinned by a commit hash
+//! that doesn't point to a Git tag, and third-party actions whose
+//! repository is archived or no longer exists.
 
-use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::fs;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context as _, Result};
 use github_actions_models::common::{RepositoryUses, Uses};
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
 
-use super::{Audit, AuditLoadError, audit_meta};
+use super::unpinned_uses::AllowedOrgs;
+use super::{Audit, AuditLoadError, AuditMeta, Network, audit_meta};
 use crate::{
     Persona,
     finding::{Confidence, Finding, Severity},
-    github_api,
+    github_api::{self, RateLimited, RepositoryStatus},
     models::{CompositeStep, Step, StepCommon, uses::RepositoryUsesExt as _},
+    resolver::UsesResolver,
     state::AuditState,
 };
 
+/// How long a repository's status remains valid in the on-disk cache.
+const STATUS_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// A repository status, as recorded in the on-disk cache.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct CachedStatus {
+    status: RepositoryStatus,
+    /// When the status was fetched, in seconds since the Unix epoch.
+    checked_at: u64,
+}
+
+impl CachedStatus {
+    fn is_fresh(&self, now: u64) -> bool {
+        now.saturating_sub(self.checked_at) < STATUS_TTL.as_secs()
+    }
+}
+
+/// Repository statuses, keyed by lowercased `owner/repo`.
+///
+/// Statuses are kept in memory for the duration of the run, and
+/// persisted to the cache directory so that subsequent runs within
+/// [`STATUS_TTL`] don't need to query the GitHub API again.
+pub(crate) struct StatusCache {
+    path: PathBuf,
+    entries: Mutex<HashMap<String, CachedStatus>>,
+}
+
+impl StatusCache {
+    pub(crate) fn load(cache_dir: &Path) -> Self {
+        let path = cache_dir.join("repository-status.json");
+
+        // A missing or corrupt cache is never fatal; we just start over.
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<RepositoryStatus> {
+        self.entries
+            .lock()
+            .expect("status cache lock poisoned")
+            .get(key)
+            .filter(|cached| cached.is_fresh(now()))
+            .map(|cached| cached.status)
+    }
+
+    pub(crate) fn insert(&self, key: String, status: RepositoryStatus) {
+        self.entries
+            .lock()
+            .expect("status cache lock poisoned")
+            .insert(
+                key,
+                CachedStatus {
+                    status,
+                    checked_at: now(),
+                },
+            );
+
+        if let Err(e) = self.persist() {
+            tracing::warn!("couldn't persist repository status cache: {e:#}");
+        }
+    }
+
+    /// Atomically write the cache's fresh entries to disk.
+    fn persist(&self) -> Result<()> {
+        let now = now();
+        let entries = self
+            .entries
+            .lock()
+            .expect("status cache lock poisoned")
+            .iter()
+            .filter(|(_, cached)| cached.is_fresh(now))
+            .map(|(key, cached)| (key.clone(), *cached))
+            .collect::<HashMap<_, _>>();
+
+        let dir = self
+            .path
+            .parent()
+            .context("cache path has no parent directory")?;
+        fs::create_dir_all(dir)?;
+
+        let mut writer = BufWriter::new(NamedTempFile::new_in(dir)?);
+        serde_json::to_writer(&mut writer, &entries)?;
+        writer.into_inner()?.persist(&self.path)?;
+
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
 pub(crate) struct StaleActionRefs {
     client: github_api::Client,
+    allowed_orgs: AllowedOrgs,
+    resolver: Arc<UsesResolver>,
+    statuses: StatusCache,
 }
 
 audit_meta!(
     StaleActionRefs,
     "stale-action-refs",
-    "commit hash does not point to a Git tag"
+    "stale action reference",
+    Severity::Low
 );
 
 impl StaleActionRefs {
@@ -34,6 +154,24 @@ impl StaleActionRefs {
         Ok(tag.is_none())
     }
 
+    /// Returns the status of the action's repository, or `None` if
+    /// it couldn't be determined because we've been rate limited.
+    fn repository_status(&self, uses: &RepositoryUses) -> Result<Option<RepositoryStatus>> {
+        let key = format!("{}/{}", uses.owner, uses.repo).to_lowercase();
+        if let Some(status) = self.statuses.get(&key) {
+            return Ok(Some(status));
+        }
+
+        let status = RateLimited::skip(
+            self.client.repository_status(&uses.owner, &uses.repo),
+            "archived and missing repository checks",
+        )?;
+        if let Some(status) = status {
+            self.statuses.insert(key, status);
+        }
+        Ok(status)
+    }
+
     fn process_step<'w>(&self, step: &impl StepCommon<'w>) -> Result<Vec<Finding<'w>>> {
         let mut findings = vec![];
 
@@ -41,13 +179,58 @@ impl StaleActionRefs {
             return Ok(findings);
         };
 
+        // NOTE: Only actions on the run's GitHub host can be looked up.
+        let (host, uses) = self.resolver.on_host(uses);
+        if !self.resolver.is_local_host(&host) {
+            return Ok(findings);
+        }
+        let uses = &uses;
+
+        if !self.allowed_orgs.contains(&uses.owner, &uses.repo) {
+            let problem = match self.repository_status(uses)? {
+                Some(RepositoryStatus::Archived) => Some((
+                    Severity::Medium,
+                    "action's repository is archived and won't receive security fixes",
+                )),
+                Some(RepositoryStatus::Missing) => Some((
+                    Severity::High,
+                    "action's repository doesn't exist and its name could be re-registered",
+                )),
+                Some(RepositoryStatus::Active) | None => None,
+            };
+
+            if let Some((severity, annotation)) = problem {
+                findings.push(
+                    Self::finding()
+                        .confidence(Confidence::High)
+                        .severity(severity)
+                        .add_location(
+                            step.location()
+                                .primary()
+                                .with_keys(&["uses".into()])
+                                .annotated(annotation),
+                        )
+                        .build(step)?,
+                );
+
+                // There are no tags to compare against in a missing
+                // repository, and an archived one won't get new ones.
+                return Ok(findings);
+            }
+        }
+
         if self.is_stale_action_ref(uses)? {
             findings.push(
                 Self::finding()
                     .confidence(Confidence::High)
                     .severity(Severity::Low)
                     .persona(Persona::Pedantic)
-                    .add_location(step.location().primary().with_keys(&["uses".into()]))
+                    .add_location(
+                        step.location()
+                            .primary()
+                            .with_keys(&["uses".into()])
+                            .annotated("commit hash does not point to a Git tag"),
+                    )
                     .build(step)?,
             );
         }
@@ -61,19 +244,23 @@ impl Audit for StaleActionRefs {
     where
         Self: Sized,
     {
-        if state.no_online_audits {
-            return Err(AuditLoadError::Skip(anyhow!(
-                "offline audits only requested"
-            )));
-        }
+        let client = state.online_client()?;
 
-        let Some(client) = state.github_client() else {
-            return Err(AuditLoadError::Skip(anyhow!(
-                "can't run without a GitHub API token"
-            )));
-        };
+        let allowed_orgs = AllowedOrgs::from_state(state).map_err(AuditLoadError::Fail)?;
+
+        Ok(Self {
+            client,
+            allowed_orgs,
+            resolver: state.resolver.clone(),
+            statuses: StatusCache::load(&state.cache_dir),
+        })
+    }
 
-        Ok(Self { client })
+    fn meta() -> AuditMeta {
+        AuditMeta {
+            network: Network::Online,
+            ..Default::default()
+        }
     }
 
     fn audit_step<'w>(&self, step: &Step<'w>) -> Result<Vec<Finding<'w>>> {
@@ -84,3 +271,50 @@ impl Audit for StaleActionRefs {
         self.process_step(step)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_status_freshness() {
+        let cached = CachedStatus {
+            status: RepositoryStatus::Archived,
+            checked_at: 1_000,
+        };
+
+        assert!(cached.is_fresh(1_000));
+        assert!(cached.is_fresh(1_000 + STATUS_TTL.as_secs() - 1));
+        assert!(!cached.is_fresh(1_000 + STATUS_TTL.as_secs()));
+        // Clock skew shouldn't make an entry stale.
+        assert!(cached.is_fresh(0));
+    }
+
+    #[test]
+    fn test_status_cache_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let cache = StatusCache::load(dir.path());
+        assert!(cache.get("foo/bar").is_none());
+        cache.insert("foo/bar".into(), RepositoryStatus::Missing);
+        cache.insert("foo/baz".into(), RepositoryStatus::Active);
+
+        let cache = StatusCache::load(dir.path());
+        assert_eq!(cache.get("foo/bar"), Some(RepositoryStatus::Missing));
+        assert_eq!(cache.get("foo/baz"), Some(RepositoryStatus::Active));
+        assert!(cache.get("foo/quux").is_none());
+    }
+
+    #[test]
+    fn test_status_cache_expired() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("repository-status.json"),
+            r#"{"foo/bar": {"status": "archived", "checked-at": 0}}"#,
+        )
+        .unwrap();
+
+        let cache = StatusCache::load(dir.path());
+        assert!(cache.get("foo/bar").is_none());
+    }
+}
@@ -0,0 +1,267 @@
+//! Detects commit-pinned actions whose trailing version comment (e.g.
+//! `# v4.1.7`, as written by Renovate and similar tools) disagrees with
+//! the pinned commit.
+//!
+//! A SHA pin's comment is what reviewers actually read, so a comment that
+//! wasn't bumped alongside its SHA (or vice versa) misrepresents what runs.
+//! With the GitHub API, each commented tag is resolved and compared to the
+//! pinned commit. Without it, only malformed version comments are flagged,
+//! and only at the pedantic level.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use anyhow::Result;
+use github_actions_models::common::{RepositoryUses, Uses};
+use regex::Regex;
+
+use super::{Audit, AuditLoadError, AuditMeta, Network, audit_meta};
+use crate::finding::{Confidence, Finding, Persona, Severity, SymbolicLocation};
+use crate::github_api::{self, RateLimited, Tag};
+use crate::models::{
+    AsDocument, CompositeStep, JobExt as _, ReusableWorkflowCallJob, Step, StepCommon,
+    uses::RepositoryUsesExt as _,
+};
+use crate::state::AuditState;
+
+/// A well-formed version, e.g. `v4`, `4.1.7`, or `v1.2.3-rc.1`.
+static VERSION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^v?\d+(\.\d+)*([-+][0-9A-Za-z.-]+)?$").unwrap());
+
+/// Something that starts like a version, e.g. `v4.1.` or `V4`.
+static VERSION_LIKE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[vV]?[._-]?\d").unwrap());
+
+/// A version comment on a `uses:` clause.
+#[derive(Debug, PartialEq, Eq)]
+enum VersionComment<'a> {
+    /// A well-formed version, i.e. a tag to compare against.
+    Version(&'a str),
+    /// Something that looks like a version, but isn't one.
+    Malformed(&'a str),
+}
+
+impl<'a> VersionComment<'a> {
+    /// Parses a comment like `# v4.1.7` or `# tag=v4.1.7`, returning
+    /// `None` if it isn't a version comment at all.
+    fn parse(comment: &'a str) -> Option<Self> {
+        let text = comment.trim_start_matches('#').trim();
+        let text = text.strip_prefix("tag=").unwrap_or(text);
+        let token = text.split_whitespace().next()?;
+
+        if VERSION.is_match(token) {
+            Some(Self::Version(token))
+        } else if VERSION_LIKE.is_match(token) {
+            Some(Self::Malformed(token))
+        } else {
+            None
+        }
+    }
+}
+
+/// Each `owner/repo`'s tags, or `None` if they couldn't be listed.
+type TagCache = HashMap<(String, String), Option<Vec<Tag>>>;
+
+pub(crate) struct StalePinComment {
+    client: Option<github_api::Client>,
+    tags: Mutex<TagCache>,
+}
+
+audit_meta!(
+    StalePinComment,
+    "stale-pin-comment",
+    "pinned commit disagrees with its version comment",
+    Severity::Medium
+);
+
+impl StalePinComment {
+    /// Returns the names of the tags that point to `commit` in `uses`'s
+    /// repository, and whether `tag` is one of the repository's tags.
+    ///
+    /// Returns `None` if there's no client or the tags couldn't be listed.
+    fn tags_for(
+        &self,
+        uses: &RepositoryUses,
+        tag: &str,
+        commit: &str,
+    ) -> Result<Option<(Vec<String>, bool)>> {
+        let Some(client) = &self.client else {
+            return Ok(None);
+        };
+
+        let key = (uses.owner.clone(), uses.repo.clone());
+        if !self.tags.lock().unwrap().contains_key(&key) {
+            let tags = RateLimited::skip(
+                client.list_tags(&uses.owner, &uses.repo),
+                "version comment checks",
+            )?;
+            self.tags.lock().unwrap().insert(key.clone(), tags);
+        }
+
+        let cache = self.tags.lock().unwrap();
+        Ok(cache[&key].as_ref().map(|tags| {
+            let names = tags
+                .iter()
+                .filter(|t| t.commit.sha.eq_ignore_ascii_case(commit))
+                .map(|t| t.name.clone())
+                .collect();
+            (names, tags.iter().any(|t| t.name == tag))
+        }))
+    }
+
+    fn process_uses<'a, 'doc>(
+        &self,
+        doc: &'a impl AsDocument<'a, 'doc>,
+        uses: Option<&Uses>,
+        location: SymbolicLocation<'doc>,
+    ) -> Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        let Some(Uses::Repository(uses)) = uses else {
+            return Ok(findings);
+        };
+        let Some(commit) = uses.commit_ref() else {
+            return Ok(findings);
+        };
+
+        let concrete = location.clone().concretize(doc.as_document())?;
+        let Some(comment) = concrete
+            .concrete
+            .comments
+            .iter()
+            .find_map(|c| VersionComment::parse(c.as_str()))
+        else {
+            return Ok(findings);
+        };
+
+        match comment {
+            VersionComment::Malformed(version) => findings.push(
+                Self::finding()
+                    .confidence(Confidence::Medium)
+                    .severity(Severity::Low)
+                    .persona(Persona::Pedantic)
+                    .add_location(
+                        location
+                            .primary()
+                            .annotated(format!("version comment {version} is malformed")),
+                    )
+                    .build(doc)?,
+            ),
+            VersionComment::Version(version) => {
+                let Some((tags, exists)) = self.tags_for(uses, version, commit)? else {
+                    return Ok(findings);
+                };
+
+                // A commented tag that doesn't exist can't be compared, and
+                // a less specific comment (e.g. `v4` for `v4.1.7`) still
+                // agrees with the commit.
+                let agrees = tags.iter().any(|tag| {
+                    tag == version
+                        || tag
+                            .strip_prefix(version)
+                            .is_some_and(|rest| rest.starts_with('.'))
+                });
+                if !exists || agrees {
+                    return Ok(findings);
+                }
+
+                // Like `longest_tag_for_commit`, the most specific tag
+                // is the one reported.
+                let annotation = match tags.iter().max_by_key(|tag| tag.len()) {
+                    Some(actual) => {
+                        format!("comment says {version}, but the pinned commit is {actual}")
+                    }
+                    None => format!(
+                        "comment says {version}, but the pinned commit isn't any tagged version"
+                    ),
+                };
+
+                findings.push(
+                    Self::finding()
+                        .confidence(Confidence::High)
+                        .severity(Severity::Medium)
+                        .add_location(location.primary().annotated(annotation))
+                        .build(doc)?,
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+impl Audit for StalePinComment {
+    fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        // Without a client, we can still check for malformed comments.
+        let client = state.online_client().ok();
+
+        Ok(Self {
+            client,
+            tags: Default::default(),
+        })
+    }
+
+    fn meta() -> AuditMeta {
+        AuditMeta {
+            network: Network::Optional,
+            ..Default::default()
+        }
+    }
+
+    fn audit_step<'doc>(&self, step: &Step<'doc>) -> Result<Vec<Finding<'doc>>> {
+        self.process_uses(
+            step.workflow(),
+            step.uses(),
+            step.location().with_keys(&["uses".into()]),
+        )
+    }
+
+    fn audit_composite_step<'doc>(&self, step: &CompositeStep<'doc>) -> Result<Vec<Finding<'doc>>> {
+        self.process_uses(
+            step.action(),
+            step.uses(),
+            step.location().with_keys(&["uses".into()]),
+        )
+    }
+
+    fn audit_reusable_job<'doc>(
+        &self,
+        job: &ReusableWorkflowCallJob<'doc>,
+    ) -> Result<Vec<Finding<'doc>>> {
+        self.process_uses(
+            job.parent(),
+            Some(&job.uses),
+            job.location().with_keys(&["uses".into()]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VersionComment;
+
+    #[test]
+    fn test_version_comment_parse() {
+        for (comment, expected) in [
+            ("# v4.1.7", Some(VersionComment::Version("v4.1.7"))),
+            ("# v4", Some(VersionComment::Version("v4"))),
+            ("#4.1.7", Some(VersionComment::Version("4.1.7"))),
+            ("# tag=v4.1.7", Some(VersionComment::Version("v4.1.7"))),
+            (
+                "# v1.2.3-rc.1 (pinned)",
+                Some(VersionComment::Version("v1.2.3-rc.1")),
+            ),
+            ("# v4.1.", Some(VersionComment::Malformed("v4.1."))),
+            ("# V4", Some(VersionComment::Malformed("V4"))),
+            ("# v4..1", Some(VersionComment::Malformed("v4..1"))),
+            ("# v.4", Some(VersionComment::Malformed("v.4"))),
+            ("# zizmor: ignore[unpinned-uses]", None),
+            ("# bump me", None),
+            ("#", None),
+        ] {
+            assert_eq!(VersionComment::parse(comment), expected, "{comment}");
+        }
+    }
+}
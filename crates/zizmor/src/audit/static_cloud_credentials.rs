@@ -0,0 +1,265 @@
+//! Detects steps that configure cloud access from long-lived secrets,
+//! when the action that they use also supports OIDC.
+//!
+//! Long-lived cloud credentials (e.g. AWS access keys or GCP service
+//! account keys) stored as secrets are valid until they're rotated, and
+//! are usable from anywhere once leaked. OIDC-based authentication instead
+//! exchanges a short-lived, workflow-scoped token for cloud credentials,
+//! leaving nothing long-lived to leak.
+
+use github_actions_expressions::Expr;
+use github_actions_models::common::Uses;
+use serde::Deserialize;
+
+use super::{Audit, AuditLoadError, AuditMeta, AuditState, audit_meta};
+use crate::finding::{Confidence, Finding, RouteComponent, Severity};
+use crate::models::uses::RepositoryUsesPattern;
+use crate::models::{CompositeStep, Step, StepBodyCommon, StepCommon};
+use crate::utils::extract_expressions;
+
+/// The well-known cloud credential actions: `(uses, static inputs,
+/// OIDC inputs, recommendation)`.
+const KNOWN_CREDENTIAL_ACTIONS: &[(&str, &[&str], &[&str], &str)] = &[
+    (
+        "aws-actions/configure-aws-credentials",
+        &["aws-secret-access-key"],
+        &["role-to-assume"],
+        "use role-to-assume with OIDC instead",
+    ),
+    (
+        "google-github-actions/auth",
+        &["credentials_json"],
+        &["workload_identity_provider"],
+        "use workload_identity_provider with OIDC instead",
+    ),
+    (
+        "azure/login",
+        &["creds"],
+        &["client-id"],
+        "use client-id, tenant-id, and subscription-id with a federated credential instead",
+    ),
+];
+
+pub(crate) struct StaticCloudCredentials {
+    actions: Vec<CredentialAction>,
+}
+
+audit_meta!(
+    StaticCloudCredentials,
+    "static-cloud-credentials",
+    "long-lived cloud credentials used instead of OIDC",
+    Severity::Medium
+);
+
+/// The raw `static-cloud-credentials` configuration.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct StaticCloudCredentialsConfig {
+    /// Additional (e.g. internal) actions that configure cloud access.
+    #[serde(default)]
+    credential_actions: Vec<CredentialActionConfig>,
+}
+
+/// A user-configured cloud credential action.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct CredentialActionConfig {
+    uses: RepositoryUsesPattern,
+    /// The inputs that take long-lived credentials.
+    static_inputs: Vec<String>,
+    /// The inputs that configure OIDC instead, if the action supports it.
+    #[serde(default)]
+    oidc_inputs: Vec<String>,
+}
+
+/// An action that configures cloud access, and how it's given credentials.
+struct CredentialAction {
+    uses: RepositoryUsesPattern,
+    static_inputs: Vec<String>,
+    oidc_inputs: Vec<String>,
+    recommendation: String,
+}
+
+impl From<CredentialActionConfig> for CredentialAction {
+    fn from(config: CredentialActionConfig) -> Self {
+        let recommendation = match config.oidc_inputs.as_slice() {
+            [] => "use OIDC instead".into(),
+            inputs => format!(
+                "use {inputs} with OIDC instead",
+                inputs = inputs.join(" or ")
+            ),
+        };
+
+        Self {
+            uses: config.uses,
+            static_inputs: config.static_inputs,
+            oidc_inputs: config.oidc_inputs,
+            recommendation,
+        }
+    }
+}
+
+impl StaticCloudCredentials {
+    /// Returns the `secrets.*` context that `value` takes its value from,
+    /// if any.
+    fn secret_in(value: &str) -> Option<String> {
+        extract_expressions(value)
+            .into_iter()
+            .find_map(|(expr, _)| {
+                let parsed = Expr::parse(expr.as_bare()).ok()?;
+                parsed
+                    .dataflow_contexts()
+                    .into_iter()
+                    .find(|ctx| ctx.child_of("secrets"))
+                    .map(|ctx| ctx.as_str().to_string())
+            })
+    }
+
+    fn process_step<'doc>(
+        &self,
+        step: &impl StepCommon<'doc>,
+    ) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        let StepBodyCommon::Uses {
+            uses: Uses::Repository(uses),
+            with,
+        } = step.body()
+        else {
+            return Ok(findings);
+        };
+
+        let Some(action) = self.actions.iter().find(|action| action.uses.matches(uses)) else {
+            return Ok(findings);
+        };
+
+        // A step that configures OIDC isn't relying on the static
+        // credentials, even if it also passes them.
+        if action
+            .oidc_inputs
+            .iter()
+            .any(|input| with.get(input).is_some_and(|v| !v.to_string().is_empty()))
+        {
+            return Ok(findings);
+        }
+
+        for input in &action.static_inputs {
+            let Some(secret) = with
+                .get(input)
+                .and_then(|v| Self::secret_in(&v.to_string()))
+            else {
+                continue;
+            };
+
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Medium)
+                    .confidence(Confidence::High)
+                    .add_location(
+                        step.location()
+                            .with_keys(&["uses".into()])
+                            .annotated("this step configures cloud access"),
+                    )
+                    .add_location(
+                        step.location()
+                            .primary()
+                            .with_keys(&["with".into(), RouteComponent::Key(input.clone().into())])
+                            .annotated(format!(
+                                "long-lived credential from {secret}; {recommendation}",
+                                recommendation = action.recommendation
+                            )),
+                    )
+                    .build(step)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
+
+impl Audit for StaticCloudCredentials {
+    fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        let config = state
+            .config
+            .rule_config::<StaticCloudCredentialsConfig>(Self::ident())
+            .map_err(AuditLoadError::Fail)?
+            .unwrap_or_default();
+
+        // NOTE: User-configured actions come first, so that they can
+        // override the well-known ones.
+        let mut actions = config
+            .credential_actions
+            .into_iter()
+            .map(CredentialAction::from)
+            .collect::<Vec<_>>();
+
+        for (uses, static_inputs, oidc_inputs, recommendation) in KNOWN_CREDENTIAL_ACTIONS {
+            actions.push(CredentialAction {
+                // NOTE: Safe unwrap, since these are all valid patterns.
+                uses: uses.parse().unwrap(),
+                static_inputs: static_inputs.iter().map(|&i| i.into()).collect(),
+                oidc_inputs: oidc_inputs.iter().map(|&i| i.into()).collect(),
+                recommendation: (*recommendation).into(),
+            });
+        }
+
+        Ok(Self { actions })
+    }
+
+    fn meta() -> AuditMeta {
+        AuditMeta {
+            config_keys: &["credential-actions"],
+            ..Default::default()
+        }
+    }
+
+    fn audit_step<'doc>(&self, step: &Step<'doc>) -> anyhow::Result<Vec<Finding<'doc>>> {
+        self.process_step(step)
+    }
+
+    fn audit_composite_step<'doc>(
+        &self,
+        step: &CompositeStep<'doc>,
+    ) -> anyhow::Result<Vec<Finding<'doc>>> {
+        self.process_step(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KNOWN_CREDENTIAL_ACTIONS, StaticCloudCredentials};
+    use crate::models::uses::RepositoryUsesPattern;
+
+    #[test]
+    fn test_known_patterns() {
+        for (uses, ..) in KNOWN_CREDENTIAL_ACTIONS {
+            assert!(uses.parse::<RepositoryUsesPattern>().is_ok(), "{uses}");
+        }
+    }
+
+    #[test]
+    fn test_secret_in() {
+        for (value, secret) in [
+            (
+                "${{ secrets.AWS_SECRET_ACCESS_KEY }}",
+                Some("secrets.AWS_SECRET_ACCESS_KEY"),
+            ),
+            ("${{ secrets['GCP_KEY'] }}", Some("secrets['GCP_KEY']")),
+            (
+                "${{ format('{0}', secrets.AZURE_CREDENTIALS) }}",
+                Some("secrets.AZURE_CREDENTIALS"),
+            ),
+            ("${{ steps.vault.outputs.key }}", None),
+            ("not-a-secret", None),
+        ] {
+            assert_eq!(
+                StaticCloudCredentials::secret_in(value).as_deref(),
+                secret,
+                "{value}"
+            );
+        }
+    }
+}
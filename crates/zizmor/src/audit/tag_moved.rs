@@ -0,0 +1,165 @@
+//! Detects actions pinned to a tag that has moved since the lockfile
+//! (see `zizmor lock`) was written.
+//!
+//! A tag that now resolves to a different commit than the locked one was
+//! re-tagged, which is exactly what pinning to a commit protects against.
+//! This audit only runs with a lockfile, i.e. with `--lockfile`.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::{Result, anyhow};
+use github_actions_models::common::{RepositoryUses, Uses};
+
+use super::{Audit, AuditLoadError, AuditMeta, Network, audit_meta};
+use crate::finding::{Confidence, Finding, Severity, SymbolicLocation};
+use crate::github_api::{self, RateLimited};
+use crate::lock::Lockfile;
+use crate::models::{
+    AsDocument, CompositeStep, JobExt as _, ReusableWorkflowCallJob, Step, StepCommon,
+    uses::RepositoryUsesExt as _,
+};
+use crate::state::AuditState;
+
+pub(crate) struct TagMoved {
+    client: github_api::Client,
+    lockfile: Lockfile,
+    /// The commit that each `owner/repo@tag` currently resolves to, or
+    /// `None` if it isn't a tag or couldn't be resolved.
+    resolved: Mutex<HashMap<(String, String, String), Option<String>>>,
+}
+
+audit_meta!(
+    TagMoved,
+    "tag-moved",
+    "tag moved since it was locked",
+    Severity::Medium
+);
+
+impl TagMoved {
+    /// Returns the commit that `uses`'s tag currently resolves to, or
+    /// `None` if its ref isn't a tag or couldn't be resolved.
+    fn current_sha(&self, uses: &RepositoryUses, tag: &str) -> Result<Option<String>> {
+        let key = (uses.owner.clone(), uses.repo.clone(), tag.to_string());
+        if let Some(sha) = self.resolved.lock().unwrap().get(&key) {
+            return Ok(sha.clone());
+        }
+
+        // NOTE: Branches are resolved before tags, and are expected to
+        // move, so refs that aren't tags are never checked.
+        let sha = match self.client.has_tag(&uses.owner, &uses.repo, tag) {
+            Ok(true) => match self.client.commit_for_ref(&uses.owner, &uses.repo, tag) {
+                Ok(sha) => sha,
+                Err(e) => return self.handle_error(e),
+            },
+            Ok(false) => None,
+            Err(e) => return self.handle_error(e),
+        };
+
+        self.resolved.lock().unwrap().insert(key, sha.clone());
+        Ok(sha)
+    }
+
+    /// Skips the tag if `e` is a rate limit, and propagates it otherwise.
+    fn handle_error(&self, e: anyhow::Error) -> Result<Option<String>> {
+        RateLimited::skip(Err(e), "moved tag checks")
+    }
+
+    fn process_uses<'a, 'doc>(
+        &self,
+        doc: &'a impl AsDocument<'a, 'doc>,
+        uses: Option<&Uses>,
+        location: SymbolicLocation<'doc>,
+    ) -> Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        let Some(Uses::Repository(uses)) = uses else {
+            return Ok(findings);
+        };
+        let Some(tag) = uses.symbolic_ref() else {
+            return Ok(findings);
+        };
+
+        let name = match &uses.subpath {
+            Some(subpath) => format!("{}/{}/{subpath}", uses.owner, uses.repo),
+            None => format!("{}/{}", uses.owner, uses.repo),
+        };
+
+        // Refs that aren't in the lockfile have nothing to compare against.
+        let Some(locked) = self.lockfile.locked_sha(&name, tag) else {
+            return Ok(findings);
+        };
+
+        let Some(current) = self.current_sha(uses, tag)? else {
+            return Ok(findings);
+        };
+
+        if !current.eq_ignore_ascii_case(locked) {
+            findings.push(
+                Self::finding()
+                    .confidence(Confidence::High)
+                    .severity(Severity::Medium)
+                    .add_location(location.primary().annotated(format!(
+                        "{tag} now points to {current}, but was locked to {locked}"
+                    )))
+                    .build(doc)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
+
+impl Audit for TagMoved {
+    fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        let client = state.online_client()?;
+
+        let Some(path) = &state.lockfile else {
+            return Err(AuditLoadError::Skip(anyhow!("no lockfile given")));
+        };
+        let lockfile = Lockfile::load(path).map_err(AuditLoadError::Fail)?;
+
+        Ok(Self {
+            client,
+            lockfile,
+            resolved: Default::default(),
+        })
+    }
+
+    fn meta() -> AuditMeta {
+        AuditMeta {
+            network: Network::Online,
+            ..Default::default()
+        }
+    }
+
+    fn audit_step<'doc>(&self, step: &Step<'doc>) -> Result<Vec<Finding<'doc>>> {
+        self.process_uses(
+            step.workflow(),
+            step.uses(),
+            step.location().with_keys(&["uses".into()]),
+        )
+    }
+
+    fn audit_composite_step<'doc>(&self, step: &CompositeStep<'doc>) -> Result<Vec<Finding<'doc>>> {
+        self.process_uses(
+            step.action(),
+            step.uses(),
+            step.location().with_keys(&["uses".into()]),
+        )
+    }
+
+    fn audit_reusable_job<'doc>(
+        &self,
+        job: &ReusableWorkflowCallJob<'doc>,
+    ) -> Result<Vec<Finding<'doc>>> {
+        self.process_uses(
+            job.parent(),
+            Some(&job.uses),
+            job.location().with_keys(&["uses".into()]),
+        )
+    }
+}
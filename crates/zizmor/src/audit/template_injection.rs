@@ -5,7 +5,10 @@
 //!
 //! The following steps are currently supported:
 //! * `run:`, indicating template expansion into a shell script or similar
-//! * `actions/github-script`, indicating template expansion into a JavaScript function
+//! * `azure/powershell` and `azure/cli`, indicating template expansion into
+//!   an inline script
+//!
+//! `actions/github-script` is handled separately, by `github-script-injection`.
 //!
 //! A small amount of additional processing is done to remove template
 //! expressions that an attacker can't control.
@@ -20,7 +23,7 @@ use github_actions_models::{
 
 use super::{Audit, AuditLoadError, audit_meta};
 use crate::{
-    finding::{Confidence, Finding, Persona, Severity, SymbolicLocation},
+    finding::{Confidence, Finding, Persona, Severity, Subfeature, SymbolicLocation},
     models::{self, CompositeStep, Step, StepCommon, uses::RepositoryUsesExt as _},
     state::AuditState,
     utils::extract_expressions,
@@ -31,7 +34,8 @@ pub(crate) struct TemplateInjection;
 audit_meta!(
     TemplateInjection,
     "template-injection",
-    "code injection via template expansion"
+    "code injection via template expansion",
+    Severity::High
 );
 
 /// Context patterns that are believed to be always safe.
@@ -93,6 +97,15 @@ static SAFE_CONTEXT_PATTERNS: LazyLock<Vec<ContextPattern>> = LazyLock::new(|| {
     .collect()
 });
 
+/// Context patterns that are attacker-controllable, but constrained to
+/// GitHub usernames.
+static ACTOR_CONTEXT_PATTERNS: LazyLock<Vec<ContextPattern>> = LazyLock::new(|| {
+    ["github.actor", "github.triggering_actor"]
+        .iter()
+        .map(|s| ContextPattern::new(s).unwrap())
+        .collect()
+});
+
 impl TemplateInjection {
     fn script_with_location<'s>(
         step: &impl StepCommon<'s>,
@@ -102,14 +115,7 @@ impl TemplateInjection {
                 uses: Uses::Repository(uses),
                 with,
             } => {
-                if uses.matches("actions/github-script") {
-                    with.get("script").map(|script| {
-                        (
-                            script.to_string(),
-                            step.location().with_keys(&["with".into(), "script".into()]),
-                        )
-                    })
-                } else if uses.matches("azure/powershell") || uses.matches("azure/cli") {
+                if uses.matches("azure/powershell") || uses.matches("azure/cli") {
                     // Both `azure/powershell` and `azure/cli` uses the same `inlineScript`
                     // option to feed arbitrary code.
 
@@ -175,13 +181,22 @@ impl TemplateInjection {
         }
     }
 
-    fn injectable_template_expressions<'s>(
-        &self,
+    /// Returns every template expression in `run` (a script for `step`)
+    /// that may be attacker-controllable, along with how it should
+    /// be reported.
+    pub(crate) fn injectable_template_expressions<'s>(
         run: &str,
         step: &impl StepCommon<'s>,
-    ) -> Vec<(String, Severity, Confidence, Persona)> {
+    ) -> Vec<(String, Subfeature, Severity, Confidence, Persona)> {
         let mut bad_expressions = vec![];
-        for (expr, _) in extract_expressions(run) {
+        for (expr, span) in extract_expressions(run) {
+            // Each finding points at the specific expression within the
+            // script, which may occur more than once.
+            let subfeature = Subfeature::new(
+                run[..span.start].matches(expr.as_raw()).count(),
+                expr.as_raw(),
+            );
+
             let Ok(parsed) = Expr::parse(expr.as_bare()) else {
                 tracing::warn!("couldn't parse expression: {expr}", expr = expr.as_bare());
                 continue;
@@ -192,6 +207,7 @@ impl TemplateInjection {
                 // all template injections are code smells, even if unexploitable.
                 bad_expressions.push((
                     expr.as_raw().into(),
+                    subfeature,
                     Severity::Unknown,
                     Confidence::Unknown,
                     Persona::Pedantic,
@@ -212,6 +228,7 @@ impl TemplateInjection {
                     // from innocuous types, e.g. booleans.
                     bad_expressions.push((
                         context.as_str().into(),
+                        subfeature.clone(),
                         Severity::High,
                         Confidence::Low,
                         Persona::default(),
@@ -222,16 +239,32 @@ impl TemplateInjection {
                     if !env_is_static {
                         bad_expressions.push((
                             context.as_str().into(),
+                            subfeature.clone(),
                             Severity::Low,
                             Confidence::High,
                             Persona::default(),
                         ));
                     }
+                } else if ACTOR_CONTEXT_PATTERNS
+                    .iter()
+                    .any(|pat| pat.matches(context))
+                {
+                    // Actor names are attacker-selected, but GitHub restricts
+                    // usernames to alphanumerics and hyphens, which leaves
+                    // little room for injection.
+                    bad_expressions.push((
+                        context.as_str().into(),
+                        subfeature.clone(),
+                        Severity::Medium,
+                        Confidence::High,
+                        Persona::Pedantic,
+                    ));
                 } else if context.child_of("github") {
                     // TODO: Filter these more finely; not everything in the event
                     // context is actually attacker-controllable.
                     bad_expressions.push((
                         context.as_str().into(),
+                        subfeature.clone(),
                         Severity::High,
                         Confidence::High,
                         Persona::default(),
@@ -253,6 +286,7 @@ impl TemplateInjection {
                         if !matrix_is_static {
                             bad_expressions.push((
                                 context.as_str().into(),
+                                subfeature.clone(),
                                 Severity::Medium,
                                 Confidence::Medium,
                                 Persona::default(),
@@ -265,6 +299,7 @@ impl TemplateInjection {
                     // but may be in obscure cases.
                     bad_expressions.push((
                         context.as_str().into(),
+                        subfeature.clone(),
                         Severity::Informational,
                         Confidence::Low,
                         Persona::default(),
@@ -286,8 +321,8 @@ impl TemplateInjection {
             return Ok(findings);
         };
 
-        for (expr, severity, confidence, persona) in
-            self.injectable_template_expressions(&script, step)
+        for (expr, subfeature, severity, confidence, persona) in
+            Self::injectable_template_expressions(&script, step)
         {
             findings.push(
                 Self::finding()
@@ -297,9 +332,13 @@ impl TemplateInjection {
                     .add_location(step.location().hidden())
                     .add_location(step.location_with_name())
                     .add_location(
-                        script_loc.clone().primary().annotated(format!(
-                            "{expr} may expand into attacker-controllable code"
-                        )),
+                        script_loc
+                            .clone()
+                            .with_subfeature(subfeature)
+                            .primary()
+                            .annotated(format!(
+                                "{expr} may expand into attacker-controllable code"
+                            )),
                     )
                     .build(step)?,
             )
@@ -0,0 +1,407 @@
+//! Detects `uses:` clauses whose `owner/repo` is a near miss of a popular
+//! (or otherwise protected) action, e.g. `actons/checkout` instead of
+//! `actions/checkout`.
+//!
+//! Anybody can register a misspelled user or organization name, so a typo
+//! in a `uses:` clause can hand control of a workflow step to an attacker.
+
+use anyhow::{Context as _, anyhow};
+use github_actions_models::common::Uses;
+use serde::Deserialize;
+
+use super::unpinned_uses::AllowedOrgs;
+use super::{Audit, AuditLoadError, AuditMeta, AuditState, audit_meta};
+use crate::finding::{Confidence, Finding, Severity};
+use crate::models::{CompositeStep, Step, StepCommon};
+
+/// Popular actions that are likely typosquatting targets.
+const POPULAR_ACTIONS: &[&str] = &[
+    "actions/attest-build-provenance",
+    "actions/cache",
+    "actions/checkout",
+    "actions/configure-pages",
+    "actions/create-github-app-token",
+    "actions/dependency-review-action",
+    "actions/deploy-pages",
+    "actions/download-artifact",
+    "actions/github-script",
+    "actions/labeler",
+    "actions/setup-dotnet",
+    "actions/setup-go",
+    "actions/setup-java",
+    "actions/setup-node",
+    "actions/setup-python",
+    "actions/stale",
+    "actions/upload-artifact",
+    "actions/upload-pages-artifact",
+    "astral-sh/setup-uv",
+    "aws-actions/configure-aws-credentials",
+    "azure/login",
+    "codecov/codecov-action",
+    "docker/build-push-action",
+    "docker/login-action",
+    "docker/metadata-action",
+    "docker/setup-buildx-action",
+    "docker/setup-qemu-action",
+    "dtolnay/rust-toolchain",
+    "github/codeql-action",
+    "golangci/golangci-lint-action",
+    "google-github-actions/auth",
+    "google-github-actions/setup-gcloud",
+    "goreleaser/goreleaser-action",
+    "gradle/actions",
+    "hashicorp/setup-terraform",
+    "peaceiris/actions-gh-pages",
+    "peter-evans/create-pull-request",
+    "pnpm/action-setup",
+    "pypa/gh-action-pypi-publish",
+    "ruby/setup-ruby",
+    "shivammathur/setup-php",
+    "softprops/action-gh-release",
+    "stefanzweifel/git-auto-commit-action",
+    "swatinem/rust-cache",
+    "tj-actions/changed-files",
+];
+
+/// The maximum edit distance, relative to the length of the longer name,
+/// for two names to be considered a near miss.
+const MAX_NORMALIZED_DISTANCE: f64 = 0.1;
+
+/// The raw `typosquatting` configuration.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct TyposquattingConfig {
+    /// Additional `owner/repo` names to protect, e.g. internal actions.
+    #[serde(default)]
+    protected_actions: Vec<String>,
+}
+
+/// A protected action name, in both its raw and canonical forms.
+struct Protected {
+    owner: String,
+    name: String,
+    canonical: String,
+}
+
+impl Protected {
+    fn new(name: &str) -> anyhow::Result<Self> {
+        let name = name.to_lowercase();
+        let Some((owner, repo)) = name.split_once('/') else {
+            return Err(anyhow!(
+                "invalid protected action: {name} (expected owner/repo)"
+            ));
+        };
+
+        if owner.is_empty() || repo.is_empty() || repo.contains('/') {
+            return Err(anyhow!(
+                "invalid protected action: {name} (expected owner/repo)"
+            ));
+        }
+
+        Ok(Self {
+            owner: owner.into(),
+            canonical: canonicalize(&name),
+            name,
+        })
+    }
+}
+
+/// Canonicalize an `owner/repo` name, erasing the differences that are
+/// common in typosquats: case, separators (`-`, `_`, `.`), and characters
+/// that look alike (e.g. `0` and `o`, `1` and `l`, `rn` and `m`).
+fn canonicalize(name: &str) -> String {
+    let name = name.to_lowercase().replace("rn", "m").replace("vv", "w");
+
+    name.chars()
+        .filter(|c| !matches!(c, '-' | '_' | '.'))
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | 'i' => 'l',
+            '3' => 'e',
+            '4' => 'a',
+            '5' => 's',
+            '7' => 't',
+            c => c,
+        })
+        .collect()
+}
+
+/// The optimal string alignment distance between `a` and `b`, i.e. the
+/// Levenshtein distance with adjacent transpositions as a single edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+
+    let mut dist = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in dist.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in dist[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            dist[i][j] = (dist[i - 1][j] + 1)
+                .min(dist[i][j - 1] + 1)
+                .min(dist[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                dist[i][j] = dist[i][j].min(dist[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    dist[a.len()][b.len()]
+}
+
+pub(crate) struct Typosquatting {
+    protected: Vec<Protected>,
+    allowed_orgs: AllowedOrgs,
+}
+
+audit_meta!(
+    Typosquatting,
+    "typosquatting",
+    "possibly typosquatted action",
+    Severity::High
+);
+
+impl Typosquatting {
+    /// Returns the protected action that `owner/repo` is most likely a
+    /// typo of, if any, along with the edit distance between their
+    /// canonical forms.
+    fn intended_action(&self, owner: &str, repo: &str) -> Option<(usize, &Protected)> {
+        let owner = owner.to_lowercase();
+        let name = format!("{owner}/{repo}").to_lowercase();
+
+        if self.protected.iter().any(|p| p.name == name) {
+            return None;
+        }
+
+        let canonical = canonicalize(&name);
+
+        self.protected
+            .iter()
+            // An owner can't typosquat its own actions.
+            .filter(|p| p.owner != owner)
+            .filter_map(|p| {
+                let distance = edit_distance(&canonical, &p.canonical);
+                let longest = canonical.chars().count().max(p.canonical.chars().count());
+
+                (distance as f64 / longest as f64 <= MAX_NORMALIZED_DISTANCE)
+                    .then_some((distance, p))
+            })
+            .min_by_key(|(distance, _)| *distance)
+    }
+
+    /// How confident we are that a name at `distance` edits from a
+    /// protected action's is a typosquat of it.
+    ///
+    /// A name that's identical once canonicalized differs only in
+    /// separators or look-alike characters, which is rarely an accident;
+    /// each further edit makes an unrelated name more likely.
+    fn confidence(distance: usize) -> Confidence {
+        match distance {
+            0 => Confidence::High,
+            1 => Confidence::Medium,
+            _ => Confidence::Low,
+        }
+    }
+
+    fn process_step<'doc>(
+        &self,
+        step: &impl StepCommon<'doc>,
+    ) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        let Some(Uses::Repository(uses)) = step.uses() else {
+            return Ok(findings);
+        };
+
+        if self.allowed_orgs.contains(&uses.owner, &uses.repo) {
+            return Ok(findings);
+        }
+
+        if let Some((distance, intended)) = self.intended_action(&uses.owner, &uses.repo) {
+            findings.push(
+                Self::finding()
+                    .confidence(Self::confidence(distance))
+                    .severity(Severity::High)
+                    .add_location(
+                        step.location()
+                            .primary()
+                            .with_keys(&["uses".into()])
+                            .annotated(format!(
+                                "`{owner}/{repo}` looks like a typo of `{intended}`",
+                                owner = uses.owner,
+                                repo = uses.repo,
+                                intended = intended.name,
+                            )),
+                    )
+                    .build(step)?,
+            );
+        }
+
+        Ok(findings)
+    }
+}
+
+impl Audit for Typosquatting {
+    fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError>
+    where
+        Self: Sized,
+    {
+        let config = state
+            .config
+            .rule_config::<TyposquattingConfig>(Self::ident())
+            .map_err(AuditLoadError::Fail)?;
+
+        let protected = POPULAR_ACTIONS
+            .iter()
+            .copied()
+            .chain(
+                config
+                    .iter()
+                    .flat_map(|config| config.protected_actions.iter().map(String::as_str)),
+            )
+            .map(Protected::new)
+            .collect::<anyhow::Result<_>>()
+            .with_context(|| state.config.rule_error_context(Self::ident()))
+            .map_err(AuditLoadError::Fail)?;
+
+        let allowed_orgs = AllowedOrgs::from_state(state).map_err(AuditLoadError::Fail)?;
+
+        Ok(Self {
+            protected,
+            allowed_orgs,
+        })
+    }
+
+    fn meta() -> AuditMeta {
+        AuditMeta {
+            config_keys: &["protected-actions"],
+            ..Default::default()
+        }
+    }
+
+    fn audit_step<'doc>(&self, step: &Step<'doc>) -> anyhow::Result<Vec<Finding<'doc>>> {
+        self.process_step(step)
+    }
+
+    fn audit_composite_step<'a>(
+        &self,
+        step: &CompositeStep<'a>,
+    ) -> anyhow::Result<Vec<Finding<'a>>> {
+        self.process_step(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        for (a, b, expected) in [
+            ("", "", 0),
+            ("checkout", "checkout", 0),
+            ("checkout", "chekout", 1),
+            ("checkout", "chekcout", 1),
+            ("checkout", "checkouts", 1),
+            ("checkout", "cache", 6),
+            ("", "cache", 5),
+        ] {
+            assert_eq!(edit_distance(a, b), expected, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        for (a, b) in [
+            ("actions/checkout", "Actions/Checkout"),
+            ("actions/setup-node", "actions/setup_node"),
+            ("actions/setup-node", "actions/setupnode"),
+            ("actions/checkout", "acti0ns/checkout"),
+            ("actions/checkout", "actlons/checkout"),
+            ("docker/metadata-action", "docker/rnetadata-action"),
+        ] {
+            assert_eq!(canonicalize(a), canonicalize(b), "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn test_popular_actions_dont_collide() {
+        // No popular action should look like a typo of another one.
+        let popular = POPULAR_ACTIONS
+            .iter()
+            .map(|name| Protected::new(name).unwrap())
+            .collect::<Vec<_>>();
+
+        for a in &popular {
+            for b in &popular {
+                if a.owner == b.owner {
+                    continue;
+                }
+
+                let distance = edit_distance(&a.canonical, &b.canonical);
+                let longest = a.canonical.len().max(b.canonical.len());
+                assert!(
+                    distance as f64 / longest as f64 > MAX_NORMALIZED_DISTANCE,
+                    "{a} collides with {b}",
+                    a = a.name,
+                    b = b.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_confidence() {
+        let config = crate::config::Config::default();
+        let state = AuditState {
+            config: &config,
+            online: false,
+            cache_dir: "/tmp/zizmor".into(),
+            gh_token: None,
+            gh_hostname: crate::github_api::GitHubHost::Standard("github.com".into()),
+            tpa_allowlist_file: None,
+            tpa_allowed_org: None,
+            lenient_allowlist: false,
+            advisories_file: None,
+            osv: false,
+            lockfile: None,
+            resolver: Default::default(),
+        };
+        let audit = Typosquatting::new(&state).unwrap();
+
+        for (owner, repo, expected) in [
+            // Only look-alike characters or separators differ.
+            ("acti0ns", "checkout", Confidence::High),
+            ("acti0ns", "setup_node", Confidence::High),
+            // A single edit.
+            ("actons", "checkout", Confidence::Medium),
+            ("actoins", "checkout", Confidence::Medium),
+            // Two edits.
+            ("actons", "uplod-artifact", Confidence::Low),
+        ] {
+            let (distance, intended) = audit.intended_action(owner, repo).unwrap();
+            assert!(intended.owner == "actions", "{owner}/{repo}");
+            assert_eq!(
+                Typosquatting::confidence(distance),
+                expected,
+                "{owner}/{repo}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_protected_new() {
+        assert!(Protected::new("example/action").is_ok());
+        assert!(Protected::new("example").is_err());
+        assert!(Protected::new("example/").is_err());
+        assert!(Protected::new("/action").is_err());
+        assert!(Protected::new("example/action/subpath").is_err());
+    }
+}
@@ -35,7 +35,8 @@ impl UnpinnedImages {
 audit_meta!(
     UnpinnedImages,
     "unpinned-images",
-    "unpinned image references"
+    "unpinned image references",
+    Severity::High
 );
 
 impl Audit for UnpinnedImages {
@@ -1,32 +1,738 @@
-use std::collections::{HashMap, HashSet};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
 use anyhow::Context;
+use github_actions_models::action;
 use github_actions_models::common::{RepositoryUses, Uses};
+use globset::{Glob, GlobMatcher};
+use itertools::Itertools as _;
+use regex::Regex;
 use serde::Deserialize;
 
-use super::{Audit, AuditLoadError, AuditState, audit_meta};
-use crate::finding::{Confidence, Finding, Persona, Severity};
+use super::{Audit, AuditInput, AuditLoadError, AuditMeta, AuditState, audit_meta};
+use crate::config::Config;
+use crate::finding::{
+    Confidence, ExternalLocation, Feature, Finding, FindingTag, Location, Persona, Point,
+    Remediation, RouteComponent, Severity,
+};
+use crate::fix;
 use crate::models::uses::RepositoryUsesPattern;
-use crate::models::{CompositeStep, Step, StepCommon, uses::UsesExt as _};
+use crate::models::{
+    AsDocument as _, CompositeStep, Job, Step, StepCommon,
+    uses::{DockerUsesExt as _, UsesExt as _, pinned_input_defaults},
+};
+use crate::registry::InputKey;
+use crate::resolver::UsesResolver;
 
 pub(crate) struct UnpinnedUses {
     policies: UnpinnedUsesPolicies,
+    /// Path-scoped policies, in configuration order.
+    overrides: Vec<PolicyOverride>,
     /// Combined set of official orgs and additional allowlisted orgs
-    allowed_orgs: HashSet<String>,
+    allowed_orgs: AllowedOrgs,
+    third_party_handling: ThirdPartyHandling,
+    /// Whether to report allowlist entries that no audited input uses.
+    report_unused_allowlist: bool,
+    /// Whether to check (online) whether owners have been renamed.
+    detect_owner_redirects: bool,
+    resolver: Arc<UsesResolver>,
 }
 
-audit_meta!(UnpinnedUses, "unpinned-uses", "unpinned action reference");
+/// A `uses:` clause's violation of the pinning policies.
+pub(crate) struct PinningViolation {
+    /// The finding's annotation.
+    pub(crate) annotation: String,
+    pub(crate) severity: Severity,
+    pub(crate) persona: Persona,
+    /// Where the violated policy came from, if applicable.
+    pub(crate) policy: Option<ExternalLocation>,
+    /// Whether the clause refers to a third-party action, i.e. one
+    /// outside of the allowlisted orgs.
+    pub(crate) third_party: bool,
+}
+
+impl PinningViolation {
+    fn new(annotation: impl Into<String>, severity: Severity, persona: Persona) -> Self {
+        Self {
+            annotation: annotation.into(),
+            severity,
+            persona,
+            policy: None,
+            third_party: false,
+        }
+    }
+
+    fn with_policy(mut self, policy: ExternalLocation) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    fn third_party(mut self, third_party: bool) -> Self {
+        self.third_party = third_party;
+        self
+    }
+}
+
+audit_meta!(
+    UnpinnedUses,
+    "unpinned-uses",
+    "unpinned action reference",
+    Severity::Medium
+);
 
-// Define a constant for the special message we'll look for in the TPA list formatter
+/// The annotation on strict-mode findings for unpinned third-party actions.
+///
+/// This is kept as-is for JSON consumers that match on it, but is otherwise
+/// free-form: consumers within zizmor select these findings by their
+/// [`FindingTag::ThirdPartyUnpinned`] tag instead.
 pub(crate) const THIRD_PARTY_MESSAGE: &str = "third-party action is not pinned to a commit SHA";
 
 // Default official GitHub organizations that are considered trusted
-const DEFAULT_OFFICIAL_ORGS: &[&str] = &["actions", "github", "dependabot"];
+pub(crate) const DEFAULT_OFFICIAL_ORGS: &[&str] = &["actions", "github", "dependabot"];
+
+/// Image tags that are always floating, regardless of `floating-tags`.
+const LATEST_TAG: &str = "latest";
+
+/// The default `floating-tags`, i.e. image tags (besides `latest`) that
+/// are conventionally moved to newer images.
+const DEFAULT_FLOATING_TAGS: &[&str] = &["main", "edge", "nightly"];
+
+/// The set of organizations whose actions are considered first-party,
+/// i.e. exempt from the third-party pinning requirement.
+///
+/// This is shared between the `unpinned-uses` audit and the TPA output
+/// formats, so that both classify actions identically. For the same
+/// reason, it also holds the image tags that are considered floating.
+#[derive(Clone, Debug)]
+pub(crate) struct AllowedOrgs {
+    /// Identifies this allowlist (and its clones) for [`UsesResolver`]'s
+    /// memoized third-party determinations.
+    id: usize,
+    orgs: HashMap<String, OrgTrust>,
+    /// The allowlist files' `re:` entries, in the order they're listed.
+    patterns: Vec<AllowedPattern>,
+    /// Every entry that isn't a default official org, and where it's
+    /// listed, for `report-unused-allowlist`.
+    entries: Vec<AllowlistEntry>,
+    /// Renamed owners (normalized), mapped to their canonical names.
+    aliases: HashMap<String, String>,
+    /// Image tags (besides `latest`) that are considered floating.
+    floating_tags: Vec<String>,
+}
+
+/// A user-provided allowlist entry.
+#[derive(Clone, Debug)]
+struct AllowlistEntry {
+    /// The entry's lowercased org, or its `re:` pattern.
+    name: String,
+    /// Where the entry is listed.
+    location: ExternalLocation,
+}
+
+impl AllowlistEntry {
+    fn new(name: &str, annotation: &str) -> Self {
+        Self {
+            name: name.into(),
+            location: ExternalLocation::new(format!("`{name}` {annotation}")),
+        }
+    }
+}
+
+/// A `re:` allowlist entry, e.g. `re:^acme-(team|squad)-[a-z]+$`.
+#[derive(Clone, Debug)]
+struct AllowedPattern {
+    /// The entry's pattern, anchored at both ends.
+    regex: Regex,
+    trust: OrgTrust,
+    /// Whether the pattern contains a `/`, i.e. is matched against
+    /// `owner/repo` rather than just the owner.
+    qualified: bool,
+    /// The entry's `re:` pattern, as in [`AllowlistEntry::name`].
+    name: String,
+    /// The entry itself and where it's listed, for logging.
+    source: String,
+}
+
+/// How far an allowlisted organization is trusted.
+///
+/// Variants are ordered from least to most strict, so that the strictest
+/// entry wins when an org is allowlisted more than once.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum OrgTrust {
+    /// A bare allowlist entry, e.g. `trustedorg`.
+    Full,
+    /// An allowlist entry qualified with `ref-pin`, e.g. `trustedorg ref-pin`:
+    /// the org's actions needn't be hash-pinned, but must be ref-pinned.
+    RefPin,
+}
+
+/// The next [`AllowedOrgs::id`].
+static NEXT_ALLOWLIST_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Invalid entries that `--lenient-allowlist` has already warned about,
+/// since the allowlist is built by every audit that uses it.
+static WARNED_INVALID: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// The longest GitHub owner name.
+const MAX_OWNER_LEN: usize = 39;
+
+/// The longest GitHub repository name.
+const MAX_REPO_LEN: usize = 100;
+
+/// Checks that an allowlist entry's (lowercased) name is a plausible
+/// GitHub owner, or `owner/repo`, returning the reason if it isn't.
+fn validate_name(name: &str) -> Result<(), &'static str> {
+    let (owner, repo) = match name.split_once('/') {
+        Some((owner, repo)) => (owner, Some(repo)),
+        None => (name, None),
+    };
+
+    if owner.is_empty() || owner.len() > MAX_OWNER_LEN {
+        return Err("owner names must be 1 to 39 characters long");
+    }
+    if !owner.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err("owner names may only contain alphanumerics and hyphens");
+    }
+    if owner.starts_with('-') || owner.ends_with('-') {
+        return Err("owner names can't begin or end with a hyphen");
+    }
+
+    let Some(repo) = repo else {
+        return Ok(());
+    };
+
+    if repo.is_empty() || repo.len() > MAX_REPO_LEN {
+        return Err("repository names must be 1 to 100 characters long");
+    }
+    if !repo
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(
+            "repository names may only contain alphanumerics, hyphens, underscores, and periods",
+        );
+    }
+    if repo == "." || repo == ".." {
+        return Err("`.` and `..` aren't valid repository names");
+    }
+
+    Ok(())
+}
+
+/// The error (or warning) for an invalid allowlist entry, listed at `source`.
+fn invalid_entry(source: &str, entry: &str, reason: &str) -> String {
+    format!("{source}: invalid allowlist entry `{entry}`: {reason}")
+}
+
+impl AllowedOrgs {
+    /// Build the effective allowlist from the default official orgs,
+    /// the CLI (`--tpa-allowlist-file`, `--tpa-allowed-org`), and
+    /// the `unpinned-uses` rule configuration.
+    pub(crate) fn from_state(state: &AuditState<'_>) -> anyhow::Result<Self> {
+        let config = state
+            .config
+            .rule_config::<UnpinnedUsesConfig>(UnpinnedUses::ident())?
+            .unwrap_or_default();
+
+        Self::new(state, &config)
+    }
+
+    /// Just the default official orgs, ignoring every allowlist.
+    pub(crate) fn official() -> Self {
+        Self {
+            id: NEXT_ALLOWLIST_ID.fetch_add(1, Ordering::Relaxed),
+            orgs: DEFAULT_OFFICIAL_ORGS
+                .iter()
+                .map(|s| (s.to_ascii_lowercase(), OrgTrust::Full))
+                .collect(),
+            patterns: vec![],
+            entries: vec![],
+            aliases: HashMap::new(),
+            floating_tags: DEFAULT_FLOATING_TAGS.iter().map(|&t| t.into()).collect(),
+        }
+    }
+
+    /// Every allowlist file (from the CLI or the configuration) that the
+    /// allowlist is built from.
+    pub(crate) fn files(state: &AuditState<'_>) -> Vec<String> {
+        let config = state
+            .config
+            .rule_config::<UnpinnedUsesConfig>(UnpinnedUses::ident())
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+
+        state
+            .tpa_allowlist_file
+            .iter()
+            .cloned()
+            .chain(config.allowlist_file)
+            .collect()
+    }
+
+    /// Checks that every allowlist file (from the CLI or the configuration)
+    /// exists, since [`AllowedOrgs::from_state`] only warns about them.
+    pub(crate) fn check_files(state: &AuditState<'_>) -> anyhow::Result<()> {
+        let missing = |path: &&String| !Path::new(path).is_file();
+
+        if let Some(path) = state.tpa_allowlist_file.as_ref().filter(missing) {
+            anyhow::bail!("--tpa-allowlist-file {path} doesn't exist");
+        }
+
+        let config = state
+            .config
+            .rule_config::<UnpinnedUsesConfig>(UnpinnedUses::ident())?
+            .unwrap_or_default();
+        if let Some(path) = config.allowlist_file.as_ref().filter(missing) {
+            anyhow::bail!(
+                "{context}: allowlist-file {path} doesn't exist",
+                context = state.config.rule_error_context(UnpinnedUses::ident())
+            );
+        }
+
+        Ok(())
+    }
+
+    fn new(state: &AuditState<'_>, config: &UnpinnedUsesConfig) -> anyhow::Result<Self> {
+        // Create the default set of allowed orgs
+        let Self {
+            mut orgs,
+            mut patterns,
+            mut entries,
+            ..
+        } = Self::official();
+        let mut invalid = vec![];
+
+        // Add allowlisted orgs from file if specified via CLI
+        if let Some(allowlist_path) = &state.tpa_allowlist_file {
+            Self::extend_from_file(
+                &mut orgs,
+                &mut patterns,
+                &mut entries,
+                &mut invalid,
+                allowlist_path,
+            )?;
+        }
+
+        // Add explicitly specified orgs from CLI
+        if let Some(additional_orgs) = &state.tpa_allowed_org {
+            for entry in additional_orgs {
+                match Self::insert_entry(&mut orgs, entry) {
+                    Ok(org) => entries.push(AllowlistEntry::new(
+                        &org,
+                        "is allowlisted with --tpa-allowed-org",
+                    )),
+                    Err(reason) => {
+                        invalid.push(invalid_entry("--tpa-allowed-org", entry, &reason));
+                    }
+                }
+            }
+        }
+
+        // Add any additional orgs specified in the config file
+        if let Some(allowlist_path) = &config.allowlist_file {
+            Self::extend_from_file(
+                &mut orgs,
+                &mut patterns,
+                &mut entries,
+                &mut invalid,
+                allowlist_path,
+            )?;
+        }
+
+        // Add any additional orgs specified in the config
+        if let Some(additional_orgs) = &config.additional_allowed_orgs {
+            for (idx, entry) in additional_orgs.iter().enumerate() {
+                let point = || {
+                    state.config.rule_config_point(
+                        UnpinnedUses::ident(),
+                        &["additional-allowed-orgs".into(), idx.into()],
+                    )
+                };
+
+                match Self::insert_entry(&mut orgs, entry) {
+                    Ok(org) => {
+                        let mut entry = AllowlistEntry::new(&org, "is allowlisted");
+                        if let Some(origin) = state.config.origin() {
+                            entry.location = entry.location.with_path(origin, point());
+                        }
+                        entries.push(entry);
+                    }
+                    Err(reason) => {
+                        let source = match (state.config.origin(), point()) {
+                            (Some(origin), Some(point)) => {
+                                format!("{origin}:{line}", line = point.row + 1)
+                            }
+                            _ => format!("additional-allowed-orgs[{idx}]"),
+                        };
+                        invalid.push(invalid_entry(&source, entry, &reason));
+                    }
+                }
+            }
+        }
+
+        if !invalid.is_empty() {
+            if !state.lenient_allowlist {
+                anyhow::bail!("{invalid}", invalid = invalid.join("\n"));
+            }
+
+            let warning = invalid.join("\n  ");
+            // NOTE: A poisoned lock only means that we might warn twice.
+            if WARNED_INVALID
+                .lock()
+                .map_or(true, |mut warned| warned.insert(warning.clone()))
+            {
+                tracing::warn!(
+                    "ignoring {count} invalid allowlist entries:\n  {warning}",
+                    count = invalid.len()
+                );
+            }
+        }
+
+        Ok(Self {
+            id: NEXT_ALLOWLIST_ID.fetch_add(1, Ordering::Relaxed),
+            orgs,
+            patterns,
+            entries,
+            aliases: config
+                .owner_aliases
+                .iter()
+                .map(|(alias, canonical)| (normalize(alias).into_owned(), canonical.clone()))
+                .collect(),
+            floating_tags: config.floating_tags.clone(),
+        })
+    }
+
+    /// Parses an allowlist entry: an org name (or `owner/repo`), optionally
+    /// followed by the `ref-pin` qualifier.
+    ///
+    /// Returns the reason that the entry is invalid, if it is.
+    fn parse_entry(entry: &str) -> Result<(String, OrgTrust), String> {
+        let mut parts = entry.split_whitespace();
+        let org = parts.next().ok_or("expected `org` or `org ref-pin`")?;
+        // NOTE: Only `re:` entries are case-sensitive.
+        let org = if org.starts_with("re:") {
+            org.to_string()
+        } else {
+            let org = org.to_ascii_lowercase();
+            validate_name(&org)?;
+            org
+        };
+
+        let trust = match parts.next() {
+            None => OrgTrust::Full,
+            Some("ref-pin") => OrgTrust::RefPin,
+            Some(qualifier) => return Err(format!("unknown qualifier `{qualifier}`")),
+        };
+
+        if parts.next().is_some() {
+            return Err("expected `org` or `org ref-pin`".into());
+        }
+
+        Ok((org, trust))
+    }
+
+    /// Adds the given allowlist entry to `orgs`, keeping the
+    /// strictest entry for orgs that are listed more than once.
+    ///
+    /// Returns the entry's org, or the reason that it's invalid.
+    fn insert_entry(orgs: &mut HashMap<String, OrgTrust>, entry: &str) -> Result<String, String> {
+        let (org, trust) = Self::parse_entry(entry)?;
+        if org.starts_with("re:") {
+            return Err("`re:` entries are only supported in allowlist files".into());
+        }
+
+        let existing = orgs.entry(org.clone()).or_insert(trust);
+        *existing = (*existing).max(trust);
+        Ok(org)
+    }
+
+    /// Compiles a `re:` allowlist entry's `pattern`, listed at `source`.
+    fn parse_pattern(
+        entry: &str,
+        pattern: &str,
+        trust: OrgTrust,
+        source: &str,
+    ) -> anyhow::Result<AllowedPattern> {
+        let regex = Regex::new(&format!("^(?:{pattern})$"))
+            .with_context(|| format!("{source}: invalid allowlist pattern `{pattern}`"))?;
+
+        Ok(AllowedPattern {
+            regex,
+            trust,
+            qualified: pattern.contains('/'),
+            name: format!("re:{pattern}"),
+            source: format!("{source}: {entry}"),
+        })
+    }
+
+    /// Add every org listed in the allowlist file at `path` to `orgs`,
+    /// every `re:` entry to `patterns`, and every invalid line to `invalid`.
+    ///
+    /// The allowlist format is one org (or `owner/repo`) per line, optionally
+    /// followed by `ref-pin` (e.g. `trustedorg ref-pin`); empty lines and
+    /// lines beginning with `#` are ignored. Lines beginning with `re:` are
+    /// regular expressions (e.g. `re:acme-[a-z]+`), which must match an
+    /// entire lowercased owner, or an entire lowercased `owner/repo` if
+    /// they contain a `/`.
+    fn extend_from_file(
+        orgs: &mut HashMap<String, OrgTrust>,
+        patterns: &mut Vec<AllowedPattern>,
+        entries: &mut Vec<AllowlistEntry>,
+        invalid: &mut Vec<String>,
+        path: &str,
+    ) -> anyhow::Result<()> {
+        match fs::read_to_string(path) {
+            Ok(contents) => {
+                for (idx, line) in contents.lines().enumerate() {
+                    let trimmed = line.trim();
+                    // Skip empty lines and comments
+                    if trimmed.is_empty() || trimmed.starts_with('#') {
+                        continue;
+                    }
+
+                    let source = format!("{path}:{line}", line = idx + 1);
+                    let name = if trimmed.starts_with("re:") {
+                        match Self::parse_entry(trimmed) {
+                            Ok((pattern, trust)) => {
+                                // NOTE: Safe unwrap, since we've checked for the prefix.
+                                let pattern = pattern.strip_prefix("re:").unwrap();
+                                let pattern =
+                                    Self::parse_pattern(trimmed, pattern, trust, &source)?;
+                                let name = pattern.name.clone();
+                                patterns.push(pattern);
+                                Ok(name)
+                            }
+                            Err(reason) => Err(reason),
+                        }
+                    } else {
+                        Self::insert_entry(orgs, trimmed)
+                    };
+
+                    let name = match name {
+                        Ok(name) => name,
+                        Err(reason) => {
+                            invalid.push(invalid_entry(&source, trimmed, &reason));
+                            continue;
+                        }
+                    };
+
+                    let point = Point {
+                        row: idx,
+                        column: line.len() - line.trim_start().len(),
+                    };
+                    let mut entry = AllowlistEntry::new(&name, "is allowlisted");
+                    entry.location = entry.location.with_path(path, Some(point));
+                    entries.push(entry);
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to read allowlist file {path}: {e}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns how far the given repository's owner is trusted, if it's
+    /// allowlisted at all.
+    ///
+    /// Exact entries take precedence over `re:` entries, which are only
+    /// matched if no exact entry does. Exact `owner` entries take precedence
+    /// over exact `owner/repo` entries.
+    fn trust(&self, owner: &str, repo: &str) -> Option<OrgTrust> {
+        let owner = self.canonical(owner);
+        if let Some(trust) = self.orgs.get(owner.as_ref()) {
+            return Some(*trust);
+        }
+
+        let qualified = format!("{owner}/{repo}", repo = normalize(repo));
+        if let Some(trust) = self.orgs.get(&qualified) {
+            return Some(*trust);
+        }
+
+        if self.patterns.is_empty() {
+            return None;
+        }
+
+        let matched = self.patterns.iter().find(|pattern| {
+            let candidate = if pattern.qualified {
+                qualified.as_str()
+            } else {
+                owner.as_ref()
+            };
+            pattern.regex.is_match(candidate)
+        })?;
+
+        tracing::debug!(
+            "{qualified}: allowlisted by {source}",
+            source = matched.source
+        );
+        Some(matched.trust)
+    }
+
+    /// Returns every user-provided entry that allowlists none of the
+    /// given `(owner, repo)` pairs, i.e. that no observed action relies on.
+    ///
+    /// Entries are used as [`AllowedOrgs::trust`] uses them: a `re:` entry
+    /// isn't used by an owner that also has an exact entry.
+    fn unused<'a>(
+        &self,
+        observed: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Vec<&AllowlistEntry> {
+        let mut used = HashSet::new();
+        for (owner, repo) in observed {
+            let normalized = self.canonical(owner);
+            if self.orgs.contains_key(normalized.as_ref()) {
+                used.insert(normalized.into_owned());
+                continue;
+            }
+
+            let qualified = format!("{normalized}/{repo}", repo = normalize(repo));
+            if self.orgs.contains_key(&qualified) {
+                used.insert(qualified);
+                continue;
+            }
+
+            if let Some(pattern) = self.patterns.iter().find(|pattern| {
+                let candidate = if pattern.qualified {
+                    qualified.as_str()
+                } else {
+                    normalized.as_ref()
+                };
+                pattern.regex.is_match(candidate)
+            }) {
+                used.insert(pattern.name.clone());
+            }
+        }
+
+        self.entries
+            .iter()
+            .filter(|entry| !used.contains(&entry.name))
+            .unique_by(|entry| &entry.name)
+            .collect()
+    }
+
+    /// Returns the canonical owner that `owner` has been renamed to, if
+    /// it's one of the `owner-aliases`.
+    pub(crate) fn alias_of(&self, owner: &str) -> Option<&str> {
+        self.aliases
+            .get(normalize(owner).as_ref())
+            .map(String::as_str)
+    }
+
+    /// Normalizes `owner` for lookups, resolving it to its canonical
+    /// owner if it's an alias.
+    fn canonical<'a>(&'a self, owner: &'a str) -> Cow<'a, str> {
+        match self.alias_of(owner) {
+            Some(canonical) => normalize(canonical),
+            None => normalize(owner),
+        }
+    }
+
+    /// This allowlist's identity; equal for clones, but distinct for
+    /// separately built allowlists, even ones with the same entries.
+    pub(crate) fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns whether the given repository's owner is allowlisted.
+    /// Owners and repositories are compared case-insensitively.
+    pub(crate) fn contains(&self, owner: &str, repo: &str) -> bool {
+        self.trust(owner, repo).is_some()
+    }
+
+    /// Returns whether the given repository's owner is allowlisted only
+    /// on the condition that its actions are ref-pinned.
+    fn requires_ref_pin(&self, owner: &str, repo: &str) -> bool {
+        self.trust(owner, repo) == Some(OrgTrust::RefPin)
+    }
+
+    /// Returns whether the given image tag is floating, i.e. is
+    /// `latest` or one of the configured `floating-tags`.
+    pub(crate) fn is_floating_tag(&self, tag: &str) -> bool {
+        tag == LATEST_TAG || self.floating_tags.iter().any(|t| t == tag)
+    }
+}
+
+/// Percentage thresholds for the colors of the `tpa-badge` output, i.e.
+/// the percentage of third-party actions that are pinned to a commit SHA.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct BadgeThresholds {
+    /// The minimum percentage for a green badge
+    pub(crate) green: u8,
+    /// The minimum percentage for a yellow badge; anything lower is red
+    pub(crate) yellow: u8,
+}
+
+impl Default for BadgeThresholds {
+    fn default() -> Self {
+        Self {
+            green: 100,
+            yellow: 80,
+        }
+    }
+}
+
+impl BadgeThresholds {
+    /// Load the thresholds from the `unpinned-uses` rule configuration.
+    pub(crate) fn from_state(state: &AuditState<'_>) -> anyhow::Result<Self> {
+        let thresholds = state
+            .config
+            .rule_config::<UnpinnedUsesConfig>(UnpinnedUses::ident())?
+            .unwrap_or_default()
+            .badge;
+
+        if thresholds.green > 100 || thresholds.yellow > thresholds.green {
+            anyhow::bail!(
+                "{context}: badge thresholds must satisfy \
+                 yellow <= green <= 100 (got yellow: {yellow}, green: {green})",
+                context = state.config.rule_error_context(UnpinnedUses::ident()),
+                yellow = thresholds.yellow,
+                green = thresholds.green,
+            );
+        }
+
+        Ok(thresholds)
+    }
+
+    /// Returns the badge color for `pinned` out of `total` actions.
+    ///
+    /// No actions at all counts as entirely pinned.
+    pub(crate) fn color(&self, pinned: usize, total: usize) -> &'static str {
+        // NOTE: Compare `pinned / total` against each threshold without
+        // rounding, so that e.g. 99.9% isn't considered 100%.
+        let at_least = |threshold: u8| pinned * 100 >= total * threshold as usize;
+
+        if at_least(self.green) {
+            "green"
+        } else if at_least(self.yellow) {
+            "yellow"
+        } else {
+            "red"
+        }
+    }
+}
 
 impl UnpinnedUses {
-    pub fn evaluate_pinning(&self, uses: &Uses) -> Option<(String, Severity, Persona)> {
+    /// Returns the policies that apply to the input at `key`: those of the
+    /// first override whose `paths` match it, or the top-level policies
+    /// if none do.
+    fn policies_for(&self, key: &InputKey) -> (&UnpinnedUsesPolicies, Option<&PolicyOverride>) {
+        match self.overrides.iter().find(|o| o.matches(key)) {
+            Some(matched) => (&matched.policies, Some(matched)),
+            None => (&self.policies, None),
+        }
+    }
+
+    /// Evaluates `uses` in the input at `key` against the pinning policies,
+    /// returning the violation if it violates one.
+    pub(crate) fn evaluate_pinning(&self, key: &InputKey, uses: &Uses) -> Option<PinningViolation> {
         match uses {
             // Don't evaluate pinning for local `uses:`, since unpinned references
             // are fully controlled by the repository anyways.
@@ -37,16 +743,37 @@ impl UnpinnedUses {
             //
             // Instead, we produce a blanket finding for unpinned images,
             // and a pedantic-only finding for unhashed images.
-            Uses::Docker(_) => {
-                if uses.unpinned() {
-                    Some((
-                        "action is not pinned to a tag, branch, or hash ref".into(),
+            Uses::Docker(docker) => {
+                // A malformed digest looks like a hash pin, but doesn't
+                // actually pin the image to anything.
+                if docker.digest_is_malformed() {
+                    Some(PinningViolation::new(
+                        "malformed image digest",
+                        Severity::Medium,
+                        Persona::default(),
+                    ))
+                } else if let Some(tag) = docker
+                    .tag
+                    .as_deref()
+                    .filter(|tag| docker.hash.is_none() && self.allowed_orgs.is_floating_tag(tag))
+                {
+                    Some(PinningViolation::new(
+                        format!(
+                            "image is pinned to the floating tag {tag}; \
+                             pin it to a digest or an immutable tag instead"
+                        ),
+                        Severity::Medium,
+                        Persona::default(),
+                    ))
+                } else if uses.unpinned() {
+                    Some(PinningViolation::new(
+                        "action is not pinned to a tag, branch, or hash ref",
                         Severity::Medium,
                         Persona::default(),
                     ))
                 } else if uses.unhashed() {
-                    Some((
-                        "action is not pinned to a hash".into(),
+                    Some(PinningViolation::new(
+                        "action is not pinned to a hash",
                         Severity::Low,
                         Persona::Pedantic,
                     ))
@@ -55,20 +782,81 @@ impl UnpinnedUses {
                 }
             }
             Uses::Repository(repo_uses) => {
+                // Server-qualified `uses:` clauses are evaluated relative to
+                // their host, e.g. `owner/repo` on `ghe.example.com`.
+                let (host, repo_uses) = self.resolver.on_host(repo_uses);
+                let repo_uses = &repo_uses;
+
                 // Check if this is a third-party action (not from allowlisted orgs)
-                let is_third_party = !self.allowed_orgs.contains(&repo_uses.owner.to_lowercase());
-                
-                // For third-party actions that aren't hash pinned, we use our special message
-                if is_third_party && uses.unhashed() {
-                    return Some((
-                        THIRD_PARTY_MESSAGE.into(),
-                        Severity::High,
-                        Persona::default(),
-                    ));
+                let is_third_party = self.resolver.third_party_on(
+                    &self.allowed_orgs,
+                    &host,
+                    &repo_uses.owner,
+                    &repo_uses.repo,
+                );
+                let unhashed = !self.resolver.is_commit(repo_uses);
+
+                // In strict mode, third-party actions must always be hash-pinned,
+                // regardless of the policies.
+                let strict = matches!(self.third_party_handling, ThirdPartyHandling::Strict);
+                if strict && is_third_party && unhashed {
+                    return Some(
+                        PinningViolation::new(
+                            THIRD_PARTY_MESSAGE,
+                            Severity::High,
+                            Persona::default(),
+                        )
+                        .third_party(true),
+                    );
+                }
+
+                // Policies are written against canonical owners, so an
+                // alias's actions are looked up under the canonical owner.
+                let canonical =
+                    self.allowed_orgs
+                        .alias_of(&repo_uses.owner)
+                        .map(|owner| RepositoryUses {
+                            owner: owner.into(),
+                            repo: repo_uses.repo.clone(),
+                            subpath: repo_uses.subpath.clone(),
+                            git_ref: repo_uses.git_ref.clone(),
+                        });
+
+                let (policies, matched) = self.policies_for(key);
+                let (pattern, policy, source) =
+                    policies.get_policy(canonical.as_ref().unwrap_or(repo_uses));
+
+                // Orgs allowlisted with the `ref-pin` qualifier only ever
+                // need to be ref-pinned, even under a hash-pin policy.
+                if matches!(policy, UsesPolicy::HashPin)
+                    && self
+                        .allowed_orgs
+                        .requires_ref_pin(&repo_uses.owner, &repo_uses.repo)
+                {
+                    return uses.unpinned().then(|| {
+                        PinningViolation::new(
+                            format!(
+                                "action is not pinned to a ref or hash (required by {owner} ref-pin allowlist entry)",
+                                owner = repo_uses.owner
+                            ),
+                            Severity::High,
+                            Persona::default(),
+                        )
+                    });
                 }
-                
-                let (pattern, policy) = self.policies.get_policy(repo_uses);
 
+                let violation = match policy {
+                    UsesPolicy::Any => None,
+                    UsesPolicy::RefPin => uses
+                        .unpinned()
+                        .then_some("action is not pinned to a ref or hash"),
+                    // In strict mode, we've already handled third-party actions above.
+                    UsesPolicy::HashPin => (unhashed && !(strict && is_third_party))
+                        .then_some("action is not pinned to a hash"),
+                }?;
+
+                // NOTE: We only describe the policy once we know it's been
+                // violated, since most `uses:` clauses don't violate any.
                 let pat_desc = match pattern {
                     Some(RepositoryUsesPattern::Any) | None => "blanket".into(),
                     Some(RepositoryUsesPattern::InOwner(owner)) => format!("{owner}/*"),
@@ -89,31 +877,115 @@ impl UnpinnedUses {
                     Some(RepositoryUsesPattern::ExactWithRef { .. }) => unreachable!(),
                 };
 
-                match policy {
-                    UsesPolicy::Any => None,
-                    UsesPolicy::RefPin => uses.unpinned().then_some((
-                        format!(
-                            "action is not pinned to a ref or hash (required by {pat_desc} policy)"
-                        ),
+                let policy_desc = match matched {
+                    Some(matched) => {
+                        format!("{pat_desc} policy in override for {}", matched.paths_desc)
+                    }
+                    None => format!("{pat_desc} policy"),
+                };
+
+                Some(
+                    PinningViolation::new(
+                        format!("{violation} (required by {policy_desc})"),
                         Severity::High,
                         Persona::default(),
-                    )),
-                    UsesPolicy::HashPin => {
-                        if uses.unhashed() && !is_third_party { // We've already handled third-party actions above
-                            Some((
-                                format!("action is not pinned to a hash (required by {pat_desc} policy)"),
-                                Severity::High,
-                                Persona::default(),
-                            ))
-                        } else {
-                            None
-                        }
-                    }
-                }
+                    )
+                    .with_policy(source.location(&pat_desc))
+                    .third_party(is_third_party),
+                )
             }
         }
     }
 
+    /// The remediation for `uses`, a violating `uses:` clause in `step`:
+    /// its current value, and (when its ref can be resolved online) the
+    /// commit-pinned value that replaces it.
+    fn remediation<'doc>(
+        &self,
+        step: &impl StepCommon<'doc>,
+        uses: &Uses,
+    ) -> anyhow::Result<Option<Remediation>> {
+        // NOTE: An aliased `uses:` is fixed where it's defined, rather than
+        // at the alias that it's used through.
+        let (location, definition) = step
+            .location()
+            .with_keys(&["uses".into()])
+            .concretize_aliased(step.as_document())?;
+        let location = definition.unwrap_or(location);
+        let source = step.as_document().source();
+        let concrete = &location.concrete.location;
+        let clause = concrete.start_byte..concrete.end_byte;
+
+        let Some(remediation) = fix::uses_value_span(source, clause.clone())
+            .and_then(|span| Remediation::new(location.symbolic.key, source, span))
+        else {
+            return Ok(None);
+        };
+
+        let Uses::Repository(repo_uses) = uses else {
+            return Ok(Some(remediation));
+        };
+        // NOTE: Refs can only be resolved on the run's GitHub host.
+        let (host, repo_uses) = self.resolver.on_host(repo_uses);
+        if !self.resolver.is_local_host(&host) {
+            return Ok(Some(remediation));
+        }
+        let Some(git_ref) = repo_uses
+            .git_ref
+            .as_deref()
+            .filter(|_| !self.resolver.is_commit(&repo_uses))
+        else {
+            return Ok(Some(remediation));
+        };
+
+        let Some(sha) = self
+            .resolver
+            .resolve_ref(&repo_uses.owner, &repo_uses.repo, git_ref)
+        else {
+            return Ok(Some(remediation));
+        };
+        let Some(edits) = fix::pin_edits(source, clause, git_ref, &sha) else {
+            return Ok(Some(remediation));
+        };
+
+        let replacement = match remediation.current.rsplit_once('@') {
+            Some((action, _)) => format!("{action}@{sha}"),
+            None => return Ok(Some(remediation)),
+        };
+        Ok(Some(remediation.with_replacement(replacement, edits)))
+    }
+
+    fn violation_finding<'doc>(
+        step: &impl StepCommon<'doc>,
+        violation: PinningViolation,
+        remediation: Option<Remediation>,
+    ) -> anyhow::Result<Finding<'doc>> {
+        let mut finding = Self::finding()
+            .confidence(Confidence::High)
+            .severity(violation.severity)
+            .persona(violation.persona)
+            .add_location(
+                step.location()
+                    .primary()
+                    .with_keys(&["uses".into()])
+                    .annotated(violation.annotation),
+            )
+            .add_step_context(step);
+        if let Some(policy) = violation.policy {
+            finding = finding.add_external_location(policy);
+        }
+        if let Some(remediation) = remediation {
+            finding = finding.remediation(remediation);
+        }
+        // NOTE: Every third-party violation is for an action that isn't
+        // pinned to a commit SHA, whichever policy it violates.
+        if violation.third_party {
+            finding = finding.tag(FindingTag::ThirdPartyUnpinned);
+        }
+
+        finding.build(step)
+    }
+
     fn process_step<'doc>(
         &self,
         step: &impl StepCommon<'doc>,
@@ -124,27 +996,203 @@ impl UnpinnedUses {
             return Ok(findings);
         };
 
-        if let Some((annotation, severity, persona)) = self.evaluate_pinning(uses) {
+        // No pinning policy can be evaluated for a `uses:` that's built
+        // from expressions, so we flag these outright -- unless they're
+        // selected by inputs with pinned defaults, in which case we
+        // evaluate those defaults instead.
+        if uses.dynamic() {
+            let Some(defaults) = pinned_input_defaults(step, uses) else {
+                findings.push(
+                    Self::finding()
+                        .confidence(Confidence::Medium)
+                        .severity(Severity::High)
+                        .add_location(
+                            step.location()
+                                .primary()
+                                .with_keys(&["uses".into()])
+                                .annotated(
+                                    "action is selected dynamically, so its pinning can't be evaluated",
+                                ),
+                        )
+                        .add_step_context(step)
+                        .build(step)?,
+                );
+
+                return Ok(findings);
+            };
+
+            let names = match defaults.as_slice() {
+                [(name, _)] => format!("input `{name}`"),
+                _ => format!(
+                    "inputs {}",
+                    defaults
+                        .iter()
+                        .map(|(name, _)| format!("`{name}`"))
+                        .join(", ")
+                ),
+            };
+            findings.push(
+                Self::finding()
+                    .confidence(Confidence::Medium)
+                    .severity(Severity::Low)
+                    .persona(Persona::Pedantic)
+                    .add_location(
+                        step.location()
+                            .primary()
+                            .with_keys(&["uses".into()])
+                            .annotated(format!(
+                                "action is selected by {names}, whose pinned {defaults} \
+                             can be overridden by callers",
+                                defaults = if defaults.len() == 1 {
+                                    "default"
+                                } else {
+                                    "defaults"
+                                },
+                            )),
+                    )
+                    .add_step_context(step)
+                    .build(step)?,
+            );
+
+            for (name, default) in &defaults {
+                if let Some(mut violation) = self.evaluate_pinning(step.location().key, default) {
+                    violation.annotation = format!(
+                        "{} (in the default of input `{name}`)",
+                        violation.annotation
+                    );
+                    findings.push(Self::violation_finding(step, violation, None)?);
+                }
+            }
+
+            return Ok(findings);
+        }
+
+        if let Some(violation) = self.evaluate_pinning(step.location().key, uses) {
+            let remediation = self.remediation(step, uses)?;
+            findings.push(Self::violation_finding(step, violation, remediation)?);
+        };
+
+        // NOTE: Renames can only be checked on the run's GitHub host.
+        if let Uses::Repository(repo_uses) = uses
+            && let (host, repo_uses) = self.resolver.on_host(repo_uses)
+            && self.resolver.is_local_host(&host)
+        {
+            findings.extend(self.renamed_owner_findings(step, &repo_uses)?);
+        }
+
+        Ok(findings)
+    }
+
+    /// Findings for a `uses:` clause whose owner has been renamed, per
+    /// `owner-aliases` or (with `detect-owner-redirects`) the GitHub API.
+    fn renamed_owner_findings<'doc>(
+        &self,
+        step: &impl StepCommon<'doc>,
+        uses: &RepositoryUses,
+    ) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+        let alias_of = self.allowed_orgs.alias_of(&uses.owner);
+
+        if let Some(canonical) = alias_of {
             findings.push(
                 Self::finding()
                     .confidence(Confidence::High)
-                    .severity(severity)
-                    .persona(persona)
+                    .severity(Severity::Low)
+                    .persona(Persona::Pedantic)
                     .add_location(
                         step.location()
                             .primary()
                             .with_keys(&["uses".into()])
-                            .annotated(annotation),
+                            .annotated(format!(
+                                "`{owner}` has been renamed to `{canonical}`; \
+                                 update this reference to the canonical owner",
+                                owner = uses.owner
+                            )),
                     )
+                    .add_step_context(step)
                     .build(step)?,
             );
+        }
+
+        if !self.detect_owner_redirects {
+            return Ok(findings);
+        }
+
+        let Some(full_name) = self.resolver.canonical_repository(&uses.owner, &uses.repo) else {
+            return Ok(findings);
         };
+        let resolved_owner = full_name
+            .split_once('/')
+            .map_or(full_name.as_str(), |(owner, _)| owner);
+
+        let annotation = match alias_of {
+            // An alias should keep redirecting to its canonical owner; if
+            // it doesn't, someone may have registered the old name.
+            Some(canonical) if !resolved_owner.eq_ignore_ascii_case(canonical) => format!(
+                "`{owner}` is an alias of `{canonical}`, but `{owner}/{repo}` \
+                 now resolves to `{full_name}`; the old name may have been re-registered",
+                owner = uses.owner,
+                repo = uses.repo
+            ),
+            None if !resolved_owner.eq_ignore_ascii_case(&uses.owner) => format!(
+                "`{owner}/{repo}` redirects to `{full_name}`; whoever registers \
+                 `{owner}` can take over this reference",
+                owner = uses.owner,
+                repo = uses.repo
+            ),
+            _ => return Ok(findings),
+        };
+
+        findings.push(
+            Self::finding()
+                .confidence(Confidence::Medium)
+                .severity(Severity::Medium)
+                .add_location(
+                    step.location()
+                        .primary()
+                        .with_keys(&["uses".into()])
+                        .annotated(annotation),
+                )
+                .add_step_context(step)
+                .build(step)?,
+        );
 
         Ok(findings)
     }
 }
 
-// Update the Audit implementation in unpinned_uses.rs
+/// Every `(owner, repo)` that `input` uses, in steps and reusable
+/// workflow calls alike.
+fn repository_owners(input: &AuditInput) -> Vec<(String, String)> {
+    // NOTE: A dynamic `uses:` could be anything, so it doesn't count.
+    fn owner(uses: Option<&Uses>) -> Option<(String, String)> {
+        match uses.filter(|uses| !uses.dynamic())? {
+            Uses::Repository(repo) => Some((repo.owner.clone(), repo.repo.clone())),
+            _ => None,
+        }
+    }
+
+    let mut owners = vec![];
+    match input {
+        AuditInput::Workflow(workflow) => {
+            for job in workflow.jobs() {
+                match job {
+                    Job::NormalJob(job) => {
+                        owners.extend(job.steps().filter_map(|step| owner(step.uses())))
+                    }
+                    Job::ReusableWorkflowCallJob(job) => owners.extend(owner(Some(&job.uses))),
+                }
+            }
+        }
+        AuditInput::Action(action) => {
+            if matches!(action.runs, action::Runs::Composite(_)) {
+                owners.extend(action.steps().filter_map(|step| owner(step.uses())));
+            }
+        }
+    }
+
+    owners
+}
 
 impl Audit for UnpinnedUses {
     fn new(state: &AuditState<'_>) -> Result<Self, AuditLoadError>
@@ -154,80 +1202,126 @@ impl Audit for UnpinnedUses {
         let config = state
             .config
             .rule_config::<UnpinnedUsesConfig>(Self::ident())
-            .context("invalid configuration")
-            .map_err(AuditLoadError::Fail)?
-            .unwrap_or_default();
+            .map_err(AuditLoadError::Fail)?;
+        let configured = config.is_some();
+        let mut config = config.unwrap_or_default();
 
-        // Create the default set of allowed orgs
-        let mut allowed_orgs = DEFAULT_OFFICIAL_ORGS
-            .iter()
-            .map(|s| s.to_string().to_lowercase())
-            .collect::<HashSet<String>>();
-        
-        // Add allowlisted orgs from file if specified via CLI
-        if let Some(allowlist_path) = &state.tpa_allowlist_file {
-            match fs::read_to_string(allowlist_path) {
-                Ok(contents) => {
-                    for line in contents.lines() {
-                        let trimmed = line.trim();
-                        // Skip empty lines and comments
-                        if !trimmed.is_empty() && !trimmed.starts_with('#') {
-                            allowed_orgs.insert(trimmed.to_lowercase());
-                        }
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to read allowlist file {}: {}", allowlist_path, e);
-                }
-            }
-        }
-        
-        // Add explicitly specified orgs from CLI
-        if let Some(additional_orgs) = &state.tpa_allowed_org {
-            for org in additional_orgs {
-                allowed_orgs.insert(org.to_lowercase());
-            }
-        }
-        
-        // Add any additional orgs specified in the config file
-        if let Some(allowlist_path) = &config.allowlist_file {
-            match fs::read_to_string(allowlist_path) {
-                Ok(contents) => {
-                    for line in contents.lines() {
-                        let trimmed = line.trim();
-                        // Skip empty lines and comments
-                        if !trimmed.is_empty() && !trimmed.starts_with('#') {
-                            allowed_orgs.insert(trimmed.to_lowercase());
-                        }
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to read config allowlist file {}: {}", allowlist_path, e);
-                }
-            }
-        }
-        
-        // Add any additional orgs specified in the config
-        if let Some(additional_orgs) = &config.additional_allowed_orgs {
-            for org in additional_orgs {
-                allowed_orgs.insert(org.to_lowercase());
-            }
-        }
+        let allowed_orgs = AllowedOrgs::new(state, &config)
+            .with_context(|| state.config.rule_error_context(Self::ident()))
+            .map_err(AuditLoadError::Fail)?;
+        let third_party_handling = config.third_party_handling;
+        let report_unused_allowlist = config.report_unused_allowlist;
+        let detect_owner_redirects = config.detect_owner_redirects;
+
+        let overrides = std::mem::take(&mut config.overrides)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, config)| PolicyOverride::new(idx, config, state.config))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .with_context(|| state.config.rule_error_context(Self::ident()))
+            .map_err(AuditLoadError::Fail)?;
 
-        let policies = UnpinnedUsesPolicies::try_from(config)
-            .context("invalid configuration")
+        let mut policies = UnpinnedUsesPolicies::try_from(config)
+            .with_context(|| state.config.rule_error_context(Self::ident()))
             .map_err(AuditLoadError::Fail)?;
+        if configured {
+            policies = policies.with_config_sources(state.config, &["policies".into()]);
+        }
 
-        Ok(Self { 
+        Ok(Self {
             policies,
+            overrides,
             allowed_orgs,
+            third_party_handling,
+            report_unused_allowlist,
+            detect_owner_redirects,
+            resolver: state.resolver.clone(),
         })
     }
 
+    fn meta() -> AuditMeta {
+        AuditMeta {
+            config_keys: &[
+                "policies",
+                "overrides",
+                "allowlist-file",
+                "additional-allowed-orgs",
+                "badge",
+                "third-party-handling",
+                "floating-tags",
+                "report-unused-allowlist",
+                "owner-aliases",
+                "detect-owner-redirects",
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn config_warnings(&self) -> Vec<String> {
+        let overrides = self.overrides.iter().flat_map(|o| {
+            o.policies
+                .shadowed
+                .iter()
+                .map(|warning| format!("in override for {}: {warning}", o.paths_desc))
+        });
+
+        self.policies
+            .shadowed
+            .iter()
+            .cloned()
+            .chain(overrides)
+            .collect()
+    }
+
     fn audit_step<'doc>(&self, step: &Step<'doc>) -> anyhow::Result<Vec<Finding<'doc>>> {
         self.process_step(step)
     }
 
+    fn audit_finish<'doc>(
+        &self,
+        inputs: &[&'doc AuditInput],
+    ) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+
+        let Some(first) = inputs.first().filter(|_| self.report_unused_allowlist) else {
+            return Ok(findings);
+        };
+
+        let observed = inputs
+            .iter()
+            .flat_map(|input| repository_owners(input))
+            .collect::<HashSet<_>>();
+
+        // NOTE: An unused entry isn't in any particular input, so its
+        // finding is anchored at the first line of the first one.
+        let source = first.as_document().source();
+        let span = 0..source.find('\n').unwrap_or(source.len());
+
+        for entry in self.allowed_orgs.unused(
+            observed
+                .iter()
+                .map(|(owner, repo)| (owner.as_str(), repo.as_str())),
+        ) {
+            findings.push(
+                Self::finding()
+                    .severity(Severity::Low)
+                    .confidence(Confidence::High)
+                    .persona(Persona::Auditor)
+                    .add_raw_location(Location::new(
+                        first.location().primary().annotated(format!(
+                            "allowlist entry `{name}` doesn't match any action used by the audited inputs",
+                            name = entry.name
+                        )),
+                        Feature::from_span(&span, first),
+                    ))
+                    .add_external_location(entry.location.clone())
+                    .build(*first)?,
+            );
+        }
+
+        Ok(findings)
+    }
+
     fn audit_composite_step<'a>(
         &self,
         step: &CompositeStep<'a>,
@@ -244,15 +1338,121 @@ impl Audit for UnpinnedUses {
 struct UnpinnedUsesConfig {
     /// A mapping of `uses:` patterns to policies.
     policies: HashMap<RepositoryUsesPattern, UsesPolicy>,
-    
+
     /// Path to a file containing additional orgs/users to treat as trusted
     /// Each line in the file should contain one org/user name
     #[serde(default)]
     allowlist_file: Option<String>,
-    
+
     /// Additional allowed organizations to consider as trusted beyond the defaults
     #[serde(default)]
     additional_allowed_orgs: Option<Vec<String>>,
+
+    /// Color thresholds for the `tpa-badge` output
+    #[serde(default)]
+    badge: BadgeThresholds,
+
+    /// Policies that replace `policies` for inputs at matching paths
+    #[serde(default)]
+    overrides: Vec<UnpinnedUsesOverrideConfig>,
+
+    /// How third-party actions are evaluated
+    #[serde(default)]
+    third_party_handling: ThirdPartyHandling,
+
+    /// Image tags (besides `latest`) that are considered floating
+    #[serde(default = "default_floating_tags")]
+    floating_tags: Vec<String>,
+
+    /// Whether to report allowlist entries that no audited input uses
+    #[serde(default)]
+    report_unused_allowlist: bool,
+
+    /// Renamed owners, mapped to their canonical (new) names
+    #[serde(default)]
+    owner_aliases: HashMap<String, String>,
+
+    /// Whether to detect renamed owners via the GitHub API, when online
+    #[serde(default)]
+    detect_owner_redirects: bool,
+}
+
+fn default_floating_tags() -> Vec<String> {
+    DEFAULT_FLOATING_TAGS.iter().map(|&t| t.into()).collect()
+}
+
+/// How the `unpinned-uses` rule evaluates third-party actions, i.e.
+/// actions outside of the allowlisted orgs.
+#[derive(Copy, Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ThirdPartyHandling {
+    /// Third-party actions must always be hash-pinned, regardless of
+    /// the policies.
+    #[default]
+    Strict,
+    /// Third-party actions are evaluated against the policies, like
+    /// any other action.
+    Policy,
+}
+
+/// A single entry in the `unpinned-uses` rule's `overrides`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct UnpinnedUsesOverrideConfig {
+    /// Path patterns for the inputs this override applies to.
+    paths: Vec<String>,
+
+    /// A mapping of `uses:` patterns to policies, used instead of the
+    /// top-level policies for matching inputs.
+    policies: HashMap<RepositoryUsesPattern, UsesPolicy>,
+}
+
+/// A set of policies that applies to inputs at matching paths.
+struct PolicyOverride {
+    paths: Vec<GlobMatcher>,
+    /// The override's path patterns, for use in finding annotations.
+    paths_desc: String,
+    policies: UnpinnedUsesPolicies,
+}
+
+impl PolicyOverride {
+    /// Reifies the `idx`th override in `config`.
+    fn new(
+        idx: usize,
+        override_config: UnpinnedUsesOverrideConfig,
+        config: &Config,
+    ) -> anyhow::Result<Self> {
+        if override_config.paths.is_empty() {
+            anyhow::bail!("override #{n} has no paths", n = idx + 1);
+        }
+
+        let paths = override_config
+            .paths
+            .iter()
+            .map(|pattern| {
+                Glob::new(pattern)
+                    .map(|glob| glob.compile_matcher())
+                    .with_context(|| format!("invalid override path pattern: {pattern}"))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        let policies = UnpinnedUsesPolicies::try_from(override_config.policies)?
+            .with_config_sources(config, &["overrides".into(), idx.into(), "policies".into()]);
+
+        Ok(Self {
+            paths,
+            paths_desc: override_config.paths.join(", "),
+            policies,
+        })
+    }
+
+    /// Whether this override applies to the input at `key`, either by
+    /// its (repository-relative) path or by its filename.
+    fn matches(&self, key: &InputKey) -> bool {
+        self.paths
+            .iter()
+            .any(|path| path.is_match(key.sarif_path()) || path.is_match(key.filename()))
+    }
 }
 
 impl Default for UnpinnedUsesConfig {
@@ -276,12 +1476,19 @@ impl Default for UnpinnedUsesConfig {
             .into(),
             allowlist_file: None,
             additional_allowed_orgs: None,
+            badge: BadgeThresholds::default(),
+            overrides: vec![],
+            third_party_handling: ThirdPartyHandling::default(),
+            floating_tags: default_floating_tags(),
+            report_unused_allowlist: false,
+            owner_aliases: HashMap::new(),
+            detect_owner_redirects: false,
         }
     }
 }
 
 /// A singular policy for a `uses:` reference.
-#[derive(Copy, Clone, Debug, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 enum UsesPolicy {
     /// No policy; all `uses:` references are allowed, even unpinned ones.
@@ -292,24 +1499,157 @@ enum UsesPolicy {
     HashPin,
 }
 
+impl UsesPolicy {
+    /// The policy's name, as it's written in the configuration.
+    fn as_str(&self) -> &'static str {
+        match self {
+            UsesPolicy::Any => "any",
+            UsesPolicy::RefPin => "ref-pin",
+            UsesPolicy::HashPin => "hash-pin",
+        }
+    }
+}
+
+/// Where a policy in [`UnpinnedUsesPolicies`] came from.
+#[derive(Clone, Debug)]
+enum PolicySource {
+    /// A built-in default policy.
+    Default,
+    /// A policy from the `unpinned-uses` configuration.
+    Config {
+        origin: String,
+        point: Option<Point>,
+    },
+}
+
+impl PolicySource {
+    fn location(&self, pattern: &str) -> ExternalLocation {
+        match self {
+            PolicySource::Default => {
+                ExternalLocation::new(format!("{pattern} policy is a built-in default"))
+            }
+            PolicySource::Config { origin, point } => {
+                ExternalLocation::new(format!("{pattern} policy defined"))
+                    .with_path(origin, point.clone())
+            }
+        }
+    }
+}
+
+/// A single `(pattern, policy)` entry in the policy tree.
+type PolicyEntry = (RepositoryUsesPattern, UsesPolicy, PolicySource);
+
+/// A policy pattern with its owner and repository lowercased, i.e. as it's
+/// matched: `(specificity, owner, repo, subpath)`.
+type NormalizedPattern = (u8, String, String, Option<String>);
+
+fn normalize_pattern(pattern: &RepositoryUsesPattern) -> NormalizedPattern {
+    let (owner, repo, subpath) = match pattern {
+        RepositoryUsesPattern::ExactWithRef {
+            owner,
+            repo,
+            subpath,
+            ..
+        } => (owner.as_str(), repo.as_str(), subpath.clone()),
+        RepositoryUsesPattern::ExactPath {
+            owner,
+            repo,
+            subpath,
+        } => (owner.as_str(), repo.as_str(), Some(subpath.clone())),
+        RepositoryUsesPattern::ExactRepo { owner, repo }
+        | RepositoryUsesPattern::InRepo { owner, repo } => (owner.as_str(), repo.as_str(), None),
+        RepositoryUsesPattern::InOwner(owner) => (owner.as_str(), "", None),
+        RepositoryUsesPattern::Any => ("", "", None),
+    };
+
+    (
+        pattern.specificity(),
+        owner.to_ascii_lowercase(),
+        repo.to_ascii_lowercase(),
+        subpath,
+    )
+}
+
+/// The patterns that match every `uses:` that `pattern` matches, from
+/// the most specific to the least, i.e. what `pattern`'s matches would
+/// fall back to without it.
+fn broader_patterns(pattern: &RepositoryUsesPattern) -> Vec<RepositoryUsesPattern> {
+    let (owner, repo) = match pattern {
+        RepositoryUsesPattern::ExactWithRef { owner, repo, .. }
+        | RepositoryUsesPattern::ExactPath { owner, repo, .. }
+        | RepositoryUsesPattern::ExactRepo { owner, repo } => (owner, Some(repo)),
+        RepositoryUsesPattern::InRepo { owner, .. } => (owner, None),
+        RepositoryUsesPattern::InOwner(_) => return vec![RepositoryUsesPattern::Any],
+        RepositoryUsesPattern::Any => return vec![],
+    };
+
+    // NOTE: `owner/repo` doesn't match subpaths, so it never covers
+    // `owner/repo/subpath`; only `owner/repo/*` does.
+    repo.map(|repo| RepositoryUsesPattern::InRepo {
+        owner: owner.clone(),
+        repo: repo.clone(),
+    })
+    .into_iter()
+    .chain([
+        RepositoryUsesPattern::InOwner(owner.clone()),
+        RepositoryUsesPattern::Any,
+    ])
+    .collect()
+}
+
+/// Normalizes an owner or repository name for case-insensitive lookups,
+/// only allocating if it isn't already lowercase.
+fn normalize(name: &str) -> Cow<'_, str> {
+    if name.bytes().any(|b| b.is_ascii_uppercase()) {
+        Cow::Owned(name.to_ascii_lowercase())
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// The number of owners in the policy tree (or of repositories under an
+/// owner), up to which `get_policy` scans them rather than looking them up.
+const SCAN_LIMIT: usize = 8;
+
+/// The policies under a single owner in the policy tree.
+#[derive(Default)]
+struct OwnerPolicies {
+    /// Policies for specific repositories (i.e. `owner/repo`, `owner/repo/*`,
+    /// and `owner/repo/subpath`), keyed by lowercased repository name and
+    /// ordered by specificity.
+    repos: HashMap<String, Vec<PolicyEntry>>,
+    /// The `owner/*` policy, if there is one.
+    owner: Option<PolicyEntry>,
+}
+
+impl OwnerPolicies {
+    fn entries_mut(&mut self) -> impl Iterator<Item = &mut PolicyEntry> {
+        self.repos.values_mut().flatten().chain(self.owner.as_mut())
+    }
+}
+
 /// Represents the set of policies used to evaluate `uses:` references.
 struct UnpinnedUsesPolicies {
-    /// The policy tree is a mapping of `owner` slugs to a list of
-    /// `(pattern, policy)` pairs under that owner, ordered by specificity.
+    /// The policy tree is a mapping of lowercased `owner` slugs to the
+    /// policies under that owner, which are in turn indexed by repository.
     ///
-    /// For example, a config containing both `foo/*: hash-pin` and
-    /// `foo/bar: ref-pin` would produce a policy tree like this:
+    /// For example, a config containing `foo/*: hash-pin`, `foo/bar: ref-pin`,
+    /// and `foo/bar/baz: any` would produce a policy tree like this:
     ///
     /// ```text
     /// foo:
-    ///   - foo/bar: ref-pin
-    ///   - foo/*: hash-pin
+    ///   repos:
+    ///     bar:
+    ///       - foo/bar/baz: any
+    ///       - foo/bar: ref-pin
+    ///   owner: foo/*: hash-pin
     /// ```
     ///
-    /// This is done for performance reasons: a two-level structure here
-    /// means that checking a `uses:` is a linear scan of the policies
-    /// for that owner, rather than a full scan of all policies.
-    policy_tree: HashMap<String, Vec<(RepositoryUsesPattern, UsesPolicy)>>,
+    /// This is done for performance reasons: configs can contain thousands
+    /// of policies under a single owner, so checking a `uses:` is two hash
+    /// lookups followed by a linear scan of the (few) policies for that
+    /// repository, rather than a scan of all of the owner's policies.
+    policy_tree: HashMap<String, OwnerPolicies>,
 
     /// This is the policy that's applied if nothing in the policy tree matches.
     ///
@@ -317,28 +1657,96 @@ struct UnpinnedUsesPolicies {
     /// `UnpinnedUsesConfig::default()`. However, if the user explicitly
     /// omits a `*` rule, this will be `UsesPolicy::HashPin`.
     default_policy: UsesPolicy,
+
+    /// Where [`Self::default_policy`] came from.
+    default_source: PolicySource,
+
+    /// Patterns that can never change the outcome, since a broader pattern
+    /// already applies the same policy to everything they match.
+    shadowed: Vec<String>,
 }
 
 impl UnpinnedUsesPolicies {
     /// Returns the most specific policy for the given repository `uses` reference,
-    /// or the default policy if none match.
-    fn get_policy(&self, uses: &RepositoryUses) -> (Option<&RepositoryUsesPattern>, UsesPolicy) {
-        match self.policy_tree.get(&uses.owner) {
-            Some(policies) => {
-                // Policies are ordered by specificity, so we can
-                // iterate and return eagerly.
-                for (uses_pattern, policy) in policies {
-                    if uses_pattern.matches(uses) {
-                        return (Some(uses_pattern), *policy);
-                    }
-                }
-                // The policies under `owner/` might be fully divergent
-                // if there isn't an `owner/*` rule, so we fall back
-                // to the default policy.
-                (None, self.default_policy)
+    /// or the default policy if none match, along with the policy's source.
+    fn get_policy(
+        &self,
+        uses: &RepositoryUses,
+    ) -> (Option<&RepositoryUsesPattern>, UsesPolicy, &PolicySource) {
+        // NOTE: Hashing a name costs more than comparing it against a
+        // handful of names, so small trees (and small owners) are scanned
+        // instead.
+        let policies = if self.policy_tree.len() <= SCAN_LIMIT {
+            self.policy_tree
+                .iter()
+                .find(|(owner, _)| owner.eq_ignore_ascii_case(&uses.owner))
+                .map(|(_, policies)| policies)
+        } else {
+            self.policy_tree.get(normalize(&uses.owner).as_ref())
+        };
+        let Some(policies) = policies else {
+            return (None, self.default_policy, &self.default_source);
+        };
+
+        let repo_policies = if policies.repos.len() <= SCAN_LIMIT {
+            policies
+                .repos
+                .iter()
+                .find(|(repo, _)| repo.eq_ignore_ascii_case(&uses.repo))
+                .map(|(_, policies)| policies)
+        } else {
+            policies.repos.get(normalize(&uses.repo).as_ref())
+        };
+
+        // Repository policies are ordered by specificity, and are all
+        // more specific than the owner policy, so we can iterate and
+        // return eagerly.
+        for (uses_pattern, policy, source) in repo_policies.into_iter().flatten() {
+            if uses_pattern.matches(uses) {
+                return (Some(uses_pattern), *policy, source);
             }
-            None => (None, self.default_policy),
         }
+        if let Some((uses_pattern, policy, source)) = &policies.owner {
+            return (Some(uses_pattern), *policy, source);
+        }
+
+        // The policies under `owner/` might be fully divergent
+        // if there isn't an `owner/*` rule, so we fall back
+        // to the default policy.
+        (None, self.default_policy, &self.default_source)
+    }
+
+    /// Marks every policy as coming from the `unpinned-uses` configuration
+    /// in `config`, except for an implicit `*` policy. `route` is the
+    /// route to these policies within the rule's configuration.
+    fn with_config_sources(mut self, config: &Config, route: &[RouteComponent<'_>]) -> Self {
+        let Some(origin) = config.origin() else {
+            return self;
+        };
+        let point = |pattern: &RepositoryUsesPattern| {
+            let mut route = route.to_vec();
+            route.push(RouteComponent::Key(pattern.to_string().into()));
+            config.rule_config_point(UnpinnedUses::ident(), &route)
+        };
+
+        for (pattern, _, source) in self
+            .policy_tree
+            .values_mut()
+            .flat_map(OwnerPolicies::entries_mut)
+        {
+            *source = PolicySource::Config {
+                origin: origin.into(),
+                point: point(pattern),
+            };
+        }
+        if let Some(point) = point(&RepositoryUsesPattern::Any) {
+            self.default_source = PolicySource::Config {
+                origin: origin.into(),
+                point: Some(point),
+            };
+        }
+
+        self
     }
 }
 
@@ -346,40 +1754,52 @@ impl TryFrom<UnpinnedUsesConfig> for UnpinnedUsesPolicies {
     type Error = anyhow::Error;
 
     fn try_from(config: UnpinnedUsesConfig) -> Result<Self, Self::Error> {
-        let mut policy_tree: HashMap<String, Vec<(RepositoryUsesPattern, UsesPolicy)>> =
-            HashMap::new();
+        config.policies.try_into()
+    }
+}
+
+impl TryFrom<HashMap<RepositoryUsesPattern, UsesPolicy>> for UnpinnedUsesPolicies {
+    type Error = anyhow::Error;
+
+    fn try_from(policies: HashMap<RepositoryUsesPattern, UsesPolicy>) -> Result<Self, Self::Error> {
+        let mut policy_tree: HashMap<String, OwnerPolicies> = HashMap::new();
         let mut default_policy = UsesPolicy::HashPin;
 
-        for (pattern, policy) in config.policies {
-            match &pattern {
+        for (pattern, &policy) in &policies {
+            match pattern {
                 // Patterns with refs don't make sense in this context, since
                 // we're establishing policies for the refs themselves.
-                RepositoryUsesPattern::ExactWithRef { .. } => {
-                    return Err(anyhow::anyhow!("can't use exact ref patterns here"));
-                }
-                RepositoryUsesPattern::ExactPath { owner, .. } => {
-                    policy_tree
-                        .entry(owner.clone())
-                        .or_default()
-                        .push((pattern, policy));
+                RepositoryUsesPattern::ExactWithRef {
+                    owner,
+                    repo,
+                    subpath,
+                    ..
+                } => {
+                    let without_ref = match subpath {
+                        Some(subpath) => format!("{owner}/{repo}/{subpath}"),
+                        None => format!("{owner}/{repo}"),
+                    };
+                    return Err(anyhow::anyhow!(
+                        "invalid policy pattern `{pattern}`: policies apply to refs, so \
+                         their patterns can't include one (did you mean `{without_ref}`?)"
+                    ));
                 }
-                RepositoryUsesPattern::ExactRepo { owner, .. } => {
+                RepositoryUsesPattern::ExactPath { owner, repo, .. }
+                | RepositoryUsesPattern::ExactRepo { owner, repo }
+                | RepositoryUsesPattern::InRepo { owner, repo } => {
+                    let (owner, repo) = (owner.to_ascii_lowercase(), repo.to_ascii_lowercase());
                     policy_tree
-                        .entry(owner.clone())
+                        .entry(owner)
                         .or_default()
-                        .push((pattern, policy));
-                }
-                RepositoryUsesPattern::InRepo { owner, .. } => {
-                    policy_tree
-                        .entry(owner.clone())
+                        .repos
+                        .entry(repo)
                         .or_default()
-                        .push((pattern, policy));
+                        .push((pattern.clone(), policy, PolicySource::Default));
                 }
                 RepositoryUsesPattern::InOwner(owner) => {
-                    policy_tree
-                        .entry(owner.clone())
-                        .or_default()
-                        .push((pattern, policy));
+                    let owner = owner.to_ascii_lowercase();
+                    policy_tree.entry(owner).or_default().owner =
+                        Some((pattern.clone(), policy, PolicySource::Default));
                 }
                 RepositoryUsesPattern::Any => {
                     default_policy = policy;
@@ -387,14 +1807,675 @@ impl TryFrom<UnpinnedUsesConfig> for UnpinnedUsesPolicies {
             }
         }
 
-        // Sort the policies for each owner by specificity.
-        for policies in policy_tree.values_mut() {
+        // Sort the policies for each repository by specificity, so that
+        // `get_policy` finds the most specific match first.
+        // See `RepositoryUsesPattern::specificity`.
+        for policies in policy_tree
+            .values_mut()
+            .flat_map(|owner| owner.repos.values_mut())
+        {
             policies.sort_by(|a, b| a.0.cmp(&b.0));
         }
 
+        // Patterns are matched case-insensitively, so patterns that only
+        // differ by case (e.g. `foo/bar` and `Foo/Bar`) are duplicates,
+        // even though they're distinct YAML keys.
+        let mut sorted = policies.iter().collect::<Vec<_>>();
+        sorted.sort_by_key(|(pattern, _)| pattern.to_string());
+        let mut normalized: HashMap<NormalizedPattern, (&RepositoryUsesPattern, UsesPolicy)> =
+            HashMap::new();
+        for (pattern, policy) in sorted.iter().copied() {
+            if let Some((existing, _)) =
+                normalized.insert(normalize_pattern(pattern), (pattern, *policy))
+            {
+                return Err(anyhow::anyhow!(
+                    "duplicate policy patterns `{existing}` and `{pattern}`: patterns \
+                     match owners and repositories case-insensitively, so only one of \
+                     them can apply"
+                ));
+            }
+        }
+
+        let mut shadowed = vec![];
+        for (pattern, &policy) in sorted {
+            let Some(&(broader, broader_policy)) = broader_patterns(pattern)
+                .iter()
+                .find_map(|broader| normalized.get(&normalize_pattern(broader)))
+            else {
+                continue;
+            };
+
+            if broader_policy == policy {
+                shadowed.push(format!(
+                    "policy pattern `{pattern}` has no effect, since the broader \
+                     `{broader}` already applies {policy} to everything it matches",
+                    policy = policy.as_str(),
+                ));
+            }
+        }
+
         Ok(Self {
             policy_tree,
             default_policy,
+            default_source: PolicySource::Default,
+            shadowed,
         })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr as _;
+
+    use github_actions_models::common::Uses;
+    use itertools::Itertools as _;
+
+    use super::{
+        AllowedOrgs, OrgTrust, ThirdPartyHandling, UnpinnedUses, UnpinnedUsesConfig,
+        UnpinnedUsesPolicies, UsesPolicy, validate_name,
+    };
+    use crate::{
+        audit::Audit as _, config::Config, github_api::GitHubHost,
+        models::uses::RepositoryUsesPattern, registry::InputKey, state::AuditState,
+    };
+
+    fn resolve(policies: &UnpinnedUsesPolicies, uses: &str) -> Option<String> {
+        let Uses::Repository(uses) = Uses::from_str(uses).unwrap() else {
+            unreachable!()
+        };
+
+        policies
+            .get_policy(&uses)
+            .0
+            .map(|pattern| pattern.to_string())
+    }
+
+    /// The official orgs, plus the entries in the allowlist file at `path`.
+    fn from_file(path: &str) -> AllowedOrgs {
+        let AllowedOrgs {
+            id,
+            mut orgs,
+            mut patterns,
+            mut entries,
+            aliases,
+            floating_tags,
+        } = AllowedOrgs::official();
+        AllowedOrgs::extend_from_file(&mut orgs, &mut patterns, &mut entries, &mut vec![], path)
+            .unwrap();
+        AllowedOrgs {
+            id,
+            orgs,
+            patterns,
+            entries,
+            aliases,
+            floating_tags,
+        }
+    }
+
+    fn policies(patterns: &[&str]) -> UnpinnedUsesPolicies {
+        UnpinnedUsesConfig {
+            policies: patterns
+                .iter()
+                .map(|pattern| {
+                    (
+                        RepositoryUsesPattern::from_str(pattern).unwrap(),
+                        UsesPolicy::Any,
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        }
+        .try_into()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_get_policy_most_specific() {
+        let patterns = ["foo/bar/baz", "foo/bar", "foo/bar/*", "foo/*"];
+
+        for (uses, expected) in [
+            ("foo/bar/baz@v1", Some("foo/bar/baz")),
+            ("foo/bar@v1", Some("foo/bar")),
+            ("foo/bar/quux@v1", Some("foo/bar/*")),
+            ("foo/quux@v1", Some("foo/*")),
+            ("other/bar@v1", None),
+        ] {
+            // Every order of the same patterns must resolve identically,
+            // regardless of how they're laid out in the config.
+            for order in patterns.iter().copied().permutations(patterns.len()) {
+                let policies = policies(&order);
+                assert_eq!(
+                    resolve(&policies, uses).as_deref(),
+                    expected,
+                    "{uses} with {order:?}"
+                );
+            }
+        }
+
+        // Without the most specific pattern, the next most specific wins.
+        for (patterns, expected) in [
+            (&["foo/bar", "foo/bar/*", "foo/*"][..], "foo/bar/*"),
+            (&["foo/bar", "foo/*"], "foo/*"),
+            (&["foo/*"], "foo/*"),
+        ] {
+            assert_eq!(
+                resolve(&policies(patterns), "foo/bar/baz@v1").as_deref(),
+                Some(expected)
+            );
+        }
+    }
+
+    /// Reifies `policies` (as YAML), returning its shadowed pattern warnings.
+    fn shadowed(policies: &str) -> anyhow::Result<Vec<String>> {
+        let policies: HashMap<RepositoryUsesPattern, UsesPolicy> =
+            serde_yaml::from_str(policies).unwrap();
+        UnpinnedUsesPolicies::try_from(policies).map(|policies| policies.shadowed)
+    }
+
+    #[test]
+    fn test_duplicate_patterns() {
+        for (policies, expected) in [
+            (
+                "foo/bar: ref-pin\nFoo/Bar: hash-pin",
+                "duplicate policy patterns `Foo/Bar` and `foo/bar`",
+            ),
+            (
+                "foo/*: ref-pin\nFOO/*: ref-pin",
+                "duplicate policy patterns `FOO/*` and `foo/*`",
+            ),
+            (
+                "foo/bar/*: any\nfoo/BAR/*: any",
+                "duplicate policy patterns `foo/BAR/*` and `foo/bar/*`",
+            ),
+        ] {
+            let err = shadowed(policies).unwrap_err().to_string();
+            assert!(err.starts_with(expected), "{policies}: {err}");
+        }
+
+        // Subpaths are matched case-sensitively, so these are distinct.
+        assert!(shadowed("foo/bar/Sub: ref-pin\nfoo/bar/sub: any").is_ok());
+    }
+
+    #[test]
+    fn test_shadowed_patterns() {
+        for (policies, expected) in [
+            (
+                "foo/*: hash-pin\nfoo/bar/*: hash-pin",
+                &[
+                    "policy pattern `foo/bar/*` has no effect, since the broader `foo/*` \
+                   already applies hash-pin to everything it matches",
+                ][..],
+            ),
+            (
+                "'*': ref-pin\nfoo/*: ref-pin\nfoo/bar: hash-pin",
+                &[
+                    "policy pattern `foo/*` has no effect, since the broader `*` \
+                   already applies ref-pin to everything it matches",
+                ],
+            ),
+            // Only the most specific broader pattern counts: `foo/bar/baz`
+            // would fall back to `foo/bar/*`, not `foo/*`.
+            (
+                "foo/*: any\nfoo/bar/*: ref-pin\nfoo/bar/baz: ref-pin\nfoo/bar: any",
+                &[
+                    "policy pattern `foo/bar/baz` has no effect, since the broader \
+                   `foo/bar/*` already applies ref-pin to everything it matches",
+                ],
+            ),
+            (
+                "foo/*: any\nfoo/bar: any\nfoo/bar/baz: any",
+                &[
+                    "policy pattern `foo/bar` has no effect, since the broader `foo/*` \
+                     already applies any to everything it matches",
+                    "policy pattern `foo/bar/baz` has no effect, since the broader `foo/*` \
+                     already applies any to everything it matches",
+                ],
+            ),
+        ] {
+            assert_eq!(shadowed(policies).unwrap(), expected, "{policies}");
+        }
+    }
+
+    #[test]
+    fn test_specific_overrides_broad() {
+        for policies in [
+            // A narrower pattern with a looser or stricter policy than
+            // a broader one always applies to what it matches.
+            "'*': hash-pin\nactions/*: ref-pin\ngithub/*: ref-pin",
+            "foo/*: hash-pin\nfoo/bar: ref-pin\nfoo/bar/*: any",
+            "foo/*: ref-pin\nfoo/bar/baz: hash-pin",
+            // `foo/bar` doesn't match subpaths, so it never covers `foo/bar/baz`.
+            "foo/bar: hash-pin\nfoo/bar/baz: hash-pin",
+            // Without an explicit `*`, nothing is broader than `owner/*`.
+            "foo/*: hash-pin",
+        ] {
+            assert_eq!(
+                shadowed(policies).unwrap(),
+                Vec::<String>::new(),
+                "{policies}"
+            );
+        }
+
+        // ...including the default policies.
+        let policies = UnpinnedUsesPolicies::try_from(UnpinnedUsesConfig::default()).unwrap();
+        assert!(policies.shadowed.is_empty());
+    }
+
+    #[test]
+    fn test_get_policy_large_config() {
+        // One exact policy per repository, as in a generated config.
+        let mut patterns = (0..5000)
+            .map(|n| format!("internal/action-{n}"))
+            .collect::<Vec<_>>();
+        patterns.extend(["internal/action-7/sub".into(), "internal/*".into()]);
+        // Enough owners that they're looked up rather than scanned.
+        patterns.extend((0..20).map(|n| format!("org-{n}/*")));
+        let policies = policies(&patterns.iter().map(String::as_str).collect::<Vec<_>>());
+
+        for (uses, expected) in [
+            ("internal/action-0@v1", Some("internal/action-0")),
+            ("internal/action-4999@v1", Some("internal/action-4999")),
+            ("internal/action-7/sub@v1", Some("internal/action-7/sub")),
+            ("internal/action-7/other@v1", Some("internal/*")),
+            ("internal/action-5000@v1", Some("internal/*")),
+            // Owners and repositories are matched case-insensitively.
+            ("Internal/Action-42@v1", Some("internal/action-42")),
+            ("INTERNAL/unknown@v1", Some("internal/*")),
+            ("Org-13/action@v1", Some("org-13/*")),
+            ("external/action-0@v1", None),
+        ] {
+            assert_eq!(resolve(&policies, uses).as_deref(), expected, "{uses}");
+        }
+    }
+
+    /// Times `rounds` rounds of `f` over each of `uses`, returning the mean
+    /// time per call in nanoseconds for the fastest of several samples.
+    fn time_per_call(uses: &[&str], rounds: usize, f: impl Fn(&Uses)) -> f64 {
+        const SAMPLES: usize = 10;
+
+        let uses = uses
+            .iter()
+            .map(|uses| Uses::from_str(uses).unwrap())
+            .collect::<Vec<_>>();
+
+        (0..SAMPLES)
+            .map(|_| {
+                let start = std::time::Instant::now();
+                for _ in 0..rounds {
+                    for uses in &uses {
+                        f(std::hint::black_box(uses));
+                    }
+                }
+                start.elapsed().as_nanos() as f64 / (rounds * uses.len()) as f64
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// The small and generated 5000-entry policy sets that the benchmarks
+    /// use, each with the `uses:` clauses to look up against it.
+    fn bench_configs() -> [(&'static str, UnpinnedUsesPolicies, Vec<String>, usize); 2] {
+        let small = policies(&["actions/*", "foo/bar", "foo/bar/baz", "foo/*", "github/*"]);
+        let small_uses = [
+            "actions/checkout@v4",
+            "foo/bar@v1",
+            "foo/bar/baz@v1",
+            "foo/other@v1",
+            "external/action@v1",
+        ]
+        .map(String::from)
+        .to_vec();
+
+        let mut patterns = (0..5000)
+            .map(|n| format!("internal/action-{n}"))
+            .collect::<Vec<_>>();
+        patterns.push("internal/*".into());
+        let large = policies(&patterns.iter().map(String::as_str).collect::<Vec<_>>());
+        let large_uses = (0..5000)
+            .step_by(50)
+            .map(|n| format!("internal/action-{n}@v1"))
+            .chain(["internal/unknown@v1".into(), "external/action@v1".into()])
+            .collect::<Vec<_>>();
+
+        [
+            ("small", small, small_uses, 100_000),
+            ("large", large, large_uses, 100),
+        ]
+    }
+
+    /// Benchmarks `get_policy` against a small config and a generated
+    /// 5000-entry config. Run with:
+    ///
+    /// ```text
+    /// cargo test --release --bin zizmor bench_get_policy -- --ignored --nocapture
+    /// ```
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_get_policy() {
+        for (name, policies, uses, rounds) in bench_configs() {
+            let uses = uses.iter().map(String::as_str).collect::<Vec<_>>();
+            let ns = time_per_call(&uses, rounds, |uses| {
+                let Uses::Repository(uses) = uses else {
+                    unreachable!()
+                };
+                std::hint::black_box(policies.get_policy(uses));
+            });
+            eprintln!("{name} config: {ns:.0} ns/lookup");
+        }
+    }
+
+    /// Benchmarks `evaluate_pinning`, i.e. everything that `unpinned-uses`
+    /// does for a single `uses:` clause, against the same configs as
+    /// `bench_get_policy`. Third-party actions are evaluated against the
+    /// policies, so that every clause reaches `get_policy`. Run with:
+    ///
+    /// ```text
+    /// cargo test --release --bin zizmor bench_evaluate_pinning -- --ignored --nocapture
+    /// ```
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_evaluate_pinning() {
+        let config = Config::default();
+        let state = AuditState {
+            config: &config,
+            online: false,
+            cache_dir: "/tmp/zizmor".into(),
+            gh_token: None,
+            gh_hostname: GitHubHost::Standard("github.com".into()),
+            tpa_allowlist_file: None,
+            tpa_allowed_org: None,
+            lenient_allowlist: false,
+            advisories_file: None,
+            osv: false,
+            lockfile: None,
+            resolver: Default::default(),
+        };
+        let key = InputKey::local("fake", None).unwrap();
+
+        for (name, policies, uses, rounds) in bench_configs() {
+            let mut audit = UnpinnedUses::new(&state).unwrap();
+            audit.policies = policies;
+            audit.third_party_handling = ThirdPartyHandling::Policy;
+
+            let uses = uses.iter().map(String::as_str).collect::<Vec<_>>();
+            let ns = time_per_call(&uses, rounds, |uses| {
+                std::hint::black_box(audit.evaluate_pinning(&key, uses));
+            });
+            eprintln!("{name} config: {ns:.0} ns/evaluation");
+        }
+    }
+
+    #[test]
+    fn test_allowlist_entries() {
+        for (entry, expected) in [
+            ("trustedorg", Some(("trustedorg", OrgTrust::Full))),
+            ("TrustedOrg", Some(("trustedorg", OrgTrust::Full))),
+            ("trustedorg ref-pin", Some(("trustedorg", OrgTrust::RefPin))),
+            (
+                "  trustedorg   ref-pin ",
+                Some(("trustedorg", OrgTrust::RefPin)),
+            ),
+            ("trustedorg hash-pin", None),
+            ("trustedorg ref-pin extra", None),
+            ("", None),
+            (
+                "Partner/Deploy-Action",
+                Some(("partner/deploy-action", OrgTrust::Full)),
+            ),
+        ] {
+            assert_eq!(
+                AllowedOrgs::parse_entry(entry).ok(),
+                expected.map(|(org, trust)| (org.to_string(), trust)),
+                "{entry}"
+            );
+        }
+
+        // The strictest entry for an org wins, regardless of order.
+        for entries in [
+            ["trustedorg", "trustedorg ref-pin"],
+            ["trustedorg ref-pin", "trustedorg"],
+        ] {
+            let mut orgs = Default::default();
+            for entry in entries {
+                AllowedOrgs::insert_entry(&mut orgs, entry).unwrap();
+            }
+            let orgs = AllowedOrgs {
+                id: 0,
+                orgs,
+                patterns: vec![],
+                entries: vec![],
+                aliases: Default::default(),
+                floating_tags: vec![],
+            };
+
+            assert!(orgs.contains("trustedorg", "action"));
+            assert!(orgs.requires_ref_pin("TrustedOrg", "action"));
+        }
+    }
+
+    #[test]
+    fn test_validate_name() {
+        for name in [
+            "trustedorg",
+            "acme-corp",
+            "a",
+            "0x",
+            &"a".repeat(39),
+            "partner/deploy-action",
+            "partner/.github",
+            "partner/my_action.v2",
+        ] {
+            assert_eq!(validate_name(name), Ok(()), "{name}");
+        }
+
+        for (name, reason) in [
+            ("", "owner names must be 1 to 39 characters long"),
+            (
+                &"a".repeat(40),
+                "owner names must be 1 to 39 characters long",
+            ),
+            (
+                "acme_corp",
+                "owner names may only contain alphanumerics and hyphens",
+            ),
+            ("-acme", "owner names can't begin or end with a hyphen"),
+            ("acme-", "owner names can't begin or end with a hyphen"),
+            (
+                "acme-corp/",
+                "repository names must be 1 to 100 characters long",
+            ),
+            ("/action", "owner names must be 1 to 39 characters long"),
+            (
+                "acme/tools/action",
+                "repository names may only contain alphanumerics, hyphens, underscores, and periods",
+            ),
+            ("acme/..", "`.` and `..` aren't valid repository names"),
+        ] {
+            assert_eq!(validate_name(name), Err(reason), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_allowlist_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("allowlist.txt");
+        std::fs::write(
+            &path,
+            "trustedorg\nacme-corp/\n# acme corp\nacme corp\nre:acme-[a-z]+ hash-pin\n\
+             partner/deploy-action ref-pin\n",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+
+        let mut orgs = Default::default();
+        let mut entries = vec![];
+        let mut invalid = vec![];
+        AllowedOrgs::extend_from_file(&mut orgs, &mut vec![], &mut entries, &mut invalid, path)
+            .unwrap();
+
+        // Invalid lines are collected with their line numbers, and skipped.
+        assert_eq!(
+            invalid,
+            [
+                format!(
+                    "{path}:2: invalid allowlist entry `acme-corp/`: \
+                     repository names must be 1 to 100 characters long"
+                ),
+                format!("{path}:4: invalid allowlist entry `acme corp`: unknown qualifier `corp`"),
+                format!(
+                    "{path}:5: invalid allowlist entry `re:acme-[a-z]+ hash-pin`: \
+                     unknown qualifier `hash-pin`"
+                ),
+            ]
+        );
+        assert_eq!(
+            entries.iter().map(|entry| &entry.name).collect::<Vec<_>>(),
+            ["trustedorg", "partner/deploy-action"]
+        );
+
+        // `re:` entries are only supported in files.
+        assert_eq!(
+            AllowedOrgs::insert_entry(&mut orgs, "re:acme-[a-z]+"),
+            Err("`re:` entries are only supported in allowlist files".into())
+        );
+
+        // Exact `owner/repo` entries only allowlist that repository.
+        let orgs = AllowedOrgs {
+            orgs,
+            ..AllowedOrgs::official()
+        };
+        assert!(orgs.requires_ref_pin("Partner", "Deploy-Action"));
+        assert!(!orgs.contains("partner", "other-action"));
+        assert!(
+            orgs.unused([("partner", "deploy-action")])
+                .iter()
+                .all(|entry| entry.name != "partner/deploy-action")
+        );
+    }
+
+    #[test]
+    fn test_allowlist_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("allowlist.txt");
+        std::fs::write(
+            &path,
+            "# Our team orgs.\nre:acme-(team|squad)-[a-z]+\nacme-team-exact ref-pin\n\
+             re:partner/[a-z]+-action ref-pin\n",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+
+        let orgs = from_file(path);
+
+        // Patterns match the entire (lowercased) owner...
+        assert!(orgs.contains("acme-team-web", "tool"));
+        assert!(orgs.contains("ACME-Squad-Infra", "tool"));
+        assert!(!orgs.contains("acme-team-", "tool"));
+        assert!(!orgs.contains("notacme-team-web", "tool"));
+        assert!(!orgs.contains("acme-team-web2", "tool"));
+        assert!(!orgs.requires_ref_pin("acme-team-web", "tool"));
+
+        // ...but exact entries are matched first.
+        assert!(orgs.requires_ref_pin("acme-team-exact", "tool"));
+
+        // Patterns with a `/` match the entire `owner/repo`.
+        assert!(orgs.requires_ref_pin("partner", "deploy-action"));
+        assert!(!orgs.contains("partner", "deploy"));
+        assert!(!orgs.contains("partner", "deploy-action-2"));
+
+        // Invalid patterns fail with their line number.
+        std::fs::write(path, "trustedorg\nre:acme-(team\n").unwrap();
+        let err = AllowedOrgs::extend_from_file(
+            &mut Default::default(),
+            &mut vec![],
+            &mut vec![],
+            &mut vec![],
+            path,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with(&format!("{path}:2: invalid allowlist pattern `acme-(team`")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_unused_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("allowlist.txt");
+        std::fs::write(
+            &path,
+            "usedorg
+  UnusedOrg ref-pin
+re:acme-[a-z]+
+re:partner/[a-z]+
+usedorg
+",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+        let orgs = from_file(path);
+
+        // Nothing is used, so every entry is unused (but only once).
+        let unused = |observed: &[(&str, &str)]| {
+            orgs.unused(observed.iter().copied())
+                .into_iter()
+                .map(|entry| entry.location.render())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            unused(&[]),
+            [
+                format!("`usedorg` is allowlisted at {path}:1:1"),
+                format!("`unusedorg` is allowlisted at {path}:2:3"),
+                format!("`re:acme-[a-z]+` is allowlisted at {path}:3:1"),
+                format!("`re:partner/[a-z]+` is allowlisted at {path}:4:1"),
+            ]
+        );
+
+        // Official orgs don't have entries, and so are never unused.
+        assert_eq!(
+            unused(&[
+                ("actions", "checkout"),
+                ("UsedOrg", "tool"),
+                ("acme-web", "tool"),
+                ("partner", "tool"),
+            ]),
+            [format!("`unusedorg` is allowlisted at {path}:2:3")]
+        );
+
+        // An owner with an exact entry doesn't use a matching pattern.
+        std::fs::write(
+            path,
+            "acme-web
+re:acme-[a-z]+
+",
+        )
+        .unwrap();
+        let orgs = from_file(path);
+        assert_eq!(
+            orgs.unused([("acme-web", "tool")])
+                .into_iter()
+                .map(|entry| entry.name.as_str())
+                .collect::<Vec<_>>(),
+            ["re:acme-[a-z]+"]
+        );
+    }
+
+    #[test]
+    fn test_owner_aliases() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("allowlist.txt");
+        std::fs::write(&path, "neworg\n").unwrap();
+        let mut orgs = from_file(path.to_str().unwrap());
+        orgs.aliases = [("oldorg".into(), "NewOrg".into())].into();
+
+        assert_eq!(orgs.alias_of("OldOrg"), Some("NewOrg"));
+        assert_eq!(orgs.alias_of("neworg"), None);
+
+        // Aliases are allowlisted (and used) under their canonical owner.
+        assert!(orgs.contains("OldOrg", "action"));
+        assert!(orgs.unused([("oldorg", "action")]).is_empty());
+    }
+}
@@ -13,7 +13,8 @@ pub(crate) struct UnredactedSecrets;
 audit_meta!(
     UnredactedSecrets,
     "unredacted-secrets",
-    "leaked secret values"
+    "leaked secret values",
+    Severity::Medium
 );
 
 impl Audit for UnredactedSecrets {
@@ -26,7 +26,8 @@ pub(crate) struct UnsoundContains;
 audit_meta!(
     UnsoundContains,
     "unsound-contains",
-    "unsound contains condition"
+    "unsound contains condition",
+    Severity::High
 );
 
 impl Audit for UnsoundContains {
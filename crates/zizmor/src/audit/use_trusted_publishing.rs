@@ -26,7 +26,8 @@ pub(crate) struct UseTrustedPublishing;
 audit_meta!(
     UseTrustedPublishing,
     "use-trusted-publishing",
-    "prefer trusted publishing for authentication"
+    "prefer trusted publishing for authentication",
+    Severity::Informational
 );
 
 impl UseTrustedPublishing {
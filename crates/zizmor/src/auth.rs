@@ -0,0 +1,349 @@
+//! Credentials for the GitHub API.
+//!
+//! A token is taken from `--gh-token`, or else from the first of
+//! [`TOKEN_VARS`] that's set. Alternatively, with `--gh-app-id` and
+//! `--gh-app-key-path`, zizmor authenticates as an installation of a
+//! GitHub App, which has far higher rate limits than a personal token.
+//!
+//! See: <https://docs.github.com/en/apps/creating-github-apps/authenticating-with-a-github-app/authenticating-as-a-github-app-installation>
+
+use std::fmt::Display;
+use std::io::BufReader;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context as _, Result, anyhow};
+use base64::Engine as _;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use camino::Utf8Path;
+use reqwest::StatusCode;
+use reqwest::header::{ACCEPT, AUTHORIZATION, USER_AGENT};
+use ring::rand::SystemRandom;
+use ring::signature::{RSA_PKCS1_SHA256, RsaKeyPair};
+use rustls_pemfile::Item;
+use serde::{Deserialize, Serialize};
+
+use crate::github_api::GitHubHost;
+
+/// The environment variables that a token is taken from, in order,
+/// when `--gh-token` isn't given.
+pub(crate) const TOKEN_VARS: &[&str] = &["ZIZMOR_GH_TOKEN", "GH_TOKEN", "GITHUB_TOKEN"];
+
+/// How long each GitHub App JWT is valid for. GitHub allows at most
+/// ten minutes.
+const JWT_LIFETIME: Duration = Duration::from_secs(9 * 60);
+
+/// How far each GitHub App JWT is backdated, to allow for clock drift.
+const JWT_BACKDATE: Duration = Duration::from_secs(60);
+
+/// Where a resolved token came from.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum TokenSource {
+    /// `--gh-token`.
+    Flag,
+    /// One of [`TOKEN_VARS`].
+    Env(&'static str),
+    /// An installation token for a GitHub App.
+    App { app_id: u64, installation_id: u64 },
+}
+
+impl Display for TokenSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenSource::Flag => write!(f, "a token from --gh-token"),
+            TokenSource::Env(var) => write!(f, "a token from {var}"),
+            TokenSource::App {
+                app_id,
+                installation_id,
+            } => write!(f, "GitHub App {app_id} (installation {installation_id})"),
+        }
+    }
+}
+
+/// Returns the token given by `flag`, or else by the first of
+/// [`TOKEN_VARS`] that `var` returns a non-empty value for.
+pub(crate) fn token_from(
+    flag: Option<&str>,
+    var: impl Fn(&str) -> Option<String>,
+) -> Option<(String, TokenSource)> {
+    if let Some(token) = flag.filter(|token| !token.is_empty()) {
+        return Some((token.into(), TokenSource::Flag));
+    }
+
+    TOKEN_VARS.iter().find_map(|name| {
+        var(name)
+            .filter(|token| !token.is_empty())
+            .map(|token| (token, TokenSource::Env(name)))
+    })
+}
+
+#[derive(Serialize)]
+struct Claims {
+    iat: u64,
+    exp: u64,
+    iss: String,
+}
+
+#[derive(Deserialize)]
+struct Installation {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+struct AccessToken {
+    token: String,
+}
+
+/// Which of a GitHub App's installations to authenticate as.
+pub(crate) enum InstallationScope<'a> {
+    /// The installation on an organization, i.e. the one given by `--org`.
+    Org(&'a str),
+    /// The installation that can access a repository.
+    Repo { owner: &'a str, repo: &'a str },
+    /// The app's only installation.
+    Only,
+}
+
+/// A GitHub App, authenticated by its private key.
+pub(crate) struct GitHubApp {
+    app_id: u64,
+    key: RsaKeyPair,
+}
+
+impl GitHubApp {
+    /// Loads the app's private key (as downloaded from GitHub, i.e. a
+    /// PKCS#1 or PKCS#8 PEM file) from `key_path`.
+    pub(crate) fn load(app_id: u64, key_path: &Utf8Path) -> Result<Self> {
+        let load = || -> Result<RsaKeyPair> {
+            let file = std::fs::File::open(key_path)?;
+            let key = match rustls_pemfile::read_one(&mut BufReader::new(file))? {
+                Some(Item::Pkcs1Key(key)) => RsaKeyPair::from_der(key.secret_pkcs1_der()),
+                Some(Item::Pkcs8Key(key)) => RsaKeyPair::from_pkcs8(key.secret_pkcs8_der()),
+                _ => return Err(anyhow!("not a PEM-encoded RSA private key")),
+            };
+
+            key.map_err(|e| anyhow!("invalid RSA private key: {e}"))
+        };
+
+        let key = load().with_context(|| format!("couldn't load GitHub App key {key_path}"))?;
+        Ok(Self { app_id, key })
+    }
+
+    /// A JWT that authenticates as the app itself, issued at `now`
+    /// (in seconds since the Unix epoch).
+    fn jwt(&self, now: u64) -> Result<String> {
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"RS256","typ":"JWT"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&Claims {
+            iat: now - JWT_BACKDATE.as_secs(),
+            exp: now + JWT_LIFETIME.as_secs(),
+            iss: self.app_id.to_string(),
+        })?);
+        let message = format!("{header}.{claims}");
+
+        let mut signature = vec![0; self.key.public().modulus_len()];
+        self.key
+            .sign(
+                &RSA_PKCS1_SHA256,
+                &SystemRandom::new(),
+                message.as_bytes(),
+                &mut signature,
+            )
+            .map_err(|_| anyhow!("couldn't sign GitHub App JWT"))?;
+
+        Ok(format!(
+            "{message}.{signature}",
+            signature = URL_SAFE_NO_PAD.encode(signature)
+        ))
+    }
+
+    /// Returns the ID of the installation that `request` looks up.
+    async fn installation(&self, request: reqwest::RequestBuilder) -> Result<u64> {
+        let resp = request.send().await?;
+
+        match resp.status() {
+            StatusCode::NOT_FOUND => Err(anyhow!(
+                "GitHub App {app_id} isn't installed there",
+                app_id = self.app_id
+            )),
+            _ => Ok(resp.error_for_status()?.json::<Installation>().await?.id),
+        }
+    }
+
+    /// Returns an installation token for the app's installation in
+    /// `scope`, and the installation that it's for.
+    #[tokio::main]
+    pub(crate) async fn installation_token(
+        &self,
+        host: &GitHubHost,
+        scope: InstallationScope<'_>,
+    ) -> Result<(String, TokenSource)> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let jwt = self.jwt(now)?;
+        let api_base = host.to_api_url();
+
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?;
+        let request = |method: reqwest::Method, url: String| {
+            http.request(method, url)
+                .header(USER_AGENT, "zizmor")
+                .header(ACCEPT, "application/vnd.github+json")
+                .header("X-GitHub-Api-Version", "2022-11-28")
+                .header(AUTHORIZATION, format!("Bearer {jwt}"))
+        };
+
+        let installation_id = match scope {
+            InstallationScope::Org(org) => self
+                .installation(request(
+                    reqwest::Method::GET,
+                    format!("{api_base}/orgs/{org}/installation"),
+                ))
+                .await
+                .with_context(|| format!("couldn't find installation on {org}"))?,
+            InstallationScope::Repo { owner, repo } => self
+                .installation(request(
+                    reqwest::Method::GET,
+                    format!("{api_base}/repos/{owner}/{repo}/installation"),
+                ))
+                .await
+                .with_context(|| format!("couldn't find installation on {owner}/{repo}"))?,
+            InstallationScope::Only => {
+                let installations = request(
+                    reqwest::Method::GET,
+                    format!("{api_base}/app/installations"),
+                )
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<Vec<Installation>>()
+                .await?;
+
+                match installations.as_slice() {
+                    [installation] => installation.id,
+                    [] => {
+                        return Err(anyhow!(
+                            "GitHub App {app_id} has no installations",
+                            app_id = self.app_id
+                        ));
+                    }
+                    _ => {
+                        return Err(anyhow!(
+                            "GitHub App {app_id} has {n} installations; pass --org or a \
+                             remote input to choose one",
+                            app_id = self.app_id,
+                            n = installations.len()
+                        ));
+                    }
+                }
+            }
+        };
+
+        let token = request(
+            reqwest::Method::POST,
+            format!("{api_base}/app/installations/{installation_id}/access_tokens"),
+        )
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<AccessToken>()
+        .await?
+        .token;
+
+        Ok((
+            token,
+            TokenSource::App {
+                app_id: self.app_id,
+                installation_id,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine as _;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use camino::Utf8Path;
+    use ring::signature::{RSA_PKCS1_2048_8192_SHA256, UnparsedPublicKey};
+
+    use super::{GitHubApp, TokenSource, token_from};
+
+    #[test]
+    fn test_token_from() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        // `--gh-token` wins, then each variable in order.
+        assert_eq!(
+            token_from(Some("flag"), env(&[("GH_TOKEN", "gh")])),
+            Some(("flag".into(), TokenSource::Flag))
+        );
+        assert_eq!(
+            token_from(
+                None,
+                env(&[
+                    ("GITHUB_TOKEN", "github"),
+                    ("GH_TOKEN", "gh"),
+                    ("ZIZMOR_GH_TOKEN", "zizmor")
+                ])
+            ),
+            Some(("zizmor".into(), TokenSource::Env("ZIZMOR_GH_TOKEN")))
+        );
+        assert_eq!(
+            token_from(None, env(&[("GITHUB_TOKEN", "github"), ("GH_TOKEN", "gh")])),
+            Some(("gh".into(), TokenSource::Env("GH_TOKEN")))
+        );
+
+        // Empty values are skipped.
+        assert_eq!(
+            token_from(
+                Some(""),
+                env(&[("ZIZMOR_GH_TOKEN", ""), ("GITHUB_TOKEN", "github")])
+            ),
+            Some(("github".into(), TokenSource::Env("GITHUB_TOKEN")))
+        );
+        assert_eq!(token_from(None, env(&[])), None);
+    }
+
+    #[test]
+    fn test_jwt() {
+        let app = GitHubApp::load(
+            1234,
+            Utf8Path::new(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/integration/test-data/github-app/test-key.pem"
+            )),
+        )
+        .unwrap();
+
+        let jwt = app.jwt(1_700_000_000).unwrap();
+        let (message, signature) = jwt.rsplit_once('.').unwrap();
+        let (header, claims) = message.split_once('.').unwrap();
+
+        assert_eq!(
+            URL_SAFE_NO_PAD.decode(header).unwrap(),
+            br#"{"alg":"RS256","typ":"JWT"}"#
+        );
+        assert_eq!(
+            URL_SAFE_NO_PAD.decode(claims).unwrap(),
+            br#"{"iat":1699999940,"exp":1700000540,"iss":"1234"}"#
+        );
+
+        let public_key = UnparsedPublicKey::new(
+            &RSA_PKCS1_2048_8192_SHA256,
+            ring::signature::KeyPair::public_key(&app.key).as_ref(),
+        );
+        public_key
+            .verify(
+                message.as_bytes(),
+                &URL_SAFE_NO_PAD.decode(signature).unwrap(),
+            )
+            .unwrap();
+
+        assert!(GitHubApp::load(1234, Utf8Path::new("/does/not/exist.pem")).is_err());
+    }
+}
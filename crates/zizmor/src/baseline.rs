@@ -0,0 +1,136 @@
+//! Baselines, i.e. sets of known findings that shouldn't fail a run.
+//!
+//! A baseline records the [`Finding::fingerprint`] of every finding
+//! at the time it was generated. Subsequent runs with the same baseline
+//! demote matching findings to a separate "baselined" bucket, so that
+//! only newly introduced findings affect the exit code.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::BufWriter;
+
+use anyhow::{Context as _, Result};
+use camino::Utf8Path;
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+use crate::finding::Finding;
+
+/// The current version of the baseline file format.
+const BASELINE_VERSION: u32 = 1;
+
+/// A single baselined finding.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub(crate) struct BaselineEntry {
+    /// The finding's fingerprint; the only field used for matching.
+    fingerprint: String,
+    /// The finding's audit, for human reviewers of the baseline.
+    ident: String,
+    /// The finding's path, for human reviewers of the baseline.
+    path: String,
+}
+
+/// The on-disk representation of a [`Baseline`].
+#[derive(Debug, Deserialize, Serialize)]
+struct BaselineFile {
+    version: u32,
+    findings: Vec<BaselineEntry>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Baseline {
+    fingerprints: HashSet<String>,
+}
+
+impl Baseline {
+    /// Load a baseline from the given path.
+    pub(crate) fn load(path: &Utf8Path) -> Result<Self> {
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("couldn't read baseline {path}"))?;
+
+        let file = serde_json::from_str::<BaselineFile>(&contents)
+            .with_context(|| format!("invalid baseline {path}"))?;
+
+        if file.version != BASELINE_VERSION {
+            anyhow::bail!(
+                "unsupported baseline version {version} in {path} (expected {BASELINE_VERSION})",
+                version = file.version,
+            );
+        }
+
+        Ok(Self {
+            fingerprints: file
+                .findings
+                .into_iter()
+                .map(|entry| entry.fingerprint)
+                .collect(),
+        })
+    }
+
+    /// Returns whether the given finding is in this baseline.
+    pub(crate) fn contains(&self, finding: &Finding) -> bool {
+        self.fingerprints.contains(finding.fingerprint())
+    }
+
+    /// Atomically write a baseline containing `entries` to `path`.
+    ///
+    /// Entries are sorted and deduplicated, so that regenerating an
+    /// unchanged baseline produces an identical file.
+    pub(crate) fn write(
+        path: &Utf8Path,
+        entries: impl IntoIterator<Item = BaselineEntry>,
+    ) -> Result<()> {
+        let mut entries = entries.into_iter().collect::<Vec<_>>();
+        entries.sort();
+        entries.dedup();
+
+        let file = BaselineFile {
+            version: BASELINE_VERSION,
+            findings: entries,
+        };
+
+        let write = || -> Result<()> {
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_str().is_empty() => dir,
+                _ => Utf8Path::new("."),
+            };
+            fs::create_dir_all(dir)?;
+
+            let mut writer = BufWriter::new(NamedTempFile::new_in(dir)?);
+            serde_json::to_writer_pretty(&mut writer, &file)?;
+            writer.into_inner()?.persist(path)?;
+
+            Ok(())
+        };
+
+        write().with_context(|| format!("couldn't write baseline {path}"))
+    }
+}
+
+impl From<&Finding<'_>> for BaselineEntry {
+    fn from(finding: &Finding<'_>) -> Self {
+        Self {
+            fingerprint: finding.fingerprint().into(),
+            ident: finding.ident.into(),
+            // NOTE: Safe unwrap because FindingBuilder::build ensures
+            // a primary location.
+            path: finding
+                .locations
+                .iter()
+                .find(|l| l.symbolic.is_primary())
+                .unwrap()
+                .symbolic
+                .key
+                .sarif_path()
+                .into(),
+        }
+    }
+}
+
+impl FromIterator<String> for Baseline {
+    fn from_iter<I: IntoIterator<Item = String>>(fingerprints: I) -> Self {
+        Self {
+            fingerprints: fingerprints.into_iter().collect(),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Discovering the files that have changed since a git ref, for
+//! `--changed-since`.
+//!
+//! Changes are determined by `git diff` in the current directory's
+//! repository, so uncommitted (including staged) changes count too.
+//! Deleted files are skipped, and renamed files are reported at their
+//! new paths.
+
+use std::collections::HashSet;
+use std::process::Command;
+
+use anyhow::{Context as _, Result, anyhow};
+use camino::{Utf8Path, Utf8PathBuf};
+
+/// The set of files that have changed since a git ref.
+pub(crate) struct ChangedFiles {
+    /// The canonical path of every changed file.
+    paths: HashSet<Utf8PathBuf>,
+}
+
+impl ChangedFiles {
+    /// Runs `git` in `dir` with the given arguments, returning its stdout.
+    fn git(dir: &Utf8Path, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .context("couldn't run git")?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "git {args} failed: {stderr}",
+                args = args.join(" "),
+                stderr = String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        String::from_utf8(output.stdout).context("git produced non-UTF-8 output")
+    }
+
+    /// Discovers the files in `dir`'s repository that have changed
+    /// since `since`.
+    pub(crate) fn discover(dir: &Utf8Path, since: &str) -> Result<Self> {
+        let toplevel = Self::git(dir, &["rev-parse", "--show-toplevel"])?;
+        let toplevel = Utf8Path::new(toplevel.trim_end());
+
+        // NOTE: `--diff-filter=d` excludes deleted files, and `--name-only`
+        // lists renamed files under their new names. `--end-of-options`
+        // keeps a ref like `--output=foo` from being treated as an option.
+        let names = Self::git(
+            dir,
+            &[
+                "diff",
+                "--name-only",
+                "-z",
+                "--diff-filter=d",
+                "--end-of-options",
+                since,
+            ],
+        )?;
+
+        let paths = names
+            .split('\0')
+            .filter(|name| !name.is_empty())
+            // Files can be changed in the diff but missing on disk,
+            // e.g. if they've since been deleted without being staged.
+            .filter_map(|name| toplevel.join(name).canonicalize_utf8().ok())
+            .collect();
+
+        Ok(Self { paths })
+    }
+
+    /// Whether the file at `path` has changed.
+    pub(crate) fn contains(&self, path: &Utf8Path) -> bool {
+        path.canonicalize_utf8()
+            .is_ok_and(|path| self.paths.contains(&path))
+    }
+}
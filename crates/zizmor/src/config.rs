@@ -1,12 +1,24 @@
-use std::{collections::HashMap, fs, num::NonZeroUsize, str::FromStr};
+use std::{collections::HashMap, fmt, fs, marker::PhantomData, num::NonZeroUsize, str::FromStr};
 
 use anyhow::{Context as _, Result, anyhow};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::ValueEnum;
 use serde::{
     Deserialize,
-    de::{self, DeserializeOwned},
+    de::{self, DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, Visitor},
 };
 
-use crate::{App, finding::Finding};
+use globset::{Glob, GlobMatcher};
+use time::Date;
+
+use crate::{
+    App, FailOn, Rollup, RollupCount,
+    finding::{
+        ConcreteLocation, Confidence, Finding, Location, Persona, Point, RouteComponent, Severity,
+    },
+    github_api::GitHubHost,
+    score::ScoreWeights,
+};
 
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct WorkflowRule {
@@ -68,43 +80,416 @@ impl<'de> Deserialize<'de> for WorkflowRule {
 #[derive(Clone, Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct AuditRuleConfig {
+    /// Whether the rule's audit is run at all; see [`Config::rule_enabled`].
+    #[serde(default)]
+    enabled: Option<bool>,
+    /// Overrides the severity of every finding the rule produces.
+    #[serde(default, deserialize_with = "deserialize_value_enum")]
+    severity: Option<Severity>,
+    /// Overrides the confidence of every finding the rule produces.
+    #[serde(default, deserialize_with = "deserialize_value_enum")]
+    confidence: Option<Confidence>,
+    /// Overrides the persona that the rule's findings are filtered at.
+    #[serde(default, deserialize_with = "deserialize_value_enum")]
+    persona: Option<Persona>,
+    /// Overrides the global `sarif` mapping for the rule's findings.
+    #[serde(default)]
+    sarif: SarifMapping,
     #[serde(default)]
     ignore: Vec<WorkflowRule>,
     #[serde(default)]
     config: Option<serde_yaml::Mapping>,
 }
 
+/// A SARIF result level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SarifLevel {
+    None,
+    Note,
+    Warning,
+    Error,
+}
+
+/// A SARIF `security-severity` score, from 0.0 to 10.0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SecuritySeverity(f64);
+
+impl<'de> Deserialize<'de> for SecuritySeverity {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let score = f64::deserialize(deserializer)?;
+        if !(0.0..=10.0).contains(&score) {
+            return Err(de::Error::custom(format!(
+                "invalid security-severity `{score}` (expected 0.0 through 10.0)"
+            )));
+        }
+
+        Ok(Self(score))
+    }
+}
+
+/// An optional value for each [`Severity`].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PerSeverity<T> {
+    unknown: Option<T>,
+    informational: Option<T>,
+    low: Option<T>,
+    medium: Option<T>,
+    high: Option<T>,
+}
+
+// NOTE: Not derived, since that would require `T: Default`.
+impl<T> Default for PerSeverity<T> {
+    fn default() -> Self {
+        Self {
+            unknown: None,
+            informational: None,
+            low: None,
+            medium: None,
+            high: None,
+        }
+    }
+}
+
+impl<T: Copy> PerSeverity<T> {
+    fn get(&self, severity: Severity) -> Option<T> {
+        match severity {
+            Severity::Unknown => self.unknown,
+            Severity::Informational => self.informational,
+            Severity::Low => self.low,
+            Severity::Medium => self.medium,
+            Severity::High => self.high,
+        }
+    }
+}
+
+/// How findings of each severity are presented in SARIF output.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct SarifMapping {
+    #[serde(default)]
+    level: PerSeverity<SarifLevel>,
+    #[serde(default)]
+    security_severity: PerSeverity<SecuritySeverity>,
+}
+
+/// An entry in the configuration's `ignores`, for ignoring findings
+/// that can't be ignored inline, e.g. in generated files.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct IgnoreEntry {
+    /// The ident of the rule whose findings are ignored.
+    rule: String,
+    /// A glob for the paths of the inputs whose findings are ignored.
+    #[serde(deserialize_with = "deserialize_glob")]
+    path: GlobMatcher,
+    /// A prefix of the symbolic location (e.g. `jobs.build.steps[0]`)
+    /// that one of the finding's locations must be at.
+    #[serde(default)]
+    location: Option<String>,
+    /// A substring that one of the finding's annotations must contain.
+    #[serde(default)]
+    annotation: Option<String>,
+    /// Why the findings are ignored.
+    pub(crate) reason: String,
+    /// The date on which this entry stops ignoring findings.
+    #[serde(default, deserialize_with = "deserialize_date")]
+    pub(crate) expires: Option<Date>,
+}
+
+impl IgnoreEntry {
+    /// Whether this entry ignores the given finding.
+    fn matches(&self, finding: &Finding<'_>) -> bool {
+        if finding.ident != self.rule {
+            return false;
+        }
+
+        let matches_location = |location: &Location<'_>| {
+            let key = location.symbolic.key;
+            (self.path.is_match(key.sarif_path()) || self.path.is_match(key.filename()))
+                && self.location.as_deref().is_none_or(|prefix| {
+                    let route = location.symbolic.route.to_string();
+                    route.strip_prefix(prefix).is_some_and(|rest| {
+                        rest.is_empty() || rest.starts_with('.') || rest.starts_with('[')
+                    })
+                })
+                && self
+                    .annotation
+                    .as_deref()
+                    .is_none_or(|annotation| location.symbolic.annotation.contains(annotation))
+        };
+
+        finding
+            .locations
+            .iter()
+            .filter(|l| !l.symbolic.is_context())
+            .any(matches_location)
+    }
+
+    /// Whether this entry has expired as of `today`.
+    pub(crate) fn expired(&self, today: Date) -> bool {
+        self.expires.is_some_and(|expires| expires <= today)
+    }
+}
+
+fn deserialize_glob<'de, D>(deserializer: D) -> Result<GlobMatcher, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    Glob::new(&raw)
+        .map(|glob| glob.compile_matcher())
+        .map_err(de::Error::custom)
+}
+
+/// Deserializes a `YYYY-MM-DD` date.
+fn deserialize_date<'de, D>(deserializer: D) -> Result<Option<Date>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    Date::parse(
+        &raw,
+        time::macros::format_description!("[year]-[month]-[day]"),
+    )
+    .map(Some)
+    .map_err(|_| de::Error::custom(format!("invalid date `{raw}` (expected YYYY-MM-DD)")))
+}
+
+/// Deserializes a value like its command-line flag does, e.g. `low` for
+/// a severity or `pedantic` for a persona.
+fn deserialize_value_enum<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: ValueEnum,
+{
+    deserialize_required_value_enum(deserializer).map(Some)
+}
+
+/// Deserializes a GitHub hostname, with the same validation as
+/// `--gh-hostname`.
+fn deserialize_gh_hostname<'de, D>(deserializer: D) -> Result<Option<GitHubHost>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    GitHubHost::from_clap(&raw)
+        .map(Some)
+        .map_err(de::Error::custom)
+}
+
+/// Like [`deserialize_value_enum`], for values that are required.
+fn deserialize_required_value_enum<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: ValueEnum,
+{
+    let raw = String::deserialize(deserializer)?;
+    T::from_str(&raw, true).map_err(de::Error::custom)
+}
+
+/// What a [`CustomRule`]'s pattern is matched against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum CustomRuleTarget {
+    /// Each step's `run:` script.
+    Run,
+    /// Each step's (or reusable workflow job's) `uses:` clause.
+    Uses,
+    /// Each value in an `env:` block, at any level.
+    EnvValues,
+    /// Each value in a step's (or reusable workflow job's) `with:` block.
+    WithValues,
+    /// Each job's or step's `if:` condition.
+    If,
+}
+
+/// An entry in the configuration's `custom-rules`, which the
+/// `custom-rules` audit matches against each input.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct CustomRule {
+    /// The rule's ID, which its findings are identified by.
+    pub(crate) id: String,
+    /// What the rule's findings mean, e.g. "the legacy deploy key is retired".
+    pub(crate) description: String,
+    #[serde(deserialize_with = "deserialize_required_value_enum")]
+    pub(crate) severity: Severity,
+    pub(crate) target: CustomRuleTarget,
+    /// The regular expression that a finding's value matches. This is
+    /// compiled when the `custom-rules` audit is loaded.
+    pub(crate) regex: String,
+}
+
+/// The paths that configuration is discovered at, relative to the root
+/// of an input target (or $CWD), in order of precedence.
+const DISCOVERY_PATHS: &[&str] = &[".github/zizmor.yml", "zizmor.yml"];
+
 /// Runtime configuration, corresponding to a `zizmor.yml` file.
 #[derive(Clone, Debug, Default, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct Config {
     rules: HashMap<String, AuditRuleConfig>,
+    /// The default for `--fail-on`.
+    #[serde(default, rename = "fail-on")]
+    pub(crate) fail_on: Option<FailOn>,
+    /// Glob patterns for paths that are never collected; see `--exclude`.
+    #[serde(default)]
+    pub(crate) exclude: Vec<String>,
+    /// Glob patterns for vendored paths, whose inputs are collected and
+    /// audited but whose findings don't affect the exit code.
+    #[serde(default, rename = "vendored-paths")]
+    pub(crate) vendored_paths: Vec<String>,
+    /// Findings to ignore, for findings that can't be ignored inline.
+    #[serde(default)]
+    ignores: Vec<IgnoreEntry>,
+    /// The default for `--rollup`.
+    #[serde(default)]
+    pub(crate) rollup: Option<Rollup>,
+    /// The default for `--rollup-count`.
+    #[serde(default, rename = "rollup-count")]
+    pub(crate) rollup_count: Option<RollupCount>,
+    /// The default for `--persona`.
+    #[serde(default, deserialize_with = "deserialize_value_enum")]
+    pub(crate) persona: Option<Persona>,
+    /// The default for `--min-severity`.
+    #[serde(
+        default,
+        rename = "min-severity",
+        deserialize_with = "deserialize_value_enum"
+    )]
+    pub(crate) min_severity: Option<Severity>,
+    /// The default for `--min-confidence`.
+    #[serde(
+        default,
+        rename = "min-confidence",
+        deserialize_with = "deserialize_value_enum"
+    )]
+    pub(crate) min_confidence: Option<Confidence>,
+    /// How findings are presented in SARIF output.
+    #[serde(default)]
+    sarif: SarifMapping,
+    /// The default for `--gh-hostname`.
+    #[serde(
+        default,
+        rename = "gh-hostname",
+        deserialize_with = "deserialize_gh_hostname"
+    )]
+    pub(crate) gh_hostname: Option<GitHubHost>,
+    /// The weights of each risk in `--score`.
+    #[serde(default, rename = "score-weights")]
+    pub(crate) score_weights: ScoreWeights,
+    /// User-defined rules, for the `custom-rules` audit.
+    #[serde(default, rename = "custom-rules")]
+    pub(crate) custom_rules: Vec<CustomRule>,
+    /// The local file this configuration was loaded from, if any.
+    #[serde(skip)]
+    path: Option<Utf8PathBuf>,
+    /// Where this configuration came from, for error messages, e.g.
+    /// `zizmor.yml` or `example/example/.github/zizmor.yml`.
+    #[serde(skip)]
+    origin: Option<String>,
+    /// This configuration's original YAML source, which per-rule
+    /// configuration is deserialized from; see [`Config::rule_config`].
+    #[serde(skip)]
+    source: Option<String>,
+}
+
+/// Deserializes the value at `keys` within a YAML mapping (e.g.
+/// `rules.<id>.config`), skipping everything else.
+///
+/// Deserializing straight from the source document like this (rather than
+/// from an intermediate [`serde_yaml::Value`]) means that errors include
+/// the offending key's path and its line and column.
+struct Select<'a, T> {
+    keys: &'a [&'a str],
+    marker: PhantomData<T>,
+}
+
+impl<'a, T> Select<'a, T> {
+    fn new(keys: &'a [&'a str]) -> Self {
+        Self {
+            keys,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for Select<'_, T> {
+    type Value = Option<T>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        if self.keys.is_empty() {
+            T::deserialize(deserializer).map(Some)
+        } else {
+            deserializer.deserialize_map(self)
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for Select<'_, T> {
+    type Value = Option<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a mapping containing `{key}`", key = self.keys[0])
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut value = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == self.keys[0] {
+                value = map.next_value_seed(Select::new(&self.keys[1..]))?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+
+        Ok(value)
+    }
 }
 
 impl Config {
+    /// Loads the run's global configuration.
+    ///
+    /// This is the `--config` file if one was given, or else the first
+    /// of [`DISCOVERY_PATHS`] that exists relative to $CWD.
     pub(crate) fn new(app: &App) -> Result<Self> {
         if app.no_config {
             return Ok(Self::default());
         }
 
         let config = match &app.config {
-            Some(path) => serde_yaml::from_str(&fs::read_to_string(path)?)?,
+            // NOTE: An explicit config isn't logged at the default level,
+            // since the user already knows where it came from.
+            Some(path) => {
+                tracing::debug!("using config: {path}");
+                Self::load(path)?
+            }
             None => {
-                // If the user didn't pass a config path explicitly with
-                // `--config`, then we attempt to discover one relative to $CWD
-                // Our procedure is to first look for `$CWD/.github/zizmor.yml`,
-                // then `$CWD/zizmor.yml`, and then bail.
                 let cwd = std::env::current_dir()
                     .with_context(|| "config discovery couldn't access CWD")?;
+                let cwd = Utf8PathBuf::try_from(cwd)?;
 
-                let path = cwd.join(".github").join("zizmor.yml");
-                if path.is_file() {
-                    serde_yaml::from_str(&fs::read_to_string(path)?)?
-                } else {
-                    let path = cwd.join("zizmor.yml");
-                    if path.is_file() {
-                        serde_yaml::from_str(&fs::read_to_string(path)?)?
-                    } else {
+                match DISCOVERY_PATHS
+                    .iter()
+                    .map(|path| cwd.join(path))
+                    .find(|path| path.is_file())
+                {
+                    Some(path) => {
+                        tracing::info!("using discovered config: {path}");
+                        Self::load(&path)?
+                    }
+                    None => {
                         tracing::debug!("no config discovered; loading default");
                         Config::default()
                     }
@@ -117,6 +502,93 @@ impl Config {
         Ok(config)
     }
 
+    /// Loads the configuration file at `path`.
+    fn load(path: &Utf8Path) -> Result<Self> {
+        let contents = fs::read_to_string(path).with_context(|| format!("couldn't read {path}"))?;
+        let config = Self::parse(&contents, path.as_str())?;
+
+        Ok(Self {
+            path: Some(path.canonicalize_utf8().unwrap_or_else(|_| path.into())),
+            ..config
+        })
+    }
+
+    /// Parses a configuration from `contents`, which came from `origin`.
+    fn parse(contents: &str, origin: &str) -> Result<Self> {
+        let config = serde_yaml::from_str::<Self>(contents).with_context(|| origin.to_string())?;
+
+        Ok(Self {
+            origin: Some(origin.into()),
+            source: Some(contents.into()),
+            ..config
+        })
+    }
+
+    /// Discovers the configuration at the root of a local input target,
+    /// i.e. a directory.
+    ///
+    /// Returns `None` when `--config` or `--no-config` was passed (since
+    /// they always win), when the target has no configuration, or when
+    /// its configuration is the `global` one, e.g. for `zizmor .`.
+    pub(crate) fn discover_local(
+        app: &App,
+        global: &Config,
+        root: &Utf8Path,
+    ) -> Result<Option<Self>> {
+        if app.no_config || app.config.is_some() {
+            return Ok(None);
+        }
+
+        let Some(path) = DISCOVERY_PATHS
+            .iter()
+            .map(|path| root.join(path))
+            .find(|path| path.is_file())
+        else {
+            return Ok(None);
+        };
+
+        let config = Self::load(&path)?;
+        if config.path == global.path {
+            return Ok(None);
+        }
+
+        tracing::info!("using config for {root}: {path}");
+        tracing::debug!("loaded config: {config:?}");
+
+        Ok(Some(config))
+    }
+
+    /// Discovers the configuration at the root of a remote input target,
+    /// i.e. a repository.
+    ///
+    /// `fetch` returns the contents of the given root-relative path, if
+    /// it exists. Returns `None` when `--config` or `--no-config` was
+    /// passed, or when the target has no configuration.
+    pub(crate) fn discover_remote(
+        app: &App,
+        target: &str,
+        mut fetch: impl FnMut(&str) -> Result<Option<String>>,
+    ) -> Result<Option<Self>> {
+        if app.no_config || app.config.is_some() {
+            return Ok(None);
+        }
+
+        for path in DISCOVERY_PATHS {
+            let Some(contents) = fetch(path)? else {
+                continue;
+            };
+
+            let config = Self::parse(&contents, &format!("{target}/{path}"))?;
+
+            tracing::info!("using config for {target}: {path}");
+            tracing::debug!("loaded config: {config:?}");
+
+            return Ok(Some(config));
+        }
+
+        Ok(None)
+    }
+
     /// Returns `true` if this [`Config`] has an ignore rule for the
     /// given finding.
     pub(crate) fn ignores(&self, finding: &Finding<'_>) -> bool {
@@ -131,7 +603,11 @@ impl Config {
         // This will hopefully minimize confusion when a finding spans
         // multiple files, as the first location is the one a user will
         // typically ignore, suppressing the rest in the process.
-        for loc in &finding.locations {
+        for loc in finding
+            .locations
+            .iter()
+            .filter(|l| !l.symbolic.is_context())
+        {
             for rule in ignores
                 .iter()
                 .filter(|i| i.filename == loc.symbolic.key.filename())
@@ -167,16 +643,157 @@ impl Config {
         false
     }
 
+    /// Returns the index and entry of the first of this [`Config`]'s
+    /// `ignores` that matches the given finding, if any.
+    pub(crate) fn ignore_entry(&self, finding: &Finding<'_>) -> Option<(usize, &IgnoreEntry)> {
+        self.ignores
+            .iter()
+            .enumerate()
+            .find(|(_, entry)| entry.matches(finding))
+    }
+
+    /// Returns the position of the `idx`th entry in this [`Config`]'s `ignores`.
+    pub(crate) fn ignore_point(&self, idx: usize) -> Option<Point> {
+        let document = yamlpath::Document::new(self.source.as_deref()?).ok()?;
+        let query = yamlpath::QueryBuilder::new()
+            .key("ignores")
+            .index(idx)
+            .build();
+        let feature = document.query(&query).ok()?;
+
+        Some(ConcreteLocation::from(&feature.location).start_point)
+    }
+
+    /// Returns the position of the `idx`th entry in this [`Config`]'s
+    /// `custom-rules`.
+    pub(crate) fn custom_rule_point(&self, idx: usize) -> Option<Point> {
+        let document = yamlpath::Document::new(self.source.as_deref()?).ok()?;
+        let query = yamlpath::QueryBuilder::new()
+            .key("custom-rules")
+            .index(idx)
+            .build();
+        let feature = document.query(&query).ok()?;
+
+        Some(ConcreteLocation::from(&feature.location).start_point)
+    }
+
+    /// Returns the ident of every rule this [`Config`] configures,
+    /// in sorted order.
+    pub(crate) fn rule_idents(&self) -> Vec<&str> {
+        let mut idents = self.rules.keys().map(String::as_str).collect::<Vec<_>>();
+        idents.sort();
+        idents
+    }
+
+    /// Returns whether the given rule is explicitly enabled or disabled.
+    pub(crate) fn rule_enabled(&self, ident: &str) -> Option<bool> {
+        self.rules.get(ident).and_then(|rule| rule.enabled)
+    }
+
+    /// Returns the severity that the given rule's findings are
+    /// overridden to, if any.
+    pub(crate) fn rule_severity(&self, ident: &str) -> Option<Severity> {
+        self.rules.get(ident).and_then(|rule| rule.severity)
+    }
+
+    /// Returns the confidence that the given rule's findings are
+    /// overridden to, if any.
+    pub(crate) fn rule_confidence(&self, ident: &str) -> Option<Confidence> {
+        self.rules.get(ident).and_then(|rule| rule.confidence)
+    }
+
+    /// Returns the persona that the given rule's findings are filtered
+    /// at, if overridden.
+    pub(crate) fn rule_persona(&self, ident: &str) -> Option<Persona> {
+        self.rules.get(ident).and_then(|rule| rule.persona)
+    }
+
+    /// Returns the SARIF level for the given rule's findings of the
+    /// given severity, if it's configured for the rule or globally.
+    pub(crate) fn sarif_level(&self, ident: &str, severity: Severity) -> Option<SarifLevel> {
+        self.rules
+            .get(ident)
+            .and_then(|rule| rule.sarif.level.get(severity))
+            .or_else(|| self.sarif.level.get(severity))
+    }
+
+    /// Returns the SARIF `security-severity` score for the given rule's
+    /// findings of the given severity, if it's configured for the rule
+    /// or globally.
+    pub(crate) fn sarif_security_severity(&self, ident: &str, severity: Severity) -> Option<f64> {
+        self.rules
+            .get(ident)
+            .and_then(|rule| rule.sarif.security_severity.get(severity))
+            .or_else(|| self.sarif.security_severity.get(severity))
+            .map(|score| score.0)
+    }
+
+    /// Deserializes the given rule's `config` section, if it has one.
+    ///
+    /// Errors identify the configuration file, the rule, and the line and
+    /// column of the problem within the file.
     pub(crate) fn rule_config<T>(&self, ident: &str) -> Result<Option<T>>
     where
         T: DeserializeOwned,
     {
-        Ok(self
-            .rules
-            .get(ident)
-            .and_then(|rule_config| rule_config.config.as_ref())
-            .map(|policy| serde_yaml::from_value::<T>(serde_yaml::Value::Mapping(policy.clone())))
-            .transpose()?)
+        let (Some(source), Some(_)) = (
+            &self.source,
+            self.rules.get(ident).and_then(|rule| rule.config.as_ref()),
+        ) else {
+            return Ok(None);
+        };
+
+        Select::<T>::new(&["rules", ident, "config"])
+            .deserialize(serde_yaml::Deserializer::from_str(source))
+            .with_context(|| self.rule_error_context(ident))
+    }
+
+    /// Returns the position of the value at `route` within the given rule's
+    /// `config` section, e.g. to point a finding at the policy behind it.
+    pub(crate) fn rule_config_point(
+        &self,
+        ident: &str,
+        route: &[RouteComponent<'_>],
+    ) -> Option<Point> {
+        let document = yamlpath::Document::new(self.source.as_deref()?).ok()?;
+        let mut builder =
+            yamlpath::QueryBuilder::new().keys(["rules", ident, "config"].into_iter());
+        for component in route {
+            builder = match component {
+                RouteComponent::Key(key) => builder.key(key.clone()),
+                RouteComponent::Index(idx) => builder.index(*idx),
+            }
+        }
+        let feature = document.query(&builder.build()).ok()?;
+
+        Some(ConcreteLocation::from(&feature.location).start_point)
+    }
+
+    /// The local file this configuration was loaded from, or `None` for
+    /// the default (or a remote) configuration.
+    pub(crate) fn path(&self) -> Option<&Utf8Path> {
+        self.path.as_deref()
+    }
+
+    /// Where this configuration came from, e.g. `zizmor.yml`, or `None`
+    /// for the default configuration.
+    pub(crate) fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+
+    /// This configuration's original YAML source, or `None` for the
+    /// default configuration.
+    pub(crate) fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// The context for errors in the given rule's configuration, e.g.
+    /// "invalid configuration for unpinned-uses in zizmor.yml".
+    pub(crate) fn rule_error_context(&self, ident: &str) -> String {
+        format!(
+            "invalid configuration for {ident} in {origin}",
+            origin = self.origin.as_deref().unwrap_or("configuration")
+        )
     }
 }
 
@@ -186,7 +803,8 @@ mod tests {
 
     use anyhow::Result;
 
-    use super::WorkflowRule;
+    use super::{Config, CustomRuleTarget, SarifLevel, WorkflowRule};
+    use crate::finding::{Confidence, Persona, Severity};
 
     #[test]
     fn test_parse_workflow_rule() -> Result<()> {
@@ -224,4 +842,228 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_rule_overrides() -> Result<()> {
+        let config = Config::parse(
+            "rules:\n  unpinned-uses:\n    severity: low\n    confidence: medium\n  artipacked:\n    enabled: false\n",
+            "zizmor.yml",
+        )?;
+
+        assert_eq!(config.rule_idents(), ["artipacked", "unpinned-uses"]);
+        assert_eq!(config.rule_enabled("artipacked"), Some(false));
+        assert_eq!(config.rule_enabled("unpinned-uses"), None);
+        assert_eq!(config.rule_severity("unpinned-uses"), Some(Severity::Low));
+        assert_eq!(config.rule_severity("artipacked"), None);
+        assert_eq!(
+            config.rule_confidence("unpinned-uses"),
+            Some(Confidence::Medium)
+        );
+        assert_eq!(config.rule_confidence("artipacked"), None);
+
+        let config = Config::parse(
+            "persona: pedantic\nrules:\n  self-hosted-runner:\n    persona: auditor\n",
+            "zizmor.yml",
+        )?;
+
+        assert_eq!(config.persona, Some(Persona::Pedantic));
+        assert_eq!(
+            config.rule_persona("self-hosted-runner"),
+            Some(Persona::Auditor)
+        );
+        assert_eq!(config.rule_persona("artipacked"), None);
+
+        let config = Config::parse(
+            "min-severity: medium\nmin-confidence: HIGH\nrules: {}\n",
+            "zizmor.yml",
+        )?;
+        assert_eq!(config.min_severity, Some(Severity::Medium));
+        assert_eq!(config.min_confidence, Some(Confidence::High));
+
+        assert!(
+            Config::parse(
+                "rules:\n  artipacked:\n    severity: critical\n",
+                "zizmor.yml"
+            )
+            .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_sarif_mapping() -> Result<()> {
+        let config = Config::parse(
+            "sarif:\n  level:\n    medium: error\n  security-severity:\n    high: 8.5\n    \
+             medium: 6\nrules:\n  artipacked:\n    sarif:\n      security-severity:\n        \
+             medium: 7.0\n",
+            "zizmor.yml",
+        )?;
+
+        assert_eq!(
+            config.sarif_level("artipacked", Severity::Medium),
+            Some(SarifLevel::Error)
+        );
+        assert_eq!(config.sarif_level("artipacked", Severity::High), None);
+        assert_eq!(
+            config.sarif_security_severity("artipacked", Severity::Medium),
+            Some(7.0)
+        );
+        assert_eq!(
+            config.sarif_security_severity("artipacked", Severity::High),
+            Some(8.5)
+        );
+        assert_eq!(
+            config.sarif_security_severity("unpinned-uses", Severity::Medium),
+            Some(6.0)
+        );
+        assert_eq!(
+            config.sarif_security_severity("unpinned-uses", Severity::Low),
+            None
+        );
+
+        for invalid in [
+            // unknown severity
+            "sarif:\n  level:\n    critical: error\nrules: {}\n",
+            "sarif:\n  security-severity:\n    critical: 9.0\nrules: {}\n",
+            "rules:\n  artipacked:\n    sarif:\n      level:\n        severe: note\n",
+            // unknown level
+            "sarif:\n  level:\n    high: fatal\nrules: {}\n",
+            // out of range score
+            "sarif:\n  security-severity:\n    high: 11\nrules: {}\n",
+            "sarif:\n  security-severity:\n    low: -1.0\nrules: {}\n",
+            // unknown setting
+            "sarif:\n  levels: {}\nrules: {}\n",
+        ] {
+            assert!(Config::parse(invalid, "zizmor.yml").is_err(), "{invalid}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_gh_hostname() -> Result<()> {
+        let config = Config::parse("gh-hostname: ghe.example.com\nrules: {}\n", "zizmor.yml")?;
+        assert_eq!(
+            config.gh_hostname.map(|host| host.to_api_url()).as_deref(),
+            Some("https://ghe.example.com/api/v3")
+        );
+
+        let config = Config::parse("rules: {}\n", "zizmor.yml")?;
+        assert!(config.gh_hostname.is_none());
+
+        // URLs aren't hostnames.
+        assert!(
+            Config::parse(
+                "gh-hostname: https://ghe.example.com\nrules: {}\n",
+                "zizmor.yml"
+            )
+            .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_custom_rules() -> Result<()> {
+        let config = Config::parse(
+            "rules: {}\ncustom-rules:\n  - id: no-publish\n    description: don't publish\n    \
+             severity: high\n    target: run\n    regex: npm publish\n  - id: no-main\n    \
+             description: no main\n    severity: low\n    target: if\n    regex: main\n",
+            "zizmor.yml",
+        )?;
+
+        assert_eq!(config.custom_rules.len(), 2);
+        assert_eq!(config.custom_rules[0].id, "no-publish");
+        assert_eq!(config.custom_rules[0].severity, Severity::High);
+        assert_eq!(config.custom_rules[0].target, CustomRuleTarget::Run);
+        assert_eq!(config.custom_rules[1].target, CustomRuleTarget::If);
+        let point = config.custom_rule_point(1).unwrap();
+        assert_eq!((point.row, point.column), (7, 4));
+
+        for invalid in [
+            // unknown target
+            "rules: {}\ncustom-rules:\n  - id: x\n    description: x\n    severity: low\n    \
+             target: name\n    regex: x\n",
+            // unknown severity
+            "rules: {}\ncustom-rules:\n  - id: x\n    description: x\n    severity: critical\n    \
+             target: run\n    regex: x\n",
+            // missing regex
+            "rules: {}\ncustom-rules:\n  - id: x\n    description: x\n    severity: low\n    \
+             target: run\n",
+            // unknown setting
+            "rules: {}\ncustom-rules:\n  - id: x\n    description: x\n    severity: low\n    \
+             target: run\n    regex: x\n    pattern: x\n",
+        ] {
+            assert!(Config::parse(invalid, "zizmor.yml").is_err(), "{invalid}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rule_config_errors() -> Result<()> {
+        #[derive(Debug, serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct RuleConfig {
+            count: u32,
+        }
+
+        let config = Config::parse(
+            "rules:\n  foo:\n    config:\n      count: 1\n  bar:\n    config:\n      count: nope\n",
+            "zizmor.yml",
+        )?;
+
+        assert_eq!(config.rule_config::<RuleConfig>("foo")?.unwrap().count, 1);
+        assert!(config.rule_config::<RuleConfig>("baz")?.is_none());
+
+        let err = config.rule_config::<RuleConfig>("bar").unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "invalid configuration for bar in zizmor.yml: rules.bar.config.count: \
+             invalid type: string \"nope\", expected u32 at line 7 column 14"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_ignores() -> Result<()> {
+        let config = Config::parse(
+            "ignores:\n  - rule: artipacked\n    path: '**/ci.yml'\n    reason: no pushes\n  \
+             - rule: unpinned-uses\n    path: '*.yml'\n    location: jobs.build\n    \
+             reason: vendored\n    expires: 2000-01-31\nrules: {}\n",
+            "zizmor.yml",
+        )?;
+
+        let [first, second] = config.ignores.as_slice() else {
+            panic!("expected two ignores");
+        };
+        assert_eq!(first.reason, "no pushes");
+        assert_eq!(first.expires, None);
+        assert!(first.path.is_match(".github/workflows/ci.yml"));
+        assert!(!first.expired(time::macros::date!(2100 - 01 - 01)));
+
+        assert_eq!(second.location.as_deref(), Some("jobs.build"));
+        assert_eq!(second.expires, Some(time::macros::date!(2000 - 01 - 31)));
+        assert!(!second.expired(time::macros::date!(2000 - 01 - 30)));
+        assert!(second.expired(time::macros::date!(2000 - 01 - 31)));
+
+        assert_eq!(config.ignore_point(1).map(|point| point.row), Some(4));
+        assert!(config.ignore_point(2).is_none());
+
+        for invalid in [
+            // missing reason
+            "ignores:\n  - rule: artipacked\n    path: ci.yml\nrules: {}\n",
+            // invalid date
+            "ignores:\n  - rule: artipacked\n    path: ci.yml\n    reason: x\n    \
+             expires: 2000-13-01\nrules: {}\n",
+            // invalid glob
+            "ignores:\n  - rule: artipacked\n    path: '[ci.yml'\n    reason: x\nrules: {}\n",
+        ] {
+            assert!(Config::parse(invalid, "zizmor.yml").is_err(), "{invalid}");
+        }
+
+        Ok(())
+    }
 }
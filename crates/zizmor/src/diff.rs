@@ -0,0 +1,187 @@
+//! Diffing a run's findings against a previous run's JSON output.
+//!
+//! A diff is a baseline built from another run's `--format=json` output:
+//! findings that were already present in that output are treated as
+//! unchanged, while findings that are no longer present are reported
+//! as resolved.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+
+use anyhow::{Context as _, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Deserialize;
+
+use crate::baseline::Baseline;
+use crate::finding::{self, Determinations, LocationKind, Route};
+use crate::registry::{FindingRegistry, InputKey};
+
+/// A point within a reported finding's concrete location.
+#[derive(Deserialize)]
+pub(crate) struct ReportedPoint {
+    pub(crate) row: usize,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ReportedSpan {
+    pub(crate) start_point: ReportedPoint,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ReportedConcreteLocation {
+    pub(crate) location: ReportedSpan,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ReportedSymbolicLocation {
+    pub(crate) key: InputKey,
+    pub(crate) annotation: String,
+    pub(crate) route: Route<'static>,
+    pub(crate) kind: LocationKind,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ReportedLocation {
+    pub(crate) symbolic: ReportedSymbolicLocation,
+    pub(crate) concrete: ReportedConcreteLocation,
+}
+
+/// A finding, as deserialized from zizmor's JSON output.
+///
+/// This mirrors [`finding::Finding`], but owns all of its data and
+/// omits anything that can't be recovered without the original input.
+#[derive(Deserialize)]
+pub(crate) struct ReportedFinding {
+    pub(crate) ident: String,
+    pub(crate) desc: String,
+    pub(crate) determinations: Determinations,
+    pub(crate) locations: Vec<ReportedLocation>,
+    /// Absent in output from older versions of zizmor.
+    #[serde(default)]
+    fingerprint: Option<String>,
+}
+
+impl ReportedFinding {
+    fn primary(&self) -> Option<&ReportedLocation> {
+        self.locations
+            .iter()
+            .find(|l| matches!(l.symbolic.kind, LocationKind::Primary))
+    }
+
+    /// This finding's fingerprint, as computed by [`finding::Finding::fingerprint`].
+    ///
+    /// Returns `None` if the finding has no fingerprint and no primary
+    /// location to compute it from, which should never happen for output
+    /// produced by zizmor itself.
+    pub(crate) fn fingerprint(&self) -> Option<String> {
+        if let Some(fingerprint) = &self.fingerprint {
+            return Some(fingerprint.clone());
+        }
+
+        self.primary().map(|primary| {
+            finding::fingerprint(
+                &self.ident,
+                &primary.symbolic.key,
+                &primary.symbolic.route,
+                &primary.symbolic.annotation,
+            )
+        })
+    }
+}
+
+pub(crate) struct Diff {
+    path: Utf8PathBuf,
+    previous: Vec<(String, ReportedFinding)>,
+}
+
+impl Diff {
+    /// Load a previous run's JSON output from the given path.
+    pub(crate) fn load(path: &Utf8Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("couldn't read previous results {path}"))?;
+
+        let findings = serde_json::from_str::<Vec<ReportedFinding>>(&contents)
+            .with_context(|| format!("invalid previous results {path} (expected JSON output)"))?;
+
+        let previous = findings
+            .into_iter()
+            .map(|finding| {
+                let fingerprint = finding.fingerprint().with_context(|| {
+                    format!(
+                        "invalid previous results {path}: {ident} finding has no primary location",
+                        ident = finding.ident
+                    )
+                })?;
+                Ok((fingerprint, finding))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            path: path.into(),
+            previous,
+        })
+    }
+
+    /// A baseline of all previous findings, so that only new findings
+    /// count towards the current run's results.
+    pub(crate) fn baseline(&self) -> Baseline {
+        self.previous
+            .iter()
+            .map(|(fingerprint, _)| fingerprint.clone())
+            .collect()
+    }
+
+    /// All previous findings that are no longer reported by this run.
+    pub(crate) fn resolved(&self, results: &FindingRegistry) -> Vec<&ReportedFinding> {
+        let current = results
+            .findings()
+            .iter()
+            .chain(results.baselined())
+            .map(|finding| finding.fingerprint())
+            .collect::<HashSet<_>>();
+
+        self.previous
+            .iter()
+            .filter(|(fingerprint, _)| !current.contains(fingerprint.as_str()))
+            .map(|(_, finding)| finding)
+            .collect()
+    }
+
+    /// Render a summary of the findings resolved since the previous run.
+    pub(crate) fn render_resolved(
+        &self,
+        mut sink: impl io::Write,
+        results: &FindingRegistry,
+    ) -> Result<()> {
+        let resolved = self.resolved(results);
+        if resolved.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(
+            sink,
+            "{count} finding{s} resolved since {path}:",
+            count = resolved.len(),
+            s = if resolved.len() == 1 { "" } else { "s" },
+            path = self.path,
+        )?;
+
+        for finding in resolved {
+            // NOTE: Safe unwrap, since we only load findings with a primary location.
+            let primary = finding.primary().unwrap();
+            writeln!(
+                sink,
+                "  {ident} ({severity:?}): {desc} at {path}:{line}: {annotation}",
+                ident = finding.ident,
+                severity = finding.determinations.severity,
+                desc = finding.desc,
+                path = primary.symbolic.key.presentation_path(),
+                line = primary.concrete.location.start_point.row + 1,
+                annotation = primary.symbolic.annotation,
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+//! An owned, serializable form of [`Finding`], for replaying findings
+//! from the results cache.
+//!
+//! Findings borrow their features and comments from their input's source,
+//! so cached findings record byte spans instead, and are rebuilt against
+//! the same source when they're replayed.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    Comment, ConcreteLocation, Determinations, ExternalLocation, Feature, Finding, FindingTag,
+    Location, LocationKind, LocationRole, Point, Remediation, Route, RouteComponent,
+    SymbolicLocation, fingerprint, qualified_ident,
+};
+use crate::audit::{AuditInput, RULES};
+use crate::models::AsDocument as _;
+
+/// A cached [`Location`].
+#[derive(Serialize, Deserialize)]
+struct CachedLocation {
+    annotation: String,
+    link: Option<String>,
+    route: Route<'static>,
+    kind: LocationKind,
+    #[serde(default)]
+    role: Option<LocationRole>,
+    start_point: Point,
+    end_point: Point,
+    offset_span: Range<usize>,
+    /// The span of the feature's text, which can include leading
+    /// whitespace beyond `offset_span`.
+    feature: Range<usize>,
+    comments: Vec<Range<usize>>,
+}
+
+/// A cached [`Finding`], as produced by an audit.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct CachedFinding {
+    ident: String,
+    subident: Option<String>,
+    determinations: Determinations,
+    locations: Vec<CachedLocation>,
+    external_locations: Vec<ExternalLocation>,
+    #[serde(default)]
+    remediation: Option<Remediation>,
+    #[serde(default)]
+    tags: Vec<FindingTag>,
+    ignored: bool,
+}
+
+/// Returns the span of `slice` within `source`, if `slice` is
+/// borrowed from it.
+fn span_within(source: &str, slice: &str) -> Option<Range<usize>> {
+    let start = (slice.as_ptr() as usize).checked_sub(source.as_ptr() as usize)?;
+    let end = start + slice.len();
+    (end <= source.len()).then_some(start..end)
+}
+
+impl CachedFinding {
+    /// Converts a finding on `input` into its cached form.
+    ///
+    /// Returns `None` if the finding can't be replayed from `input`
+    /// alone, e.g. because it has a location in a different input.
+    pub(crate) fn new(finding: &Finding<'_>, input: &AuditInput) -> Option<Self> {
+        let source = input.as_document().source();
+
+        let locations = finding
+            .locations
+            .iter()
+            .map(|location| {
+                if location.symbolic.key != input.key() {
+                    return None;
+                }
+
+                let concrete = &location.concrete;
+                Some(CachedLocation {
+                    annotation: location.symbolic.annotation.clone(),
+                    link: location.symbolic.link.clone(),
+                    route: Route {
+                        components: location
+                            .symbolic
+                            .route
+                            .components
+                            .iter()
+                            .cloned()
+                            .map(|component| match component {
+                                RouteComponent::Key(key) => {
+                                    RouteComponent::Key(key.into_owned().into())
+                                }
+                                RouteComponent::Index(idx) => RouteComponent::Index(idx),
+                            })
+                            .collect(),
+                    },
+                    kind: location.symbolic.kind,
+                    role: location.symbolic.role,
+                    start_point: concrete.location.start_point.clone(),
+                    end_point: concrete.location.end_point.clone(),
+                    offset_span: concrete.location.offset_span.clone(),
+                    feature: span_within(source, concrete.feature)?,
+                    comments: concrete
+                        .comments
+                        .iter()
+                        .map(|comment| span_within(source, comment.0))
+                        .collect::<Option<_>>()?,
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            ident: finding.ident.into(),
+            subident: finding.subident.clone(),
+            determinations: Determinations {
+                confidence: finding.determinations.confidence,
+                severity: finding.determinations.severity,
+                persona: finding.determinations.persona,
+            },
+            locations,
+            external_locations: finding.external_locations.clone(),
+            remediation: finding.remediation.clone(),
+            tags: finding.tags.clone(),
+            ignored: finding.ignored,
+        })
+    }
+
+    /// Rebuilds this finding against `input`, which must have the same
+    /// source as the input it was cached from.
+    ///
+    /// Returns `None` if the finding doesn't fit `input`, e.g. because
+    /// its rule no longer exists.
+    pub(crate) fn replay<'doc>(self, input: &'doc AuditInput) -> Option<Finding<'doc>> {
+        let rule = RULES.iter().find(|rule| rule.ident == self.ident)?;
+        let key = input.key();
+        let source = input.as_document().source();
+
+        let locations = self
+            .locations
+            .into_iter()
+            .map(|location| {
+                Some(Location {
+                    symbolic: SymbolicLocation {
+                        key,
+                        annotation: location.annotation,
+                        link: location.link,
+                        route: location.route,
+                        kind: location.kind,
+                        role: location.role,
+                        subfeature: None,
+                    },
+                    concrete: Feature {
+                        location: ConcreteLocation::new(
+                            location.start_point,
+                            location.end_point,
+                            location.offset_span,
+                        ),
+                        feature: source.get(location.feature)?,
+                        comments: location
+                            .comments
+                            .into_iter()
+                            .map(|span| source.get(span).map(Comment))
+                            .collect::<Option<_>>()?,
+                    },
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let primary = locations.iter().find(|l| l.symbolic.is_primary())?;
+        let fingerprint = fingerprint(
+            &qualified_ident(rule.ident, self.subident.as_deref()),
+            key,
+            &primary.symbolic.route,
+            &primary.symbolic.annotation,
+        );
+
+        Some(Finding {
+            ident: rule.ident,
+            subident: self.subident,
+            desc: rule.desc,
+            url: rule.url,
+            repository: key.repository(),
+            collection: Default::default(),
+            template: key.is_template(),
+            vendored: false,
+            determinations: self.determinations,
+            locations,
+            occurrences: vec![],
+            external_locations: self.external_locations,
+            remediation: self.remediation,
+            tags: self.tags,
+            ignored: self.ignored,
+            ignore_reason: None,
+            baselined: false,
+            hidden: false,
+            filtered: false,
+            fingerprint,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CachedFinding;
+    use crate::audit::{AuditCore as _, AuditInput, artipacked::Artipacked};
+    use crate::finding::{Confidence, Subfeature};
+    use crate::models::Workflow;
+    use crate::registry::InputKey;
+
+    #[test]
+    fn test_replay_round_trip() {
+        let source = "on: push # ✨\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: | # zizmor: ignore[artipacked]\n          echo ${{ github.ref }}\n";
+        let input = AuditInput::from(
+            Workflow::from_string(source.into(), InputKey::local("fake", None).unwrap()).unwrap(),
+        );
+        let AuditInput::Workflow(workflow) = &input else {
+            unreachable!();
+        };
+
+        let step = workflow.location().with_keys(&[
+            "jobs".into(),
+            "test".into(),
+            "steps".into(),
+            0.into(),
+        ]);
+        let finding = Artipacked::finding()
+            .confidence(Confidence::High)
+            .add_location(step.clone().hidden())
+            .add_location(
+                step.with_keys(&["run".into()])
+                    .with_subfeature(Subfeature::new(0, "${{ github.ref }}"))
+                    .primary()
+                    .annotated("here"),
+            )
+            .build(workflow)
+            .unwrap();
+        assert!(finding.ignored);
+
+        let cached = CachedFinding::new(&finding, &input).unwrap();
+        let cached: CachedFinding =
+            serde_json::from_str(&serde_json::to_string(&cached).unwrap()).unwrap();
+        let replayed = cached.replay(&input).unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&replayed).unwrap(),
+            serde_json::to_string(&finding).unwrap()
+        );
+        assert_eq!(replayed.fingerprint(), finding.fingerprint());
+    }
+}
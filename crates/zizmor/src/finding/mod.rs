@@ -3,18 +3,23 @@
 use std::{borrow::Cow, ops::Range, sync::LazyLock};
 
 use anyhow::{Result, anyhow};
+use camino::Utf8Path;
 use clap::ValueEnum;
 use line_index::{LineCol, TextSize};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
 use terminal_link::Link;
 
 use crate::{
     audit::AuditInput,
-    models::{AsDocument, CompositeStep, JobExt, Step},
-    registry::InputKey,
+    fix::Edit,
+    models::{AsDocument, CompositeStep, JobExt, Step, StepCommon},
+    registry::{Collection, InputKey},
 };
 
+pub(crate) mod cached;
+
 /// Represents the expected "persona" that would be interested in a given
 /// finding. This is used to model the sensitivity of different use-cases
 /// to false positives.
@@ -98,24 +103,7 @@ pub(crate) enum Severity {
     High,
 }
 
-#[derive(Serialize, Clone, Debug)]
-pub(crate) struct StepLocation<'doc> {
-    pub(crate) index: usize,
-    pub(crate) id: Option<&'doc str>,
-    pub(crate) name: Option<&'doc str>,
-}
-
-impl<'doc> From<&Step<'doc>> for StepLocation<'doc> {
-    fn from(step: &Step<'doc>) -> Self {
-        Self {
-            index: step.index,
-            id: step.id.as_deref(),
-            name: step.name.as_deref(),
-        }
-    }
-}
-
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub(crate) enum RouteComponent<'doc> {
     Key(Cow<'doc, str>),
     Index(usize),
@@ -133,7 +121,7 @@ impl<'doc> From<&'doc str> for RouteComponent<'doc> {
     }
 }
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub(crate) struct Route<'doc> {
     components: Vec<RouteComponent<'doc>>,
 }
@@ -152,8 +140,22 @@ impl<'doc> Route<'doc> {
     }
 }
 
+impl std::fmt::Display for Route<'_> {
+    /// Renders the route like `jobs.build.steps[0].uses`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (n, component) in self.components.iter().enumerate() {
+            match component {
+                RouteComponent::Key(key) if n == 0 => write!(f, "{key}")?,
+                RouteComponent::Key(key) => write!(f, ".{key}")?,
+                RouteComponent::Index(idx) => write!(f, "[{idx}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Represents a location's type.
-#[derive(Serialize, Copy, Clone, Debug, Default)]
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default)]
 pub(crate) enum LocationKind {
     /// A location that is subjectively "primary" to a finding.
     ///
@@ -173,6 +175,20 @@ pub(crate) enum LocationKind {
     Hidden,
 }
 
+/// The context that a related location gives a finding, e.g. the job
+/// that a step-level finding is in.
+///
+/// Context locations are rendered as a summary line rather than as
+/// snippets, and never affect whether a finding is ignored.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum LocationRole {
+    /// The enclosing job, located at its ID.
+    Job,
+    /// The enclosing workflow's triggers, i.e. its `on:` block.
+    Trigger,
+}
+
 /// Represents a symbolic location.
 #[derive(Serialize, Clone, Debug)]
 pub(crate) struct SymbolicLocation<'doc> {
@@ -193,6 +209,43 @@ pub(crate) struct SymbolicLocation<'doc> {
 
     /// The kind of location.
     pub(crate) kind: LocationKind,
+
+    /// The context this location gives, if it's a context location.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) role: Option<LocationRole>,
+
+    /// An optional fragment within the final location, used to narrow
+    /// the concrete location to just that fragment.
+    #[serde(skip_serializing)]
+    pub(crate) subfeature: Option<Subfeature>,
+}
+
+/// A fragment of a feature's source, e.g. a single expression within
+/// a `run:` block.
+#[derive(Clone, Debug)]
+pub(crate) struct Subfeature {
+    /// The number of earlier occurrences of `fragment` to skip over.
+    pub(crate) occurrence: usize,
+    /// The fragment's literal text.
+    pub(crate) fragment: String,
+}
+
+impl Subfeature {
+    pub(crate) fn new(occurrence: usize, fragment: impl Into<String>) -> Self {
+        Self {
+            occurrence,
+            fragment: fragment.into(),
+        }
+    }
+
+    /// Locate this fragment within `feature`, returning its
+    /// offset span relative to the start of `feature`.
+    fn locate_within(&self, feature: &str) -> Option<Range<usize>> {
+        feature
+            .match_indices(&self.fragment)
+            .nth(self.occurrence)
+            .map(|(start, fragment)| start..start + fragment.len())
+    }
 }
 
 impl<'doc> SymbolicLocation<'doc> {
@@ -203,6 +256,8 @@ impl<'doc> SymbolicLocation<'doc> {
             link: None,
             route: self.route.with_keys(keys),
             kind: self.kind,
+            role: self.role,
+            subfeature: None,
         }
     }
 
@@ -230,6 +285,16 @@ impl<'doc> SymbolicLocation<'doc> {
         self
     }
 
+    /// Narrows the current `SymbolicLocation` to the given fragment
+    /// of its final feature.
+    ///
+    /// If the fragment can't be found when concretizing, the location
+    /// spans the entire feature instead.
+    pub(crate) fn with_subfeature(mut self, subfeature: Subfeature) -> SymbolicLocation<'doc> {
+        self.subfeature = Some(subfeature);
+        self
+    }
+
     /// Mark the current `SymbolicLocation` as a "primary" location.
     pub(crate) fn primary(mut self) -> SymbolicLocation<'doc> {
         self.kind = LocationKind::Primary;
@@ -242,6 +307,13 @@ impl<'doc> SymbolicLocation<'doc> {
         self
     }
 
+    /// Mark the current `SymbolicLocation` as a context location with
+    /// the given role.
+    pub(crate) fn with_role(mut self, role: LocationRole) -> SymbolicLocation<'doc> {
+        self.role = Some(role);
+        self
+    }
+
     pub(crate) fn is_primary(&self) -> bool {
         matches!(self.kind, LocationKind::Primary)
     }
@@ -250,8 +322,25 @@ impl<'doc> SymbolicLocation<'doc> {
         matches!(self.kind, LocationKind::Hidden)
     }
 
+    pub(crate) fn is_context(&self) -> bool {
+        self.role.is_some()
+    }
+
     /// Concretize this `SymbolicLocation`, consuming it in the process.
     pub(crate) fn concretize(self, document: &'doc yamlpath::Document) -> Result<Location<'doc>> {
+        self.concretize_aliased(document)
+            .map(|(location, _)| location)
+    }
+
+    /// Like [`SymbolicLocation::concretize`], but for features that are
+    /// reached through a YAML alias (e.g. a step that's `- *checkout`).
+    ///
+    /// These are located at the alias, i.e. where they're used, and their
+    /// definition is returned as an additional related location.
+    pub(crate) fn concretize_aliased(
+        self,
+        document: &'doc yamlpath::Document,
+    ) -> Result<(Location<'doc>, Option<Location<'doc>>)> {
         // If we don't have a path into the workflow, all
         // we have is the workflow itself.
         let feature = if self.route.components.is_empty() {
@@ -271,23 +360,73 @@ impl<'doc> SymbolicLocation<'doc> {
             document.query(&query)?
         };
 
-        Ok(Location {
-            symbolic: self,
-            concrete: Feature {
-                location: ConcreteLocation::from(&feature.location),
-                feature: document.extract_with_leading_whitespace(&feature),
-                comments: document
-                    .feature_comments(&feature)
-                    .into_iter()
-                    .map(Comment)
-                    .collect(),
+        let Some(alias) = feature.alias() else {
+            let concrete = Self::locate(document, &feature, self.subfeature.as_ref());
+            return Ok((
+                Location {
+                    symbolic: self,
+                    concrete,
+                },
+                None,
+            ));
+        };
+
+        let definition = Location {
+            symbolic: SymbolicLocation {
+                kind: LocationKind::Related,
+                role: None,
+                annotation: "defined here, and used via an alias".into(),
+                ..self.clone()
             },
-        })
+            concrete: Self::locate(document, &feature, self.subfeature.as_ref()),
+        };
+
+        Ok((
+            Location {
+                symbolic: self,
+                concrete: Self::locate(document, &alias, None),
+            },
+            Some(definition),
+        ))
+    }
+
+    /// Returns the concrete location of `feature`, or of `subfeature`
+    /// within it.
+    fn locate(
+        document: &'doc yamlpath::Document,
+        feature: &yamlpath::Feature<'doc>,
+        subfeature: Option<&Subfeature>,
+    ) -> Feature<'doc> {
+        let (location, extracted) = match subfeature.and_then(|subfeature| {
+            let span = feature.location.byte_span.0..feature.location.byte_span.1;
+            subfeature
+                .locate_within(&document.source()[span.clone()])
+                .map(|sub| span.start + sub.start..span.start + sub.end)
+        }) {
+            Some(span) => (
+                ConcreteLocation::from_source_span(document.source(), span.clone()),
+                &document.source()[span],
+            ),
+            None => (
+                ConcreteLocation::from(&feature.location),
+                document.extract_with_leading_whitespace(feature),
+            ),
+        };
+
+        Feature {
+            location,
+            feature: extracted,
+            comments: document
+                .feature_comments(feature)
+                .into_iter()
+                .map(Comment)
+                .collect(),
+        }
     }
 }
 
 /// Represents a `(row, column)` point within a file.
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub(crate) struct Point {
     pub(crate) row: usize,
     pub(crate) column: usize,
@@ -305,11 +444,22 @@ impl From<LineCol> for Point {
 /// A "concrete" location for some feature.
 /// Every concrete location contains two spans: a line-and-column span,
 /// and an offset range.
+///
+/// Both spans are exact with respect to the original document: offsets
+/// are byte offsets, rows are zero-indexed lines (split on `\n`, so a
+/// `\r\n` line ending leaves its `\r` at the end of the line), and
+/// columns are byte offsets from the start of their row. In other words,
+/// `&source[start_byte..end_byte]` is always exactly the located feature,
+/// suitable for machine edits.
 #[derive(Serialize)]
 pub(crate) struct ConcreteLocation {
     pub(crate) start_point: Point,
     pub(crate) end_point: Point,
     pub(crate) offset_span: Range<usize>,
+    /// The byte offset of the start of the feature, i.e. `offset_span.start`.
+    pub(crate) start_byte: usize,
+    /// The byte offset just past the end of the feature, i.e. `offset_span.end`.
+    pub(crate) end_byte: usize,
 }
 
 impl ConcreteLocation {
@@ -317,24 +467,38 @@ impl ConcreteLocation {
         Self {
             start_point,
             end_point,
+            start_byte: offset_span.start,
+            end_byte: offset_span.end,
             offset_span,
         }
     }
+
+    /// Computes a concrete location for the given offset span within `source`.
+    fn from_source_span(source: &str, span: Range<usize>) -> Self {
+        let point = |offset: usize| {
+            let before = &source[..offset];
+            let row = before.matches('\n').count();
+            let column = offset - before.rfind('\n').map_or(0, |idx| idx + 1);
+            Point { row, column }
+        };
+
+        Self::new(point(span.start), point(span.end), span)
+    }
 }
 
 impl From<&yamlpath::Location> for ConcreteLocation {
     fn from(value: &yamlpath::Location) -> Self {
-        Self {
-            start_point: Point {
+        Self::new(
+            Point {
                 row: value.point_span.0.0,
                 column: value.point_span.0.1,
             },
-            end_point: Point {
+            Point {
                 row: value.point_span.1.0,
                 column: value.point_span.1.1,
             },
-            offset_span: value.byte_span.0..value.byte_span.1,
-        }
+            value.byte_span.0..value.byte_span.1,
+        )
     }
 }
 
@@ -348,7 +512,12 @@ static IGNORE_EXPR: LazyLock<Regex> =
 #[serde(transparent)]
 pub(crate) struct Comment<'doc>(&'doc str);
 
-impl Comment<'_> {
+impl<'doc> Comment<'doc> {
+    /// The comment's text, including its leading `#`.
+    pub(crate) fn as_str(&self) -> &'doc str {
+        self.0
+    }
+
     fn ignores(&self, rule_id: &str) -> bool {
         // Extracts foo,bar from `# zizmor: ignore[foo,bar]`
         let Some(caps) = IGNORE_EXPR.captures(self.0) else {
@@ -370,6 +539,10 @@ pub(crate) struct Feature<'doc> {
     pub(crate) location: ConcreteLocation,
 
     /// The feature's textual content.
+    ///
+    /// NOTE: This can include leading indentation that isn't part of
+    /// the feature's concrete location, so consumers that need exact
+    /// offsets should use [`ConcreteLocation`] rather than this text.
     pub(crate) feature: &'doc str,
 
     /// Any comments within the feature's line span.
@@ -417,6 +590,44 @@ impl<'doc> Feature<'doc> {
             comments,
         }
     }
+
+    /// The point at which this feature ends, for outputs that display it
+    /// as a region of lines (e.g. SARIF and GitHub annotations).
+    ///
+    /// Multi-line features like block scalars often end just past their
+    /// trailing newline, i.e. at the start of the following line. These
+    /// regions end on the preceding line instead, so that they don't
+    /// cover a line that isn't part of the feature.
+    pub(crate) fn region_end(&self) -> Point {
+        let ConcreteLocation {
+            start_point,
+            end_point,
+            start_byte,
+            end_byte,
+            ..
+        } = &self.location;
+
+        if end_point.column != 0 || end_point.row == start_point.row {
+            return end_point.clone();
+        }
+
+        // NOTE: The feature's text always ends at `end_byte`, so its
+        // last full line is the one that the region ends on.
+        let text = self.feature.strip_suffix('\n').unwrap_or(self.feature);
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        let column = match text.rfind('\n') {
+            Some(idx) => text.len() - (idx + 1),
+            // The feature starts on the line that the region ends on.
+            None => {
+                start_point.column + (end_byte - start_byte) - (self.feature.len() - text.len())
+            }
+        };
+
+        Point {
+            row: end_point.row - 1,
+            column,
+        }
+    }
 }
 
 /// A location within a GitHub Actions workflow, with both symbolic and concrete components.
@@ -434,8 +645,145 @@ impl<'doc> Location<'doc> {
     }
 }
 
+/// A location outside of any audited input, e.g. the line of a
+/// configuration file that caused a finding.
+///
+/// These are rendered alongside a finding's other locations, and only
+/// have a snippet when they're primary. Locations without a path describe
+/// built-in behavior, e.g. a default policy.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct ExternalLocation {
+    /// A description of what's at this location.
+    pub(crate) annotation: String,
+    /// The file this location is in, if any.
+    pub(crate) path: Option<String>,
+    /// The location's position within its file, if known.
+    pub(crate) point: Option<Point>,
+    /// The source line at `point`, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) line: Option<String>,
+    /// Whether this is the finding's primary location, in place of the
+    /// primary location within its input; see
+    /// [`Finding::primary_external_location`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) primary: bool,
+}
+
+impl ExternalLocation {
+    pub(crate) fn new(annotation: impl Into<String>) -> Self {
+        Self {
+            annotation: annotation.into(),
+            path: None,
+            point: None,
+            line: None,
+            primary: false,
+        }
+    }
+
+    pub(crate) fn with_path(mut self, path: impl Into<String>, point: Option<Point>) -> Self {
+        self.path = Some(path.into());
+        self.point = point;
+        self
+    }
+
+    /// Records the line at this location's point from `source`, the
+    /// contents of the file it's in.
+    pub(crate) fn with_source(mut self, source: &str) -> Self {
+        self.line = self
+            .point
+            .as_ref()
+            .and_then(|point| source.lines().nth(point.row))
+            .map(Into::into);
+        self
+    }
+
+    /// Marks this location as its finding's primary location.
+    ///
+    /// Only locations with a path can be primary.
+    pub(crate) fn primary(mut self) -> Self {
+        self.primary = self.path.is_some();
+        self
+    }
+
+    /// A one-line rendering of this location, e.g.
+    /// `policy defined at zizmor.yml:5:7`.
+    pub(crate) fn render(&self) -> String {
+        match (&self.path, &self.point) {
+            (Some(path), Some(point)) => format!(
+                "{annotation} at {path}:{line}:{column}",
+                annotation = self.annotation,
+                line = point.row + 1,
+                column = point.column + 1
+            ),
+            (Some(path), None) => format!("{annotation} in {path}", annotation = self.annotation),
+            (None, _) => self.annotation.clone(),
+        }
+    }
+}
+
+/// Machine-readable remediation data for a finding, for tools that fix
+/// findings automatically.
+///
+/// `--fix` applies exactly these [`Remediation::edits`], so that other
+/// tools and the built-in fixer can't disagree about a fix.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct Remediation {
+    /// The input to remediate, as its presentation path.
+    pub(crate) path: String,
+    /// The byte span of the value to remediate, e.g. a `uses:` clause's
+    /// value (without any quotes).
+    pub(crate) span: Range<usize>,
+    /// The value's current text.
+    pub(crate) current: String,
+    /// The suggested replacement for the value, if one is known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) replacement: Option<String>,
+    /// The edits to the input's source that apply `replacement`, which
+    /// may include more than the value itself (e.g. a trailing comment).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) edits: Vec<Edit>,
+}
+
+impl Remediation {
+    /// A remediation of the value at `span` in `source`, the source of the
+    /// input at `key`, without a known replacement.
+    ///
+    /// Returns `None` if `span` isn't within `source`.
+    pub(crate) fn new(key: &InputKey, source: &str, span: Range<usize>) -> Option<Self> {
+        Some(Self {
+            path: key.presentation_path().into(),
+            current: source.get(span.clone())?.into(),
+            span,
+            replacement: None,
+            edits: vec![],
+        })
+    }
+
+    /// Suggests `replacement` for the value, applied by `edits`.
+    pub(crate) fn with_replacement(
+        mut self,
+        replacement: impl Into<String>,
+        edits: Vec<Edit>,
+    ) -> Self {
+        self.replacement = Some(replacement.into());
+        self.edits = edits;
+        self
+    }
+}
+
+/// A machine-readable tag on a finding, for consumers (e.g. output formats)
+/// that need to select findings by what they mean rather than by their
+/// free-form annotations.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum FindingTag {
+    /// The finding is for a third-party action that isn't pinned to a
+    /// commit SHA.
+    ThirdPartyUnpinned,
+}
+
 /// A finding's "determination," i.e. its various classifications.
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub(crate) struct Determinations {
     pub(crate) confidence: Confidence,
     pub(crate) severity: Severity,
@@ -445,19 +793,88 @@ pub(crate) struct Determinations {
 #[derive(Serialize)]
 pub(crate) struct Finding<'doc> {
     pub(crate) ident: &'static str,
+    /// The ID of the user-defined rule that produced this finding, for
+    /// audits that run user-defined rules (i.e. `custom-rules`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) subident: Option<String>,
     pub(crate) desc: &'static str,
     pub(crate) url: &'static str,
+    /// The `owner/repo` slug of the repository this finding was found in,
+    /// for findings in remote inputs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) repository: Option<String>,
+    /// Whether this finding's input was collected from a non-standard
+    /// location, e.g. with `--collect=all`.
+    #[serde(skip_serializing_if = "Collection::is_standard")]
+    pub(crate) collection: Collection,
+    /// Whether this finding's input is a workflow template, i.e. in an
+    /// org's `workflow-templates/`; see [`InputKey::is_template`].
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) template: bool,
+    /// Whether this finding's input was collected from one of the
+    /// configuration's `vendored-paths`. Vendored findings don't
+    /// affect the exit code.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) vendored: bool,
     pub(crate) determinations: Determinations,
     pub(crate) locations: Vec<Location<'doc>>,
+    /// The primary locations of the identical findings that are rolled up
+    /// into this one with `--rollup`, in the order they were found.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) occurrences: Vec<Location<'doc>>,
+    /// Locations outside of the audited inputs, e.g. in the configuration.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) external_locations: Vec<ExternalLocation>,
+    /// How to remediate this finding, for audits that know.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) remediation: Option<Remediation>,
+    /// See [`FindingTag`].
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) tags: Vec<FindingTag>,
     pub(crate) ignored: bool,
+    /// Why this finding is ignored, if it's ignored by an entry in the
+    /// configuration's `ignores`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) ignore_reason: Option<String>,
+    /// Whether this finding matches an entry in the `--baseline`.
+    pub(crate) baselined: bool,
+    /// Whether this finding is hidden by the current persona; hidden
+    /// findings are only output with `--show-all`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) hidden: bool,
+    /// Whether this finding is below `--min-severity` or `--min-confidence`;
+    /// filtered findings are only output with `--show-filtered`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) filtered: bool,
+    /// See [`Finding::fingerprint`].
+    fingerprint: String,
 }
 
 impl Finding<'_> {
+    /// The finding's ident, qualified by its [`Finding::subident`] (if
+    /// any), e.g. `custom-rules/no-legacy-deploy-key`.
+    pub(crate) fn qualified_ident(&self) -> Cow<'_, str> {
+        qualified_ident(self.ident, self.subident.as_deref())
+    }
+
+    /// The external location that outputs present as this finding's
+    /// location, if it has one, e.g. the configuration entry that an
+    /// `expired-ignores` finding is about.
+    ///
+    /// Such findings still have a primary location within their input,
+    /// which identifies them (for fingerprints, baselines and the like)
+    /// and is rendered as a related location.
+    pub(crate) fn primary_external_location(&self) -> Option<&ExternalLocation> {
+        self.external_locations
+            .iter()
+            .find(|location| location.primary)
+    }
+
     /// A basic Markdown representation of the finding's metadata.
     pub(crate) fn to_markdown(&self) -> String {
         format!(
             "`{ident}`: {desc}\n\nDocs: <{url}>",
-            ident = self.ident,
+            ident = self.qualified_ident(),
             desc = self.desc,
             url = self.url
         )
@@ -466,40 +883,146 @@ impl Finding<'_> {
     pub(crate) fn visible_locations(&self) -> impl Iterator<Item = &Location<'_>> {
         self.locations.iter().filter(|l| !l.symbolic.is_hidden())
     }
+
+    /// The (normalized) action reference, e.g. `third-party/action@v1`,
+    /// whose `uses:` clause is this finding's primary location, if any.
+    ///
+    /// The action's owner and repository are case-insensitive, and so
+    /// are lowercased; its ref isn't.
+    pub(crate) fn action_reference(&self) -> Option<String> {
+        let primary = self.locations.iter().find(|l| l.symbolic.is_primary())?;
+        if !matches!(
+            primary.symbolic.route.components.last(),
+            Some(RouteComponent::Key(key)) if key == "uses"
+        ) {
+            return None;
+        }
+
+        let value = primary.concrete.feature.trim().strip_prefix("uses:")?;
+        let value = value
+            .split(" #")
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .trim_matches(['"', '\'']);
+
+        match value.split_once('@') {
+            Some((action, git_ref)) => Some(format!("{}@{git_ref}", action.to_lowercase())),
+            None if !value.is_empty() => Some(value.to_lowercase()),
+            None => None,
+        }
+    }
+
+    /// A stable identifier for this finding, e.g. for matching it
+    /// against a baseline or a previous run.
+    ///
+    /// This is a hex-encoded SHA-256 digest of the finding's ident and
+    /// its primary location's (normalized, relative) path, symbolic route,
+    /// and annotation. Remote paths are qualified with their repository.
+    /// Concrete positions are deliberately excluded, so that the
+    /// fingerprint survives edits elsewhere in the file, including ones
+    /// that renumber its lines.
+    pub(crate) fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+}
+
+/// Qualifies `ident` by `subident`, if there is one.
+pub(crate) fn qualified_ident<'a>(ident: &'a str, subident: Option<&str>) -> Cow<'a, str> {
+    match subident {
+        Some(subident) => format!("{ident}/{subident}").into(),
+        None => ident.into(),
+    }
+}
+
+/// Computes a finding fingerprint from its constituent parts.
+///
+/// See [`Finding::fingerprint`].
+pub(crate) fn fingerprint(ident: &str, key: &InputKey, route: &Route, annotation: &str) -> String {
+    // Absolute paths (e.g. to individual workflows) are made relative to
+    // the current directory where possible, so that the same checkout
+    // produces the same fingerprints regardless of where it lives.
+    let path = Utf8Path::new(key.sarif_path());
+    let cwd = std::env::current_dir().ok();
+    let path = cwd
+        .as_deref()
+        .and_then(|cwd| path.as_std_path().strip_prefix(cwd).ok())
+        .and_then(Utf8Path::from_path)
+        .unwrap_or(path);
+
+    let path = path.as_str().replace('\\', "/");
+    let path = path.strip_prefix("./").unwrap_or(&path);
+
+    let mut hasher = Sha256::new();
+    hasher.update(ident);
+    hasher.update("\0");
+    // Remote inputs from different repositories can share a path,
+    // so their repository is part of their identity.
+    if let Some(repository) = key.repository() {
+        hasher.update(repository);
+        hasher.update("/");
+    }
+    hasher.update(path);
+    for component in &route.components {
+        hasher.update("\0");
+        match component {
+            RouteComponent::Key(key) => hasher.update(key.as_bytes()),
+            RouteComponent::Index(idx) => hasher.update(idx.to_string()),
+        }
+    }
+    hasher.update("\0");
+    hasher.update(annotation);
+
+    format!("{:x}", hasher.finalize())
 }
 
 pub(crate) struct FindingBuilder<'doc> {
     ident: &'static str,
+    subident: Option<String>,
     desc: &'static str,
     url: &'static str,
     severity: Severity,
-    confidence: Confidence,
+    /// Unlike severity, there's no sensible default for a finding's
+    /// confidence, so every audit must set it; see [`FindingBuilder::build`].
+    confidence: Option<Confidence>,
     persona: Persona,
     raw_locations: Vec<Location<'doc>>,
     locations: Vec<SymbolicLocation<'doc>>,
+    external_locations: Vec<ExternalLocation>,
+    remediation: Option<Remediation>,
+    tags: Vec<FindingTag>,
 }
 
 impl<'doc> FindingBuilder<'doc> {
     pub(crate) fn new(ident: &'static str, desc: &'static str, url: &'static str) -> Self {
         Self {
             ident,
+            subident: None,
             desc,
             url,
             severity: Default::default(),
-            confidence: Default::default(),
+            confidence: None,
             persona: Default::default(),
             raw_locations: vec![],
             locations: vec![],
+            external_locations: vec![],
+            remediation: None,
+            tags: vec![],
         }
     }
 
+    pub(crate) fn subident(mut self, subident: impl Into<String>) -> Self {
+        self.subident = Some(subident.into());
+        self
+    }
+
     pub(crate) fn severity(mut self, severity: Severity) -> Self {
         self.severity = severity;
         self
     }
 
     pub(crate) fn confidence(mut self, confidence: Confidence) -> Self {
-        self.confidence = confidence;
+        self.confidence = Some(confidence);
         self
     }
 
@@ -518,15 +1041,59 @@ impl<'doc> FindingBuilder<'doc> {
         self
     }
 
+    pub(crate) fn add_external_location(mut self, location: ExternalLocation) -> Self {
+        self.external_locations.push(location);
+        self
+    }
+
+    pub(crate) fn remediation(mut self, remediation: Remediation) -> Self {
+        self.remediation = Some(remediation);
+        self
+    }
+
+    pub(crate) fn tag(mut self, tag: FindingTag) -> Self {
+        if !self.tags.contains(&tag) {
+            self.tags.push(tag);
+        }
+        self
+    }
+
+    /// Adds the given step's context locations (see
+    /// [`StepCommon::context_locations`]) as related locations.
+    pub(crate) fn add_step_context(mut self, step: &impl StepCommon<'doc>) -> Self {
+        self.locations.extend(step.context_locations());
+        self
+    }
+
     pub(crate) fn build<'a>(
         self,
         document: &'a impl AsDocument<'a, 'doc>,
     ) -> Result<Finding<'doc>> {
-        let mut locations = self
-            .locations
-            .iter()
-            .map(|l| l.clone().concretize(document.as_document()))
-            .collect::<Result<Vec<_>>>()?;
+        let Some(confidence) = self.confidence else {
+            return Err(anyhow!(
+                "API misuse: {ident} finding has no confidence",
+                ident = self.ident
+            ));
+        };
+
+        let mut locations = vec![];
+        for location in &self.locations {
+            match location.clone().concretize_aliased(document.as_document()) {
+                Ok((location, definition)) => {
+                    // NOTE: Only the primary location's definition is shown,
+                    // since a finding's other locations are typically within
+                    // the same aliased definition.
+                    let primary = location.symbolic.is_primary();
+                    locations.push(location);
+                    if let Some(definition) = definition.filter(|_| primary) {
+                        locations.push(definition);
+                    }
+                }
+                // Context is best-effort, so it never fails a finding.
+                Err(_) if location.is_context() => continue,
+                Err(e) => return Err(e),
+            }
+        }
 
         locations.extend(self.raw_locations);
 
@@ -538,23 +1105,47 @@ impl<'doc> FindingBuilder<'doc> {
 
         let should_ignore = Self::ignored_from_inlined_comment(&locations, self.ident);
 
+        // NOTE: Safe unwrap, since we've checked for a primary location above.
+        let primary = locations.iter().find(|l| l.symbolic.is_primary()).unwrap();
+        let fingerprint = fingerprint(
+            &qualified_ident(self.ident, self.subident.as_deref()),
+            primary.symbolic.key,
+            &primary.symbolic.route,
+            &primary.symbolic.annotation,
+        );
+
         Ok(Finding {
             ident: self.ident,
+            subident: self.subident,
             desc: self.desc,
             url: self.url,
+            repository: primary.symbolic.key.repository(),
+            collection: Collection::default(),
+            template: primary.symbolic.key.is_template(),
+            vendored: false,
             determinations: Determinations {
-                confidence: self.confidence,
+                confidence,
                 severity: self.severity,
                 persona: self.persona,
             },
             locations,
+            occurrences: vec![],
+            external_locations: self.external_locations,
+            remediation: self.remediation,
+            tags: self.tags,
             ignored: should_ignore,
+            ignore_reason: None,
+            baselined: false,
+            hidden: false,
+            filtered: false,
+            fingerprint,
         })
     }
 
     fn ignored_from_inlined_comment(locations: &[Location], id: &str) -> bool {
         locations
             .iter()
+            .filter(|l| !l.symbolic.is_context())
             .flat_map(|l| &l.concrete.comments)
             .any(|c| c.ignores(id))
     }
@@ -562,7 +1153,9 @@ impl<'doc> FindingBuilder<'doc> {
 
 #[cfg(test)]
 mod tests {
-    use crate::finding::Comment;
+    use crate::finding::{Comment, Confidence, FindingBuilder, Point, RouteComponent, Subfeature};
+    use crate::models::{AsDocument as _, Workflow};
+    use crate::registry::InputKey;
 
     #[test]
     fn test_comment_ignores() {
@@ -610,4 +1203,250 @@ mod tests {
             )
         }
     }
+
+    /// Recomputes a point from scratch, as a byte offset from the
+    /// start of its `\n`-delimited line.
+    fn naive_point(source: &str, offset: usize) -> (usize, usize) {
+        let before = &source[..offset];
+        let row = before.matches('\n').count();
+        let column = offset - before.rfind('\n').map_or(0, |idx| idx + 1);
+        (row, column)
+    }
+
+    #[test]
+    fn test_concretize_exact_offsets() {
+        let cases: &[(&str, &[RouteComponent], Option<Subfeature>, &str)] = &[
+            // CRLF line endings.
+            (
+                "on: push\r\njobs:\r\n  test:\r\n    runs-on: ubuntu-latest\r\n    steps:\r\n      - run: echo hi\r\n",
+                &["jobs".into(), "test".into(), "runs-on".into()],
+                None,
+                "runs-on: ubuntu-latest",
+            ),
+            (
+                "on: push\r\njobs:\r\n  test:\r\n    runs-on: ubuntu-latest\r\n    steps:\r\n      - run: echo ${{ github.ref }}\r\n",
+                &[
+                    "jobs".into(),
+                    "test".into(),
+                    "steps".into(),
+                    0.into(),
+                    "run".into(),
+                ],
+                Some(Subfeature::new(0, "${{ github.ref }}")),
+                "${{ github.ref }}",
+            ),
+            // Multi-byte UTF-8 in comments ahead of (and on the same line as) the feature.
+            (
+                "# héllo wörld 🌈\non: push # ☃☃☃\njobs:\n  test: # 日本語\n    runs-on: ubuntu-latest # ✨\n    steps:\n      - run: echo hi\n",
+                &["jobs".into(), "test".into(), "runs-on".into()],
+                None,
+                "runs-on: ubuntu-latest",
+            ),
+            (
+                "# 🌈🌈🌈\non: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: echo ${{ github.ref }} # ünïcödé\n",
+                &[
+                    "jobs".into(),
+                    "test".into(),
+                    "steps".into(),
+                    0.into(),
+                    "run".into(),
+                ],
+                Some(Subfeature::new(0, "${{ github.ref }}")),
+                "${{ github.ref }}",
+            ),
+            // Block scalars.
+            (
+                "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: |\n          echo a\n          echo ${{ github.ref }}\n",
+                &[
+                    "jobs".into(),
+                    "test".into(),
+                    "steps".into(),
+                    0.into(),
+                    "run".into(),
+                ],
+                Some(Subfeature::new(0, "${{ github.ref }}")),
+                "${{ github.ref }}",
+            ),
+            (
+                "on: push\r\njobs:\r\n  test:\r\n    runs-on: ubuntu-latest\r\n    steps:\r\n      - run: >-\r\n          echo ☃\r\n          echo ${{ github.ref }}\r\n",
+                &[
+                    "jobs".into(),
+                    "test".into(),
+                    "steps".into(),
+                    0.into(),
+                    "run".into(),
+                ],
+                Some(Subfeature::new(0, "${{ github.ref }}")),
+                "${{ github.ref }}",
+            ),
+        ];
+
+        for (source, route, subfeature, expected) in cases {
+            let workflow =
+                Workflow::from_string(source.to_string(), InputKey::local("fake", None).unwrap())
+                    .unwrap();
+
+            let mut location = workflow.location().with_keys(route);
+            if let Some(subfeature) = subfeature {
+                location = location.with_subfeature(subfeature.clone());
+            }
+
+            let concrete = location
+                .concretize(workflow.as_document())
+                .unwrap()
+                .concrete
+                .location;
+
+            assert_eq!(concrete.offset_span, concrete.start_byte..concrete.end_byte);
+            assert_eq!(&source[concrete.start_byte..concrete.end_byte], *expected);
+
+            let Point { row, column } = concrete.start_point;
+            assert_eq!((row, column), naive_point(source, concrete.start_byte));
+            let Point { row, column } = concrete.end_point;
+            assert_eq!((row, column), naive_point(source, concrete.end_byte));
+        }
+    }
+
+    #[test]
+    fn test_concretize_block_scalar() {
+        let source = "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: | # ✨\n          echo ☃\n          echo hi\n";
+
+        let workflow =
+            Workflow::from_string(source.into(), InputKey::local("fake", None).unwrap()).unwrap();
+        let concrete = workflow
+            .location()
+            .with_keys(&[
+                "jobs".into(),
+                "test".into(),
+                "steps".into(),
+                0.into(),
+                "run".into(),
+            ])
+            .concretize(workflow.as_document())
+            .unwrap()
+            .concrete;
+
+        let span = concrete.location.start_byte..concrete.location.end_byte;
+        assert!(source[span.clone()].starts_with("run: | # ✨\n"));
+        assert!(source[span.clone()].contains("echo ☃\n          echo hi"));
+        assert_eq!(
+            (
+                concrete.location.start_point.row,
+                concrete.location.start_point.column
+            ),
+            naive_point(source, span.start)
+        );
+        assert_eq!(
+            (
+                concrete.location.end_point.row,
+                concrete.location.end_point.column
+            ),
+            naive_point(source, span.end)
+        );
+    }
+
+    #[test]
+    fn test_build_requires_confidence() {
+        let workflow = Workflow::from_string(
+            "on: push\njobs: {}\n".into(),
+            InputKey::local("fake", None).unwrap(),
+        )
+        .unwrap();
+        let builder = || {
+            FindingBuilder::new("test", "test", "https://example.com")
+                .add_location(workflow.location().with_keys(&["on".into()]).primary())
+        };
+
+        assert!(builder().build(&workflow).is_err());
+        assert_eq!(
+            builder()
+                .confidence(Confidence::Low)
+                .build(&workflow)
+                .unwrap()
+                .determinations
+                .confidence,
+            Confidence::Low
+        );
+    }
+
+    #[test]
+    fn test_region_end() {
+        let steps: &[RouteComponent] = &[
+            "jobs".into(),
+            "test".into(),
+            "steps".into(),
+            0.into(),
+            "run".into(),
+        ];
+
+        // Each case is a source, a route into it, and the expected start
+        // and (region) end of the routed feature.
+        type Case<'a> = (
+            &'a str,
+            &'a [RouteComponent<'a>],
+            (usize, usize),
+            (usize, usize),
+        );
+
+        let cases: &[Case] = &[
+            // Single-line values are unchanged.
+            (
+                "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: echo hi\n",
+                &["jobs".into(), "test".into(), "runs-on".into()],
+                (3, 4),
+                (3, 26),
+            ),
+            // Literal block scalars end on their last line, not the next.
+            (
+                "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: |\n          echo a\n          echo b\n",
+                steps,
+                (5, 8),
+                (7, 16),
+            ),
+            (
+                "on: push\r\njobs:\r\n  test:\r\n    runs-on: ubuntu-latest\r\n    steps:\r\n      - run: |\r\n          echo a\r\n          echo b\r\n",
+                steps,
+                (5, 8),
+                (7, 16),
+            ),
+            // Folded block scalars, too.
+            (
+                "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: >\n          echo a\n          && echo b\n",
+                steps,
+                (5, 8),
+                (7, 19),
+            ),
+            // Block scalars followed by another key.
+            (
+                "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: >-\n          echo a\n          && echo b\n        shell: bash\n",
+                steps,
+                (5, 8),
+                (7, 19),
+            ),
+            // Flow mappings that span lines.
+            (
+                "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    env: { A: a,\n      B: b }\n    steps:\n      - run: echo hi\n",
+                &["jobs".into(), "test".into(), "env".into()],
+                (4, 4),
+                (5, 12),
+            ),
+        ];
+
+        for (source, route, start, end) in cases {
+            let workflow =
+                Workflow::from_string(source.to_string(), InputKey::local("fake", None).unwrap())
+                    .unwrap();
+            let concrete = workflow
+                .location()
+                .with_keys(route)
+                .concretize(workflow.as_document())
+                .unwrap()
+                .concrete;
+
+            let Point { row, column } = concrete.location.start_point;
+            assert_eq!((row, column), *start, "{source:?}");
+            let Point { row, column } = concrete.region_end();
+            assert_eq!((row, column), *end, "{source:?}");
+        }
+    }
 }
@@ -0,0 +1,663 @@
+//! Automatic fixes for findings, i.e. `--fix`, and pinning refs to a
+//! lockfile's commits, i.e. `zizmor pin`.
+//!
+//! Fixes are planned up front as a list of edits per input. That list is
+//! either applied in place or, with `--dry-run`, rendered as a unified
+//! diff, so that the preview always matches what `--fix` would write.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, Write as _};
+use std::ops::Range;
+
+use anyhow::{Context as _, Result, anyhow};
+use camino::Utf8Path;
+use github_actions_models::action;
+use github_actions_models::common::Uses;
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use tempfile::NamedTempFile;
+
+use crate::audit::AuditInput;
+use crate::finding::SymbolicLocation;
+use crate::lock::Lockfile;
+use crate::models::uses::RepositoryUsesExt as _;
+use crate::models::{AsDocument as _, Job, JobExt as _, StepCommon as _};
+use crate::registry::{FindingRegistry, InputKey, InputRegistry};
+
+/// A single replacement of `span` (a byte range) in an input's source.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Edit {
+    pub(crate) span: Range<usize>,
+    pub(crate) replacement: String,
+}
+
+impl Edit {
+    /// Applies `edits` to `source`.
+    ///
+    /// Edits may be given in any order, but must not overlap.
+    fn apply_all(source: &str, edits: &[Edit]) -> Result<String> {
+        let mut edits = edits.iter().collect::<Vec<_>>();
+        edits.sort_by_key(|edit| (edit.span.start, edit.span.end));
+
+        let mut fixed = String::with_capacity(source.len());
+        let mut cursor = 0;
+        for edit in edits {
+            if edit.span.start < cursor {
+                return Err(anyhow!("overlapping edits at byte {}", edit.span.start));
+            }
+
+            fixed.push_str(
+                source
+                    .get(cursor..edit.span.start)
+                    .ok_or_else(|| anyhow!("edit at byte {} is out of bounds", edit.span.start))?,
+            );
+            fixed.push_str(&edit.replacement);
+            cursor = edit.span.end;
+        }
+        fixed.push_str(
+            source
+                .get(cursor..)
+                .ok_or_else(|| anyhow!("edit at byte {cursor} is out of bounds"))?,
+        );
+
+        Ok(fixed)
+    }
+}
+
+/// The edits that pin a `uses:` clause's `git_ref` to `sha`, where `span`
+/// is the byte range of the clause (e.g. `uses: foo/bar@v1`) in `source`.
+///
+/// The original ref is kept in a trailing comment, unless the clause
+/// is followed by anything else on its line (e.g. an existing comment,
+/// or the rest of a flow mapping).
+pub(crate) fn pin_edits(
+    source: &str,
+    span: Range<usize>,
+    git_ref: &str,
+    sha: &str,
+) -> Option<Vec<Edit>> {
+    let clause = source.get(span.clone())?;
+    let start = span.start + clause.rfind(&format!("@{git_ref}"))? + 1;
+    let end = start + git_ref.len();
+
+    let mut edits = vec![Edit {
+        span: start..end,
+        replacement: sha.into(),
+    }];
+
+    let (value_end, line_rest) = after_value(source, end);
+    if line_rest.trim().is_empty() {
+        edits.push(Edit {
+            span: value_end..value_end,
+            replacement: format!(" # {git_ref}"),
+        });
+    }
+
+    Some(edits)
+}
+
+/// The byte range of the value of the `uses:` clause at `span` (e.g.
+/// `uses: "foo/bar@v1"`) in `source`, without any quotes.
+pub(crate) fn uses_value_span(source: &str, span: Range<usize>) -> Option<Range<usize>> {
+    let clause = source.get(span.clone())?;
+    let (_, value) = clause.split_once(':')?;
+
+    let start = span.end - value.len() + (value.len() - value.trim_start().len());
+    let value = value.trim();
+    let quoted = value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')));
+
+    Some(match quoted {
+        true => start + 1..start + value.len() - 1,
+        false => start..start + value.len(),
+    })
+}
+
+/// The offset just past the closing quote (if any) of the value that ends
+/// at `end` in `source`, and the rest of that value's line.
+fn after_value(source: &str, end: usize) -> (usize, &str) {
+    let rest = &source[end..];
+    let value_end = end + rest.find(|c| c != '"' && c != '\'').unwrap_or(rest.len());
+
+    (
+        value_end,
+        source[value_end..].lines().next().unwrap_or_default(),
+    )
+}
+
+/// The ref in the trailing comment of the `uses:` clause at `span`, if
+/// any, e.g. `v4` for `uses: actions/checkout@<sha> # v4`.
+fn commented_ref(source: &str, span: Range<usize>) -> Option<&str> {
+    let (_, line_rest) = after_value(source, span.end);
+
+    line_rest
+        .trim_start()
+        .strip_prefix('#')?
+        .split_whitespace()
+        .next()
+}
+
+/// A repository `uses:` clause, e.g. `uses: foo/bar@v1`.
+struct UsesClause {
+    /// The action (`owner/repo[/subpath]`).
+    name: String,
+    git_ref: Option<String>,
+    /// Whether `git_ref` is a commit.
+    pinned: bool,
+    /// The byte range of the clause in its input's source.
+    span: Range<usize>,
+    /// The (1-based) line that the clause is on.
+    line: usize,
+}
+
+/// Every repository `uses:` clause in `input`, in source order.
+fn repository_uses(input: &AuditInput) -> Result<Vec<UsesClause>> {
+    let mut clauses: Vec<(Option<&Uses>, SymbolicLocation)> = vec![];
+    let mut steps = vec![];
+    let mut composite_steps = vec![];
+
+    match input {
+        AuditInput::Workflow(workflow) => {
+            for job in workflow.jobs() {
+                match job {
+                    Job::NormalJob(job) => steps.extend(job.steps()),
+                    Job::ReusableWorkflowCallJob(job) => {
+                        clauses.push((Some(&job.uses), job.location()))
+                    }
+                }
+            }
+        }
+        AuditInput::Action(action) => {
+            if matches!(action.runs, action::Runs::Composite(_)) {
+                composite_steps.extend(action.steps());
+            }
+        }
+    }
+    clauses.extend(steps.iter().map(|step| (step.uses(), step.location())));
+    clauses.extend(
+        composite_steps
+            .iter()
+            .map(|step| (step.uses(), step.location())),
+    );
+
+    let mut uses = vec![];
+    for (clause, location) in clauses {
+        let Some(Uses::Repository(clause)) = clause else {
+            continue;
+        };
+
+        let location = location
+            .with_keys(&["uses".into()])
+            .concretize(input.as_document())?;
+        let concrete = &location.concrete.location;
+        uses.push(UsesClause {
+            name: match &clause.subpath {
+                Some(subpath) => format!("{}/{}/{subpath}", clause.owner, clause.repo),
+                None => format!("{}/{}", clause.owner, clause.repo),
+            },
+            git_ref: clause.git_ref.clone(),
+            pinned: clause.ref_is_commit(),
+            span: concrete.start_byte..concrete.end_byte,
+            line: concrete.start_point.row + 1,
+        });
+    }
+
+    // NOTE: Reusable workflows come before steps above, so put every
+    // clause back in source order.
+    uses.sort_by_key(|clause| clause.span.start);
+    Ok(uses)
+}
+
+/// A `uses:` clause that `zizmor pin` couldn't check against the lockfile.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct PinProblem {
+    /// Where the clause is, as `path:line`.
+    pub(crate) location: String,
+    /// The action (`owner/repo[/subpath]`).
+    pub(crate) name: String,
+    /// The ref that the clause uses.
+    pub(crate) git_ref: String,
+    pub(crate) kind: PinProblemKind,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum PinProblemKind {
+    /// The ref isn't in the lockfile, so it was left as-is.
+    Unlocked,
+    /// The ref is already pinned to a commit, but its commented ref
+    /// (e.g. `# v4`) is locked to a different commit.
+    Disagrees { commented: String, locked: String },
+}
+
+impl Display for PinProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            location,
+            name,
+            git_ref,
+            kind,
+        } = self;
+
+        match kind {
+            PinProblemKind::Unlocked => {
+                write!(f, "{location}: {name}@{git_ref}: not in the lockfile")
+            }
+            PinProblemKind::Disagrees { commented, locked } => write!(
+                f,
+                "{location}: {name}@{git_ref}: pinned to a different commit than {commented} \
+                 is locked to ({locked})"
+            ),
+        }
+    }
+}
+
+/// The planned edits for a single input.
+struct FileFix<'a> {
+    key: &'a InputKey,
+    source: &'a str,
+    edits: Vec<Edit>,
+    /// How many findings (or, for `zizmor pin`, refs) these edits fix.
+    nfixed: usize,
+}
+
+impl FileFix<'_> {
+    /// The input's fixed source.
+    fn fixed(&self) -> Result<String> {
+        Edit::apply_all(self.source, &self.edits)
+            .with_context(|| format!("couldn't fix {}", self.key.presentation_path()))
+    }
+
+    /// A unified diff between the input's current and fixed sources,
+    /// suitable for `git apply`.
+    fn diff(&self) -> Result<String> {
+        let fixed = self.fixed()?;
+        let path = self.key.presentation_path();
+        let path = path.strip_prefix("./").unwrap_or(path);
+
+        Ok(TextDiff::from_lines(self.source, &fixed)
+            .unified_diff()
+            .header(&format!("a/{path}"), &format!("b/{path}"))
+            .to_string())
+    }
+
+    /// Atomically replaces the input's file with its fixed source.
+    fn write(&self) -> Result<()> {
+        let fixed = self.fixed()?;
+        let path = Utf8Path::new(self.key.presentation_path());
+
+        let write = || -> Result<()> {
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_str().is_empty() => dir,
+                _ => Utf8Path::new("."),
+            };
+
+            let mut tmp = NamedTempFile::new_in(dir)?;
+            tmp.write_all(fixed.as_bytes())?;
+            // Keep the original file's permissions.
+            tmp.as_file()
+                .set_permissions(fs::metadata(path)?.permissions())?;
+            tmp.persist(path)?;
+
+            Ok(())
+        };
+
+        write().with_context(|| format!("couldn't write fixes to {path}"))
+    }
+}
+
+/// Every fix that applies to a run's findings, by input.
+pub(crate) struct FixPlan<'a> {
+    files: Vec<FileFix<'a>>,
+    /// How applying each planned edit is logged, e.g. `("fixed", "finding")`.
+    logged_as: (&'static str, &'static str),
+}
+
+impl<'a> FixPlan<'a> {
+    /// Plans fixes for every fixable finding in `results`, i.e. every
+    /// finding whose [`Remediation`](crate::finding::Remediation) has edits.
+    ///
+    /// Only local inputs are fixed. Currently, only `unpinned-uses`
+    /// findings for actions with a symbolic ref are fixable, when the ref
+    /// could be resolved (online) to the commit that they're pinned to.
+    pub(crate) fn new(registry: &'a InputRegistry, results: &FindingRegistry<'a>) -> Result<Self> {
+        let mut files: BTreeMap<&InputKey, FileFix> = BTreeMap::new();
+
+        for finding in results.findings() {
+            let Some(remediation) = finding
+                .remediation
+                .as_ref()
+                .filter(|remediation| !remediation.edits.is_empty())
+            else {
+                continue;
+            };
+
+            let Some(location) = finding.locations.iter().find(|l| l.symbolic.is_primary()) else {
+                continue;
+            };
+
+            let key = location.symbolic.key;
+            if !matches!(key, InputKey::Local(_)) {
+                continue;
+            }
+
+            let file = files.entry(key).or_insert_with(|| FileFix {
+                key,
+                source: registry.get_input(key).as_document().source(),
+                edits: vec![],
+                nfixed: 0,
+            });
+
+            // NOTE: Several findings can share a remediation, e.g. when
+            // the same clause is audited under several configurations, or
+            // is used via several YAML aliases.
+            if remediation
+                .edits
+                .iter()
+                .any(|edit| file.edits.contains(edit))
+            {
+                continue;
+            }
+            file.edits.extend(remediation.edits.iter().cloned());
+            file.nfixed += 1;
+        }
+
+        Ok(Self {
+            files: files.into_values().collect(),
+            logged_as: ("fixed", "finding"),
+        })
+    }
+
+    /// Plans pinning every symbolic ref in `registry`'s local inputs to
+    /// the commit that `lockfile` locks it to, i.e. `zizmor pin`.
+    ///
+    /// Refs that aren't locked are left as-is and reported, as are refs
+    /// that are already pinned to a commit other than the one that their
+    /// commented ref (e.g. `# v4`) is locked to.
+    pub(crate) fn from_lockfile(
+        registry: &'a InputRegistry,
+        lockfile: &Lockfile,
+    ) -> Result<(Self, Vec<PinProblem>)> {
+        let mut files: BTreeMap<&InputKey, FileFix> = BTreeMap::new();
+        let mut problems = vec![];
+
+        for (key, input) in registry.iter_inputs() {
+            if !matches!(key, InputKey::Local(_)) {
+                continue;
+            }
+
+            let source = input.as_document().source();
+            for UsesClause {
+                name,
+                git_ref,
+                pinned,
+                span,
+                line,
+            } in repository_uses(input)?
+            {
+                let Some(git_ref) = git_ref.as_deref() else {
+                    continue;
+                };
+
+                let problem = |kind| PinProblem {
+                    location: format!("{path}:{line}", path = key.presentation_path()),
+                    name: name.clone(),
+                    git_ref: git_ref.into(),
+                    kind,
+                };
+
+                if pinned {
+                    let refs = lockfile.actions.get(&name);
+                    let commented = commented_ref(source, span.clone());
+                    match commented.and_then(|commented| lockfile.locked_sha(&name, commented)) {
+                        Some(locked) if locked.eq_ignore_ascii_case(git_ref) => {}
+                        Some(locked) => problems.push(problem(PinProblemKind::Disagrees {
+                            // NOTE: Safe unwrap, since there's a locked commit.
+                            commented: commented.unwrap().into(),
+                            locked: locked.into(),
+                        })),
+                        // Without a locked commented ref, the commit only
+                        // needs to be locked under some ref.
+                        None if refs.is_some_and(|refs| {
+                            refs.iter().any(|(locked_ref, locked)| {
+                                locked_ref.eq_ignore_ascii_case(git_ref)
+                                    || locked.eq_ignore_ascii_case(git_ref)
+                            })
+                        }) => {}
+                        None => problems.push(problem(PinProblemKind::Unlocked)),
+                    }
+                    continue;
+                }
+
+                let Some(sha) = lockfile.locked_sha(&name, git_ref) else {
+                    problems.push(problem(PinProblemKind::Unlocked));
+                    continue;
+                };
+                let Some(edits) = pin_edits(source, span, git_ref, sha) else {
+                    continue;
+                };
+
+                let file = files.entry(key).or_insert_with(|| FileFix {
+                    key,
+                    source,
+                    edits: vec![],
+                    nfixed: 0,
+                });
+                file.edits.extend(edits);
+                file.nfixed += 1;
+            }
+        }
+
+        Ok((
+            Self {
+                files: files.into_values().collect(),
+                logged_as: ("pinned", "ref"),
+            },
+            problems,
+        ))
+    }
+
+    /// Whether there's nothing to fix.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Writes a unified diff of every planned fix to `sink`.
+    ///
+    /// Inputs without any fixes don't appear in the diff.
+    pub(crate) fn render_diff(&self, mut sink: impl io::Write) -> Result<()> {
+        for file in &self.files {
+            write!(sink, "{}", file.diff()?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies every planned fix in place.
+    pub(crate) fn apply(&self) -> Result<()> {
+        for file in &self.files {
+            file.write()?;
+            tracing::info!(
+                "{verb} {n} {noun}{s} in {path}",
+                verb = self.logged_as.0,
+                n = file.nfixed,
+                noun = self.logged_as.1,
+                s = if file.nfixed == 1 { "" } else { "s" },
+                path = file.key.presentation_path()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Edit, commented_ref, pin_edits, uses_value_span};
+
+    const SHA: &str = "11bd71901bbe5b1630ceea73d27597364c9af683";
+
+    fn pin(source: &str, clause: &str, git_ref: &str) -> String {
+        let start = source.find(clause).unwrap();
+        let edits = pin_edits(source, start..start + clause.len(), git_ref, SHA).unwrap();
+        Edit::apply_all(source, &edits).unwrap()
+    }
+
+    #[test]
+    fn test_apply_all() {
+        let edits = [
+            Edit {
+                span: 6..11,
+                replacement: "there".into(),
+            },
+            Edit {
+                span: 0..0,
+                replacement: "oh, ".into(),
+            },
+        ];
+        assert_eq!(
+            Edit::apply_all("hello world", &edits).unwrap(),
+            "oh, hello there"
+        );
+
+        let overlapping = [
+            Edit {
+                span: 0..5,
+                replacement: "".into(),
+            },
+            Edit {
+                span: 4..6,
+                replacement: "".into(),
+            },
+        ];
+        assert!(Edit::apply_all("hello world", &overlapping).is_err());
+        assert!(
+            Edit::apply_all(
+                "hello",
+                &[Edit {
+                    span: 3..10,
+                    replacement: "".into()
+                }]
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_pin_edits() {
+        // The original ref is kept in a comment.
+        assert_eq!(
+            pin(
+                "steps:\n  - uses: actions/checkout@v4\n",
+                "uses: actions/checkout@v4",
+                "v4"
+            ),
+            format!("steps:\n  - uses: actions/checkout@{SHA} # v4\n")
+        );
+
+        // Quoted values keep their quotes, and CRLF line endings are kept.
+        assert_eq!(
+            pin(
+                "steps:\r\n  - uses: \"actions/checkout@v4\"\r\n",
+                "uses: \"actions/checkout@v4\"",
+                "v4"
+            ),
+            format!("steps:\r\n  - uses: \"actions/checkout@{SHA}\" # v4\r\n")
+        );
+
+        // Existing comments and flow mappings don't get another comment.
+        assert_eq!(
+            pin(
+                "steps:\n  - uses: foo/bar@main # bump me\n",
+                "uses: foo/bar@main",
+                "main"
+            ),
+            format!("steps:\n  - uses: foo/bar@{SHA} # bump me\n")
+        );
+        assert_eq!(
+            pin(
+                "steps: [{uses: foo/bar@v1, with: {x: 1}}]\n",
+                "uses: foo/bar@v1",
+                "v1"
+            ),
+            format!("steps: [{{uses: foo/bar@{SHA}, with: {{x: 1}}}}]\n")
+        );
+
+        // Refs are matched in full, including subpaths and slashes.
+        assert_eq!(
+            pin(
+                "- uses: foo/bar/sub@release/v1\n",
+                "uses: foo/bar/sub@release/v1",
+                "release/v1"
+            ),
+            format!("- uses: foo/bar/sub@{SHA} # release/v1\n")
+        );
+
+        assert!(pin_edits("- uses: foo/bar@v1\n", 2..18, "v2", SHA).is_none());
+    }
+
+    #[test]
+    fn test_uses_value_span() {
+        let value = |source: &str, clause: &str| {
+            let start = source.find(clause).unwrap();
+            uses_value_span(source, start..start + clause.len())
+                .map(|span| source[span].to_string())
+        };
+
+        assert_eq!(
+            value("- uses: foo/bar@v1\n", "uses: foo/bar@v1"),
+            Some("foo/bar@v1".into())
+        );
+        assert_eq!(
+            value("- uses:   'foo/bar@v1' # v1\n", "uses:   'foo/bar@v1'"),
+            Some("foo/bar@v1".into())
+        );
+        assert_eq!(
+            value(
+                "- {uses: \"docker://ubuntu:24.04\"}\n",
+                "uses: \"docker://ubuntu:24.04\""
+            ),
+            Some("docker://ubuntu:24.04".into())
+        );
+        assert_eq!(value("- uses\n", "uses"), None);
+    }
+
+    #[test]
+    fn test_commented_ref() {
+        let commented = |source: &str, clause: &str| {
+            let start = source.find(clause).unwrap();
+            commented_ref(source, start..start + clause.len()).map(str::to_string)
+        };
+
+        assert_eq!(
+            commented(
+                &format!("- uses: foo/bar@{SHA} # v4.2.2\n"),
+                &format!("uses: foo/bar@{SHA}")
+            ),
+            Some("v4.2.2".into())
+        );
+        assert_eq!(
+            commented(
+                &format!("- uses: 'foo/bar@{SHA}' #v4 (latest)\n- run: echo # v5\n"),
+                &format!("uses: 'foo/bar@{SHA}'")
+            ),
+            Some("v4".into())
+        );
+        assert_eq!(
+            commented(
+                &format!("- uses: foo/bar@{SHA}\n  # v4\n"),
+                &format!("uses: foo/bar@{SHA}")
+            ),
+            None
+        );
+        assert_eq!(
+            commented(
+                &format!("- {{uses: foo/bar@{SHA}}} # v4\n"),
+                &format!("uses: foo/bar@{SHA}")
+            ),
+            None
+        );
+    }
+}
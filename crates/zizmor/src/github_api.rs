@@ -3,7 +3,12 @@
 //! Build on synchronous reqwest to avoid octocrab's need to taint
 //! the whole codebase with async.
 
-use std::{io::Read, ops::Deref, path::Path};
+use std::{
+    io::Read,
+    ops::Deref,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use anyhow::{Context, Result, anyhow};
 use camino::Utf8Path;
@@ -18,13 +23,16 @@ use reqwest::{
     header::{ACCEPT, AUTHORIZATION, HeaderMap, USER_AGENT},
 };
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
-use serde::{Deserialize, de::DeserializeOwned};
+use serde::{
+    Deserialize, Serialize,
+    de::{DeserializeOwned, IgnoredAny},
+};
 use tar::Archive;
 use tracing::instrument;
 
 use crate::{
-    InputRegistry,
-    registry::{InputKey, InputKind},
+    CollectionMode, Exclusions, InputRegistry,
+    registry::{Collection, InputKey, InputKind},
     utils::PipeSelf,
 };
 
@@ -53,17 +61,67 @@ impl GitHubHost {
         }
     }
 
-    fn to_api_url(&self) -> String {
+    pub(crate) fn to_api_url(&self) -> String {
         match self {
             Self::Enterprise(host) => format!("https://{host}/api/v3"),
             Self::Standard(host) => format!("https://api.{host}"),
         }
     }
+
+    /// The host's (lowercased) domain name, e.g. `github.com`.
+    pub(crate) fn hostname(&self) -> String {
+        match self {
+            Self::Enterprise(host) | Self::Standard(host) => host.to_ascii_lowercase(),
+        }
+    }
+}
+
+impl Default for GitHubHost {
+    fn default() -> Self {
+        Self::Standard("github.com".into())
+    }
+}
+
+/// The root cause of an API error when GitHub rejected the request
+/// because of its rate limits.
+///
+/// Once a [`Client`] has been rate limited, it stops sending requests
+/// for the rest of the run, and fails them with [`RateLimited::Earlier`].
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum RateLimited {
+    /// GitHub rejected this request.
+    #[error("rate limited by the GitHub API")]
+    Now,
+    /// GitHub rejected an earlier request, so this one wasn't sent.
+    #[error("not sent, since the GitHub API rate limited an earlier request")]
+    Earlier,
+}
+
+impl RateLimited {
+    /// Returns `result`'s value, or `None` if it failed because of rate
+    /// limits, in which case `skipping` (what the caller can no longer
+    /// check) is logged the first time. Other errors are propagated.
+    pub(crate) fn skip<T>(result: Result<T>, skipping: &str) -> Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(e) => match e.downcast_ref::<RateLimited>() {
+                Some(RateLimited::Now) => {
+                    tracing::warn!("{e:#}; skipping {skipping}");
+                    Ok(None)
+                }
+                Some(RateLimited::Earlier) => Ok(None),
+                None => Err(e),
+            },
+        }
+    }
 }
 
 pub(crate) struct Client {
     api_base: String,
     http: ClientWithMiddleware,
+    /// Set once GitHub has rate limited one of our requests; see
+    /// [`RateLimited`].
+    rate_limited: AtomicBool,
 }
 
 impl Client {
@@ -107,13 +165,36 @@ impl Client {
         Self {
             api_base: hostname.to_api_url(),
             http,
+            rate_limited: AtomicBool::new(false),
         }
     }
 
-    async fn paginate<T: DeserializeOwned>(
-        &self,
-        endpoint: &str,
-    ) -> reqwest_middleware::Result<Vec<T>> {
+    /// Fails with [`RateLimited::Earlier`] if we've already been rate
+    /// limited, rather than sending another request.
+    fn check_rate_limit(&self) -> Result<()> {
+        if self.rate_limited.load(Ordering::Relaxed) {
+            Err(RateLimited::Earlier.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Records that we've been rate limited, returning the error (with
+    /// `msg` as its context) for the rejected request.
+    fn rate_limit_error(&self, msg: String) -> anyhow::Error {
+        self.rate_limited.store(true, Ordering::Relaxed);
+        anyhow::Error::new(RateLimited::Now).context(msg)
+    }
+
+    /// Allows requests again after being rate limited, for callers that
+    /// have waited for the limit to pass.
+    pub(crate) fn reset_rate_limit(&self) {
+        self.rate_limited.store(false, Ordering::Relaxed);
+    }
+
+    async fn paginate<T: DeserializeOwned>(&self, endpoint: &str) -> Result<Vec<T>> {
+        self.check_rate_limit()?;
+
         let mut dest = vec![];
         let url = format!("{api_base}/{endpoint}", api_base = self.api_base);
 
@@ -121,15 +202,21 @@ impl Client {
         // the remaining number of pages. But this is annoying, and we are
         // not nice, so we simply request pages until GitHub bails on us
         // and returns empty results.
-        let mut pageno = 0;
+        let mut pageno = 1;
         loop {
             let resp = self
                 .http
                 .get(&url)
                 .query(&[("page", pageno), ("per_page", 100)])
                 .send()
-                .await?
-                .error_for_status()?;
+                .await?;
+            if Client::resp_rate_limited(&resp) {
+                return Err(self.rate_limit_error(format!(
+                    "error from GitHub API while listing {endpoint}: {status}",
+                    status = resp.status()
+                )));
+            }
+            let resp = resp.error_for_status()?;
 
             let page = resp.json::<Vec<T>>().await?;
             if page.is_empty() {
@@ -148,42 +235,74 @@ impl Client {
     ///
     /// The error variants communicate all other status codes,
     /// with additional context where helpful.
-    fn resp_present(resp: Response) -> Result<bool> {
+    fn resp_present(&self, resp: Response) -> Result<bool> {
         match resp.status() {
             StatusCode::OK => Ok(true),
             StatusCode::NOT_FOUND => Ok(false),
+            _ if Client::resp_rate_limited(&resp) => Err(self.rate_limit_error(format!(
+                "error from GitHub API: {status}",
+                status = resp.status()
+            ))),
             StatusCode::FORBIDDEN => Err(anyhow::Error::from(resp.error_for_status().unwrap_err())
                 .context("request forbidden; token permissions may be insufficient")),
             _ => Err(resp.error_for_status().unwrap_err().into()),
         }
     }
 
+    /// Returns whether the response indicates that we've been rate limited,
+    /// either via a secondary rate limit (429) or by exhausting our primary
+    /// rate limit (403 with no remaining requests).
+    fn resp_rate_limited(resp: &Response) -> bool {
+        match resp.status() {
+            StatusCode::TOO_MANY_REQUESTS => true,
+            StatusCode::FORBIDDEN => resp
+                .headers()
+                .get("x-ratelimit-remaining")
+                .is_some_and(|remaining| remaining == "0"),
+            _ => false,
+        }
+    }
+
+    /// Builds an error for a failed ref lookup, preserving `RateLimited`
+    /// as the root cause when applicable.
+    fn ref_error(&self, resp: &Response, owner: &str, repo: &str, git_ref: &str) -> anyhow::Error {
+        let msg = format!(
+            "{owner}/{repo}: error from GitHub API while accessing ref {git_ref}: {status}",
+            status = resp.status()
+        );
+
+        if Client::resp_rate_limited(resp) {
+            self.rate_limit_error(msg)
+        } else {
+            anyhow!(msg)
+        }
+    }
+
     #[instrument(skip(self))]
     #[tokio::main]
     pub(crate) async fn list_branches(&self, owner: &str, repo: &str) -> Result<Vec<Branch>> {
         self.paginate(&format!("repos/{owner}/{repo}/branches"))
             .await
-            .map_err(Into::into)
     }
 
     #[instrument(skip(self))]
     #[tokio::main]
     pub(crate) async fn list_tags(&self, owner: &str, repo: &str) -> Result<Vec<Tag>> {
-        self.paginate(&format!("repos/{owner}/{repo}/tags"))
-            .await
-            .map_err(Into::into)
+        self.paginate(&format!("repos/{owner}/{repo}/tags")).await
     }
 
     #[instrument(skip(self))]
     #[tokio::main]
     pub(crate) async fn has_branch(&self, owner: &str, repo: &str, branch: &str) -> Result<bool> {
+        self.check_rate_limit()?;
+
         let url = format!(
             "{api_base}/repos/{owner}/{repo}/git/ref/heads/{branch}",
             api_base = self.api_base
         );
 
         let resp = self.http.get(&url).send().await?;
-        Client::resp_present(resp).with_context(|| {
+        self.resp_present(resp).with_context(|| {
             format!("{owner}/{repo}: error from the GitHub API while checking {branch}")
         })
     }
@@ -191,13 +310,15 @@ impl Client {
     #[instrument(skip(self))]
     #[tokio::main]
     pub(crate) async fn has_tag(&self, owner: &str, repo: &str, tag: &str) -> Result<bool> {
+        self.check_rate_limit()?;
+
         let url = format!(
             "{api_base}/repos/{owner}/{repo}/git/ref/tags/{tag}",
             api_base = self.api_base
         );
 
         let resp = self.http.get(&url).send().await?;
-        Client::resp_present(resp).with_context(|| {
+        self.resp_present(resp).with_context(|| {
             format!("{owner}/{repo}: error from the GitHub API while checking {tag}")
         })
     }
@@ -210,6 +331,8 @@ impl Client {
         repo: &str,
         git_ref: &str,
     ) -> Result<Option<String>> {
+        self.check_rate_limit()?;
+
         // GitHub Actions generally resolves branches before tags, so try
         // the repo's branches first.
         let url = format!(
@@ -230,14 +353,10 @@ impl Client {
                 match resp.status() {
                     StatusCode::OK => Ok(Some(resp.json::<GitRef>().await?.object.sha)),
                     StatusCode::NOT_FOUND => Ok(None),
-                    s => Err(anyhow!(
-                        "{owner}/{repo}: error from GitHub API while accessing ref {git_ref}: {s}"
-                    )),
+                    _ => Err(self.ref_error(&resp, owner, repo, git_ref)),
                 }
             }
-            s => Err(anyhow!(
-                "{owner}/{repo}: error from GitHub API while accessing ref {git_ref}: {s}"
-            )),
+            _ => Err(self.ref_error(&resp, owner, repo, git_ref)),
         }
     }
 
@@ -292,6 +411,117 @@ impl Client {
         .map_err(Into::into)
     }
 
+    /// Returns the status of the given repository, i.e. whether it's
+    /// archived or doesn't exist (or isn't visible to our token).
+    #[instrument(skip(self))]
+    #[tokio::main]
+    pub(crate) async fn repository_status(
+        &self,
+        owner: &str,
+        repo: &str,
+    ) -> Result<RepositoryStatus> {
+        self.check_rate_limit()?;
+
+        let url = format!("{api_base}/repos/{owner}/{repo}", api_base = self.api_base);
+
+        let resp = self.http.get(url).send().await?;
+        match resp.status() {
+            StatusCode::OK => match resp.json::<Repository>().await?.archived {
+                true => Ok(RepositoryStatus::Archived),
+                false => Ok(RepositoryStatus::Active),
+            },
+            StatusCode::NOT_FOUND => Ok(RepositoryStatus::Missing),
+            // NOTE: We treat all 403s here as rate limits, since the
+            // repository endpoint doesn't need any token permissions.
+            StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => {
+                Err(self.rate_limit_error(format!(
+                    "{owner}/{repo}: error from GitHub API while accessing repository: {status}",
+                    status = resp.status()
+                )))
+            }
+            _ => Err(resp.error_for_status().unwrap_err().into()),
+        }
+    }
+
+    /// Returns the canonical `owner/repo` of the given repository, i.e.
+    /// the one that its requested name redirects to if it's been renamed,
+    /// or `None` if it doesn't exist (or isn't visible to our token).
+    #[instrument(skip(self))]
+    #[tokio::main]
+    pub(crate) async fn canonical_repository(
+        &self,
+        owner: &str,
+        repo: &str,
+    ) -> Result<Option<String>> {
+        self.check_rate_limit()?;
+
+        let url = format!("{api_base}/repos/{owner}/{repo}", api_base = self.api_base);
+
+        // NOTE: The API answers renamed repositories with a redirect to
+        // their canonical location, which is followed transparently.
+        let resp = self.http.get(url).send().await?;
+        match resp.status() {
+            StatusCode::OK => Ok(Some(resp.json::<Repository>().await?.full_name)),
+            StatusCode::NOT_FOUND => Ok(None),
+            StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => {
+                Err(self.rate_limit_error(format!(
+                    "{owner}/{repo}: error from GitHub API while accessing repository: {status}",
+                    status = resp.status()
+                )))
+            }
+            _ => Err(resp.error_for_status().unwrap_err().into()),
+        }
+    }
+
+    /// Returns the given deployment environment in the given repository,
+    /// or `None` if it doesn't exist (or the repository isn't visible to
+    /// our token).
+    #[instrument(skip(self))]
+    #[tokio::main]
+    pub(crate) async fn environment(
+        &self,
+        owner: &str,
+        repo: &str,
+        name: &str,
+    ) -> Result<Option<Environment>> {
+        self.check_rate_limit()?;
+
+        let mut url = reqwest::Url::parse(&format!(
+            "{api_base}/repos/{owner}/{repo}/environments",
+            api_base = self.api_base
+        ))?;
+        // NOTE: Environment names can contain spaces and slashes, so
+        // they're pushed as a single (escaped) path segment.
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("invalid API base: {api_base}", api_base = self.api_base))?
+            .push(name);
+
+        let resp = self.http.get(url).send().await?;
+        match resp.status() {
+            StatusCode::OK => Ok(Some(resp.json::<Environment>().await?)),
+            StatusCode::NOT_FOUND => Ok(None),
+            // NOTE: Like with repositories, we treat all 403s here as rate
+            // limits, since tokens without access to the environment get 404s.
+            StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => {
+                Err(self.rate_limit_error(format!(
+                    "{owner}/{repo}: error from GitHub API while accessing environment {name}: {status}",
+                    status = resp.status()
+                )))
+            }
+            _ => Err(resp.error_for_status().unwrap_err().into()),
+        }
+    }
+
+    /// Returns every repository in the given organization, including
+    /// archived ones.
+    #[instrument(skip(self))]
+    #[tokio::main]
+    pub(crate) async fn list_org_repositories(&self, org: &str) -> Result<Vec<Repository>> {
+        self.paginate(&format!("orgs/{org}/repos"))
+            .await
+            .with_context(|| format!("couldn't list repositories for {org}"))
+    }
+
     #[instrument(skip(self))]
     #[tokio::main]
     pub(crate) async fn gha_advisories(
@@ -322,11 +552,12 @@ impl Client {
     ///
     /// This is an optimized variant of `fetch_audit_inputs` for the workflow-only
     /// collection case.
-    #[instrument(skip(self, registry))]
+    #[instrument(skip(self, exclusions, registry))]
     #[tokio::main]
     pub(crate) async fn fetch_workflows(
         &self,
         slug: &RepositoryUses,
+        exclusions: &Exclusions,
         registry: &mut InputRegistry,
     ) -> Result<()> {
         let owner = &slug.owner;
@@ -360,6 +591,11 @@ impl Client {
             .into_iter()
             .filter(|file| file.name.ends_with(".yml") || file.name.ends_with(".yaml"))
         {
+            if let Some(pattern) = exclusions.exclusion(Utf8Path::new(&file.path)) {
+                tracing::debug!("excluding {path}: matches {pattern}", path = file.path);
+                continue;
+            }
+
             let file_url = format!("{url}/{file}", file = file.name);
             tracing::debug!("fetching {file_url}");
 
@@ -377,23 +613,65 @@ impl Client {
                 .text()
                 .await?;
 
+            let vendored = exclusions.is_vendored(Utf8Path::new(&file.path));
             let key = InputKey::remote(slug, file.path)?;
-            registry.register(InputKind::Workflow, contents, key)?;
+            registry.register_collected(
+                InputKind::Workflow,
+                contents,
+                key,
+                Collection::Standard,
+                vendored,
+            )?;
         }
 
         Ok(())
     }
 
+    /// Fetch a single file from the given remote repository slug,
+    /// returning `None` if it doesn't exist.
+    #[instrument(skip(self))]
+    #[tokio::main]
+    pub(crate) async fn fetch_file(
+        &self,
+        slug: &RepositoryUses,
+        path: &str,
+    ) -> Result<Option<String>> {
+        let url = format!(
+            "{api_base}/repos/{owner}/{repo}/contents/{path}",
+            api_base = self.api_base,
+            owner = slug.owner,
+            repo = slug.repo,
+        );
+
+        let resp = self
+            .http
+            .get(&url)
+            .header(ACCEPT, "application/vnd.github.raw+json")
+            .pipe(|req| match slug.git_ref.as_ref() {
+                Some(g) => req.query(&[("ref", g)]),
+                None => req,
+            })
+            .send()
+            .await?;
+
+        match resp.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            _ => Ok(Some(resp.error_for_status()?.text().await?)),
+        }
+    }
+
     /// Fetch all auditable inputs (both workflows and actions)
     /// from the given remote repository slug.
     ///
     /// This is much slower than `fetch_workflows`, since it involves
     /// retrieving the entire repository archive and decompressing it.
-    #[instrument(skip(self, registry))]
+    #[instrument(skip(self, mode, exclusions, registry))]
     #[tokio::main]
     pub(crate) async fn fetch_audit_inputs(
         &self,
         slug: &RepositoryUses,
+        mode: &CollectionMode,
+        exclusions: &Exclusions,
         registry: &mut InputRegistry,
     ) -> Result<()> {
         let url = format!(
@@ -439,20 +717,17 @@ impl Client {
                 components.as_path().try_into()?
             };
 
-            if matches!(file_path.extension(), Some("yaml" | "yml"))
-                && file_path
-                    .parent()
-                    .is_some_and(|dir| dir.ends_with(".github/workflows"))
-            {
-                let key = InputKey::remote(slug, file_path.to_string())?;
-                let mut contents = String::with_capacity(entry.size() as usize);
-                entry.read_to_string(&mut contents)?;
-                registry.register(InputKind::Workflow, contents, key)?;
-            } else if matches!(file_path.file_name(), Some("action.yml" | "action.yaml")) {
+            if let Some(pattern) = exclusions.exclusion(file_path) {
+                tracing::debug!("excluding {file_path}: matches {pattern}");
+                continue;
+            }
+
+            if let Some((kind, collection)) = mode.classify(file_path, file_path) {
                 let key = InputKey::remote(slug, file_path.to_string())?;
+                let vendored = exclusions.is_vendored(file_path);
                 let mut contents = String::with_capacity(entry.size() as usize);
                 entry.read_to_string(&mut contents)?;
-                registry.register(InputKind::Action, contents, key)?;
+                registry.register_collected(kind, contents, key, collection, vendored)?;
             }
         }
 
@@ -508,6 +783,67 @@ pub(crate) struct Comparison {
     pub(crate) status: ComparisonStatus,
 }
 
+/// A single repository, as returned by GitHub's repository endpoints.
+///
+/// This model is intentionally incomplete.
+///
+/// See <https://docs.github.com/en/rest/repos/repos?apiVersion=2022-11-28#get-a-repository>
+#[derive(Deserialize)]
+pub(crate) struct Repository {
+    pub(crate) name: String,
+    /// The repository's canonical `owner/name`, which differs from the
+    /// requested one when the repository (or its owner) has been renamed.
+    #[serde(default)]
+    pub(crate) full_name: String,
+    pub(crate) archived: bool,
+    #[serde(default)]
+    pub(crate) topics: Vec<String>,
+}
+
+/// The status of a repository, as determined by [`Client::repository_status`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum RepositoryStatus {
+    Active,
+    Archived,
+    /// The repository doesn't exist, or isn't visible to the token in use.
+    Missing,
+}
+
+/// A deployment environment, as returned by GitHub's environments endpoint.
+///
+/// This model is intentionally incomplete.
+///
+/// See <https://docs.github.com/en/rest/deployments/environments?apiVersion=2022-11-28#get-an-environment>
+#[derive(Deserialize)]
+pub(crate) struct Environment {
+    #[serde(default)]
+    protection_rules: Vec<ProtectionRule>,
+    /// The environment's deployment branch policy, or `None` if any
+    /// branch can deploy to it. Only its presence matters here.
+    deployment_branch_policy: Option<IgnoredAny>,
+}
+
+impl Environment {
+    /// Whether deployments to this environment are gated, i.e. need a
+    /// reviewer's approval or come from restricted branches.
+    ///
+    /// Wait timers alone don't count, since they only delay deployments.
+    pub(crate) fn is_protected(&self) -> bool {
+        self.deployment_branch_policy.is_some()
+            || self
+                .protection_rules
+                .iter()
+                .any(|rule| rule.kind == "required_reviewers")
+    }
+}
+
+#[derive(Deserialize)]
+struct ProtectionRule {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
 /// Represents a GHSA advisory.
 #[derive(Deserialize)]
 pub(crate) struct Advisory {
@@ -524,7 +860,7 @@ pub(crate) struct File {
 
 #[cfg(test)]
 mod tests {
-    use crate::github_api::GitHubHost;
+    use crate::github_api::{Client, Environment, GitHubHost, RateLimited};
 
     #[test]
     fn test_github_host() {
@@ -538,5 +874,77 @@ mod tests {
         ] {
             assert_eq!(GitHubHost::from_clap(host).unwrap().to_api_url(), expected);
         }
+
+        assert_eq!(GitHubHost::default().to_api_url(), "https://api.github.com");
+        assert_eq!(
+            GitHubHost::from_clap("GHE.Example.com").unwrap().hostname(),
+            "ghe.example.com"
+        );
+    }
+
+    #[test]
+    fn test_environment_protection() {
+        for (raw, protected) in [
+            (
+                r#"{"protection_rules": [], "deployment_branch_policy": null}"#,
+                false,
+            ),
+            (r#"{"name": "production"}"#, false),
+            (
+                r#"{"protection_rules": [{"id": 1, "type": "wait_timer", "wait_timer": 30}],
+                    "deployment_branch_policy": null}"#,
+                false,
+            ),
+            (
+                r#"{"protection_rules": [{"id": 1, "type": "required_reviewers", "reviewers": []}],
+                    "deployment_branch_policy": null}"#,
+                true,
+            ),
+            (
+                r#"{"protection_rules": [],
+                    "deployment_branch_policy": {"protected_branches": true, "custom_branch_policies": false}}"#,
+                true,
+            ),
+        ] {
+            let environment = serde_json::from_str::<Environment>(raw).unwrap();
+            assert_eq!(environment.is_protected(), protected, "{raw}");
+        }
+    }
+    #[test]
+    fn test_rate_limited() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let client = Client::new(&GitHubHost::default(), "token", cache_dir.path());
+
+        let first = client.rate_limit_error("rejected".into());
+        assert!(matches!(first.downcast_ref(), Some(RateLimited::Now)));
+        assert!(
+            RateLimited::skip::<()>(Err(first), "checks")
+                .unwrap()
+                .is_none()
+        );
+
+        // Once rate limited, requests fail without being sent.
+        for result in [
+            client.repository_status("owner", "repo").map(drop),
+            client.list_tags("owner", "repo").map(drop),
+            client.has_tag("owner", "repo", "v1").map(drop),
+        ] {
+            let e = result.unwrap_err();
+            assert!(
+                matches!(e.downcast_ref(), Some(RateLimited::Earlier)),
+                "{e:#}"
+            );
+            assert!(
+                RateLimited::skip(Err::<(), _>(e), "checks")
+                    .unwrap()
+                    .is_none()
+            );
+        }
+
+        // Other errors are propagated.
+        assert!(RateLimited::skip::<()>(Err(anyhow::anyhow!("nope")), "checks").is_err());
+
+        client.reset_rate_limit();
+        assert!(client.check_rate_limit().is_ok());
     }
 }
@@ -0,0 +1,507 @@
+//! Lockfiles of the commits that action refs resolve to, i.e. `zizmor lock`.
+//!
+//! A lockfile (`gha.lock` by default) records every `owner/repo[/subpath]`
+//! action used by the inputs, each ref it's used at, and the commit SHA
+//! that the ref resolved to when the lockfile was generated. Docker images
+//! are recorded in the same way, with the digest that they're pinned to.
+//!
+//! Lockfiles are YAML, with every map sorted by key so that regenerating a
+//! lockfile only changes the entries that actually changed.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use std::fs;
+
+use anyhow::{Context as _, Result, anyhow};
+use camino::Utf8Path;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::output::tpa_list::{Action, ActionKind};
+use crate::resolver::UsesResolver;
+
+/// The current version of the lockfile format.
+///
+/// This must be bumped whenever the format changes incompatibly.
+pub(crate) const LOCKFILE_VERSION: u32 = 1;
+
+/// The default lockfile path, relative to the current directory.
+pub(crate) const DEFAULT_LOCKFILE: &str = "gha.lock";
+
+const HEADER: &str = "# Generated by `zizmor lock`. Do not edit by hand.\n";
+
+/// A lockfile.
+#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Lockfile {
+    /// The version of the lockfile format; see [`LOCKFILE_VERSION`].
+    pub(crate) version: u32,
+    /// When the lockfile was generated.
+    #[serde(with = "time::serde::rfc3339")]
+    pub(crate) generated: OffsetDateTime,
+    /// The commit that each action's refs resolved to, keyed by action
+    /// (`owner/repo[/subpath]`) and then by ref.
+    #[serde(default)]
+    pub(crate) actions: BTreeMap<String, BTreeMap<String, String>>,
+    /// The digest that each image's tags are pinned to, keyed by image
+    /// (`docker://[registry/]image`) and then by tag (or digest).
+    ///
+    /// Tags aren't resolved, so images that aren't pinned to a digest
+    /// have no digest recorded.
+    #[serde(default)]
+    pub(crate) images: BTreeMap<String, BTreeMap<String, Option<String>>>,
+}
+
+impl Lockfile {
+    /// An empty lockfile, generated at `generated`.
+    pub(crate) fn new(generated: OffsetDateTime) -> Self {
+        Self {
+            version: LOCKFILE_VERSION,
+            generated,
+            actions: Default::default(),
+            images: Default::default(),
+        }
+    }
+
+    /// Locks every action and image in `actions`, resolving each action's
+    /// ref with `resolver`.
+    ///
+    /// Actions that are already pinned to a commit are locked to that commit.
+    /// Refs that can't be resolved are reported and left out of the lockfile.
+    pub(crate) fn generate(actions: &[Action], resolver: &UsesResolver) -> Resolution {
+        let mut resolution = Resolution {
+            lockfile: Self::new(OffsetDateTime::now_utc()),
+            usages: Default::default(),
+            unresolved: Default::default(),
+        };
+        for action in actions {
+            let name = action.name();
+            match action.kind {
+                ActionKind::Docker => {
+                    let tag = action.git_ref.as_deref().unwrap_or("latest");
+                    let digest = action.pinned_to_sha.then(|| tag.to_string());
+                    resolution
+                        .lockfile
+                        .images
+                        .entry(name.clone())
+                        .or_default()
+                        .insert(tag.into(), digest);
+                    resolution
+                        .usages
+                        .entry((name, tag.into()))
+                        .or_default()
+                        .insert(action.qualified_path());
+                }
+                // There's nothing to lock for a `uses:` that's selected dynamically.
+                ActionKind::Dynamic => continue,
+                ActionKind::Action | ActionKind::ReusableWorkflow => {
+                    // Actions without a ref can't be used, so there's nothing to lock.
+                    let Some(git_ref) = action.git_ref.as_deref() else {
+                        continue;
+                    };
+
+                    let sha = if action.pinned_to_sha {
+                        Some(git_ref.to_string())
+                    } else {
+                        resolver.resolve_ref(&action.owner, &action.repo, git_ref)
+                    };
+
+                    match sha {
+                        Some(sha) => {
+                            resolution
+                                .lockfile
+                                .actions
+                                .entry(name.clone())
+                                .or_default()
+                                .insert(git_ref.into(), sha);
+                        }
+                        None => {
+                            resolution.unresolved.insert((name.clone(), git_ref.into()));
+                        }
+                    }
+                    resolution
+                        .usages
+                        .entry((name, git_ref.into()))
+                        .or_default()
+                        .insert(action.qualified_path());
+                }
+            }
+        }
+
+        resolution
+    }
+
+    /// Loads a lockfile from `path`.
+    pub(crate) fn load(path: &Utf8Path) -> Result<Self> {
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("couldn't read lockfile {path}"))?;
+        let lockfile: Self =
+            serde_yaml::from_str(&contents).with_context(|| format!("invalid lockfile {path}"))?;
+
+        if lockfile.version != LOCKFILE_VERSION {
+            return Err(anyhow!(
+                "{path} has unsupported lockfile version {version} (expected {LOCKFILE_VERSION})",
+                version = lockfile.version,
+            ));
+        }
+
+        Ok(lockfile)
+    }
+
+    /// The commit that `git_ref` of the action `name` was locked to, if any.
+    pub(crate) fn locked_sha(&self, name: &str, git_ref: &str) -> Option<&str> {
+        self.actions
+            .get(name)
+            .and_then(|refs| refs.get(git_ref))
+            .map(String::as_str)
+    }
+
+    /// Whether this lockfile has an entry for `git_ref` of `name`, which
+    /// is either an action or an image.
+    fn contains(&self, name: &str, git_ref: &str) -> bool {
+        match self.images.get(name) {
+            Some(tags) => tags.contains_key(git_ref),
+            None => self
+                .actions
+                .get(name)
+                .is_some_and(|refs| refs.contains_key(git_ref)),
+        }
+    }
+
+    /// Every `(name, ref)` entry in this lockfile, for both actions and images.
+    fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        let actions = self.actions.iter().flat_map(|(name, refs)| {
+            refs.keys()
+                .map(move |git_ref| (name.as_str(), git_ref.as_str()))
+        });
+        let images = self.images.iter().flat_map(|(name, tags)| {
+            tags.keys()
+                .map(move |git_ref| (name.as_str(), git_ref.as_str()))
+        });
+
+        actions.chain(images)
+    }
+
+    /// Renders this lockfile, including its header comment.
+    pub(crate) fn render(&self) -> Result<String> {
+        Ok(format!("{HEADER}{}", serde_yaml::to_string(self)?))
+    }
+}
+
+/// A freshly generated [`Lockfile`], along with where its entries are used.
+pub(crate) struct Resolution {
+    pub(crate) lockfile: Lockfile,
+    /// The files that use each `(name, ref)`, for both actions and
+    /// images, including refs that couldn't be resolved.
+    pub(crate) usages: BTreeMap<(String, String), BTreeSet<String>>,
+    /// The `(name, ref)` of each action ref that couldn't be resolved.
+    pub(crate) unresolved: BTreeSet<(String, String)>,
+}
+
+impl Resolution {
+    /// Compares `locked` against the refs that the inputs currently use.
+    ///
+    /// Entries are sorted by name and then by ref.
+    pub(crate) fn verify(&self, locked: &Lockfile) -> Vec<Discrepancy> {
+        let mut discrepancies = vec![];
+
+        for ((name, git_ref), files) in &self.usages {
+            let kind = if !locked.contains(name, git_ref) {
+                DiscrepancyKind::Unlocked
+            } else if self.unresolved.contains(&(name.clone(), git_ref.clone())) {
+                DiscrepancyKind::Unresolved
+            } else {
+                // NOTE: Images are identified by their digests (if any),
+                // so only action refs can move.
+                let old = locked.actions.get(name).and_then(|refs| refs.get(git_ref));
+                let new = self
+                    .lockfile
+                    .actions
+                    .get(name)
+                    .and_then(|refs| refs.get(git_ref));
+                match (old, new) {
+                    (Some(old), Some(new)) if old != new => DiscrepancyKind::Moved {
+                        old: old.clone(),
+                        new: new.clone(),
+                    },
+                    _ => continue,
+                }
+            };
+
+            discrepancies.push(Discrepancy {
+                name: name.clone(),
+                git_ref: git_ref.clone(),
+                kind,
+                files: files.iter().cloned().collect(),
+            });
+        }
+
+        for (name, git_ref) in locked.entries() {
+            if !self.usages.contains_key(&(name.into(), git_ref.into())) {
+                discrepancies.push(Discrepancy {
+                    name: name.into(),
+                    git_ref: git_ref.into(),
+                    kind: DiscrepancyKind::Unused,
+                    files: vec![],
+                });
+            }
+        }
+
+        discrepancies.sort_by(|a, b| (&a.name, &a.git_ref).cmp(&(&b.name, &b.git_ref)));
+        discrepancies
+    }
+
+    /// The updated version of `locked`, i.e. this resolution's lockfile,
+    /// except that refs that couldn't be resolved keep their locked commits.
+    pub(crate) fn update(mut self, locked: &Lockfile) -> Lockfile {
+        for (name, git_ref) in &self.unresolved {
+            if let Some(sha) = locked.actions.get(name).and_then(|refs| refs.get(git_ref)) {
+                self.lockfile
+                    .actions
+                    .entry(name.clone())
+                    .or_default()
+                    .insert(git_ref.clone(), sha.clone());
+            }
+        }
+
+        self.lockfile
+    }
+}
+
+/// A difference between a lockfile and the refs that the inputs use.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Discrepancy {
+    /// The action (`owner/repo[/subpath]`) or image (`docker://...`).
+    pub(crate) name: String,
+    /// The ref (or, for images, the tag or digest).
+    pub(crate) git_ref: String,
+    pub(crate) kind: DiscrepancyKind,
+    /// The files that use the ref, sorted; empty for unused refs.
+    pub(crate) files: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum DiscrepancyKind {
+    /// The ref now resolves to a different commit than the locked one,
+    /// e.g. because a tag was moved or a branch was pushed to.
+    Moved { old: String, new: String },
+    /// The ref is used, but isn't in the lockfile.
+    Unlocked,
+    /// The ref is in the lockfile, but is no longer used.
+    Unused,
+    /// The ref is in the lockfile, but couldn't be resolved to check it.
+    Unresolved,
+}
+
+impl Display for Discrepancy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            name,
+            git_ref,
+            kind,
+            files,
+        } = self;
+
+        match kind {
+            DiscrepancyKind::Moved { old, new } => {
+                write!(f, "{name}@{git_ref}: moved from {old} to {new}")?
+            }
+            DiscrepancyKind::Unlocked => write!(f, "{name}@{git_ref}: not in the lockfile")?,
+            DiscrepancyKind::Unused => write!(f, "{name}@{git_ref}: no longer used")?,
+            DiscrepancyKind::Unresolved => write!(f, "{name}@{git_ref}: couldn't be resolved")?,
+        }
+
+        if !files.is_empty() {
+            write!(f, "\n  used by {files}", files = files.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::{Discrepancy, DiscrepancyKind, Lockfile, Resolution};
+
+    fn example() -> Lockfile {
+        let mut lockfile = Lockfile::new(datetime!(2025-05-01 12:30:00 UTC));
+        lockfile
+            .actions
+            .entry("pypa/gh-action-pypi-publish".into())
+            .or_default()
+            .insert(
+                "release/v1".into(),
+                "76f52bc884231f62b9a034ebfe128415bbaabdfc".into(),
+            );
+        let checkout = lockfile
+            .actions
+            .entry("actions/checkout".into())
+            .or_default();
+        checkout.insert(
+            "v4".into(),
+            "11bd71901bbe5b1630ceea73d27597364c9af683".into(),
+        );
+        checkout.insert(
+            "v3".into(),
+            "f43a0e5ff2bd294095638e18286ca9a3d1956744".into(),
+        );
+        let alpine = lockfile
+            .images
+            .entry("docker://library/alpine".into())
+            .or_default();
+        alpine.insert("3.20".into(), None);
+        alpine.insert(
+            "sha256:beefdbd8a1da6d2915566fde36db9db0b524eb737fc57cd1367effd16dc0d06d".into(),
+            Some("sha256:beefdbd8a1da6d2915566fde36db9db0b524eb737fc57cd1367effd16dc0d06d".into()),
+        );
+
+        lockfile
+    }
+
+    #[test]
+    fn test_render() {
+        // Entries are sorted, regardless of insertion order.
+        assert_eq!(
+            example().render().unwrap(),
+            "# Generated by `zizmor lock`. Do not edit by hand.
+version: 1
+generated: 2025-05-01T12:30:00Z
+actions:
+  actions/checkout:
+    v3: f43a0e5ff2bd294095638e18286ca9a3d1956744
+    v4: 11bd71901bbe5b1630ceea73d27597364c9af683
+  pypa/gh-action-pypi-publish:
+    release/v1: 76f52bc884231f62b9a034ebfe128415bbaabdfc
+images:
+  docker://library/alpine:
+    '3.20': null
+    sha256:beefdbd8a1da6d2915566fde36db9db0b524eb737fc57cd1367effd16dc0d06d: sha256:beefdbd8a1da6d2915566fde36db9db0b524eb737fc57cd1367effd16dc0d06d
+"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let lockfile = example();
+        let rendered = lockfile.render().unwrap();
+        let parsed: Lockfile = serde_yaml::from_str(&rendered).unwrap();
+        assert_eq!(parsed, lockfile);
+        assert_eq!(parsed.render().unwrap(), rendered);
+
+        // Missing maps are empty, but unknown fields are rejected.
+        let minimal: Lockfile =
+            serde_yaml::from_str("version: 1\ngenerated: 2025-05-01T12:30:00Z\n").unwrap();
+        assert!(minimal.actions.is_empty() && minimal.images.is_empty());
+        assert!(
+            serde_yaml::from_str::<Lockfile>(
+                "version: 1\ngenerated: 2025-05-01T12:30:00Z\nextra: true\n"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let locked = example();
+
+        // Freshly resolved: `actions/checkout@v4` moved, `v3` is no longer
+        // used, `foo/bar@v1` is new, and `pypa/gh-action-pypi-publish`
+        // couldn't be resolved.
+        let mut current = Lockfile::new(datetime!(2025-06-01 00:00:00 UTC));
+        current
+            .actions
+            .entry("actions/checkout".into())
+            .or_default()
+            .insert(
+                "v4".into(),
+                "08c6903cd8c0fde910a37f88322edcfb5dd907a8".into(),
+            );
+        current.actions.entry("foo/bar".into()).or_default().insert(
+            "v1".into(),
+            "2d756ea4c53f7f6b397767d8723b3a10a9f35bf2".into(),
+        );
+        let alpine = current
+            .images
+            .entry("docker://library/alpine".into())
+            .or_default();
+        alpine.insert("3.20".into(), None);
+        alpine.insert(
+            "sha256:beefdbd8a1da6d2915566fde36db9db0b524eb737fc57cd1367effd16dc0d06d".into(),
+            Some("sha256:beefdbd8a1da6d2915566fde36db9db0b524eb737fc57cd1367effd16dc0d06d".into()),
+        );
+
+        let usages = [
+            ("actions/checkout", "v4", "ci.yml"),
+            ("foo/bar", "v1", "release.yml"),
+            ("pypa/gh-action-pypi-publish", "release/v1", "release.yml"),
+            ("docker://library/alpine", "3.20", "ci.yml"),
+            (
+                "docker://library/alpine",
+                "sha256:beefdbd8a1da6d2915566fde36db9db0b524eb737fc57cd1367effd16dc0d06d",
+                "ci.yml",
+            ),
+        ];
+        let resolution = Resolution {
+            lockfile: current,
+            usages: usages
+                .iter()
+                .map(|(name, git_ref, file)| {
+                    (
+                        (name.to_string(), git_ref.to_string()),
+                        [file.to_string()].into(),
+                    )
+                })
+                .collect(),
+            unresolved: [("pypa/gh-action-pypi-publish".into(), "release/v1".into())].into(),
+        };
+
+        let discrepancy = |name: &str, git_ref: &str, kind, files: &[&str]| Discrepancy {
+            name: name.into(),
+            git_ref: git_ref.into(),
+            kind,
+            files: files.iter().map(|f| f.to_string()).collect(),
+        };
+        let discrepancies = resolution.verify(&locked);
+        assert_eq!(
+            discrepancies,
+            [
+                discrepancy("actions/checkout", "v3", DiscrepancyKind::Unused, &[]),
+                discrepancy(
+                    "actions/checkout",
+                    "v4",
+                    DiscrepancyKind::Moved {
+                        old: "11bd71901bbe5b1630ceea73d27597364c9af683".into(),
+                        new: "08c6903cd8c0fde910a37f88322edcfb5dd907a8".into(),
+                    },
+                    &["ci.yml"]
+                ),
+                discrepancy("foo/bar", "v1", DiscrepancyKind::Unlocked, &["release.yml"]),
+                discrepancy(
+                    "pypa/gh-action-pypi-publish",
+                    "release/v1",
+                    DiscrepancyKind::Unresolved,
+                    &["release.yml"]
+                ),
+            ]
+        );
+        assert_eq!(
+            discrepancies[1].to_string(),
+            "actions/checkout@v4: moved from 11bd71901bbe5b1630ceea73d27597364c9af683 \
+             to 08c6903cd8c0fde910a37f88322edcfb5dd907a8\n  used by ci.yml"
+        );
+
+        // Updating keeps the locked commit for refs that couldn't be resolved.
+        let updated = resolution.update(&locked);
+        assert_eq!(
+            updated.actions["pypa/gh-action-pypi-publish"]["release/v1"],
+            "76f52bc884231f62b9a034ebfe128415bbaabdfc"
+        );
+        assert!(!updated.actions["actions/checkout"].contains_key("v3"));
+        assert_eq!(
+            updated.actions["actions/checkout"]["v4"],
+            "08c6903cd8c0fde910a37f88322edcfb5dd907a8"
+        );
+    }
+}
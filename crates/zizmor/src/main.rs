@@ -1,42 +1,83 @@
 use std::{
-    io::{Write, stdout},
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    io::Write,
+    num::NonZeroUsize,
     process::ExitCode,
     str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
 use annotate_snippets::{Level, Renderer};
 use anstream::{eprintln, stream::IsTerminal};
 use anyhow::{Context, Result, anyhow};
-use audit::{Audit, AuditLoadError};
+use audit::{
+    Audit as _, AuditCore as _, AuditInput, AuditLoadError, AuditMeta,
+    custom_rules::CustomRules,
+    unpinned_uses::{AllowedOrgs, BadgeThresholds},
+};
+use auth::{GitHubApp, InstallationScope};
+use baseline::Baseline;
 use camino::{Utf8Path, Utf8PathBuf};
-use clap::{CommandFactory, Parser, ValueEnum};
+use changed::ChangedFiles;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum, builder::BoolishValueParser};
 use clap_verbosity_flag::InfoLevel;
 use config::Config;
-use finding::{Confidence, Persona, Severity};
-use github_actions_models::common::Uses;
-use github_api::GitHubHost;
+use diff::Diff;
+use finding::{Confidence, Finding, Persona, Severity, cached::CachedFinding};
+use fix::FixPlan;
+use github_actions_models::common::{RepositoryUses, Uses};
+use github_api::{Client, GitHubHost};
+use globset::{Glob, GlobMatcher, GlobSet, GlobSetBuilder};
 use ignore::WalkBuilder;
 use indicatif::ProgressStyle;
+use output::{Sink, summary::RunSummary};
 use owo_colors::OwoColorize;
-use registry::{AuditRegistry, FindingRegistry, InputKey, InputKind, InputRegistry};
+use registry::{AuditRegistry, Collection, FindingRegistry, InputKey, InputKind, InputRegistry};
+use results_cache::ResultsCache;
+use score::Score;
+use serde::{Deserialize, Serialize};
 use state::AuditState;
 use tracing::{Span, info_span, instrument};
 use tracing_indicatif::{IndicatifLayer, span_ext::IndicatifSpanExt};
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt as _, util::SubscriberInitExt as _};
+use watch::Watched;
 
 mod audit;
+mod auth;
+mod baseline;
+mod changed;
 mod config;
+mod diff;
 mod finding;
+mod fix;
 mod github_api;
+mod lock;
 mod models;
+mod osv;
 mod output;
 mod registry;
+mod resolver;
+mod results_cache;
+mod score;
 mod state;
+mod transitive;
 mod utils;
+mod watch;
 
 /// Finds security issues in GitHub Actions setups.
 #[derive(Parser)]
-#[command(about, version)]
+#[command(
+    about,
+    version,
+    subcommand_negates_reqs = true,
+    after_long_help = EXIT_CODES_HELP
+)]
 struct App {
     /// Emit 'pedantic' findings.
     ///
@@ -45,30 +86,68 @@ struct App {
     pedantic: bool,
 
     /// The persona to use while auditing.
-    #[arg(long, group = "_persona", value_enum, default_value_t)]
-    persona: Persona,
+    ///
+    /// Overrides the configuration's `persona` and per-rule personas.
+    /// Defaults to 'regular' when not configured.
+    #[arg(long, group = "_persona", value_enum)]
+    persona: Option<Persona>,
+
+    /// Include findings hidden by the current persona in JSON output.
+    #[arg(long)]
+    show_all: bool,
 
     /// Perform only offline operations.
     ///
     /// This disables all online audit rules, and prevents zizmor from
     /// auditing remote repositories.
-    #[arg(short, long, env = "ZIZMOR_OFFLINE")]
+    ///
+    /// `ZIZMOR_OFFLINE` accepts any boolish value, e.g. `1` or `true`.
+    #[arg(short, long, global = true, env = "ZIZMOR_OFFLINE", value_parser = BoolishValueParser::new())]
     offline: bool,
 
     /// The GitHub API token to use.
-    #[arg(long, env)]
+    ///
+    /// Defaults to the first of `ZIZMOR_GH_TOKEN`, `GH_TOKEN`, and
+    /// `GITHUB_TOKEN` that's set.
+    #[arg(long, global = true)]
     gh_token: Option<String>,
 
-    /// The GitHub Server Hostname. Defaults to github.com
-    #[arg(long, env = "GH_HOST", default_value = "github.com", value_parser = GitHubHost::from_clap)]
-    gh_hostname: GitHubHost,
+    /// Authenticate as an installation of this GitHub App, instead of
+    /// with a token.
+    ///
+    /// The installation is the app's installation on `--org`, or on the
+    /// first remote input, or else the app's only installation.
+    #[arg(
+        long,
+        global = true,
+        requires = "gh_app_key_path",
+        conflicts_with = "gh_token"
+    )]
+    gh_app_id: Option<u64>,
+
+    /// The private key (a PEM file) for `--gh-app-id`.
+    #[arg(long, global = true, requires = "gh_app_id")]
+    gh_app_key_path: Option<Utf8PathBuf>,
+
+    /// The GitHub Server Hostname, for every online audit and for
+    /// classifying `uses:` clauses.
+    ///
+    /// Defaults to the configuration's `gh-hostname`, or github.com.
+    #[arg(
+        long,
+        visible_alias = "gh-host",
+        global = true,
+        env = "GH_HOST",
+        value_parser = GitHubHost::from_clap
+    )]
+    gh_hostname: Option<GitHubHost>,
 
     /// Perform only offline audits.
     ///
     /// This is a weaker version of `--offline`: instead of completely
     /// forbidding all online operations, it only disables audits that
     /// require connectivity.
-    #[arg(long, env = "ZIZMOR_NO_ONLINE_AUDITS")]
+    #[arg(long, env = "ZIZMOR_NO_ONLINE_AUDITS", value_parser = BoolishValueParser::new())]
     no_online_audits: bool,
 
     #[command(flatten)]
@@ -82,48 +161,281 @@ struct App {
     #[arg(long, value_enum, default_value_t)]
     format: OutputFormat,
 
+    /// Write the selected output format to this file instead of stdout,
+    /// or `-` for stdout.
+    ///
+    /// When writing to a file, a brief summary is written to stderr.
+    #[arg(long, value_name = "PATH")]
+    output: Option<Utf8PathBuf>,
+
     /// Control the use of color in output.
     #[arg(long, value_enum, value_name = "MODE")]
     color: Option<ColorMode>,
 
     /// The configuration file to load. By default, any config will be
     /// discovered relative to $CWD.
-    #[arg(short, long, env = "ZIZMOR_CONFIG", group = "conf")]
+    #[arg(short, long, global = true, env = "ZIZMOR_CONFIG", group = "conf")]
     config: Option<Utf8PathBuf>,
 
     /// Disable all configuration loading.
-    #[arg(long, group = "conf")]
+    #[arg(long, global = true, group = "conf")]
     no_config: bool,
 
+    /// Validate the configuration and exit, without auditing anything.
+    ///
+    /// This checks every rule's configuration, including pattern syntax
+    /// and whether allowlist files exist.
+    #[arg(long, conflicts_with_all = ["inputs", "org", "stdin"])]
+    check_config: bool,
+
+    /// List every audit rule and exit, without auditing anything.
+    ///
+    /// Use with `--format=json` for machine-readable output.
+    #[arg(long, conflicts_with_all = ["inputs", "org", "stdin", "check_config"])]
+    list_rules: bool,
+
+    /// Write an allowlist of every third-party org used by the inputs
+    /// to the given path (or `-` for stdout) and exit, without auditing
+    /// anything.
+    ///
+    /// The allowlist is in the `--tpa-allowlist-file` format.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["check_config", "list_rules"])]
+    emit_allowlist: Option<Utf8PathBuf>,
+
+    /// Fix findings in place, where possible.
+    ///
+    /// Currently, this pins `unpinned-uses` findings' symbolic refs to the
+    /// commits they point to, which requires GitHub API access.
+    #[arg(long, conflicts_with_all = ["stdin", "update_baseline"])]
+    fix: bool,
+
+    /// Re-run the audits whenever an input, or the configuration or an
+    /// allowlist file, changes, until interrupted with Ctrl-C.
+    ///
+    /// Each run clears the screen and re-renders the plain output, while
+    /// replaying unchanged inputs' findings from the results cache (as with
+    /// `--cache-results`). On exit, the exit code is the most recent run's.
+    #[arg(
+        long,
+        conflicts_with_all = ["stdin", "org", "fix", "update_baseline", "check_config", "list_rules", "emit_allowlist"]
+    )]
+    watch: bool,
+
+    /// With `--fix`, print a unified diff of the fixes instead of
+    /// applying them.
+    ///
+    /// Exits with a dedicated error code when any fixes are pending.
+    #[arg(long, requires = "fix")]
+    dry_run: bool,
+
     /// Disable all error codes besides success and tool failure.
     #[arg(long)]
     no_exit_codes: bool,
 
+    /// Fail if any audit can't be loaded, rather than auditing without it.
+    #[arg(long)]
+    strict: bool,
+
+    /// Summarize what happened to each audit after auditing: whether it
+    /// ran (and how many findings it produced, and how long it took), was
+    /// skipped, or failed to load.
+    ///
+    /// The summary is part of the output for the plain and jsonl formats,
+    /// and is written to stderr for the others.
+    #[arg(long)]
+    audit_summary: bool,
+
+    /// Summarize the run after auditing: each audit's findings by severity,
+    /// the files they're in, and the time spent in it, along with totals,
+    /// the slowest inputs, and the audits that didn't run.
+    ///
+    /// The summary is part of the output for the plain, json, and jsonl
+    /// formats, and is written to stderr for the others. With `--format=json`,
+    /// the output becomes an object with `findings` and `summary` keys.
+    #[arg(long)]
+    summary: bool,
+
+    /// Summarize the run's third-party exposure as a single "actions
+    /// supply-chain score", from 0 (worst) to 100 (best), weighted by the
+    /// configuration's `score-weights`.
+    ///
+    /// The score follows the plain and tpa-list outputs, and is written to
+    /// stderr for the others. With `--format=json`, the output becomes an
+    /// object with `findings` and `score` keys, and the tpa-list report
+    /// gains a `score` key.
+    #[arg(long)]
+    score: bool,
+
+    /// Fail with a dedicated exit code when the supply-chain score is
+    /// below this threshold. Implies `--score`.
+    #[arg(long, value_name = "SCORE", value_parser = clap::value_parser!(u64).range(0..=100))]
+    min_score: Option<u64>,
+
+    /// Only fail on findings at or above this severity, or never.
+    ///
+    /// Overrides the `fail-on` configuration key. By default, any
+    /// finding fails the run.
+    #[arg(long, value_name = "SEVERITY")]
+    fail_on: Option<FailOn>,
+
+    /// Roll up identical findings into a single finding with several
+    /// occurrences, e.g. every `unpinned-uses` finding for the same action.
+    ///
+    /// Overrides the `rollup` configuration key.
+    #[arg(long, value_enum, value_name = "MODE")]
+    rollup: Option<Rollup>,
+
+    /// Whether rolled-up findings are counted once, or once per occurrence,
+    /// in the summaries and by `--fail-on`.
+    ///
+    /// Overrides the `rollup-count` configuration key.
+    #[arg(long, value_enum, value_name = "COUNT")]
+    rollup_count: Option<RollupCount>,
+
+    /// Don't fail on findings recorded in this baseline file; see
+    /// `--update-baseline`.
+    #[arg(long, value_name = "PATH")]
+    baseline: Option<Utf8PathBuf>,
+
+    /// Regenerate the `--baseline` file from this run's findings.
+    #[arg(long, requires = "baseline")]
+    update_baseline: bool,
+
+    /// Only report findings that aren't in this previous `--format=json`
+    /// output, and list any of its findings that have been resolved.
+    #[arg(long, value_name = "PATH", conflicts_with = "baseline")]
+    diff: Option<Utf8PathBuf>,
+
+    /// Only audit local workflows and actions that have changed since
+    /// this git ref, e.g. `origin/main`.
+    ///
+    /// Changes are determined by `git diff` in the current directory's
+    /// repository. If they can't be determined (e.g. in a shallow clone
+    /// without the ref), every input is audited instead.
+    #[arg(long, value_name = "REF", conflicts_with = "stdin")]
+    changed_since: Option<String>,
+
+    /// How to group findings in the cargo-style ("plain") output.
+    #[arg(long, value_enum, default_value_t)]
+    group_by: GroupBy,
+
+    /// Don't link to each rule's documentation in the cargo-style
+    /// ("plain") output.
+    #[arg(long)]
+    no_help_links: bool,
+
     /// Filter all results below this severity.
+    ///
+    /// Overrides the configuration's `min-severity`. Unlike `--fail-on`,
+    /// this hides findings from the output, rather than only from the
+    /// exit code.
     #[arg(long)]
     min_severity: Option<Severity>,
 
     /// Filter all results below this confidence.
+    ///
+    /// Overrides the configuration's `min-confidence`.
     #[arg(long)]
     min_confidence: Option<Confidence>,
 
+    /// Include findings filtered by `--min-severity` or `--min-confidence`
+    /// in JSON output.
+    #[arg(long)]
+    show_filtered: bool,
+
+    /// Don't ignore any findings, whether by inline `# zizmor: ignore`
+    /// comments or by the configuration.
+    #[arg(long)]
+    no_ignores: bool,
+
+    /// Run these rules, even if they're disabled by the configuration.
+    #[arg(long, value_name = "RULE", value_delimiter = ',')]
+    enable: Vec<String>,
+
+    /// Don't run these rules, even if they're enabled by the configuration.
+    #[arg(long, value_name = "RULE", value_delimiter = ',')]
+    disable: Vec<String>,
+
     /// The directory to use for HTTP caching. By default, a
     /// host-appropriate user-caching directory will be used.
-    #[arg(long)]
+    #[arg(long, global = true)]
     cache_dir: Option<Utf8PathBuf>,
 
+    /// Cache each input's findings, and replay them on later runs while
+    /// the input, its configuration, and the enabled audits are unchanged.
+    ///
+    /// Only offline audits' findings are cached; other audits always run.
+    #[arg(long, env = "ZIZMOR_CACHE_RESULTS", value_parser = BoolishValueParser::new())]
+    cache_results: bool,
+
+    /// Don't read or write cached results, even with `--cache-results`.
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Load additional action advisories from the given JSON file.
+    ///
+    /// These are used by the `known-vulnerable-actions` audit, alongside
+    /// zizmor's bundled advisories.
+    #[arg(long, value_name = "PATH")]
+    advisories: Option<Utf8PathBuf>,
+
+    /// Also query OSV for advisories affecting each action.
+    ///
+    /// These are used by the `known-vulnerable-actions` audit, unless
+    /// online audits are disabled.
+    #[arg(long)]
+    osv: bool,
+
+    /// Compare the tags that actions are pinned to against the commits
+    /// locked in this lockfile (see `zizmor lock`).
+    ///
+    /// These are used by the `tag-moved` audit and the TPA report, unless
+    /// online audits are disabled.
+    #[arg(long, value_name = "PATH")]
+    lockfile: Option<Utf8PathBuf>,
+
     /// Control which kinds of inputs are collected for auditing.
     ///
-    /// By default, all workflows and composite actions are collected,
-    /// while honoring `.gitignore` files.
-    #[arg(long, value_enum, default_value_t)]
+    /// By default ('default'), all workflows and composite actions are
+    /// collected, while honoring `.gitignore` files. 'all' also collects
+    /// inputs from non-standard locations (like `.github/actions/`)
+    /// and ignores `.gitignore` files, 'workflows-only' and 'actions-only'
+    /// limit collection to one kind of input, and any other value is a
+    /// glob that selects exactly which paths to collect.
+    #[arg(
+        long,
+        global = true,
+        value_name = "MODE",
+        default_value = "default",
+        value_parser = CollectionMode::from_str
+    )]
     collect: CollectionMode,
 
+    /// Don't collect inputs at paths matching this glob, relative to
+    /// the root of their local or remote repository (e.g. 'tests/**').
+    ///
+    /// A path is also excluded if any of its parent directories match.
+    /// Added to the configuration's `exclude` patterns.
+    #[arg(long, global = true, value_name = "PATTERN", value_parser = Glob::new)]
+    exclude: Vec<Glob>,
+
+    /// Collect inputs from local directories regardless of `.gitignore`
+    /// files, like `--collect=all` does.
+    #[arg(long, global = true)]
+    no_ignore: bool,
+
     /// Fail instead of warning on syntax and schema errors
     /// in collected inputs.
     #[arg(long)]
     strict_collection: bool,
 
+    /// The number of inputs to audit in parallel.
+    ///
+    /// Defaults to the number of available CPUs. Output is identical
+    /// regardless of the number of jobs.
+    #[arg(short, long, value_name = "N")]
+    jobs: Option<NonZeroUsize>,
+
     /// Generate tab completion scripts for the specified shell.
     #[arg(long, value_enum, value_name = "SHELL", exclusive = true)]
     completions: Option<clap_complete::Shell>,
@@ -132,20 +444,280 @@ struct App {
     #[arg(long, hide = true, env = "ZIZMOR_NACHES")]
     naches: bool,
 
+    /// Audit every non-archived repository in the given GitHub organization.
+    ///
+    /// This can be combined with other inputs, and requires a GitHub
+    /// API token.
+    #[arg(long, value_name = "ORG")]
+    org: Option<String>,
+
+    /// Only audit `--org` repositories whose names match this glob,
+    /// e.g. `api-*`.
+    #[arg(long, value_name = "GLOB", requires = "org", value_parser = repo_glob)]
+    repo_filter: Option<GlobMatcher>,
+
+    /// Only audit `--org` repositories with this topic.
+    #[arg(long, value_name = "TOPIC", requires = "org")]
+    repo_topic: Option<String>,
+
+    /// The number of `--org` repositories to fetch in parallel.
+    ///
+    /// Lower this to stay under the GitHub API's rate limits.
+    #[arg(long, value_name = "N", requires = "org", default_value = "4")]
+    concurrency: NonZeroUsize,
+
+    /// Also audit the remote composite actions used by each input, and
+    /// the composite actions that they use in turn.
+    ///
+    /// Findings in these actions are attributed to synthetic paths like
+    /// `remote:owner/repo@ref/action.yml`. This requires a GitHub API token.
+    #[arg(long, conflicts_with = "offline")]
+    audit_transitive: bool,
+
+    /// How many levels of composite actions `--audit-transitive` follows.
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "audit_transitive",
+        default_value = "3"
+    )]
+    audit_transitive_depth: NonZeroUsize,
+
     /// The inputs to audit.
     ///
     /// These can be individual workflow filenames, action definitions
     /// (typically `action.yml`), entire directories, or a `user/repo` slug
     /// for a GitHub repository. In the latter case, a `@ref` can be appended
     /// to audit the repository at a particular git reference state.
-    #[arg(required = true)]
+    #[arg(required_unless_present_any = ["org", "stdin", "check_config", "list_rules"])]
     inputs: Vec<String>,
 
+    /// Audit a single workflow or action definition read from stdin,
+    /// instead of any inputs.
+    #[arg(long, requires = "stdin_filename", conflicts_with_all = ["inputs", "org"])]
+    stdin: bool,
+
+    /// The filename of the `--stdin` input, e.g. `.github/workflows/ci.yml`.
+    ///
+    /// This determines whether the input is a workflow or an action
+    /// definition, and is used as the input's path in the output.
+    #[arg(long, value_name = "PATH", requires = "stdin")]
+    stdin_filename: Option<Utf8PathBuf>,
+
     #[arg(long)]
     tpa_allowlist_file: Option<String>,
 
     #[arg(long, value_delimiter = ',')]
     tpa_allowed_org: Option<Vec<String>>,
+
+    /// Skip invalid allowlist entries with a warning, rather than failing.
+    ///
+    /// This applies to `--tpa-allowlist-file`, `--tpa-allowed-org`, and
+    /// the `unpinned-uses` allowlist configuration.
+    #[arg(long)]
+    lenient_allowlist: bool,
+
+    /// Group the tpa-list text output, rather than listing each
+    /// occurrence on its own line.
+    #[arg(long, value_enum, value_name = "GROUPING")]
+    tpa_group_by: Option<TpaGroupBy>,
+
+    /// Select which actions are listed in the tpa-list text output.
+    ///
+    /// This doesn't affect the contents of the JSON report.
+    #[arg(long, value_enum, value_name = "SELECTION", default_value_t)]
+    tpa_show: TpaShow,
+
+    /// List actions used with more than one distinct ref at the end of
+    /// the tpa-list text output.
+    #[arg(long)]
+    tpa_show_inconsistent: bool,
+
+    /// Summarize each third-party owner's actions at the end of the
+    /// tpa-list text output, with the owners with the most unpinned
+    /// actions first.
+    #[arg(long)]
+    tpa_owner_summary: bool,
+
+    /// Exit with a dedicated error code when the TPA formats
+    /// find actions matching this threshold.
+    #[arg(long, value_enum, value_name = "THRESHOLD", default_value_t)]
+    tpa_fail_on: TpaFailOn,
+
+    /// Where the tpa-list format writes its JSON report, or `-` to write
+    /// the report to stdout and the text list to stderr instead.
+    ///
+    /// Defaults to `all_actions.json`, which is only written when at least
+    /// one action is found.
+    #[arg(long, value_name = "PATH")]
+    tpa_report: Option<String>,
+
+    /// How the TPA formats treat third-party actions whose `unpinned-uses` finding
+    /// is ignored, e.g. with an inline `# zizmor: ignore` comment.
+    #[arg(long, value_enum, value_name = "MODE", default_value_t)]
+    tpa_suppressed: TpaSuppressed,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Write a lockfile of the commit that each action's refs resolve to.
+    ///
+    /// Every action, reusable workflow, and Docker image used by the
+    /// inputs is recorded. Resolving refs requires a GitHub API token.
+    Lock {
+        /// The lockfile to write.
+        #[arg(long, value_name = "PATH", default_value = lock::DEFAULT_LOCKFILE)]
+        lockfile: Utf8PathBuf,
+
+        /// The inputs to lock, as with auditing. Defaults to the
+        /// current directory.
+        inputs: Vec<String>,
+    },
+    /// Check that a lockfile matches the refs that the inputs use.
+    ///
+    /// Every ref is resolved again, and each one that moved since it was
+    /// locked, isn't locked, or is locked but no longer used is reported.
+    VerifyLock {
+        /// The lockfile to check.
+        #[arg(long, value_name = "PATH", default_value = lock::DEFAULT_LOCKFILE)]
+        lockfile: Utf8PathBuf,
+
+        /// Rewrite the lockfile to match the inputs, instead of failing.
+        #[arg(long)]
+        update: bool,
+
+        /// The inputs to check, as with auditing. Defaults to the
+        /// current directory.
+        inputs: Vec<String>,
+    },
+    /// Pin every symbolic ref in the inputs to the commit that a
+    /// lockfile locks it to.
+    ///
+    /// Unlike `--fix`, refs aren't resolved, so this works offline. Refs
+    /// that aren't locked are reported and left as-is, as are refs already
+    /// pinned to a different commit than their commented ref is locked to.
+    Pin {
+        /// The lockfile to pin to.
+        #[arg(long, value_name = "PATH", default_value = lock::DEFAULT_LOCKFILE)]
+        lockfile: Utf8PathBuf,
+
+        /// Print a unified diff of the pins instead of applying them.
+        ///
+        /// Exits with a dedicated error code when any pins are pending.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// The inputs to pin, as with auditing. Defaults to the
+        /// current directory.
+        inputs: Vec<String>,
+    },
+    /// Manage zizmor's caches.
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// Remove every cached audit result (see `--cache-results`).
+    Clear,
+}
+
+/// The exit code used when the configuration (including any audit's
+/// part of it) can't be loaded.
+const CONFIG_ERROR_EXIT_CODE: u8 = 3;
+
+/// The exit code used when the inputs can't be collected, e.g. because
+/// an input doesn't exist or a remote repository can't be fetched.
+const COLLECTION_ERROR_EXIT_CODE: u8 = 4;
+
+/// The exit code used when `--fix --dry-run` (or `zizmor pin --dry-run`)
+/// finds fixes to apply.
+const FIX_PENDING_EXIT_CODE: u8 = 21;
+
+/// The exit code used when `zizmor verify-lock` finds that the lockfile
+/// is out of date, or `zizmor pin` finds refs that disagree with it.
+///
+/// Refs that can't be resolved are an error (1) instead, since they
+/// don't mean that anything changed.
+const LOCK_DRIFT_EXIT_CODE: u8 = 22;
+
+/// The exit code used when a TPA format meets the `--tpa-fail-on` threshold.
+///
+/// This is distinct from both tool failure (1) and the severity-based
+/// exit codes used by the findings formats (10 through 14).
+const TPA_FAILURE_EXIT_CODE: u8 = 20;
+
+/// The exit code used when the supply-chain score is below `--min-score`.
+const SCORE_FAILURE_EXIT_CODE: u8 = 23;
+
+/// The exit codes, as listed by `--help`.
+///
+/// Keep this in sync with the exit code table in `docs/usage.md`.
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0      Success; no findings meet the failure threshold
+  1      An unexpected error stopped the run
+  2      Invalid command-line usage
+  3      The configuration, a baseline or diff, or an audit couldn't be
+         loaded, or the configured options conflict
+  4      The inputs couldn't be collected
+  10-14  Findings meet the --fail-on threshold; the highest finding is
+         unknown (10), informational (11), low (12), medium (13) or high (14)
+  20     A TPA format meets the --tpa-fail-on threshold
+  21     --fix --dry-run or pin --dry-run: one or more fixes would be applied
+  22     verify-lock or pin: the lockfile is out of date
+  23     The supply-chain score is below --min-score
+
+Exit codes 10 and above aren't used with --no-exit-codes or SARIF output.";
+
+/// An error that stops a run, classified by what went wrong so that
+/// it exits with the right code.
+///
+/// Errors that aren't wrapped in one of these exit with 1.
+#[derive(Debug, thiserror::Error)]
+enum RunError {
+    /// The configuration (or an audit's part of it) couldn't be loaded.
+    #[error(transparent)]
+    Config(anyhow::Error),
+    /// The inputs couldn't be collected.
+    #[error(transparent)]
+    Collection(anyhow::Error),
+}
+
+impl RunError {
+    /// Classifies `err` as a configuration error, unless it's
+    /// already classified.
+    fn config(err: anyhow::Error) -> anyhow::Error {
+        if err.is::<Self>() {
+            err
+        } else {
+            Self::Config(err).into()
+        }
+    }
+
+    /// Classifies `err` as an input collection error, unless it's
+    /// already classified.
+    fn collection(err: anyhow::Error) -> anyhow::Error {
+        if err.is::<Self>() {
+            err
+        } else {
+            Self::Collection(err).into()
+        }
+    }
+
+    /// The exit code for a run that stopped with `err`.
+    fn exit_code(err: &anyhow::Error) -> ExitCode {
+        match err.downcast_ref::<Self>() {
+            Some(Self::Config(_)) => ExitCode::from(CONFIG_ERROR_EXIT_CODE),
+            Some(Self::Collection(_)) => ExitCode::from(COLLECTION_ERROR_EXIT_CODE),
+            None => ExitCode::FAILURE,
+        }
+    }
 }
 
 #[derive(Debug, Default, Copy, Clone, ValueEnum)]
@@ -160,12 +732,149 @@ pub(crate) enum OutputFormat {
     Json,
     /// "v1" JSON format.
     JsonV1,
+    /// JSON Lines output, streamed as each input is audited.
+    Jsonl,
     /// SARIF-formatted output.
     Sarif,
     /// GitHub Actions workflow command-formatted output.
     Github,
-    /// Third-Party Actions 
-    TpaList
+    /// A self-contained HTML report.
+    Html,
+    /// A pre-filled GitHub issue (or PR comment) body, as Markdown.
+    IssueMd,
+    /// Third-Party Actions
+    TpaList,
+    /// Third-Party Actions, as a Markdown report.
+    TpaMarkdown,
+    /// Third-Party Actions, as a CSV inventory.
+    TpaCsv,
+    /// Third-Party Actions, as SARIF.
+    TpaSarif,
+    /// Third-Party Actions, as a shields.io endpoint badge.
+    TpaBadge,
+    /// Third-Party Actions, as a CycloneDX 1.5 JSON SBOM.
+    Cyclonedx,
+    /// Third-Party Actions, as an SPDX 2.3 JSON document.
+    Spdx,
+}
+
+impl OutputFormat {
+    /// Whether this format can render each input's findings as soon as
+    /// it's been audited, and then release them.
+    ///
+    /// Every other format (e.g. SARIF, HTML, or the JSON array) needs
+    /// every finding at once, and so buffers them until the end of the run.
+    fn streams(self) -> bool {
+        matches!(self, Self::Plain | Self::Jsonl | Self::Github)
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub(crate) enum GroupBy {
+    /// List findings by input file, in the order they were found.
+    #[default]
+    File,
+    /// Group findings under a header for each rule (i.e. audit).
+    Rule,
+}
+
+#[derive(Debug, Copy, Clone, ValueEnum)]
+pub(crate) enum TpaGroupBy {
+    /// Group occurrences by action, ignoring the action's ref.
+    Action,
+}
+
+#[derive(Debug, Default, Copy, Clone, ValueEnum)]
+pub(crate) enum TpaShow {
+    /// Show every action.
+    All,
+    /// Show actions that aren't pinned to a commit SHA.
+    Unpinned,
+    /// Show third-party actions.
+    ThirdParty,
+    /// Show third-party actions that aren't pinned to a commit SHA.
+    #[default]
+    UnpinnedThirdParty,
+}
+
+#[derive(Debug, Default, Copy, Clone, ValueEnum)]
+pub(crate) enum TpaFailOn {
+    /// Fail if any third-party action isn't pinned to a commit SHA.
+    #[default]
+    UnpinnedThirdParty,
+    /// Fail if any third-party action is used at all.
+    AnyThirdParty,
+    /// Never fail because of the TPA report.
+    Never,
+}
+
+#[derive(Debug, Default, Copy, Clone, ValueEnum)]
+pub(crate) enum TpaSuppressed {
+    /// Leave suppressed actions out of the report entirely.
+    #[default]
+    Exclude,
+    /// Keep suppressed actions in the JSON report, marked as suppressed,
+    /// but leave them out of the text output and the summary's counts.
+    Mark,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Rollup {
+    /// Report every finding separately.
+    #[default]
+    None,
+    /// Roll up findings from the same rule, with the same annotation, for
+    /// the same action (e.g. `third-party/action@v1`).
+    ByAction,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum RollupCount {
+    /// Count each rolled-up finding once.
+    #[default]
+    Findings,
+    /// Count each occurrence of a rolled-up finding.
+    Occurrences,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum FailOn {
+    /// Fail on findings of any severity.
+    Unknown,
+    /// Fail on informational findings or worse.
+    Informational,
+    /// Fail on low severity findings or worse.
+    Low,
+    /// Fail on medium severity findings or worse.
+    Medium,
+    /// Fail on high severity findings.
+    High,
+    /// Never fail because of findings.
+    Never,
+}
+
+impl FailOn {
+    /// The minimum severity that fails a run, if any.
+    pub(crate) fn threshold(self) -> Option<Severity> {
+        match self {
+            FailOn::Unknown => Some(Severity::Unknown),
+            FailOn::Informational => Some(Severity::Informational),
+            FailOn::Low => Some(Severity::Low),
+            FailOn::Medium => Some(Severity::Medium),
+            FailOn::High => Some(Severity::High),
+            FailOn::Never => None,
+        }
+    }
+}
+
+impl std::fmt::Display for FailOn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // NOTE: Safe unwrap, since we don't skip any variants.
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
 }
 
 #[derive(Debug, Copy, Clone, ValueEnum)]
@@ -211,9 +920,10 @@ impl From<ColorMode> for anstream::ColorChoice {
 }
 
 /// How `zizmor` collects inputs from local and remote repository sources.
-#[derive(Copy, Clone, Debug, Default, ValueEnum)]
+#[derive(Clone, Debug, Default)]
 pub(crate) enum CollectionMode {
-    /// Collect all possible inputs, ignoring `.gitignore` files.
+    /// Collect all possible inputs, including those in non-standard
+    /// locations, ignoring `.gitignore` files.
     All,
     /// Collect all possible inputs, respecting `.gitignore` files.
     #[default]
@@ -222,31 +932,169 @@ pub(crate) enum CollectionMode {
     WorkflowsOnly,
     /// Collect only action definitions (i.e. `action.yml`).
     ActionsOnly,
+    /// Collect only the inputs at paths matching this glob, relative
+    /// to the root of their local or remote repository.
+    Glob(GlobMatcher),
+}
+
+impl FromStr for CollectionMode {
+    type Err = globset::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "all" => CollectionMode::All,
+            "default" => CollectionMode::Default,
+            "workflows-only" => CollectionMode::WorkflowsOnly,
+            "actions-only" => CollectionMode::ActionsOnly,
+            pattern => CollectionMode::Glob(repo_glob(pattern)?),
+        })
+    }
 }
 
 impl CollectionMode {
     pub(crate) fn respects_gitignore(&self) -> bool {
-        matches!(
-            self,
-            CollectionMode::Default | CollectionMode::WorkflowsOnly | CollectionMode::ActionsOnly
-        )
+        !matches!(self, CollectionMode::All)
     }
 
-    pub(crate) fn workflows(&self) -> bool {
-        matches!(
-            self,
-            CollectionMode::All | CollectionMode::Default | CollectionMode::WorkflowsOnly
-        )
+    /// Classifies the file at `path`, which is at `relative` within its
+    /// repository, returning `None` if it shouldn't be collected.
+    pub(crate) fn classify(
+        &self,
+        path: &Utf8Path,
+        relative: &Utf8Path,
+    ) -> Option<(InputKind, Collection)> {
+        let standard = Self::standard_kind(path);
+
+        match self {
+            CollectionMode::Default => standard.map(|kind| (kind, Collection::Standard)),
+            CollectionMode::WorkflowsOnly => standard
+                .filter(|kind| matches!(kind, InputKind::Workflow))
+                .map(|kind| (kind, Collection::Standard)),
+            CollectionMode::ActionsOnly => standard
+                .filter(|kind| matches!(kind, InputKind::Action))
+                .map(|kind| (kind, Collection::Standard)),
+            CollectionMode::All => standard
+                .map(|kind| (kind, Collection::Standard))
+                .or_else(|| Self::extra_kind(path).map(|kind| (kind, Collection::Extra))),
+            CollectionMode::Glob(glob) => {
+                if !glob.is_match(relative) {
+                    return None;
+                }
+
+                match standard {
+                    Some(kind) => Some((kind, Collection::Standard)),
+                    None if matches!(path.file_name(), Some("action.yml" | "action.yaml")) => {
+                        Some((InputKind::Action, Collection::Extra))
+                    }
+                    None => Some((InputKind::Workflow, Collection::Extra)),
+                }
+            }
+        }
     }
 
-    pub(crate) fn actions(&self) -> bool {
-        matches!(
-            self,
-            CollectionMode::All | CollectionMode::Default | CollectionMode::ActionsOnly
-        )
+    /// The kind of input at `path`, if it's in a location that GitHub
+    /// itself loads inputs from, including an org's workflow templates.
+    fn standard_kind(path: &Utf8Path) -> Option<InputKind> {
+        if (matches!(path.extension(), Some("yml" | "yaml"))
+            && path
+                .parent()
+                .is_some_and(|dir| dir.ends_with(".github/workflows")))
+            || registry::is_template_path(path)
+        {
+            Some(InputKind::Workflow)
+        } else if matches!(path.file_name(), Some("action.yml" | "action.yaml")) {
+            Some(InputKind::Action)
+        } else {
+            None
+        }
+    }
+
+    /// The kind of input at `path`, if it's in one of the non-standard
+    /// locations that `--collect=all` also collects from: subdirectories
+    /// of `.github/workflows/`, and any YAML under `.github/actions/`.
+    fn extra_kind(path: &Utf8Path) -> Option<InputKind> {
+        if !matches!(path.extension(), Some("yml" | "yaml")) {
+            return None;
+        }
+
+        let mut ancestors = path.ancestors().skip(1);
+        if ancestors
+            .clone()
+            .any(|dir| dir.ends_with(".github/workflows"))
+        {
+            Some(InputKind::Workflow)
+        } else if ancestors.any(|dir| dir.ends_with(".github/actions")) {
+            Some(InputKind::Action)
+        } else {
+            None
+        }
+    }
+}
+
+/// Paths that are never collected from repository input sources,
+/// per `--exclude` and the configuration's `exclude`, along with the
+/// configuration's `vendored-paths`.
+pub(crate) struct Exclusions {
+    globs: Vec<Glob>,
+    set: GlobSet,
+    vendored: GlobSet,
+}
+
+impl Exclusions {
+    /// Combines `--exclude` with the `exclude` patterns of `config`, which
+    /// should be the configuration that applies to the input being collected.
+    fn new(app: &App, config: &Config) -> Result<Self> {
+        let mut globs = app.exclude.clone();
+        for pattern in &config.exclude {
+            globs.push(
+                Glob::new(pattern)
+                    .with_context(|| format!("invalid exclude pattern in config: {pattern}"))?,
+            );
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        for glob in &globs {
+            builder.add(glob.clone());
+        }
+
+        let mut vendored = GlobSetBuilder::new();
+        for pattern in &config.vendored_paths {
+            vendored.add(
+                Glob::new(pattern).with_context(|| {
+                    format!("invalid vendored-paths pattern in config: {pattern}")
+                })?,
+            );
+        }
+
+        Ok(Self {
+            set: builder.build()?,
+            globs,
+            vendored: vendored.build()?,
+        })
+    }
+
+    /// Returns the pattern that excludes the given repository-relative
+    /// path (or any of its parent directories), if any.
+    pub(crate) fn exclusion(&self, path: &Utf8Path) -> Option<&str> {
+        path.ancestors()
+            .filter(|path| !path.as_str().is_empty())
+            .find_map(|path| self.set.matches(path).first().copied())
+            .map(|index| self.globs[index].glob())
+    }
+
+    /// Returns whether the given repository-relative path (or any of its
+    /// parent directories) is vendored.
+    pub(crate) fn is_vendored(&self, path: &Utf8Path) -> bool {
+        path.ancestors()
+            .filter(|path| !path.as_str().is_empty())
+            .any(|path| self.vendored.is_match(path))
     }
 }
 
+fn repo_glob(pattern: &str) -> Result<GlobMatcher, globset::Error> {
+    Ok(Glob::new(pattern)?.compile_matcher())
+}
+
 fn tips(err: impl AsRef<str>, tips: &[impl AsRef<str>]) -> String {
     let mut message = Level::Error.title(err.as_ref());
     for tip in tips {
@@ -257,10 +1105,12 @@ fn tips(err: impl AsRef<str>, tips: &[impl AsRef<str>]) -> String {
     format!("{}", renderer.render(message))
 }
 
-#[instrument(skip(mode, registry))]
+#[instrument(skip(app, exclusions, seen, registry))]
 fn collect_from_dir(
     input_path: &Utf8Path,
-    mode: &CollectionMode,
+    app: &App,
+    exclusions: &Exclusions,
+    seen: &mut HashSet<Utf8PathBuf>,
     registry: &mut InputRegistry,
 ) -> Result<()> {
     // Start with all filters disabled, i.e. walk everything.
@@ -278,7 +1128,7 @@ fn collect_from_dir(
     // zizmor integrators.
     //
     // See: https://github.com/zizmorcore/zizmor/issues/596
-    if mode.respects_gitignore() {
+    if app.collect.respects_gitignore() && !app.no_ignore {
         walker
             .require_git(false)
             .git_ignore(true)
@@ -290,26 +1140,31 @@ fn collect_from_dir(
         let entry = entry?;
         let entry = <&Utf8Path>::try_from(entry.path())?;
 
-        if mode.workflows()
-            && entry.is_file()
-            && matches!(entry.extension(), Some("yml" | "yaml"))
-            && entry
-                .parent()
-                .is_some_and(|dir| dir.ends_with(".github/workflows"))
-        {
-            let key = InputKey::local(entry, Some(input_path))?;
-            let contents = std::fs::read_to_string(entry)?;
-            registry.register(InputKind::Workflow, contents, key)?;
+        if !entry.is_file() {
+            continue;
         }
 
-        if mode.actions()
-            && entry.is_file()
-            && matches!(entry.file_name(), Some("action.yml" | "action.yaml"))
-        {
-            let key = InputKey::local(entry, Some(input_path))?;
-            let contents = std::fs::read_to_string(entry)?;
-            registry.register(InputKind::Action, contents, key)?;
+        let relative = entry.strip_prefix(input_path).unwrap_or(entry);
+        let Some((kind, collection)) = app.collect.classify(entry, relative) else {
+            continue;
+        };
+
+        if let Some(pattern) = exclusions.exclusion(relative) {
+            tracing::debug!("excluding {entry}: matches {pattern}");
+            continue;
+        }
+
+        // Overlapping inputs (e.g. `zizmor . .github/`) would otherwise
+        // audit the same file more than once.
+        if !seen.insert(entry.canonicalize_utf8()?) {
+            tracing::debug!("skipping {entry}: already collected");
+            continue;
         }
+
+        let key = InputKey::local(entry, Some(input_path))?;
+        let contents = std::fs::read_to_string(entry)?;
+        let vendored = exclusions.is_vendored(relative);
+        registry.register_collected(kind, contents, key, collection, vendored)?;
     }
 
     Ok(())
@@ -317,7 +1172,7 @@ fn collect_from_dir(
 
 fn collect_from_repo_slug(
     input: &str,
-    mode: &CollectionMode,
+    app: &App,
     state: &AuditState,
     registry: &mut InputRegistry,
 ) -> Result<()> {
@@ -353,15 +1208,46 @@ fn collect_from_repo_slug(
         ))
     })?;
 
-    if matches!(mode, CollectionMode::WorkflowsOnly) {
+    let mut collected = InputRegistry::new(app.strict_collection);
+    let config = collect_from_repository(app, state.config, &client, &slug, &mut collected)?;
+
+    if !matches!(app.collect, CollectionMode::WorkflowsOnly) {
+        tracing::info!(
+            "collected {len} inputs from {owner}/{repo}",
+            len = collected.len(),
+            owner = slug.owner,
+            repo = slug.repo
+        );
+    }
+
+    registry.merge(collected, config)
+}
+
+/// Collects inputs from the given repository into `registry`, returning
+/// the configuration discovered at the repository's root (if any).
+fn collect_from_repository(
+    app: &App,
+    config: &Config,
+    client: &Client,
+    slug: &RepositoryUses,
+    registry: &mut InputRegistry,
+) -> Result<Option<Config>> {
+    // The repository's own configuration (if any) is discovered first,
+    // since its `exclude` patterns apply during collection.
+    let target = format!("{owner}/{repo}", owner = slug.owner, repo = slug.repo);
+    let target_config =
+        Config::discover_remote(app, &target, |path| client.fetch_file(slug, path))?;
+    let exclusions =
+        Exclusions::new(app, target_config.as_ref().unwrap_or(config)).map_err(RunError::config)?;
+
+    if matches!(app.collect, CollectionMode::WorkflowsOnly) {
         // Performance: if we're *only* collecting workflows, then we
         // can save ourselves a full repo download and only fetch the
         // repo's workflow files.
-        client.fetch_workflows(&slug, registry)?;
+        client.fetch_workflows(slug, &exclusions, registry)?;
     } else {
-        let before = registry.len();
         client
-            .fetch_audit_inputs(&slug, registry)
+            .fetch_audit_inputs(slug, &app.collect, &exclusions, registry)
             .with_context(|| {
                 tips(
                     format!(
@@ -372,60 +1258,559 @@ fn collect_from_repo_slug(
                     &["confirm the repository exists and that you have access to it"],
                 )
             })?;
-        let after = registry.len();
-        let len = after - before;
-
-        tracing::info!(
-            "collected {len} inputs from {owner}/{repo}",
-            owner = slug.owner,
-            repo = slug.repo
-        );
     }
 
-    Ok(())
+    Ok(target_config)
 }
 
-#[instrument(skip_all)]
-fn collect_inputs(
-    inputs: &[String],
-    mode: &CollectionMode,
-    strict: bool,
+/// Collects inputs from every selected, non-archived repository in `org`,
+/// fetching up to `--concurrency` repositories at a time.
+///
+/// Repositories that can't be fetched (e.g. due to missing permissions
+/// or rate limits) are reported individually and skipped, rather than
+/// aborting the entire scan.
+fn collect_from_org(
+    org: &str,
+    app: &App,
     state: &AuditState,
-) -> Result<InputRegistry> {
-    let mut registry = InputRegistry::new(strict);
+    registry: &mut InputRegistry,
+) -> Result<()> {
+    let client = state.github_client().ok_or_else(|| {
+        anyhow!(tips(
+            format!("can't list repositories for {org}", org = org.green()),
+            &[format!(
+                "try removing {offline} or passing {gh_token}",
+                offline = "--offline".yellow(),
+                gh_token = "--gh-token <TOKEN>".yellow(),
+            )]
+        ))
+    })?;
 
-    for input in inputs {
-        let input_path = Utf8Path::new(input);
-        if input_path.is_file() {
-            // When collecting individual files, we don't know which part
-            // of the input path is the prefix.
-            let (key, kind) = match (input_path.file_stem(), input_path.extension()) {
-                (Some("action"), Some("yml" | "yaml")) => {
-                    (InputKey::local(input_path, None)?, InputKind::Action)
-                }
-                (Some(_), Some("yml" | "yaml")) => {
-                    (InputKey::local(input_path, None)?, InputKind::Workflow)
-                }
-                _ => return Err(anyhow!("invalid input: {input}")),
-            };
+    let mut repos = client
+        .list_org_repositories(org)
+        .with_context(|| {
+            tips(
+                format!("couldn't list repositories for {org}"),
+                &["confirm the organization exists and that you have access to it"],
+            )
+        })?
+        .into_iter()
+        .filter(|repo| {
+            !repo.archived
+                && app
+                    .repo_filter
+                    .as_ref()
+                    .is_none_or(|filter| filter.is_match(&repo.name))
+                && app
+                    .repo_topic
+                    .as_ref()
+                    .is_none_or(|topic| repo.topics.contains(topic))
+        })
+        .map(|repo| RepositoryUses {
+            owner: org.into(),
+            repo: repo.name,
+            subpath: None,
+            git_ref: None,
+        })
+        .collect::<Vec<_>>();
+    repos.sort_by(|a, b| a.repo.cmp(&b.repo));
+
+    tracing::info!(
+        "collecting inputs from {n} repositories in {org}",
+        n = repos.len()
+    );
 
-            let contents = std::fs::read_to_string(input_path)?;
-            registry.register(kind, contents, key)?;
-        } else if input_path.is_dir() {
-            collect_from_dir(input_path, mode, &mut registry)?;
-            // collect_from_repo_dir(input_path, input_path, mode, &mut registry)?;
-        } else {
-            // If this input isn't a file or directory, it's probably an
-            // `owner/repo(@ref)?` slug.
-            collect_from_repo_slug(input, mode, state, &mut registry)?;
+    // Each repository is collected into its own registry, so that a
+    // failure partway through a repository doesn't leave it half-registered.
+    let next = AtomicUsize::new(0);
+    let results = thread::scope(|scope| {
+        let workers = (0..app.concurrency.get().min(repos.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = vec![];
+                    while let Some(slug) = repos.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        let mut collected = InputRegistry::new(app.strict_collection);
+                        let result = collect_from_repository(
+                            app,
+                            state.config,
+                            &client,
+                            slug,
+                            &mut collected,
+                        )
+                        .map(|config| (collected, config));
+                        results.push((&slug.repo, result));
+                    }
+                    results
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("repository collection panicked"))
+            .collect::<BTreeMap<_, _>>()
+    });
+
+    let mut failed = 0;
+    for (repo, result) in results {
+        match result {
+            Ok((collected, config)) => {
+                tracing::info!(
+                    "collected {len} inputs from {org}/{repo}",
+                    len = collected.len()
+                );
+                registry.merge(collected, config)?;
+            }
+            Err(e) => {
+                tracing::warn!("couldn't collect inputs from {org}/{repo}: {e:#}");
+                failed += 1;
+            }
         }
     }
 
-    if registry.len() == 0 {
-        return Err(anyhow!("no inputs collected"));
+    if failed > 0 {
+        tracing::warn!(
+            "{failed} of {total} repositories in {org} couldn't be audited",
+            total = repos.len()
+        );
     }
 
-    Ok(registry)
+    Ok(())
+}
+
+/// Registers a single workflow or action definition, whose kind is
+/// determined by its path.
+fn collect_from_file(
+    path: &Utf8Path,
+    contents: String,
+    registry: &mut InputRegistry,
+) -> Result<()> {
+    // When collecting individual files, we don't know which part
+    // of the input path is the prefix.
+    let key = InputKey::local(path, None)?;
+    let kind = match (path.file_stem(), path.extension()) {
+        (Some("action"), Some("yml" | "yaml")) => InputKind::Action,
+        (Some(_), Some("yml" | "yaml")) => InputKind::Workflow,
+        _ => return Err(anyhow!("invalid input: {path}")),
+    };
+
+    registry.register(kind, contents, key)
+}
+
+#[instrument(skip_all)]
+fn collect_inputs(app: &App, config: &Config, state: &AuditState) -> Result<InputRegistry> {
+    let mut registry = InputRegistry::new(app.strict_collection);
+    // The canonical paths of every local file collected so far.
+    let mut seen = HashSet::new();
+
+    for input in &app.inputs {
+        let input_path = Utf8Path::new(input);
+        if input_path.is_file() {
+            if !seen.insert(input_path.canonicalize_utf8()?) {
+                tracing::debug!("skipping {input_path}: already collected");
+                continue;
+            }
+
+            let contents = std::fs::read_to_string(input_path)?;
+            collect_from_file(input_path, contents, &mut registry)?;
+        } else if input_path.is_dir() {
+            let target_config =
+                Config::discover_local(app, config, input_path).map_err(RunError::config)?;
+            let exclusions = Exclusions::new(app, target_config.as_ref().unwrap_or(config))
+                .map_err(RunError::config)?;
+
+            let mut collected = InputRegistry::new(app.strict_collection);
+            collect_from_dir(input_path, app, &exclusions, &mut seen, &mut collected)?;
+            registry.merge(collected, target_config)?;
+        } else {
+            // If this input isn't a file or directory, it's probably an
+            // `owner/repo(@ref)?` slug.
+            collect_from_repo_slug(input, app, state, &mut registry)?;
+        }
+    }
+
+    if let Some(org) = &app.org {
+        collect_from_org(org, app, state, &mut registry)?;
+    }
+
+    if let Some(filename) = app.stdin_filename.as_deref().filter(|_| app.stdin) {
+        let contents = std::io::read_to_string(std::io::stdin())
+            .with_context(|| format!("couldn't read {filename} from stdin"))?;
+        collect_from_file(filename, contents, &mut registry)?;
+    }
+
+    if registry.len() == 0 {
+        return Err(anyhow!("no inputs collected"));
+    }
+
+    if let Some(since) = &app.changed_since {
+        match ChangedFiles::discover(Utf8Path::new("."), since) {
+            Ok(changed) => registry.skip_unchanged(&changed),
+            Err(err) => tracing::warn!(
+                "couldn't determine which files changed since {since}, auditing everything: {err:#}"
+            ),
+        }
+    }
+
+    // Local actions that are used by the collected inputs are audited
+    // along with them, unless only workflows are being collected.
+    if !matches!(app.collect, CollectionMode::WorkflowsOnly) {
+        let n = transitive::collect_local(&mut registry, &mut seen)?;
+        if n > 0 {
+            tracing::info!(
+                "collected {n} local action{s} used by other inputs",
+                s = if n == 1 { "" } else { "s" }
+            );
+        }
+    }
+
+    if app.audit_transitive {
+        let client = state.github_client().ok_or_else(|| {
+            anyhow!(tips(
+                "can't audit composite actions transitively",
+                &[format!(
+                    "try passing {gh_token}",
+                    gh_token = "--gh-token <TOKEN>".yellow(),
+                )]
+            ))
+        })?;
+
+        let n = transitive::collect(&client, app.audit_transitive_depth.get(), &mut registry)?;
+        tracing::info!(
+            "collected {n} composite action{s} transitively",
+            s = if n == 1 { "" } else { "s" }
+        );
+    }
+
+    Ok(registry)
+}
+
+/// Audits each input in `registry` with every audit in `audit_registry`
+/// (or, for inputs with a target configuration, in the corresponding
+/// `target_audits`), using up to `jobs` threads.
+///
+/// `on_audited` is called with each input and its findings in input
+/// order, regardless of the order in which the inputs finish, so that
+/// output is deterministic. Auditing stops at the first error (in input
+/// order) from either an audit or `on_audited`.
+fn audit_inputs<'doc>(
+    registry: &'doc InputRegistry,
+    audit_registry: &AuditRegistry,
+    target_audits: &[AuditRegistry],
+    cache: Option<&ResultsCache>,
+    jobs: NonZeroUsize,
+    progress: &Span,
+    mut on_audited: impl FnMut(Option<&'doc AuditInput>, Vec<Finding<'doc>>, Duration) -> Result<()>,
+) -> Result<()> {
+    let inputs = registry
+        .iter_inputs()
+        .map(|(_, input)| input)
+        .collect::<Vec<_>>();
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.get().min(inputs.len()) {
+            let (tx, next, inputs) = (tx.clone(), &next, &inputs);
+            scope.spawn(move || {
+                while let Some(input) = inputs.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    progress.pb_set_message(input.key().filename());
+
+                    let config_index = registry.config_index(input.key());
+                    let audits = config_index.map_or(audit_registry, |index| &target_audits[index]);
+
+                    let start = Instant::now();
+                    let result = audit_input(input, audits, cache, config_index, progress)
+                        .and_then(|mut findings| {
+                            registry.add_caller_locations(input, &mut findings)?;
+                            Ok((findings, start.elapsed()))
+                        });
+
+                    // The receiver is only dropped once auditing has failed,
+                    // so there's no point in continuing.
+                    if tx.send((input, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        // Inputs can finish in any order, so we hold on to each one's
+        // results until every input before it has been handled.
+        let mut pending = BTreeMap::new();
+        let mut handled = 0;
+        for (input, result) in rx {
+            pending.insert(input.key(), result);
+
+            while let Some(result) = inputs
+                .get(handled)
+                .and_then(|input| pending.remove(input.key()))
+            {
+                let (findings, elapsed) = result?;
+                on_audited(Some(inputs[handled]), findings, elapsed)?;
+                handled += 1;
+            }
+        }
+
+        Ok::<_, anyhow::Error>(())
+    })?;
+
+    // Audits that report on the run as a whole go last, with the inputs
+    // that they were run on.
+    for (index, audits) in std::iter::once(audit_registry)
+        .chain(target_audits)
+        .enumerate()
+    {
+        let config_index = index.checked_sub(1);
+        let inputs = inputs
+            .iter()
+            .copied()
+            .filter(|input| registry.config_index(input.key()) == config_index)
+            .collect::<Vec<_>>();
+        if inputs.is_empty() {
+            continue;
+        }
+
+        let start = Instant::now();
+        let findings = audits.finish(&inputs)?;
+        on_audited(None, findings, start.elapsed())?;
+    }
+
+    Ok(())
+}
+
+/// Audits a single input with `audits`, replaying cacheable audits'
+/// findings from `cache` when they're still valid, and caching them
+/// otherwise.
+fn audit_input<'doc>(
+    input: &'doc AuditInput,
+    audits: &AuditRegistry,
+    cache: Option<&ResultsCache>,
+    config_index: Option<usize>,
+    progress: &Span,
+) -> Result<Vec<Finding<'doc>>> {
+    let mut cached = cache.and_then(|cache| cache.load(input, config_index));
+    // Fresh results are only cached when they weren't replayed, and
+    // only if every one of them can be.
+    let mut uncached = cache.filter(|_| cached.is_none()).map(|_| HashMap::new());
+
+    let mut findings = vec![];
+    for (name, audit) in audits.iter_audits() {
+        let cacheable = ResultsCache::cacheable(name);
+
+        if let Some(replayed) = cached
+            .as_mut()
+            .filter(|_| cacheable)
+            .and_then(|cached| cached.remove(*name))
+        {
+            findings.extend(replayed);
+            progress.pb_inc(1);
+            continue;
+        }
+
+        let start = Instant::now();
+        let audited = audit
+            .audit(input)
+            .with_context(|| format!("{name} failed on {input}", input = input.key().filename()))?;
+        audits.record_wall_time(name, start.elapsed());
+        progress.pb_inc(1);
+
+        if cacheable {
+            uncached = uncached.and_then(|mut uncached| {
+                let audited = audited
+                    .iter()
+                    .map(|finding| CachedFinding::new(finding, input))
+                    .collect::<Option<Vec<_>>>()?;
+                uncached.insert(name.to_string(), audited);
+                Some(uncached)
+            });
+        }
+
+        findings.extend(audited);
+    }
+
+    if let (Some(cache), Some(uncached)) = (cache, uncached) {
+        if let Err(err) = cache.store(input, config_index, uncached) {
+            tracing::warn!("{err:#}");
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Loads every audit with the given state, skipping audits that can't
+/// run in it (e.g. online audits in offline mode).
+fn load_audits(app: &App, state: &AuditState) -> Result<AuditRegistry> {
+    let mut audit_registry = AuditRegistry::new();
+    for rule in audit::RULES.iter() {
+        if let Some(reason) = disabled_reason(app, state.config, rule.ident) {
+            tracing::debug!("skipping {audit}: {reason}", audit = rule.ident);
+            audit_registry.skip_audit(rule.ident, reason.into());
+            continue;
+        }
+
+        match (rule.new)(state) {
+            Ok(audit) => audit_registry.register_audit(rule.ident, audit),
+            Err(AuditLoadError::Skip(e)) => {
+                tracing::debug!("skipping {audit}: {e}", audit = rule.ident);
+                audit_registry.skip_audit(rule.ident, e.to_string());
+            }
+            Err(AuditLoadError::Broken(e)) if !app.strict => {
+                tracing::warn!(
+                    "failed to load {audit}, continuing without it: {e:#}",
+                    audit = rule.ident
+                );
+                audit_registry.fail_audit(rule.ident, format!("{e:#}"));
+            }
+            Err(AuditLoadError::Fail(e) | AuditLoadError::Broken(e)) => {
+                return Err(anyhow!(tips(
+                    format!("failed to load audit: {audit}", audit = rule.ident),
+                    &[format!("{e:#}"), format!("see: {url}", url = rule.url)]
+                )));
+            }
+        }
+    }
+
+    // Typos in rule idents would otherwise silently do nothing.
+    let unknown = state
+        .config
+        .rule_idents()
+        .into_iter()
+        .map(|ident| (ident, "configuration"))
+        .chain(
+            app.enable
+                .iter()
+                .chain(&app.disable)
+                .map(|ident| (ident.as_str(), "--enable/--disable")),
+        )
+        .find(|(ident, _)| !audit::RULES.iter().any(|rule| rule.ident == *ident));
+    if let Some((ident, source)) = unknown {
+        return Err(anyhow!(tips(
+            format!("unknown rule in {source}: {ident}"),
+            &["see `zizmor --list-rules` for a list of rules"]
+        )));
+    }
+
+    Ok(audit_registry)
+}
+
+/// Returns why the given audit is disabled, if it is.
+///
+/// `--enable` and `--disable` override the configuration's `enabled`.
+fn disabled_reason(app: &App, config: &Config, ident: &str) -> Option<&'static str> {
+    if app.disable.iter().any(|rule| rule == ident) {
+        Some("disabled by --disable")
+    } else if app.enable.iter().any(|rule| rule == ident) {
+        None
+    } else if config.rule_enabled(ident) == Some(false) {
+        Some("disabled by config")
+    } else {
+        None
+    }
+}
+
+/// A single row of `--list-rules`.
+#[derive(Serialize)]
+struct ListedRule<'a> {
+    ident: Cow<'a, str>,
+    desc: &'a str,
+    url: &'static str,
+    #[serde(flatten)]
+    meta: AuditMeta,
+}
+
+/// The widest that `--list-rules` renders a line of a rule's config keys.
+const CONFIG_KEYS_WIDTH: usize = 40;
+
+/// Lists every rule, followed by each of `config`'s custom rules.
+fn list_rules(format: OutputFormat, config: &Config) -> Result<ExitCode> {
+    let rules = audit::RULES
+        .iter()
+        .map(|rule| ListedRule {
+            ident: rule.ident.into(),
+            desc: rule.desc,
+            url: rule.url,
+            meta: rule.meta,
+        })
+        .chain(config.custom_rules.iter().map(|rule| ListedRule {
+            ident: finding::qualified_ident(CustomRules::ident(), Some(&rule.id)),
+            desc: &rule.description,
+            url: CustomRules::url(),
+            meta: CustomRules::meta(),
+        }))
+        .collect::<Vec<_>>();
+
+    match format {
+        OutputFormat::Plain => {
+            let rows = rules
+                .iter()
+                .map(|rule| {
+                    let config_keys = match rule.meta.config_keys {
+                        [] => vec!["-".into()],
+                        keys => wrap_config_keys(keys),
+                    };
+                    (rule, config_keys)
+                })
+                .collect::<Vec<_>>();
+            let width = rows
+                .iter()
+                .map(|(rule, _)| rule.ident.len())
+                .max()
+                .unwrap_or(0);
+            let desc_width = rows
+                .iter()
+                .map(|(rule, _)| rule.desc.chars().count())
+                .max()
+                .unwrap_or(0);
+
+            // NOTE: The config keys come last, since some rules have many
+            // of them; those are wrapped onto continuation lines.
+            println!(
+                "{:width$}  {:8}  {:desc_width$}  CONFIG",
+                "RULE", "NETWORK", "DESCRIPTION"
+            );
+            let indent = width + 2 + 8 + 2 + desc_width + 2;
+            for (rule, config_keys) in rows {
+                println!(
+                    "{:width$}  {:8}  {desc:desc_width$}  {keys}",
+                    rule.ident,
+                    rule.meta.network.as_str(),
+                    desc = rule.desc,
+                    keys = config_keys[0],
+                );
+                for keys in &config_keys[1..] {
+                    println!("{:indent$}{keys}", "");
+                }
+            }
+        }
+        OutputFormat::Json | OutputFormat::JsonV1 => {
+            println!("{}", serde_json::to_string_pretty(&rules)?);
+        }
+        _ => anyhow::bail!("--list-rules only supports the plain and json formats"),
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Joins `keys` into comma-separated lines of at most [`CONFIG_KEYS_WIDTH`]
+/// characters each, for `--list-rules`.
+fn wrap_config_keys(keys: &[&str]) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for key in keys {
+        // NOTE: Safe unwrap, since there's always at least one line.
+        let line = lines.last_mut().unwrap();
+        if line.is_empty() {
+            line.push_str(key);
+        } else if line.len() + ", ".len() + key.len() > CONFIG_KEYS_WIDTH {
+            line.push(',');
+            lines.push(key.to_string());
+        } else {
+            line.push_str(", ");
+            line.push_str(key);
+        }
+    }
+    lines
 }
 
 fn completions<G: clap_complete::Generator>(generator: G, cmd: &mut clap::Command) {
@@ -437,11 +1822,293 @@ fn completions<G: clap_complete::Generator>(generator: G, cmd: &mut clap::Comman
     );
 }
 
+/// Validates the configuration in `state` as thoroughly as auditing would,
+/// without collecting or auditing any inputs.
+fn check_config(app: &App, state: &AuditState) -> Result<ExitCode> {
+    // Missing allowlist files only produce warnings while loading audits,
+    // so they're checked first. The badge thresholds are only loaded by
+    // the tpa-badge format, so they're checked explicitly too.
+    AllowedOrgs::check_files(state)?;
+    BadgeThresholds::from_state(state)?;
+    // Loading every audit checks for unknown rules, and checks each
+    // rule's configuration.
+    for warning in load_audits(app, state)?.config_warnings() {
+        tracing::warn!("{warning}");
+    }
+
+    match state.config.origin() {
+        Some(origin) => println!("{origin}: configuration is valid"),
+        None => println!("no configuration to check"),
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Fixes (or, with `--dry-run`, previews fixes for) the run's findings.
+fn fix(
+    app: &App,
+    state: &AuditState,
+    mut sink: Sink,
+    registry: &InputRegistry,
+    results: &FindingRegistry,
+) -> Result<ExitCode> {
+    state
+        .online_client()
+        .map_err(|e| anyhow!("--fix needs the GitHub API: {e}"))?;
+    let plan = FixPlan::new(registry, results)?;
+
+    if app.dry_run {
+        plan.render_diff(&mut sink)?;
+        sink.finish()?;
+
+        return Ok(if plan.is_empty() || app.no_exit_codes {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::from(FIX_PENDING_EXIT_CODE)
+        });
+    }
+
+    sink.finish()?;
+    if plan.is_empty() {
+        tracing::info!("no fixable findings");
+    }
+    plan.apply()?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn emit_allowlist(path: &Utf8Path, registry: &InputRegistry) -> Result<ExitCode> {
+    let mut sink = Sink::new(Some(path))?;
+    let orgs = output::tpa_allowlist::output(&mut sink, registry)?;
+    if let Some(path) = sink.path() {
+        tracing::info!("wrote {orgs} third-party org(s) to {path}");
+    }
+    sink.finish()?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Writes a lockfile for every action used by the inputs in `registry`.
+fn lock(state: &AuditState, registry: &InputRegistry, path: &Utf8Path) -> Result<ExitCode> {
+    let lockfile = resolve_lock(state, registry)?.lockfile;
+    write_lockfile(&lockfile, path)?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Checks (or, with `--update`, updates) the lockfile at `path` against
+/// the actions used by the inputs in `registry`.
+fn verify_lock(
+    app: &App,
+    state: &AuditState,
+    registry: &InputRegistry,
+    path: &Utf8Path,
+    update: bool,
+) -> Result<ExitCode> {
+    let locked = lock::Lockfile::load(path)?;
+    let resolution = resolve_lock(state, registry)?;
+
+    let discrepancies = resolution.verify(&locked);
+    for discrepancy in &discrepancies {
+        println!("{discrepancy}");
+    }
+
+    let nunresolved = discrepancies
+        .iter()
+        .filter(|d| matches!(d.kind, lock::DiscrepancyKind::Unresolved))
+        .count();
+    let ndrifted = discrepancies.len() - nunresolved;
+
+    if update {
+        write_lockfile(&resolution.update(&locked), path)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if ndrifted > 0 {
+        tracing::info!(
+            "{ndrifted} lockfile entr{ies} out of date; run with {update} to update {path}",
+            ies = if ndrifted == 1 { "y is" } else { "ies are" },
+            update = "--update".green(),
+        );
+
+        return Ok(if app.no_exit_codes {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::from(LOCK_DRIFT_EXIT_CODE)
+        });
+    }
+
+    if nunresolved > 0 {
+        return Err(anyhow!(
+            "couldn't verify {nunresolved} lockfile entr{ies}",
+            ies = if nunresolved == 1 { "y" } else { "ies" }
+        ));
+    }
+
+    tracing::info!("{path} is up to date");
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Pins (or, with `--dry-run`, previews pinning) every symbolic ref used by
+/// the inputs in `registry` to the commit locked in the lockfile at `path`.
+fn pin(app: &App, registry: &InputRegistry, path: &Utf8Path, dry_run: bool) -> Result<ExitCode> {
+    let locked = lock::Lockfile::load(path)?;
+    let (plan, problems) = FixPlan::from_lockfile(registry, &locked)?;
+
+    for problem in &problems {
+        tracing::warn!("{problem}");
+    }
+    let ndisagreeing = problems
+        .iter()
+        .filter(|p| matches!(p.kind, fix::PinProblemKind::Disagrees { .. }))
+        .count();
+
+    let mut sink = Sink::new(app.output.as_deref())?;
+    if dry_run {
+        plan.render_diff(&mut sink)?;
+    }
+    sink.finish()?;
+
+    if !dry_run {
+        if plan.is_empty() {
+            tracing::info!("no refs to pin");
+        }
+        plan.apply()?;
+    }
+
+    Ok(if app.no_exit_codes {
+        ExitCode::SUCCESS
+    } else if ndisagreeing > 0 {
+        tracing::info!(
+            "{ndisagreeing} pinned ref{s} disagree{es} with {path}",
+            s = if ndisagreeing == 1 { "" } else { "s" },
+            es = if ndisagreeing == 1 { "s" } else { "" },
+        );
+        ExitCode::from(LOCK_DRIFT_EXIT_CODE)
+    } else if dry_run && !plan.is_empty() {
+        ExitCode::from(FIX_PENDING_EXIT_CODE)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// Resolves the refs of every action used by the inputs in `registry`.
+fn resolve_lock(state: &AuditState, registry: &InputRegistry) -> Result<lock::Resolution> {
+    if !state.resolver.online() {
+        return Err(anyhow!(tips(
+            "can't resolve refs for the lockfile",
+            &[format!(
+                "try removing {offline} or passing {gh_token}",
+                offline = "--offline".yellow(),
+                gh_token = "--gh-token <TOKEN>".yellow(),
+            )]
+        )));
+    }
+
+    Ok(lock::Lockfile::generate(
+        &output::tpa_list::extract_actions(registry, &AllowedOrgs::official(), &state.resolver)?,
+        &state.resolver,
+    ))
+}
+
+fn write_lockfile(lockfile: &lock::Lockfile, path: &Utf8Path) -> Result<()> {
+    let mut sink = Sink::new(Some(path))?;
+    sink.write_all(lockfile.render()?.as_bytes())?;
+    if let Some(path) = sink.path() {
+        tracing::info!(
+            "locked {n} action(s) and {m} image(s) in {path}",
+            n = lockfile.actions.len(),
+            m = lockfile.images.len()
+        );
+    }
+
+    sink.finish()
+}
+
+/// Removes every cached audit result, for `zizmor cache clear`.
+fn cache_clear(app: &App) -> Result<ExitCode> {
+    let cache_dir = AuditState::cache_dir(app);
+    if ResultsCache::clear(&cache_dir)? {
+        tracing::info!(
+            "cleared cached results in {dir}",
+            dir = ResultsCache::dir(&cache_dir).display()
+        );
+    } else {
+        tracing::info!("no cached results to clear");
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Returns the GitHub API token to use, if any, logging how it was
+/// resolved.
+fn resolve_credentials(app: &App, config: &Config) -> Result<Option<String>> {
+    let resolved = match (app.gh_app_id, &app.gh_app_key_path) {
+        (Some(app_id), Some(key_path)) => {
+            // Remote inputs are `owner/repo(@ref)?` slugs, each of which
+            // can only be audited with access to its repository.
+            let remote = app
+                .inputs
+                .iter()
+                .filter(|input| !Utf8Path::new(input).exists())
+                .find_map(|input| match Uses::from_str(input) {
+                    Ok(Uses::Repository(slug)) => Some(slug),
+                    _ => None,
+                });
+            let scope = match (&app.org, &remote) {
+                (Some(org), _) => InstallationScope::Org(org),
+                (None, Some(slug)) => InstallationScope::Repo {
+                    owner: &slug.owner,
+                    repo: &slug.repo,
+                },
+                (None, None) => InstallationScope::Only,
+            };
+
+            let (token, source) = GitHubApp::load(app_id, key_path)
+                .and_then(|gh_app| {
+                    gh_app.installation_token(&AuditState::gh_hostname(app, config), scope)
+                })
+                .map_err(|e| {
+                    anyhow!(tips(
+                        format!("couldn't authenticate as GitHub App {app_id}: {e:#}"),
+                        &["check --gh-app-id, --gh-app-key-path, and the app's installations"]
+                    ))
+                })?;
+            Some((token, source))
+        }
+        _ => auth::token_from(app.gh_token.as_deref(), |var| std::env::var(var).ok()),
+    };
+
+    Ok(match resolved {
+        Some((token, source)) => {
+            tracing::info!("authenticating to GitHub with {source}");
+            Some(token)
+        }
+        None => {
+            tracing::debug!("no GitHub credentials available");
+            None
+        }
+    })
+}
+
 fn run() -> Result<ExitCode> {
     human_panic::setup_panic!();
 
     let mut app = App::parse();
 
+    // Subcommands take their own inputs, but collect them like audits do.
+    if let Some(
+        Command::Lock { inputs, .. }
+        | Command::VerifyLock { inputs, .. }
+        | Command::Pin { inputs, .. },
+    ) = &mut app.command
+    {
+        app.inputs = match std::mem::take(inputs) {
+            inputs if inputs.is_empty() => vec![".".into()],
+            inputs => inputs,
+        };
+    }
+
     if let Some(shell) = app.completions {
         let mut cmd = App::command();
         completions(shell, &mut cmd);
@@ -479,15 +2146,7 @@ fn run() -> Result<ExitCode> {
 
     // `--pedantic` is a shortcut for `--persona=pedantic`.
     if app.pedantic {
-        app.persona = Persona::Pedantic;
-    }
-
-    // Unset the GitHub token if we're in offline mode.
-    // We do this manually instead of with clap's `conflicts_with` because
-    // we want to support explicitly enabling offline mode while still
-    // having `GH_TOKEN` present in the environment.
-    if app.offline {
-        app.gh_token = None;
+        app.persona = Some(Persona::Pedantic);
     }
 
     let indicatif_layer = IndicatifLayer::new();
@@ -517,114 +2176,657 @@ fn run() -> Result<ExitCode> {
         reg.with(indicatif_layer).init();
     }
 
-    let config = Config::new(&app).map_err(|e| {
-        anyhow!(tips(
+    if let Some(Command::Cache {
+        command: CacheCommand::Clear,
+    }) = &app.command
+    {
+        return cache_clear(&app);
+    }
+
+    if app.watch {
+        return watch(app);
+    }
+
+    audit(&mut app, &mut Watched::default())
+}
+
+/// Runs `--watch`; see [`watch::watch`].
+fn watch(mut app: App) -> Result<ExitCode> {
+    if !matches!(app.format, OutputFormat::Plain) {
+        return Err(RunError::config(anyhow!(tips(
+            format!(
+                "--watch doesn't support --format={format}",
+                // NOTE: Safe unwrap, since we don't skip any variants.
+                format = app.format.to_possible_value().unwrap().get_name()
+            ),
+            &["run without --watch for machine-readable output"]
+        ))));
+    }
+
+    if app.command.is_some() {
+        return Err(RunError::config(anyhow!(
+            "--watch can't be used with subcommands"
+        )));
+    }
+
+    if let Some(input) = app.inputs.iter().find(|i| !Utf8Path::new(i).exists()) {
+        return Err(RunError::collection(anyhow!(
+            "--watch only supports local inputs, but {input} isn't a local path"
+        )));
+    }
+
+    // Unchanged inputs' findings are replayed, rather than re-audited.
+    app.cache_results = true;
+
+    watch::watch(|watched| {
+        for input in &app.inputs {
+            watched.add(Utf8Path::new(input));
+        }
+
+        audit(&mut app, watched).unwrap_or_else(|err| report_error(&err))
+    })
+}
+
+/// Runs the audits (or subcommand) that `app` selects, adding the paths
+/// that the run depends on to `watched`.
+fn audit(app: &mut App, watched: &mut Watched) -> Result<ExitCode> {
+    if let Some(path) = &app.config {
+        watched.add(path);
+    }
+
+    let config = Config::new(app).map_err(|e| {
+        RunError::config(anyhow!(tips(
             format!("failed to load config: {e:#}"),
             &[
                 "check your configuration file for errors",
                 "see: https://docs.zizmor.sh/configuration/"
             ]
-        ))
+        )))
     })?;
+    if let Some(path) = config.path() {
+        watched.add(path);
+    }
 
-    let audit_state = AuditState::new(&app, &config);
-    let registry = collect_inputs(
-        &app.inputs,
-        &app.collect,
-        app.strict_collection,
-        &audit_state,
-    )?;
+    if app.list_rules {
+        return list_rules(app.format, &config);
+    }
 
-    let mut audit_registry = AuditRegistry::new();
-    macro_rules! register_audit {
-        ($rule:path) => {{
-            // HACK: https://github.com/rust-lang/rust/issues/48067
-            use crate::audit::AuditCore as _;
-            use $rule as base;
-            match base::new(&audit_state) {
-                Ok(audit) => audit_registry.register_audit(base::ident(), Box::new(audit)),
-                Err(AuditLoadError::Skip(e)) => {
-                    tracing::info!("skipping {audit}: {e}", audit = base::ident())
-                }
-                Err(AuditLoadError::Fail(e)) => {
-                    return Err(anyhow!(tips(
-                        format!("failed to load audit: {audit}", audit = base::ident()),
-                        &[format!("{e:#}"), format!("see: {url}", url = base::url())]
-                    )));
-                }
-            }
-        }};
-    }
-
-    register_audit!(audit::artipacked::Artipacked);
-    register_audit!(audit::unsound_contains::UnsoundContains);
-    register_audit!(audit::excessive_permissions::ExcessivePermissions);
-    register_audit!(audit::dangerous_triggers::DangerousTriggers);
-    register_audit!(audit::impostor_commit::ImpostorCommit);
-    register_audit!(audit::ref_confusion::RefConfusion);
-    register_audit!(audit::use_trusted_publishing::UseTrustedPublishing);
-    register_audit!(audit::template_injection::TemplateInjection);
-    register_audit!(audit::hardcoded_container_credentials::HardcodedContainerCredentials);
-    register_audit!(audit::self_hosted_runner::SelfHostedRunner);
-    register_audit!(audit::known_vulnerable_actions::KnownVulnerableActions);
-    register_audit!(audit::unpinned_uses::UnpinnedUses);
-    register_audit!(audit::insecure_commands::InsecureCommands);
-    register_audit!(audit::github_env::GitHubEnv);
-    register_audit!(audit::cache_poisoning::CachePoisoning);
-    register_audit!(audit::secrets_inherit::SecretsInherit);
-    register_audit!(audit::bot_conditions::BotConditions);
-    register_audit!(audit::overprovisioned_secrets::OverprovisionedSecrets);
-    register_audit!(audit::unredacted_secrets::UnredactedSecrets);
-    register_audit!(audit::forbidden_uses::ForbiddenUses);
-    register_audit!(audit::obfuscation::Obfuscation);
-    register_audit!(audit::stale_action_refs::StaleActionRefs);
-    register_audit!(audit::unpinned_images::UnpinnedImages);
-
-    let mut results = FindingRegistry::new(&app, &config);
+    if let Some(rule) = app.enable.iter().find(|rule| app.disable.contains(rule)) {
+        return Err(RunError::config(anyhow!(
+            "--enable and --disable can't both be {rule}"
+        )));
+    }
+
+    // Resolve GitHub credentials, unless we're in offline mode.
+    app.gh_token = match app.offline {
+        true => None,
+        false => resolve_credentials(app, &config)?,
+    };
+    let app: &App = app;
+
+    let audit_state = AuditState::new(app, &config);
+    for path in AllowedOrgs::files(&audit_state) {
+        watched.add(Utf8Path::new(&path));
+    }
+    if app.check_config {
+        return check_config(app, &audit_state).map_err(RunError::config);
+    }
+
+    let registry = collect_inputs(app, &config, &audit_state).map_err(RunError::collection)?;
+    for path in registry.configs().iter().filter_map(Config::path) {
+        watched.add(path);
+    }
+
+    if let Some(path) = &app.emit_allowlist {
+        return emit_allowlist(path, &registry);
+    }
+
+    match &app.command {
+        Some(Command::Lock { lockfile, .. }) => return lock(&audit_state, &registry, lockfile),
+        Some(Command::VerifyLock {
+            lockfile, update, ..
+        }) => return verify_lock(app, &audit_state, &registry, lockfile, *update),
+        Some(Command::Pin {
+            lockfile, dry_run, ..
+        }) => return pin(app, &registry, lockfile, *dry_run),
+        // NOTE: Cache commands don't need any inputs, so they're handled
+        // before collecting them.
+        Some(Command::Cache { .. }) | None => {}
+    }
+
+    let audit_registry = load_audits(app, &audit_state).map_err(RunError::config)?;
+    // Inputs with their own target configuration are audited with audits
+    // loaded from that configuration, so that (e.g.) `rule_config` lookups
+    // behave identically however the configuration was found.
+    let target_audits = registry
+        .configs()
+        .iter()
+        .map(|config| load_audits(app, &audit_state.for_config(config)))
+        .collect::<Result<Vec<_>>>()
+        .map_err(RunError::config)?;
+
+    let diff = app
+        .diff
+        .as_deref()
+        .map(Diff::load)
+        .transpose()
+        .map_err(RunError::config)?;
+
+    // When updating the baseline, the previous one (if any) is irrelevant.
+    let baseline = match (&app.baseline, &diff) {
+        (Some(path), _) if !app.update_baseline => {
+            Some(Baseline::load(path).map_err(RunError::config)?)
+        }
+        (_, Some(diff)) => Some(diff.baseline()),
+        _ => None,
+    };
+
+    if let Some(path) = app.output.as_deref().filter(|p| p.as_str() != "-") {
+        if matches!(app.format, OutputFormat::TpaList)
+            && path.as_str()
+                == app
+                    .tpa_report
+                    .as_deref()
+                    .unwrap_or(output::tpa_list::DEFAULT_REPORT)
+        {
+            return Err(RunError::config(anyhow!(
+                "--output and --tpa-report can't both be {path}"
+            )));
+        }
+    }
+
+    let mut sink = Sink::new(app.output.as_deref())?;
+
+    let mut results = FindingRegistry::new(app, &config, &registry, baseline.as_ref());
+    // The time spent auditing each input, for `--summary`.
+    let mut input_times = vec![];
+
+    // Streamed formats render each input's findings as soon as it's been
+    // audited, and then release them, so that (e.g.) an org-wide run
+    // doesn't hold every finding in memory at once. Anything that needs
+    // every finding at the end of the run buffers them instead.
+    let release = app.format.streams()
+        && results.rollup_mode() == Rollup::None
+        && !(app.fix || app.summary || app.score || app.min_score.is_some())
+        && diff.is_none()
+        && app.group_by == GroupBy::File;
+    let workspace = std::env::var("GITHUB_WORKSPACE").ok();
+    // The findings to annotate, for streamed `--format=github`.
+    let mut annotations = output::github::Annotations::default();
     {
         // Note: block here so that we drop the span here at the right time.
         let span = info_span!("audit");
-        span.pb_set_length((registry.len() * audit_registry.len()) as u64);
+        span.pb_set_length(
+            registry
+                .iter_inputs()
+                .map(|(key, _)| {
+                    registry
+                        .config_index(key)
+                        .map_or(&audit_registry, |index| &target_audits[index])
+                        .len() as u64
+                })
+                .sum(),
+        );
         span.pb_set_style(
             &ProgressStyle::with_template("[{elapsed_precise}] {bar:!30.cyan/blue} {msg}").unwrap(),
         );
 
         let _guard = span.enter();
 
-        for (_, input) in registry.iter_inputs() {
-            Span::current().pb_set_message(input.key().filename());
-            for (name, audit) in audit_registry.iter_audits() {
-                results.extend(audit.audit(input).with_context(|| {
-                    format!("{name} failed on {input}", input = input.key().filename())
-                })?);
-                Span::current().pb_inc(1);
-            }
-            tracing::info!(
-                "🌈 {completed} {input}",
-                completed = "completed".green(),
-                input = input.key().presentation_path()
-            );
-        }
+        let jobs = app
+            .jobs
+            .unwrap_or_else(|| thread::available_parallelism().unwrap_or(NonZeroUsize::MIN));
+
+        let results_cache = (app.cache_results && !app.no_cache).then(|| {
+            ResultsCache::new(
+                &audit_state.cache_dir,
+                ResultsCache::settings(&audit_state, &audit_registry),
+                registry
+                    .configs()
+                    .iter()
+                    .zip(&target_audits)
+                    .map(|(config, audits)| {
+                        ResultsCache::settings(&audit_state.for_config(config), audits)
+                    })
+                    .collect(),
+            )
+        });
+
+        audit_inputs(
+            &registry,
+            &audit_registry,
+            &target_audits,
+            results_cache.as_ref(),
+            jobs,
+            &span,
+            |input, findings, elapsed| {
+                if let Some(input) = input.filter(|_| app.summary) {
+                    input_times.push((input.key().presentation_path().to_string(), elapsed));
+                }
+
+                let (nfindings, nbaselined, nignored, nsuppressed, nfiltered) = (
+                    results.findings().len(),
+                    results.baselined().len(),
+                    results.ignored().len(),
+                    results.suppressed().len(),
+                    results.filtered().len(),
+                );
+                results.extend(findings);
+
+                // JSON Lines output is always streamed, rather than waiting
+                // for every input to be audited. Rolled-up findings can only
+                // be output once every input has been audited, though.
+                if matches!(app.format, OutputFormat::Jsonl)
+                    && results.rollup_mode() == Rollup::None
+                {
+                    output::jsonl::output_findings(
+                        &mut sink,
+                        results.findings()[nfindings..]
+                            .iter()
+                            .chain(
+                                results.baselined()[nbaselined..]
+                                    .iter()
+                                    .filter(|_| diff.is_none()),
+                            )
+                            .chain(
+                                results.ignored()[nignored..]
+                                    .iter()
+                                    .filter(|f| f.ignore_reason.is_some()),
+                            )
+                            .chain(
+                                results.suppressed()[nsuppressed..]
+                                    .iter()
+                                    .filter(|_| app.show_all),
+                            )
+                            .chain(
+                                results.filtered()[nfiltered..]
+                                    .iter()
+                                    .filter(|_| app.show_filtered),
+                            ),
+                    )?;
+                } else if release && matches!(app.format, OutputFormat::Plain) {
+                    // NOTE: The progress bar is hidden while rendering, so
+                    // that it doesn't garble the (terminal-bound) findings.
+                    tracing_indicatif::suspend_tracing_indicatif(|| {
+                        output::plain::render_findings_by_file(
+                            &mut sink,
+                            app,
+                            &registry,
+                            results.findings(),
+                        )?;
+                        Ok::<_, anyhow::Error>(sink.flush()?)
+                    })?;
+                } else if release && matches!(app.format, OutputFormat::Github) {
+                    annotations.add(workspace.as_deref().map(Utf8Path::new), results.findings())?;
+                }
+                if release {
+                    results.release();
+                }
+                if let Some(input) = input {
+                    tracing::info!(
+                        "🌈 {completed} {input}",
+                        completed = "completed".green(),
+                        input = input.key().presentation_path()
+                    );
+                }
+
+                Ok(())
+            },
+        )?;
+    }
+
+    if let Some(path) = app.baseline.as_deref().filter(|_| app.update_baseline) {
+        Baseline::write(path, results.baseline_entries())?;
+        tracing::info!(
+            "wrote {count} findings to baseline {path}",
+            count = results.nbaselined()
+        );
     }
 
-    match app.format {
-        OutputFormat::Plain => output::plain::render_findings(&app, &registry, &results),
+    if app.fix {
+        return fix(app, &audit_state, sink, &registry, &results);
+    }
+
+    // NOTE: Findings are rolled up after they're baselined and fixed,
+    // since both need every finding.
+    results.rollup();
+
+    let audit_summary = app
+        .audit_summary
+        .then(|| audit_registry.statuses(&target_audits, &results));
+    let run_summary = app.summary.then(|| {
+        RunSummary::new(
+            audit_registry.statuses(&target_audits, &results),
+            &results,
+            input_times,
+        )
+    });
+
+    let score = (app.score || app.min_score.is_some())
+        .then(|| -> Result<Score> {
+            let actions = output::tpa_list::collect_actions(
+                &registry,
+                &AllowedOrgs::from_state(&audit_state)?,
+                &audit_state.resolver,
+                &results,
+                app.tpa_suppressed,
+            )?;
+            let summary = output::tpa_list::generate_summary(&actions);
+
+            Ok(Score::new(
+                &config.score_weights,
+                &score::Counts::from_run(&summary, &registry, &results),
+            ))
+        })
+        .transpose()?;
+
+    let tpa_summary = match app.format {
+        OutputFormat::Plain => {
+            if release {
+                output::plain::render_totals(&mut sink, app, &results)?;
+            } else {
+                output::plain::render_findings(&mut sink, app, &registry, &results)?;
+            }
+            if let Some(score) = &score {
+                writeln!(sink, "{score}")?;
+            }
+            if let Some(audits) = &audit_summary {
+                writeln!(sink)?;
+                output::plain::render_audit_summary(&mut sink, audits)?;
+            }
+            if let Some(run) = &run_summary {
+                writeln!(sink)?;
+                run.render(&mut sink)?;
+            }
+            None
+        }
         OutputFormat::Json | OutputFormat::JsonV1 => {
-            serde_json::to_writer_pretty(stdout(), &results.findings())?
+            // NOTE: Baselined findings are included (and marked as such),
+            // since they're still findings. Unchanged findings in a diff
+            // aren't, since only new findings are of interest. Findings
+            // ignored by the configuration's `ignores` are included (with
+            // their reasons), so that ignores can be reviewed. Findings
+            // hidden by the persona are only included (and marked as such)
+            // with `--show-all`, and likewise for findings below the
+            // severity or confidence thresholds with `--show-filtered`.
+            let findings = results
+                .findings()
+                .iter()
+                .chain(results.baselined().iter().filter(|_| diff.is_none()))
+                .chain(
+                    results
+                        .ignored()
+                        .iter()
+                        .filter(|f| f.ignore_reason.is_some()),
+                )
+                .chain(results.suppressed().iter().filter(|_| app.show_all))
+                .chain(results.filtered().iter().filter(|_| app.show_filtered))
+                .collect::<Vec<_>>();
+
+            #[derive(serde::Serialize)]
+            struct WithSummary<'a> {
+                findings: Vec<&'a Finding<'a>>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                summary: Option<&'a RunSummary>,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                score: Option<&'a Score>,
+            }
+
+            if run_summary.is_some() || score.is_some() {
+                serde_json::to_writer_pretty(
+                    &mut sink,
+                    &WithSummary {
+                        findings,
+                        summary: run_summary.as_ref(),
+                        score: score.as_ref(),
+                    },
+                )?
+            } else {
+                serde_json::to_writer_pretty(&mut sink, &findings)?
+            }
+            None
+        }
+        OutputFormat::Jsonl => {
+            if results.rollup_mode() != Rollup::None {
+                output::jsonl::output_findings(
+                    &mut sink,
+                    results
+                        .findings()
+                        .iter()
+                        .chain(results.baselined().iter().filter(|_| diff.is_none()))
+                        .chain(
+                            results
+                                .ignored()
+                                .iter()
+                                .filter(|f| f.ignore_reason.is_some()),
+                        )
+                        .chain(results.suppressed().iter().filter(|_| app.show_all))
+                        .chain(results.filtered().iter().filter(|_| app.show_filtered)),
+                )?;
+            }
+            output::jsonl::output_summary(
+                &mut sink,
+                &results,
+                audit_summary.as_deref(),
+                run_summary.as_ref(),
+            )?;
+            None
         }
         OutputFormat::Sarif => {
-            serde_json::to_writer_pretty(stdout(), &output::sarif::build(results.findings()))?
+            serde_json::to_writer_pretty(&mut sink, &output::sarif::build(&results, &config))?;
+            None
         }
-        OutputFormat::Github => output::github::output(stdout(), results.findings())?,
-        OutputFormat::TpaList => output::tpa_list::output(stdout(), results.findings())?,
-
+        OutputFormat::Github => {
+            if release {
+                // NOTE: Annotations are only output once the run is done, so
+                // that the most severe findings are the ones annotated.
+                annotations.output(&mut sink, results.nbaselined())?;
+            } else {
+                output::github::output(
+                    &mut sink,
+                    workspace.as_deref().map(Utf8Path::new),
+                    &results,
+                )?;
+            }
+            None
+        }
+        OutputFormat::Html => {
+            output::html::output(
+                &mut sink,
+                app,
+                &registry,
+                &AllowedOrgs::from_state(&audit_state)?,
+                &audit_state.resolver,
+                &results,
+            )?;
+            None
+        }
+        OutputFormat::IssueMd => {
+            output::issue_md::output(
+                &mut sink,
+                app,
+                &registry,
+                &AllowedOrgs::from_state(&audit_state)?,
+                &audit_state.resolver,
+                &results,
+            )?;
+            None
+        }
+        OutputFormat::TpaList => Some(output::tpa_list::output(
+            &mut sink,
+            app,
+            &registry,
+            &AllowedOrgs::from_state(&audit_state)?,
+            &audit_state.resolver,
+            &results,
+            score.as_ref(),
+        )?),
+        OutputFormat::TpaMarkdown => Some(output::tpa_markdown::output(
+            &mut sink,
+            app,
+            &registry,
+            &AllowedOrgs::from_state(&audit_state)?,
+            &audit_state.resolver,
+            &results,
+        )?),
+        OutputFormat::TpaCsv => Some(output::tpa_csv::output(
+            &mut sink,
+            app,
+            &registry,
+            &AllowedOrgs::from_state(&audit_state)?,
+            &audit_state.resolver,
+            &results,
+        )?),
+        OutputFormat::TpaSarif => Some(output::tpa_sarif::output(
+            &mut sink,
+            app,
+            &config,
+            &registry,
+            &AllowedOrgs::from_state(&audit_state)?,
+            &audit_state.resolver,
+            &results,
+        )?),
+        OutputFormat::TpaBadge => Some(output::tpa_badge::output(
+            &mut sink,
+            app,
+            &registry,
+            &AllowedOrgs::from_state(&audit_state)?,
+            &audit_state.resolver,
+            &BadgeThresholds::from_state(&audit_state)?,
+            &results,
+        )?),
+        OutputFormat::Cyclonedx => Some(output::cyclonedx::output(
+            &mut sink,
+            app,
+            &registry,
+            &AllowedOrgs::from_state(&audit_state)?,
+            &audit_state.resolver,
+            &results,
+        )?),
+        OutputFormat::Spdx => Some(output::spdx::output(
+            &mut sink,
+            app,
+            &registry,
+            &AllowedOrgs::from_state(&audit_state)?,
+            &audit_state.resolver,
+            &results,
+        )?),
     };
 
-    if app.no_exit_codes || matches!(app.format, OutputFormat::Sarif) {
-        Ok(ExitCode::SUCCESS)
+    let output_path = sink.path().map(Utf8Path::to_owned);
+    sink.finish()?;
+
+    if let Some(audits) = audit_summary
+        .as_deref()
+        .filter(|_| !matches!(app.format, OutputFormat::Plain | OutputFormat::Jsonl))
+    {
+        output::plain::render_audit_summary(&mut anstream::stderr(), audits)?;
+    }
+
+    if let Some(run) = run_summary.as_ref().filter(|_| {
+        !matches!(
+            app.format,
+            OutputFormat::Plain | OutputFormat::Json | OutputFormat::JsonV1 | OutputFormat::Jsonl
+        )
+    }) {
+        run.render(&mut anstream::stderr())?;
+    }
+
+    if let Some(score) = score.as_ref().filter(|_| {
+        !matches!(
+            app.format,
+            OutputFormat::Plain | OutputFormat::Json | OutputFormat::JsonV1 | OutputFormat::TpaList
+        )
+    }) {
+        eprintln!("{score}");
+    }
+
+    for skipped in audit_registry.skipped_summary() {
+        tracing::info!("{skipped}");
+    }
+
+    // NOTE: Inputs with their own configuration have their own audits,
+    // whose configuration warnings are listed after the main ones.
+    let config_warnings = std::iter::once(&audit_registry)
+        .chain(&target_audits)
+        .flat_map(AuditRegistry::config_warnings)
+        .collect::<indexmap::IndexSet<_>>();
+    for warning in config_warnings {
+        tracing::warn!("{warning}");
+    }
+
+    if results.nsuppressed() > 0 {
+        let nhidden = results.nsuppressed();
+        tracing::info!(
+            "{nhidden} finding{s} hidden at current persona",
+            s = if nhidden == 1 { "" } else { "s" }
+        );
+    }
+
+    for filtered in results.filtered_summary() {
+        tracing::info!("{filtered}");
+    }
+
+    if let Some(diff) = &diff {
+        diff.render_resolved(std::io::stderr(), &results)?;
+    }
+
+    let summary = output::plain::summary(app, &results);
+    let (exit_code, reason) = if app.no_exit_codes {
+        (ExitCode::SUCCESS, "exit codes are disabled".into())
+    } else if matches!(app.format, OutputFormat::Sarif | OutputFormat::TpaSarif) {
+        (ExitCode::SUCCESS, "SARIF output always succeeds".into())
+    } else if let Some(reason) = score
+        .as_ref()
+        .zip(app.min_score)
+        .and_then(|(score, min_score)| score.failure_reason(min_score))
+    {
+        (ExitCode::from(SCORE_FAILURE_EXIT_CODE), reason)
+    } else if let Some(summary) = &tpa_summary {
+        // The TPA formats report on actions rather than findings, so their
+        // exit code is determined by `--tpa-fail-on` instead.
+        match output::tpa_list::failure_reason(app.tpa_fail_on, summary) {
+            Some(reason) => (ExitCode::from(TPA_FAILURE_EXIT_CODE), reason),
+            None => (
+                ExitCode::SUCCESS,
+                "no actions meet the --tpa-fail-on threshold".into(),
+            ),
+        }
     } else {
-        Ok(results.into())
+        let reason = match results.failing_severity() {
+            Some(severity) => format!(
+                "failing: highest severity finding is {severity}",
+                // NOTE: Safe unwrap, since we don't skip any variants.
+                severity = severity.to_possible_value().unwrap().get_name()
+            ),
+            None if results.nfindings() == 0 => "no findings".into(),
+            None => "no findings meet the --fail-on threshold".into(),
+        };
+        (results.into(), reason)
+    };
+
+    // The output itself went elsewhere, so summarize it for the log.
+    if let Some(path) = output_path {
+        eprintln!(
+            "wrote {format} output to {path}",
+            // NOTE: Safe unwrap, since we don't skip any variants.
+            format = app.format.to_possible_value().unwrap().get_name()
+        );
+        eprintln!("{summary}");
+        eprintln!("{reason}");
     }
+
+    Ok(exit_code)
+}
+
+/// Reports a run that stopped with `err`, returning its exit code.
+fn report_error(err: &anyhow::Error) -> ExitCode {
+    eprintln!(
+        "{fatal}: no audit was performed",
+        fatal = "fatal".red().bold()
+    );
+    eprintln!("{err:?}");
+    RunError::exit_code(err)
 }
 
 fn main() -> ExitCode {
@@ -632,13 +2834,6 @@ fn main() -> ExitCode {
     // we always exit cleanly, rather than performing a hard process exit.
     match run() {
         Ok(exit) => exit,
-        Err(err) => {
-            eprintln!(
-                "{fatal}: no audit was performed",
-                fatal = "fatal".red().bold()
-            );
-            eprintln!("{err:?}");
-            ExitCode::FAILURE
-        }
+        Err(err) => report_error(&err),
     }
 }
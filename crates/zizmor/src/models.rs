@@ -17,7 +17,7 @@ use line_index::LineIndex;
 use serde_json::json;
 use terminal_link::Link;
 
-use crate::finding::{Route, SymbolicLocation};
+use crate::finding::{LocationRole, Route, Subfeature, SymbolicLocation};
 use crate::registry::{InputError, InputKey};
 use crate::utils::{
     self, ACTION_VALIDATOR, WORKFLOW_VALIDATOR, extract_expressions, from_str_with_validation,
@@ -55,7 +55,7 @@ pub(crate) trait StepCommon<'s> {
     fn strategy(&self) -> Option<&Strategy>;
 
     /// Returns a [`StepBodyCommon`] for this step.
-    fn body(&self) -> StepBodyCommon;
+    fn body(&self) -> StepBodyCommon<'_>;
 
     /// Returns a [`SymbolicLocation`] for this step.
     fn location(&self) -> SymbolicLocation<'s>;
@@ -66,6 +66,15 @@ pub(crate) trait StepCommon<'s> {
 
     /// Returns the document which contains this step.
     fn document(&self) -> &'s yamlpath::Document;
+
+    /// Returns related locations for this step's context, i.e. its
+    /// job's ID and its workflow's `on:` block, for findings to attach
+    /// with [`FindingBuilder::add_step_context`].
+    ///
+    /// Composite action steps have no such context.
+    ///
+    /// [`FindingBuilder::add_step_context`]: crate::finding::FindingBuilder::add_step_context
+    fn context_locations(&self) -> Vec<SymbolicLocation<'s>>;
 }
 
 pub(crate) trait AsDocument<'a, 'doc> {
@@ -140,13 +149,15 @@ impl Workflow {
     }
 
     /// This workflow's [`SymbolicLocation`].
-    pub(crate) fn location(&self) -> SymbolicLocation {
+    pub(crate) fn location(&self) -> SymbolicLocation<'_> {
         SymbolicLocation {
             key: &self.key,
             annotation: "this workflow".to_string(),
             link: None,
             route: Route::new(),
             kind: Default::default(),
+            role: None,
+            subfeature: None,
         }
     }
 
@@ -155,6 +166,42 @@ impl Workflow {
         Jobs::new(self)
     }
 
+    /// Returns the job with the given ID, if this workflow has one.
+    pub(crate) fn job(&self, id: &str) -> Option<Job<'_>> {
+        self.jobs
+            .get_key_value(id)
+            .map(|(id, job)| Job::new(id, job, self))
+    }
+
+    /// The names of the events that trigger this workflow, in the order
+    /// that its `on:` block lists them.
+    pub(crate) fn trigger_names(&self) -> Vec<String> {
+        let query = yamlpath::QueryBuilder::new().key("on").build();
+        let Ok(feature) = self.document.query(&query) else {
+            return vec![];
+        };
+
+        // NOTE: The extracted feature is the entire `on: ...` pair.
+        let Ok(serde_yaml::Value::Mapping(pair)) =
+            serde_yaml::from_str(self.document.extract(&feature))
+        else {
+            return vec![];
+        };
+
+        match pair.into_iter().next().map(|(_, on)| on) {
+            Some(serde_yaml::Value::String(event)) => vec![event],
+            Some(serde_yaml::Value::Sequence(events)) => events
+                .into_iter()
+                .filter_map(|event| event.as_str().map(Into::into))
+                .collect(),
+            Some(serde_yaml::Value::Mapping(events)) => events
+                .into_iter()
+                .filter_map(|(event, _)| event.as_str().map(Into::into))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
     /// Whether this workflow is triggered by pull_request_target.
     pub(crate) fn has_pull_request_target(&self) -> bool {
         match &self.on {
@@ -164,6 +211,15 @@ impl Workflow {
         }
     }
 
+    /// Whether this workflow is triggered by pull_request.
+    pub(crate) fn has_pull_request(&self) -> bool {
+        match &self.on {
+            Trigger::BareEvent(event) => *event == BareEvent::PullRequest,
+            Trigger::BareEvents(events) => events.contains(&BareEvent::PullRequest),
+            Trigger::Events(events) => !matches!(events.pull_request, OptionalBody::Missing),
+        }
+    }
+
     /// Whether this workflow is triggered by workflow_run.
     pub(crate) fn has_workflow_run(&self) -> bool {
         match &self.on {
@@ -202,6 +258,83 @@ pub(crate) trait JobExt<'doc> {
 
     /// The job's parent [`Workflow`].
     fn parent(&self) -> &'doc Workflow;
+
+    /// The IDs of the jobs that this job directly depends on, per `needs:`.
+    fn needs(&self) -> &'doc [String];
+
+    /// Resolves `context` (e.g. `needs.build.outputs.version`, or a child
+    /// of it) to the job output that it refers to.
+    ///
+    /// Returns `None` if `context` isn't a `needs.<job>.outputs.<name>`
+    /// context, or if this job doesn't directly depend on `<job>`.
+    fn needs_output(&self, context: &str) -> Option<NeedsOutput<'doc>> {
+        let mut parts = context.split('.');
+        let (Some(head), Some(id), Some(outputs), Some(name)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return None;
+        };
+
+        if !head.eq_ignore_ascii_case("needs")
+            || !outputs.eq_ignore_ascii_case("outputs")
+            || !self
+                .needs()
+                .iter()
+                .any(|need| need.eq_ignore_ascii_case(id))
+        {
+            return None;
+        }
+
+        let job = self.parent().job(id)?;
+        let definition = match &job {
+            Job::NormalJob(job) => Some(
+                job.inner
+                    .outputs
+                    .get_key_value(name)
+                    .map(|(key, value)| (key.as_str(), value.as_str()))?,
+            ),
+            // A reusable workflow's outputs are defined by the workflow
+            // itself, which isn't available here.
+            Job::ReusableWorkflowCallJob(_) => None,
+        };
+
+        Some(NeedsOutput {
+            job,
+            name: name.into(),
+            definition,
+        })
+    }
+}
+
+/// A job's output, as consumed by another job via
+/// `needs.<job>.outputs.<name>`; see [`JobExt::needs_output`].
+pub(crate) struct NeedsOutput<'doc> {
+    /// The job that produces the output.
+    pub(crate) job: Job<'doc>,
+    /// The output's name.
+    pub(crate) name: String,
+    /// The output's key and value (typically an expression) within
+    /// `outputs:`, if the producing job is a normal job.
+    definition: Option<(&'doc str, &'doc str)>,
+}
+
+impl<'doc> NeedsOutput<'doc> {
+    /// The output's value, if the producing job is a normal job.
+    pub(crate) fn value(&self) -> Option<&'doc str> {
+        self.definition.map(|(_, value)| value)
+    }
+
+    /// The output's symbolic location, i.e. its definition within the
+    /// producing job's `outputs:`, or the producing job itself.
+    pub(crate) fn location(&self) -> SymbolicLocation<'doc> {
+        match (&self.job, self.definition) {
+            (Job::NormalJob(job), Some((key, _))) => {
+                job.location().with_keys(&["outputs".into(), key.into()])
+            }
+            (Job::NormalJob(job), None) => job.location(),
+            (Job::ReusableWorkflowCallJob(job), _) => job.location(),
+        }
+    }
 }
 
 /// Represents a single "normal" GitHub Actions job.
@@ -271,6 +404,10 @@ impl<'doc> JobExt<'doc> for NormalJob<'doc> {
     fn parent(&self) -> &'doc Workflow {
         self.parent
     }
+
+    fn needs(&self) -> &'doc [String] {
+        &self.inner.needs
+    }
 }
 
 impl<'doc> Deref for NormalJob<'doc> {
@@ -317,6 +454,10 @@ impl<'doc> JobExt<'doc> for ReusableWorkflowCallJob<'doc> {
     fn parent(&self) -> &'doc Workflow {
         self.parent
     }
+
+    fn needs(&self) -> &'doc [String] {
+        &self.inner.needs
+    }
 }
 
 impl<'doc> Deref for ReusableWorkflowCallJob<'doc> {
@@ -343,6 +484,14 @@ impl<'doc> Job<'doc> {
             }
         }
     }
+
+    /// The job's unique ID (i.e., its key in the workflow's `jobs:` block).
+    pub(crate) fn id(&self) -> &'doc str {
+        match self {
+            Job::NormalJob(job) => job.id(),
+            Job::ReusableWorkflowCallJob(job) => job.id(),
+        }
+    }
 }
 
 /// An iterable container for jobs within a [`Workflow`].
@@ -591,7 +740,7 @@ impl<'doc> StepCommon<'doc> for Step<'doc> {
         self.job().strategy.as_ref()
     }
 
-    fn body(&self) -> StepBodyCommon {
+    fn body(&self) -> StepBodyCommon<'_> {
         match &self.body {
             StepBody::Uses { uses, with } => StepBodyCommon::Uses { uses, with },
             StepBody::Run {
@@ -626,6 +775,29 @@ impl<'doc> StepCommon<'doc> for Step<'doc> {
     fn document(&self) -> &'doc yamlpath::Document {
         self.workflow().as_document()
     }
+
+    fn context_locations(&self) -> Vec<SymbolicLocation<'doc>> {
+        let job = self.job();
+        let mut locations = vec![
+            job.location()
+                .with_subfeature(Subfeature::new(0, job.id()))
+                .annotated(format!("in job {id}", id = job.id()))
+                .with_role(LocationRole::Job),
+        ];
+
+        let triggers = self.workflow().trigger_names();
+        if !triggers.is_empty() {
+            locations.push(
+                self.workflow()
+                    .location()
+                    .with_keys(&["on".into()])
+                    .annotated(format!("triggered by {}", triggers.join(", ")))
+                    .with_role(LocationRole::Trigger),
+            );
+        }
+
+        locations
+    }
 }
 
 impl<'doc> Step<'doc> {
@@ -665,7 +837,7 @@ impl<'doc> Step<'doc> {
         // The steps's own `shell:` takes precedence, followed by the
         // job's default, followed by the entire workflow's default,
         // followed by the runner's default.
-        let shell = shell
+        shell
             .as_deref()
             .or_else(|| {
                 self.job()
@@ -679,9 +851,7 @@ impl<'doc> Step<'doc> {
                     .as_ref()
                     .and_then(|d| d.run.as_ref().and_then(|r| r.shell.as_deref()))
             })
-            .or_else(|| self.parent.runner_default_shell());
-
-        shell
+            .or_else(|| self.parent.runner_default_shell())
     }
 }
 
@@ -775,13 +945,15 @@ impl Action {
     }
 
     /// This actions's [`SymbolicLocation`].
-    pub(crate) fn location(&self) -> SymbolicLocation {
+    pub(crate) fn location(&self) -> SymbolicLocation<'_> {
         SymbolicLocation {
             key: &self.key,
             annotation: "this action".to_string(),
             link: None,
             route: Route::new(),
             kind: Default::default(),
+            role: None,
+            subfeature: None,
         }
     }
 
@@ -871,7 +1043,7 @@ impl<'s> StepCommon<'s> for CompositeStep<'s> {
         None
     }
 
-    fn body(&self) -> StepBodyCommon {
+    fn body(&self) -> StepBodyCommon<'_> {
         match &self.body {
             action::StepBody::Uses { uses, with } => StepBodyCommon::Uses { uses, with },
             action::StepBody::Run {
@@ -903,6 +1075,10 @@ impl<'s> StepCommon<'s> for CompositeStep<'s> {
     fn document(&self) -> &'s yamlpath::Document {
         self.action().as_document()
     }
+
+    fn context_locations(&self) -> Vec<SymbolicLocation<'s>> {
+        vec![]
+    }
 }
 
 impl<'a> CompositeStep<'a> {
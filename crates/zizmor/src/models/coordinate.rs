@@ -346,7 +346,7 @@ mod tests {
             unimplemented!()
         }
 
-        fn body(&self) -> super::StepBodyCommon {
+        fn body(&self) -> super::StepBodyCommon<'_> {
             match &self.body {
                 github_actions_models::workflow::job::StepBody::Uses { uses, with } => {
                     super::StepBodyCommon::Uses { uses, with }
@@ -376,6 +376,10 @@ mod tests {
         fn document(&self) -> &'s yamlpath::Document {
             unimplemented!()
         }
+
+        fn context_locations(&self) -> Vec<crate::finding::SymbolicLocation<'s>> {
+            unimplemented!()
+        }
     }
 
     #[test]
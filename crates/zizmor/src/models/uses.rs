@@ -1,10 +1,16 @@
 //! Extension traits for the `Uses` APIs.
 
-use std::{str::FromStr, sync::LazyLock};
+use std::{cmp::Ordering, collections::HashMap, fmt::Display, str::FromStr, sync::LazyLock};
 
-use github_actions_models::common::{RepositoryUses, Uses};
+use github_actions_expressions::Expr;
+use github_actions_models::common::{DockerUses, RepositoryUses, Uses};
+use itertools::Itertools as _;
 use regex::Regex;
 use serde::Deserialize;
+use thiserror::Error;
+
+use super::StepCommon;
+use crate::utils::extract_expressions;
 
 /// Matches all variants of [`RepositoryUsesPattern`] except `*`.
 ///
@@ -35,9 +41,9 @@ static REPOSITORY_USES_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
 });
 
 /// Represents a pattern for matching repository `uses` references.
-/// These patterns are ordered by specificity; more specific patterns
-/// should be listed first.
-#[derive(Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+/// These patterns are ordered by specificity (see
+/// [`RepositoryUsesPattern::specificity`]); more specific patterns sort first.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub(crate) enum RepositoryUsesPattern {
     /// Matches exactly `owner/repo/subpath@ref`.
     ExactWithRef {
@@ -64,6 +70,46 @@ pub(crate) enum RepositoryUsesPattern {
 }
 
 impl RepositoryUsesPattern {
+    /// Ranks this pattern's specificity, from 0 (most specific) up.
+    ///
+    /// Policy resolution depends on this ranking rather than on the order
+    /// of the variants above, so that reordering them can't change which
+    /// pattern wins.
+    pub(crate) fn specificity(&self) -> u8 {
+        match self {
+            RepositoryUsesPattern::ExactWithRef { .. } => 0,
+            RepositoryUsesPattern::ExactPath { .. } => 1,
+            RepositoryUsesPattern::ExactRepo { .. } => 2,
+            RepositoryUsesPattern::InRepo { .. } => 3,
+            RepositoryUsesPattern::InOwner(_) => 4,
+            RepositoryUsesPattern::Any => 5,
+        }
+    }
+
+    /// This pattern's components, for tie-breaking between patterns
+    /// of the same specificity.
+    fn components(&self) -> [Option<&str>; 4] {
+        match self {
+            RepositoryUsesPattern::ExactWithRef {
+                owner,
+                repo,
+                subpath,
+                git_ref,
+            } => [Some(owner), Some(repo), subpath.as_deref(), Some(git_ref)],
+            RepositoryUsesPattern::ExactPath {
+                owner,
+                repo,
+                subpath,
+            } => [Some(owner), Some(repo), Some(subpath), None],
+            RepositoryUsesPattern::ExactRepo { owner, repo }
+            | RepositoryUsesPattern::InRepo { owner, repo } => {
+                [Some(owner), Some(repo), None, None]
+            }
+            RepositoryUsesPattern::InOwner(owner) => [Some(owner), None, None, None],
+            RepositoryUsesPattern::Any => [None; 4],
+        }
+    }
+
     pub(crate) fn matches(&self, uses: &RepositoryUses) -> bool {
         match self {
             RepositoryUsesPattern::ExactWithRef {
@@ -106,8 +152,116 @@ impl RepositoryUsesPattern {
     }
 }
 
+impl Ord for RepositoryUsesPattern {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.specificity()
+            .cmp(&other.specificity())
+            .then_with(|| self.components().cmp(&other.components()))
+    }
+}
+
+impl PartialOrd for RepositoryUsesPattern {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for RepositoryUsesPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepositoryUsesPattern::ExactWithRef {
+                owner,
+                repo,
+                subpath: Some(subpath),
+                git_ref,
+            } => write!(f, "{owner}/{repo}/{subpath}@{git_ref}"),
+            RepositoryUsesPattern::ExactWithRef {
+                owner,
+                repo,
+                subpath: None,
+                git_ref,
+            } => write!(f, "{owner}/{repo}@{git_ref}"),
+            RepositoryUsesPattern::ExactPath {
+                owner,
+                repo,
+                subpath,
+            } => write!(f, "{owner}/{repo}/{subpath}"),
+            RepositoryUsesPattern::ExactRepo { owner, repo } => write!(f, "{owner}/{repo}"),
+            RepositoryUsesPattern::InRepo { owner, repo } => write!(f, "{owner}/{repo}/*"),
+            RepositoryUsesPattern::InOwner(owner) => write!(f, "{owner}/*"),
+            RepositoryUsesPattern::Any => write!(f, "*"),
+        }
+    }
+}
+
+/// An invalid [`RepositoryUsesPattern`], and why it's invalid.
+#[derive(Debug, Error)]
+#[error(
+    "invalid pattern `{pattern}`: {reason} (expected `*`, `owner/*`, `owner/repo`, \
+     `owner/repo/*`, or `owner/repo/subpath`, with an optional `@ref` after a \
+     pattern without `*`)"
+)]
+pub(crate) struct PatternError {
+    pattern: String,
+    reason: &'static str,
+}
+
+impl PatternError {
+    fn new(pattern: &str, reason: &'static str) -> Self {
+        Self {
+            pattern: pattern.into(),
+            reason,
+        }
+    }
+
+    /// Explains why `pattern` doesn't match [`REPOSITORY_USES_PATTERN`].
+    fn diagnose(pattern: &str) -> Self {
+        let (path, git_ref) = match pattern.split_once('@') {
+            Some((path, git_ref)) => (path, Some(git_ref)),
+            None => (pattern, None),
+        };
+        let mut segments = path.splitn(3, '/');
+        let owner = segments.next().unwrap_or_default();
+        let repo = segments.next();
+        let subpath = segments.next();
+
+        let reason = if pattern.is_empty() {
+            "pattern is empty"
+        } else if pattern.chars().any(char::is_whitespace) {
+            "patterns can't contain whitespace"
+        } else if path.contains("**") {
+            "`**` isn't supported, use `*` instead"
+        } else if git_ref.is_some_and(|git_ref| git_ref.is_empty()) {
+            "`@` must be followed by a ref"
+        } else if git_ref.is_some_and(|git_ref| git_ref.contains('*')) {
+            "refs can't contain `*`"
+        } else if owner.is_empty() {
+            "the owner is empty"
+        } else if owner.contains('*') {
+            "the owner can't contain `*`, use `*` alone to match everything"
+        } else if repo.is_none() {
+            "missing a repository, use `owner/*` to match every repository under an owner"
+        } else if repo.is_some_and(str::is_empty) {
+            "the repository is empty"
+        } else if repo.is_some_and(|repo| repo.contains('*') && repo != "*") {
+            "`*` must be the entire repository"
+        } else if subpath.is_some_and(|subpath| subpath.contains('*') && subpath != "*") {
+            "`*` must be the entire subpath"
+        } else if !owner
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-'))
+        {
+            "the owner can only contain letters, digits, `_`, and `-`"
+        } else {
+            "the repository can only contain letters, digits, `_`, `.`, and `-`"
+        };
+
+        Self::new(pattern, reason)
+    }
+}
+
 impl FromStr for RepositoryUsesPattern {
-    type Err = anyhow::Error;
+    type Err = PatternError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s == "*" {
@@ -116,7 +270,7 @@ impl FromStr for RepositoryUsesPattern {
 
         let caps = REPOSITORY_USES_PATTERN
             .captures(s)
-            .ok_or_else(|| anyhow::anyhow!("invalid pattern: {s}"))?;
+            .ok_or_else(|| PatternError::diagnose(s))?;
 
         let owner = &caps[1];
         let repo = &caps[2];
@@ -129,7 +283,10 @@ impl FromStr for RepositoryUsesPattern {
                 owner: owner.into(),
                 repo: repo.into(),
             }),
-            (_, "*", Some(_), _) => Err(anyhow::anyhow!("invalid pattern: {s}")),
+            (_, "*", Some(_), _) => Err(PatternError::new(
+                s,
+                "`owner/*` can't be followed by a subpath",
+            )),
             (owner, repo, Some("*"), None) => Ok(RepositoryUsesPattern::InRepo {
                 owner: owner.into(),
                 repo: repo.into(),
@@ -139,7 +296,9 @@ impl FromStr for RepositoryUsesPattern {
                 repo: repo.into(),
                 subpath: subpath.into(),
             }),
-            (_, _, Some("*"), Some(_)) => Err(anyhow::anyhow!("invalid pattern: {s}")),
+            (_, "*", _, Some(_)) | (_, _, Some("*"), Some(_)) => {
+                Err(PatternError::new(s, "patterns with `*` can't have a ref"))
+            }
             (owner, repo, subpath, Some(git_ref)) => Ok(RepositoryUsesPattern::ExactWithRef {
                 owner: owner.into(),
                 repo: repo.into(),
@@ -160,6 +319,55 @@ impl<'de> Deserialize<'de> for RepositoryUsesPattern {
     }
 }
 
+/// The kind of `git` ref in a `uses:` clause, as far as can be told
+/// from the ref alone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RefKind {
+    /// A full (40 hex character) commit SHA.
+    FullSha,
+    /// An abbreviated commit SHA. GitHub doesn't resolve these for
+    /// `uses:`, but they're still distinct from symbolic refs.
+    ShortSha,
+    /// A version-like tag, e.g. `v1` or `v1.2.3`.
+    TagLike,
+    /// Any other symbolic ref, e.g. `main`.
+    BranchLike,
+    /// A ref built from `${{ }}` expressions, e.g. `${{ matrix.ref }}`.
+    Expression,
+    /// A workflow template placeholder, e.g. `$default-branch`, which
+    /// GitHub only substitutes when the template is used.
+    Placeholder,
+}
+
+impl RefKind {
+    /// The shortest abbreviated SHA that `git` itself produces.
+    const MIN_SHORT_SHA: usize = 7;
+
+    /// Classifies the given `git` ref.
+    pub(crate) fn of(git_ref: &str) -> Self {
+        let hex = git_ref.chars().all(|c| c.is_ascii_hexdigit());
+        let version = git_ref.strip_prefix('v').unwrap_or(git_ref);
+
+        if git_ref.contains("${{") {
+            Self::Expression
+        } else if git_ref.starts_with('$') {
+            Self::Placeholder
+        } else if hex && git_ref.len() == 40 {
+            Self::FullSha
+        } else if hex && (Self::MIN_SHORT_SHA..40).contains(&git_ref.len()) {
+            Self::ShortSha
+        } else if version.starts_with(|c: char| c.is_ascii_digit())
+            && version
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+        {
+            Self::TagLike
+        } else {
+            Self::BranchLike
+        }
+    }
+}
+
 /// Useful APIs for interacting with `uses: owner/repo` clauses.
 pub(crate) trait RepositoryUsesExt {
     /// Returns whether this `uses:` clause matches the given pattern.
@@ -168,6 +376,9 @@ pub(crate) trait RepositoryUsesExt {
     /// same matching rules.
     fn matches(&self, pattern: &str) -> bool;
 
+    /// Returns the kind of this `uses:` clause's `git` ref, if it has one.
+    fn ref_kind(&self) -> Option<RefKind>;
+
     /// Returns whether this `uses:` clause has a `git` ref and, if so,
     /// whether that ref is a commit ref.
     ///
@@ -182,6 +393,13 @@ pub(crate) trait RepositoryUsesExt {
     ///
     /// Commit refs (i.e. SHA refs) are not returned.
     fn symbolic_ref(&self) -> Option<&str>;
+
+    /// Returns the host and the host-relative `owner/repo` of a
+    /// server-qualified `uses:` clause, e.g. `ghe.example.com` and
+    /// `owner/repo@v1` for `https://ghe.example.com/owner/repo@v1`.
+    ///
+    /// Returns `None` for ordinary (host-relative) `uses:` clauses.
+    fn server_qualified(&self) -> Option<(String, RepositoryUses)>;
 }
 
 impl RepositoryUsesExt for RepositoryUses {
@@ -193,11 +411,12 @@ impl RepositoryUsesExt for RepositoryUses {
         pat.matches(self)
     }
 
+    fn ref_kind(&self) -> Option<RefKind> {
+        self.git_ref.as_deref().map(RefKind::of)
+    }
+
     fn ref_is_commit(&self) -> bool {
-        match &self.git_ref {
-            Some(git_ref) => git_ref.len() == 40 && git_ref.chars().all(|c| c.is_ascii_hexdigit()),
-            None => false,
-        }
+        self.ref_kind() == Some(RefKind::FullSha)
     }
 
     fn commit_ref(&self) -> Option<&str> {
@@ -213,12 +432,137 @@ impl RepositoryUsesExt for RepositoryUses {
             _ => None,
         }
     }
+
+    fn server_qualified(&self) -> Option<(String, RepositoryUses)> {
+        // NOTE: `https://host/owner/repo` parses as the owner `https:`,
+        // an empty repo, and everything after the `//` as the subpath.
+        if !matches!(self.owner.as_str(), "https:" | "http:") || !self.repo.is_empty() {
+            return None;
+        }
+
+        let (host, path) = self.subpath.as_deref()?.split_once('/')?;
+        let mut components = path.splitn(3, '/');
+        let (Some(owner), Some(repo)) = (components.next(), components.next()) else {
+            return None;
+        };
+        if host.is_empty() || owner.is_empty() || repo.is_empty() {
+            return None;
+        }
+
+        Some((
+            host.to_ascii_lowercase(),
+            RepositoryUses {
+                owner: owner.into(),
+                repo: repo.into(),
+                subpath: components.next().map(Into::into),
+                git_ref: self.git_ref.clone(),
+            },
+        ))
+    }
+}
+
+/// Useful APIs for interacting with `uses: docker://...` clauses.
+pub(crate) trait DockerUsesExt {
+    /// Whether this `uses:` has a digest (i.e. an `@...` component)
+    /// that isn't a well-formed image digest.
+    fn digest_is_malformed(&self) -> bool;
+
+    /// Whether this `uses:` is pinned by a well-formed image digest.
+    fn digest_is_valid(&self) -> bool;
+}
+
+impl DockerUsesExt for DockerUses {
+    fn digest_is_malformed(&self) -> bool {
+        self.hash.is_some() && !self.digest_is_valid()
+    }
+
+    fn digest_is_valid(&self) -> bool {
+        self.hash.as_deref().is_some_and(is_valid_digest)
+    }
+}
+
+/// Whether `digest` is a well-formed OCI image digest, i.e. `sha256:` or
+/// `sha512:` followed by the algorithm's full-length lowercase hex encoding.
+fn is_valid_digest(digest: &str) -> bool {
+    let Some((algorithm, encoded)) = digest.split_once(':') else {
+        return false;
+    };
+
+    let len = match algorithm {
+        "sha256" => 64,
+        "sha512" => 128,
+        _ => return false,
+    };
+
+    encoded.len() == len
+        && encoded
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// Returns the default of the `name` input in `document`, which is
+/// either a workflow (with `on.workflow_call.inputs`) or an action.
+fn input_default(document: &yamlpath::Document, name: &str) -> Option<String> {
+    [
+        &["on", "workflow_call", "inputs", name, "default"][..],
+        &["inputs", name, "default"],
+    ]
+    .into_iter()
+    .find_map(|route| {
+        let query = yamlpath::QueryBuilder::new()
+            .keys(route.iter().copied())
+            .build();
+        let feature = document.query(&query).ok()?;
+
+        // NOTE: The feature is the whole `default: ...` pair.
+        serde_yaml::from_str::<HashMap<String, String>>(document.extract(&feature))
+            .ok()?
+            .remove("default")
+    })
+}
+
+/// Returns the inputs that `uses` is selected by, along with their
+/// defaults, if `uses` is a single expression (e.g. `${{ inputs.uploader }}`)
+/// that references only inputs, each of whose defaults is pinned to a
+/// commit SHA.
+pub(crate) fn pinned_input_defaults<'doc>(
+    step: &impl StepCommon<'doc>,
+    uses: &Uses,
+) -> Option<Vec<(String, Uses)>> {
+    let (expr, _) = uses
+        .expression_only()
+        .and_then(|expr| extract_expressions(expr).into_iter().next())?;
+    let expr = Expr::parse(expr.as_bare()).ok()?;
+
+    let contexts = expr.dataflow_contexts();
+    if contexts.is_empty() {
+        return None;
+    }
+
+    contexts
+        .iter()
+        .map(|context| {
+            let name = context.pop_if("inputs")?;
+            let default = input_default(step.document(), name)?;
+            let default = Uses::from_str(&default).ok()?;
+            matches!(&default, Uses::Repository(repo) if repo.ref_is_commit())
+                .then(|| (name.to_string(), default))
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|defaults| {
+            defaults
+                .into_iter()
+                .unique_by(|(name, _)| name.clone())
+                .collect()
+        })
 }
 
 /// Useful APIs for interacting with all kinds of `uses:` clauses.
 pub(crate) trait UsesExt {
     fn unpinned(&self) -> bool;
     fn unhashed(&self) -> bool;
+    fn dynamic(&self) -> bool;
+    fn expression_only(&self) -> Option<&str>;
 }
 
 impl UsesExt for Uses {
@@ -234,6 +578,8 @@ impl UsesExt for Uses {
     }
 
     /// Whether the `uses:` is unhashed (but potentially pinned with a non-hash),
+    ///
+    /// Docker `uses:` with a malformed digest count as unhashed.
     fn unhashed(&self) -> bool {
         match self {
             // TODO: Handle this case. Right now it's not very important,
@@ -242,7 +588,50 @@ impl UsesExt for Uses {
             // (since it's fully contained within the calling repo),
             Uses::Local(_) => false,
             Uses::Repository(repo) => !repo.ref_is_commit(),
-            Uses::Docker(docker) => docker.hash.is_none(),
+            Uses::Docker(docker) => !docker.digest_is_valid(),
+        }
+    }
+
+    /// Whether the `uses:` is built from `${{ }}` expressions, e.g.
+    /// `uses: ${{ matrix.owner }}/action@v1`.
+    fn dynamic(&self) -> bool {
+        let mut components = match self {
+            Uses::Local(local) => vec![Some(&local.path)],
+            Uses::Repository(repo) => vec![
+                Some(&repo.owner),
+                Some(&repo.repo),
+                repo.subpath.as_ref(),
+                repo.git_ref.as_ref(),
+            ],
+            Uses::Docker(docker) => vec![
+                docker.registry.as_ref(),
+                Some(&docker.image),
+                docker.tag.as_ref(),
+                docker.hash.as_ref(),
+            ],
+        }
+        .into_iter()
+        .flatten();
+
+        components.any(|component| component.contains("${{"))
+    }
+
+    /// The expression that the `uses:` consists of entirely, if any,
+    /// e.g. `${{ inputs.action }}` for `uses: ${{ inputs.action }}`.
+    ///
+    /// These are loaded as repository `uses:` with the expression as
+    /// their owner (see [`crate::utils::from_str_with_validation`]).
+    fn expression_only(&self) -> Option<&str> {
+        match self {
+            Uses::Repository(RepositoryUses {
+                owner,
+                repo,
+                subpath: None,
+                git_ref: None,
+            }) if repo.is_empty() && owner.starts_with("${{") && owner.ends_with("}}") => {
+                Some(owner)
+            }
+            _ => None,
         }
     }
 }
@@ -254,7 +643,9 @@ mod tests {
     use anyhow::anyhow;
     use github_actions_models::common::Uses;
 
-    use super::RepositoryUsesPattern;
+    use super::{
+        DockerUsesExt as _, RefKind, RepositoryUsesExt as _, RepositoryUsesPattern, UsesExt as _,
+    };
 
     #[test]
     fn test_repositoryusespattern_parse() {
@@ -376,6 +767,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_repositoryusespattern_parse_errors() {
+        for (pattern, reason) in [
+            ("", "pattern is empty"),
+            ("owner", "missing a repository"),
+            ("**", "`**` isn't supported"),
+            ("owner/repo/**", "`**` isn't supported"),
+            ("/", "the owner is empty"),
+            ("actions//checkout", "the repository is empty"),
+            ("foo/", "the repository is empty"),
+            ("*/foo", "the owner can't contain `*`"),
+            ("foo/b*r", "`*` must be the entire repository"),
+            ("owner/repo/sub*", "`*` must be the entire subpath"),
+            ("owner/repo/*/subpath@v1", "`*` must be the entire subpath"),
+            ("owner/*/foo", "`owner/*` can't be followed by a subpath"),
+            ("owner/*@v1", "patterns with `*` can't have a ref"),
+            ("owner/repo/*@v1", "patterns with `*` can't have a ref"),
+            ("owner/repo@*", "refs can't contain `*`"),
+            ("owner/repo@", "`@` must be followed by a ref"),
+            ("owner/re po", "patterns can't contain whitespace"),
+            ("own.er/repo", "the owner can only contain"),
+            ("owner/re$po", "the repository can only contain"),
+        ] {
+            let err = RepositoryUsesPattern::from_str(pattern).unwrap_err();
+            let message = err.to_string();
+            assert!(
+                message.starts_with(&format!("invalid pattern `{pattern}`: {reason}")),
+                "{message}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_repositoryusespattern_display() {
+        for pattern in [
+            "*",
+            "owner/*",
+            "owner/repo",
+            "owner/repo/*",
+            "owner/repo/subpath",
+            "owner/repo@v1",
+            "owner/repo/subpath@v1",
+        ] {
+            assert_eq!(
+                RepositoryUsesPattern::from_str(pattern)
+                    .unwrap()
+                    .to_string(),
+                pattern
+            );
+        }
+    }
+
     #[test]
     fn test_repositoryusespattern_ord() {
         let mut patterns = vec![
@@ -402,6 +845,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_repositoryusespattern_specificity() {
+        let ranked = [
+            "foo/bar@v1",
+            "foo/bar/baz@v1",
+            "foo/bar/baz",
+            "foo/bar",
+            "foo/bar/*",
+            "foo/*",
+            "*",
+        ]
+        .map(|pattern| RepositoryUsesPattern::from_str(pattern).unwrap());
+
+        for pair in ranked.windows(2) {
+            assert!(pair[0].specificity() <= pair[1].specificity());
+            assert!(pair[0] < pair[1], "{} < {}", pair[0], pair[1]);
+        }
+
+        let mut shuffled = ranked.iter().rev().collect::<Vec<_>>();
+        shuffled.sort();
+        assert!(shuffled.into_iter().eq(ranked.iter()));
+    }
+
     #[test]
     fn test_repositoryusespattern_matches() -> anyhow::Result<()> {
         for (uses, pattern, matches) in [
@@ -482,4 +948,158 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_uses_dynamic() {
+        for (uses, dynamic) in [
+            ("actions/checkout@v4", false),
+            ("actions/checkout", false),
+            ("./.github/actions/foo", false),
+            ("docker://ubuntu:latest", false),
+            ("${{ matrix.owner }}/checkout@v4", true),
+            ("actions/${{ matrix.repo }}@v4", true),
+            ("actions/checkout@${{ matrix.ref }}", true),
+            ("github/codeql-action/${{ matrix.step }}@v3", true),
+            ("./.github/actions/${{ matrix.action }}", true),
+            ("docker://ubuntu:${{ matrix.tag }}", true),
+        ] {
+            let uses = Uses::from_str(uses).unwrap();
+            assert_eq!(uses.dynamic(), dynamic, "{uses:?}");
+        }
+    }
+
+    #[test]
+    fn test_uses_expression_only() {
+        for (uses, expression) in [
+            ("${{ matrix.action }}/", Some("${{ matrix.action }}")),
+            ("${{ matrix.owner }}/checkout@v4", None),
+            ("actions/checkout@${{ matrix.ref }}", None),
+            ("actions/checkout", None),
+        ] {
+            let uses = Uses::from_str(uses).unwrap();
+            assert_eq!(uses.expression_only(), expression, "{uses:?}");
+        }
+    }
+
+    #[test]
+    fn test_ref_kind() {
+        for (git_ref, kind) in [
+            ("a".repeat(39).as_str(), RefKind::ShortSha),
+            (&"a".repeat(40), RefKind::FullSha),
+            (&"a".repeat(41), RefKind::BranchLike),
+            (&"a".repeat(64), RefKind::BranchLike),
+            (&"A".repeat(40), RefKind::FullSha),
+            ("8f4b7f84864484a7bf31766abe9204da3cbe65b3", RefKind::FullSha),
+            ("8f4b7f8", RefKind::ShortSha),
+            ("deadbe", RefKind::BranchLike),
+            ("v1", RefKind::TagLike),
+            ("v1.2.3", RefKind::TagLike),
+            ("1.2.3", RefKind::TagLike),
+            ("v1.2.3-rc.1", RefKind::TagLike),
+            ("main", RefKind::BranchLike),
+            ("release/v1", RefKind::BranchLike),
+            ("v", RefKind::BranchLike),
+            ("${{ matrix.ref }}", RefKind::Expression),
+            ("v${{ matrix.version }}", RefKind::Expression),
+            ("$default-branch", RefKind::Placeholder),
+        ] {
+            assert_eq!(RefKind::of(git_ref), kind, "{git_ref}");
+        }
+    }
+
+    #[test]
+    fn test_uses_unhashed() {
+        for (uses, unhashed) in [
+            (format!("actions/checkout@{}", "a".repeat(39)), true),
+            (format!("actions/checkout@{}", "a".repeat(40)), false),
+            (format!("actions/checkout@{}", "a".repeat(41)), true),
+            (format!("actions/checkout@{}", "A".repeat(40)), false),
+            ("actions/checkout@v1.2.3".into(), true),
+            ("actions/checkout@${{ matrix.ref }}".into(), true),
+        ] {
+            let parsed = Uses::from_str(&uses).unwrap();
+            assert_eq!(parsed.unhashed(), unhashed, "{uses}");
+
+            let Uses::Repository(repo) = parsed else {
+                unreachable!()
+            };
+            assert_eq!(repo.ref_is_commit(), !unhashed, "{uses}");
+        }
+    }
+
+    #[test]
+    fn test_docker_digests() {
+        let sha256 = "a".repeat(64);
+        let sha512 = "0".repeat(128);
+
+        for (uses, valid) in [
+            (format!("docker://alpine@sha256:{sha256}"), true),
+            (format!("docker://ghcr.io/org/image@sha256:{sha256}"), true),
+            (format!("docker://alpine@sha512:{sha512}"), true),
+            ("docker://alpine@sha256:abc".into(), false),
+            (format!("docker://alpine@sha256:{}", "a".repeat(63)), false),
+            (format!("docker://alpine@sha256:{}", "a".repeat(65)), false),
+            (format!("docker://alpine@sha256:{}", "A".repeat(64)), false),
+            (format!("docker://alpine@sha256:{}", "g".repeat(64)), false),
+            (format!("docker://alpine@sha512:{sha256}"), false),
+            (format!("docker://alpine@md5:{}", "a".repeat(32)), false),
+            ("docker://alpine@".into(), false),
+        ] {
+            let Uses::Docker(docker) = Uses::from_str(&uses).unwrap() else {
+                unreachable!()
+            };
+
+            assert_eq!(docker.digest_is_valid(), valid, "{uses}");
+            assert_eq!(docker.digest_is_malformed(), !valid, "{uses}");
+            assert_eq!(Uses::Docker(docker).unhashed(), !valid, "{uses}");
+        }
+
+        // No digest at all isn't malformed, just absent.
+        let Uses::Docker(docker) = Uses::from_str("docker://alpine:3.20").unwrap() else {
+            unreachable!()
+        };
+        assert!(!docker.digest_is_valid());
+        assert!(!docker.digest_is_malformed());
+    }
+
+    #[test]
+    fn test_server_qualified() {
+        let qualified = |uses: &str| {
+            let Uses::Repository(uses) = Uses::from_str(uses).unwrap() else {
+                panic!("not a repository uses: {uses}");
+            };
+            uses.server_qualified()
+                .map(|(host, uses)| (host, uses.owner, uses.repo, uses.subpath, uses.git_ref))
+        };
+
+        assert_eq!(
+            qualified("https://GHE.example.com/owner/repo@v1"),
+            Some((
+                "ghe.example.com".into(),
+                "owner".into(),
+                "repo".into(),
+                None,
+                Some("v1".into())
+            ))
+        );
+        assert_eq!(
+            qualified("https://github.com/owner/repo/sub/path"),
+            Some((
+                "github.com".into(),
+                "owner".into(),
+                "repo".into(),
+                Some("sub/path".into()),
+                None
+            ))
+        );
+
+        for uses in [
+            "owner/repo@v1",
+            "https://ghe.example.com/owner@v1",
+            "https://ghe.example.com/",
+            "ftp://ghe.example.com/owner/repo@v1",
+        ] {
+            assert_eq!(qualified(uses), None, "{uses}");
+        }
+    }
 }
@@ -0,0 +1,272 @@
+//! A very minimal OSV API client, for `--osv`.
+//!
+//! Queries are sent in batches to OSV's batch endpoint, and each query's
+//! results are cached on disk for a day. OSV's batch endpoint only
+//! returns the IDs of matching advisories, which is all we need.
+//!
+//! See: <https://google.github.io/osv.dev/post-v1-querybatch/>
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context as _, Result, anyhow};
+use reqwest::header::USER_AGENT;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use tempfile::NamedTempFile;
+use tracing::instrument;
+
+/// The OSV ecosystem for GitHub Actions.
+const ECOSYSTEM: &str = "GitHub Actions";
+
+/// The most queries that OSV's batch endpoint accepts at once.
+const MAX_BATCH_SIZE: usize = 1000;
+
+/// How long a cached query result is used for.
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub(crate) struct Package {
+    ecosystem: &'static str,
+    name: String,
+}
+
+/// A query for the advisories that affect something.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(untagged)]
+pub(crate) enum Query {
+    /// A version of an action, e.g. `actions/checkout` at `4.1.0`.
+    Version { package: Package, version: String },
+    /// A commit, in any repository.
+    Commit { commit: String },
+}
+
+impl Query {
+    /// A query for `version` of the action in the `name` repository,
+    /// e.g. `actions/checkout`.
+    pub(crate) fn version(name: &str, version: &str) -> Self {
+        Self::Version {
+            package: Package {
+                ecosystem: ECOSYSTEM,
+                name: name.into(),
+            },
+            version: version.into(),
+        }
+    }
+
+    pub(crate) fn commit(commit: &str) -> Self {
+        Self::Commit {
+            commit: commit.to_ascii_lowercase(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct BatchRequest<'a> {
+    queries: &'a [Query],
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    results: Vec<BatchResult>,
+}
+
+#[derive(Deserialize)]
+struct BatchResult {
+    #[serde(default)]
+    vulns: Vec<Vuln>,
+}
+
+#[derive(Deserialize)]
+struct Vuln {
+    id: String,
+}
+
+pub(crate) struct Client {
+    api_base: String,
+    http: reqwest::Client,
+    cache_dir: PathBuf,
+    /// The results of every query made so far, shared across inputs.
+    seen: Mutex<HashMap<Query, Vec<String>>>,
+}
+
+impl Client {
+    pub(crate) fn new(cache_dir: &Path) -> Self {
+        Self::with_api_base("https://api.osv.dev/v1", cache_dir)
+    }
+
+    fn with_api_base(api_base: &str, cache_dir: &Path) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("couldn't build OSV client?");
+
+        Self {
+            api_base: api_base.into(),
+            http,
+            cache_dir: cache_dir.join("osv"),
+            seen: Default::default(),
+        }
+    }
+
+    /// The file that `query`'s results are cached in.
+    fn cache_path(&self, query: &Query) -> PathBuf {
+        // NOTE: Safe unwrap, since queries are plain data.
+        let name = format!(
+            "{:x}",
+            Sha256::digest(serde_json::to_string(query).unwrap())
+        );
+        self.cache_dir.join(format!("{name}.json"))
+    }
+
+    /// Loads `query`'s cached results, if they're fresh enough.
+    fn load(&self, query: &Query) -> Option<Vec<String>> {
+        let path = self.cache_path(query);
+        let age = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+
+        if age > CACHE_TTL {
+            return None;
+        }
+
+        fs::read(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_slice(&contents).ok())
+    }
+
+    /// Caches `query`'s results.
+    fn store(&self, query: &Query, ids: &[String]) -> Result<()> {
+        fs::create_dir_all(&self.cache_dir)?;
+
+        let mut writer = BufWriter::new(NamedTempFile::new_in(&self.cache_dir)?);
+        serde_json::to_writer(&mut writer, ids)?;
+        writer.into_inner()?.persist(self.cache_path(query))?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(queries = queries.len()))]
+    #[tokio::main]
+    async fn query_batch(&self, queries: &[Query]) -> Result<Vec<Vec<String>>> {
+        let url = format!("{api_base}/querybatch", api_base = self.api_base);
+
+        let response: BatchResponse = self
+            .http
+            .post(url)
+            .header(USER_AGENT, "zizmor")
+            .json(&BatchRequest { queries })
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if response.results.len() != queries.len() {
+            return Err(anyhow!(
+                "expected {expected} results from OSV, got {actual}",
+                expected = queries.len(),
+                actual = response.results.len()
+            ));
+        }
+
+        Ok(response
+            .results
+            .into_iter()
+            .map(|result| result.vulns.into_iter().map(|vuln| vuln.id).collect())
+            .collect())
+    }
+
+    /// Returns the IDs of the advisories that affect each of `queries`.
+    ///
+    /// Only queries without fresh cached results are sent to OSV, in
+    /// batches of at most [`MAX_BATCH_SIZE`].
+    pub(crate) fn advisories(&self, queries: &[Query]) -> Result<HashMap<Query, Vec<String>>> {
+        let mut results = HashMap::new();
+        let mut pending = vec![];
+
+        {
+            let seen = self.seen.lock().unwrap();
+            for query in queries {
+                if results.contains_key(query) || pending.contains(query) {
+                    continue;
+                }
+
+                match seen.get(query).cloned().or_else(|| self.load(query)) {
+                    Some(ids) => {
+                        results.insert(query.clone(), ids);
+                    }
+                    None => pending.push(query.clone()),
+                }
+            }
+        }
+
+        for batch in pending.chunks(MAX_BATCH_SIZE) {
+            let batch_results = self
+                .query_batch(batch)
+                .context("couldn't query OSV for advisories")?;
+
+            for (query, ids) in batch.iter().zip(batch_results) {
+                if let Err(err) = self.store(query, &ids) {
+                    tracing::debug!("couldn't cache OSV results: {err:#}");
+                }
+                results.insert(query.clone(), ids);
+            }
+        }
+
+        self.seen.lock().unwrap().extend(
+            results
+                .iter()
+                .map(|(query, ids)| (query.clone(), ids.clone())),
+        );
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Client, Query};
+
+    #[test]
+    fn test_query_serialization() {
+        assert_eq!(
+            serde_json::to_string(&Query::version("actions/checkout", "4.1.0")).unwrap(),
+            r#"{"package":{"ecosystem":"GitHub Actions","name":"actions/checkout"},"version":"4.1.0"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Query::commit("ABCDEF")).unwrap(),
+            r#"{"commit":"abcdef"}"#
+        );
+    }
+
+    #[test]
+    fn test_advisories_cached() {
+        let cache_dir = tempfile::tempdir().unwrap();
+
+        // Nothing listens here, so any query that isn't cached fails.
+        let client = Client::with_api_base("http://127.0.0.1:9", cache_dir.path());
+
+        let affected = Query::version("example/action", "1.0.0");
+        let unaffected = Query::commit("0123456789abcdef0123456789abcdef01234567");
+        client.store(&affected, &["GHSA-test".into()]).unwrap();
+        client.store(&unaffected, &[]).unwrap();
+
+        let results = client
+            .advisories(&[affected.clone(), unaffected.clone(), affected.clone()])
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[&affected], ["GHSA-test"]);
+        assert!(results[&unaffected].is_empty());
+
+        let uncached = Query::version("example/action", "2.0.0");
+        assert!(client.advisories(&[uncached]).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! CycloneDX output for the third-party action inventory.
+//!
+//! Each unique action, reusable workflow, and container image is a
+//! component of a CycloneDX 1.5 JSON BOM, identified by its package URL:
+//! `pkg:github/owner/repo@ref` for actions and reusable workflows, and
+//! `pkg:docker/namespace/image@tag` for images. The files that use each
+//! component are recorded as its evidence, and zizmor's third-party and
+//! pinning classifications as its properties.
+
+use std::io;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::tpa_list::{
+    Action, ActionKind, Summary, collect_actions, generate_summary, group_by_purl,
+};
+use crate::App;
+use crate::audit::unpinned_uses::AllowedOrgs;
+use crate::registry::{FindingRegistry, InputRegistry};
+use crate::resolver::UsesResolver;
+use crate::utils::uuid_v5;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Bom {
+    bom_format: &'static str,
+    spec_version: &'static str,
+    serial_number: String,
+    version: u32,
+    metadata: Metadata,
+    components: Vec<Component>,
+}
+
+#[derive(Serialize)]
+struct Metadata {
+    tools: Tools,
+}
+
+#[derive(Serialize)]
+struct Tools {
+    components: Vec<Tool>,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+struct Component {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(rename = "bom-ref")]
+    bom_ref: String,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    purl: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hashes: Vec<Hash>,
+    properties: Vec<Property>,
+    evidence: Evidence,
+}
+
+#[derive(Serialize)]
+struct Hash {
+    alg: &'static str,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct Property {
+    name: &'static str,
+    value: String,
+}
+
+#[derive(Serialize)]
+struct Evidence {
+    occurrences: Vec<Occurrence>,
+}
+
+#[derive(Serialize)]
+struct Occurrence {
+    location: String,
+}
+
+/// The hash that the given action is pinned to, if it's pinned.
+fn pinned_hash(action: &Action) -> Option<Hash> {
+    let content = action.pinned_digest()?;
+    let alg = match content.len() {
+        40 => "SHA-1",
+        64 => "SHA-256",
+        _ => return None,
+    };
+
+    Some(Hash {
+        alg,
+        content: content.to_ascii_lowercase(),
+    })
+}
+
+/// A deterministic serial number for a BOM with the given components,
+/// as a version 5 UUID named by their package URLs and occurrences.
+fn serial_number(components: &[Component]) -> String {
+    let mut name = String::new();
+    for component in components {
+        name.push_str(&component.purl);
+        for occurrence in &component.evidence.occurrences {
+            name.push(' ');
+            name.push_str(&occurrence.location);
+        }
+        name.push('\n');
+    }
+
+    format!("urn:uuid:{}", uuid_v5(&name))
+}
+
+/// Builds a BOM from the given actions, with one component per
+/// unique package URL.
+fn build(actions: &[Action]) -> Bom {
+    let components = group_by_purl(actions)
+        .into_iter()
+        .map(|(purl, uses)| {
+            // NOTE: Every use of a package URL has the same identity.
+            let action = uses[0];
+
+            let mut occurrences: Vec<Occurrence> = vec![];
+            for location in uses.iter().map(|action| action.qualified_path()) {
+                if !occurrences.iter().any(|o| o.location == location) {
+                    occurrences.push(Occurrence { location });
+                }
+            }
+
+            Component {
+                kind: match action.kind {
+                    ActionKind::Docker => "container",
+                    _ => "application",
+                },
+                bom_ref: purl.clone(),
+                name: action.package_name(),
+                version: action.git_ref.clone(),
+                purl,
+                hashes: pinned_hash(action).into_iter().collect(),
+                properties: vec![
+                    Property {
+                        name: "zizmor:kind",
+                        value: action.kind.as_str().into(),
+                    },
+                    Property {
+                        name: "zizmor:third-party",
+                        value: action.third_party.to_string(),
+                    },
+                    Property {
+                        name: "zizmor:pinned",
+                        value: action.pinned_to_sha.to_string(),
+                    },
+                ],
+                evidence: Evidence { occurrences },
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Bom {
+        bom_format: "CycloneDX",
+        spec_version: "1.5",
+        serial_number: serial_number(&components),
+        version: 1,
+        metadata: Metadata {
+            tools: Tools {
+                components: vec![Tool {
+                    kind: "application",
+                    name: "zizmor",
+                    version: env!("CARGO_PKG_VERSION"),
+                }],
+            },
+        },
+        components,
+    }
+}
+
+/// Output the TPA inventory as a CycloneDX BOM.
+pub(crate) fn output(
+    mut sink: impl io::Write,
+    app: &App,
+    registry: &InputRegistry,
+    allowed_orgs: &AllowedOrgs,
+    resolver: &UsesResolver,
+    results: &FindingRegistry,
+) -> Result<Summary> {
+    let actions = collect_actions(
+        registry,
+        allowed_orgs,
+        resolver,
+        results,
+        app.tpa_suppressed,
+    )?;
+
+    serde_json::to_writer_pretty(&mut sink, &build(&actions))?;
+
+    Ok(generate_summary(&actions))
+}
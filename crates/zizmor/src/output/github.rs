@@ -2,11 +2,52 @@
 //!
 //! See: <https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions>
 
+use std::borrow::Cow;
 use std::io;
 
 use anyhow::Result;
+use camino::Utf8Path;
+use clap::ValueEnum as _;
 
-use crate::{Severity, finding::Finding};
+use crate::{
+    Severity,
+    finding::{Confidence, Finding},
+    registry::FindingRegistry,
+};
+
+/// The maximum number of annotations that GitHub displays for a single step.
+const MAX_ANNOTATIONS: usize = 10;
+
+/// Escapes `data` for use as a workflow command's message.
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes `property` for use as a workflow command's property value.
+fn escape_property(property: &str) -> String {
+    escape_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// Returns `path` relative to `workspace`, if it's within it.
+///
+/// Inputs are usually given relative to the workspace already, in which
+/// case they're returned as-is (minus any leading `./`).
+fn workspace_relative<'a>(path: &'a str, workspace: Option<&Utf8Path>) -> Cow<'a, str> {
+    let path = Utf8Path::new(path);
+
+    let relative = workspace
+        .and_then(|workspace| path.strip_prefix(workspace).ok())
+        .unwrap_or(path);
+
+    match relative.strip_prefix("./") {
+        Ok(relative) => Cow::Owned(relative.to_string()),
+        Err(_) => Cow::Borrowed(relative.as_str()),
+    }
+}
 
 impl Severity {
     /// Converts a `Severity` to a GitHub Actions command command.
@@ -23,42 +64,204 @@ impl Severity {
 }
 
 impl Finding<'_> {
-    fn format_command(&self, sink: &mut impl io::Write) -> Result<()> {
+    fn format_command(
+        &self,
+        sink: &mut impl io::Write,
+        workspace: Option<&Utf8Path>,
+    ) -> Result<()> {
         let primary = self
             .visible_locations()
             .find(|l| l.symbolic.is_primary())
             .unwrap();
 
-        // NOTE: We intentionally only use the start line, since our spans
-        // sometimes end at EOF and GitHub's annotations don't handle that
-        // gracefully.
-        let filepath = primary.symbolic.key.sarif_path();
-        let start_line = primary.concrete.location.start_point.row + 1;
-        let title = self.ident;
+        let mut path = primary.symbolic.key.presentation_path();
+        let mut filename = primary.symbolic.key.filename();
+        let mut start_line = primary.concrete.location.start_point.row + 1;
+        let mut end_line = primary.concrete.region_end().row + 1;
+        let mut annotation = primary.symbolic.annotation.as_str();
+
+        // A primary external location (e.g. a configuration entry) is
+        // annotated instead, when its position is known.
+        if let Some(external) = self.primary_external_location()
+            && let (Some(external_path), Some(point)) = (&external.path, &external.point)
+        {
+            path = external_path;
+            filename = Utf8Path::new(external_path)
+                .file_name()
+                .unwrap_or(external_path);
+            start_line = point.row + 1;
+            end_line = start_line;
+            annotation = &external.annotation;
+        }
+
+        let filepath = workspace_relative(path, workspace);
+        let title = self.qualified_ident();
 
         let message = format!(
-            "{filename}:{start_line}: {desc}: {annotation}",
-            filename = primary.symbolic.key.filename(),
+            "{filename}:{start_line}: {desc}: {annotation} ({confidence} confidence)",
             desc = self.desc,
-            annotation = primary.symbolic.annotation,
+            confidence = self
+                .determinations
+                .confidence
+                .to_possible_value()
+                .unwrap()
+                .get_name(),
         );
 
         writeln!(
             sink,
-            "::{} file={filepath},line={start_line},title={title}::{message}",
-            self.determinations.severity.as_github_command()
+            "::{command} file={filepath},line={start_line},endLine={end_line},title={title}::{message}",
+            command = self.determinations.severity.as_github_command(),
+            filepath = escape_property(&filepath),
+            title = escape_property(&title),
+            message = escape_data(&message),
         )?;
 
         Ok(())
     }
 }
 
-pub(crate) fn output(sink: impl io::Write, findings: &[Finding]) -> Result<()> {
-    let mut sink = sink;
+/// Output the given findings as workflow commands, with paths relative to
+/// `workspace` (i.e. `$GITHUB_WORKSPACE`) if given.
+///
+/// GitHub only displays the first few annotations for each step, so only
+/// the most severe (and then most confident) findings are annotated, and
+/// the rest are summarized. Baselined findings are never annotated, only
+/// counted.
+pub(crate) fn output(
+    sink: impl io::Write,
+    workspace: Option<&Utf8Path>,
+    results: &FindingRegistry,
+) -> Result<()> {
+    let mut annotations = Annotations::default();
+    annotations.add(workspace, results.findings())?;
+    annotations.output(sink, results.baselined().len())
+}
+
+/// The findings to annotate, i.e. the most severe (and then most
+/// confident) ones seen so far, up to [`MAX_ANNOTATIONS`] of them.
+///
+/// Each is kept as its rendered workflow command, so that findings
+/// streamed per input can be released once they've been added.
+#[derive(Default)]
+pub(crate) struct Annotations {
+    /// The rendered commands, ordered by their findings' severity and then
+    /// confidence (descending), and otherwise in the order they were found.
+    commands: Vec<((Severity, Confidence), Vec<u8>)>,
+    /// The number of findings added, annotated or not.
+    total: usize,
+}
+
+impl Annotations {
+    /// Adds `findings`, with paths relative to `workspace` if given.
+    pub(crate) fn add(&mut self, workspace: Option<&Utf8Path>, findings: &[Finding]) -> Result<()> {
+        for finding in findings {
+            self.total += 1;
+
+            let rank = (
+                finding.determinations.severity,
+                finding.determinations.confidence,
+            );
+            let idx = self.commands.partition_point(|(other, _)| *other >= rank);
+            if idx >= MAX_ANNOTATIONS {
+                continue;
+            }
 
-    for finding in findings {
-        finding.format_command(&mut sink)?;
+            let mut command = vec![];
+            finding.format_command(&mut command, workspace)?;
+            self.commands.insert(idx, (rank, command));
+            self.commands.truncate(MAX_ANNOTATIONS);
+        }
+
+        Ok(())
+    }
+
+    /// Outputs the annotations, followed by a summary of the findings that
+    /// weren't annotated: those past the annotation limit, and the
+    /// `baselined` ones.
+    pub(crate) fn output(self, mut sink: impl io::Write, baselined: usize) -> Result<()> {
+        for (_, command) in &self.commands {
+            sink.write_all(command)?;
+        }
+
+        output_unannotated(sink, self.total, baselined)
+    }
+}
+
+/// Summarizes the findings that weren't annotated: those past the
+/// annotation limit, out of `total` findings, and the `baselined` ones.
+fn output_unannotated(mut sink: impl io::Write, total: usize, baselined: usize) -> Result<()> {
+    let truncated = total.saturating_sub(MAX_ANNOTATIONS);
+    if truncated > 0 {
+        writeln!(
+            sink,
+            "{truncated} more {findings} not annotated (GitHub only displays {MAX_ANNOTATIONS} annotations per step); use another output format to see all findings",
+            findings = if truncated == 1 {
+                "finding"
+            } else {
+                "findings"
+            },
+        )?;
+    }
+
+    if baselined > 0 {
+        writeln!(
+            sink,
+            "{baselined} baselined {findings} not annotated",
+            findings = if baselined == 1 {
+                "finding"
+            } else {
+                "findings"
+            },
+        )?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use camino::Utf8Path;
+
+    use super::{escape_data, escape_property, workspace_relative};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape_data("100% sure"), "100%25 sure");
+        assert_eq!(
+            escape_data("line one\nline two\r\n"),
+            "line one%0Aline two%0D%0A"
+        );
+        assert_eq!(escape_data("a: b, c"), "a: b, c");
+
+        assert_eq!(escape_property("a: b, c"), "a%3A b%2C c");
+        assert_eq!(escape_property("50%\n"), "50%25%0A");
+    }
+
+    #[test]
+    fn test_workspace_relative() {
+        let workspace = Utf8Path::new("/home/runner/work/repo/repo");
+
+        for (path, workspace, expected) in [
+            (".github/workflows/ci.yml", None, ".github/workflows/ci.yml"),
+            (
+                "./.github/workflows/ci.yml",
+                None,
+                ".github/workflows/ci.yml",
+            ),
+            (
+                "/home/runner/work/repo/repo/.github/workflows/ci.yml",
+                Some(workspace),
+                ".github/workflows/ci.yml",
+            ),
+            (
+                ".github/workflows/ci.yml",
+                Some(workspace),
+                ".github/workflows/ci.yml",
+            ),
+            ("/elsewhere/ci.yml", Some(workspace), "/elsewhere/ci.yml"),
+        ] {
+            assert_eq!(workspace_relative(path, workspace), expected);
+        }
+    }
+}
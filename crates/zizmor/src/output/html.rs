@@ -0,0 +1,424 @@
+//! HTML output, as a single self-contained report.
+//!
+//! The report has no external assets (its styles and scripts are inline),
+//! so that it can be attached to a CI run as an artifact and opened offline.
+
+use std::collections::BTreeMap;
+use std::io;
+
+use anyhow::Result;
+
+use super::tpa_list::{Action, collect_actions, generate_summary};
+use crate::App;
+use crate::audit::unpinned_uses::AllowedOrgs;
+use crate::finding::{Confidence, Finding, Severity};
+use crate::registry::{FindingRegistry, InputRegistry};
+use crate::resolver::UsesResolver;
+
+const STYLE: &str = r#"
+body { font-family: system-ui, sans-serif; margin: 2em; color: #1f2328; }
+h1 { font-size: 1.5em; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1.5em; }
+th, td { border: 1px solid #d0d7de; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f6f8fa; }
+th[data-sort] { cursor: pointer; }
+tr.summary { cursor: pointer; }
+tr.summary:hover { background: #f6f8fa; }
+pre { margin: 0.3em 0 0.8em; padding: 0.5em; background: #f6f8fa; overflow-x: auto; }
+.tabs button { font: inherit; padding: 0.4em 1em; }
+.tabs button[aria-selected="true"] { font-weight: bold; }
+.filters { margin-bottom: 1em; }
+.severity-high { color: #cf222e; }
+.severity-medium { color: #bc4c00; }
+.severity-low { color: #9a6700; }
+"#;
+
+const SCRIPT: &str = r#"
+document.querySelectorAll(".tabs button").forEach((tab) => {
+  tab.addEventListener("click", () => {
+    document.querySelectorAll(".tabs button").forEach((other) => {
+      other.setAttribute("aria-selected", other === tab);
+      document.getElementById(other.dataset.panel).hidden = other !== tab;
+    });
+  });
+});
+
+document.querySelectorAll("tr.summary").forEach((row) => {
+  row.addEventListener("click", () => {
+    const detail = row.nextElementSibling;
+    detail.hidden = !detail.hidden;
+  });
+});
+
+const findings = document.getElementById("findings-table");
+if (findings) {
+  const text = document.getElementById("filter-text");
+  const severity = document.getElementById("filter-severity");
+  const rule = document.getElementById("filter-rule");
+
+  function filter() {
+    const needle = text.value.toLowerCase();
+    findings.querySelectorAll("tbody.finding").forEach((finding) => {
+      finding.hidden =
+        (severity.value !== "" && finding.dataset.severity !== severity.value) ||
+        (rule.value !== "" && finding.dataset.rule !== rule.value) ||
+        !finding.querySelector("tr.summary").textContent.toLowerCase().includes(needle);
+    });
+  }
+  [text, severity, rule].forEach((input) => input.addEventListener("input", filter));
+
+  findings.querySelectorAll("th[data-sort]").forEach((header, column) => {
+    header.addEventListener("click", () => {
+      const descending = header.dataset.order !== "descending";
+      header.dataset.order = descending ? "descending" : "ascending";
+      const key = (finding) => {
+        const cell = finding.querySelector("tr.summary").children[column];
+        return cell.dataset.value ?? cell.textContent;
+      };
+      const numeric = header.dataset.sort === "number";
+      [...findings.querySelectorAll("tbody.finding")]
+        .sort((a, b) => {
+          const order = numeric ? key(a) - key(b) : key(a).localeCompare(key(b));
+          return descending ? -order : order;
+        })
+        .forEach((finding) => findings.appendChild(finding));
+    });
+  });
+}
+"#;
+
+/// Escapes `text` for use in HTML text and (quoted) attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Unknown => "unknown",
+        Severity::Informational => "informational",
+        Severity::Low => "low",
+        Severity::Medium => "medium",
+        Severity::High => "high",
+    }
+}
+
+fn confidence_name(confidence: Confidence) -> &'static str {
+    match confidence {
+        Confidence::Unknown => "unknown",
+        Confidence::Low => "low",
+        Confidence::Medium => "medium",
+        Confidence::High => "high",
+    }
+}
+
+const SEVERITIES: [Severity; 5] = [
+    Severity::High,
+    Severity::Medium,
+    Severity::Low,
+    Severity::Informational,
+    Severity::Unknown,
+];
+
+fn render_summary(sink: &mut impl io::Write, findings: &[Finding]) -> Result<()> {
+    let mut by_rule = BTreeMap::<_, usize>::new();
+    for finding in findings {
+        *by_rule.entry(finding.qualified_ident()).or_default() += 1;
+    }
+
+    writeln!(sink, "<h2>Summary</h2>")?;
+    writeln!(sink, "<table id=\"summary-severities\">")?;
+    writeln!(sink, "<tr><th>Severity</th><th>Findings</th></tr>")?;
+    for severity in SEVERITIES {
+        writeln!(
+            sink,
+            "<tr><td class=\"severity-{name}\">{name}</td><td>{count}</td></tr>",
+            name = severity_name(severity),
+            count = findings
+                .iter()
+                .filter(|f| f.determinations.severity == severity)
+                .count(),
+        )?;
+    }
+    writeln!(sink, "</table>")?;
+
+    if !by_rule.is_empty() {
+        writeln!(sink, "<table id=\"summary-rules\">")?;
+        writeln!(sink, "<tr><th>Rule</th><th>Findings</th></tr>")?;
+        for (rule, count) in &by_rule {
+            writeln!(
+                sink,
+                "<tr><td>{rule}</td><td>{count}</td></tr>",
+                rule = escape(rule)
+            )?;
+        }
+        writeln!(sink, "</table>")?;
+    }
+
+    Ok(())
+}
+
+fn render_finding(sink: &mut impl io::Write, finding: &Finding) -> Result<()> {
+    // NOTE: Safe unwrap, since FindingBuilder::build ensures a primary location.
+    let primary = finding
+        .visible_locations()
+        .find(|l| l.symbolic.is_primary())
+        .unwrap();
+    let severity = finding.determinations.severity;
+    let confidence = finding.determinations.confidence;
+    let ident = finding.qualified_ident();
+    let mut file = primary.symbolic.key.presentation_path();
+    let mut line = primary.concrete.location.start_point.row + 1;
+    let mut annotation = primary.symbolic.annotation.as_str();
+    // A primary external location (e.g. a configuration entry) is listed
+    // instead, when its position is known.
+    if let Some(external) = finding.primary_external_location()
+        && let (Some(path), Some(point)) = (&external.path, &external.point)
+    {
+        file = path;
+        line = point.row + 1;
+        annotation = &external.annotation;
+    }
+
+    writeln!(
+        sink,
+        "<tbody class=\"finding\" data-severity=\"{severity}\" data-rule=\"{rule}\">",
+        severity = severity_name(severity),
+        rule = escape(&ident),
+    )?;
+    writeln!(
+        sink,
+        "<tr class=\"summary\"><td><a href=\"{url}\">{rule}</a></td>\
+         <td class=\"severity-{severity}\" data-value=\"{rank}\">{severity}</td>\
+         <td data-value=\"{confidence_rank}\">{confidence}</td>\
+         <td>{file}</td><td data-value=\"{line}\">{line}</td><td>{annotation}</td></tr>",
+        url = escape(finding.url),
+        rule = escape(&ident),
+        severity = severity_name(severity),
+        rank = severity as u8,
+        confidence = confidence_name(confidence),
+        confidence_rank = confidence as u8,
+        file = escape(file),
+        annotation = escape(annotation),
+    )?;
+
+    writeln!(sink, "<tr class=\"detail\" hidden><td colspan=\"6\">")?;
+    writeln!(sink, "<p>{desc}</p>", desc = escape(finding.desc))?;
+    for location in finding
+        .visible_locations()
+        .filter(|l| !l.symbolic.is_context())
+    {
+        writeln!(
+            sink,
+            "<div>{file}:{line}: {annotation}</div>",
+            file = escape(location.symbolic.key.presentation_path()),
+            line = location.concrete.location.start_point.row + 1,
+            annotation = escape(&location.symbolic.annotation),
+        )?;
+        writeln!(
+            sink,
+            "<pre>{feature}</pre>",
+            feature = escape(location.concrete.feature)
+        )?;
+    }
+    let context = finding
+        .visible_locations()
+        .filter(|l| l.symbolic.is_context())
+        .map(|l| escape(&l.symbolic.annotation))
+        .collect::<Vec<_>>();
+    if !context.is_empty() {
+        writeln!(sink, "<div>{}</div>", context.join(", "))?;
+    }
+    for location in &finding.external_locations {
+        writeln!(sink, "<div>{}</div>", escape(&location.render()))?;
+    }
+    writeln!(sink, "</td></tr>")?;
+    writeln!(sink, "</tbody>")?;
+
+    Ok(())
+}
+
+fn render_findings(sink: &mut impl io::Write, findings: &[Finding]) -> Result<()> {
+    writeln!(sink, "<h2>Findings</h2>")?;
+    if findings.is_empty() {
+        writeln!(sink, "<p>No findings to report.</p>")?;
+        return Ok(());
+    }
+
+    let rules = findings
+        .iter()
+        .map(|f| f.qualified_ident())
+        .collect::<std::collections::BTreeSet<_>>();
+
+    writeln!(sink, "<div class=\"filters\">")?;
+    writeln!(
+        sink,
+        "<input id=\"filter-text\" type=\"search\" placeholder=\"Filter findings\">"
+    )?;
+    writeln!(
+        sink,
+        "<select id=\"filter-severity\"><option value=\"\">All severities</option>"
+    )?;
+    for severity in SEVERITIES {
+        writeln!(sink, "<option>{}</option>", severity_name(severity))?;
+    }
+    writeln!(sink, "</select>")?;
+    writeln!(
+        sink,
+        "<select id=\"filter-rule\"><option value=\"\">All rules</option>"
+    )?;
+    for rule in &rules {
+        writeln!(sink, "<option>{}</option>", escape(rule))?;
+    }
+    writeln!(sink, "</select>")?;
+    writeln!(sink, "</div>")?;
+
+    writeln!(sink, "<table id=\"findings-table\">")?;
+    writeln!(
+        sink,
+        "<thead><tr><th data-sort=\"text\">Rule</th><th data-sort=\"number\">Severity</th>\
+         <th data-sort=\"number\">Confidence</th>\
+         <th data-sort=\"text\">File</th><th data-sort=\"number\">Line</th>\
+         <th data-sort=\"text\">Annotation</th></tr></thead>"
+    )?;
+    for finding in findings {
+        render_finding(sink, finding)?;
+    }
+    writeln!(sink, "</table>")?;
+
+    Ok(())
+}
+
+fn render_actions(sink: &mut impl io::Write, actions: &[Action]) -> Result<()> {
+    let summary = generate_summary(actions);
+
+    writeln!(sink, "<h2>Third-party actions</h2>")?;
+    writeln!(
+        sink,
+        "<p>{total} actions: {unpinned} unpinned third-party, {pinned} pinned \
+         third-party, {official} official.</p>",
+        total = summary.total_actions,
+        unpinned = summary.unpinned_third_party,
+        pinned = summary.pinned_third_party,
+        official = summary.official_actions,
+    )?;
+
+    writeln!(sink, "<table id=\"actions-table\">")?;
+    writeln!(
+        sink,
+        "<thead><tr><th>Action</th><th>Ref</th><th>Kind</th><th>Third-party</th>\
+         <th>Pinned</th><th>File</th><th>Line</th></tr></thead>"
+    )?;
+    writeln!(sink, "<tbody>")?;
+    for action in actions.iter().filter(|action| !action.suppressed) {
+        writeln!(
+            sink,
+            "<tr><td>{name}</td><td>{git_ref}</td><td>{kind}</td><td>{third_party}</td>\
+             <td>{pinned}</td><td>{file}</td><td>{line}</td></tr>",
+            name = escape(&action.name()),
+            git_ref = escape(action.git_ref.as_deref().unwrap_or("")),
+            kind = action.kind.as_str(),
+            third_party = if action.third_party { "yes" } else { "no" },
+            pinned = if action.pinned_to_sha { "yes" } else { "no" },
+            file = escape(&action.file_path),
+            line = action.line_number,
+        )?;
+    }
+    writeln!(sink, "</tbody>")?;
+    writeln!(sink, "</table>")?;
+
+    Ok(())
+}
+
+/// Renders the given findings (and third-party actions, if any) as a
+/// self-contained HTML report.
+pub(crate) fn render(
+    sink: &mut impl io::Write,
+    findings: &[Finding],
+    actions: &[Action],
+) -> Result<()> {
+    writeln!(sink, "<!DOCTYPE html>")?;
+    writeln!(sink, "<html lang=\"en\">")?;
+    writeln!(sink, "<head>")?;
+    writeln!(sink, "<meta charset=\"utf-8\">")?;
+    writeln!(sink, "<title>zizmor report</title>")?;
+    writeln!(sink, "<style>{STYLE}</style>")?;
+    writeln!(sink, "</head>")?;
+    writeln!(sink, "<body>")?;
+    writeln!(sink, "<h1>zizmor report</h1>")?;
+
+    render_summary(sink, findings)?;
+
+    if !actions.is_empty() {
+        writeln!(sink, "<div class=\"tabs\">")?;
+        writeln!(
+            sink,
+            "<button data-panel=\"findings\" aria-selected=\"true\">Findings</button>"
+        )?;
+        writeln!(
+            sink,
+            "<button data-panel=\"actions\" aria-selected=\"false\">Third-party actions</button>"
+        )?;
+        writeln!(sink, "</div>")?;
+    }
+
+    writeln!(sink, "<section id=\"findings\">")?;
+    render_findings(sink, findings)?;
+    writeln!(sink, "</section>")?;
+
+    if !actions.is_empty() {
+        writeln!(sink, "<section id=\"actions\" hidden>")?;
+        render_actions(sink, actions)?;
+        writeln!(sink, "</section>")?;
+    }
+
+    writeln!(sink, "<script>{SCRIPT}</script>")?;
+    writeln!(sink, "</body>")?;
+    writeln!(sink, "</html>")?;
+
+    Ok(())
+}
+
+/// Output the findings (and third-party action inventory) as an HTML report.
+pub(crate) fn output(
+    mut sink: impl io::Write,
+    app: &App,
+    registry: &InputRegistry,
+    allowed_orgs: &AllowedOrgs,
+    resolver: &UsesResolver,
+    results: &FindingRegistry,
+) -> Result<()> {
+    let actions = collect_actions(
+        registry,
+        allowed_orgs,
+        resolver,
+        results,
+        app.tpa_suppressed,
+    )?;
+
+    render(&mut sink, results.findings(), &actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escape;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(
+            escape(r#"<a href="x">'&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+}
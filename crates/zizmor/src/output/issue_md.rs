@@ -0,0 +1,393 @@
+//! Markdown output as a pre-filled GitHub issue (or PR comment) body,
+//! with a checklist of the third-party actions to pin.
+//!
+//! Checklist entries are sorted and contain nothing run-specific, so that
+//! an updated body can be diffed against the original issue's.
+
+use std::collections::BTreeMap;
+use std::io;
+
+use anyhow::Result;
+use clap::ValueEnum as _;
+use itertools::Itertools as _;
+
+use super::summary::SeverityCounts;
+use super::tpa_list::{Action, collect_actions, qualified_path, suggest_pins};
+use crate::App;
+use crate::audit::unpinned_uses::AllowedOrgs;
+use crate::finding::{Finding, FindingTag};
+use crate::registry::{FindingRegistry, InputRegistry};
+use crate::resolver::UsesResolver;
+
+/// Quotes `arg` for a POSIX shell, if it needs it.
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@,+%".contains(c));
+
+    if plain {
+        arg.into()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// The command that this run was invoked with, e.g. `zizmor --format=issue-md .`.
+fn command() -> String {
+    std::iter::once("zizmor".into())
+        .chain(std::env::args().skip(1).map(|arg| shell_quote(&arg)))
+        .join(" ")
+}
+
+/// The primary location of `finding`, as a `(file, line)`.
+fn primary_location(finding: &Finding) -> (String, usize) {
+    // NOTE: Safe unwrap, since FindingBuilder::build ensures a primary location.
+    let primary = finding
+        .visible_locations()
+        .find(|l| l.symbolic.is_primary())
+        .unwrap();
+    let key = primary.symbolic.key;
+
+    (
+        qualified_path(key.repository().as_deref(), key.presentation_path()),
+        primary.concrete.location.start_point.row + 1,
+    )
+}
+
+/// A checklist entry for an unpinned third-party action.
+fn action_entry(action: &Action) -> String {
+    let git_ref = match &action.git_ref {
+        Some(git_ref) => format!("`{git_ref}`"),
+        None => "no ref".into(),
+    };
+
+    let mut entry = format!(
+        "- [ ] `{file}:{line}`: `{name}` at {git_ref}",
+        file = action.qualified_path(),
+        line = action.line_number,
+        name = action.name(),
+    );
+
+    match (&action.suggested_sha, &action.suggested_tag) {
+        (Some(sha), Some(tag)) => {
+            entry.push_str(&format!(" → `{name}@{sha} # {tag}`", name = action.name()))
+        }
+        (Some(sha), None) => entry.push_str(&format!(" → `{name}@{sha}`", name = action.name())),
+        _ => {}
+    }
+
+    entry
+}
+
+/// A checklist entry for a finding.
+fn finding_entry(finding: &Finding) -> String {
+    let (file, line) = primary_location(finding);
+    // NOTE: Safe unwrap, since FindingBuilder::build ensures a primary location.
+    let annotation = &finding
+        .visible_locations()
+        .find(|l| l.symbolic.is_primary())
+        .unwrap()
+        .symbolic
+        .annotation;
+
+    let mut entry = format!(
+        "- [ ] `{file}:{line}`: {annotation} ({severity}, {confidence} confidence)",
+        severity = finding
+            .determinations
+            .severity
+            .to_possible_value()
+            .unwrap()
+            .get_name(),
+        confidence = finding
+            .determinations
+            .confidence
+            .to_possible_value()
+            .unwrap()
+            .get_name(),
+    );
+
+    match finding.occurrences.len() {
+        0 => {}
+        1 => entry.push_str(", and 1 more occurrence"),
+        n => entry.push_str(&format!(", and {n} more occurrences")),
+    }
+
+    entry
+}
+
+/// Renders the preamble's one-line summary of the run.
+fn render_counts(
+    sink: &mut impl io::Write,
+    unpinned: &[&Action],
+    files: usize,
+    counts: SeverityCounts,
+) -> Result<()> {
+    let severities = [
+        (counts.high, "high"),
+        (counts.medium, "medium"),
+        (counts.low, "low"),
+        (counts.informational, "informational"),
+        (counts.unknown, "unknown"),
+    ];
+    let findings = severities.iter().map(|(count, _)| count).sum::<usize>();
+
+    write!(
+        sink,
+        "**{count}** unpinned third-party action{s} in {files} file{fs}, \
+         and **{findings}** other finding{ns}",
+        count = unpinned.len(),
+        s = if unpinned.len() == 1 { "" } else { "s" },
+        fs = if files == 1 { "" } else { "s" },
+        ns = if findings == 1 { "" } else { "s" },
+    )?;
+
+    let breakdown = severities
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, name)| format!("{count} {name}"))
+        .collect::<Vec<_>>();
+    if breakdown.is_empty() {
+        writeln!(sink, ".")?;
+    } else {
+        writeln!(sink, " ({}).", breakdown.join(", "))?;
+    }
+
+    Ok(())
+}
+
+/// Renders the issue body for the given actions and findings.
+///
+/// Findings tagged [`FindingTag::ThirdPartyUnpinned`] for the unpinned
+/// third-party actions are left out of the findings, since the checklist
+/// already covers them.
+pub(crate) fn render(
+    sink: &mut impl io::Write,
+    command: &str,
+    results: &FindingRegistry,
+    actions: &[Action],
+) -> Result<()> {
+    let unpinned = actions
+        .iter()
+        .filter(|action| !action.suppressed && action.third_party && !action.pinned_to_sha)
+        .collect::<Vec<_>>();
+
+    let mut by_file = BTreeMap::<_, Vec<_>>::new();
+    for action in &unpinned {
+        by_file
+            .entry(action.qualified_path())
+            .or_default()
+            .push(action_entry(action));
+    }
+
+    let findings = results
+        .findings()
+        .iter()
+        .filter(|finding| {
+            !finding.tags.contains(&FindingTag::ThirdPartyUnpinned) || {
+                let (file, line) = primary_location(finding);
+                !unpinned
+                    .iter()
+                    .any(|action| action.qualified_path() == file && action.line_number == line)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut by_rule = BTreeMap::<_, (&Finding, Vec<_>)>::new();
+    for finding in &findings {
+        by_rule
+            .entry(finding.qualified_ident())
+            .or_insert_with(|| (finding, vec![]))
+            .1
+            .push((primary_location(finding), finding_entry(finding)));
+    }
+
+    writeln!(sink, "## zizmor report")?;
+    writeln!(sink)?;
+    render_counts(
+        sink,
+        &unpinned,
+        by_file.len(),
+        SeverityCounts::of(results, findings.iter().copied()),
+    )?;
+    writeln!(sink)?;
+    writeln!(
+        sink,
+        "Generated by zizmor {version} with `{command}`.",
+        version = env!("CARGO_PKG_VERSION"),
+    )?;
+    writeln!(sink)?;
+
+    writeln!(sink, "### Unpinned third-party actions")?;
+    writeln!(sink)?;
+    if by_file.is_empty() {
+        writeln!(sink, "No unpinned third-party actions found.")?;
+    }
+    for (file, entries) in &by_file {
+        writeln!(sink, "#### `{file}`")?;
+        writeln!(sink)?;
+        for entry in entries {
+            writeln!(sink, "{entry}")?;
+        }
+        writeln!(sink)?;
+    }
+
+    if by_rule.is_empty() {
+        return Ok(());
+    }
+
+    if by_file.is_empty() {
+        writeln!(sink)?;
+    }
+    writeln!(sink, "### Other findings")?;
+    for (rule, (finding, mut entries)) in by_rule {
+        // NOTE: Entries are sorted by location (and then by their text),
+        // since findings are in the order that they were found.
+        entries.sort();
+        entries.dedup();
+
+        writeln!(sink)?;
+        writeln!(sink, "#### [`{rule}`]({url})", url = finding.url)?;
+        writeln!(sink)?;
+        writeln!(sink, "{desc}", desc = finding.desc)?;
+        writeln!(sink)?;
+        for (_, entry) in entries {
+            writeln!(sink, "{entry}")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Output the findings (and unpinned third-party actions) as an issue body.
+pub(crate) fn output(
+    mut sink: impl io::Write,
+    app: &App,
+    registry: &InputRegistry,
+    allowed_orgs: &AllowedOrgs,
+    resolver: &UsesResolver,
+    results: &FindingRegistry,
+) -> Result<()> {
+    let mut actions = collect_actions(
+        registry,
+        allowed_orgs,
+        resolver,
+        results,
+        app.tpa_suppressed,
+    )?;
+
+    // Suggest pins for unpinned actions, if we're online.
+    if resolver.online() {
+        suggest_pins(resolver, &mut actions);
+    }
+
+    render(&mut sink, &command(), results, &actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser as _;
+
+    use super::{render, shell_quote};
+    use crate::audit::unpinned_uses::{AllowedOrgs, UnpinnedUses};
+    use crate::audit::{Audit as _, AuditInput};
+    use crate::config::Config;
+    use crate::finding::FindingTag;
+    use crate::github_api::GitHubHost;
+    use crate::models::Workflow;
+    use crate::output::tpa_list::collect_actions;
+    use crate::registry::{FindingRegistry, InputKey, InputRegistry};
+    use crate::resolver::UsesResolver;
+    use crate::state::AuditState;
+    use crate::{App, TpaSuppressed};
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("--format=issue-md"), "--format=issue-md");
+        assert_eq!(shell_quote(".github/workflows"), ".github/workflows");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("my workflows"), "'my workflows'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+
+    /// Renders the issue body for `contents`, first rewording the
+    /// annotations of its third-party unpinned findings with `reword`.
+    fn render_with(contents: &str, reword: impl Fn(&str) -> String) -> String {
+        let key = InputKey::local(".github/workflows/test.yml", None).unwrap();
+        let workflow = Workflow::from_string(contents.into(), key.clone()).unwrap();
+
+        let mut registry = InputRegistry::new(false);
+        registry.register_input(workflow.into()).unwrap();
+
+        let app = App::parse_from(["zizmor", "."]);
+        let config = Config::default();
+        let state = AuditState {
+            config: &config,
+            online: false,
+            cache_dir: "/tmp/zizmor".into(),
+            gh_token: None,
+            gh_hostname: GitHubHost::Standard("github.com".into()),
+            tpa_allowlist_file: None,
+            tpa_allowed_org: None,
+            lenient_allowlist: false,
+            advisories_file: None,
+            osv: false,
+            lockfile: None,
+            resolver: Default::default(),
+        };
+        let allowed_orgs = AllowedOrgs::from_state(&state).unwrap();
+
+        let input: &AuditInput = registry.get_input(&key);
+        let mut findings = UnpinnedUses::new(&state).unwrap().audit(input).unwrap();
+        for finding in &mut findings {
+            if finding.tags.contains(&FindingTag::ThirdPartyUnpinned) {
+                for location in &mut finding.locations {
+                    location.symbolic.annotation = reword(&location.symbolic.annotation);
+                }
+            }
+        }
+
+        let mut results = FindingRegistry::new(&app, &config, &registry, None);
+        results.extend(findings);
+
+        let actions = collect_actions(
+            &registry,
+            &allowed_orgs,
+            &UsesResolver::default(),
+            &results,
+            TpaSuppressed::Mark,
+        )
+        .unwrap();
+
+        let mut sink = vec![];
+        render(&mut sink, "zizmor --format=issue-md .", &results, &actions).unwrap();
+        String::from_utf8(sink).unwrap()
+    }
+
+    #[test]
+    fn test_render_ignores_annotations() {
+        let contents = r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout
+      - uses: some-org/some-action@v1
+      - uses: other-org/other-action@main
+"#;
+
+        let original = render_with(contents, str::to_string);
+        assert!(original.contains("`some-org/some-action` at `v1`"));
+        assert!(original.contains("`other-org/other-action` at `main`"));
+        // Only the first-party action's finding is left over.
+        assert!(original.contains("`.github/workflows/test.yml:8`: action is not pinned"));
+        assert!(!original.contains("test.yml:9`: action"));
+
+        let reworded = render_with(contents, |annotation| {
+            format!("reworded: {}", annotation.to_uppercase())
+        });
+        assert_eq!(original, reworded);
+    }
+}
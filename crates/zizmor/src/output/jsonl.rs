@@ -0,0 +1,72 @@
+//! JSON Lines output.
+//!
+//! Each finding is written as a single line, in the same shape as an
+//! element of the JSON output, as soon as its input has been audited.
+//! The final line is a summary object, marked with `"type": "summary"`.
+
+use std::io;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::summary::{RunSummary, SeverityCounts};
+use crate::finding::Finding;
+use crate::registry::{AuditSummary, FindingRegistry};
+
+#[derive(Serialize)]
+struct Summary<'a> {
+    r#type: &'static str,
+    findings: usize,
+    ignored: usize,
+    suppressed: usize,
+    filtered: usize,
+    baselined: usize,
+    severities: SeverityCounts,
+    /// Only present with `--audit-summary`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    audits: Option<&'a [AuditSummary]>,
+    /// Only present with `--summary`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a RunSummary>,
+}
+
+/// Write the given findings, one per line, and flush them.
+pub(crate) fn output_findings<'a>(
+    mut sink: impl io::Write,
+    findings: impl IntoIterator<Item = &'a Finding<'a>>,
+) -> Result<()> {
+    for finding in findings {
+        serde_json::to_writer(&mut sink, finding)?;
+        writeln!(sink)?;
+    }
+    sink.flush()?;
+
+    Ok(())
+}
+
+/// Write the final summary line for all of the run's findings (including
+/// those already streamed and released), and
+/// optionally each audit's status and the run summary.
+pub(crate) fn output_summary(
+    mut sink: impl io::Write,
+    results: &FindingRegistry,
+    audits: Option<&[AuditSummary]>,
+    run: Option<&RunSummary>,
+) -> Result<()> {
+    let summary = Summary {
+        r#type: "summary",
+        findings: results.nfindings(),
+        ignored: results.nignored(),
+        suppressed: results.nsuppressed(),
+        filtered: results.nfiltered(),
+        baselined: results.nbaselined(),
+        severities: results.severities(),
+        audits,
+        summary: run,
+    };
+
+    serde_json::to_writer(&mut sink, &summary)?;
+    writeln!(sink)?;
+
+    Ok(())
+}
@@ -1,4 +1,111 @@
+pub(crate) mod cyclonedx;
 pub(crate) mod github;
+pub(crate) mod html;
+pub(crate) mod issue_md;
+pub(crate) mod jsonl;
 pub(crate) mod plain;
 pub(crate) mod sarif;
+pub(crate) mod spdx;
+pub(crate) mod summary;
+pub(crate) mod tpa_allowlist;
+pub(crate) mod tpa_badge;
+pub(crate) mod tpa_csv;
 pub(crate) mod tpa_list;
+pub(crate) mod tpa_markdown;
+pub(crate) mod tpa_sarif;
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+
+use anstream::AutoStream;
+use anyhow::{Context as _, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use tempfile::NamedTempFile;
+
+/// The destination for the selected output format, i.e. `--output`.
+pub(crate) enum Sink {
+    /// The output is written to stdout.
+    Stdout(anstream::Stdout),
+    /// The output is written to a temporary file, which is atomically
+    /// moved to `path` by [`Sink::finish`].
+    File {
+        path: Utf8PathBuf,
+        tmp: NamedTempFile,
+        writer: BufWriter<AutoStream<File>>,
+    },
+}
+
+impl Sink {
+    /// Creates a sink for the given `--output`, where `None` or `-`
+    /// means stdout.
+    pub(crate) fn new(path: Option<&Utf8Path>) -> Result<Self> {
+        let Some(path) = path.filter(|p| p.as_str() != "-") else {
+            return Ok(Self::Stdout(anstream::stdout()));
+        };
+
+        let create = || -> Result<Self> {
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_str().is_empty() => dir,
+                _ => Utf8Path::new("."),
+            };
+            fs::create_dir_all(dir)?;
+
+            let tmp = NamedTempFile::new_in(dir)?;
+            // NOTE: Files only get colors if they're explicitly requested.
+            let writer = BufWriter::new(AutoStream::auto(tmp.reopen()?));
+
+            Ok(Self::File {
+                path: path.into(),
+                tmp,
+                writer,
+            })
+        };
+
+        create().with_context(|| format!("couldn't create output {path}"))
+    }
+
+    /// The path that this sink writes to, if it isn't stdout.
+    pub(crate) fn path(&self) -> Option<&Utf8Path> {
+        match self {
+            Sink::Stdout(_) => None,
+            Sink::File { path, .. } => Some(path),
+        }
+    }
+
+    /// Flushes this sink, moving its output into place if it's a file.
+    pub(crate) fn finish(self) -> Result<()> {
+        match self {
+            Sink::Stdout(mut stdout) => Ok(stdout.flush()?),
+            Sink::File {
+                path,
+                tmp,
+                mut writer,
+            } => {
+                let finish = || -> Result<()> {
+                    writer.flush()?;
+                    drop(writer);
+                    tmp.persist(&path)?;
+                    Ok(())
+                };
+
+                finish().with_context(|| format!("couldn't write output {path}"))
+            }
+        }
+    }
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Sink::Stdout(stdout) => stdout.write(buf),
+            Sink::File { writer, .. } => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Stdout(stdout) => stdout.flush(),
+            Sink::File { writer, .. } => writer.flush(),
+        }
+    }
+}
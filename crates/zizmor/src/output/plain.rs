@@ -1,17 +1,18 @@
 //! "plain" (i.e. cargo-style) output.
 
-use std::collections::{HashMap, hash_map::Entry};
+use std::{borrow::Cow, collections::BTreeMap, io, ops::Range};
 
 use annotate_snippets::{Level, Renderer, Snippet};
-use anstream::{eprintln, print, println};
+use anstream::eprintln;
+use anyhow::Result;
 use owo_colors::OwoColorize;
 use terminal_link::Link;
 
 use crate::{
-    App,
+    App, FailOn, GroupBy,
     finding::{Finding, Location, Severity},
     models::AsDocument,
-    registry::{FindingRegistry, InputKey, InputRegistry},
+    registry::{AuditStatus, AuditSummary, FindingRegistry, InputKey, InputRegistry},
 };
 
 impl From<&Severity> for Level {
@@ -26,159 +27,559 @@ impl From<&Severity> for Level {
     }
 }
 
-pub(crate) fn finding_snippet<'doc>(
-    registry: &'doc InputRegistry,
-    finding: &'doc Finding<'doc>,
-) -> Vec<Snippet<'doc>> {
-    // Our finding might span multiple workflows, so we need to group locations
-    // by their enclosing workflow to generate each snippet correctly.
-    let mut locations_by_workflow: HashMap<&InputKey, Vec<&Location<'doc>>> = HashMap::new();
-    for location in &finding.locations {
-        // Never include hidden locations in the output.
-        if location.symbolic.is_hidden() {
-            continue;
+/// The maximum width (in characters) of a source line in a snippet.
+///
+/// Longer lines (e.g. in minified YAML) are shortened to a window around
+/// one of their annotations, with `...` marking each elided part.
+const MAX_LINE_WIDTH: usize = 120;
+
+/// How many characters to keep before the annotation on a long line.
+const LINE_CONTEXT: usize = 20;
+
+/// A contiguous piece of a [`ShortenedSource`].
+struct Piece {
+    /// The piece's byte range in the original source.
+    original: Range<usize>,
+    /// The piece's byte range in the shortened source.
+    shortened: Range<usize>,
+    /// Whether this piece was elided, i.e. replaced with `...`.
+    elided: bool,
+}
+
+/// A source whose long lines have been shortened for display.
+struct ShortenedSource<'doc> {
+    source: Cow<'doc, str>,
+    /// The pieces that make up the source, in order. Empty if the
+    /// source didn't need to be shortened.
+    pieces: Vec<Piece>,
+}
+
+impl<'doc> ShortenedSource<'doc> {
+    /// Shortens any long lines in `source`, keeping the last span
+    /// that starts on each line in view, since it's typically the most
+    /// specific one.
+    fn new(source: &'doc str, spans: &[Range<usize>]) -> Self {
+        let is_long = |line: &str| line.chars().count() > MAX_LINE_WIDTH;
+        if !source.lines().any(is_long) {
+            return Self {
+                source: Cow::Borrowed(source),
+                pieces: vec![],
+            };
         }
 
-        match locations_by_workflow.entry(location.symbolic.key) {
-            Entry::Occupied(mut e) => {
-                e.get_mut().push(location);
+        let mut shortened = String::with_capacity(source.len());
+        let mut pieces = vec![];
+        let mut keep = |shortened: &mut String, original: Range<usize>, elided: bool| {
+            let start = shortened.len();
+            if elided {
+                shortened.push_str("...");
+            } else {
+                shortened.push_str(&source[original.clone()]);
+            }
+            pieces.push(Piece {
+                original,
+                shortened: start..shortened.len(),
+                elided,
+            });
+        };
+
+        let mut line_start = 0;
+        for line in source.split_inclusive('\n') {
+            let line_end = line_start + line.len();
+            let content = line.trim_end_matches(['\r', '\n']);
+
+            if !is_long(content) {
+                keep(&mut shortened, line_start..line_end, false);
+                line_start = line_end;
+                continue;
+            }
+
+            // NOTE: Windows are measured in characters, but sliced in bytes.
+            let chars = content
+                .char_indices()
+                .map(|(idx, _)| idx)
+                .collect::<Vec<_>>();
+            let byte = |char: usize| chars.get(char).copied().unwrap_or(content.len());
+            let anchor = spans
+                .iter()
+                .map(|span| span.start)
+                .filter(|start| (line_start..line_start + content.len()).contains(start))
+                .max()
+                .map(|start| chars.partition_point(|idx| *idx < start - line_start))
+                .unwrap_or(0);
+            let window_start = anchor
+                .saturating_sub(LINE_CONTEXT)
+                .min(chars.len() - MAX_LINE_WIDTH);
+            let window = byte(window_start)..byte(window_start + MAX_LINE_WIDTH);
+
+            if window.start > 0 {
+                keep(&mut shortened, line_start..line_start + window.start, true);
             }
-            Entry::Vacant(e) => {
-                e.insert(vec![location]);
+            keep(
+                &mut shortened,
+                line_start + window.start..line_start + window.end,
+                false,
+            );
+            if window.end < content.len() {
+                keep(
+                    &mut shortened,
+                    line_start + window.end..line_start + content.len(),
+                    true,
+                );
             }
+            keep(&mut shortened, line_start + content.len()..line_end, false);
+
+            line_start = line_end;
+        }
+
+        Self {
+            source: Cow::Owned(shortened),
+            pieces,
         }
     }
 
-    let mut snippets = vec![];
-    for (input_key, locations) in locations_by_workflow {
-        let input = registry.get_input(input_key);
+    /// Maps a byte offset in the original source to the shortened source.
+    ///
+    /// Offsets within an elided piece map to the start of its `...`, or
+    /// to its end if `end` is set, so that spans cover the elision.
+    fn map(&self, offset: usize, end: bool) -> usize {
+        if self.pieces.is_empty() {
+            return offset;
+        }
 
-        snippets.push(
-            Snippet::source(input.as_document().source())
-                .fold(true)
-                .line_start(1)
-                .origin(input.link().unwrap_or(input_key.presentation_path()))
-                .annotations(locations.iter().map(|loc| {
-                    let annotation = match loc.symbolic.link {
-                        Some(ref link) => link,
-                        None => &loc.symbolic.annotation,
-                    };
+        let idx = if end {
+            self.pieces.partition_point(|p| p.original.end < offset)
+        } else {
+            self.pieces.partition_point(|p| p.original.end <= offset)
+        };
+        let Some(piece) = self.pieces.get(idx) else {
+            return self.source.len();
+        };
 
-                    Level::from(&finding.determinations.severity)
-                        .span(
-                            loc.concrete.location.offset_span.start
-                                ..loc.concrete.location.offset_span.end,
-                        )
-                        .label(annotation)
-                })),
-        );
+        match (piece.elided, end) {
+            (true, false) => piece.shortened.start,
+            (true, true) => piece.shortened.end,
+            (false, _) => piece.shortened.start + (offset - piece.original.start),
+        }
     }
 
-    snippets
+    fn map_span(&self, span: &Range<usize>) -> Range<usize> {
+        self.map(span.start, false)..self.map(span.end, true)
+    }
 }
 
-pub(crate) fn render_findings(app: &App, registry: &InputRegistry, findings: &FindingRegistry) {
-    for finding in findings.findings() {
-        render_finding(registry, finding);
-        println!();
+/// Groups a finding's visible locations by their enclosing input, since
+/// a finding might span multiple inputs and each needs its own snippet.
+///
+/// The primary location's input comes first, so that it's the one that
+/// the rendered finding is headed with.
+fn locations_by_input<'doc>(
+    finding: &'doc Finding<'doc>,
+) -> Vec<(&'doc InputKey, Vec<&'doc Location<'doc>>)> {
+    let mut locations_by_input = BTreeMap::<_, Vec<_>>::new();
+    for location in finding
+        .visible_locations()
+        .filter(|l| !l.symbolic.is_context())
+    {
+        locations_by_input
+            .entry(location.symbolic.key)
+            .or_default()
+            .push(location);
     }
 
-    let mut qualifiers = vec![];
-    if !findings.ignored().is_empty() {
-        qualifiers.push(format!(
-            "{nignored} ignored",
-            nignored = findings.ignored().len().bright_yellow()
-        ));
+    let primary = finding
+        .locations
+        .iter()
+        .find(|l| l.symbolic.is_primary())
+        .map(|l| l.symbolic.key);
+
+    let mut locations_by_input = locations_by_input.into_iter().collect::<Vec<_>>();
+    locations_by_input.sort_by_key(|(key, _)| Some(*key) != primary);
+    locations_by_input
+}
+
+fn offset_span(location: &Location) -> Range<usize> {
+    location.concrete.location.offset_span.start..location.concrete.location.offset_span.end
+}
+
+pub(crate) fn render_findings(
+    sink: &mut impl io::Write,
+    app: &App,
+    registry: &InputRegistry,
+    findings: &FindingRegistry,
+) -> Result<()> {
+    match app.group_by {
+        GroupBy::File => render_findings_by_file(sink, app, registry, findings.findings())?,
+        GroupBy::Rule => render_findings_by_rule(sink, app, registry, findings)?,
     }
-    if !findings.suppressed().is_empty() {
-        qualifiers.push(format!(
-            "{nsuppressed} suppressed",
-            nsuppressed = findings.suppressed().len().bright_yellow()
-        ));
+
+    render_totals(sink, app, findings)
+}
+
+/// Renders `findings` in the order they were found, i.e. by input file.
+///
+/// Also used to stream each input's findings as soon as it's been audited.
+pub(crate) fn render_findings_by_file(
+    sink: &mut impl io::Write,
+    app: &App,
+    registry: &InputRegistry,
+    findings: &[Finding],
+) -> Result<()> {
+    for finding in findings {
+        render_finding(sink, registry, finding, !app.no_help_links)?;
+        writeln!(sink)?;
     }
 
-    if findings.findings().is_empty() {
+    Ok(())
+}
+
+/// Renders the run's totals (and `--fail-on` threshold, if any), after
+/// its findings.
+///
+/// Findings that were streamed as each input was audited (and since
+/// released) are counted too.
+pub(crate) fn render_totals(
+    sink: &mut impl io::Write,
+    app: &App,
+    findings: &FindingRegistry,
+) -> Result<()> {
+    if findings.nfindings() == 0 {
+        let qualifiers = qualifiers(app, findings);
         if qualifiers.is_empty() {
-            println!("{}", "No findings to report. Good job!".green());
+            writeln!(sink, "{}", "No findings to report. Good job!".green())?;
         } else {
-            println!(
+            writeln!(
+                sink,
                 "{no_findings} ({qualifiers})",
                 no_findings = "No findings to report. Good job!".green(),
                 qualifiers = qualifiers.join(", ").bold(),
-            );
+            )?;
         }
 
         if app.naches {
             naches();
         }
     } else {
-        let mut findings_by_severity = HashMap::new();
-
-        for finding in findings.findings() {
-            match findings_by_severity.entry(&finding.determinations.severity) {
-                Entry::Occupied(mut e) => {
-                    *e.get_mut() += 1;
-                }
-                Entry::Vacant(e) => {
-                    e.insert(1);
-                }
-            }
+        writeln!(sink, "{}", summary(app, findings))?;
+    }
+
+    if let Some(fail_on) = findings.fail_on() {
+        render_fail_on(sink, fail_on, findings)?;
+    }
+
+    Ok(())
+}
+
+/// The reasons (if any) that findings aren't shown, e.g. "2 ignored".
+fn qualifiers(app: &App, findings: &FindingRegistry) -> Vec<String> {
+    let mut qualifiers = vec![];
+    if findings.nignored() > 0 {
+        qualifiers.push(format!(
+            "{nignored} ignored",
+            nignored = findings.nignored().bright_yellow()
+        ));
+    }
+    if findings.nsuppressed() > 0 {
+        qualifiers.push(format!(
+            "{nsuppressed} suppressed",
+            nsuppressed = findings.nsuppressed().bright_yellow()
+        ));
+    }
+    if findings.nfiltered() > 0 {
+        qualifiers.push(format!(
+            "{nfiltered} filtered",
+            nfiltered = findings.nfiltered().bright_yellow()
+        ));
+    }
+    if findings.unchanged_inputs() > 0 {
+        let nunchanged = findings.unchanged_inputs();
+        qualifiers.push(format!(
+            "{nunchanged} unchanged file{s} skipped",
+            nunchanged = nunchanged.bright_yellow(),
+            s = if nunchanged == 1 { "" } else { "s" },
+        ));
+    }
+    if findings.nbaselined() > 0 {
+        qualifiers.push(format!(
+            "{nbaselined} {baselined}",
+            nbaselined = findings.nbaselined().bright_yellow(),
+            baselined = if app.diff.is_some() {
+                "unchanged"
+            } else {
+                "baselined"
+            },
+        ));
+    }
+
+    qualifiers
+}
+
+/// A one-line summary of the given findings' counts, by severity.
+pub(crate) fn summary(app: &App, findings: &FindingRegistry) -> String {
+    let severities = findings.severities();
+
+    let qualifiers = qualifiers(app, findings);
+    let nfindings = findings.count();
+    let prefix = if qualifiers.is_empty() {
+        format!(
+            "{nfindings} finding{s}",
+            nfindings = nfindings.green(),
+            s = if nfindings == 1 { "" } else { "s" },
+        )
+    } else {
+        format!(
+            "{nfindings} findings ({qualifiers})",
+            nfindings = nfindings.green(),
+            qualifiers = qualifiers.join(", ").bold(),
+        )
+    };
+
+    format!(
+        "{prefix}: {nunknown} unknown, {ninformational} informational, {nlow} low, {nmedium} medium, {nhigh} high",
+        nunknown = severities.unknown,
+        ninformational = severities.informational.purple(),
+        nlow = severities.low.cyan(),
+        nmedium = severities.medium.yellow(),
+        nhigh = severities.high.red(),
+    )
+}
+
+/// Renders each audit's status, e.g. for `--audit-summary`.
+pub(crate) fn render_audit_summary(
+    sink: &mut impl io::Write,
+    audits: &[AuditSummary],
+) -> Result<()> {
+    let width = audits
+        .iter()
+        .map(|audit| audit.ident.len())
+        .max()
+        .unwrap_or(0);
+
+    writeln!(sink, "{}", "audit summary:".bold())?;
+    for audit in audits {
+        let ident = format!("{:width$}", audit.ident);
+        match &audit.status {
+            AuditStatus::Ran {
+                findings,
+                wall_time,
+            } => writeln!(
+                sink,
+                "  {ident}  {status}  {findings} finding{s} in {wall_time:.2?}",
+                status = "ran    ".green(),
+                s = if *findings == 1 { "" } else { "s" },
+            )?,
+            AuditStatus::Skipped { reason } => writeln!(
+                sink,
+                "  {ident}  {status}  {reason}",
+                status = "skipped".bright_yellow(),
+            )?,
+            AuditStatus::Failed { error } => writeln!(
+                sink,
+                "  {ident}  {status}  {error}",
+                status = "failed ".red(),
+            )?,
         }
+    }
 
-        if qualifiers.is_empty() {
-            let nfindings = findings.count();
-            print!(
-                "{nfindings} finding{s}: ",
-                nfindings = nfindings.green(),
-                s = if nfindings == 1 { "" } else { "s" },
-            );
-        } else {
-            print!(
-                "{nfindings} findings ({qualifiers}): ",
-                nfindings = findings.count().green(),
-                qualifiers = qualifiers.join(", ").bold(),
-            );
+    Ok(())
+}
+
+/// Renders each rule's findings under a header with the rule's
+/// description and finding count, in rule order.
+fn render_findings_by_rule(
+    sink: &mut impl io::Write,
+    app: &App,
+    registry: &InputRegistry,
+    findings: &FindingRegistry,
+) -> Result<()> {
+    let mut by_rule = BTreeMap::<_, Vec<_>>::new();
+    for finding in findings.findings() {
+        by_rule.entry(finding.ident).or_default().push(finding);
+    }
+
+    for (ident, rule_findings) in by_rule {
+        let nfindings = rule_findings
+            .iter()
+            .map(|finding| findings.weight(finding))
+            .sum::<usize>();
+        writeln!(
+            sink,
+            "{ident}: {desc} ({nfindings} finding{s})",
+            ident = ident.bold(),
+            desc = rule_findings[0].desc,
+            nfindings = nfindings.green(),
+            s = if nfindings == 1 { "" } else { "s" },
+        )?;
+        // NOTE: The docs link is only printed once per rule, rather than
+        // once per finding.
+        if !app.no_help_links {
+            writeln!(sink, "docs: {link}", link = help_link(rule_findings[0].url))?;
         }
+        writeln!(sink)?;
 
-        println!(
-            "{nunknown} unknown, {ninformational} informational, {nlow} low, {nmedium} medium, {nhigh} high",
-            nunknown = findings_by_severity.get(&Severity::Unknown).unwrap_or(&0),
-            ninformational = findings_by_severity
-                .get(&Severity::Informational)
-                .unwrap_or(&0)
-                .purple(),
-            nlow = findings_by_severity
-                .get(&Severity::Low)
-                .unwrap_or(&0)
-                .cyan(),
-            nmedium = findings_by_severity
-                .get(&Severity::Medium)
-                .unwrap_or(&0)
-                .yellow(),
-            nhigh = findings_by_severity
-                .get(&Severity::High)
-                .unwrap_or(&0)
-                .red(),
-        );
+        for finding in rule_findings {
+            render_finding(sink, registry, finding, false)?;
+            writeln!(sink)?;
+        }
     }
+
+    Ok(())
+}
+
+/// Explains the exit code under an explicit `--fail-on` threshold.
+fn render_fail_on(
+    sink: &mut impl io::Write,
+    fail_on: FailOn,
+    findings: &FindingRegistry,
+) -> Result<()> {
+    let Some(threshold) = fail_on.threshold() else {
+        writeln!(
+            sink,
+            "fail-on {}: findings don't fail this run",
+            fail_on.bold()
+        )?;
+        return Ok(());
+    };
+
+    let nfailing = findings.severities().at_or_above(threshold);
+
+    writeln!(
+        sink,
+        "fail-on {fail_on}: {nfailing} finding{s} at or above this severity",
+        fail_on = fail_on.bold(),
+        nfailing = if nfailing == 0 {
+            nfailing.green().to_string()
+        } else {
+            nfailing.red().to_string()
+        },
+        s = if nfailing == 1 { "" } else { "s" },
+    )?;
+
+    Ok(())
+}
+
+/// A link to the given documentation URL, as an OSC 8 hyperlink.
+///
+/// The sink strips the hyperlink's escape sequences (leaving just the URL)
+/// when it isn't a color-capable terminal.
+fn help_link(url: &str) -> String {
+    Link::new(url, url).to_string()
 }
 
-fn render_finding(registry: &InputRegistry, finding: &Finding) {
-    let link = Link::new(finding.ident, finding.url).to_string();
+/// Renders a single finding, followed by a `help:` link to its rule's
+/// documentation if `with_help_link` is set.
+fn render_finding(
+    sink: &mut impl io::Write,
+    registry: &InputRegistry,
+    finding: &Finding,
+    with_help_link: bool,
+) -> Result<()> {
+    let locations_by_input = locations_by_input(finding);
+    let sources = locations_by_input
+        .iter()
+        .map(|(key, locations)| {
+            let spans = locations.iter().map(|l| offset_span(l)).collect::<Vec<_>>();
+            ShortenedSource::new(registry.get_input(key).as_document().source(), &spans)
+        })
+        .collect::<Vec<_>>();
+
+    // A primary external location (with a known line) heads the finding
+    // with a snippet of its own, rather than being summarized in a note.
+    let external_snippet = finding.primary_external_location().and_then(|location| {
+        let path = location.path.as_deref()?;
+        let point = location.point.as_ref()?;
+        let line = location.line.as_deref()?;
+
+        Some(
+            Snippet::source(line)
+                .line_start(point.row + 1)
+                .origin(path)
+                .annotation(
+                    Level::from(&finding.determinations.severity)
+                        .span(point.column.min(line.len())..line.len())
+                        .label(&location.annotation),
+                ),
+        )
+    });
+    let headed = external_snippet.is_some();
+
+    let snippets = external_snippet
+        .into_iter()
+        .chain(
+            locations_by_input
+                .iter()
+                .zip(&sources)
+                .map(|((key, locations), source)| {
+                    let input = registry.get_input(key);
+
+                    Snippet::source(&source.source)
+                        .fold(true)
+                        .line_start(1)
+                        .origin(input.link().unwrap_or(key.presentation_path()))
+                        .annotations(locations.iter().map(|loc| {
+                            let annotation = match loc.symbolic.link {
+                                Some(ref link) => link,
+                                None => &loc.symbolic.annotation,
+                            };
+
+                            Level::from(&finding.determinations.severity)
+                                .span(source.map_span(&offset_span(loc)))
+                                .label(annotation)
+                        }))
+                }),
+        );
+
+    let ident = finding.qualified_ident();
+    let link = Link::new(&ident, finding.url).to_string();
     let confidence = format!(
         "audit confidence → {:?}",
         &finding.determinations.confidence
     );
     let confidence_footer = Level::Note.title(&confidence);
+    let help = help_link(finding.url);
+    // Context locations are summarized on one line, e.g.
+    // `in job build, triggered by pull_request_target`.
+    let context = finding
+        .visible_locations()
+        .filter(|l| l.symbolic.is_context())
+        .map(|l| l.symbolic.annotation.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let external_locations = finding
+        .external_locations
+        .iter()
+        .filter(|location| !(headed && location.primary))
+        .map(|location| location.render())
+        .collect::<Vec<_>>();
+    // Rolled-up findings are rendered at their first occurrence.
+    let occurrences = match finding.occurrences.len() {
+        0 => None,
+        1 => Some("and 1 more occurrence".to_string()),
+        n => Some(format!("and {n} more occurrences")),
+    };
 
     let message = Level::from(&finding.determinations.severity)
         .title(finding.desc)
         .id(&link)
-        .snippets(finding_snippet(registry, finding))
-        .footer(confidence_footer);
+        .snippets(snippets)
+        .footers((!context.is_empty()).then(|| Level::Note.title(&context)))
+        .footers(
+            external_locations
+                .iter()
+                .map(|location| Level::Note.title(location)),
+        )
+        .footers(
+            occurrences
+                .as_deref()
+                .map(|occurrences| Level::Note.title(occurrences)),
+        )
+        .footer(confidence_footer)
+        .footers(with_help_link.then(|| Level::Help.title(&help)));
 
     let renderer = Renderer::styled();
-    println!("{}", renderer.render(message));
+    writeln!(sink, "{}", renderer.render(message))?;
+
+    Ok(())
 }
 
 fn naches() {
@@ -200,3 +601,55 @@ fn naches() {
                 thank you, dr. zizmor!"
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_LINE_WIDTH, ShortenedSource};
+
+    #[test]
+    fn test_shortened_source_short_lines() {
+        let source = "foo: bar\nbaz: quux\n";
+        let shortened = ShortenedSource::new(source, &[0..8, 5..8]);
+
+        assert_eq!(shortened.source, source);
+        assert_eq!(shortened.map_span(&(5..8)), 5..8);
+    }
+
+    #[test]
+    fn test_shortened_source_long_lines() {
+        let long = format!("run: {}${{{{ x }}}}{}", "a".repeat(200), "b".repeat(200));
+        let source = format!("on: push\n{long}\nlast: line\n");
+        let expr = source.find("${{").unwrap();
+
+        let shortened = ShortenedSource::new(&source, &[9..9 + long.len(), expr..expr + 8]);
+        let lines = shortened.source.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[0], "on: push");
+        assert!(lines[1].starts_with("...") && lines[1].ends_with("..."));
+        assert_eq!(lines[1].len(), MAX_LINE_WIDTH + 6);
+        assert_eq!(lines[2], "last: line");
+
+        // The expression stays in view, and spans covering elided text
+        // cover the elisions too.
+        let mapped = shortened.map_span(&(expr..expr + 8));
+        assert_eq!(&shortened.source[mapped], "${{ x }}");
+        let mapped = shortened.map_span(&(9..9 + long.len()));
+        assert_eq!(&shortened.source[mapped], lines[1]);
+
+        // Subsequent lines are shifted, but otherwise unchanged.
+        let last = source.find("last").unwrap();
+        let mapped = shortened.map_span(&(last..last + 4));
+        assert_eq!(&shortened.source[mapped], "last");
+    }
+
+    #[test]
+    fn test_shortened_source_multibyte() {
+        let source = format!("{}\n", "é".repeat(MAX_LINE_WIDTH * 2));
+        let shortened = ShortenedSource::new(&source, &[]);
+
+        assert_eq!(
+            shortened.source,
+            format!("{}...\n", "é".repeat(MAX_LINE_WIDTH))
+        );
+    }
+}
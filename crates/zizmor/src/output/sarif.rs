@@ -1,14 +1,22 @@
 //! SARIF output.
 
-use std::collections::HashSet;
+use std::collections::HashMap;
 
+use clap::ValueEnum as _;
 use serde_sarif::sarif::{
-    ArtifactContent, ArtifactLocation, Invocation, Location as SarifLocation, LogicalLocation,
-    Message, MultiformatMessageString, PhysicalLocation, PropertyBag, Region, ReportingDescriptor,
-    Result as SarifResult, ResultKind, ResultLevel, Run, Sarif, Tool, ToolComponent,
+    ArtifactChange, ArtifactContent, ArtifactLocation, Fix, Invocation, Location as SarifLocation,
+    LogicalLocation, Message, MultiformatMessageString, PhysicalLocation, PropertyBag, Region,
+    Replacement, ReportingConfiguration, ReportingDescriptor, Result as SarifResult,
+    ResultBaselineState, ResultKind, ResultLevel, Run, Sarif, Tool, ToolComponent,
 };
 
-use crate::finding::{Finding, Location, Severity};
+use crate::audit::RULES;
+use crate::config::{Config, SarifLevel};
+use crate::finding::{ExternalLocation, Finding, Location, Remediation, Severity};
+use crate::registry::FindingRegistry;
+
+/// The key that each result's [`Finding::fingerprint`] is stored under.
+const FINGERPRINT_KEY: &str = "zizmorFindingHash/v1";
 
 impl From<Severity> for ResultKind {
     fn from(value: Severity) -> Self {
@@ -36,15 +44,73 @@ impl From<Severity> for ResultLevel {
     }
 }
 
-pub(crate) fn build(findings: &[Finding]) -> Sarif {
+impl From<SarifLevel> for ResultLevel {
+    fn from(value: SarifLevel) -> Self {
+        match value {
+            SarifLevel::None => ResultLevel::None,
+            SarifLevel::Note => ResultLevel::Note,
+            SarifLevel::Warning => ResultLevel::Warning,
+            SarifLevel::Error => ResultLevel::Error,
+        }
+    }
+}
+
+/// The SARIF level for the given rule's findings of the given severity.
+///
+/// This is the configured level, if any, or else the default mapping.
+pub(crate) fn level(config: &Config, ident: &str, severity: Severity) -> ResultLevel {
+    config
+        .sarif_level(ident, severity)
+        .map_or_else(|| severity.into(), Into::into)
+}
+
+/// The properties of a rule or result for the given rule's findings of
+/// the given severity, with the configured `security-severity` (if any).
+///
+/// GitHub's code scanning uses a rule's `security-severity` to rank (and
+/// gate on) its alerts.
+pub(crate) fn properties(
+    config: &Config,
+    ident: &str,
+    severity: Severity,
+    tags: Vec<String>,
+) -> Option<PropertyBag> {
+    let score = config.sarif_security_severity(ident, severity);
+    if score.is_none() && tags.is_empty() {
+        return None;
+    }
+
+    let mut properties = PropertyBag::builder().build();
+    if !tags.is_empty() {
+        properties.tags = Some(tags);
+    }
+    if let Some(score) = score {
+        // NOTE: GitHub expects the score as a string, e.g. "8.0".
+        properties
+            .additional_properties
+            .insert("security-severity".into(), format!("{score:?}").into());
+    }
+
+    Some(properties)
+}
+
+pub(crate) fn build(results: &FindingRegistry, config: &Config) -> Sarif {
+    // NOTE: Baselined findings are included, with their baseline state.
+    let findings = results
+        .findings()
+        .iter()
+        .chain(results.baselined())
+        .collect::<Vec<_>>();
+    let baselined = results.has_baseline();
+
     Sarif::builder()
         .version("2.1.0")
         .schema("https://docs.oasis-open.org/sarif/sarif/v2.1.0/os/schemas/sarif-schema-2.1.0.json")
-        .runs([build_run(findings)])
+        .runs([build_run(&findings, baselined, config)])
         .build()
 }
 
-fn build_run(findings: &[Finding]) -> Run {
+fn build_run(findings: &[&Finding], baselined: bool, config: &Config) -> Run {
     Run::builder()
         .tool(
             Tool::builder()
@@ -55,12 +121,12 @@ fn build_run(findings: &[Finding]) -> Run {
                         .semantic_version(env!("CARGO_PKG_VERSION"))
                         .download_uri(env!("CARGO_PKG_REPOSITORY"))
                         .information_uri(env!("CARGO_PKG_HOMEPAGE"))
-                        .rules(build_rules(findings))
+                        .rules(build_rules(findings, config))
                         .build(),
                 )
                 .build(),
         )
-        .results(build_results(findings))
+        .results(build_results(findings, baselined, config))
         .invocations([Invocation::builder()
             // We only produce results on successful executions.
             .execution_successful(true)
@@ -68,44 +134,100 @@ fn build_run(findings: &[Finding]) -> Run {
         .build()
 }
 
-fn build_rules(findings: &[Finding]) -> Vec<ReportingDescriptor> {
-    // use the set to filter out duplicate rules
-    let mut unique_rules = HashSet::new();
+fn build_rules(findings: &[&Finding], config: &Config) -> Vec<ReportingDescriptor> {
+    // Each rule is described by its first finding, and scored by its
+    // most severe one.
+    let mut highest = HashMap::new();
+    for finding in findings {
+        let severity = highest
+            .entry(finding.qualified_ident())
+            .or_insert(finding.determinations.severity);
+        *severity = (*severity).max(finding.determinations.severity);
+    }
+
     findings
         .iter()
-        .filter(|finding| unique_rules.insert(finding.ident))
-        .map(|finding| build_rule(finding))
+        .filter_map(|finding| {
+            // NOTE: Removing the ident filters out duplicate rules.
+            let severity = highest.remove(&finding.qualified_ident())?;
+            Some(build_rule(finding, severity, config))
+        })
         .collect()
 }
 
-fn build_rule(finding: &Finding) -> ReportingDescriptor {
+fn build_rule(finding: &Finding, highest: Severity, config: &Config) -> ReportingDescriptor {
+    // NOTE: Every finding comes from a known rule.
+    let rule = RULES
+        .iter()
+        .find(|rule| rule.ident == finding.ident)
+        .expect("API misuse: finding from an unknown rule");
+
+    let ident = finding.qualified_ident();
     ReportingDescriptor::builder()
-        .id(format!("zizmor/{id}", id = finding.ident))
-        .name(finding.ident)
-        .help_uri(finding.url)
+        .id(format!("zizmor/{ident}"))
+        .name(ident.as_ref())
+        .short_description(MultiformatMessageString::builder().text(rule.desc).build())
+        .help_uri(rule.url)
         .help(
             MultiformatMessageString::builder()
-                .text(finding.desc)
-                .markdown(finding.to_markdown())
+                .text(rule.desc)
+                .markdown(format!(
+                    "{overview}\n\n{remediation}",
+                    overview = finding.to_markdown(),
+                    remediation = rule.remediation.trim_end(),
+                ))
                 .build(),
         )
-        .properties(PropertyBag::builder().tags(["security".into()]).build())
+        .default_configuration(default_configuration(
+            config,
+            rule.ident,
+            rule.default_severity,
+        ))
+        .properties(
+            // NOTE: Safe unwrap, since there's always a tag.
+            properties(config, rule.ident, highest, vec!["security".into()]).unwrap(),
+        )
         .build()
 }
 
-fn build_results(findings: &[Finding]) -> Vec<SarifResult> {
-    findings.iter().map(|f| build_result(f)).collect()
+/// The reporting configuration for the given rule, whose findings are
+/// typically of the given severity.
+pub(crate) fn default_configuration(
+    config: &Config,
+    ident: &str,
+    severity: Severity,
+) -> ReportingConfiguration {
+    ReportingConfiguration::builder()
+        .level(serde_json::to_value(level(config, ident, severity)).unwrap())
+        .build()
+}
+
+fn build_results(findings: &[&Finding], baselined: bool, config: &Config) -> Vec<SarifResult> {
+    findings
+        .iter()
+        .map(|f| build_result(f, baselined, config))
+        .collect()
 }
 
-fn build_result(finding: &Finding<'_>) -> SarifResult {
+fn build_result(finding: &Finding<'_>, baselined: bool, config: &Config) -> SarifResult {
     // NOTE: Safe unwrap because FindingBuilder::build ensures a primary location.
     let primary = finding
         .visible_locations()
         .find(|l| l.symbolic.is_primary())
         .unwrap();
 
-    SarifResult::builder()
-        .rule_id(format!("zizmor/{id}", id = finding.ident))
+    // NOTE: A primary external location stands in for the primary
+    // location, which is demoted to a related location.
+    let external = finding.primary_external_location();
+    let locations = match external {
+        Some(location) => vec![build_external_location(location)],
+        // NOTE: A rolled-up finding's occurrences are each a location
+        // of the same result.
+        None => build_locations(std::iter::once(primary).chain(&finding.occurrences)),
+    };
+
+    let mut result = SarifResult::builder()
+        .rule_id(format!("zizmor/{id}", id = finding.qualified_ident()))
         // NOTE: We use the primary location's annotation for the result's message.
         // This is conceptually incorrect since the location's annotation should
         // only be on the location itself. However, GitHub's SARIF viewer does not
@@ -114,21 +236,125 @@ fn build_result(finding: &Finding<'_>) -> SarifResult {
         // This ends up being OK since the only other thing we'd put here
         // is the finding's description, which is already in the rule's help message.
         // See https://github.com/zizmorcore/zizmor/issues/526 for context.
-        .message(&primary.symbolic.annotation)
-        .locations(build_locations(std::iter::once(primary)))
-        .related_locations(build_locations(
-            finding
-                .visible_locations()
-                .filter(|l| !l.symbolic.is_primary()),
+        .message(external.map_or(&primary.symbolic.annotation, |location| {
+            &location.annotation
+        }))
+        .locations(locations)
+        .related_locations(
+            build_locations(
+                finding
+                    .visible_locations()
+                    .filter(|l| external.is_some() || !l.symbolic.is_primary()),
+            )
+            .into_iter()
+            .chain(
+                finding
+                    .external_locations
+                    .iter()
+                    .filter(|location| !location.primary)
+                    .map(build_external_location),
+            )
+            .collect::<Vec<_>>(),
+        )
+        .partial_fingerprints([(FINGERPRINT_KEY.into(), finding.fingerprint().into())])
+        .level(level(
+            config,
+            finding.ident,
+            finding.determinations.severity,
         ))
-        .level(ResultLevel::from(finding.determinations.severity))
         .kind(ResultKind::from(finding.determinations.severity))
-        .build()
+        .build();
+
+    result.properties = properties(
+        config,
+        finding.ident,
+        finding.determinations.severity,
+        vec![],
+    );
+
+    // Every result carries its confidence, since SARIF has no notion of
+    // one; consumers can use it to triage lower-confidence results.
+    result
+        .properties
+        .get_or_insert_with(|| PropertyBag::builder().build())
+        .additional_properties
+        .insert(
+            "confidence".into(),
+            finding
+                .determinations
+                .confidence
+                .to_possible_value()
+                .unwrap()
+                .get_name()
+                .into(),
+        );
+
+    // Findings from remote inputs are qualified by their repository,
+    // since paths alone are ambiguous when auditing an entire org.
+    if let Some(repository) = &finding.repository {
+        result
+            .properties
+            .get_or_insert_with(|| PropertyBag::builder().build())
+            .additional_properties
+            .insert("repository".into(), repository.clone().into());
+    }
+
+    // Findings from inputs outside of the standard locations are marked,
+    // so that CI can treat them differently.
+    if !finding.collection.is_standard() {
+        result
+            .properties
+            .get_or_insert_with(|| PropertyBag::builder().build())
+            .additional_properties
+            .insert("collection".into(), "extra".into());
+    }
+
+    // Likewise for workflow templates, which are copied into every
+    // repository that's created from them.
+    if finding.template {
+        result
+            .properties
+            .get_or_insert_with(|| PropertyBag::builder().build())
+            .additional_properties
+            .insert("template".into(), true.into());
+    }
+
+    // Vendored findings don't fail the run, and so shouldn't fail CI.
+    if finding.vendored {
+        result
+            .properties
+            .get_or_insert_with(|| PropertyBag::builder().build())
+            .additional_properties
+            .insert("vendored".into(), true.into());
+    }
+
+    if let Some(fix) = finding
+        .remediation
+        .as_ref()
+        .and_then(|remediation| build_fix(remediation, primary.symbolic.key.sarif_path()))
+    {
+        result.fixes = Some(vec![fix]);
+    }
+
+    // NOTE: We only report a baseline state when there's a baseline
+    // to compare against.
+    if baselined {
+        let state = if finding.baselined {
+            ResultBaselineState::Unchanged
+        } else {
+            ResultBaselineState::New
+        };
+        result.baseline_state = Some(serde_json::to_value(state).unwrap());
+    }
+
+    result
 }
 
 fn build_locations<'a>(locations: impl Iterator<Item = &'a Location<'a>>) -> Vec<SarifLocation> {
     locations
         .map(|location| {
+            let end = location.concrete.region_end();
+
             SarifLocation::builder()
                 .logical_locations([LogicalLocation::builder()
                     .properties(
@@ -151,13 +377,11 @@ fn build_locations<'a>(locations: impl Iterator<Item = &'a Location<'a>>) -> Vec
                             Region::builder()
                                 // NOTE: SARIF lines/columns are 1-based.
                                 .start_line((location.concrete.location.start_point.row as i64) + 1)
-                                .end_line((location.concrete.location.end_point.row as i64) + 1)
+                                .end_line((end.row as i64) + 1)
                                 .start_column(
                                     (location.concrete.location.start_point.column as i64) + 1,
                                 )
-                                .end_column(
-                                    (location.concrete.location.end_point.column as i64) + 1,
-                                )
+                                .end_column((end.column as i64) + 1)
                                 .source_language("yaml")
                                 .snippet(
                                     ArtifactContent::builder()
@@ -178,11 +402,81 @@ fn build_locations<'a>(locations: impl Iterator<Item = &'a Location<'a>>) -> Vec
         .collect()
 }
 
+/// The SARIF fix for `remediation` to the artifact at `uri`, if it has a
+/// concrete replacement.
+fn build_fix(remediation: &Remediation, uri: &str) -> Option<Fix> {
+    let replacement = remediation.replacement.as_ref()?;
+    if remediation.edits.is_empty() {
+        return None;
+    }
+
+    Some(
+        Fix::builder()
+            .description(
+                Message::builder()
+                    .text(format!("replace with {replacement}"))
+                    .build(),
+            )
+            .artifact_changes([ArtifactChange::builder()
+                .artifact_location(ArtifactLocation::builder().uri(uri).build())
+                .replacements(
+                    remediation
+                        .edits
+                        .iter()
+                        .map(|edit| {
+                            Replacement::builder()
+                                .deleted_region(
+                                    Region::builder()
+                                        .byte_offset(edit.span.start as i64)
+                                        .byte_length(edit.span.len() as i64)
+                                        .build(),
+                                )
+                                .inserted_content(
+                                    ArtifactContent::builder().text(&edit.replacement).build(),
+                                )
+                                .build()
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .build()])
+            .build(),
+    )
+}
+
+fn build_external_location(location: &ExternalLocation) -> SarifLocation {
+    let message = Message::builder().text(&location.annotation).build();
+
+    let Some(path) = &location.path else {
+        return SarifLocation::builder().message(message).build();
+    };
+
+    let mut physical = PhysicalLocation::builder()
+        .artifact_location(ArtifactLocation::builder().uri(path).build())
+        .build();
+    physical.region = location.point.as_ref().map(|point| {
+        // NOTE: SARIF lines/columns are 1-based.
+        Region::builder()
+            .start_line((point.row as i64) + 1)
+            .start_column((point.column as i64) + 1)
+            .source_language("yaml")
+            .build()
+    });
+
+    SarifLocation::builder()
+        .physical_location(physical)
+        .message(message)
+        .build()
+}
+
 #[cfg(test)]
 mod tests {
-    use serde_sarif::sarif::ResultKind;
+    use serde_sarif::sarif::{ResultKind, ResultLevel};
 
-    use crate::finding::Severity;
+    use super::{build_fix, level, properties};
+    use crate::config::Config;
+    use crate::finding::{Remediation, Severity};
+    use crate::fix::Edit;
+    use crate::registry::InputKey;
 
     #[test]
     fn test_resultkind_from_severity() {
@@ -191,4 +485,127 @@ mod tests {
             "\"fail\""
         );
     }
+
+    #[test]
+    fn test_default_mapping() {
+        let config = Config::default();
+
+        for (severity, expected) in [
+            (Severity::Unknown, ResultLevel::None),
+            (Severity::Informational, ResultLevel::Note),
+            (Severity::Low, ResultLevel::Warning),
+            (Severity::Medium, ResultLevel::Warning),
+            (Severity::High, ResultLevel::Error),
+        ] {
+            assert_eq!(level(&config, "artipacked", severity), expected);
+            assert!(properties(&config, "artipacked", severity, vec![]).is_none());
+        }
+    }
+
+    #[test]
+    fn test_configured_mapping() {
+        let config = serde_yaml::from_str::<Config>(
+            "sarif:
+  level:
+    unknown: note
+    informational: none
+    low: note
+    medium: error
+    high: warning
+  security-severity:
+    unknown: 0.0
+    informational: 1.5
+    low: 3.0
+    medium: 5.5
+    high: 8.0
+rules:
+  artipacked:
+    sarif:
+      level:
+        high: error
+      security-severity:
+        high: 9.5
+",
+        )
+        .unwrap();
+
+        for (severity, expected_level, expected_score) in [
+            (Severity::Unknown, ResultLevel::Note, "0.0"),
+            (Severity::Informational, ResultLevel::None, "1.5"),
+            (Severity::Low, ResultLevel::Note, "3.0"),
+            (Severity::Medium, ResultLevel::Error, "5.5"),
+            (Severity::High, ResultLevel::Warning, "8.0"),
+        ] {
+            assert_eq!(level(&config, "unpinned-uses", severity), expected_level);
+
+            let properties =
+                properties(&config, "unpinned-uses", severity, vec!["security".into()]).unwrap();
+            assert_eq!(properties.tags.as_deref(), Some(&["security".into()][..]));
+            assert_eq!(
+                properties.additional_properties["security-severity"],
+                expected_score
+            );
+        }
+
+        // Each rule's own mapping takes precedence over the global one.
+        assert_eq!(
+            level(&config, "artipacked", Severity::High),
+            ResultLevel::Error
+        );
+        assert_eq!(
+            properties(&config, "artipacked", Severity::High, vec![])
+                .unwrap()
+                .additional_properties["security-severity"],
+            "9.5"
+        );
+        assert_eq!(
+            level(&config, "artipacked", Severity::Medium),
+            ResultLevel::Error
+        );
+    }
+
+    #[test]
+    fn test_build_fix() {
+        let key = InputKey::local("ci.yml", None).unwrap();
+        let source = "- uses: foo/bar@v1\n";
+        let remediation = Remediation::new(&key, source, 8..18).unwrap();
+        assert_eq!(remediation.current, "foo/bar@v1");
+
+        // Nothing to fix without a concrete replacement.
+        assert!(build_fix(&remediation, "ci.yml").is_none());
+
+        let remediation = remediation.with_replacement(
+            "foo/bar@abc",
+            vec![
+                Edit {
+                    span: 16..18,
+                    replacement: "abc".into(),
+                },
+                Edit {
+                    span: 18..18,
+                    replacement: " # v1".into(),
+                },
+            ],
+        );
+        let fix = serde_json::to_value(build_fix(&remediation, "ci.yml").unwrap()).unwrap();
+        assert_eq!(
+            fix,
+            serde_json::json!({
+                "artifactChanges": [{
+                    "artifactLocation": {"uri": "ci.yml"},
+                    "replacements": [
+                        {
+                            "deletedRegion": {"byteLength": 2, "byteOffset": 16},
+                            "insertedContent": {"text": "abc"}
+                        },
+                        {
+                            "deletedRegion": {"byteLength": 0, "byteOffset": 18},
+                            "insertedContent": {"text": " # v1"}
+                        }
+                    ]
+                }],
+                "description": {"text": "replace with foo/bar@abc"}
+            })
+        );
+    }
 }
@@ -0,0 +1,251 @@
+//! SPDX output for the third-party action inventory.
+//!
+//! Each unique action, reusable workflow, and container image is a
+//! package in an SPDX 2.3 JSON document, with the same package URLs as
+//! the CycloneDX output. A root `workflows` package stands in for the
+//! audited inputs, and depends on every other package.
+
+use std::io;
+
+use anyhow::{Context as _, Result};
+use serde::Serialize;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+use super::tpa_list::{
+    Action, ActionKind, Summary, collect_actions, generate_summary, group_by_purl,
+};
+use crate::App;
+use crate::audit::unpinned_uses::AllowedOrgs;
+use crate::registry::{FindingRegistry, InputRegistry};
+use crate::resolver::UsesResolver;
+use crate::utils::uuid_v5;
+
+/// The SPDX identifier of the root package.
+const ROOT_ID: &str = "SPDXRef-workflows";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Document {
+    spdx_version: &'static str,
+    data_license: &'static str,
+    #[serde(rename = "SPDXID")]
+    spdx_id: &'static str,
+    name: &'static str,
+    document_namespace: String,
+    creation_info: CreationInfo,
+    packages: Vec<Package>,
+    relationships: Vec<Relationship>,
+}
+
+#[derive(Serialize)]
+struct CreationInfo {
+    created: String,
+    creators: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Package {
+    #[serde(rename = "SPDXID")]
+    spdx_id: String,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version_info: Option<String>,
+    download_location: String,
+    files_analyzed: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    checksums: Vec<Checksum>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    external_refs: Vec<ExternalRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Checksum {
+    algorithm: &'static str,
+    checksum_value: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExternalRef {
+    reference_category: &'static str,
+    reference_type: &'static str,
+    reference_locator: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Relationship {
+    spdx_element_id: String,
+    relationship_type: &'static str,
+    related_spdx_element: String,
+}
+
+/// The time that the document was created, as an SPDX timestamp.
+///
+/// This respects [`SOURCE_DATE_EPOCH`], for reproducible documents.
+///
+/// [`SOURCE_DATE_EPOCH`]: https://reproducible-builds.org/specs/source-date-epoch/
+fn created() -> Result<String> {
+    let now = match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(epoch) => {
+            let epoch = epoch
+                .parse()
+                .with_context(|| format!("invalid SOURCE_DATE_EPOCH: {epoch}"))?;
+            OffsetDateTime::from_unix_timestamp(epoch)
+                .with_context(|| format!("invalid SOURCE_DATE_EPOCH: {epoch}"))?
+        }
+        Err(_) => OffsetDateTime::now_utc(),
+    };
+
+    // SPDX timestamps don't have fractional seconds.
+    Ok(now.replace_nanosecond(0)?.format(&Rfc3339)?)
+}
+
+/// Where the given action can be downloaded from, as an SPDX
+/// download location.
+fn download_location(action: &Action) -> String {
+    match action.kind {
+        ActionKind::Action | ActionKind::ReusableWorkflow => {
+            let mut location = format!(
+                "git+https://github.com/{owner}/{repo}",
+                owner = action.owner,
+                repo = action.repo
+            );
+            if let Some(git_ref) = &action.git_ref {
+                location.push('@');
+                location.push_str(git_ref);
+            }
+            if let Some(subpath) = &action.subpath {
+                location.push('#');
+                location.push_str(subpath);
+            }
+            location
+        }
+        // NOTE: SPDX has no download location syntax for images;
+        // their package URLs identify them instead. Dynamic actions
+        // can't be downloaded from anywhere in particular.
+        ActionKind::Docker | ActionKind::Dynamic => "NOASSERTION".into(),
+    }
+}
+
+/// The checksum that the given action is pinned to, if it's pinned.
+fn pinned_checksum(action: &Action) -> Option<Checksum> {
+    let value = action.pinned_digest()?;
+    let algorithm = match value.len() {
+        40 => "SHA1",
+        64 => "SHA256",
+        _ => return None,
+    };
+
+    Some(Checksum {
+        algorithm,
+        checksum_value: value.to_ascii_lowercase(),
+    })
+}
+
+/// Builds an SPDX document from the given actions, with one package per
+/// unique package URL, created at `created`.
+fn build(actions: &[Action], created: String) -> Document {
+    let mut packages = vec![Package {
+        spdx_id: ROOT_ID.into(),
+        name: "workflows".into(),
+        version_info: None,
+        download_location: "NOASSERTION".into(),
+        files_analyzed: false,
+        checksums: vec![],
+        external_refs: vec![],
+        comment: Some("The workflows and actions audited by zizmor".into()),
+    }];
+    let mut relationships = vec![Relationship {
+        spdx_element_id: "SPDXRef-DOCUMENT".into(),
+        relationship_type: "DESCRIBES",
+        related_spdx_element: ROOT_ID.into(),
+    }];
+
+    // The namespace has to be unique to this document's contents.
+    let mut name = String::new();
+
+    for (index, (purl, uses)) in group_by_purl(actions).into_iter().enumerate() {
+        // NOTE: Every use of a package URL has the same identity.
+        let action = uses[0];
+        let spdx_id = format!("SPDXRef-Package-{index}");
+
+        let mut files: Vec<String> = vec![];
+        for file in uses.iter().map(|action| action.qualified_path()) {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+
+        name.push_str(&purl);
+        name.push('\n');
+
+        relationships.push(Relationship {
+            spdx_element_id: ROOT_ID.into(),
+            relationship_type: "DEPENDS_ON",
+            related_spdx_element: spdx_id.clone(),
+        });
+
+        packages.push(Package {
+            spdx_id,
+            name: action.package_name(),
+            version_info: action.git_ref.clone(),
+            download_location: download_location(action),
+            files_analyzed: false,
+            checksums: pinned_checksum(action).into_iter().collect(),
+            external_refs: vec![ExternalRef {
+                reference_category: "PACKAGE-MANAGER",
+                reference_type: "purl",
+                reference_locator: purl,
+            }],
+            comment: Some(format!(
+                "{kind} used by {files} (third-party: {third_party}, pinned: {pinned})",
+                kind = action.kind.as_str(),
+                files = files.join(", "),
+                third_party = action.third_party,
+                pinned = action.pinned_to_sha,
+            )),
+        });
+    }
+
+    Document {
+        spdx_version: "SPDX-2.3",
+        data_license: "CC0-1.0",
+        spdx_id: "SPDXRef-DOCUMENT",
+        name: "zizmor-actions",
+        document_namespace: format!("https://docs.zizmor.sh/spdx/{}", uuid_v5(&name)),
+        creation_info: CreationInfo {
+            created,
+            creators: vec![format!("Tool: zizmor-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        packages,
+        relationships,
+    }
+}
+
+/// Output the TPA inventory as an SPDX document.
+pub(crate) fn output(
+    mut sink: impl io::Write,
+    app: &App,
+    registry: &InputRegistry,
+    allowed_orgs: &AllowedOrgs,
+    resolver: &UsesResolver,
+    results: &FindingRegistry,
+) -> Result<Summary> {
+    let actions = collect_actions(
+        registry,
+        allowed_orgs,
+        resolver,
+        results,
+        app.tpa_suppressed,
+    )?;
+
+    serde_json::to_writer_pretty(&mut sink, &build(&actions, created()?))?;
+
+    Ok(generate_summary(&actions))
+}
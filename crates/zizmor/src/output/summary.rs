@@ -0,0 +1,361 @@
+//! A summary of an entire run, for `--summary`.
+//!
+//! The summary breaks each audit's findings down by severity and by
+//! confidence, alongside
+//! the number of files they're in and the time spent in the audit, and
+//! lists the inputs that took the longest to audit.
+
+use std::collections::HashSet;
+use std::io;
+use std::ops::Add;
+use std::time::Duration;
+
+use anyhow::Result;
+use owo_colors::OwoColorize as _;
+use serde::Serialize;
+
+use crate::finding::{Confidence, Finding, Severity};
+use crate::registry::{AuditStatus, AuditSummary, FindingRegistry, serialize_millis};
+
+/// How many of the slowest inputs are listed.
+const SLOWEST_INPUTS: usize = 5;
+
+/// A count of findings at each severity.
+#[derive(Clone, Copy, Default, Serialize)]
+pub(crate) struct SeverityCounts {
+    pub(crate) unknown: usize,
+    pub(crate) informational: usize,
+    pub(crate) low: usize,
+    pub(crate) medium: usize,
+    pub(crate) high: usize,
+}
+
+impl SeverityCounts {
+    /// Counts `findings` by severity, weighted per `--rollup-count`.
+    pub(crate) fn of<'a>(
+        results: &FindingRegistry,
+        findings: impl IntoIterator<Item = &'a Finding<'a>>,
+    ) -> Self {
+        let mut counts = Self::default();
+        for finding in findings {
+            counts.record(finding.determinations.severity, results.weight(finding));
+        }
+        counts
+    }
+
+    /// Counts `weight` more findings at `severity`.
+    pub(crate) fn record(&mut self, severity: Severity, weight: usize) {
+        match severity {
+            Severity::Unknown => self.unknown += weight,
+            Severity::Informational => self.informational += weight,
+            Severity::Low => self.low += weight,
+            Severity::Medium => self.medium += weight,
+            Severity::High => self.high += weight,
+        }
+    }
+
+    /// The total count, at every severity.
+    pub(crate) fn total(&self) -> usize {
+        self.unknown + self.informational + self.low + self.medium + self.high
+    }
+
+    /// The count at `threshold` or any higher severity.
+    pub(crate) fn at_or_above(&self, threshold: Severity) -> usize {
+        [
+            (Severity::Unknown, self.unknown),
+            (Severity::Informational, self.informational),
+            (Severity::Low, self.low),
+            (Severity::Medium, self.medium),
+            (Severity::High, self.high),
+        ]
+        .into_iter()
+        .filter(|(severity, _)| *severity >= threshold)
+        .map(|(_, count)| count)
+        .sum()
+    }
+}
+
+impl Add for SeverityCounts {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            unknown: self.unknown + other.unknown,
+            informational: self.informational + other.informational,
+            low: self.low + other.low,
+            medium: self.medium + other.medium,
+            high: self.high + other.high,
+        }
+    }
+}
+
+/// A count of findings at each confidence, e.g. to tell the findings that
+/// are definite from those that need a human to look at them.
+#[derive(Clone, Copy, Default, Serialize)]
+pub(crate) struct ConfidenceCounts {
+    pub(crate) unknown: usize,
+    pub(crate) low: usize,
+    pub(crate) medium: usize,
+    pub(crate) high: usize,
+}
+
+impl ConfidenceCounts {
+    /// Counts `findings` by confidence, weighted per `--rollup-count`.
+    pub(crate) fn of<'a>(
+        results: &FindingRegistry,
+        findings: impl IntoIterator<Item = &'a Finding<'a>>,
+    ) -> Self {
+        let mut counts = Self::default();
+        for finding in findings {
+            let weight = results.weight(finding);
+            match finding.determinations.confidence {
+                Confidence::Unknown => counts.unknown += weight,
+                Confidence::Low => counts.low += weight,
+                Confidence::Medium => counts.medium += weight,
+                Confidence::High => counts.high += weight,
+            }
+        }
+        counts
+    }
+}
+
+/// The findings of a single audit that ran.
+#[derive(Serialize)]
+struct RuleSummary {
+    ident: &'static str,
+    findings: usize,
+    severities: SeverityCounts,
+    confidences: ConfidenceCounts,
+    /// The number of distinct files with findings from this audit.
+    files: usize,
+    #[serde(rename = "wall_time_ms", serialize_with = "serialize_millis")]
+    wall_time: Duration,
+}
+
+/// The time spent auditing a single input.
+#[derive(Serialize)]
+struct InputTime {
+    input: String,
+    #[serde(rename = "wall_time_ms", serialize_with = "serialize_millis")]
+    wall_time: Duration,
+}
+
+#[derive(Serialize)]
+struct Totals {
+    inputs: usize,
+    findings: usize,
+    severities: SeverityCounts,
+    confidences: ConfidenceCounts,
+    files: usize,
+    ignored: usize,
+    suppressed: usize,
+    filtered: usize,
+    baselined: usize,
+    #[serde(rename = "wall_time_ms", serialize_with = "serialize_millis")]
+    wall_time: Duration,
+}
+
+/// A summary of a run's findings and timing.
+#[derive(Serialize)]
+pub(crate) struct RunSummary {
+    rules: Vec<RuleSummary>,
+    totals: Totals,
+    slowest_inputs: Vec<InputTime>,
+    /// The audits that didn't run, because they were skipped or
+    /// failed to load.
+    not_run: Vec<AuditSummary>,
+}
+
+/// The path of the file that `finding` is in.
+fn finding_file<'a>(finding: &'a Finding<'_>) -> Option<&'a str> {
+    finding
+        .locations
+        .iter()
+        .find(|location| location.symbolic.is_primary())
+        .map(|location| location.symbolic.key.presentation_path())
+}
+
+impl RunSummary {
+    /// Summarizes a run, from each audit's status, the run's findings,
+    /// and the time spent auditing each input.
+    pub(crate) fn new(
+        audits: Vec<AuditSummary>,
+        results: &FindingRegistry,
+        input_times: Vec<(String, Duration)>,
+    ) -> Self {
+        let mut rules = vec![];
+        let mut not_run = vec![];
+        for audit in audits {
+            let AuditStatus::Ran { wall_time, .. } = audit.status else {
+                not_run.push(audit);
+                continue;
+            };
+
+            let findings = results
+                .findings()
+                .iter()
+                .filter(|finding| finding.ident == audit.ident)
+                .collect::<Vec<_>>();
+
+            rules.push(RuleSummary {
+                ident: audit.ident,
+                findings: findings.iter().map(|finding| results.weight(finding)).sum(),
+                severities: SeverityCounts::of(results, findings.iter().copied()),
+                confidences: ConfidenceCounts::of(results, findings.iter().copied()),
+                files: findings
+                    .iter()
+                    .filter_map(|finding| finding_file(finding))
+                    .collect::<HashSet<_>>()
+                    .len(),
+                wall_time,
+            });
+        }
+
+        let wall_time = rules.iter().map(|rule| rule.wall_time).sum();
+        let totals = Totals {
+            inputs: input_times.len(),
+            findings: results.tally(results.findings()),
+            severities: SeverityCounts::of(results, results.findings()),
+            confidences: ConfidenceCounts::of(results, results.findings()),
+            files: results
+                .findings()
+                .iter()
+                .filter_map(finding_file)
+                .collect::<HashSet<_>>()
+                .len(),
+            ignored: results.tally(results.ignored()),
+            suppressed: results.tally(results.suppressed()),
+            filtered: results.tally(results.filtered()),
+            baselined: results.tally(results.baselined()),
+            wall_time,
+        };
+
+        let mut slowest_inputs = input_times
+            .into_iter()
+            .map(|(input, wall_time)| InputTime { input, wall_time })
+            .collect::<Vec<_>>();
+        slowest_inputs.sort_by_key(|input| std::cmp::Reverse(input.wall_time));
+        slowest_inputs.truncate(SLOWEST_INPUTS);
+
+        Self {
+            rules,
+            totals,
+            slowest_inputs,
+            not_run,
+        }
+    }
+
+    /// Renders the summary as a table.
+    pub(crate) fn render(&self, sink: &mut impl io::Write) -> Result<()> {
+        let width = self
+            .rules
+            .iter()
+            .map(|rule| rule.ident.len())
+            .chain(self.not_run.iter().map(|audit| audit.ident.len()))
+            .max()
+            .unwrap_or(0)
+            .max("total".len());
+
+        writeln!(sink, "{}", "run summary:".bold())?;
+        writeln!(
+            sink,
+            "  {rule:width$}  {unk:>4} {info:>4} {low:>4} {med:>4} {high:>4}  {files:>5}  {time:>10}",
+            rule = "rule",
+            unk = "unk",
+            info = "info",
+            low = "low",
+            med = "med",
+            high = "high",
+            files = "files",
+            time = "time",
+        )?;
+
+        let row = |sink: &mut dyn io::Write,
+                   ident: &str,
+                   severities: &SeverityCounts,
+                   files: usize,
+                   wall_time: Duration|
+         -> io::Result<()> {
+            writeln!(
+                sink,
+                "  {ident:width$}  {unk:>4} {info:>4} {low:>4} {med:>4} {high:>4}  {files:>5}  {time:>10}",
+                unk = severities.unknown,
+                info = severities.informational,
+                low = severities.low,
+                med = severities.medium,
+                high = severities.high,
+                time = format!("{wall_time:.2?}"),
+            )
+        };
+
+        for rule in &self.rules {
+            row(
+                sink,
+                rule.ident,
+                &rule.severities,
+                rule.files,
+                rule.wall_time,
+            )?;
+        }
+        row(
+            sink,
+            "total",
+            &self.totals.severities,
+            self.totals.files,
+            self.totals.wall_time,
+        )?;
+
+        let totals = &self.totals;
+        writeln!(
+            sink,
+            "  {findings} finding{s} in {inputs} input{is}; {ignored} ignored, \
+             {suppressed} suppressed, {filtered} filtered, {baselined} baselined",
+            findings = totals.findings,
+            s = if totals.findings == 1 { "" } else { "s" },
+            inputs = totals.inputs,
+            is = if totals.inputs == 1 { "" } else { "s" },
+            ignored = totals.ignored,
+            suppressed = totals.suppressed,
+            filtered = totals.filtered,
+            baselined = totals.baselined,
+        )?;
+        writeln!(
+            sink,
+            "  by confidence: {high} high, {medium} medium, {low} low, {unknown} unknown",
+            high = totals.confidences.high,
+            medium = totals.confidences.medium,
+            low = totals.confidences.low,
+            unknown = totals.confidences.unknown,
+        )?;
+
+        if !self.not_run.is_empty() {
+            writeln!(sink, "{}", "not run:".bold())?;
+            for audit in &self.not_run {
+                let (status, reason) = match &audit.status {
+                    AuditStatus::Skipped { reason } => ("skipped", reason),
+                    AuditStatus::Failed { error } => ("failed ", error),
+                    AuditStatus::Ran { .. } => unreachable!(),
+                };
+                writeln!(
+                    sink,
+                    "  {ident:width$}  {status}  {reason}",
+                    ident = audit.ident
+                )?;
+            }
+        }
+
+        if !self.slowest_inputs.is_empty() {
+            writeln!(sink, "{}", "slowest inputs:".bold())?;
+            for input in &self.slowest_inputs {
+                writeln!(
+                    sink,
+                    "  {time:>10}  {path}",
+                    time = format!("{:.2?}", input.wall_time),
+                    path = input.input,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+//! Allowlist generation from the third-party actions currently in use,
+//! i.e. `--emit-allowlist`.
+//!
+//! The generated file is in the `--tpa-allowlist-file` format, with each
+//! org preceded by comments listing the actions (and files) that use it.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+
+use anyhow::Result;
+
+use super::tpa_list::{Action, extract_actions};
+use crate::audit::unpinned_uses::AllowedOrgs;
+use crate::registry::InputRegistry;
+use crate::resolver::UsesResolver;
+
+const HEADER: &str = "# Generated by `zizmor --emit-allowlist`.";
+
+/// The actions that caused each org's inclusion, keyed by (lowercased) org,
+/// along with the files that use each action.
+type OrgUsage = BTreeMap<String, BTreeMap<String, BTreeSet<String>>>;
+
+/// Group the given actions' third-party orgs.
+///
+/// Images without an owner (i.e. on private registries) have no org to
+/// allowlist, and so are skipped.
+fn group_by_org(actions: &[Action]) -> OrgUsage {
+    let mut orgs = OrgUsage::new();
+    for action in actions {
+        if !action.third_party || action.owner.is_empty() {
+            continue;
+        }
+
+        orgs.entry(action.owner.to_lowercase())
+            .or_default()
+            .entry(action.name())
+            .or_default()
+            .insert(action.qualified_path());
+    }
+
+    orgs
+}
+
+/// Render the allowlist for the given orgs.
+fn render(mut sink: impl io::Write, orgs: &OrgUsage) -> Result<()> {
+    writeln!(sink, "{HEADER}")?;
+
+    if orgs.is_empty() {
+        writeln!(sink, "# No third-party actions were found.")?;
+        return Ok(());
+    }
+
+    for (org, actions) in orgs {
+        writeln!(sink)?;
+        for (action, files) in actions {
+            let files = files.iter().map(String::as_str).collect::<Vec<_>>();
+            writeln!(sink, "# {action} ({files})", files = files.join(", "))?;
+        }
+        writeln!(sink, "{org}")?;
+    }
+
+    Ok(())
+}
+
+/// Write an allowlist of every third-party org used by any input
+/// in `registry`.
+///
+/// Only the official orgs are considered first-party here, so that
+/// orgs that are already allowlisted are still listed.
+///
+/// Returns the number of orgs written.
+pub(crate) fn output(sink: impl io::Write, registry: &InputRegistry) -> Result<usize> {
+    let actions = extract_actions(registry, &AllowedOrgs::official(), &UsesResolver::default())?;
+
+    let orgs = group_by_org(&actions);
+    render(sink, &orgs)?;
+
+    Ok(orgs.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OrgUsage, render};
+
+    #[test]
+    fn test_render() {
+        let mut out = vec![];
+        render(&mut out, &OrgUsage::new()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "# Generated by `zizmor --emit-allowlist`.\n# No third-party actions were found.\n"
+        );
+
+        let mut orgs = OrgUsage::new();
+        let foo = orgs.entry("foo".into()).or_default();
+        foo.entry("foo/bar".into())
+            .or_default()
+            .extend(["b.yml".into(), "a.yml".into()]);
+        foo.entry("foo/baz/sub".into())
+            .or_default()
+            .insert("a.yml".into());
+        orgs.entry("qux".into())
+            .or_default()
+            .entry("docker://qux/image".into())
+            .or_default()
+            .insert("action.yml".into());
+
+        let mut out = vec![];
+        render(&mut out, &orgs).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "# Generated by `zizmor --emit-allowlist`.\n\
+             \n\
+             # foo/bar (a.yml, b.yml)\n\
+             # foo/baz/sub (a.yml)\n\
+             foo\n\
+             \n\
+             # docker://qux/image (action.yml)\n\
+             qux\n"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! shields.io endpoint badge output for the third-party action report.
+//!
+//! See: <https://shields.io/badges/endpoint-badge>
+
+use std::io;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::tpa_list::{Summary, collect_actions, generate_summary};
+use crate::App;
+use crate::audit::unpinned_uses::{AllowedOrgs, BadgeThresholds};
+use crate::registry::{FindingRegistry, InputRegistry};
+use crate::resolver::UsesResolver;
+
+/// A shields.io endpoint badge.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Badge {
+    /// Always 1, per the endpoint schema.
+    schema_version: u32,
+    label: &'static str,
+    message: String,
+    color: &'static str,
+}
+
+/// Builds the badge for the given summary, i.e. the number of pinned
+/// third-party actions out of all third-party actions.
+fn build(summary: &Summary, thresholds: &BadgeThresholds) -> Badge {
+    let pinned = summary.pinned_third_party;
+    let total = summary.pinned_third_party + summary.unpinned_third_party;
+
+    Badge {
+        schema_version: 1,
+        label: "pinned actions",
+        message: format!("{pinned}/{total}"),
+        color: thresholds.color(pinned, total),
+    }
+}
+
+/// Output the TPA report's summary as a shields.io endpoint badge.
+pub(crate) fn output(
+    sink: impl io::Write,
+    app: &App,
+    registry: &InputRegistry,
+    allowed_orgs: &AllowedOrgs,
+    resolver: &UsesResolver,
+    thresholds: &BadgeThresholds,
+    results: &FindingRegistry,
+) -> Result<Summary> {
+    let actions = collect_actions(
+        registry,
+        allowed_orgs,
+        resolver,
+        results,
+        app.tpa_suppressed,
+    )?;
+    let summary = generate_summary(&actions);
+
+    serde_json::to_writer_pretty(sink, &build(&summary, thresholds))?;
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Badge, build};
+    use crate::audit::unpinned_uses::BadgeThresholds;
+    use crate::output::tpa_list::Summary;
+
+    fn summary(pinned_third_party: usize, unpinned_third_party: usize) -> Summary {
+        Summary {
+            total_actions: pinned_third_party + unpinned_third_party,
+            unpinned_third_party,
+            pinned_third_party,
+            official_actions: 0,
+            docker_images: 0,
+            unpinned_third_party_docker_images: 0,
+            reusable_workflows: 0,
+            unpinned_third_party_reusable_workflows: 0,
+            by_file: Default::default(),
+            by_owner: Default::default(),
+            by_repository: Default::default(),
+            suppressed: 0,
+        }
+    }
+
+    #[test]
+    fn test_badge_colors() {
+        let thresholds = BadgeThresholds::default();
+
+        for (pinned, unpinned, color) in [
+            (0, 0, "green"),
+            (42, 0, "green"),
+            (999, 1, "yellow"),
+            (37, 5, "yellow"),
+            (8, 2, "yellow"),
+            (7, 3, "red"),
+            (0, 1, "red"),
+        ] {
+            assert_eq!(
+                build(&summary(pinned, unpinned), &thresholds).color,
+                color,
+                "{pinned}/{}",
+                pinned + unpinned
+            );
+        }
+
+        let lenient = BadgeThresholds {
+            green: 50,
+            yellow: 0,
+        };
+        assert_eq!(build(&summary(1, 1), &lenient).color, "green");
+        assert_eq!(build(&summary(0, 1), &lenient).color, "yellow");
+    }
+
+    #[test]
+    fn test_badge_json() {
+        let badge = build(&summary(37, 5), &BadgeThresholds::default());
+        assert_eq!(
+            badge,
+            Badge {
+                schema_version: 1,
+                label: "pinned actions",
+                message: "37/42".into(),
+                color: "yellow",
+            }
+        );
+
+        assert_eq!(
+            serde_json::to_string(&badge).unwrap(),
+            r#"{"schemaVersion":1,"label":"pinned actions","message":"37/42","color":"yellow"}"#
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! CSV output for the third-party action inventory.
+//!
+//! Each row is a single action occurrence, using the same data source as
+//! the JSON report so that counts always agree between formats.
+
+use std::borrow::Cow;
+use std::io;
+
+use anyhow::Result;
+
+use super::tpa_list::{Action, Summary, collect_actions, generate_summary};
+use crate::App;
+use crate::audit::unpinned_uses::AllowedOrgs;
+use crate::registry::{FindingRegistry, InputRegistry};
+use crate::resolver::UsesResolver;
+
+const HEADER: &[&str] = &[
+    "file",
+    "line",
+    "owner",
+    "repo",
+    "subpath",
+    "ref",
+    "pinned_to_sha",
+    "third_party",
+    "kind",
+];
+
+/// Quotes `field` per RFC 4180, if it contains any characters that
+/// would otherwise be ambiguous.
+fn field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+fn write_row<'a>(
+    sink: &mut impl io::Write,
+    fields: impl IntoIterator<Item = Cow<'a, str>>,
+) -> Result<()> {
+    let row = fields.into_iter().collect::<Vec<_>>().join(",");
+    writeln!(sink, "{row}")?;
+
+    Ok(())
+}
+
+/// Renders the given actions as CSV, including a header row and
+/// leaving out any suppressed actions.
+pub(crate) fn render(sink: &mut impl io::Write, actions: &[Action]) -> Result<()> {
+    write_row(sink, HEADER.iter().map(|h| Cow::Borrowed(*h)))?;
+
+    for action in actions.iter().filter(|action| !action.suppressed) {
+        write_row(
+            sink,
+            [
+                field(&action.file_path),
+                Cow::Owned(action.line_number.to_string()),
+                field(&action.owner),
+                field(&action.repo),
+                field(action.subpath.as_deref().unwrap_or_default()),
+                field(action.git_ref.as_deref().unwrap_or_default()),
+                Cow::Owned(action.pinned_to_sha.to_string()),
+                Cow::Owned(action.third_party.to_string()),
+                Cow::Borrowed(action.kind.as_str()),
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Output the TPA inventory as CSV.
+pub(crate) fn output(
+    mut sink: impl io::Write,
+    app: &App,
+    registry: &InputRegistry,
+    allowed_orgs: &AllowedOrgs,
+    resolver: &UsesResolver,
+    results: &FindingRegistry,
+) -> Result<Summary> {
+    let actions = collect_actions(
+        registry,
+        allowed_orgs,
+        resolver,
+        results,
+        app.tpa_suppressed,
+    )?;
+
+    render(&mut sink, &actions)?;
+
+    Ok(generate_summary(&actions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::field;
+
+    #[test]
+    fn test_field_quoting() {
+        for (input, expected) in &[
+            ("plain", "plain"),
+            ("", ""),
+            ("a,b", "\"a,b\""),
+            ("say \"hi\"", "\"say \"\"hi\"\"\""),
+            ("multi\nline", "\"multi\nline\""),
+        ] {
+            assert_eq!(field(input), *expected);
+        }
+    }
+}
@@ -1,227 +1,2506 @@
 //! Format for listing third-party actions not pinned to commit SHAs.
 //! Can output either a simple text list or a comprehensive JSON report.
 
-use std::collections::HashSet;
-use std::io;
-use std::fs::File;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufWriter};
 use std::path::Path;
 
-use anyhow::Result;
-use regex::Regex;
-use serde::Serialize;
+use anyhow::{Context as _, Result};
+use clap::ValueEnum as _;
+use github_actions_models::action;
+use github_actions_models::common::{DockerUses, RepositoryUses, Uses};
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
 
-use crate::finding::Finding;
+use crate::audit::AuditInput;
+use crate::audit::unpinned_uses::AllowedOrgs;
+use crate::finding::{FindingTag, Location};
+use crate::lock::Lockfile;
+use crate::models::uses::{DockerUsesExt as _, UsesExt as _};
+use crate::models::{AsDocument as _, Job, JobExt as _, StepCommon as _, Workflow};
+use crate::output::Sink;
+use crate::registry::{FindingRegistry, InputKey, InputRegistry};
+use crate::resolver::UsesResolver;
+use crate::score::Score;
+use crate::{App, TpaFailOn, TpaGroupBy, TpaShow, TpaSuppressed};
+
+/// The kind of dependency an entry in the report refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ActionKind {
+    /// A GitHub action, e.g. `uses: owner/repo@ref`.
+    Action,
+    /// A container image, e.g. `uses: docker://image:tag`.
+    Docker,
+    /// A reusable workflow, e.g. `uses: owner/repo/.github/workflows/x.yml@ref`.
+    ReusableWorkflow,
+    /// An action or image that's selected by an expression, e.g.
+    /// `uses: ${{ matrix.action }}`.
+    Dynamic,
+}
+
+impl ActionKind {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            ActionKind::Action => "action",
+            ActionKind::Docker => "docker",
+            ActionKind::ReusableWorkflow => "reusable-workflow",
+            ActionKind::Dynamic => "dynamic",
+        }
+    }
+}
 
 /// An action extracted from a workflow file
-#[derive(Debug, Serialize, Clone)]
-struct Action {
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub(crate) struct Action {
+    /// The kind of action
+    pub(crate) kind: ActionKind,
     /// The action reference (e.g., "actions/checkout@v3")
-    reference: String,
-    /// Whether the action is pinned to a commit SHA
-    pinned_to_sha: bool,
+    pub(crate) reference: String,
+    /// The action's owner (e.g., "actions"), or the image's namespace
+    pub(crate) owner: String,
+    /// The action's repository (e.g., "checkout"), or the image's name
+    pub(crate) repo: String,
+    /// The action's subpath within its repository, if any
+    pub(crate) subpath: Option<String>,
+    /// The GitHub host that the action is on (e.g., "github.com"); absent
+    /// for Docker images and dynamic actions
+    pub(crate) host: Option<String>,
+    /// The image's registry, if explicitly specified
+    pub(crate) registry: Option<String>,
+    /// The action's ref (e.g., "v3"), or the image's tag or digest, if any
+    #[serde(rename = "ref")]
+    pub(crate) git_ref: Option<String>,
+    /// Whether the action is pinned to a commit SHA, or the image to a digest
+    pub(crate) pinned_to_sha: bool,
+    /// Whether the image is pinned to a floating tag, like `latest`;
+    /// always false for actions
+    pub(crate) floating_tag: bool,
     /// Whether the action is from a third party (non-trusted organization)
-    third_party: bool,
+    pub(crate) third_party: bool,
     /// Full line where the action is defined
-    line: String,
+    pub(crate) line: String,
     /// File path where the action is defined
-    file_path: String,
+    pub(crate) file_path: String,
+    /// The `owner/repo` slug of the repository that `file_path` is in,
+    /// for actions used by remote inputs
+    pub(crate) repository: Option<String>,
+    /// The (1-based) line number of the action's `uses:` clause
+    pub(crate) line_number: usize,
+    /// The (1-based) column of the action's `uses:` clause
+    pub(crate) column: usize,
+    /// The ID of the job that uses the action; absent for composite
+    /// action steps
+    pub(crate) job_id: Option<String>,
+    /// The (0-based) index of the step that uses the action, within its job;
+    /// absent for reusable workflows, which are used by the job itself
+    pub(crate) step_index: Option<usize>,
+    /// The commit that the action's ref currently resolves to, if the
+    /// action is unpinned and the ref could be resolved
+    pub(crate) suggested_sha: Option<String>,
+    /// The most specific tag pointing at `suggested_sha`, if any
+    pub(crate) suggested_tag: Option<String>,
+    /// The commit that the action's ref was locked to in `--lockfile`,
+    /// if the action is unpinned and its ref is locked
+    pub(crate) locked_sha: Option<String>,
+    /// The commit that the action's locked ref currently resolves to, if
+    /// it could be resolved; differs from `locked_sha` if the ref moved
+    pub(crate) current_sha: Option<String>,
+    /// Whether the action's third-party unpinned finding was ignored; suppressed
+    /// actions are only reported with `--tpa-suppressed=mark`
+    pub(crate) suppressed: bool,
+    /// Whether the action is used by a remote composite action that was
+    /// only audited transitively, i.e. with `--audit-transitive`
+    pub(crate) transitive: bool,
+    /// Whether the action is used by a workflow template, i.e. one in an
+    /// org's `workflow-templates/`, which new repositories are seeded from
+    pub(crate) template: bool,
 }
 
+impl Action {
+    /// The action's name without its ref, e.g. `owner/repo[/subpath]`
+    /// or `docker://[registry/]image`.
+    ///
+    /// Dynamic actions have no name besides their reference.
+    pub(crate) fn name(&self) -> String {
+        match (self.kind, &self.subpath) {
+            (ActionKind::Dynamic, _) => self.reference.clone(),
+            (ActionKind::Docker, _) => {
+                let image = if self.owner.is_empty() {
+                    self.repo.clone()
+                } else {
+                    format!("{}/{}", self.owner, self.repo)
+                };
+
+                match &self.registry {
+                    Some(registry) => format!("docker://{registry}/{image}"),
+                    None => format!("docker://{image}"),
+                }
+            }
+            (_, Some(subpath)) => format!("{}/{}/{subpath}", self.owner, self.repo),
+            (_, None) => format!("{}/{}", self.owner, self.repo),
+        }
+    }
+
+    /// The file path where the action is defined, qualified with its
+    /// repository (if any), e.g. `owner/repo/.github/workflows/ci.yml`.
+    pub(crate) fn qualified_path(&self) -> String {
+        qualified_path(self.repository.as_deref(), &self.file_path)
+    }
+
+    /// The action's name as a package, i.e. [`Action::name`] without
+    /// the `docker://` prefix for images.
+    pub(crate) fn package_name(&self) -> String {
+        let name = self.name();
+        match name.strip_prefix("docker://") {
+            Some(image) => image.into(),
+            None => name,
+        }
+    }
+
+    /// The action's package URL, e.g. `pkg:github/actions/checkout@v4`
+    /// or `pkg:docker/library/alpine@3.20`.
+    pub(crate) fn purl(&self) -> String {
+        let mut purl = match self.kind {
+            // NOTE: GitHub names are case-insensitive, and the `github`
+            // purl type requires them to be lowercased.
+            ActionKind::Action | ActionKind::ReusableWorkflow => format!(
+                "pkg:github/{owner}/{repo}",
+                owner = purl_encode(&self.owner.to_ascii_lowercase(), false),
+                repo = purl_encode(&self.repo.to_ascii_lowercase(), false),
+            ),
+            ActionKind::Docker if self.owner.is_empty() => {
+                format!("pkg:docker/{name}", name = purl_encode(&self.repo, false))
+            }
+            ActionKind::Docker => format!(
+                "pkg:docker/{namespace}/{name}",
+                namespace = purl_encode(&self.owner, false),
+                name = purl_encode(&self.repo, true),
+            ),
+            // NOTE: There's no package to identify, so the reference
+            // itself is the (generic) name.
+            ActionKind::Dynamic => {
+                return format!("pkg:generic/{}", purl_encode(&self.reference, false));
+            }
+        };
+
+        if let Some(git_ref) = &self.git_ref {
+            purl.push('@');
+            purl.push_str(&purl_encode(git_ref, false));
+        }
+
+        if let Some(registry) = &self.registry {
+            purl.push_str("?repository_url=");
+            purl.push_str(&purl_encode(registry, true));
+        }
+
+        if let Some(subpath) = &self.subpath {
+            purl.push('#');
+            purl.push_str(&purl_encode(subpath, true));
+        }
+
+        purl
+    }
+
+    /// The commit SHA (or image digest, without its algorithm) that the
+    /// action is pinned to, if it's pinned.
+    pub(crate) fn pinned_digest(&self) -> Option<&str> {
+        if !self.pinned_to_sha {
+            return None;
+        }
+
+        let pin = self.git_ref.as_deref()?;
+        Some(pin.strip_prefix("sha256:").unwrap_or(pin))
+    }
+}
+
+/// Percent-encodes `value` for use in a package URL, leaving only
+/// unreserved characters (and `/`, if `keep_slashes`) as-is.
+fn purl_encode(value: &str, keep_slashes: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slashes => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Groups `actions` by package URL, in package URL order.
+///
+/// This is the package inventory that the SBOM formats report, with
+/// each package's uses in their original order.
+pub(crate) fn group_by_purl(actions: &[Action]) -> BTreeMap<String, Vec<&Action>> {
+    let mut packages: BTreeMap<String, Vec<&Action>> = BTreeMap::new();
+    for action in actions {
+        packages.entry(action.purl()).or_default().push(action);
+    }
+    packages
+}
+
+/// Qualifies `path` with `repository`, if there is one, so that paths
+/// from different repositories don't collide.
+pub(crate) fn qualified_path(repository: Option<&str>, path: &str) -> String {
+    match repository {
+        Some(repository) => format!("{repository}/{path}"),
+        None => path.into(),
+    }
+}
+
+impl Action {
+    fn new(
+        identity: UsesIdentity,
+        key: &InputKey,
+        location: &Location,
+        job_id: Option<&str>,
+        step_index: Option<usize>,
+    ) -> Self {
+        let UsesIdentity {
+            kind,
+            reference,
+            owner,
+            repo,
+            subpath,
+            host,
+            registry,
+            git_ref,
+            pinned_to_sha,
+            floating_tag,
+            third_party,
+        } = identity;
+
+        Self {
+            kind,
+            line: format!("uses: {reference}"),
+            reference,
+            owner,
+            repo,
+            subpath,
+            host,
+            registry,
+            git_ref,
+            pinned_to_sha,
+            floating_tag,
+            third_party,
+            file_path: key.presentation_path().into(),
+            repository: key.repository(),
+            line_number: location.concrete.location.start_point.row + 1,
+            column: location.concrete.location.start_point.column + 1,
+            job_id: job_id.map(Into::into),
+            step_index,
+            suggested_sha: None,
+            suggested_tag: None,
+            locked_sha: None,
+            current_sha: None,
+            suppressed: false,
+            transitive: key.is_transitive(),
+            template: key.is_template(),
+        }
+    }
+}
+
+/// The parts of an `uses:` clause that identify the dependency it refers to.
+struct UsesIdentity {
+    kind: ActionKind,
+    reference: String,
+    owner: String,
+    repo: String,
+    subpath: Option<String>,
+    host: Option<String>,
+    registry: Option<String>,
+    git_ref: Option<String>,
+    pinned_to_sha: bool,
+    floating_tag: bool,
+    third_party: bool,
+}
+
+/// The current version of the [`ActionReport`] schema.
+///
+/// This must be bumped whenever the report's serialized shape changes,
+/// i.e. when fields are added, removed, renamed, or change type.
+///
+/// Version history, with the full shape given by `test_report_schema` below:
+///
+/// * Version 1: the report has `schema_version`, `actions`, and `summary`.
+/// * Version 2: adds `inconsistent_pins`.
+/// * Version 3: adds `actions[].suppressed` and `summary.suppressed`.
+/// * Version 4: adds `actions[].repository` and `summary.by_repository`.
+/// * Version 5: adds `actions[].transitive`.
+/// * Version 6: adds `actions[].floating_tag`.
+/// * Version 7: adds `actions[].locked_sha` and `actions[].current_sha`.
+/// * Version 8: adds the `dynamic` kind.
+/// * Version 9: adds `actions[].template`.
+/// * Version 10: adds `score`, with `--score`.
+/// * Version 11: adds `actions[].host`.
+pub(crate) const SCHEMA_VERSION: u32 = 11;
+
+/// Where the JSON report is written, when `--tpa-report` isn't given.
+pub(crate) const DEFAULT_REPORT: &str = "all_actions.json";
+
 /// Report structure for JSON output
-#[derive(Debug, Serialize)]
+#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
 struct ActionReport {
-    /// All actions found in the repository
+    /// The version of the report's schema; see [`SCHEMA_VERSION`]
+    schema_version: u32,
+    /// All actions found, sorted by repository and file and then
+    /// by position within the file
     actions: Vec<Action>,
     /// Summary statistics
     summary: Summary,
+    /// Actions used with more than one distinct ref, sorted by action
+    inconsistent_pins: Vec<InconsistentPin>,
+    /// The supply-chain score, with `--score`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    score: Option<Score>,
+}
+
+/// An action that's used with more than one distinct ref.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub(crate) struct InconsistentPin {
+    /// The action's name, without its ref (e.g. "actions/checkout")
+    pub(crate) action: String,
+    /// Each distinct ref the action is used with, sorted by ref
+    pub(crate) refs: Vec<PinUsage>,
+}
+
+/// A single ref of an [`InconsistentPin`], and where it's used.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub(crate) struct PinUsage {
+    /// The ref (e.g. "v4", or a commit SHA)
+    #[serde(rename = "ref")]
+    pub(crate) git_ref: String,
+    /// The files using the action at this ref, sorted and deduplicated
+    pub(crate) files: Vec<String>,
 }
 
 /// Summary statistics for the report
-#[derive(Debug, Serialize)]
-struct Summary {
-    /// Total number of actions found
-    total_actions: usize,
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub(crate) struct Summary {
+    /// Total number of actions found, excluding suppressed actions
+    pub(crate) total_actions: usize,
     /// Number of unpinned third-party actions
-    unpinned_third_party: usize,
+    pub(crate) unpinned_third_party: usize,
     /// Number of pinned third-party actions
-    pinned_third_party: usize,
+    pub(crate) pinned_third_party: usize,
     /// Number of official actions
-    official_actions: usize,
+    pub(crate) official_actions: usize,
+    /// Number of Docker images, of any provenance
+    pub(crate) docker_images: usize,
+    /// Number of third-party Docker images not pinned to a digest
+    pub(crate) unpinned_third_party_docker_images: usize,
+    /// Number of reusable workflows, of any provenance
+    pub(crate) reusable_workflows: usize,
+    /// Number of third-party reusable workflows not pinned to a commit SHA
+    pub(crate) unpinned_third_party_reusable_workflows: usize,
+    /// Per-file breakdown, keyed by file path (qualified with its
+    /// repository, for remote inputs)
+    pub(crate) by_file: BTreeMap<String, Breakdown>,
+    /// Per-owner breakdown, keyed by action owner
+    pub(crate) by_owner: BTreeMap<String, Breakdown>,
+    /// Per-repository breakdown, keyed by the `owner/repo` of the audited
+    /// repository; empty unless remote repositories were audited
+    pub(crate) by_repository: BTreeMap<String, Breakdown>,
+    /// Number of suppressed actions, which aren't included in any
+    /// of the other counts
+    pub(crate) suppressed: usize,
+}
+
+/// Summary statistics for a subset of the report
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub(crate) struct Breakdown {
+    /// Total number of actions in this subset
+    pub(crate) total: usize,
+    /// Number of unpinned third-party actions in this subset
+    pub(crate) unpinned_third_party: usize,
+    /// Number of pinned third-party actions in this subset
+    pub(crate) pinned_third_party: usize,
+    /// Number of official actions in this subset
+    pub(crate) official: usize,
+}
+
+impl Breakdown {
+    fn add(&mut self, action: &Action) {
+        self.total += 1;
+        match (action.third_party, action.pinned_to_sha) {
+            (true, false) => self.unpinned_third_party += 1,
+            (true, true) => self.pinned_third_party += 1,
+            (false, _) => self.official += 1,
+        }
+    }
+}
+
+/// Renders a repository `uses:` back into its `owner/repo[/subpath][@ref]` form.
+pub(crate) fn reference(uses: &RepositoryUses) -> String {
+    let mut reference = format!("{owner}/{repo}", owner = uses.owner, repo = uses.repo);
+
+    if let Some(subpath) = &uses.subpath {
+        reference.push('/');
+        reference.push_str(subpath);
+    }
+
+    if let Some(git_ref) = &uses.git_ref {
+        reference.push('@');
+        reference.push_str(git_ref);
+    }
+
+    reference
+}
+
+/// Renders a Docker `uses:` back into its `docker://[registry/]image[:tag|@hash]` form.
+pub(crate) fn docker_reference(uses: &DockerUses) -> String {
+    let mut reference = String::from("docker://");
+
+    if let Some(registry) = &uses.registry {
+        reference.push_str(registry);
+        reference.push('/');
+    }
+
+    reference.push_str(&uses.image);
+
+    if let Some(hash) = &uses.hash {
+        reference.push('@');
+        reference.push_str(hash);
+    } else if let Some(tag) = &uses.tag {
+        reference.push(':');
+        reference.push_str(tag);
+    }
+
+    reference
+}
+
+/// Registries whose images are published by arbitrary third parties.
+/// Images on any other (e.g. private) registry are considered first-party.
+const PUBLIC_REGISTRIES: &[&str] = &[
+    "docker.io",
+    "index.docker.io",
+    "registry-1.docker.io",
+    "registry.hub.docker.com",
+    "ghcr.io",
+];
+
+/// Identify the dependency a `uses:` clause refers to, if it's one
+/// we report on.
+fn identify(
+    uses: &Uses,
+    allowed_orgs: &AllowedOrgs,
+    resolver: &UsesResolver,
+) -> Option<UsesIdentity> {
+    match uses {
+        // Local actions are controlled by the repository.
+        Uses::Local(_) => None,
+        // We can't tell what a dynamic `uses:` refers to, so we assume
+        // the worst: that it's an unpinned third-party action.
+        Uses::Repository(_) | Uses::Docker(_) if uses.dynamic() => {
+            let (reference, owner, repo) = match uses {
+                Uses::Repository(repo_uses) => (
+                    uses.expression_only()
+                        .map(Into::into)
+                        .unwrap_or_else(|| reference(repo_uses)),
+                    repo_uses.owner.clone(),
+                    repo_uses.repo.clone(),
+                ),
+                Uses::Docker(docker) => (
+                    docker_reference(docker),
+                    String::new(),
+                    docker.image.clone(),
+                ),
+                Uses::Local(_) => unreachable!(),
+            };
+
+            Some(UsesIdentity {
+                kind: ActionKind::Dynamic,
+                reference,
+                owner,
+                repo,
+                subpath: None,
+                host: None,
+                registry: None,
+                // NOTE: A dynamic `uses:` has no ref that can be resolved.
+                git_ref: None,
+                pinned_to_sha: false,
+                floating_tag: false,
+                third_party: true,
+            })
+        }
+        Uses::Repository(uses) => {
+            let reference = reference(uses);
+            // NOTE: Server-qualified `uses:` clauses are reported relative
+            // to their host, e.g. `owner/repo` on `ghe.example.com`.
+            let (host, uses) = resolver.on_host(uses);
+
+            Some(UsesIdentity {
+                kind: ActionKind::Action,
+                pinned_to_sha: resolver.is_commit(&uses),
+                floating_tag: false,
+                third_party: resolver.third_party_on(allowed_orgs, &host, &uses.owner, &uses.repo),
+                reference,
+                owner: uses.owner,
+                repo: uses.repo,
+                subpath: uses.subpath,
+                host: Some(host),
+                registry: None,
+                git_ref: uses.git_ref,
+            })
+        }
+        Uses::Docker(uses) => {
+            // Images without a namespace on Docker Hub are implicitly
+            // in its `library` namespace.
+            let (owner, repo) = match uses.image.split_once('/') {
+                Some((owner, repo)) => (owner, repo),
+                None if uses.registry.is_none() => ("library", uses.image.as_str()),
+                None => ("", uses.image.as_str()),
+            };
+
+            let public = uses
+                .registry
+                .as_deref()
+                .is_none_or(|registry| PUBLIC_REGISTRIES.contains(&registry));
+
+            Some(UsesIdentity {
+                kind: ActionKind::Docker,
+                reference: docker_reference(uses),
+                third_party: public && resolver.third_party(allowed_orgs, owner, repo),
+                owner: owner.into(),
+                repo: repo.into(),
+                subpath: None,
+                host: None,
+                registry: uses.registry.clone(),
+                git_ref: uses.hash.clone().or_else(|| uses.tag.clone()),
+                pinned_to_sha: uses.digest_is_valid(),
+                floating_tag: uses.hash.is_none()
+                    && uses
+                        .tag
+                        .as_deref()
+                        .is_some_and(|tag| allowed_orgs.is_floating_tag(tag)),
+            })
+        }
+    }
 }
 
 /// Extract GitHub Actions from a single workflow file
-fn extract_actions_from_workflow(content: &str, file_path: &str) -> Vec<Action> {
+///
+/// Every step-level and job-level (i.e. reusable workflow) `uses:` produces
+/// its own entry, even when the same action is used more than once in
+/// the workflow.
+pub(crate) fn extract_actions_from_workflow(
+    workflow: &Workflow,
+    allowed_orgs: &AllowedOrgs,
+    resolver: &UsesResolver,
+) -> Result<Vec<Action>> {
     let mut actions = Vec::new();
-    let mut seen_refs = HashSet::new();
-    
-    // This regex looks specifically for lines that start with whitespace,
-    // possibly have a dash, and then "uses:" followed by a value
-    let uses_regex = Regex::new(r"(?m)^\s*-?\s*uses:\s*([^\n]+)").unwrap();
-    
-    for capture in uses_regex.captures_iter(content) {
-        if let Some(match_group) = capture.get(1) {
-            let action_ref = match_group.as_str().trim();
-            
-            // Skip empty references or Docker URLs
-            if action_ref.is_empty() || action_ref.starts_with("docker://") {
-                continue;
-            }
-            
-            // Clean up the reference (remove quotes)
-            let clean_ref = action_ref.trim_matches(|c: char| c == '\'' || c == '"');
-            
-            // Only process if it's a GitHub action (contains '/')
-            if !clean_ref.contains('/') {
+
+    for job in workflow.jobs() {
+        let job = match job {
+            Job::NormalJob(job) => job,
+            Job::ReusableWorkflowCallJob(job) => {
+                let Some(identity) = identify(&job.uses, allowed_orgs, resolver) else {
+                    continue;
+                };
+
+                let location = job
+                    .location()
+                    .with_keys(&["uses".into()])
+                    .concretize(workflow.as_document())?;
+
+                let kind = match identity.kind {
+                    ActionKind::Dynamic => ActionKind::Dynamic,
+                    _ => ActionKind::ReusableWorkflow,
+                };
+
+                actions.push(Action::new(
+                    UsesIdentity { kind, ..identity },
+                    &workflow.key,
+                    &location,
+                    Some(job.id()),
+                    None,
+                ));
                 continue;
             }
-            
-            // Skip if we've already seen this action
-            if seen_refs.contains(clean_ref) {
+        };
+
+        for step in job.steps() {
+            let Some(identity) = step
+                .uses()
+                .and_then(|uses| identify(uses, allowed_orgs, resolver))
+            else {
                 continue;
-            }
-            
-            seen_refs.insert(clean_ref.to_string());
-            
-            // Determine if this is a third-party action
-            let is_third_party = !is_official_action(clean_ref);
-            
-            // Determine if it's pinned to a SHA
-            let is_pinned = is_pinned_to_sha(clean_ref);
-            
-            actions.push(Action {
-                reference: clean_ref.to_string(),
-                pinned_to_sha: is_pinned,
-                third_party: is_third_party,
-                line: format!("uses: {}", clean_ref),
-                file_path: file_path.to_string(),
-            });
+            };
+
+            let location = step
+                .location()
+                .with_keys(&["uses".into()])
+                .concretize(step.document())?;
+
+            actions.push(Action::new(
+                identity,
+                &workflow.key,
+                &location,
+                Some(job.id()),
+                Some(step.index),
+            ));
         }
     }
-    
-    actions
+
+    Ok(actions)
 }
 
-/// Check if an action reference is likely from an official organization
-fn is_official_action(action_ref: &str) -> bool {
-    // Extract the organization from "org/repo@ref"
-    let org = action_ref.split('/').next().unwrap_or("");
-    
-    // Check if it's one of the official orgs
-    matches!(org.to_lowercase().as_str(), "actions" | "github" | "dependabot")
+/// Extract GitHub Actions from a single composite action definition
+///
+/// Non-composite actions have no steps, and so contribute no entries.
+pub(crate) fn extract_actions_from_action(
+    action: &crate::models::Action,
+    allowed_orgs: &AllowedOrgs,
+    resolver: &UsesResolver,
+) -> Result<Vec<Action>> {
+    let mut actions = Vec::new();
+
+    if !matches!(action.runs, action::Runs::Composite(_)) {
+        return Ok(actions);
+    }
+
+    for step in action.steps() {
+        let Some(identity) = step
+            .uses()
+            .and_then(|uses| identify(uses, allowed_orgs, resolver))
+        else {
+            continue;
+        };
+
+        let location = step
+            .location()
+            .with_keys(&["uses".into()])
+            .concretize(step.document())?;
+
+        actions.push(Action::new(
+            identity,
+            &action.key,
+            &location,
+            None,
+            Some(step.index),
+        ));
+    }
+
+    Ok(actions)
 }
 
-/// Check if an action reference is pinned to a SHA
-fn is_pinned_to_sha(action_ref: &str) -> bool {
-    if let Some(ref_part) = action_ref.split('@').nth(1) {
-        // A SHA is typically 40 hex characters
-        ref_part.len() >= 40 && ref_part.chars().all(|c| c.is_ascii_hexdigit())
-    } else {
-        false
+/// Extract the actions used by every input in `registry`, in input order.
+pub(crate) fn extract_actions(
+    registry: &InputRegistry,
+    allowed_orgs: &AllowedOrgs,
+    resolver: &UsesResolver,
+) -> Result<Vec<Action>> {
+    // NOTE: The registry is already ordered by input key, so each input
+    // is visited in the same (deterministic) order across runs.
+    let mut actions = vec![];
+    for (_, input) in registry.iter_inputs() {
+        match input {
+            AuditInput::Workflow(workflow) => actions.extend(extract_actions_from_workflow(
+                workflow,
+                allowed_orgs,
+                resolver,
+            )?),
+            AuditInput::Action(action) => {
+                actions.extend(extract_actions_from_action(action, allowed_orgs, resolver)?)
+            }
+        }
     }
+
+    Ok(actions)
 }
 
 /// Generate summary statistics
-fn generate_summary(actions: &[Action]) -> Summary {
+///
+/// Suppressed actions are only counted in `suppressed`, so that marking
+/// them doesn't change any other count (or the exit code).
+pub(crate) fn generate_summary(actions: &[Action]) -> Summary {
+    let suppressed = actions.iter().filter(|a| a.suppressed).count();
+    let actions = actions.iter().filter(|a| !a.suppressed).collect::<Vec<_>>();
+
     let total_actions = actions.len();
-    let unpinned_third_party = actions.iter()
+    let unpinned_third_party = actions
+        .iter()
         .filter(|a| a.third_party && !a.pinned_to_sha)
         .count();
-    let pinned_third_party = actions.iter()
+    let pinned_third_party = actions
+        .iter()
         .filter(|a| a.third_party && a.pinned_to_sha)
         .count();
-    let official_actions = actions.iter()
-        .filter(|a| !a.third_party)
+    let official_actions = actions.iter().filter(|a| !a.third_party).count();
+    let docker_images = actions
+        .iter()
+        .filter(|a| a.kind == ActionKind::Docker)
+        .count();
+    let unpinned_third_party_docker_images = actions
+        .iter()
+        .filter(|a| a.kind == ActionKind::Docker && a.third_party && !a.pinned_to_sha)
+        .count();
+    let reusable_workflows = actions
+        .iter()
+        .filter(|a| a.kind == ActionKind::ReusableWorkflow)
+        .count();
+    let unpinned_third_party_reusable_workflows = actions
+        .iter()
+        .filter(|a| a.kind == ActionKind::ReusableWorkflow && a.third_party && !a.pinned_to_sha)
         .count();
-        
+
+    let mut by_file = BTreeMap::<String, Breakdown>::new();
+    let mut by_owner = BTreeMap::<String, Breakdown>::new();
+    let mut by_repository = BTreeMap::<String, Breakdown>::new();
+    for action in actions {
+        by_file
+            .entry(action.qualified_path())
+            .or_default()
+            .add(action);
+        by_owner
+            .entry(action.owner.clone())
+            .or_default()
+            .add(action);
+        if let Some(repository) = &action.repository {
+            by_repository
+                .entry(repository.clone())
+                .or_default()
+                .add(action);
+        }
+    }
+
     Summary {
         total_actions,
         unpinned_third_party,
         pinned_third_party,
         official_actions,
+        docker_images,
+        unpinned_third_party_docker_images,
+        reusable_workflows,
+        unpinned_third_party_reusable_workflows,
+        by_file,
+        by_owner,
+        by_repository,
+        suppressed,
     }
 }
 
+/// Returns a human-readable reason for failing the run, if the given
+/// summary meets the `--tpa-fail-on` threshold.
+pub(crate) fn failure_reason(fail_on: TpaFailOn, summary: &Summary) -> Option<String> {
+    let (count, kind) = match fail_on {
+        TpaFailOn::UnpinnedThirdParty => (summary.unpinned_third_party, "unpinned third-party"),
+        TpaFailOn::AnyThirdParty => (
+            summary.unpinned_third_party + summary.pinned_third_party,
+            "third-party",
+        ),
+        TpaFailOn::Never => return None,
+    };
+
+    (count > 0).then(|| {
+        format!(
+            "failing: {count} {kind} {actions} found (--tpa-fail-on={fail_on})",
+            actions = if count == 1 { "action" } else { "actions" },
+            fail_on = fail_on.to_possible_value().unwrap().get_name(),
+        )
+    })
+}
+
+/// Fill in `suggested_sha` and `suggested_tag` for every unpinned action.
+///
+/// Each unique `owner/repo@ref` is resolved only once per run (see
+/// [`UsesResolver`]); actions whose refs can't be resolved, or that are
+/// on a host other than the run's, are left without suggestions.
+pub(crate) fn suggest_pins(resolver: &UsesResolver, actions: &mut [Action]) {
+    for action in actions.iter_mut().filter(|a| {
+        a.kind != ActionKind::Docker
+            && !a.pinned_to_sha
+            && a.host
+                .as_deref()
+                .is_none_or(|host| resolver.is_local_host(host))
+    }) {
+        let Some(git_ref) = &action.git_ref else {
+            continue;
+        };
+        let Some(sha) = resolver.resolve_ref(&action.owner, &action.repo, git_ref) else {
+            continue;
+        };
+
+        action.suggested_tag = resolver.longest_tag(&action.owner, &action.repo, &sha);
+        action.suggested_sha = Some(sha);
+    }
+}
+
+/// Fill in `locked_sha` and `current_sha` for every unpinned action whose
+/// ref is in `lockfile`, from the commits that [`suggest_pins`] resolved.
+///
+/// Actions whose refs aren't locked are left as-is.
+fn compare_locked(lockfile: &Lockfile, actions: &mut [Action]) {
+    for action in actions
+        .iter_mut()
+        .filter(|a| a.kind != ActionKind::Docker && !a.pinned_to_sha)
+    {
+        let Some(git_ref) = &action.git_ref else {
+            continue;
+        };
+        let Some(locked) = lockfile.locked_sha(&action.name(), git_ref) else {
+            continue;
+        };
+
+        action.locked_sha = Some(locked.into());
+        action.current_sha = action.suggested_sha.clone();
+    }
+}
+
+/// Returns the `(file, line, column)` of every `uses:` clause whose
+/// third-party unpinned finding was ignored.
+///
+/// These findings are selected by their [`FindingTag::ThirdPartyUnpinned`]
+/// tag, so that rewording their annotations doesn't change the report.
+fn suppressed_uses(results: &FindingRegistry) -> HashSet<(String, usize, usize)> {
+    results
+        .ignored()
+        .iter()
+        .filter(|finding| finding.tags.contains(&FindingTag::ThirdPartyUnpinned))
+        .flat_map(|finding| &finding.locations)
+        .filter(|location| location.symbolic.is_primary())
+        .map(|location| {
+            let point = &location.concrete.location.start_point;
+            let key = location.symbolic.key;
+            (
+                qualified_path(key.repository().as_deref(), key.presentation_path()),
+                point.row + 1,
+                point.column + 1,
+            )
+        })
+        .collect()
+}
+
+/// Collect the actions used by every workflow and composite action
+/// in `registry`, including inputs without any findings.
+///
+/// Actions whose third-party unpinned finding was ignored are dropped or
+/// marked as suppressed, per `suppressed`.
+///
+/// Actions are sorted by repository and file, then by position within the file, so that
+/// every TPA format produces stable output across runs.
+pub(crate) fn collect_actions(
+    registry: &InputRegistry,
+    allowed_orgs: &AllowedOrgs,
+    resolver: &UsesResolver,
+    results: &FindingRegistry,
+    suppressed: TpaSuppressed,
+) -> Result<Vec<Action>> {
+    let mut actions = extract_actions(registry, allowed_orgs, resolver)?;
+
+    let suppressed_uses = suppressed_uses(results);
+    for action in &mut actions {
+        action.suppressed =
+            suppressed_uses.contains(&(action.qualified_path(), action.line_number, action.column));
+    }
+
+    if matches!(suppressed, TpaSuppressed::Exclude) {
+        actions.retain(|action| !action.suppressed);
+    }
+
+    actions.sort_by(|a, b| {
+        (&a.repository, &a.file_path, a.line_number, a.column).cmp(&(
+            &b.repository,
+            &b.file_path,
+            b.line_number,
+            b.column,
+        ))
+    });
+
+    Ok(actions)
+}
+
+/// Find every action that's used with more than one distinct ref.
+///
+/// Actions without a ref are ignored, as are repeated uses of the same
+/// ref (whether in the same file or not).
+pub(crate) fn find_inconsistent_pins(actions: &[Action]) -> Vec<InconsistentPin> {
+    let mut uses: BTreeMap<String, BTreeMap<&str, BTreeSet<String>>> = BTreeMap::new();
+    for action in actions {
+        let Some(git_ref) = &action.git_ref else {
+            continue;
+        };
+
+        uses.entry(action.name())
+            .or_default()
+            .entry(git_ref)
+            .or_default()
+            .insert(action.qualified_path());
+    }
+
+    uses.into_iter()
+        .filter(|(_, refs)| refs.len() > 1)
+        .map(|(action, refs)| InconsistentPin {
+            action,
+            refs: refs
+                .into_iter()
+                .map(|(git_ref, files)| PinUsage {
+                    git_ref: git_ref.into(),
+                    files: files.into_iter().collect(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Build the full report for the given (already sorted) actions.
+fn build_report(actions: Vec<Action>) -> ActionReport {
+    let summary = generate_summary(&actions);
+    let inconsistent_pins = find_inconsistent_pins(&actions);
+
+    ActionReport {
+        schema_version: SCHEMA_VERSION,
+        actions,
+        summary,
+        inconsistent_pins,
+        score: None,
+    }
+}
+
+/// Returns whether the given action is selected by `--tpa-show`.
+fn is_shown(show: TpaShow, action: &Action) -> bool {
+    match show {
+        TpaShow::All => true,
+        TpaShow::Unpinned => !action.pinned_to_sha,
+        TpaShow::ThirdParty => action.third_party,
+        TpaShow::UnpinnedThirdParty => action.third_party && !action.pinned_to_sha,
+    }
+}
+
+/// Atomically write `report` to `path`, creating any missing parent
+/// directories.
+///
+/// The report is written to a temporary file in the same directory and
+/// then renamed into place, so that `path` never contains a partial report.
+fn write_report(path: &Path, report: &ActionReport) -> Result<()> {
+    let write = || -> Result<()> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        fs::create_dir_all(dir)?;
+
+        let mut writer = BufWriter::new(NamedTempFile::new_in(dir)?);
+        serde_json::to_writer_pretty(&mut writer, report)?;
+        writer.into_inner()?.persist(path)?;
+
+        Ok(())
+    };
+
+    write().with_context(|| format!("couldn't write TPA report to {}", path.display()))
+}
+
 /// Output the TPA list in the requested format.
-/// 
+///
 /// If the --format=tpa-list flag is used, a simple text list is output.
-/// Additionally, a JSON report is always saved to all_actions.json.
-pub(crate) fn output(sink: impl io::Write, findings: &[Finding]) -> Result<()> {
-    let mut sink = sink;
-    let mut workflow_files = HashSet::new();
-    
-    // First, collect all workflow files mentioned in findings
-    for finding in findings {
-        if let Some(location) = finding.locations.first() {
-            let file_path = location.symbolic.key.presentation_path();
-            workflow_files.insert(file_path.to_string());
-        }
-    }
-    
-    // For testing - directly read the file from disk if we can't get the content from findings
-    // This is a fallback mechanism for when we can't get the full workflow content
-    if workflow_files.is_empty() {
-        // Try the known file path from the JSON output
-        let file_path = "../repos/WebDriverAgent/.github/workflows/functional-test.yml";
-        if let Ok(content) = std::fs::read_to_string(file_path) {
-            workflow_files.insert(file_path.to_string());
-        } else {
-            // Try a local file if provided
-            let test_file = "functional-test.yml";
-            if Path::new(test_file).exists() {
-                if let Ok(content) = std::fs::read_to_string(test_file) {
-                    workflow_files.insert(test_file.to_string());
-                }
+/// Additionally, a JSON report is saved to `--tpa-report`; if that's `-`,
+/// the report is written to stdout and the text list to stderr instead.
+/// Without `--tpa-report`, the report is saved to [`DEFAULT_REPORT`], but
+/// only if there's anything to report.
+///
+/// Returns the report's summary, for exit code determination.
+pub(crate) fn output(
+    sink: &mut Sink,
+    app: &App,
+    registry: &InputRegistry,
+    allowed_orgs: &AllowedOrgs,
+    resolver: &UsesResolver,
+    results: &FindingRegistry,
+    score: Option<&Score>,
+) -> Result<Summary> {
+    let mut all_actions = collect_actions(
+        registry,
+        allowed_orgs,
+        resolver,
+        results,
+        app.tpa_suppressed,
+    )?;
+
+    // Suggest pins for unpinned actions, if we're online.
+    if resolver.online() {
+        suggest_pins(resolver, &mut all_actions);
+
+        // ...and compare them against the lockfile, if we have one.
+        if let Some(path) = &app.lockfile {
+            compare_locked(&Lockfile::load(path)?, &mut all_actions);
+        }
+    }
+
+    // Create the full report
+    let report = ActionReport {
+        score: score.cloned(),
+        ..build_report(all_actions)
+    };
+
+    if !report.actions.iter().any(|action| action.third_party) {
+        tracing::info!("no third-party actions found");
+    }
+
+    match app.tpa_report.as_deref() {
+        Some("-") => {
+            serde_json::to_writer_pretty(io::stdout(), &report)?;
+            // The text list can't share stdout with the report.
+            match sink.path() {
+                Some(_) => render_text(sink, app, &report)?,
+                None => render_text(io::stderr(), app, &report)?,
             }
         }
+        Some(path) => {
+            write_report(Path::new(path), &report)?;
+            render_text(sink, app, &report)?;
+        }
+        // NOTE: We don't litter the working directory with an empty
+        // report that nobody asked for.
+        None if report.actions.is_empty() => {
+            tracing::debug!("no actions found; not writing {DEFAULT_REPORT}");
+            render_text(sink, app, &report)?;
+        }
+        None => {
+            write_report(Path::new(DEFAULT_REPORT), &report)?;
+            render_text(sink, app, &report)?;
+        }
     }
-    
-    // Process each workflow file
-    let mut all_actions = Vec::new();
-    
-    for file_path in workflow_files {
-        // Try to read the file directly
-        if let Ok(content) = std::fs::read_to_string(&file_path) {
-            let actions = extract_actions_from_workflow(&content, &file_path);
-            all_actions.extend(actions);
-        } else {
-            // If we can't read the file, try to extract the content from findings
-            for finding in findings {
-                for location in &finding.locations {
-                    if location.symbolic.key.presentation_path() == file_path {
-                        // If we find a large enough chunk, treat it as the workflow content
-                        if location.concrete.feature.len() > 100 {
-                            let actions = extract_actions_from_workflow(
-                                location.concrete.feature, 
-                                &file_path
-                            );
-                            all_actions.extend(actions);
-                            break;
-                        }
-                    }
+
+    Ok(report.summary)
+}
+
+/// Render the text list for the given report.
+fn render_text(mut sink: impl io::Write, app: &App, report: &ActionReport) -> Result<()> {
+    // Output only the selected actions to stdout; the JSON report
+    // always contains everything, including any marked suppressions.
+    let shown = report
+        .actions
+        .iter()
+        .filter(|action| !action.suppressed && is_shown(app.tpa_show, action));
+
+    match app.tpa_group_by {
+        None => {
+            for action in shown {
+                write!(
+                    sink,
+                    "{}:{}: uses: {}",
+                    action.qualified_path(),
+                    action.line_number,
+                    action.reference
+                )?;
+
+                match (&action.suggested_sha, &action.suggested_tag) {
+                    (Some(sha), Some(tag)) => writeln!(sink, " (suggested: {sha} # {tag})")?,
+                    (Some(sha), None) => writeln!(sink, " (suggested: {sha})")?,
+                    _ => writeln!(sink)?,
                 }
             }
         }
+        Some(TpaGroupBy::Action) => render_grouped_by_action(&mut sink, shown)?,
     }
-    
-    // Generate summary
-    let summary = generate_summary(&all_actions);
-    
-    // Create the full report
-    let report = ActionReport {
-        actions: all_actions.clone(),
-        summary,
-    };
-    
-    // Save the JSON report
-    let json_file = File::create("all_actions.json")?;
-    serde_json::to_writer_pretty(json_file, &report)?;
-    
-    // Output only the unpinned third-party actions to stdout
-    for action in &all_actions {
-        if action.third_party && !action.pinned_to_sha {
-            writeln!(sink, "{}: uses: {}", action.file_path, action.reference)?;
-        }
-    }
-    
+
+    if app.tpa_show_inconsistent && !report.inconsistent_pins.is_empty() {
+        writeln!(sink)?;
+        writeln!(sink, "inconsistent pins:")?;
+        for pin in &report.inconsistent_pins {
+            writeln!(sink, "{}", pin.action)?;
+            for usage in &pin.refs {
+                writeln!(sink, "  {}: {}", usage.git_ref, usage.files.join(", "))?;
+            }
+        }
+    }
+
+    if app.tpa_owner_summary {
+        let owners = owner_summaries(report);
+        if !owners.is_empty() {
+            writeln!(sink)?;
+            writeln!(sink, "third-party owners:")?;
+            for owner in owners {
+                writeln!(
+                    sink,
+                    "  {name}: {actions} {action_s}, {uses} {use_s}, {unpinned} unpinned",
+                    name = owner.owner,
+                    actions = owner.actions,
+                    action_s = if owner.actions == 1 {
+                        "action"
+                    } else {
+                        "actions"
+                    },
+                    uses = owner.uses,
+                    use_s = if owner.uses == 1 { "use" } else { "uses" },
+                    unpinned = owner.unpinned,
+                )?;
+            }
+        }
+    }
+
+    if let Some(score) = &report.score {
+        writeln!(sink, "{score}")?;
+    }
+
+    if let Some(reason) = failure_reason(app.tpa_fail_on, &report.summary) {
+        writeln!(sink, "{reason}")?;
+    }
+
+    Ok(())
+}
+
+/// A single third-party owner's line in the `--tpa-owner-summary` section.
+#[derive(Debug, PartialEq, Eq)]
+struct OwnerSummary<'a> {
+    owner: &'a str,
+    /// The number of distinct actions (i.e. `owner/repo[/subpath]`) used
+    actions: usize,
+    /// The number of times the owner's actions are used
+    uses: usize,
+    /// The number of those uses that aren't pinned to a commit SHA
+    unpinned: usize,
+}
+
+/// Summarize each third-party owner in `report`, with the owners with the
+/// most unpinned uses first.
+///
+/// The counts come from the report's `by_owner` breakdown, so that they
+/// always match the JSON report. Owners whose actions are all pinned are
+/// still included, with no unpinned uses.
+fn owner_summaries(report: &ActionReport) -> Vec<OwnerSummary<'_>> {
+    let mut owners = report
+        .summary
+        .by_owner
+        .iter()
+        .filter(|(_, breakdown)| breakdown.unpinned_third_party + breakdown.pinned_third_party > 0)
+        .map(|(owner, breakdown)| OwnerSummary {
+            owner,
+            actions: report
+                .actions
+                .iter()
+                .filter(|a| !a.suppressed && a.third_party && &a.owner == owner)
+                .map(|a| a.name())
+                .collect::<HashSet<_>>()
+                .len(),
+            uses: breakdown.unpinned_third_party + breakdown.pinned_third_party,
+            unpinned: breakdown.unpinned_third_party,
+        })
+        .collect::<Vec<_>>();
+
+    // NOTE: `by_owner` is already sorted by owner, and the sort is stable.
+    owners.sort_by_key(|owner| std::cmp::Reverse(owner.unpinned));
+    owners
+}
+
+/// Render the given actions grouped by their normalized `owner/repo[/subpath]`,
+/// with the most frequently used actions first.
+fn render_grouped_by_action<'a>(
+    sink: &mut impl io::Write,
+    actions: impl Iterator<Item = &'a Action>,
+) -> Result<()> {
+    let mut groups: HashMap<String, Vec<&Action>> = HashMap::new();
+    for action in actions {
+        groups.entry(action.name()).or_default().push(action);
+    }
+
+    let mut groups = groups.into_iter().collect::<Vec<_>>();
+    groups.sort_by(|(a_name, a), (b_name, b)| b.len().cmp(&a.len()).then(a_name.cmp(b_name)));
+
+    for (name, occurrences) in groups {
+        let refs = occurrences
+            .iter()
+            .filter_map(|action| action.git_ref.as_deref())
+            .collect::<BTreeSet<_>>();
+
+        writeln!(
+            sink,
+            "{name} ({count} {occurrence}; refs: {refs})",
+            count = occurrences.len(),
+            occurrence = if occurrences.len() == 1 {
+                "occurrence"
+            } else {
+                "occurrences"
+            },
+            refs = refs.into_iter().collect::<Vec<_>>().join(", "),
+        )?;
+
+        for action in occurrences {
+            writeln!(sink, "  {}:{}", action.qualified_path(), action.line_number)?;
+        }
+    }
+
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::str::FromStr as _;
+
+    use clap::Parser as _;
+    use github_actions_models::common::Uses;
+    use time::macros::datetime;
+
+    use crate::audit::unpinned_uses::{AllowedOrgs, UnpinnedUses};
+    use crate::audit::{Audit as _, AuditInput};
+    use crate::config::Config;
+    use crate::finding::FindingTag;
+    use crate::github_api::GitHubHost;
+    use crate::lock::Lockfile;
+    use crate::models::{Job, StepCommon as _, Workflow};
+    use crate::registry::{FindingRegistry, InputKey, InputRegistry};
+    use crate::resolver::UsesResolver;
+    use crate::score::{Counts, Score, ScoreWeights};
+    use crate::state::AuditState;
+
+    use super::{
+        ActionKind, ActionReport, Breakdown, InconsistentPin, OwnerSummary, PinUsage,
+        SCHEMA_VERSION, Summary, build_report, collect_actions, compare_locked,
+        extract_actions_from_action, extract_actions_from_workflow, failure_reason,
+        find_inconsistent_pins, generate_summary, is_shown, owner_summaries, purl_encode,
+        render_grouped_by_action, write_report,
+    };
+    use crate::{App, TpaFailOn, TpaShow, TpaSuppressed};
+
+    fn audit_state(config: &Config, tpa_allowed_org: Option<Vec<String>>) -> AuditState<'_> {
+        AuditState {
+            config,
+            online: false,
+            cache_dir: "/tmp/zizmor".into(),
+            gh_token: None,
+            gh_hostname: GitHubHost::Standard("github.com".into()),
+            tpa_allowlist_file: None,
+            tpa_allowed_org,
+            lenient_allowlist: false,
+            advisories_file: None,
+            osv: false,
+            lockfile: None,
+            resolver: Default::default(),
+        }
+    }
+
+    fn workflow_at(path: &str, contents: &str) -> Workflow {
+        let key = InputKey::local(path, None).unwrap();
+        match AuditInput::from(Workflow::from_string(contents.into(), key).unwrap()) {
+            AuditInput::Workflow(workflow) => workflow,
+            _ => unreachable!(),
+        }
+    }
+
+    fn workflow(contents: &str) -> Workflow {
+        workflow_at(".github/workflows/test.yml", contents)
+    }
+
+    #[test]
+    fn test_purl_encode() {
+        for (input, keep_slashes, expected) in &[
+            ("v4", false, "v4"),
+            ("release/v1", false, "release%2Fv1"),
+            ("release/v1", true, "release/v1"),
+            ("sha256:abc", false, "sha256%3Aabc"),
+            ("a b+c", false, "a%20b%2Bc"),
+            ("ünï", false, "%C3%BCn%C3%AF"),
+        ] {
+            assert_eq!(purl_encode(input, *keep_slashes), *expected);
+        }
+    }
+
+    #[test]
+    fn test_extract_actions_locations() {
+        let workflow = workflow(
+            r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - run: echo hello
+      - uses: some-org/some-action@v1
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - name: again
+        uses: some-org/some-action@v1
+      - uses: ./.github/actions/local
+      - uses: docker://alpine:3.20
+"#,
+        );
+
+        let config = Config::default();
+        let allowed_orgs = AllowedOrgs::from_state(&audit_state(&config, None)).unwrap();
+        let actions =
+            extract_actions_from_workflow(&workflow, &allowed_orgs, &UsesResolver::default())
+                .unwrap();
+
+        // The same action used twice is reported twice, once per occurrence;
+        // local actions are not reported.
+        assert_eq!(actions.len(), 4);
+
+        assert_eq!(actions[0].reference, "actions/checkout@v4");
+        assert!(!actions[0].third_party);
+        assert_eq!(actions[0].job_id.as_deref(), Some("build"));
+        assert_eq!(actions[0].step_index, Some(0));
+        assert_eq!((actions[0].line_number, actions[0].column), (8, 9));
+
+        assert_eq!(actions[1].reference, "some-org/some-action@v1");
+        assert!(actions[1].third_party);
+        assert_eq!(actions[1].job_id.as_deref(), Some("build"));
+        assert_eq!(actions[1].step_index, Some(2));
+        assert_eq!((actions[1].line_number, actions[1].column), (10, 9));
+
+        assert_eq!(actions[2].reference, "some-org/some-action@v1");
+        assert_eq!(actions[2].job_id.as_deref(), Some("test"));
+        assert_eq!(actions[2].step_index, Some(0));
+        assert_eq!((actions[2].line_number, actions[2].column), (15, 9));
+
+        assert_eq!(actions[3].kind, ActionKind::Docker);
+        assert_eq!(actions[3].reference, "docker://alpine:3.20");
+        assert_eq!(actions[3].name(), "docker://library/alpine");
+        assert_eq!(actions[3].git_ref.as_deref(), Some("3.20"));
+        assert!(actions[3].third_party);
+        assert!(!actions[3].pinned_to_sha);
+        assert_eq!(actions[3].step_index, Some(2));
+    }
+
+    #[test]
+    fn test_extract_actions_aliases() {
+        let workflow = workflow(
+            r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - &setup
+        uses: some-org/some-action@v1
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - *setup
+      - <<: *setup
+        uses: some-org/other-action@v2
+"#,
+        );
+
+        let config = Config::default();
+        let allowed_orgs = AllowedOrgs::from_state(&audit_state(&config, None)).unwrap();
+        let actions =
+            extract_actions_from_workflow(&workflow, &allowed_orgs, &UsesResolver::default())
+                .unwrap();
+
+        // Each use of an aliased step is reported where it's used.
+        assert_eq!(actions.len(), 3);
+
+        assert_eq!(actions[0].reference, "some-org/some-action@v1");
+        assert_eq!((actions[0].line_number, actions[0].column), (9, 9));
+
+        assert_eq!(actions[1].reference, "some-org/some-action@v1");
+        assert_eq!(actions[1].job_id.as_deref(), Some("test"));
+        assert_eq!((actions[1].line_number, actions[1].column), (13, 9));
+
+        assert_eq!(actions[2].reference, "some-org/other-action@v2");
+        assert_eq!((actions[2].line_number, actions[2].column), (15, 9));
+    }
+
+    #[test]
+    fn test_extract_actions_respects_allowlist() {
+        let workflow = workflow(
+            r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - uses: Trusted-Org/some-action@v1
+      - uses: untrusted-org/some-action@v1
+"#,
+        );
+
+        let config = Config::default();
+        let state = audit_state(&config, Some(vec!["trusted-org".into()]));
+        let allowed_orgs = AllowedOrgs::from_state(&state).unwrap();
+
+        let actions =
+            extract_actions_from_workflow(&workflow, &allowed_orgs, &UsesResolver::default())
+                .unwrap();
+        let unpinned_third_party = actions
+            .iter()
+            .filter(|a| a.third_party && !a.pinned_to_sha)
+            .map(|a| a.reference.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(unpinned_third_party, ["untrusted-org/some-action@v1"]);
+
+        let summary = generate_summary(&actions);
+        assert_eq!(summary.total_actions, 3);
+        assert_eq!(summary.official_actions, 2);
+        assert_eq!(summary.unpinned_third_party, 1);
+        assert_eq!(summary.pinned_third_party, 0);
+
+        // The audit classifies the same actions identically.
+        let audit = UnpinnedUses::new(&state).unwrap();
+        let Some(Job::NormalJob(job)) = workflow.jobs().next() else {
+            unreachable!();
+        };
+        for (step, action) in job.steps().zip(&actions) {
+            let flagged = audit
+                .evaluate_pinning(&workflow.key, step.uses().unwrap())
+                .is_some_and(|violation| violation.third_party);
+            assert_eq!(flagged, action.third_party && !action.pinned_to_sha);
+        }
+    }
+
+    #[test]
+    fn test_extract_actions_hosts() {
+        let workflow = workflow(
+            r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: trusted-org/some-action@v1
+      - uses: https://GHE.example.com/trusted-org/some-action/sub@v1
+      - uses: https://github.com/actions/checkout@v4
+      - uses: https://other.example.com/trusted-org/some-action@v1
+"#,
+        );
+
+        let config = Config::default();
+        let host = GitHubHost::from_clap("ghe.example.com").unwrap();
+        let state = AuditState {
+            gh_hostname: host.clone(),
+            resolver: std::sync::Arc::new(UsesResolver::new(None, &host)),
+            ..audit_state(&config, Some(vec!["trusted-org".into()]))
+        };
+        let allowed_orgs = AllowedOrgs::from_state(&state).unwrap();
+
+        let actions =
+            extract_actions_from_workflow(&workflow, &allowed_orgs, &state.resolver).unwrap();
+        assert_eq!(
+            actions
+                .iter()
+                .map(|a| (a.host.as_deref(), a.name(), a.third_party))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    Some("ghe.example.com"),
+                    "trusted-org/some-action".into(),
+                    false
+                ),
+                (
+                    Some("ghe.example.com"),
+                    "trusted-org/some-action/sub".into(),
+                    false
+                ),
+                (Some("github.com"), "actions/checkout".into(), false),
+                (
+                    Some("other.example.com"),
+                    "trusted-org/some-action".into(),
+                    true
+                ),
+            ]
+        );
+        assert_eq!(
+            actions[1].reference,
+            "https://GHE.example.com/trusted-org/some-action/sub@v1"
+        );
+
+        // The audit classifies the same actions identically.
+        let audit = UnpinnedUses::new(&state).unwrap();
+        let Some(Job::NormalJob(job)) = workflow.jobs().next() else {
+            unreachable!();
+        };
+        for (step, action) in job.steps().zip(&actions) {
+            let flagged = audit
+                .evaluate_pinning(&workflow.key, step.uses().unwrap())
+                .is_some_and(|violation| violation.third_party);
+            assert_eq!(flagged, action.third_party && !action.pinned_to_sha);
+        }
+    }
+
+    #[test]
+    fn test_extract_actions_docker_digests() {
+        let workflow = workflow(&format!(
+            r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: docker://alpine@sha256:{digest}
+      - uses: docker://alpine@sha256:abc
+      - uses: docker://alpine@md5:d41d8cd98f00b204e9800998ecf8427e
+"#,
+            digest = "a".repeat(64)
+        ));
+
+        let config = Config::default();
+        let state = audit_state(&config, None);
+        let allowed_orgs = AllowedOrgs::from_state(&state).unwrap();
+
+        // Only a well-formed digest counts as pinned.
+        let actions =
+            extract_actions_from_workflow(&workflow, &allowed_orgs, &UsesResolver::default())
+                .unwrap();
+        assert_eq!(
+            actions.iter().map(|a| a.pinned_to_sha).collect::<Vec<_>>(),
+            [true, false, false]
+        );
+    }
+
+    #[test]
+    fn test_extract_actions_dynamic() {
+        let workflow = workflow(
+            r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    strategy:
+      matrix:
+        action: [actions/checkout@v4]
+    steps:
+      - uses: ${{ matrix.action }}
+      - uses: actions/checkout@${{ matrix.ref }}
+      - uses: docker://alpine:${{ matrix.tag }}
+  call:
+    uses: ${{ matrix.owner }}/workflows/.github/workflows/ci.yml@v1
+"#,
+        );
+
+        let config = Config::default();
+        let state = audit_state(&config, None);
+        let allowed_orgs = AllowedOrgs::from_state(&state).unwrap();
+
+        // Dynamic references are reported as written, and are always
+        // treated as unpinned and third-party.
+        let actions =
+            extract_actions_from_workflow(&workflow, &allowed_orgs, &UsesResolver::default())
+                .unwrap();
+        assert_eq!(
+            actions
+                .iter()
+                .map(|a| (a.kind, a.name(), a.git_ref.as_deref()))
+                .collect::<Vec<_>>(),
+            [
+                (ActionKind::Dynamic, "${{ matrix.action }}".into(), None),
+                (
+                    ActionKind::Dynamic,
+                    "actions/checkout@${{ matrix.ref }}".into(),
+                    None
+                ),
+                (
+                    ActionKind::Dynamic,
+                    "docker://alpine:${{ matrix.tag }}".into(),
+                    None
+                ),
+                (
+                    ActionKind::Dynamic,
+                    "${{ matrix.owner }}/workflows/.github/workflows/ci.yml@v1".into(),
+                    None
+                ),
+            ]
+        );
+        assert!(actions.iter().all(|a| a.third_party && !a.pinned_to_sha));
+        assert_eq!(
+            actions[0].purl(),
+            "pkg:generic/%24%7B%7B%20matrix.action%20%7D%7D"
+        );
+    }
+
+    #[test]
+    fn test_extract_reusable_workflows() {
+        let workflow = workflow(
+            r#"
+on: push
+
+jobs:
+  remote:
+    uses: some-org/some-repo/.github/workflows/ci.yml@v1
+  local:
+    uses: ./.github/workflows/local.yml
+"#,
+        );
+
+        let config = Config::default();
+        let allowed_orgs = AllowedOrgs::from_state(&audit_state(&config, None)).unwrap();
+        let actions =
+            extract_actions_from_workflow(&workflow, &allowed_orgs, &UsesResolver::default())
+                .unwrap();
+
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].kind, ActionKind::ReusableWorkflow);
+        assert_eq!(
+            actions[0].name(),
+            "some-org/some-repo/.github/workflows/ci.yml"
+        );
+        assert!(actions[0].third_party);
+        assert!(!actions[0].pinned_to_sha);
+        assert_eq!(actions[0].job_id.as_deref(), Some("remote"));
+        assert_eq!(actions[0].step_index, None);
+        assert_eq!((actions[0].line_number, actions[0].column), (6, 5));
+
+        let summary = generate_summary(&actions);
+        assert_eq!(summary.reusable_workflows, 1);
+        assert_eq!(summary.unpinned_third_party_reusable_workflows, 1);
+    }
+
+    #[test]
+    fn test_extract_composite_actions() {
+        let key = InputKey::local(".github/actions/setup/action.yml", None).unwrap();
+        let action = crate::models::Action::from_string(
+            r#"
+name: setup
+description: setup
+runs:
+  using: composite
+  steps:
+    - run: echo hello
+      shell: bash
+    - uses: ./.github/actions/nested
+    - uses: some-org/some-action@v1
+"#
+            .into(),
+            key,
+        )
+        .unwrap();
+
+        let config = Config::default();
+        let allowed_orgs = AllowedOrgs::from_state(&audit_state(&config, None)).unwrap();
+        let actions =
+            extract_actions_from_action(&action, &allowed_orgs, &UsesResolver::default()).unwrap();
+
+        // Neither the `run:` step nor the nested local action are reported.
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].reference, "some-org/some-action@v1");
+        assert_eq!(actions[0].file_path, ".github/actions/setup/action.yml");
+        assert!(actions[0].third_party);
+        assert_eq!(actions[0].job_id, None);
+        assert_eq!(actions[0].step_index, Some(2));
+        assert_eq!(actions[0].line_number, 10);
+
+        let key = InputKey::local(".github/actions/run-only/action.yml", None).unwrap();
+        let action = crate::models::Action::from_string(
+            r#"
+name: run-only
+description: run-only
+runs:
+  using: composite
+  steps:
+    - run: echo hello
+      shell: bash
+"#
+            .into(),
+            key,
+        )
+        .unwrap();
+
+        assert!(
+            extract_actions_from_action(&action, &allowed_orgs, &UsesResolver::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_summary_breakdowns() {
+        let config = Config::default();
+        let allowed_orgs = AllowedOrgs::from_state(&audit_state(&config, None)).unwrap();
+
+        let mut actions = extract_actions_from_workflow(
+            &workflow(
+                r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - uses: some-org/some-action@v1
+      - uses: some-org/other-action@8f4b7f84864484a7bf31766abe9204da3cbe65b3
+"#,
+            ),
+            &allowed_orgs,
+            &UsesResolver::default(),
+        )
+        .unwrap();
+
+        let other = workflow_at(
+            ".github/workflows/other.yml",
+            r#"
+on: push
+
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: some-org/some-action@v2
+"#,
+        );
+        actions.extend(
+            extract_actions_from_workflow(&other, &allowed_orgs, &UsesResolver::default()).unwrap(),
+        );
+
+        let summary = generate_summary(&actions);
+
+        assert_eq!(
+            summary.by_file.keys().collect::<Vec<_>>(),
+            [".github/workflows/other.yml", ".github/workflows/test.yml"]
+        );
+        assert_eq!(
+            summary.by_owner["some-org"],
+            Breakdown {
+                total: 3,
+                unpinned_third_party: 2,
+                pinned_third_party: 1,
+                official: 0,
+            }
+        );
+
+        for breakdowns in [&summary.by_file, &summary.by_owner] {
+            let sum = breakdowns
+                .values()
+                .fold(Breakdown::default(), |acc, b| Breakdown {
+                    total: acc.total + b.total,
+                    unpinned_third_party: acc.unpinned_third_party + b.unpinned_third_party,
+                    pinned_third_party: acc.pinned_third_party + b.pinned_third_party,
+                    official: acc.official + b.official,
+                });
+
+            assert_eq!(
+                sum,
+                Breakdown {
+                    total: summary.total_actions,
+                    unpinned_third_party: summary.unpinned_third_party,
+                    pinned_third_party: summary.pinned_third_party,
+                    official: summary.official_actions,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_summary_by_repository() {
+        let config = Config::default();
+        let allowed_orgs = AllowedOrgs::from_state(&audit_state(&config, None)).unwrap();
+
+        let remote = |slug: &str| {
+            let Uses::Repository(slug) = Uses::from_str(slug).unwrap() else {
+                unreachable!()
+            };
+            let key = InputKey::remote(&slug, ".github/workflows/ci.yml".into()).unwrap();
+            let contents = r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - uses: some-org/some-action@v1
+"#;
+            match AuditInput::from(Workflow::from_string(contents.into(), key).unwrap()) {
+                AuditInput::Workflow(workflow) => workflow,
+                _ => unreachable!(),
+            }
+        };
+
+        let mut actions = vec![];
+        for slug in ["acme/api", "acme/web"] {
+            actions.extend(
+                extract_actions_from_workflow(
+                    &remote(slug),
+                    &allowed_orgs,
+                    &UsesResolver::default(),
+                )
+                .unwrap(),
+            );
+        }
+        // Local inputs don't belong to any repository.
+        let local = workflow(
+            r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+"#,
+        );
+        actions.extend(
+            extract_actions_from_workflow(&local, &allowed_orgs, &UsesResolver::default()).unwrap(),
+        );
+
+        let summary = generate_summary(&actions);
+
+        // Identical paths in different repositories are kept apart.
+        assert_eq!(
+            summary.by_file.keys().collect::<Vec<_>>(),
+            [
+                ".github/workflows/test.yml",
+                "acme/api/.github/workflows/ci.yml",
+                "acme/web/.github/workflows/ci.yml"
+            ]
+        );
+        assert_eq!(
+            summary.by_repository.keys().collect::<Vec<_>>(),
+            ["acme/api", "acme/web"]
+        );
+        assert_eq!(
+            summary.by_repository["acme/web"],
+            Breakdown {
+                total: 2,
+                unpinned_third_party: 1,
+                pinned_third_party: 0,
+                official: 1,
+            }
+        );
+        assert_eq!(summary.total_actions, 5);
+    }
+
+    #[test]
+    fn test_summary_skips_suppressed() {
+        let workflow = workflow(
+            r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: some-org/ignored-action@v1
+      - uses: some-org/active-action@v1
+"#,
+        );
+
+        let config = Config::default();
+        let allowed_orgs = AllowedOrgs::from_state(&audit_state(&config, None)).unwrap();
+        let mut actions =
+            extract_actions_from_workflow(&workflow, &allowed_orgs, &UsesResolver::default())
+                .unwrap();
+        actions[0].suppressed = true;
+
+        let summary = generate_summary(&actions);
+        assert_eq!(summary.total_actions, 1);
+        assert_eq!(summary.unpinned_third_party, 1);
+        assert_eq!(summary.suppressed, 1);
+        assert_eq!(summary.by_owner["some-org"].total, 1);
+    }
+
+    #[test]
+    fn test_is_shown() {
+        let workflow = workflow(
+            r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - uses: actions/setup-python@a26af69be951a213d495a4c3e4e4022e16d87065
+      - uses: some-org/some-action@v1
+      - uses: some-org/other-action@8f4b7f84864484a7bf31766abe9204da3cbe65b3
+"#,
+        );
+
+        let config = Config::default();
+        let allowed_orgs = AllowedOrgs::from_state(&audit_state(&config, None)).unwrap();
+        let actions =
+            extract_actions_from_workflow(&workflow, &allowed_orgs, &UsesResolver::default())
+                .unwrap();
+
+        for (show, expected) in [
+            (TpaShow::All, vec![true, true, true, true]),
+            (TpaShow::Unpinned, vec![true, false, true, false]),
+            (TpaShow::ThirdParty, vec![false, false, true, true]),
+            (TpaShow::UnpinnedThirdParty, vec![false, false, true, false]),
+        ] {
+            assert_eq!(
+                actions
+                    .iter()
+                    .map(|action| is_shown(show, action))
+                    .collect::<Vec<_>>(),
+                expected,
+                "{show:?}"
+            );
+        }
+    }
+
+    /// Flattens `value` into the sorted set of field paths it contains,
+    /// e.g. `actions[].kind`. Maps keyed by arbitrary data (like `by_file`)
+    /// are collapsed into a single `{}` component.
+    fn shape(value: &serde_json::Value, path: &str, paths: &mut BTreeSet<String>) {
+        match value {
+            serde_json::Value::Object(fields)
+                if path.ends_with("by_file")
+                    || path.ends_with("by_owner")
+                    || path.ends_with("by_repository") =>
+            {
+                for value in fields.values() {
+                    shape(value, &format!("{path}.{{}}"), paths);
+                }
+            }
+            serde_json::Value::Object(fields) => {
+                for (name, value) in fields {
+                    let path = if path.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{path}.{name}")
+                    };
+                    paths.insert(path.clone());
+                    shape(value, &path, paths);
+                }
+            }
+            serde_json::Value::Array(values) => {
+                for value in values {
+                    shape(value, &format!("{path}[]"), paths);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn example_report() -> ActionReport {
+        let config = Config::default();
+        let allowed_orgs = AllowedOrgs::from_state(&audit_state(&config, None)).unwrap();
+        let actions = extract_actions_from_workflow(
+            &workflow(
+                r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - uses: actions/checkout@v3
+      - uses: some-org/some-action/subpath@v1
+      - uses: docker://alpine:3.20
+  reusable:
+    uses: some-org/some-repo/.github/workflows/ci.yml@v1
+"#,
+            ),
+            &allowed_orgs,
+            &UsesResolver::default(),
+        )
+        .unwrap();
+
+        let report = build_report(actions);
+        let score = Score::new(
+            &ScoreWeights::default(),
+            &Counts {
+                unpinned_third_party: report.summary.unpinned_third_party,
+                ..Default::default()
+            },
+        );
+
+        ActionReport {
+            score: Some(score),
+            ..report
+        }
+    }
+
+    #[test]
+    fn test_compare_locked() {
+        let mut lockfile = Lockfile::new(datetime!(2025-05-01 12:30:00 UTC));
+        let checkout = lockfile
+            .actions
+            .entry("actions/checkout".into())
+            .or_default();
+        checkout.insert(
+            "v4".into(),
+            "11bd71901bbe5b1630ceea73d27597364c9af683".into(),
+        );
+        checkout.insert(
+            "v3".into(),
+            "f43a0e5ff2bd294095638e18286ca9a3d1956744".into(),
+        );
+        lockfile
+            .images
+            .entry("docker://alpine".into())
+            .or_default()
+            .insert("3.20".into(), None);
+
+        // `v4` moved, `v3` couldn't be resolved, and nothing else is locked.
+        let mut actions = example_report().actions;
+        for action in &mut actions {
+            if action.git_ref.as_deref() == Some("v4") {
+                action.suggested_sha = Some("08c6903cd8c0fde910a37f88322edcfb5dd907a8".into());
+            }
+        }
+        compare_locked(&lockfile, &mut actions);
+
+        let compared = actions
+            .iter()
+            .map(|a| {
+                (
+                    a.reference.as_str(),
+                    a.locked_sha.as_deref(),
+                    a.current_sha.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            compared,
+            [
+                (
+                    "actions/checkout@v4",
+                    Some("11bd71901bbe5b1630ceea73d27597364c9af683"),
+                    Some("08c6903cd8c0fde910a37f88322edcfb5dd907a8"),
+                ),
+                (
+                    "actions/checkout@v3",
+                    Some("f43a0e5ff2bd294095638e18286ca9a3d1956744"),
+                    None,
+                ),
+                ("some-org/some-action/subpath@v1", None, None),
+                ("docker://alpine:3.20", None, None),
+                ("some-org/some-repo/.github/workflows/ci.yml@v1", None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_owner_summaries() {
+        let config = Config::default();
+        let allowed_orgs = AllowedOrgs::from_state(&audit_state(&config, None)).unwrap();
+        let actions = extract_actions_from_workflow(
+            &workflow(
+                r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - uses: some-org/some-action@v1
+      - uses: some-org/some-action@v2
+      - uses: some-org/other-action@b4ffde65f46336ab88eb53be808477a3936bae11
+      - uses: done-org/tool@b4ffde65f46336ab88eb53be808477a3936bae11
+      - uses: another-org/tool@v1
+"#,
+            ),
+            &allowed_orgs,
+            &UsesResolver::default(),
+        )
+        .unwrap();
+
+        // Official owners aren't listed, and fully pinned owners are
+        // listed last, with no unpinned uses.
+        assert_eq!(
+            owner_summaries(&build_report(actions)),
+            [
+                OwnerSummary {
+                    owner: "some-org",
+                    actions: 2,
+                    uses: 3,
+                    unpinned: 2,
+                },
+                OwnerSummary {
+                    owner: "another-org",
+                    actions: 1,
+                    uses: 1,
+                    unpinned: 1,
+                },
+                OwnerSummary {
+                    owner: "done-org",
+                    actions: 1,
+                    uses: 1,
+                    unpinned: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_round_trip() {
+        let report = example_report();
+
+        let json = serde_json::to_string(&report).unwrap();
+        let parsed = serde_json::from_str::<ActionReport>(&json).unwrap();
+        assert_eq!(parsed, report);
+        assert_eq!(parsed.schema_version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_write_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/reports/all_actions.json");
+
+        // Missing parent directories are created.
+        write_report(&path, &example_report()).unwrap();
+        let report =
+            serde_json::from_str::<ActionReport>(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(report, example_report());
+
+        // Existing reports are replaced, and no temporary files are left behind.
+        write_report(&path, &build_report(vec![])).unwrap();
+        let report =
+            serde_json::from_str::<ActionReport>(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(report.actions.is_empty());
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            1
+        );
+
+        // Failures name the report's path.
+        let bad = dir
+            .path()
+            .join("nested/reports/all_actions.json/report.json");
+        let err = write_report(&bad, &example_report()).unwrap_err();
+        assert!(err.to_string().contains(&bad.display().to_string()));
+    }
+
+    #[test]
+    fn test_report_schema() {
+        // If this test fails, the report's serialized shape has changed:
+        // bump SCHEMA_VERSION, and then update the version and shape below.
+        assert_eq!(SCHEMA_VERSION, 11);
+
+        let mut paths = BTreeSet::new();
+        shape(
+            &serde_json::to_value(example_report()).unwrap(),
+            "",
+            &mut paths,
+        );
+
+        assert_eq!(
+            paths.iter().map(String::as_str).collect::<Vec<_>>(),
+            [
+                "actions",
+                "actions[].column",
+                "actions[].current_sha",
+                "actions[].file_path",
+                "actions[].floating_tag",
+                "actions[].host",
+                "actions[].job_id",
+                "actions[].kind",
+                "actions[].line",
+                "actions[].line_number",
+                "actions[].locked_sha",
+                "actions[].owner",
+                "actions[].pinned_to_sha",
+                "actions[].ref",
+                "actions[].reference",
+                "actions[].registry",
+                "actions[].repo",
+                "actions[].repository",
+                "actions[].step_index",
+                "actions[].subpath",
+                "actions[].suggested_sha",
+                "actions[].suggested_tag",
+                "actions[].suppressed",
+                "actions[].template",
+                "actions[].third_party",
+                "actions[].transitive",
+                "inconsistent_pins",
+                "inconsistent_pins[].action",
+                "inconsistent_pins[].refs",
+                "inconsistent_pins[].refs[].files",
+                "inconsistent_pins[].refs[].ref",
+                "schema_version",
+                "score",
+                "score.components",
+                "score.components[].count",
+                "score.components[].name",
+                "score.components[].penalty",
+                "score.components[].weight",
+                "score.formula",
+                "score.value",
+                "summary",
+                "summary.by_file",
+                "summary.by_file.{}.official",
+                "summary.by_file.{}.pinned_third_party",
+                "summary.by_file.{}.total",
+                "summary.by_file.{}.unpinned_third_party",
+                "summary.by_owner",
+                "summary.by_owner.{}.official",
+                "summary.by_owner.{}.pinned_third_party",
+                "summary.by_owner.{}.total",
+                "summary.by_owner.{}.unpinned_third_party",
+                "summary.by_repository",
+                "summary.docker_images",
+                "summary.official_actions",
+                "summary.pinned_third_party",
+                "summary.reusable_workflows",
+                "summary.suppressed",
+                "summary.total_actions",
+                "summary.unpinned_third_party",
+                "summary.unpinned_third_party_docker_images",
+                "summary.unpinned_third_party_reusable_workflows",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_inconsistent_pins() {
+        let config = Config::default();
+        let allowed_orgs = AllowedOrgs::from_state(&audit_state(&config, None)).unwrap();
+
+        let mut actions = extract_actions_from_workflow(
+            &workflow(
+                r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - uses: actions/checkout@v4
+      - uses: some-org/some-action@v1
+      - uses: some-org/some-action@v1
+"#,
+            ),
+            &allowed_orgs,
+            &UsesResolver::default(),
+        )
+        .unwrap();
+
+        // Exact duplicates aren't inconsistent.
+        assert!(find_inconsistent_pins(&actions).is_empty());
+
+        actions.extend(
+            extract_actions_from_workflow(
+                &workflow_at(
+                    ".github/workflows/other.yml",
+                    r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@11bd71901bbe5b1630ceea73d27597364c9af683
+      - uses: some-org/some-action@v1
+"#,
+                ),
+                &allowed_orgs,
+                &UsesResolver::default(),
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(
+            find_inconsistent_pins(&actions),
+            [InconsistentPin {
+                action: "actions/checkout".into(),
+                refs: vec![
+                    PinUsage {
+                        git_ref: "11bd71901bbe5b1630ceea73d27597364c9af683".into(),
+                        files: vec![".github/workflows/other.yml".into()],
+                    },
+                    PinUsage {
+                        git_ref: "v4".into(),
+                        files: vec![".github/workflows/test.yml".into()],
+                    },
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_render_grouped_by_action() {
+        let workflow = workflow(
+            r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: other-org/other-action@v1
+      - uses: some-org/some-action@v3
+      - uses: some-org/some-action@v4
+      - uses: some-org/some-action@v3
+"#,
+        );
+
+        let config = Config::default();
+        let allowed_orgs = AllowedOrgs::from_state(&audit_state(&config, None)).unwrap();
+        let actions =
+            extract_actions_from_workflow(&workflow, &allowed_orgs, &UsesResolver::default())
+                .unwrap();
+
+        let mut out = vec![];
+        render_grouped_by_action(&mut out, actions.iter()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+some-org/some-action (3 occurrences; refs: v3, v4)
+  .github/workflows/test.yml:9
+  .github/workflows/test.yml:10
+  .github/workflows/test.yml:11
+other-org/other-action (1 occurrence; refs: v1)
+  .github/workflows/test.yml:8
+"
+        );
+    }
+
+    #[test]
+    fn test_failure_reason() {
+        let summary = Summary {
+            total_actions: 3,
+            unpinned_third_party: 0,
+            pinned_third_party: 1,
+            official_actions: 2,
+            docker_images: 0,
+            unpinned_third_party_docker_images: 0,
+            reusable_workflows: 0,
+            unpinned_third_party_reusable_workflows: 0,
+            by_file: Default::default(),
+            by_owner: Default::default(),
+            by_repository: Default::default(),
+            suppressed: 0,
+        };
+
+        assert_eq!(
+            failure_reason(TpaFailOn::UnpinnedThirdParty, &summary),
+            None
+        );
+        assert_eq!(
+            failure_reason(TpaFailOn::AnyThirdParty, &summary).as_deref(),
+            Some("failing: 1 third-party action found (--tpa-fail-on=any-third-party)")
+        );
+        assert_eq!(failure_reason(TpaFailOn::Never, &summary), None);
+    }
+
+    #[test]
+    fn test_collect_actions_without_findings() {
+        // A remote input, which doesn't exist anywhere on disk.
+        let Uses::Repository(slug) = Uses::from_str("some-org/some-repo@v1").unwrap() else {
+            unreachable!()
+        };
+        let key = InputKey::remote(&slug, ".github/workflows/ci.yml".into()).unwrap();
+        let workflow = Workflow::from_string(
+            r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@11bd71901bbe5b1630ceea73d27597364c9af683
+"#
+            .into(),
+            key,
+        )
+        .unwrap();
+
+        let mut registry = InputRegistry::new(false);
+        registry.register_input(workflow.into()).unwrap();
+
+        let app = App::parse_from(["zizmor", "some-org/some-repo"]);
+        let config = Config::default();
+        let allowed_orgs = AllowedOrgs::from_state(&audit_state(&config, None)).unwrap();
+
+        // The workflow has no findings, but still contributes its actions.
+        let results = FindingRegistry::new(&app, &config, &registry, None);
+        let actions = collect_actions(
+            &registry,
+            &allowed_orgs,
+            &UsesResolver::default(),
+            &results,
+            TpaSuppressed::Mark,
+        )
+        .unwrap();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].repository.as_deref(), Some("some-org/some-repo"));
+        assert!(actions[0].pinned_to_sha);
+        assert!(!actions[0].third_party);
+
+        let summary = generate_summary(&actions);
+        assert_eq!(summary.total_actions, 1);
+        assert_eq!(summary.official_actions, 1);
+    }
+
+    /// Writes the TPA report for `contents`, with all of its `unpinned-uses`
+    /// findings ignored and the annotations of its third-party unpinned
+    /// findings first reworded with `reword`.
+    fn report_with(contents: &str, reword: impl Fn(&str) -> String) -> String {
+        let key = InputKey::local(".github/workflows/test.yml", None).unwrap();
+        let workflow = Workflow::from_string(contents.into(), key.clone()).unwrap();
+
+        let mut registry = InputRegistry::new(false);
+        registry.register_input(workflow.into()).unwrap();
+
+        let app = App::parse_from(["zizmor", "."]);
+        let config = Config::default();
+        let state = audit_state(&config, None);
+        let allowed_orgs = AllowedOrgs::from_state(&state).unwrap();
+
+        let input: &AuditInput = registry.get_input(&key);
+        let mut findings = UnpinnedUses::new(&state).unwrap().audit(input).unwrap();
+        for finding in &mut findings {
+            finding.ignored = true;
+            if finding.tags.contains(&FindingTag::ThirdPartyUnpinned) {
+                for location in &mut finding.locations {
+                    location.symbolic.annotation = reword(&location.symbolic.annotation);
+                }
+            }
+        }
+
+        let mut results = FindingRegistry::new(&app, &config, &registry, None);
+        results.extend(findings);
+
+        let actions = collect_actions(
+            &registry,
+            &allowed_orgs,
+            &UsesResolver::default(),
+            &results,
+            TpaSuppressed::Mark,
+        )
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("all_actions.json");
+        write_report(&path, &build_report(actions)).unwrap();
+        std::fs::read_to_string(&path).unwrap()
+    }
+
+    #[test]
+    fn test_report_ignores_annotations() {
+        let contents = r#"
+on: push
+
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout
+      - uses: some-org/some-action@v1
+      - uses: other-org/other-action@main
+"#;
+
+        let original = report_with(contents, str::to_string);
+        let report = serde_json::from_str::<ActionReport>(&original).unwrap();
+        // Only the third-party actions are suppressed, even though the
+        // first-party action's finding was ignored too.
+        assert_eq!(
+            report
+                .actions
+                .iter()
+                .map(|a| (a.reference.as_str(), a.suppressed))
+                .collect::<Vec<_>>(),
+            [
+                ("actions/checkout", false),
+                ("some-org/some-action@v1", true),
+                ("other-org/other-action@main", true),
+            ]
+        );
+        assert_eq!(report.summary.suppressed, 2);
+
+        let reworded = report_with(contents, |annotation| {
+            format!("reworded: {}", annotation.to_uppercase())
+        });
+        assert_eq!(original, reworded);
+    }
+}
@@ -0,0 +1,117 @@
+//! Markdown output for the third-party action report, suitable for
+//! posting into PR comments.
+
+use std::io;
+
+use anyhow::Result;
+
+use super::tpa_list::{Action, Summary, collect_actions, generate_summary};
+use crate::App;
+use crate::audit::unpinned_uses::AllowedOrgs;
+use crate::registry::{FindingRegistry, InputRegistry};
+use crate::resolver::UsesResolver;
+
+/// Escapes `contents` for use within a Markdown table cell.
+fn cell(contents: &str) -> String {
+    contents.replace('|', "\\|")
+}
+
+fn render_summary(sink: &mut impl io::Write, summary: &Summary) -> Result<()> {
+    writeln!(
+        sink,
+        "| Total actions | Unpinned third-party | Pinned third-party | Official |"
+    )?;
+    writeln!(sink, "| --- | --- | --- | --- |")?;
+    writeln!(
+        sink,
+        "| {} | {} | {} | {} |",
+        summary.total_actions,
+        summary.unpinned_third_party,
+        summary.pinned_third_party,
+        summary.official_actions
+    )?;
+
+    Ok(())
+}
+
+fn render_actions<'a>(
+    sink: &mut impl io::Write,
+    actions: impl Iterator<Item = &'a Action>,
+) -> Result<()> {
+    writeln!(sink, "| Action | Ref | File | Line |")?;
+    writeln!(sink, "| --- | --- | --- | --- |")?;
+
+    for action in actions {
+        let git_ref = match &action.git_ref {
+            Some(git_ref) => format!("`{}`", cell(git_ref)),
+            None => "*(none)*".into(),
+        };
+
+        writeln!(
+            sink,
+            "| `{name}` | {git_ref} | `{file}` | {line} |",
+            name = cell(&action.name()),
+            file = cell(&action.file_path),
+            line = action.line_number,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Renders the given actions as a Markdown report, leaving out any
+/// suppressed actions.
+pub(crate) fn render(sink: &mut impl io::Write, actions: &[Action]) -> Result<()> {
+    let summary = generate_summary(actions);
+    let (unpinned, rest): (Vec<_>, Vec<_>) = actions
+        .iter()
+        .filter(|action| !action.suppressed)
+        .partition(|action| action.third_party && !action.pinned_to_sha);
+
+    writeln!(sink, "# Third-party action report")?;
+    writeln!(sink)?;
+    render_summary(sink, &summary)?;
+    writeln!(sink)?;
+
+    writeln!(sink, "## Unpinned third-party actions")?;
+    writeln!(sink)?;
+    if unpinned.is_empty() {
+        writeln!(sink, "No unpinned third-party actions found.")?;
+    } else {
+        render_actions(sink, unpinned.into_iter())?;
+    }
+
+    if !rest.is_empty() {
+        writeln!(sink)?;
+        writeln!(sink, "<details>")?;
+        writeln!(sink, "<summary>Other actions ({})</summary>", rest.len())?;
+        writeln!(sink)?;
+        render_actions(sink, rest.into_iter())?;
+        writeln!(sink)?;
+        writeln!(sink, "</details>")?;
+    }
+
+    Ok(())
+}
+
+/// Output the TPA report as Markdown.
+pub(crate) fn output(
+    mut sink: impl io::Write,
+    app: &App,
+    registry: &InputRegistry,
+    allowed_orgs: &AllowedOrgs,
+    resolver: &UsesResolver,
+    results: &FindingRegistry,
+) -> Result<Summary> {
+    let actions = collect_actions(
+        registry,
+        allowed_orgs,
+        resolver,
+        results,
+        app.tpa_suppressed,
+    )?;
+
+    render(&mut sink, &actions)?;
+
+    Ok(generate_summary(&actions))
+}
@@ -0,0 +1,266 @@
+//! SARIF output for the third-party action report.
+//!
+//! Each unpinned third-party action becomes a result under the
+//! `unpinned-uses` rule, with a partial fingerprint that's stable across
+//! unrelated edits to the file, so that code scanning tracks each
+//! alert across runs instead of reopening it.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde_sarif::sarif::{
+    ArtifactLocation, Invocation, Location as SarifLocation, Message, MultiformatMessageString,
+    PhysicalLocation, Region, ReportingDescriptor, Result as SarifResult, ResultKind, Run, Sarif,
+    Tool, ToolComponent,
+};
+use sha2::{Digest as _, Sha256};
+
+use super::sarif::{default_configuration, level, properties};
+use super::tpa_list::{Action, Summary, collect_actions, generate_summary};
+use crate::App;
+use crate::audit::AuditCore as _;
+use crate::audit::unpinned_uses::{AllowedOrgs, THIRD_PARTY_MESSAGE, UnpinnedUses};
+use crate::config::Config;
+use crate::registry::{FindingRegistry, InputRegistry};
+use crate::resolver::UsesResolver;
+
+/// The key that each result's fingerprint is stored under.
+const FINGERPRINT_KEY: &str = "unpinnedUsesHash/v1";
+
+const HELP_TEXT: &str = "\
+Third-party actions should be pinned to a full commit SHA, e.g. \
+`owner/repo@<sha> # v1.2.3`. Tags and branches are mutable: whoever controls \
+the action's repository can change the code they point to at any time, \
+including after it's been reviewed. A commit SHA can't be changed, so pinning \
+to one ensures that your workflows run exactly the code you expect.";
+
+fn rule_id() -> String {
+    format!("zizmor/{id}", id = UnpinnedUses::ident())
+}
+
+/// Computes the partial fingerprint for `action` in the file at `uri`.
+///
+/// This deliberately excludes the action's position, since line numbers
+/// churn whenever the file is edited. The action's name (but not its ref)
+/// is normalized to lowercase, just as GitHub treats it.
+fn fingerprint(action: &Action, uri: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(action.name().to_lowercase());
+    hasher.update("@");
+    hasher.update(action.git_ref.as_deref().unwrap_or_default());
+    hasher.update("\0");
+    hasher.update(uri);
+
+    format!("{:x}", hasher.finalize())
+}
+
+fn build_rule(config: &Config) -> ReportingDescriptor {
+    ReportingDescriptor::builder()
+        .id(rule_id())
+        .name(UnpinnedUses::ident())
+        .short_description(
+            MultiformatMessageString::builder()
+                .text(UnpinnedUses::desc())
+                .build(),
+        )
+        .help_uri(UnpinnedUses::url())
+        .help(
+            MultiformatMessageString::builder()
+                .text(HELP_TEXT)
+                .markdown(UnpinnedUses::remediation())
+                .build(),
+        )
+        .default_configuration(default_configuration(
+            config,
+            UnpinnedUses::ident(),
+            UnpinnedUses::default_severity(),
+        ))
+        .properties(
+            // NOTE: Safe unwrap, since there's always a tag.
+            properties(
+                config,
+                UnpinnedUses::ident(),
+                UnpinnedUses::default_severity(),
+                vec!["security".into()],
+            )
+            .unwrap(),
+        )
+        .build()
+}
+
+fn build_result(action: &Action, uri: &str, config: &Config) -> SarifResult {
+    let mut result = SarifResult::builder()
+        .rule_id(rule_id())
+        .message(
+            Message::builder()
+                .text(format!("{THIRD_PARTY_MESSAGE}: {}", action.reference))
+                .build(),
+        )
+        .locations([SarifLocation::builder()
+            .physical_location(
+                PhysicalLocation::builder()
+                    .artifact_location(ArtifactLocation::builder().uri(uri).build())
+                    .region(
+                        Region::builder()
+                            // NOTE: Both of these are already 1-based, like SARIF's.
+                            .start_line(action.line_number as i64)
+                            .start_column(action.column as i64)
+                            .source_language("yaml")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .build()])
+        .partial_fingerprints([(FINGERPRINT_KEY.into(), fingerprint(action, uri))])
+        .level(level(
+            config,
+            UnpinnedUses::ident(),
+            UnpinnedUses::default_severity(),
+        ))
+        .kind(ResultKind::Fail)
+        .build();
+
+    result.properties = properties(
+        config,
+        UnpinnedUses::ident(),
+        UnpinnedUses::default_severity(),
+        vec![],
+    );
+
+    result
+}
+
+/// Builds a SARIF log with a result for each unpinned third-party action.
+///
+/// `uris` maps each action's file path to its SARIF artifact URI.
+pub(crate) fn build(actions: &[Action], uris: &HashMap<&str, &str>, config: &Config) -> Sarif {
+    let results = actions
+        .iter()
+        .filter(|action| action.third_party && !action.pinned_to_sha && !action.suppressed)
+        .map(|action| {
+            let uri = uris
+                .get(action.file_path.as_str())
+                .copied()
+                .unwrap_or(&action.file_path);
+            build_result(action, uri, config)
+        })
+        .collect::<Vec<_>>();
+
+    let run = Run::builder()
+        .tool(
+            Tool::builder()
+                .driver(
+                    ToolComponent::builder()
+                        .name(env!("CARGO_CRATE_NAME"))
+                        .version(env!("CARGO_PKG_VERSION"))
+                        .semantic_version(env!("CARGO_PKG_VERSION"))
+                        .download_uri(env!("CARGO_PKG_REPOSITORY"))
+                        .information_uri(env!("CARGO_PKG_HOMEPAGE"))
+                        .rules([build_rule(config)])
+                        .build(),
+                )
+                .build(),
+        )
+        .results(results)
+        .invocations([Invocation::builder().execution_successful(true).build()])
+        .build();
+
+    Sarif::builder()
+        .version("2.1.0")
+        .schema("https://docs.oasis-open.org/sarif/sarif/v2.1.0/os/schemas/sarif-schema-2.1.0.json")
+        .runs([run])
+        .build()
+}
+
+/// Output the TPA report as SARIF.
+pub(crate) fn output(
+    sink: impl std::io::Write,
+    app: &App,
+    config: &Config,
+    registry: &InputRegistry,
+    allowed_orgs: &AllowedOrgs,
+    resolver: &UsesResolver,
+    results: &FindingRegistry,
+) -> Result<Summary> {
+    let actions = collect_actions(
+        registry,
+        allowed_orgs,
+        resolver,
+        results,
+        app.tpa_suppressed,
+    )?;
+    let uris = registry
+        .iter_inputs()
+        .map(|(key, _)| (key.presentation_path(), key.sarif_path()))
+        .collect();
+
+    serde_json::to_writer_pretty(sink, &build(&actions, &uris, config))?;
+
+    Ok(generate_summary(&actions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fingerprint;
+    use crate::output::tpa_list::{Action, ActionKind};
+
+    fn action(reference: &str, owner: &str, git_ref: &str, line_number: usize) -> Action {
+        Action {
+            kind: ActionKind::Action,
+            reference: reference.into(),
+            owner: owner.into(),
+            repo: "some-action".into(),
+            subpath: None,
+            host: Some("github.com".into()),
+            registry: None,
+            git_ref: Some(git_ref.into()),
+            pinned_to_sha: false,
+            floating_tag: false,
+            third_party: true,
+            line: format!("uses: {reference}"),
+            file_path: ".github/workflows/test.yml".into(),
+            repository: None,
+            line_number,
+            column: 9,
+            job_id: Some("build".into()),
+            step_index: Some(0),
+            suggested_sha: None,
+            suggested_tag: None,
+            locked_sha: None,
+            current_sha: None,
+            suppressed: false,
+            transitive: false,
+            template: false,
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_position_and_case() {
+        let uri = ".github/workflows/test.yml";
+        let base = fingerprint(&action("some-org/some-action@v1", "some-org", "v1", 8), uri);
+
+        assert_eq!(
+            base,
+            fingerprint(
+                &action("some-org/some-action@v1", "some-org", "v1", 20),
+                uri
+            )
+        );
+        assert_eq!(
+            base,
+            fingerprint(&action("Some-Org/some-action@v1", "Some-Org", "v1", 8), uri)
+        );
+
+        assert_ne!(
+            base,
+            fingerprint(&action("some-org/some-action@v2", "some-org", "v2", 8), uri)
+        );
+        assert_ne!(
+            base,
+            fingerprint(
+                &action("some-org/some-action@v1", "some-org", "v1", 8),
+                ".github/workflows/other.yml"
+            )
+        );
+    }
+}
@@ -2,24 +2,30 @@
 //! audits.
 
 use std::{
-    collections::{BTreeMap, btree_map},
+    collections::{BTreeMap, HashMap, HashSet, btree_map},
     fmt::Display,
     process::ExitCode,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
 };
 
 use anyhow::{Context, anyhow};
-use camino::{Utf8Path, Utf8PathBuf};
-use github_actions_models::common::RepositoryUses;
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use github_actions_models::common::{LocalUses, RepositoryUses, Uses};
 use indexmap::IndexMap;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use time::{Date, OffsetDateTime};
 
 use crate::{
-    App,
-    audit::{Audit, AuditInput},
-    config::Config,
-    finding::{Confidence, Finding, Persona, Severity},
-    models::{Action, Workflow},
+    App, FailOn, Rollup, RollupCount,
+    audit::{self, Audit, AuditCore as _, AuditInput, expired_ignores::ExpiredIgnores},
+    baseline::{Baseline, BaselineEntry},
+    changed::ChangedFiles,
+    config::{Config, IgnoreEntry},
+    finding::{Confidence, ExternalLocation, Finding, Persona, Severity},
+    models::{Action, AsDocument as _, Workflow},
+    output::summary::SeverityCounts,
 };
 
 #[derive(Error, Debug)]
@@ -54,7 +60,71 @@ pub(crate) enum InputKind {
     Action,
 }
 
-#[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, PartialOrd, Ord)]
+/// Whether an input was collected from a standard location, per `--collect`.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Collection {
+    /// The input is in a location that GitHub uses, e.g. directly within
+    /// `.github/workflows/`, or was given explicitly.
+    #[default]
+    Standard,
+    /// The input is somewhere else, e.g. in a subdirectory of
+    /// `.github/workflows/`, and was only collected because of `--collect`.
+    Extra,
+}
+
+impl Collection {
+    pub(crate) fn is_standard(&self) -> bool {
+        matches!(self, Collection::Standard)
+    }
+}
+
+/// Whether `path` is a workflow template, i.e. a YAML file directly within
+/// a `workflow-templates/` directory, like those in an org's `.github`
+/// repository.
+///
+/// Each template's `.properties.json` sibling only holds metadata for
+/// GitHub's "new workflow" UI, and isn't an input.
+pub(crate) fn is_template_path(path: &Utf8Path) -> bool {
+    matches!(path.extension(), Some("yml" | "yaml"))
+        && path
+            .parent()
+            .and_then(|dir| dir.file_name())
+            .is_some_and(|dir| dir == "workflow-templates")
+}
+
+/// Normalizes the path of a local `uses: ./path` clause, returning `None`
+/// if it escapes the repository root (e.g. `./../other`).
+fn normalize_local_path(path: &str) -> Option<Utf8PathBuf> {
+    let mut normalized = Utf8PathBuf::new();
+    for component in Utf8Path::new(path).components() {
+        match component {
+            Utf8Component::CurDir => {}
+            Utf8Component::Normal(name) => normalized.push(name),
+            Utf8Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            Utf8Component::RootDir | Utf8Component::Prefix(_) => return None,
+        }
+    }
+
+    Some(normalized)
+}
+
+/// Where a local `uses: ./path` action points; see [`InputKey::local_action`].
+#[derive(Debug, PartialEq)]
+pub(crate) enum LocalAction {
+    /// The path escapes the repository root.
+    Escapes,
+    /// The action's manifest, i.e. its `action.yml` or `action.yaml`.
+    Manifest(Utf8PathBuf),
+    /// The action's directory, which doesn't contain a manifest.
+    Missing(Utf8PathBuf),
+}
+
+#[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize, PartialOrd, Ord)]
 pub(crate) struct LocalKey {
     /// The path's nondeterministic prefix, if any.
     prefix: Option<Utf8PathBuf>,
@@ -62,12 +132,17 @@ pub(crate) struct LocalKey {
     given_path: Utf8PathBuf,
 }
 
-#[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, PartialOrd, Ord)]
+#[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize, PartialOrd, Ord)]
 pub(crate) struct RemoteKey {
     owner: String,
     repo: String,
     git_ref: Option<String>,
     path: Utf8PathBuf,
+    /// The synthetic path that findings are attributed to, for actions
+    /// that are only audited because another input uses them;
+    /// see [`InputKey::transitive`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transitive_path: Option<Utf8PathBuf>,
 }
 
 /// A unique identifying "key" for a workflow file in a given run of zizmor.
@@ -75,7 +150,7 @@ pub(crate) struct RemoteKey {
 /// zizmor currently knows two different kinds of keys: local keys
 /// are just canonical paths to files on disk, while remote keys are
 /// relative paths within a referenced GitHub repository.
-#[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, PartialOrd, Ord)]
+#[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize, PartialOrd, Ord)]
 pub(crate) enum InputKey {
     Local(LocalKey),
     Remote(RemoteKey),
@@ -126,9 +201,54 @@ impl InputKey {
             repo: slug.repo.clone(),
             git_ref: slug.git_ref.clone(),
             path: path.into(),
+            transitive_path: None,
         }))
     }
 
+    /// Creates a key for an action that's audited transitively, i.e.
+    /// because another input uses it, rather than because it was
+    /// requested directly.
+    ///
+    /// Findings in these actions are attributed to a synthetic path
+    /// like `remote:owner/repo@ref/action.yml`, so that they can't be
+    /// confused with local files (or with each other).
+    pub(crate) fn transitive(slug: &RepositoryUses, path: String) -> Result<Self, InputError> {
+        let Self::Remote(mut remote) = Self::remote(slug, path)? else {
+            unreachable!()
+        };
+
+        let mut uses = format!("{owner}/{repo}", owner = slug.owner, repo = slug.repo);
+        if let Some(subpath) = &slug.subpath {
+            uses.push('/');
+            uses.push_str(subpath);
+        }
+        let git_ref = slug.git_ref.as_deref().unwrap_or("HEAD");
+        let filename = remote.path.file_name().unwrap_or_default();
+        remote.transitive_path = Some(format!("remote:{uses}@{git_ref}/{filename}").into());
+
+        Ok(Self::Remote(remote))
+    }
+
+    /// Whether this [`InputKey`] is for an action that's audited
+    /// transitively; see [`InputKey::transitive`].
+    pub(crate) fn is_transitive(&self) -> bool {
+        matches!(self, InputKey::Remote(remote) if remote.transitive_path.is_some())
+    }
+
+    /// Whether this [`InputKey`] is for a workflow template, i.e. a
+    /// workflow directly within a `workflow-templates/` directory.
+    ///
+    /// Org `.github` repositories use these to seed new repositories'
+    /// workflows; see [`is_template_path`].
+    pub(crate) fn is_template(&self) -> bool {
+        match self {
+            InputKey::Local(local) => is_template_path(&local.given_path),
+            InputKey::Remote(remote) => {
+                remote.transitive_path.is_none() && is_template_path(&remote.path)
+            }
+        }
+    }
+
     /// Returns a path for this [`InputKey`] that's suitable for SARIF
     /// outputs.
     ///
@@ -151,21 +271,143 @@ impl InputKey {
                 .and_then(|pfx| local.given_path.strip_prefix(pfx).ok())
                 .unwrap_or_else(|| &local.given_path)
                 .as_str(),
-            InputKey::Remote(remote) => remote.path.as_str(),
+            InputKey::Remote(remote) => remote
+                .transitive_path
+                .as_ref()
+                .unwrap_or(&remote.path)
+                .as_str(),
         }
     }
 
     /// Return a "presentation" path for this [`InputKey`].
     ///
-    /// This will always be a relative path for remote keys,
-    /// and will be the given path for local keys.
+    /// This will always be a relative (or synthetic, for transitive) path
+    /// for remote keys, and will be the given path for local keys.
     pub(crate) fn presentation_path(&self) -> &str {
         match self {
             InputKey::Local(local) => local.given_path.as_str(),
-            InputKey::Remote(remote) => remote.path.as_str(),
+            InputKey::Remote(remote) => remote
+                .transitive_path
+                .as_ref()
+                .unwrap_or(&remote.path)
+                .as_str(),
         }
     }
 
+    /// Returns the owner of the repository this [`InputKey`] was
+    /// fetched from, if it's a remote key.
+    pub(crate) fn remote_owner(&self) -> Option<&str> {
+        match self {
+            InputKey::Local(_) => None,
+            InputKey::Remote(remote) => Some(&remote.owner),
+        }
+    }
+
+    /// Returns the `owner/repo` slug of the repository this [`InputKey`]
+    /// was fetched from, if it's a remote key.
+    ///
+    /// Transitive keys have no repository, since their synthetic path
+    /// already identifies it.
+    pub(crate) fn repository(&self) -> Option<String> {
+        match self {
+            InputKey::Local(_) => None,
+            InputKey::Remote(remote) if remote.transitive_path.is_some() => None,
+            InputKey::Remote(remote) => Some(format!(
+                "{owner}/{repo}",
+                owner = remote.owner,
+                repo = remote.repo
+            )),
+        }
+    }
+
+    /// Returns the key that the reusable workflow called with `uses` would
+    /// have, if it were collected in the same run as this (calling) input.
+    ///
+    /// Local `uses:` paths are relative to the caller's repository root,
+    /// which for local inputs is the directory containing `.github/`.
+    /// Returns `None` when that root can't be determined, or for `uses:`
+    /// clauses that can't call a workflow.
+    pub(crate) fn reusable_workflow_key(&self, uses: &Uses) -> Option<InputKey> {
+        match uses {
+            Uses::Local(local) => {
+                let path = local.path.trim_start_matches("./");
+                match self {
+                    InputKey::Local(caller) => {
+                        let root = caller
+                            .given_path
+                            .ancestors()
+                            .find(|dir| {
+                                dir.file_name() == Some("workflows")
+                                    && dir.parent().and_then(Utf8Path::file_name) == Some(".github")
+                            })?
+                            .parent()?
+                            .parent()?;
+
+                        Some(InputKey::Local(LocalKey {
+                            prefix: caller.prefix.clone(),
+                            given_path: root.join(path),
+                        }))
+                    }
+                    InputKey::Remote(caller) => Some(InputKey::Remote(RemoteKey {
+                        owner: caller.owner.clone(),
+                        repo: caller.repo.clone(),
+                        git_ref: caller.git_ref.clone(),
+                        path: path.into(),
+                        transitive_path: None,
+                    })),
+                }
+            }
+            Uses::Repository(uses) => InputKey::remote(uses, uses.subpath.clone()?).ok(),
+            Uses::Docker(_) => None,
+        }
+    }
+
+    /// Resolves the local action used with `uses` against the root of
+    /// this input's repository, which for local inputs is the directory
+    /// containing `.github/`.
+    ///
+    /// Paths that escape the root are detected for every input, but
+    /// manifests are only looked up for local inputs. Returns `None` when
+    /// the root can't be determined.
+    pub(crate) fn local_action(&self, uses: &LocalUses) -> Option<LocalAction> {
+        let Some(path) = normalize_local_path(&uses.path) else {
+            return Some(LocalAction::Escapes);
+        };
+
+        let InputKey::Local(local) = self else {
+            return None;
+        };
+
+        let dir = local
+            .given_path
+            .ancestors()
+            .find(|dir| dir.file_name() == Some(".github"))?
+            .parent()?
+            .join(path);
+
+        Some(
+            ["action.yml", "action.yaml"]
+                .into_iter()
+                .map(|filename| dir.join(filename))
+                .find(|manifest| manifest.is_file())
+                .map_or(LocalAction::Missing(dir), LocalAction::Manifest),
+        )
+    }
+
+    /// Creates a key for the local action manifest at `path`, which this
+    /// (local) input uses, sharing this input's prefix where it applies.
+    pub(crate) fn local_action_key(&self, path: &Utf8Path) -> Result<InputKey, InputError> {
+        let prefix = match self {
+            InputKey::Local(local) => local
+                .prefix
+                .as_deref()
+                .filter(|prefix| path.starts_with(prefix)),
+            InputKey::Remote(_) => None,
+        };
+
+        InputKey::local(path, prefix)
+    }
+
     /// Returns the filename component of this [`InputKey`].
     pub(crate) fn filename(&self) -> &str {
         // NOTE: Safe unwraps, since the presence of a filename component
@@ -177,6 +419,11 @@ impl InputKey {
     }
 }
 
+/// Every collected input, parsed exactly once.
+///
+/// The registry owns each input's source text along with its parsed
+/// models, so audits and output formatters borrow from it rather than
+/// re-reading or re-parsing inputs themselves.
 pub(crate) struct InputRegistry {
     strict: bool,
     // NOTE: We use a BTreeMap here to ensure that registered inputs
@@ -184,6 +431,20 @@ pub(crate) struct InputRegistry {
     // while snapshot testing across multiple input files, and makes
     // the user experience more predictable.
     pub(crate) inputs: BTreeMap<InputKey, AuditInput>,
+    /// Configurations discovered at the roots of individual input
+    /// targets; see [`InputRegistry::config_index`].
+    configs: Vec<Config>,
+    /// The index into `configs` of each input's target configuration.
+    input_configs: HashMap<InputKey, usize>,
+    /// The inputs that were collected from non-standard locations.
+    extra: HashSet<InputKey>,
+    /// The inputs that were collected from `vendored-paths`.
+    vendored: HashSet<InputKey>,
+    /// The number of local inputs skipped by [`InputRegistry::skip_unchanged`].
+    unchanged: usize,
+    /// The local actions that were only collected because another input
+    /// uses them, along with that input; see [`InputRegistry::caller`].
+    callers: HashMap<InputKey, InputKey>,
 }
 
 impl InputRegistry {
@@ -191,6 +452,12 @@ impl InputRegistry {
         Self {
             strict,
             inputs: Default::default(),
+            configs: Default::default(),
+            input_configs: Default::default(),
+            extra: Default::default(),
+            vendored: Default::default(),
+            unchanged: 0,
+            callers: Default::default(),
         }
     }
 
@@ -223,8 +490,62 @@ impl InputRegistry {
         }
     }
 
+    /// Like [`InputRegistry::register`], but records how the input was
+    /// collected; see [`InputRegistry::collection`] and
+    /// [`InputRegistry::is_vendored`].
+    pub(crate) fn register_collected(
+        &mut self,
+        kind: InputKind,
+        contents: String,
+        key: InputKey,
+        collection: Collection,
+        vendored: bool,
+    ) -> anyhow::Result<()> {
+        self.register(kind, contents, key.clone())?;
+
+        // NOTE: Inputs that failed to load (but didn't fail the run)
+        // aren't registered, and so aren't tracked either.
+        if !self.inputs.contains_key(&key) {
+            return Ok(());
+        }
+
+        if !collection.is_standard() {
+            self.extra.insert(key.clone());
+        }
+        if vendored {
+            self.vendored.insert(key);
+        }
+
+        Ok(())
+    }
+
+    /// Registers the local action at `key`, which is only being collected
+    /// because `caller` uses it. The action shares its caller's target
+    /// configuration.
+    pub(crate) fn register_local_action(
+        &mut self,
+        contents: String,
+        key: InputKey,
+        caller: &InputKey,
+    ) -> anyhow::Result<()> {
+        self.register(InputKind::Action, contents, key.clone())?;
+
+        // NOTE: As with `register_collected`, inputs that failed to load
+        // aren't tracked.
+        if !self.inputs.contains_key(&key) {
+            return Ok(());
+        }
+
+        if let Some(index) = self.config_index(caller) {
+            self.input_configs.insert(key.clone(), index);
+        }
+        self.callers.insert(key, caller.clone());
+
+        Ok(())
+    }
+
     /// Registers an already-loaded workflow or action definition.
-    fn register_input(&mut self, input: AuditInput) -> anyhow::Result<()> {
+    pub(crate) fn register_input(&mut self, input: AuditInput) -> anyhow::Result<()> {
         if self.inputs.contains_key(input.key()) {
             return Err(anyhow!(
                 "can't register {key} more than once",
@@ -237,6 +558,81 @@ impl InputRegistry {
         Ok(())
     }
 
+    /// Moves every input in `other` into this registry, applying
+    /// the given target configuration (if any) to them.
+    pub(crate) fn merge(
+        &mut self,
+        other: InputRegistry,
+        config: Option<Config>,
+    ) -> anyhow::Result<()> {
+        let index = config.map(|config| {
+            self.configs.push(config);
+            self.configs.len() - 1
+        });
+
+        for (key, input) in other.inputs {
+            self.register_input(input)?;
+            if other.extra.contains(&key) {
+                self.extra.insert(key.clone());
+            }
+            if other.vendored.contains(&key) {
+                self.vendored.insert(key.clone());
+            }
+            if let Some(index) = index {
+                self.input_configs.insert(key, index);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes every local input that isn't in `changed`. Remote inputs
+    /// are always kept, since they aren't part of the local checkout.
+    pub(crate) fn skip_unchanged(&mut self, changed: &ChangedFiles) {
+        let before = self.inputs.len();
+        self.inputs.retain(|key, _| match key {
+            InputKey::Local(local) => changed.contains(&local.given_path),
+            InputKey::Remote(_) => true,
+        });
+        self.input_configs
+            .retain(|key, _| self.inputs.contains_key(key));
+        self.extra.retain(|key| self.inputs.contains_key(key));
+        self.vendored.retain(|key| self.inputs.contains_key(key));
+        self.unchanged += before - self.inputs.len();
+    }
+
+    /// The number of local inputs that were skipped as unchanged.
+    pub(crate) fn unchanged(&self) -> usize {
+        self.unchanged
+    }
+
+    /// Every target configuration, in the order they were discovered.
+    pub(crate) fn configs(&self) -> &[Config] {
+        &self.configs
+    }
+
+    /// Returns the index into [`InputRegistry::configs`] of the given
+    /// input's target configuration, or `None` if the global
+    /// configuration applies to it.
+    pub(crate) fn config_index(&self, key: &InputKey) -> Option<usize> {
+        self.input_configs.get(key).copied()
+    }
+
+    /// Returns how the given input was collected.
+    pub(crate) fn collection(&self, key: &InputKey) -> Collection {
+        if self.extra.contains(key) {
+            Collection::Extra
+        } else {
+            Collection::Standard
+        }
+    }
+
+    /// Returns whether the given input was collected from one of its
+    /// configuration's `vendored-paths`.
+    pub(crate) fn is_vendored(&self, key: &InputKey) -> bool {
+        self.vendored.contains(key)
+    }
+
     pub(crate) fn iter_inputs(&self) -> btree_map::Iter<'_, InputKey, AuditInput> {
         self.inputs.iter()
     }
@@ -246,16 +642,99 @@ impl InputRegistry {
             .get(key)
             .expect("API misuse: requested an un-registered input")
     }
+
+    /// Returns the input that uses the given local action, if the action
+    /// was only collected because of it.
+    pub(crate) fn caller(&self, key: &InputKey) -> Option<&AuditInput> {
+        self.callers.get(key).map(|caller| self.get_input(caller))
+    }
+
+    /// Adds the step that uses `input` to each of `findings`, as a related
+    /// location, if `input` is a local action that was only collected
+    /// because of that step.
+    pub(crate) fn add_caller_locations<'doc>(
+        &'doc self,
+        input: &AuditInput,
+        findings: &mut [Finding<'doc>],
+    ) -> anyhow::Result<()> {
+        let Some(caller) = self.caller(input.key()) else {
+            return Ok(());
+        };
+
+        let Some((location, _)) = caller.local_action_uses().into_iter().find(|(_, uses)| {
+            matches!(
+                caller.key().local_action(uses),
+                Some(LocalAction::Manifest(path))
+                    if caller.key().local_action_key(&path).is_ok_and(|key| key == *input.key())
+            )
+        }) else {
+            return Ok(());
+        };
+
+        let location = location.annotated("this step uses the action");
+        for finding in findings {
+            finding
+                .locations
+                .push(location.clone().concretize(caller.as_document())?);
+        }
+
+        Ok(())
+    }
+}
+
+/// What happened to an audit during a run.
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub(crate) enum AuditStatus {
+    /// The audit ran on every input.
+    Ran {
+        /// The number of reported findings.
+        findings: usize,
+        /// The total time spent in the audit, across all threads.
+        #[serde(rename = "wall_time_ms", serialize_with = "serialize_millis")]
+        wall_time: Duration,
+    },
+    /// The audit was deliberately not run, e.g. because it's disabled.
+    Skipped { reason: String },
+    /// The audit couldn't be loaded, and the run continued without it.
+    Failed { error: String },
+}
+
+/// An audit's ident, along with its [`AuditStatus`].
+#[derive(Serialize)]
+pub(crate) struct AuditSummary {
+    pub(crate) ident: &'static str,
+    #[serde(flatten)]
+    pub(crate) status: AuditStatus,
+}
+
+pub(crate) fn serialize_millis<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
 }
 
 pub(crate) struct AuditRegistry {
     pub(crate) audits: IndexMap<&'static str, Box<dyn Audit>>,
+    /// Audits that were skipped while loading, and why.
+    skipped: IndexMap<&'static str, String>,
+    /// Audits that failed to load, and why.
+    failed: IndexMap<&'static str, String>,
+    /// The time spent in each loaded audit, in nanoseconds.
+    wall_times: IndexMap<&'static str, AtomicU64>,
+    /// Each loaded audit's configuration warnings, if it has any.
+    config_warnings: IndexMap<&'static str, Vec<String>>,
 }
 
 impl AuditRegistry {
     pub(crate) fn new() -> Self {
         Self {
             audits: Default::default(),
+            skipped: Default::default(),
+            failed: Default::default(),
+            config_warnings: Default::default(),
+            wall_times: Default::default(),
         }
     }
 
@@ -264,37 +743,203 @@ impl AuditRegistry {
     }
 
     pub(crate) fn register_audit(&mut self, ident: &'static str, audit: Box<dyn Audit>) {
+        let warnings = audit.config_warnings();
+        if !warnings.is_empty() {
+            self.config_warnings.insert(ident, warnings);
+        }
         self.audits.insert(ident, audit);
+        self.wall_times.insert(ident, AtomicU64::new(0));
     }
 
-    pub(crate) fn iter_audits(&self) -> indexmap::map::Iter<&str, Box<dyn Audit>> {
+    pub(crate) fn iter_audits(&self) -> indexmap::map::Iter<'_, &str, Box<dyn Audit>> {
         self.audits.iter()
     }
+
+    /// Runs every audit's [`Audit::audit_finish`], with `inputs` (the
+    /// inputs that were audited with this registry).
+    pub(crate) fn finish<'doc>(
+        &self,
+        inputs: &[&'doc AuditInput],
+    ) -> anyhow::Result<Vec<Finding<'doc>>> {
+        let mut findings = vec![];
+        for (name, audit) in self.iter_audits() {
+            findings.extend(
+                audit
+                    .audit_finish(inputs)
+                    .with_context(|| format!("{name} failed to finish"))?,
+            );
+        }
+
+        Ok(findings)
+    }
+
+    pub(crate) fn skip_audit(&mut self, ident: &'static str, reason: String) {
+        self.skipped.insert(ident, reason);
+    }
+
+    pub(crate) fn fail_audit(&mut self, ident: &'static str, error: String) {
+        self.failed.insert(ident, error);
+    }
+
+    /// Records time spent running the given (loaded) audit.
+    pub(crate) fn record_wall_time(&self, ident: &str, elapsed: Duration) {
+        if let Some(wall_time) = self.wall_times.get(ident) {
+            wall_time.fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// The status of every known audit, in the order they're run.
+    ///
+    /// Wall times are summed over `others`, e.g. the audits loaded for
+    /// inputs with their own configuration.
+    pub(crate) fn statuses(
+        &self,
+        others: &[AuditRegistry],
+        findings: &FindingRegistry,
+    ) -> Vec<AuditSummary> {
+        audit::RULES
+            .iter()
+            .map(|rule| {
+                let status = if let Some(reason) = self.skipped.get(rule.ident) {
+                    AuditStatus::Skipped {
+                        reason: reason.clone(),
+                    }
+                } else if let Some(error) = self.failed.get(rule.ident) {
+                    AuditStatus::Failed {
+                        error: error.clone(),
+                    }
+                } else {
+                    let nanos = std::iter::once(self)
+                        .chain(others)
+                        .filter_map(|registry| registry.wall_times.get(rule.ident))
+                        .map(|wall_time| wall_time.load(Ordering::Relaxed))
+                        .sum();
+
+                    AuditStatus::Ran {
+                        findings: findings.rule_count(rule.ident),
+                        wall_time: Duration::from_nanos(nanos),
+                    }
+                };
+
+                AuditSummary {
+                    ident: rule.ident,
+                    status,
+                }
+            })
+            .collect()
+    }
+
+    /// Every loaded audit's configuration warnings, one line per warning,
+    /// e.g. "unpinned-uses: policy pattern `foo/bar` has no effect, ...".
+    pub(crate) fn config_warnings(&self) -> Vec<String> {
+        self.config_warnings
+            .iter()
+            .flat_map(|(ident, warnings)| {
+                warnings
+                    .iter()
+                    .map(move |warning| format!("{ident}: {warning}"))
+            })
+            .collect()
+    }
+
+    /// A summary of the skipped audits, one line per distinct reason,
+    /// e.g. "3 audits skipped: no GitHub token (impostor-commit, ...)".
+    pub(crate) fn skipped_summary(&self) -> Vec<String> {
+        let mut by_reason: IndexMap<&str, Vec<&str>> = IndexMap::new();
+        for (ident, reason) in &self.skipped {
+            by_reason.entry(reason).or_default().push(ident);
+        }
+
+        by_reason
+            .into_iter()
+            .map(|(reason, idents)| {
+                format!(
+                    "{n} audit{s} skipped: {reason} ({idents})",
+                    n = idents.len(),
+                    s = if idents.len() == 1 { "" } else { "s" },
+                    idents = idents.join(", "),
+                )
+            })
+            .collect()
+    }
 }
 
 /// A registry of all findings discovered during a `zizmor` run.
 pub(crate) struct FindingRegistry<'a> {
     config: &'a Config,
+    inputs: &'a InputRegistry,
     minimum_severity: Option<Severity>,
     minimum_confidence: Option<Confidence>,
-    persona: Persona,
+    persona: Option<Persona>,
+    baseline: Option<&'a Baseline>,
+    update_baseline: bool,
+    fail_on: Option<FailOn>,
+    rollup: Rollup,
+    rollup_count: RollupCount,
+    /// Whether ignores (inline and configured) are disabled, per `--no-ignores`.
+    no_ignores: bool,
+    /// The date that configured ignores expire against.
+    today: Date,
+    /// The configured ignores that have been reported as expired, by
+    /// their configuration's origin and their index in its `ignores`.
+    expired_ignores: HashSet<(Option<&'a str>, usize)>,
     suppressed: Vec<Finding<'a>>,
     ignored: Vec<Finding<'a>>,
+    filtered: Vec<Finding<'a>>,
+    /// How many of the `filtered` findings are below the severity
+    /// threshold, rather than (only) the confidence threshold.
+    nbelow_severity: usize,
+    baselined: Vec<Finding<'a>>,
     findings: Vec<Finding<'a>>,
     highest_seen_severity: Option<Severity>,
+    /// What's kept of the findings released by [`FindingRegistry::release`].
+    released: Released,
+}
+
+/// What's kept of findings once they've been released, i.e. streamed out:
+/// enough to compute the run's counts and exit code, and (when updating
+/// the baseline) to write the baseline.
+#[derive(Default)]
+struct Released {
+    /// The released non-ignored, non-suppressed, non-baselined findings,
+    /// weighted per `--rollup-count`, by rule and severity.
+    by_rule: HashMap<&'static str, SeverityCounts>,
+    ignored: usize,
+    suppressed: usize,
+    filtered: usize,
+    baselined: usize,
+    baseline_entries: Vec<BaselineEntry>,
 }
 
 impl<'a> FindingRegistry<'a> {
-    pub(crate) fn new(app: &App, config: &'a Config) -> Self {
+    pub(crate) fn new(
+        app: &App,
+        config: &'a Config,
+        inputs: &'a InputRegistry,
+        baseline: Option<&'a Baseline>,
+    ) -> Self {
         Self {
             config,
+            inputs,
             minimum_severity: app.min_severity,
             minimum_confidence: app.min_confidence,
             persona: app.persona,
+            baseline,
+            update_baseline: app.update_baseline,
+            fail_on: app.fail_on.or(config.fail_on),
+            rollup: app.rollup.or(config.rollup).unwrap_or_default(),
+            rollup_count: app.rollup_count.or(config.rollup_count).unwrap_or_default(),
+            no_ignores: app.no_ignores,
+            today: OffsetDateTime::now_utc().date(),
+            expired_ignores: Default::default(),
             suppressed: Default::default(),
             ignored: Default::default(),
+            filtered: Default::default(),
+            nbelow_severity: 0,
+            baselined: Default::default(),
             findings: Default::default(),
             highest_seen_severity: None,
+            released: Default::default(),
         }
     }
 
@@ -303,23 +948,64 @@ impl<'a> FindingRegistry<'a> {
     pub(crate) fn extend(&mut self, results: Vec<Finding<'a>>) {
         // TODO: is it faster to iterate like this, or do `find_by_max`
         // and then `extend`?
-        for finding in results {
-            if self.persona > finding.determinations.persona {
+        let mut expired = vec![];
+
+        for mut finding in results {
+            if let Some(location) = finding.locations.iter().find(|l| l.symbolic.is_primary()) {
+                finding.collection = self.inputs.collection(location.symbolic.key);
+                finding.vendored = self.inputs.is_vendored(location.symbolic.key);
+            }
+
+            // Severity and confidence overrides apply before any filtering,
+            // so that (e.g.) `--min-severity` sees the overridden severity.
+            let config = self.config_for(&finding);
+            if let Some(severity) = config.rule_severity(finding.ident) {
+                finding.determinations.severity = severity;
+            }
+            if let Some(confidence) = config.rule_confidence(finding.ident) {
+                finding.determinations.confidence = confidence;
+            }
+
+            if self.no_ignores {
+                finding.ignored = false;
+            }
+
+            let ignore_reason = self.ignore_reason(&finding, &mut expired);
+
+            if self.persona_for(&finding) > finding.determinations.persona {
+                finding.hidden = true;
                 self.suppressed.push(finding);
-            } else if finding.ignored
-                || self
-                    .minimum_severity
-                    .is_some_and(|min| min > finding.determinations.severity)
-                || self
-                    .minimum_confidence
-                    .is_some_and(|min| min > finding.determinations.confidence)
-                || self.config.ignores(&finding)
+            } else if let Some(reason) = ignore_reason {
+                finding.ignored = true;
+                finding.ignore_reason = Some(reason);
+                self.ignored.push(finding);
+            } else if !self.no_ignores
+                && (finding.ignored || self.config_for(&finding).ignores(&finding))
             {
                 self.ignored.push(finding);
+            } else if self.below_severity(&finding) {
+                finding.filtered = true;
+                self.nbelow_severity += 1;
+                self.filtered.push(finding);
+            } else if self.below_confidence(&finding) {
+                finding.filtered = true;
+                self.filtered.push(finding);
+            } else if self.update_baseline
+                || self
+                    .baseline
+                    .is_some_and(|baseline| baseline.contains(&finding))
+            {
+                // When updating the baseline, every finding is about to
+                // become part of it.
+                finding.baselined = true;
+                self.baselined.push(finding);
             } else {
-                if self
-                    .highest_seen_severity
-                    .is_none_or(|s| finding.determinations.severity > s)
+                // Vendored findings are still reported, but never
+                // fail the run.
+                if !finding.vendored
+                    && self
+                        .highest_seen_severity
+                        .is_none_or(|s| finding.determinations.severity > s)
                 {
                     self.highest_seen_severity = Some(finding.determinations.severity);
                 }
@@ -327,14 +1013,312 @@ impl<'a> FindingRegistry<'a> {
                 self.findings.push(finding);
             }
         }
+
+        // Expired ignores are reported alongside everything else, so they
+        // can themselves be filtered (or ignored).
+        if !expired.is_empty() {
+            self.extend(expired);
+        }
+    }
+
+    /// The reason that the given finding is ignored by its configuration's
+    /// `ignores`, if it is.
+    ///
+    /// Expired entries don't ignore anything; instead, the first finding
+    /// that one would have ignored produces a finding of its own, which
+    /// is added to `expired`.
+    fn ignore_reason(
+        &mut self,
+        finding: &Finding<'a>,
+        expired: &mut Vec<Finding<'a>>,
+    ) -> Option<String> {
+        if self.no_ignores {
+            return None;
+        }
+
+        let config = self.config_for(finding);
+        let (idx, entry) = config.ignore_entry(finding)?;
+
+        if !entry.expired(self.today) {
+            return Some(entry.reason.clone());
+        }
+
+        if config.rule_enabled(ExpiredIgnores::ident()) != Some(false)
+            && self.expired_ignores.insert((config.origin(), idx))
+        {
+            match self.expired_finding(config, idx, entry, finding) {
+                Ok(finding) => expired.push(finding),
+                Err(err) => tracing::warn!("couldn't report expired ignore: {err:#}"),
+            }
+        }
+
+        None
+    }
+
+    /// Builds the `expired-ignores` finding for `entry`, the `idx`th entry
+    /// in `config`'s `ignores`, which no longer ignores `finding`.
+    fn expired_finding(
+        &self,
+        config: &Config,
+        idx: usize,
+        entry: &IgnoreEntry,
+        finding: &Finding<'a>,
+    ) -> anyhow::Result<Finding<'a>> {
+        // NOTE: Safe unwraps, since only expired entries (which have
+        // expiry dates) are reported, and built findings have a primary
+        // location.
+        let primary = finding
+            .locations
+            .iter()
+            .find(|l| l.symbolic.is_primary())
+            .unwrap();
+
+        // The expired entry itself is the finding's location, when it's
+        // in a file; the finding it no longer ignores is related to it.
+        let mut entry_location = ExternalLocation::new(format!(
+            "ignore expired on {expires}; its reason was: {reason}",
+            expires = entry.expires.unwrap(),
+            reason = entry.reason,
+        ));
+        if let (Some(origin), Some(source)) = (config.origin(), config.source()) {
+            entry_location = entry_location
+                .with_path(origin, config.ignore_point(idx))
+                .with_source(source)
+                .primary();
+        }
+
+        ExpiredIgnores::finding()
+            .severity(Severity::Low)
+            .confidence(Confidence::High)
+            .add_location(primary.symbolic.clone().annotated(format!(
+                "this {ident} finding is no longer ignored",
+                ident = finding.ident
+            )))
+            .add_external_location(entry_location)
+            .build(self.inputs.get_input(primary.symbolic.key))
+    }
+
+    /// The configuration that applies to the given finding: its primary
+    /// location's target configuration, or else the global configuration.
+    fn config_for(&self, finding: &Finding) -> &'a Config {
+        finding
+            .locations
+            .iter()
+            .find(|l| l.symbolic.is_primary())
+            .and_then(|l| self.inputs.config_index(l.symbolic.key))
+            .map_or(self.config, |index| &self.inputs.configs()[index])
+    }
+
+    /// Whether the given finding is below `--min-severity`, or else the
+    /// configuration's `min-severity`.
+    fn below_severity(&self, finding: &Finding) -> bool {
+        self.minimum_severity
+            .or_else(|| self.config_for(finding).min_severity)
+            .is_some_and(|min| min > finding.determinations.severity)
+    }
+
+    /// Whether the given finding is below `--min-confidence`, or else the
+    /// configuration's `min-confidence`.
+    fn below_confidence(&self, finding: &Finding) -> bool {
+        self.minimum_confidence
+            .or_else(|| self.config_for(finding).min_confidence)
+            .is_some_and(|min| min > finding.determinations.confidence)
+    }
+
+    /// The persona that the given finding is filtered at: `--persona` if
+    /// given, or else the rule's configured persona, or else the
+    /// configuration's default persona.
+    fn persona_for(&self, finding: &Finding) -> Persona {
+        let config = self.config_for(finding);
+        self.persona
+            .or_else(|| config.rule_persona(finding.ident))
+            .or(config.persona)
+            .unwrap_or_default()
+    }
+
+    /// Releases every finding added so far, keeping only what's needed
+    /// for the run's counts, exit code, and baseline.
+    ///
+    /// Streamed output formats release each input's findings once they've
+    /// been rendered, so that findings don't accumulate over the run.
+    /// Released findings are still counted (e.g. by [`FindingRegistry::nfindings`]),
+    /// but are no longer returned by [`FindingRegistry::findings`] and
+    /// friends, which only return the findings added since.
+    pub(crate) fn release(&mut self) {
+        for finding in &self.findings {
+            let weight = self.weight(finding);
+            self.released
+                .by_rule
+                .entry(finding.ident)
+                .or_default()
+                .record(finding.determinations.severity, weight);
+        }
+
+        let (ignored, suppressed, filtered, baselined) = (
+            self.tally(&self.ignored),
+            self.tally(&self.suppressed),
+            self.tally(&self.filtered),
+            self.tally(&self.baselined),
+        );
+        self.released.ignored += ignored;
+        self.released.suppressed += suppressed;
+        self.released.filtered += filtered;
+        self.released.baselined += baselined;
+        if self.update_baseline {
+            self.released
+                .baseline_entries
+                .extend(self.baselined.iter().map(BaselineEntry::from));
+        }
+
+        // NOTE: The vectors keep their capacity, which is bounded by the
+        // most findings that any single input has.
+        self.findings.clear();
+        self.ignored.clear();
+        self.suppressed.clear();
+        self.filtered.clear();
+        self.baselined.clear();
     }
 
     /// The total count of all findings, regardless of status.
     pub(crate) fn count(&self) -> usize {
-        self.findings.len() + self.ignored.len() + self.suppressed.len()
+        self.nfindings()
+            + self.nignored()
+            + self.nsuppressed()
+            + self.nfiltered()
+            + self.nbaselined()
+    }
+
+    /// The number of non-ignored, non-suppressed, and non-baselined
+    /// findings, including released ones, per `--rollup-count`.
+    pub(crate) fn nfindings(&self) -> usize {
+        self.severities().total()
+    }
+
+    /// The number of ignored findings, including released ones.
+    pub(crate) fn nignored(&self) -> usize {
+        self.tally(&self.ignored) + self.released.ignored
     }
 
-    /// All non-ignored and non-suppressed findings.
+    /// The number of persona-suppressed findings, including released ones.
+    pub(crate) fn nsuppressed(&self) -> usize {
+        self.tally(&self.suppressed) + self.released.suppressed
+    }
+
+    /// The number of findings below the severity or confidence thresholds,
+    /// including released ones.
+    pub(crate) fn nfiltered(&self) -> usize {
+        self.tally(&self.filtered) + self.released.filtered
+    }
+
+    /// The number of baselined findings, including released ones.
+    pub(crate) fn nbaselined(&self) -> usize {
+        self.tally(&self.baselined) + self.released.baselined
+    }
+
+    /// The non-ignored, non-suppressed, and non-baselined findings'
+    /// counts by severity, including released ones.
+    pub(crate) fn severities(&self) -> SeverityCounts {
+        self.released
+            .by_rule
+            .values()
+            .fold(SeverityCounts::of(self, &self.findings), |total, counts| {
+                total + *counts
+            })
+    }
+
+    /// The number of the given rule's non-ignored, non-suppressed, and
+    /// non-baselined findings, including released ones.
+    pub(crate) fn rule_count(&self, ident: &str) -> usize {
+        let released = self
+            .released
+            .by_rule
+            .get(ident)
+            .map_or(0, SeverityCounts::total);
+
+        self.findings
+            .iter()
+            .filter(|finding| finding.ident == ident)
+            .map(|finding| self.weight(finding))
+            .sum::<usize>()
+            + released
+    }
+
+    /// The baseline entries of every baselined finding, including
+    /// released ones.
+    pub(crate) fn baseline_entries(&self) -> impl Iterator<Item = BaselineEntry> + '_ {
+        self.released
+            .baseline_entries
+            .iter()
+            .cloned()
+            .chain(self.baselined.iter().map(BaselineEntry::from))
+    }
+
+    /// The `--rollup` mode for this run.
+    pub(crate) fn rollup_mode(&self) -> Rollup {
+        self.rollup
+    }
+
+    /// Rolls up identical findings, per `--rollup`.
+    ///
+    /// Each set of identical findings is rolled up into the first of them,
+    /// and the others' primary locations become its occurrences. Findings
+    /// are only rolled up with others of the same status, e.g. an ignored
+    /// finding is never rolled up into one that isn't.
+    pub(crate) fn rollup(&mut self) {
+        if self.rollup == Rollup::None {
+            return;
+        }
+
+        for findings in [
+            &mut self.findings,
+            &mut self.ignored,
+            &mut self.suppressed,
+            &mut self.filtered,
+            &mut self.baselined,
+        ] {
+            *findings = rollup_by_action(std::mem::take(findings));
+        }
+    }
+
+    /// How many times `finding` is counted, per `--rollup-count`.
+    pub(crate) fn weight(&self, finding: &Finding) -> usize {
+        match self.rollup_count {
+            RollupCount::Findings => 1,
+            RollupCount::Occurrences => 1 + finding.occurrences.len(),
+        }
+    }
+
+    /// The number of `findings`, per `--rollup-count`.
+    pub(crate) fn tally(&self, findings: &[Finding]) -> usize {
+        findings.iter().map(|finding| self.weight(finding)).sum()
+    }
+
+    /// Whether findings are being compared against a baseline.
+    pub(crate) fn has_baseline(&self) -> bool {
+        self.baseline.is_some() || self.update_baseline
+    }
+
+    /// The `--fail-on` threshold for this run, if one was given
+    /// (on the command line or in the configuration).
+    pub(crate) fn fail_on(&self) -> Option<FailOn> {
+        self.fail_on
+    }
+
+    /// The highest severity among findings that fail this run, if any.
+    ///
+    /// Only non-ignored, non-suppressed, and non-baselined findings
+    /// at or above the `--fail-on` threshold fail a run.
+    pub(crate) fn failing_severity(&self) -> Option<Severity> {
+        match self.fail_on {
+            Some(fail_on) => fail_on
+                .threshold()
+                .and_then(|threshold| self.highest_seen_severity.filter(|s| *s >= threshold)),
+            None => self.highest_seen_severity,
+        }
+    }
+
+    /// All non-ignored and non-suppressed findings, excluding any
+    /// that are baselined.
     pub(crate) fn findings(&self) -> &[Finding<'a>] {
         &self.findings
     }
@@ -344,15 +1328,99 @@ impl<'a> FindingRegistry<'a> {
         &self.ignored
     }
 
+    /// All findings below the severity or confidence thresholds.
+    pub(crate) fn filtered(&self) -> &[Finding<'a>] {
+        &self.filtered
+    }
+
+    /// One line per threshold that filtered any findings, e.g.
+    /// `9 findings below severity threshold hidden`.
+    pub(crate) fn filtered_summary(&self) -> Vec<String> {
+        let nbelow_confidence = self.filtered.len() + self.released.filtered - self.nbelow_severity;
+
+        [
+            (self.nbelow_severity, "severity"),
+            (nbelow_confidence, "confidence"),
+        ]
+        .into_iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, threshold)| {
+            format!(
+                "{n} finding{s} below {threshold} threshold hidden",
+                s = if n == 1 { "" } else { "s" }
+            )
+        })
+        .collect()
+    }
+
+    /// All findings that match the baseline.
+    pub(crate) fn baselined(&self) -> &[Finding<'a>] {
+        &self.baselined
+    }
+
     /// All persona-suppressed findings.
     pub(crate) fn suppressed(&self) -> &[Finding<'a>] {
         &self.suppressed
     }
+
+    /// The number of inputs that were skipped as unchanged, per `--changed-since`.
+    pub(crate) fn unchanged_inputs(&self) -> usize {
+        self.inputs.unchanged()
+    }
+}
+
+/// Rolls up `findings` from the same rule, with the same determinations
+/// and primary annotation, for the same action.
+fn rollup_by_action(findings: Vec<Finding<'_>>) -> Vec<Finding<'_>> {
+    let mut rolled_up: Vec<Finding> = vec![];
+    let mut firsts = HashMap::new();
+
+    for mut finding in findings {
+        let Some(action) = finding.action_reference() else {
+            rolled_up.push(finding);
+            continue;
+        };
+        // NOTE: Safe unwrap, since built findings have a primary location.
+        let annotation = finding
+            .locations
+            .iter()
+            .find(|l| l.symbolic.is_primary())
+            .unwrap()
+            .symbolic
+            .annotation
+            .clone();
+        let key = (
+            finding.qualified_ident().into_owned(),
+            finding.determinations.severity,
+            finding.determinations.confidence,
+            action,
+            annotation,
+        );
+
+        match firsts.get(&key) {
+            Some(&idx) => {
+                let first: &mut Finding = &mut rolled_up[idx];
+                first.occurrences.extend(
+                    finding
+                        .locations
+                        .drain(..)
+                        .filter(|l| l.symbolic.is_primary()),
+                );
+                first.occurrences.append(&mut finding.occurrences);
+            }
+            None => {
+                firsts.insert(key, rolled_up.len());
+                rolled_up.push(finding);
+            }
+        }
+    }
+
+    rolled_up
 }
 
 impl From<FindingRegistry<'_>> for ExitCode {
     fn from(value: FindingRegistry<'_>) -> Self {
-        match value.highest_seen_severity {
+        match value.failing_severity() {
             Some(sev) => match sev {
                 Severity::Unknown => ExitCode::from(10),
                 Severity::Informational => ExitCode::from(11),
@@ -369,9 +1437,15 @@ impl From<FindingRegistry<'_>> for ExitCode {
 mod tests {
     use std::str::FromStr;
 
-    use github_actions_models::common::Uses;
+    use clap::Parser as _;
+    use github_actions_models::common::{LocalUses, Uses};
 
-    use super::InputKey;
+    use super::{FindingRegistry, InputKey, InputKind, InputRegistry, LocalAction};
+    use crate::App;
+    use crate::audit::{AuditCore as _, expired_ignores::ExpiredIgnores};
+    use crate::config::Config;
+    use crate::finding::{Confidence, Persona, Severity};
+    use crate::models::AsDocument as _;
 
     #[test]
     fn test_input_key_display() {
@@ -399,6 +1473,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_reusable_workflow_key() {
+        let callee = |caller: &InputKey, uses: &str| {
+            caller
+                .reusable_workflow_key(&Uses::from_str(uses).unwrap())
+                .map(|key| key.to_string())
+        };
+
+        // Local calls are relative to the caller's repository root...
+        let local = InputKey::local("repo/.github/workflows/ci.yml", Some("repo")).unwrap();
+        assert_eq!(
+            callee(&local, "./.github/workflows/deploy.yml").as_deref(),
+            Some("file://repo/.github/workflows/deploy.yml")
+        );
+        let local = InputKey::local(".github/workflows/ci.yml", None).unwrap();
+        assert_eq!(
+            callee(&local, "./.github/workflows/deploy.yml").as_deref(),
+            Some("file://.github/workflows/deploy.yml")
+        );
+
+        // ...which isn't known outside of `.github/workflows/`.
+        let local = InputKey::local("repo/ci.yml", None).unwrap();
+        assert_eq!(callee(&local, "./.github/workflows/deploy.yml"), None);
+
+        // Remote callers' local calls stay in the same repository.
+        let Uses::Repository(slug) = Uses::from_str("foo/bar@v1").unwrap() else {
+            panic!()
+        };
+        let remote = InputKey::remote(&slug, ".github/workflows/ci.yml".into()).unwrap();
+        assert_eq!(
+            callee(&remote, "./.github/workflows/deploy.yml").as_deref(),
+            Some("https://github.com/foo/bar/blob/v1/.github/workflows/deploy.yml")
+        );
+
+        assert_eq!(
+            callee(&local, "other/repo/.github/workflows/deploy.yml@v2").as_deref(),
+            Some("https://github.com/other/repo/blob/v2/.github/workflows/deploy.yml")
+        );
+        assert_eq!(callee(&local, "docker://alpine"), None);
+    }
+
+    #[test]
+    fn test_local_action() {
+        let local_action =
+            |caller: &InputKey, path: &str| caller.local_action(&LocalUses { path: path.into() });
+
+        // Missing actions are resolved against the caller's repository root...
+        let local = InputKey::local("repo/.github/workflows/ci.yml", Some("repo")).unwrap();
+        assert_eq!(
+            local_action(&local, "./.github/actions/build"),
+            Some(LocalAction::Missing("repo/.github/actions/build".into()))
+        );
+        assert_eq!(
+            local_action(&local, "./.github/actions/../actions/build/"),
+            Some(LocalAction::Missing("repo/.github/actions/build".into()))
+        );
+
+        // ...as are ones that escape it, even when it isn't known.
+        for path in ["./..", "./../other", "./.github/../../other"] {
+            assert_eq!(
+                local_action(&local, path),
+                Some(LocalAction::Escapes),
+                "{path}"
+            );
+        }
+
+        let local = InputKey::local("repo/ci.yml", None).unwrap();
+        assert_eq!(local_action(&local, "./.github/actions/build"), None);
+        assert_eq!(
+            local_action(&local, "./../other"),
+            Some(LocalAction::Escapes)
+        );
+
+        let Uses::Repository(slug) = Uses::from_str("foo/bar@v1").unwrap() else {
+            panic!()
+        };
+        let remote = InputKey::remote(&slug, ".github/workflows/ci.yml".into()).unwrap();
+        assert_eq!(local_action(&remote, "./.github/actions/build"), None);
+        assert_eq!(
+            local_action(&remote, "./../other"),
+            Some(LocalAction::Escapes)
+        );
+    }
+
     #[test]
     fn test_input_key_local_presentation_path() {
         let local = InputKey::local("/foo/bar/baz.yml", None).unwrap();
@@ -442,4 +1600,177 @@ mod tests {
         let local = InputKey::local("./.github/workflows/baz.yml", Some(".")).unwrap();
         assert_eq!(local.sarif_path(), ".github/workflows/baz.yml");
     }
+
+    #[test]
+    fn test_input_key_repository() {
+        let local = InputKey::local("/foo/bar/baz.yml", None).unwrap();
+        assert_eq!(local.repository(), None);
+
+        let Uses::Repository(slug) = Uses::from_str("foo/bar@v1").unwrap() else {
+            panic!()
+        };
+        let remote = InputKey::remote(&slug, ".github/workflows/baz.yml".into()).unwrap();
+        assert_eq!(remote.repository().as_deref(), Some("foo/bar"));
+    }
+
+    #[test]
+    fn test_input_key_transitive() {
+        let Uses::Repository(slug) = Uses::from_str("foo/bar/sub@v1").unwrap() else {
+            panic!()
+        };
+        let key = InputKey::transitive(&slug, "sub/action.yml".into()).unwrap();
+        assert!(key.is_transitive());
+        assert_eq!(key.presentation_path(), "remote:foo/bar/sub@v1/action.yml");
+        assert_eq!(key.sarif_path(), "remote:foo/bar/sub@v1/action.yml");
+        assert_eq!(key.filename(), "action.yml");
+        assert_eq!(key.repository(), None);
+        assert_eq!(
+            key.to_string(),
+            "https://github.com/foo/bar/blob/v1/sub/action.yml"
+        );
+
+        let remote = InputKey::remote(&slug, "sub/action.yml".into()).unwrap();
+        assert!(!remote.is_transitive());
+        assert_ne!(key, remote);
+    }
+
+    #[test]
+    fn test_input_registry_registers_once() {
+        let contents = "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: echo hi\n";
+        let key = InputKey::local("/foo/bar/baz.yml", None).unwrap();
+
+        let mut registry = InputRegistry::new(true);
+        registry
+            .register(InputKind::Workflow, contents.into(), key.clone())
+            .unwrap();
+
+        // The registry hands out the source it was given.
+        assert_eq!(registry.get_input(&key).as_document().source(), contents);
+
+        // Registering the same input again is an error, rather than a re-parse.
+        assert!(
+            registry
+                .register(InputKind::Workflow, contents.into(), key.clone())
+                .is_err()
+        );
+        assert_eq!(registry.len(), 1);
+    }
+
+    /// Benchmarks registering (and so parsing) a few hundred workflows.
+    /// Run with:
+    ///
+    /// ```text
+    /// cargo test --release --bin zizmor bench_register_inputs -- --ignored --nocapture
+    /// ```
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_register_inputs() {
+        const WORKFLOWS: usize = 400;
+        const SAMPLES: usize = 10;
+
+        let contents = r#"
+on:
+  push:
+  pull_request:
+
+permissions: {}
+
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    strategy:
+      matrix:
+        python: ["3.10", "3.11", "3.12"]
+    steps:
+      - uses: actions/checkout@11bd71901bbe5b1630ceea73d27597364c9af683
+        with:
+          persist-credentials: false
+      - uses: actions/setup-python@v5
+        with:
+          python-version: ${{ matrix.python }}
+      - run: |
+          pip install -r requirements.txt
+          pytest
+  lint:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - run: make lint
+"#;
+        let keys = (0..WORKFLOWS)
+            .map(|n| InputKey::local(format!("/repo/.github/workflows/ci-{n}.yml"), None).unwrap())
+            .collect::<Vec<_>>();
+
+        let best = (0..SAMPLES)
+            .map(|_| {
+                let start = std::time::Instant::now();
+                let mut registry = InputRegistry::new(true);
+                for key in &keys {
+                    registry
+                        .register(InputKind::Workflow, contents.into(), key.clone())
+                        .unwrap();
+                }
+                std::hint::black_box(&registry);
+                start.elapsed()
+            })
+            .min()
+            .unwrap();
+
+        eprintln!(
+            "{WORKFLOWS} workflows: {:.1} ms ({:.0} µs/workflow)",
+            best.as_secs_f64() * 1e3,
+            best.as_secs_f64() * 1e6 / WORKFLOWS as f64
+        );
+    }
+
+    #[test]
+    fn test_finding_registry_release() {
+        let contents = "on: push\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: echo hi\n";
+        let mut inputs = InputRegistry::new(false);
+        for n in 0..1000 {
+            let key = InputKey::local(format!(".github/workflows/{n}.yml"), None).unwrap();
+            inputs
+                .register(InputKind::Workflow, contents.into(), key)
+                .unwrap();
+        }
+
+        let app = App::parse_from(["zizmor", "."]);
+        let config = Config::default();
+        let mut results = FindingRegistry::new(&app, &config, &inputs, None);
+
+        // Each input's findings are released once they've been added, so
+        // that no more than one input's findings are ever held at once.
+        let mut capacity = None;
+        for (_, input) in inputs.iter_inputs() {
+            let finding = |severity, persona| {
+                ExpiredIgnores::finding()
+                    .severity(severity)
+                    .confidence(Confidence::High)
+                    .persona(persona)
+                    .add_location(input.location().primary())
+                    .build(input)
+                    .unwrap()
+            };
+            results.extend(vec![
+                finding(Severity::Medium, Persona::Regular),
+                finding(Severity::Low, Persona::Pedantic),
+            ]);
+            assert_eq!(results.findings().len(), 1);
+
+            results.release();
+            assert!(results.findings().is_empty());
+            assert!(results.suppressed().is_empty());
+
+            let capacity = *capacity.get_or_insert(results.findings.capacity());
+            assert_eq!(results.findings.capacity(), capacity);
+        }
+
+        // Released findings are still counted.
+        assert_eq!(results.nfindings(), 1000);
+        assert_eq!(results.nsuppressed(), 1000);
+        assert_eq!(results.count(), 2000);
+        assert_eq!(results.severities().medium, 1000);
+        assert_eq!(results.rule_count(ExpiredIgnores::ident()), 1000);
+        assert_eq!(results.failing_severity(), Some(Severity::Medium));
+    }
 }
@@ -0,0 +1,350 @@
+//! A shared, memoizing resolver for `uses:` clauses.
+//!
+//! The same `owner/repo@ref` typically appears many times across a run's
+//! inputs (especially in org-wide scans), and is classified (and, online,
+//! resolved) by several independent consumers: the `unpinned-uses` audit,
+//! the TPA output formats, and `zizmor lock`. [`UsesResolver`] lives on the
+//! [`AuditState`](crate::state::AuditState) so that each of these lookups
+//! happens once per run, however many consumers need it.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use github_actions_models::common::RepositoryUses;
+
+use crate::audit::unpinned_uses::AllowedOrgs;
+use crate::github_api::{Client, GitHubHost, RateLimited};
+use crate::models::uses::{RefKind, RepositoryUsesExt as _};
+
+/// The host that GitHub Enterprise Server instances mirror actions from,
+/// e.g. with `actions-sync`.
+const GITHUB_COM: &str = "github.com";
+
+/// The number of attempts made to resolve a ref before giving up,
+/// when rate limited by the GitHub API.
+const RESOLVE_ATTEMPTS: u32 = 4;
+
+/// An `(owner, repo, ref-or-commit)` triple.
+type RepoKey = (String, String, String);
+
+#[derive(Default)]
+pub(crate) struct UsesResolver {
+    /// The client used for online resolution, if there are credentials.
+    client: Option<Client>,
+    /// The run's GitHub host, which host-relative `uses:` clauses (and
+    /// `client`) refer to.
+    host: GitHubHost,
+    kinds: Mutex<HashMap<String, RefKind>>,
+    /// Third-party determinations, keyed by [`AllowedOrgs::id`] as well
+    /// as `(owner, repo)`, since different consumers use different allowlists.
+    third_party: Mutex<HashMap<(usize, String, String), bool>>,
+    commits: Mutex<HashMap<RepoKey, Option<String>>>,
+    tags: Mutex<HashMap<RepoKey, Option<String>>>,
+    canonical: Mutex<HashMap<(String, String), Option<String>>>,
+    /// The number of lookups answered from the caches above.
+    saved: AtomicUsize,
+    /// The number of those lookups that would have used the GitHub API.
+    saved_online: AtomicUsize,
+}
+
+impl UsesResolver {
+    pub(crate) fn new(client: Option<Client>, host: &GitHubHost) -> Self {
+        Self {
+            client,
+            host: host.clone(),
+            kinds: Default::default(),
+            third_party: Default::default(),
+            commits: Default::default(),
+            tags: Default::default(),
+            canonical: Default::default(),
+            saved: Default::default(),
+            saved_online: Default::default(),
+        }
+    }
+
+    /// Whether refs can be resolved online, i.e. there's a client.
+    pub(crate) fn online(&self) -> bool {
+        self.client.is_some()
+    }
+
+    /// Returns the cached value for `key`, or computes and caches it.
+    ///
+    /// NOTE: The cache isn't locked while computing, so that slow (i.e.
+    /// online) lookups don't block each other. Concurrent lookups of the
+    /// same key may both compute it, but only one result is kept.
+    fn memoized<K: Eq + Hash, V: Clone>(
+        &self,
+        cache: &Mutex<HashMap<K, V>>,
+        key: K,
+        online: bool,
+        compute: impl FnOnce(&K) -> V,
+    ) -> V {
+        if let Some(value) = cache.lock().unwrap().get(&key) {
+            self.saved.fetch_add(1, Ordering::Relaxed);
+            if online {
+                self.saved_online.fetch_add(1, Ordering::Relaxed);
+            }
+            return value.clone();
+        }
+
+        let value = compute(&key);
+        cache.lock().unwrap().entry(key).or_insert(value).clone()
+    }
+
+    /// Classifies the given `git` ref; see [`RefKind::of`].
+    pub(crate) fn ref_kind(&self, git_ref: &str) -> RefKind {
+        self.memoized(&self.kinds, git_ref.into(), false, |git_ref| {
+            RefKind::of(git_ref)
+        })
+    }
+
+    /// Whether `uses` is pinned to a full commit SHA.
+    pub(crate) fn is_commit(&self, uses: &RepositoryUses) -> bool {
+        uses.git_ref
+            .as_deref()
+            .is_some_and(|git_ref| self.ref_kind(git_ref) == RefKind::FullSha)
+    }
+
+    /// Returns the host that `uses` refers to, along with `uses` relative
+    /// to that host: the run's GitHub host, unless `uses` is qualified
+    /// with another (e.g. `https://ghe.example.com/owner/repo@v1`).
+    pub(crate) fn on_host(&self, uses: &RepositoryUses) -> (String, RepositoryUses) {
+        uses.server_qualified().unwrap_or_else(|| {
+            (
+                self.host.hostname(),
+                RepositoryUses {
+                    owner: uses.owner.clone(),
+                    repo: uses.repo.clone(),
+                    subpath: uses.subpath.clone(),
+                    git_ref: uses.git_ref.clone(),
+                },
+            )
+        })
+    }
+
+    /// Whether `host` is the run's GitHub host, i.e. the one that refs
+    /// are resolved against.
+    pub(crate) fn is_local_host(&self, host: &str) -> bool {
+        host == self.host.hostname()
+    }
+
+    /// Whether `owner/repo` on `host` is a third party.
+    ///
+    /// The allowlist applies to the run's GitHub host and to github.com,
+    /// which GitHub Enterprise Server instances mirror actions from; actions
+    /// on any other host are always third-party.
+    pub(crate) fn third_party_on(
+        &self,
+        allowed_orgs: &AllowedOrgs,
+        host: &str,
+        owner: &str,
+        repo: &str,
+    ) -> bool {
+        if !(self.is_local_host(host) || host == GITHUB_COM) {
+            return true;
+        }
+
+        self.third_party(allowed_orgs, owner, repo)
+    }
+
+    /// Whether `owner/repo` is a third party, i.e. isn't in `allowed_orgs`.
+    pub(crate) fn third_party(&self, allowed_orgs: &AllowedOrgs, owner: &str, repo: &str) -> bool {
+        let key = (allowed_orgs.id(), owner.into(), repo.into());
+        self.memoized(&self.third_party, key, false, |(_, owner, repo)| {
+            !allowed_orgs.contains(owner, repo)
+        })
+    }
+
+    /// Resolves `owner/repo@ref` to a commit, backing off when rate limited.
+    ///
+    /// Returns `None` when offline, or if the ref couldn't be resolved.
+    pub(crate) fn resolve_ref(&self, owner: &str, repo: &str, git_ref: &str) -> Option<String> {
+        let client = self.client.as_ref()?;
+
+        // Placeholders (e.g. `$default-branch` in a workflow template) only
+        // mean something once the template is used.
+        if self.ref_kind(git_ref) == RefKind::Placeholder {
+            tracing::debug!("not resolving placeholder ref {owner}/{repo}@{git_ref}");
+            return None;
+        }
+
+        let key = (owner.into(), repo.into(), git_ref.into());
+        self.memoized(&self.commits, key, true, |(owner, repo, git_ref)| {
+            let mut delay = Duration::from_secs(1);
+
+            for attempt in 1..=RESOLVE_ATTEMPTS {
+                match client.commit_for_ref(owner, repo, git_ref) {
+                    Ok(commit) => return commit,
+                    // NOTE: Once we've run out of attempts, the client stops
+                    // sending requests, so later refs aren't retried.
+                    Err(e)
+                        if matches!(e.downcast_ref::<RateLimited>(), Some(RateLimited::Now))
+                            && attempt < RESOLVE_ATTEMPTS =>
+                    {
+                        tracing::warn!("{e:#}; retrying in {delay:?}");
+                        thread::sleep(delay);
+                        delay *= 2;
+                        client.reset_rate_limit();
+                    }
+                    Err(e) if matches!(e.downcast_ref(), Some(RateLimited::Earlier)) => {
+                        tracing::debug!("not resolving {owner}/{repo}@{git_ref}: {e:#}");
+                        return None;
+                    }
+                    Err(e) => {
+                        tracing::warn!("couldn't resolve {owner}/{repo}@{git_ref}: {e:#}");
+                        return None;
+                    }
+                }
+            }
+
+            None
+        })
+    }
+
+    /// Returns the most specific tag pointing at `commit` in `owner/repo`.
+    ///
+    /// Returns `None` when offline, or if the tags couldn't be listed.
+    pub(crate) fn longest_tag(&self, owner: &str, repo: &str, commit: &str) -> Option<String> {
+        let client = self.client.as_ref()?;
+
+        let key = (owner.into(), repo.into(), commit.into());
+        self.memoized(&self.tags, key, true, |(owner, repo, commit)| {
+            client
+                .longest_tag_for_commit(owner, repo, commit)
+                .inspect_err(|e| tracing::warn!("{e:#}"))
+                .ok()
+                .flatten()
+                .map(|tag| tag.name)
+        })
+    }
+
+    /// Returns the canonical `owner/repo` that `owner/repo` redirects to,
+    /// or `owner/repo` itself if it hasn't been renamed.
+    ///
+    /// Returns `None` when offline, or if the repository couldn't be found.
+    pub(crate) fn canonical_repository(&self, owner: &str, repo: &str) -> Option<String> {
+        let client = self.client.as_ref()?;
+
+        let key = (owner.into(), repo.into());
+        self.memoized(&self.canonical, key, true, |(owner, repo)| {
+            client
+                .canonical_repository(owner, repo)
+                .inspect_err(|e| tracing::warn!("{e:#}"))
+                .ok()
+                .flatten()
+        })
+    }
+}
+
+impl Drop for UsesResolver {
+    fn drop(&mut self) {
+        let saved = *self.saved.get_mut();
+        if saved > 0 {
+            tracing::debug!(
+                "uses resolver saved {saved} lookup(s), {online} of them online",
+                online = self.saved_online.get_mut()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+    use std::sync::atomic::Ordering;
+
+    use github_actions_models::common::Uses;
+
+    use super::UsesResolver;
+    use crate::audit::unpinned_uses::AllowedOrgs;
+    use crate::github_api::GitHubHost;
+    use crate::models::uses::RefKind;
+
+    #[test]
+    fn test_memoized_lookups() {
+        let resolver = UsesResolver::default();
+        let official = AllowedOrgs::official();
+
+        assert_eq!(resolver.ref_kind("v4"), RefKind::TagLike);
+        assert_eq!(resolver.ref_kind("v4"), RefKind::TagLike);
+        assert!(!resolver.third_party(&official, "actions", "checkout"));
+        assert!(resolver.third_party(&official, "pypa", "gh-action-pypi-publish"));
+        assert!(resolver.third_party(&official, "pypa", "gh-action-pypi-publish"));
+        assert_eq!(resolver.saved.load(Ordering::Relaxed), 2);
+
+        let Uses::Repository(uses) =
+            Uses::from_str("actions/checkout@8f4b7f84864484a7bf31766abe9204da3cbe65b3").unwrap()
+        else {
+            unreachable!()
+        };
+        assert!(resolver.is_commit(&uses));
+
+        // Nothing is resolved offline.
+        assert!(!resolver.online());
+        assert_eq!(resolver.resolve_ref("actions", "checkout", "v4"), None);
+        assert_eq!(resolver.saved_online.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_third_party_per_allowlist() {
+        let resolver = UsesResolver::default();
+        let official = AllowedOrgs::official();
+        let other = AllowedOrgs::official();
+
+        // Different allowlists (even equal ones) don't share determinations.
+        assert!(resolver.third_party(&official, "pypa", "x"));
+        assert!(resolver.third_party(&other, "pypa", "x"));
+        assert_eq!(resolver.saved.load(Ordering::Relaxed), 0);
+
+        // ...but clones of the same allowlist do.
+        assert!(resolver.third_party(&official.clone(), "pypa", "x"));
+        assert_eq!(resolver.saved.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_on_host() {
+        let host = GitHubHost::from_clap("ghe.example.com").unwrap();
+        let resolver = UsesResolver::new(None, &host);
+        let official = AllowedOrgs::official();
+        let uses = |uses: &str| {
+            let Uses::Repository(uses) = Uses::from_str(uses).unwrap() else {
+                unreachable!()
+            };
+            uses
+        };
+
+        // Host-relative `uses:` are on the run's host...
+        let relative = uses("actions/checkout@v4");
+        let (host, on_host) = resolver.on_host(&relative);
+        assert_eq!(host, "ghe.example.com");
+        assert_eq!(on_host.owner, "actions");
+
+        // ...and server-qualified ones on their own.
+        let qualified = uses("https://GHE.example.com/actions/checkout@v4");
+        let (host, on_host) = resolver.on_host(&qualified);
+        assert_eq!(host, "ghe.example.com");
+        assert_eq!(
+            (on_host.owner.as_str(), on_host.repo.as_str()),
+            ("actions", "checkout")
+        );
+        assert!(resolver.is_local_host(&host));
+
+        // The allowlist applies to the run's host and github.com, but not
+        // to any other host.
+        for (host, third_party) in [
+            ("ghe.example.com", false),
+            ("github.com", false),
+            ("other.example.com", true),
+        ] {
+            assert_eq!(
+                resolver.third_party_on(&official, host, "actions", "checkout"),
+                third_party,
+                "{host}"
+            );
+        }
+    }
+}
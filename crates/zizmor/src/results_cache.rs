@@ -0,0 +1,218 @@
+//! A cache of audit results, for `--cache-results`.
+//!
+//! Each input's findings are cached under a digest of everything that
+//! determines them: the input's contents, its configuration (and any
+//! allowlist files it uses), the settings that offline audits read (like
+//! `--tpa-allowed-org`), the local actions it uses, the enabled audits,
+//! and `zizmor`'s own version. When an input's digest is unchanged, its
+//! cached findings are replayed instead of re-running its audits.
+//!
+//! Only offline audits' findings are cached, since online (and optionally
+//! online) audits depend on remote state that can't be part of the digest.
+//! Those audits always run.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufWriter, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use tempfile::NamedTempFile;
+
+use crate::{
+    audit::{AuditInput, Network, RULES, unpinned_uses::AllowedOrgs},
+    finding::{Finding, cached::CachedFinding},
+    models::AsDocument as _,
+    registry::{AuditRegistry, LocalAction},
+    state::AuditState,
+};
+
+/// The cached results for a single input.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    /// The digest that these results are valid for.
+    digest: String,
+    /// The findings of each cacheable audit, by ident.
+    audits: HashMap<String, Vec<CachedFinding>>,
+}
+
+/// A directory of cached audit results.
+pub(crate) struct ResultsCache {
+    dir: PathBuf,
+    /// The settings digest for inputs audited with the global configuration.
+    settings: String,
+    /// The settings digest for inputs audited with each target configuration,
+    /// indexed like [`InputRegistry::configs`](crate::registry::InputRegistry::configs).
+    target_settings: Vec<String>,
+}
+
+impl ResultsCache {
+    /// The directory that results are cached in, within `cache_dir`.
+    pub(crate) fn dir(cache_dir: &Path) -> PathBuf {
+        cache_dir.join("results")
+    }
+
+    pub(crate) fn new(cache_dir: &Path, settings: String, target_settings: Vec<String>) -> Self {
+        Self {
+            dir: Self::dir(cache_dir),
+            settings,
+            target_settings,
+        }
+    }
+
+    /// Whether the given audit's findings can be cached.
+    pub(crate) fn cacheable(ident: &str) -> bool {
+        RULES
+            .iter()
+            .find(|rule| rule.ident == ident)
+            .is_some_and(|rule| matches!(rule.meta.network, Network::Offline))
+    }
+
+    /// Computes the digest of everything besides an input's contents that
+    /// determines the findings of `audits`, as loaded with `state`.
+    pub(crate) fn settings(state: &AuditState<'_>, audits: &AuditRegistry) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(env!("CARGO_PKG_VERSION"));
+        hasher.update("\0");
+        hasher.update(state.config.source().unwrap_or_default());
+        hasher.update("\0");
+
+        for (ident, _) in audits
+            .iter_audits()
+            .filter(|(ident, _)| Self::cacheable(ident))
+        {
+            hasher.update(ident);
+            hasher.update("\0");
+        }
+
+        // Every `AuditState` field that an offline audit reads. Of these,
+        // the allowlist files are hashed by contents below.
+        hasher.update(format!(
+            "{host:?}\0{orgs:?}\0{lenient}\0{resolves}\0",
+            host = state.gh_hostname,
+            orgs = state.tpa_allowed_org,
+            lenient = state.lenient_allowlist,
+            resolves = state.resolver.online(),
+        ));
+
+        // NOTE: Unreadable allowlists are hashed as empty, matching
+        // how `unpinned-uses` treats them.
+        for path in AllowedOrgs::files(state) {
+            hasher.update(&path);
+            hasher.update("\0");
+            hasher.update(fs::read(&path).unwrap_or_default());
+            hasher.update("\0");
+        }
+
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Removes every cached result under `cache_dir`, returning whether
+    /// there were any.
+    pub(crate) fn clear(cache_dir: &Path) -> Result<bool> {
+        let dir = Self::dir(cache_dir);
+        match fs::remove_dir_all(&dir) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => {
+                Err(err).with_context(|| format!("couldn't remove {dir}", dir = dir.display()))
+            }
+        }
+    }
+
+    /// The file that `input`'s results are cached in.
+    fn path(&self, input: &AuditInput) -> PathBuf {
+        let name = format!("{:x}", Sha256::digest(input.key().to_string()));
+        self.dir.join(format!("{name}.json"))
+    }
+
+    /// The digest that `input`'s cached results must match, when it's
+    /// audited with the target configuration at `config_index` (if any).
+    fn digest(&self, input: &AuditInput, config_index: Option<usize>) -> String {
+        let settings = config_index.map_or(&self.settings, |index| &self.target_settings[index]);
+
+        let mut hasher = Sha256::new();
+        hasher.update(settings);
+        hasher.update("\0");
+        hasher.update(input.as_document().source());
+
+        // Findings on local `uses: ./path` steps depend on the action
+        // they point to, so we also hash where each one resolves to and
+        // (when it exists) its manifest's contents.
+        for (_, uses) in input.local_action_uses() {
+            let action = input.key().local_action(uses);
+            hasher.update("\0");
+            hasher.update(format!("{action:?}"));
+            if let Some(LocalAction::Manifest(manifest)) = &action {
+                hasher.update("\0");
+                hasher.update(fs::read(manifest).unwrap_or_default());
+            }
+        }
+
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Loads `input`'s cached findings, by audit ident, if they're
+    /// still valid.
+    pub(crate) fn load<'doc>(
+        &self,
+        input: &'doc AuditInput,
+        config_index: Option<usize>,
+    ) -> Option<HashMap<String, Vec<Finding<'doc>>>> {
+        let path = self.path(input);
+        let entry = fs::read(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_slice::<Entry>(&contents).ok())
+            .filter(|entry| entry.digest == self.digest(input, config_index))?;
+
+        let replayed = entry
+            .audits
+            .into_iter()
+            .map(|(ident, findings)| {
+                findings
+                    .into_iter()
+                    .map(|finding| finding.replay(input))
+                    .collect::<Option<Vec<_>>>()
+                    .map(|findings| (ident, findings))
+            })
+            .collect::<Option<HashMap<_, _>>>();
+
+        if replayed.is_none() {
+            tracing::debug!(
+                "ignoring unreplayable cached results for {input}",
+                input = input.key()
+            );
+        }
+
+        replayed
+    }
+
+    /// Caches `input`'s findings, by audit ident.
+    pub(crate) fn store(
+        &self,
+        input: &AuditInput,
+        config_index: Option<usize>,
+        audits: HashMap<String, Vec<CachedFinding>>,
+    ) -> Result<()> {
+        let entry = Entry {
+            digest: self.digest(input, config_index),
+            audits,
+        };
+
+        let write = || -> Result<()> {
+            fs::create_dir_all(&self.dir)?;
+
+            let mut writer = BufWriter::new(NamedTempFile::new_in(&self.dir)?);
+            serde_json::to_writer(&mut writer, &entry)?;
+            writer.into_inner()?.persist(self.path(input))?;
+
+            Ok(())
+        };
+
+        write().with_context(|| format!("couldn't cache results for {input}", input = input.key()))
+    }
+}
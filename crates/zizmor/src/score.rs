@@ -0,0 +1,298 @@
+//! `--score`: summarizing a run's third-party exposure as a single
+//! "actions supply-chain score", from 0 (worst) to 100 (best).
+//!
+//! The score starts at 100, and each occurrence of a risk (e.g. an
+//! unpinned third-party action) takes its weight off of it. The weights
+//! are configurable with `score-weights`, and both the formula and the
+//! weights are included with the score, so that any score can be
+//! reproduced from its output alone.
+
+use std::fmt::Display;
+
+use github_actions_models::common::{BasePermission, Permissions};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::AuditInput;
+use crate::models::{Job, Workflow};
+use crate::output::tpa_list::Summary;
+use crate::registry::{FindingRegistry, InputRegistry};
+
+/// How the score is computed from its components.
+const FORMULA: &str = "max(0, 100 - sum(count * weight))";
+
+/// How many points each occurrence of a risk takes off of the score,
+/// as configured by `score-weights`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct ScoreWeights {
+    /// Per third-party action that isn't pinned to a commit SHA.
+    #[serde(default = "ScoreWeights::default_unpinned_third_party")]
+    unpinned_third_party: u32,
+    /// Per `secrets-to-third-party` finding.
+    #[serde(default = "ScoreWeights::default_secrets_to_third_party")]
+    secrets_to_third_party: u32,
+    /// Per `dangerous-triggers` finding.
+    #[serde(default = "ScoreWeights::default_dangerous_triggers")]
+    dangerous_triggers: u32,
+    /// Per job without a `permissions:` block, at either the job or the
+    /// workflow level.
+    #[serde(default = "ScoreWeights::default_missing_permissions")]
+    missing_permissions: u32,
+}
+
+impl ScoreWeights {
+    fn default_unpinned_third_party() -> u32 {
+        2
+    }
+
+    fn default_secrets_to_third_party() -> u32 {
+        10
+    }
+
+    fn default_dangerous_triggers() -> u32 {
+        15
+    }
+
+    fn default_missing_permissions() -> u32 {
+        3
+    }
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            unpinned_third_party: Self::default_unpinned_third_party(),
+            secrets_to_third_party: Self::default_secrets_to_third_party(),
+            dangerous_triggers: Self::default_dangerous_triggers(),
+            missing_permissions: Self::default_missing_permissions(),
+        }
+    }
+}
+
+/// The number of occurrences of each risk that the score accounts for.
+#[derive(Debug, Default)]
+pub(crate) struct Counts {
+    pub(crate) unpinned_third_party: usize,
+    pub(crate) secrets_to_third_party: usize,
+    pub(crate) dangerous_triggers: usize,
+    pub(crate) missing_permissions: usize,
+}
+
+impl Counts {
+    /// Counts each risk in a run, from its TPA summary and its findings.
+    ///
+    /// Baselined findings are counted, since they're still present;
+    /// ignored and suppressed findings aren't. Rolled-up findings are
+    /// counted once per occurrence, regardless of `--rollup-count`.
+    pub(crate) fn from_run(
+        summary: &Summary,
+        registry: &InputRegistry,
+        results: &FindingRegistry,
+    ) -> Self {
+        let findings = |ident: &str| {
+            results
+                .findings()
+                .iter()
+                .chain(results.baselined())
+                .filter(|finding| finding.ident == ident)
+                .map(|finding| 1 + finding.occurrences.len())
+                .sum()
+        };
+
+        Self {
+            unpinned_third_party: summary.unpinned_third_party,
+            secrets_to_third_party: findings("secrets-to-third-party"),
+            dangerous_triggers: findings("dangerous-triggers"),
+            missing_permissions: registry
+                .iter_inputs()
+                .filter_map(|(_, input)| match input {
+                    AuditInput::Workflow(workflow) => Some(missing_permissions(workflow)),
+                    AuditInput::Action(_) => None,
+                })
+                .sum(),
+        }
+    }
+}
+
+/// The number of jobs in `workflow` that don't have a `permissions:`
+/// block, at either the job or the workflow level.
+fn missing_permissions(workflow: &Workflow) -> usize {
+    let missing = |permissions: &Permissions| {
+        matches!(permissions, Permissions::Base(BasePermission::Default))
+    };
+
+    if !missing(&workflow.permissions) {
+        return 0;
+    }
+
+    workflow
+        .jobs()
+        .filter(|job| match job {
+            Job::NormalJob(job) => missing(&job.permissions),
+            Job::ReusableWorkflowCallJob(job) => missing(&job.permissions),
+        })
+        .count()
+}
+
+/// A single risk's contribution to a [`Score`].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub(crate) struct Component {
+    /// The risk, e.g. `unpinned-third-party`.
+    pub(crate) name: String,
+    /// The number of times the risk occurs.
+    pub(crate) count: usize,
+    /// The points taken off per occurrence.
+    pub(crate) weight: u32,
+    /// The points taken off in total, i.e. `count * weight`.
+    pub(crate) penalty: u64,
+}
+
+/// An "actions supply-chain score", along with how it was computed.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub(crate) struct Score {
+    /// The score, from 0 (worst) to 100 (best).
+    pub(crate) value: u64,
+    /// How `value` is computed from `components`.
+    pub(crate) formula: String,
+    /// Each risk's contribution, in a fixed order.
+    pub(crate) components: Vec<Component>,
+}
+
+impl Score {
+    pub(crate) fn new(weights: &ScoreWeights, counts: &Counts) -> Self {
+        let components = [
+            (
+                "unpinned-third-party",
+                counts.unpinned_third_party,
+                weights.unpinned_third_party,
+            ),
+            (
+                "secrets-to-third-party",
+                counts.secrets_to_third_party,
+                weights.secrets_to_third_party,
+            ),
+            (
+                "dangerous-triggers",
+                counts.dangerous_triggers,
+                weights.dangerous_triggers,
+            ),
+            (
+                "missing-permissions",
+                counts.missing_permissions,
+                weights.missing_permissions,
+            ),
+        ]
+        .into_iter()
+        .map(|(name, count, weight)| Component {
+            name: name.into(),
+            count,
+            weight,
+            penalty: (count as u64).saturating_mul(weight.into()),
+        })
+        .collect::<Vec<_>>();
+
+        let penalty = components.iter().fold(0u64, |total, component| {
+            total.saturating_add(component.penalty)
+        });
+
+        Self {
+            value: 100u64.saturating_sub(penalty),
+            formula: FORMULA.into(),
+            components,
+        }
+    }
+
+    /// The reason that this score fails `--min-score`, if it does.
+    pub(crate) fn failure_reason(&self, min_score: u64) -> Option<String> {
+        (self.value < min_score).then(|| {
+            format!(
+                "failing: supply-chain score {value} is below --min-score {min_score}",
+                value = self.value
+            )
+        })
+    }
+}
+
+/// The one-line summary, e.g. `actions supply-chain score: 84/100
+/// (3 unpinned-third-party * 2, 1 dangerous-triggers * 15)`.
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "actions supply-chain score: {}/100", self.value)?;
+
+        let penalties = self
+            .components
+            .iter()
+            .filter(|component| component.penalty > 0)
+            .map(|component| {
+                format!(
+                    "{count} {name} * {weight}",
+                    count = component.count,
+                    name = component.name,
+                    weight = component.weight
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if !penalties.is_empty() {
+            write!(f, " ({})", penalties.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Counts, Score, ScoreWeights};
+
+    #[test]
+    fn test_score() {
+        let counts = Counts {
+            unpinned_third_party: 3,
+            secrets_to_third_party: 1,
+            dangerous_triggers: 2,
+            missing_permissions: 4,
+        };
+
+        // NOTE: This pins the default formula and weights; if it fails,
+        // every score that's being tracked over time has changed.
+        let score = Score::new(&ScoreWeights::default(), &counts);
+        assert_eq!(score.value, 100 - (3 * 2 + 10 + 2 * 15 + 4 * 3));
+        assert_eq!(
+            score.to_string(),
+            "actions supply-chain score: 42/100 (3 unpinned-third-party * 2, \
+             1 secrets-to-third-party * 10, 2 dangerous-triggers * 15, \
+             4 missing-permissions * 3)"
+        );
+
+        let weights = serde_yaml::from_str::<ScoreWeights>(
+            "{unpinned-third-party: 0, dangerous-triggers: 50}",
+        )
+        .unwrap();
+        let score = Score::new(&weights, &counts);
+        assert_eq!(score.value, 0);
+        assert_eq!(score.components[0].penalty, 0);
+        assert_eq!(score.components[2].penalty, 100);
+
+        let score = Score::new(&ScoreWeights::default(), &Counts::default());
+        assert_eq!(score.value, 100);
+        assert_eq!(score.to_string(), "actions supply-chain score: 100/100");
+    }
+
+    #[test]
+    fn test_failure_reason() {
+        let score = Score::new(
+            &ScoreWeights::default(),
+            &Counts {
+                dangerous_triggers: 1,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(score.failure_reason(85), None);
+        assert_eq!(
+            score.failure_reason(86).as_deref(),
+            Some("failing: supply-chain score 85 is below --min-score 86")
+        );
+    }
+}
@@ -1,51 +1,118 @@
 //! zizmor's runtime state, including application-level caching.
 
 use std::path::PathBuf;
+use std::sync::Arc;
 
+use anyhow::anyhow;
+use camino::Utf8PathBuf;
 use etcetera::{AppStrategy, AppStrategyArgs, choose_app_strategy};
 
 use crate::{
     App,
+    audit::AuditLoadError,
     config::Config,
     github_api::{Client, GitHubHost},
+    resolver::UsesResolver,
 };
 
 #[derive(Clone)]
 pub(crate) struct AuditState<'a> {
     pub(crate) config: &'a Config,
-    pub(crate) no_online_audits: bool,
+    /// Whether audits may perform online operations, i.e. neither
+    /// `--offline` nor `--no-online-audits` was passed.
+    pub(crate) online: bool,
     pub(crate) cache_dir: PathBuf,
     pub(crate) gh_token: Option<String>,
     pub(crate) gh_hostname: GitHubHost,
     pub(crate) tpa_allowlist_file: Option<String>,
     pub(crate) tpa_allowed_org: Option<Vec<String>>,
+    /// Whether invalid allowlist entries are skipped with a warning,
+    /// rather than failing, i.e. `--lenient-allowlist`.
+    pub(crate) lenient_allowlist: bool,
+    pub(crate) advisories_file: Option<Utf8PathBuf>,
+    /// Whether to query OSV for advisories, i.e. `--osv`.
+    pub(crate) osv: bool,
+    /// The lockfile to compare tags against, i.e. `--lockfile`.
+    pub(crate) lockfile: Option<Utf8PathBuf>,
+    /// The run's shared `uses:` resolver, including for audits loaded
+    /// from other configurations (see [`AuditState::for_config`]).
+    pub(crate) resolver: Arc<UsesResolver>,
 }
 
 impl<'a> AuditState<'a> {
     pub(crate) fn new(app: &App, config: &'a Config) -> Self {
-        let cache_dir = match &app.cache_dir {
-            Some(cache_dir) => cache_dir.as_std_path().to_path_buf(),
-            None => choose_app_strategy(AppStrategyArgs {
-                top_level_domain: "io.github".into(),
-                author: "woodruffw".into(),
-                app_name: "zizmor".into(),
-            })
-            // NOTE: no point in failing gracefully here.
-            .expect("failed to determine default cache directory")
-            .cache_dir(),
-        };
+        let cache_dir = Self::cache_dir(app);
+        let gh_hostname = Self::gh_hostname(app, config);
 
         tracing::debug!("using cache directory: {cache_dir:?}");
 
+        let client = app
+            .gh_token
+            .as_ref()
+            .map(|token| Client::new(&gh_hostname, token, &cache_dir));
+        let resolver = Arc::new(UsesResolver::new(client, &gh_hostname));
+
         Self {
             config,
-            no_online_audits: app.no_online_audits,
+            online: !(app.offline || app.no_online_audits),
             cache_dir,
             gh_token: app.gh_token.clone(),
-            gh_hostname: app.gh_hostname.clone(),
+            gh_hostname,
             // Initialize the new fields
             tpa_allowlist_file: app.tpa_allowlist_file.clone(),
             tpa_allowed_org: app.tpa_allowed_org.clone(),
+            lenient_allowlist: app.lenient_allowlist,
+            advisories_file: app.advisories.clone(),
+            osv: app.osv,
+            lockfile: app.lockfile.clone(),
+            resolver,
+        }
+    }
+
+    /// This state, but with `config` instead of its configuration.
+    ///
+    /// Unlike [`AuditState::new`], the returned state shares this
+    /// state's [`UsesResolver`].
+    pub(crate) fn for_config<'b>(&self, config: &'b Config) -> AuditState<'b> {
+        AuditState {
+            config,
+            online: self.online,
+            cache_dir: self.cache_dir.clone(),
+            gh_token: self.gh_token.clone(),
+            gh_hostname: self.gh_hostname.clone(),
+            tpa_allowlist_file: self.tpa_allowlist_file.clone(),
+            tpa_allowed_org: self.tpa_allowed_org.clone(),
+            lenient_allowlist: self.lenient_allowlist,
+            advisories_file: self.advisories_file.clone(),
+            osv: self.osv,
+            lockfile: self.lockfile.clone(),
+            resolver: self.resolver.clone(),
+        }
+    }
+
+    /// The GitHub host that audits connect to and classify `uses:` clauses
+    /// against: `--gh-hostname` (or `GH_HOST`) if given, or else the
+    /// configuration's `gh-hostname`, or else github.com.
+    pub(crate) fn gh_hostname(app: &App, config: &Config) -> GitHubHost {
+        app.gh_hostname
+            .clone()
+            .or_else(|| config.gh_hostname.clone())
+            .unwrap_or_default()
+    }
+
+    /// The directory that zizmor caches things in: `--cache-dir` if given,
+    /// or else a host-appropriate user-caching directory.
+    pub(crate) fn cache_dir(app: &App) -> PathBuf {
+        match &app.cache_dir {
+            Some(cache_dir) => cache_dir.as_std_path().to_path_buf(),
+            None => choose_app_strategy(AppStrategyArgs {
+                top_level_domain: "io.github".into(),
+                author: "woodruffw".into(),
+                app_name: "zizmor".into(),
+            })
+            // NOTE: no point in failing gracefully here.
+            .expect("failed to determine default cache directory")
+            .cache_dir(),
         }
     }
 
@@ -57,4 +124,20 @@ impl<'a> AuditState<'a> {
             .as_ref()
             .map(|token| Client::new(&self.gh_hostname, token, &self.cache_dir))
     }
+
+    /// Return a GitHub API client for an audit that can't run without one.
+    ///
+    /// Returns [`AuditLoadError::Skip`] with a human-readable reason when
+    /// online audits are disabled or no GitHub credentials were resolved.
+    /// Audits that merely benefit from the network should use
+    /// [`AuditState::github_client`] when [`AuditState::online`] is set
+    /// instead, and degrade gracefully without it.
+    pub(crate) fn online_client(&self) -> Result<Client, AuditLoadError> {
+        if !self.online {
+            return Err(AuditLoadError::Skip(anyhow!("offline mode requested")));
+        }
+
+        self.github_client()
+            .ok_or_else(|| AuditLoadError::Skip(anyhow!("no credentials available")))
+    }
 }
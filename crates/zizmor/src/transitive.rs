@@ -0,0 +1,200 @@
+//! Transitive collection of the actions used by other inputs: the remote
+//! composite actions used with `--audit-transitive`, and the local actions
+//! used with `uses: ./path`, which are always collected.
+//!
+//! A hash-pinned composite action can still use unpinned (or otherwise
+//! dangerous) actions internally. Collecting the composite actions that
+//! each input uses, and the composite actions that *those* use, and so on,
+//! makes their steps visible to the composite step audits.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use camino::Utf8PathBuf;
+use github_actions_models::action;
+use github_actions_models::common::RepositoryUses;
+
+use crate::audit::AuditInput;
+use crate::audit::unpinned_uses::AllowedOrgs;
+use crate::github_api::Client;
+use crate::models::Action;
+use crate::output::tpa_list::{
+    self, ActionKind, extract_actions_from_action, extract_actions_from_workflow,
+};
+use crate::registry::{InputKey, InputRegistry, LocalAction};
+use crate::resolver::UsesResolver;
+
+/// Fetches the definition of the action that `uses` refers to, returning
+/// `None` if it doesn't have one or isn't a composite action.
+fn fetch_composite_action(client: &Client, uses: &RepositoryUses) -> Result<Option<Action>> {
+    let dir = match &uses.subpath {
+        Some(subpath) => format!("{}/", subpath.trim_end_matches('/')),
+        None => String::new(),
+    };
+
+    for filename in ["action.yml", "action.yaml"] {
+        let path = format!("{dir}{filename}");
+        let Some(contents) = client.fetch_file(uses, &path)? else {
+            continue;
+        };
+
+        let action = Action::from_string(contents, InputKey::transitive(uses, path)?)?;
+        return Ok(matches!(action.runs, action::Runs::Composite(_)).then_some(action));
+    }
+
+    Ok(None)
+}
+
+/// The remote actions among `actions`, as their `uses:` slugs.
+fn used_actions(actions: Vec<tpa_list::Action>) -> impl Iterator<Item = RepositoryUses> {
+    actions
+        .into_iter()
+        .filter(|action| matches!(action.kind, ActionKind::Action))
+        .map(|action| RepositoryUses {
+            owner: action.owner,
+            repo: action.repo,
+            subpath: action.subpath,
+            git_ref: action.git_ref,
+        })
+}
+
+/// Identifies the action that `uses` refers to, at its ref.
+fn identity(uses: &RepositoryUses) -> (String, String, Option<String>, Option<String>) {
+    (
+        uses.owner.to_lowercase(),
+        uses.repo.to_lowercase(),
+        uses.subpath.clone(),
+        uses.git_ref.clone(),
+    )
+}
+
+/// Registers every remote composite action used by the inputs in
+/// `registry`, up to `depth` levels of composite actions deep.
+///
+/// Each distinct `uses:` is fetched at most once, which also breaks any
+/// cycles between composite actions. Actions that can't be fetched or
+/// loaded are reported individually and skipped.
+///
+/// Returns the number of composite actions registered.
+pub(crate) fn collect(
+    client: &Client,
+    depth: usize,
+    registry: &mut InputRegistry,
+) -> Result<usize> {
+    let allowed_orgs = AllowedOrgs::official();
+    let resolver = UsesResolver::default();
+
+    let mut pending = vec![];
+    for (_, input) in registry.iter_inputs() {
+        let actions = match input {
+            AuditInput::Workflow(workflow) => {
+                extract_actions_from_workflow(workflow, &allowed_orgs, &resolver)?
+            }
+            AuditInput::Action(action) => {
+                extract_actions_from_action(action, &allowed_orgs, &resolver)?
+            }
+        };
+        pending.extend(used_actions(actions));
+    }
+
+    let mut seen = HashSet::new();
+    let mut collected = 0;
+    for _ in 0..depth {
+        let mut next = vec![];
+
+        for uses in pending {
+            if !seen.insert(identity(&uses)) {
+                continue;
+            }
+
+            let action = match fetch_composite_action(client, &uses) {
+                Ok(Some(action)) => action,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::warn!(
+                        "couldn't audit {uses} transitively: {e:#}",
+                        uses = tpa_list::reference(&uses)
+                    );
+                    continue;
+                }
+            };
+
+            next.extend(used_actions(extract_actions_from_action(
+                &action,
+                &allowed_orgs,
+                &resolver,
+            )?));
+            tracing::debug!("collected {key} transitively", key = action.key);
+            registry.register_input(action.into())?;
+            collected += 1;
+        }
+
+        pending = next;
+    }
+
+    let skipped = pending
+        .iter()
+        .map(identity)
+        .filter(|id| !seen.contains(id))
+        .collect::<HashSet<_>>();
+    if !skipped.is_empty() {
+        tracing::info!(
+            "not following {n} action{s} past the maximum transitive depth of {depth}",
+            n = skipped.len(),
+            s = if skipped.len() == 1 { "" } else { "s" }
+        );
+    }
+
+    Ok(collected)
+}
+
+/// Registers every local action used (with `uses: ./path`) by the local
+/// inputs in `registry`, and the local actions that *those* use, unless
+/// they're already among `seen`, the canonical paths of every local file
+/// collected so far.
+///
+/// Returns the number of local actions registered.
+pub(crate) fn collect_local(
+    registry: &mut InputRegistry,
+    seen: &mut HashSet<Utf8PathBuf>,
+) -> Result<usize> {
+    let local_actions = |registry: &InputRegistry, key: &InputKey| {
+        registry
+            .get_input(key)
+            .local_action_uses()
+            .into_iter()
+            .filter_map(|(_, uses)| match key.local_action(uses) {
+                Some(LocalAction::Manifest(manifest)) => Some((key.clone(), manifest)),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut pending = registry
+        .iter_inputs()
+        .flat_map(|(key, _)| local_actions(registry, key))
+        .collect::<Vec<_>>();
+
+    let mut collected = 0;
+    while let Some((caller, manifest)) = pending.pop() {
+        if !seen.insert(manifest.canonicalize_utf8()?) {
+            continue;
+        }
+
+        let key = caller.local_action_key(&manifest)?;
+        let contents = std::fs::read_to_string(&manifest)?;
+        registry.register_local_action(contents, key.clone(), &caller)?;
+
+        // NOTE: Actions that failed to load (but didn't fail the run)
+        // aren't registered.
+        if registry.caller(&key).is_none() {
+            continue;
+        }
+
+        tracing::debug!("collected {manifest}, since {caller} uses it");
+        pending.extend(local_actions(registry, &key));
+        collected += 1;
+    }
+
+    Ok(collected)
+}
@@ -12,6 +12,8 @@ use jsonschema::{
     output::{ErrorDescription, OutputUnit},
     validator_for,
 };
+use regex::Regex;
+use sha1::{Digest as _, Sha1};
 use std::{collections::VecDeque, ops::Range};
 use std::{fmt::Write, sync::LazyLock};
 
@@ -55,15 +57,81 @@ fn parse_validation_errors(errors: VecDeque<OutputUnit<ErrorDescription>>) -> Er
     anyhow!(message)
 }
 
+/// Matches a `uses:` whose entire value is a single expression that
+/// can't be an `owner/repo` slug by itself, e.g. `uses: ${{ matrix.action }}`.
+static EXPRESSION_USES: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?m)^(\s*(?:-\s+)?uses:\s*)(["']?)(\$\{\{[^/\n]*?\}\})(["']?)(\s*(?:#.*)?)$"#)
+        .unwrap()
+});
+
+/// Rewrites each `uses:` that's a single expression (see
+/// [`EXPRESSION_USES`]) into `<expression>/`, returning `None` if there
+/// aren't any.
+///
+/// `github-actions-models` can't parse these as any kind of `uses:`,
+/// so this lets them parse as repository `uses:` whose owner is the
+/// expression, which audits can then treat as dynamic.
+fn placeholder_expression_uses(contents: &str) -> Option<String> {
+    let mut replaced = false;
+    let normalized = EXPRESSION_USES.replace_all(contents, |caps: &regex::Captures| {
+        // The quotes have to match for the value to be a single scalar.
+        if caps[2] != caps[4] {
+            return caps[0].to_string();
+        }
+
+        replaced = true;
+        format!(
+            "{}{}{}/{}{}",
+            &caps[1], &caps[2], &caps[3], &caps[4], &caps[5]
+        )
+    });
+
+    replaced.then(|| normalized.into_owned())
+}
+
+/// Returns `contents` with its YAML merge keys (`<<: *anchor`) applied,
+/// or `None` if it doesn't have any.
+///
+/// `serde_yaml` only applies merge keys to `Value`s, and otherwise
+/// deserializes each merging mapping without any of its merged keys.
+fn apply_merge_keys(contents: &str) -> Option<String> {
+    if !contents.contains("<<") {
+        return None;
+    }
+
+    let original = serde_yaml::from_str::<serde_yaml::Value>(contents).ok()?;
+    let mut merged = original.clone();
+    merged.apply_merge().ok()?;
+
+    (merged != original)
+        .then(|| serde_yaml::to_string(&merged).ok())
+        .flatten()
+}
+
 /// Like `serde_yaml::from_str`, but with a JSON schema validator
 /// and an error type that distinguishes between syntax and semantic
 /// errors.
-pub(crate) fn from_str_with_validation<'de, T>(
-    contents: &'de str,
+///
+/// Inputs with YAML merge keys are parsed with their merges applied
+/// (see [`apply_merge_keys`]), and inputs with expression-valued `uses:`
+/// that `github-actions-models` can't parse are retried with
+/// [`placeholder_expression_uses`].
+pub(crate) fn from_str_with_validation<T>(
+    contents: &str,
     validator: &'static Validator,
 ) -> Result<T, InputError>
 where
-    T: serde::Deserialize<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    match apply_merge_keys(contents) {
+        Some(merged) => parse_with_validation(&merged, validator),
+        None => parse_with_validation(contents, validator),
+    }
+}
+
+fn parse_with_validation<T>(contents: &str, validator: &'static Validator) -> Result<T, InputError>
+where
+    T: serde::de::DeserializeOwned,
 {
     match serde_yaml::from_str::<T>(contents) {
         Ok(value) => Ok(value),
@@ -93,9 +161,14 @@ where
                 //    needs to fix it.
                 // We the JSON schema `validator` to separate these.
                 Ok(raw_value) => match validator.apply(&raw_value).basic() {
-                    Valid(_) => Err(e)
-                        .context("this strongly suggests a bug in zizmor; please report it!")
-                        .map_err(InputError::Model),
+                    Valid(_) => match placeholder_expression_uses(contents)
+                        .and_then(|normalized| serde_yaml::from_str::<T>(&normalized).ok())
+                    {
+                        Some(value) => Ok(value),
+                        None => Err(e)
+                            .context("this strongly suggests a bug in zizmor; please report it!")
+                            .map_err(InputError::Model),
+                    },
                     Invalid(errors) => Err(InputError::Schema(parse_validation_errors(errors))),
                 },
                 // Syntax error.
@@ -186,6 +259,17 @@ pub(crate) fn extract_expressions(text: &str) -> Vec<(ExplicitExpr, Range<usize>
     exprs
 }
 
+/// Replaces each `${{ ... }}` expression in `script` with a placeholder
+/// of the same length, so that expressions don't confuse shell parsers
+/// while offsets into `script` remain valid.
+pub(crate) fn mask_expressions(script: &str) -> String {
+    let mut masked = script.to_string();
+    for (_, span) in extract_expressions(script) {
+        masked.replace_range(span.clone(), &"_".repeat(span.len()));
+    }
+    masked
+}
+
 /// Like `extract_expressions`, but over an entire audit input (e.g. workflow
 /// or action definition).
 ///
@@ -259,6 +343,35 @@ pub(crate) fn normalize_shell(shell: &str) -> &str {
     Utf8Path::new(path).file_name().unwrap_or(path)
 }
 
+/// The RFC 9562 namespace for UUIDs named by URLs.
+const URL_NAMESPACE: [u8; 16] = [
+    0x6b, 0xa7, 0xb8, 0x11, 0x9d, 0xad, 0x11, 0xd1, 0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4, 0x30, 0xc8,
+];
+
+/// Returns the version 5 UUID for `name` in the URL namespace,
+/// e.g. `7589655e-533c-5e5d-bfa1-77fd9cd0db44`.
+pub(crate) fn uuid_v5(name: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(URL_NAMESPACE);
+    hasher.update(name);
+
+    let mut bytes: [u8; 16] = hasher.finalize()[..16]
+        .try_into()
+        .expect("SHA-1 digests are 20 bytes");
+    bytes[6] = (bytes[6] & 0x0f) | 0x50;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = bytes.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
@@ -268,6 +381,7 @@ mod tests {
         registry::InputKey,
         utils::{
             extract_expression, extract_expressions, normalize_shell, parse_expressions_from_input,
+            placeholder_expression_uses, uuid_v5,
         },
     };
 
@@ -303,6 +417,40 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_placeholder_expression_uses() {
+        assert_eq!(
+            placeholder_expression_uses(
+                r#"
+steps:
+  - uses: ${{ matrix.action }}
+  - uses: "${{ inputs.action }}" # comment
+  -   uses: '${{ inputs.action }}'
+  - uses: ${{ matrix.owner }}/checkout@v4
+  - uses: "${{ inputs.action }}'
+  - run: echo ${{ matrix.action }}
+"#
+            )
+            .as_deref(),
+            Some(
+                r#"
+steps:
+  - uses: ${{ matrix.action }}/
+  - uses: "${{ inputs.action }}/" # comment
+  -   uses: '${{ inputs.action }}/'
+  - uses: ${{ matrix.owner }}/checkout@v4
+  - uses: "${{ inputs.action }}'
+  - run: echo ${{ matrix.action }}
+"#
+            )
+        );
+
+        assert_eq!(
+            placeholder_expression_uses("steps:\n  - uses: actions/checkout@v4\n"),
+            None
+        );
+    }
+
     #[test]
     fn test_extract_expression() {
         let exprs = &[
@@ -421,4 +569,13 @@ jobs:
             assert_eq!(normalize_shell(actual), *expected)
         }
     }
+
+    #[test]
+    fn test_uuid_v5() {
+        // Matches Python's `uuid.uuid5(uuid.NAMESPACE_URL, ...)`.
+        assert_eq!(
+            uuid_v5("https://docs.zizmor.sh/"),
+            "d23310ae-122a-54a1-9a36-1072b3185d28"
+        );
+    }
 }
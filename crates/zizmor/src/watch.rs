@@ -0,0 +1,256 @@
+//! `--watch`: re-running the audits whenever their inputs change.
+//!
+//! Each input root, and each configuration or allowlist file that the
+//! inputs are audited with, is watched for filesystem events. Bursts of
+//! events (e.g. from an editor's atomic save) are debounced into a single
+//! re-run, whose unchanged inputs are replayed from the results cache.
+
+use std::{
+    collections::BTreeSet,
+    io::{IsTerminal as _, Write as _},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+    },
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+use owo_colors::OwoColorize as _;
+use time::{OffsetDateTime, UtcOffset, macros::format_description};
+
+/// How long to wait for further events after a change, before re-running.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// The exit code when interrupted twice, i.e. without waiting for the
+/// current run to finish.
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Whether Ctrl-C has been pressed.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+enum Event {
+    /// Something happened to these paths.
+    Changed(Vec<PathBuf>),
+    /// Ctrl-C was pressed.
+    Interrupted,
+}
+
+/// The paths that a run depends on.
+#[derive(Default)]
+pub(crate) struct Watched {
+    /// Directories, whose YAML files are watched recursively.
+    dirs: BTreeSet<Utf8PathBuf>,
+    /// Individual files. These are watched via their parent directories,
+    /// so that they can be deleted and re-created (as editors often do).
+    files: BTreeSet<Utf8PathBuf>,
+    /// The directories that the watcher has been told about.
+    registered: BTreeSet<Utf8PathBuf>,
+}
+
+impl Watched {
+    /// Adds `path`, which is either a directory or a (possibly not yet
+    /// existing) file.
+    pub(crate) fn add(&mut self, path: &Utf8Path) {
+        let path = absolute(path);
+        if path.is_dir() {
+            self.dirs.insert(path);
+        } else {
+            self.files.insert(path);
+        }
+    }
+
+    /// Whether a change to `path` can change a run's results.
+    fn relevant(&self, path: &Path) -> bool {
+        let Some(path) = Utf8Path::from_path(path) else {
+            return false;
+        };
+
+        self.files.contains(path)
+            || (matches!(path.extension(), Some("yml" | "yaml"))
+                && self.dirs.iter().any(|dir| path.starts_with(dir)))
+    }
+
+    /// Tells `watcher` about any paths that it isn't watching yet.
+    fn register(&mut self, watcher: &mut RecommendedWatcher) {
+        let targets = self
+            .dirs
+            .iter()
+            .map(|dir| (dir.clone(), RecursiveMode::Recursive))
+            .chain(self.files.iter().filter_map(|file| {
+                file.parent()
+                    .map(|parent| (parent.to_owned(), RecursiveMode::NonRecursive))
+            }))
+            .collect::<Vec<_>>();
+
+        for (target, mode) in targets {
+            if self.registered.contains(&target) {
+                continue;
+            }
+
+            // NOTE: Failures are retried after the next run, e.g. for an
+            // allowlist file whose directory doesn't exist yet.
+            match watcher.watch(target.as_std_path(), mode) {
+                Ok(()) => {
+                    self.registered.insert(target);
+                }
+                Err(e) => tracing::debug!("couldn't watch {target}: {e}"),
+            }
+        }
+    }
+}
+
+/// Returns `path` as an absolute path, resolving symlinks where possible
+/// so that it matches the paths in filesystem events.
+fn absolute(path: &Utf8Path) -> Utf8PathBuf {
+    if let Ok(path) = path.canonicalize_utf8() {
+        return path;
+    }
+
+    // A file that doesn't exist (yet) can't be canonicalized, but its
+    // parent directory might be.
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_str().is_empty() {
+                Utf8Path::new(".")
+            } else {
+                parent
+            };
+            match parent.canonicalize_utf8() {
+                Ok(parent) => parent.join(name),
+                Err(_) => path.to_owned(),
+            }
+        }
+        _ => path.to_owned(),
+    }
+}
+
+/// Clears the screen (when stdout is a terminal), and prints the time
+/// that the next run started at.
+fn start_run(offset: Option<UtcOffset>) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    if stdout.is_terminal() {
+        write!(stdout, "\x1b[2J\x1b[3J\x1b[H")?;
+        stdout.flush()?;
+    }
+
+    let now = OffsetDateTime::now_utc();
+    let (now, zone) = match offset {
+        Some(offset) => (now.to_offset(offset), ""),
+        None => (now, " UTC"),
+    };
+    let now = now.format(format_description!("[hour]:[minute]:[second]"))?;
+
+    anstream::println!(
+        "{}",
+        format!("[{now}{zone}] zizmor --watch (press Ctrl-C to exit)").bold()
+    );
+
+    Ok(())
+}
+
+/// Calls `run`, and then calls it again whenever any path that it adds
+/// to the given [`Watched`] changes, until interrupted with Ctrl-C.
+///
+/// Returns the exit code of the most recent run.
+pub(crate) fn watch(mut run: impl FnMut(&mut Watched) -> ExitCode) -> Result<ExitCode> {
+    // NOTE: The local offset can only be determined soundly while there's
+    // a single thread, so it's determined once, before spawning any.
+    let offset = UtcOffset::current_local_offset().ok();
+
+    let (tx, rx) = mpsc::channel();
+
+    ctrlc::set_handler({
+        let tx = tx.clone();
+        move || {
+            // A second Ctrl-C doesn't wait for the current run to finish.
+            if INTERRUPTED.swap(true, Ordering::SeqCst) {
+                std::process::exit(INTERRUPTED_EXIT_CODE);
+            }
+            let _ = tx.send(Event::Interrupted);
+        }
+    })
+    .context("couldn't handle Ctrl-C")?;
+
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                let _ = tx.send(Event::Changed(event.paths));
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("couldn't watch for changes: {e}"),
+        })
+        .context("couldn't watch for changes")?;
+
+    let mut watched = Watched::default();
+    loop {
+        start_run(offset)?;
+        let exit_code = run(&mut watched);
+
+        watched.register(&mut watcher);
+        tracing::info!(
+            "watching {count} path(s) for changes",
+            count = watched.dirs.len() + watched.files.len()
+        );
+
+        // Wait for a relevant change...
+        loop {
+            match rx.recv() {
+                Ok(Event::Changed(paths)) if paths.iter().any(|p| watched.relevant(p)) => break,
+                Ok(Event::Changed(_)) => continue,
+                Ok(Event::Interrupted) | Err(_) => return Ok(exit_code),
+            }
+        }
+
+        // ...and then for things to settle down.
+        loop {
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(Event::Changed(_)) => continue,
+                Err(RecvTimeoutError::Timeout) => break,
+                Ok(Event::Interrupted) | Err(RecvTimeoutError::Disconnected) => {
+                    return Ok(exit_code);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use camino::Utf8Path;
+
+    use super::Watched;
+
+    #[test]
+    fn test_relevant() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        fs::create_dir(root.join("workflows")).unwrap();
+
+        let mut watched = Watched::default();
+        watched.add(&root.join("workflows"));
+        watched.add(&root.join("allowlist.txt"));
+
+        let root = root.canonicalize_utf8().unwrap();
+        for (path, relevant) in [
+            ("workflows/ci.yml", true),
+            ("workflows/nested/ci.yaml", true),
+            ("workflows/notes.md", false),
+            ("allowlist.txt", true),
+            ("other.yml", false),
+            ("other.txt", false),
+        ] {
+            assert_eq!(
+                watched.relevant(root.join(path).as_std_path()),
+                relevant,
+                "{path}"
+            );
+        }
+    }
+}
@@ -303,3 +303,159 @@ fn audit_unpinned_images() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn tpa_sarif_is_valid() -> anyhow::Result<()> {
+    let auditable = input_under_test("tpa/tpa-menagerie.yml");
+
+    let execution = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--format", "tpa-sarif", &auditable])
+        .output()?;
+    assert_eq!(execution.status.code(), Some(0));
+
+    let sarif: Value = serde_json::from_slice(&execution.stdout)?;
+
+    let schema = serde_json::from_str(&std::fs::read_to_string(input_under_test(
+        "sarif-schema-2.1.0.json",
+    ))?)?;
+    let validator = jsonschema::validator_for(&schema)?;
+    if let Err(e) = validator.validate(&sarif) {
+        panic!("invalid SARIF: {e}");
+    }
+
+    assert_value_match(
+        &sarif,
+        "$.runs[0].tool.driver.rules[0].id",
+        "zizmor/unpinned-uses",
+    );
+
+    let fingerprints = JsonPath::parse("$.runs[0].results[*].partialFingerprints")?
+        .query(&sarif)
+        .all();
+    assert!(!fingerprints.is_empty());
+    assert!(
+        fingerprints
+            .iter()
+            .all(|fingerprint| fingerprint["unpinnedUsesHash/v1"].is_string())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn sarif_is_valid() -> anyhow::Result<()> {
+    let auditable = input_under_test("unpinned-uses.yml");
+
+    let execution = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--format", "sarif", &auditable])
+        .output()?;
+    assert_eq!(execution.status.code(), Some(0));
+
+    let sarif: Value = serde_json::from_slice(&execution.stdout)?;
+
+    let schema = serde_json::from_str(&std::fs::read_to_string(input_under_test(
+        "sarif-schema-2.1.0.json",
+    ))?)?;
+    let validator = jsonschema::validator_for(&schema)?;
+    if let Err(e) = validator.validate(&sarif) {
+        panic!("invalid SARIF: {e}");
+    }
+
+    // Each rule links to its docs, with a remediation blurb and a default level.
+    assert_value_match(
+        &sarif,
+        "$.runs[0].tool.driver.rules[?@.id == 'zizmor/unpinned-uses'].helpUri",
+        "https://docs.zizmor.sh/audits/#unpinned-uses",
+    );
+    assert_value_match(
+        &sarif,
+        "$.runs[0].tool.driver.rules[?@.id == 'zizmor/unpinned-uses'].help.markdown",
+        "Pin the action to a full commit SHA",
+    );
+    assert_value_match(
+        &sarif,
+        "$.runs[0].tool.driver.rules[?@.id == 'zizmor/unpinned-uses'].defaultConfiguration.level",
+        "warning",
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cyclonedx_is_valid() -> anyhow::Result<()> {
+    let auditable = input_under_test("tpa/tpa-menagerie.yml");
+
+    let execution = Command::cargo_bin("zizmor")?
+        .args([
+            "--offline",
+            "--format",
+            "cyclonedx",
+            "--tpa-fail-on",
+            "never",
+            &auditable,
+        ])
+        .output()?;
+    assert_eq!(execution.status.code(), Some(0));
+
+    let bom: Value = serde_json::from_slice(&execution.stdout)?;
+
+    // NOTE: This is a subset of the official schema, since the official
+    // schema references other schemas by remote URL.
+    let schema = serde_json::from_str(&std::fs::read_to_string(input_under_test(
+        "cyclonedx-1.5-subset.schema.json",
+    ))?)?;
+    let validator = jsonschema::validator_for(&schema)?;
+    if let Err(e) = validator.validate(&bom) {
+        panic!("invalid CycloneDX: {e}");
+    }
+
+    assert_value_match(
+        &bom,
+        "$.components[?@.name == 'actions/checkout'].purl",
+        "pkg:github/actions/checkout@11bd71901bbe5b1630ceea73d27597364c9af683",
+    );
+    assert_value_match(
+        &bom,
+        "$.components[?@.name == 'actions/checkout'].hashes[0].alg",
+        "SHA-1",
+    );
+    assert_value_match(
+        &bom,
+        "$.components[?@.name == 'library/alpine'].purl",
+        "pkg:docker/library/alpine@3.20",
+    );
+
+    Ok(())
+}
+
+#[test]
+fn sbom_formats_agree() -> anyhow::Result<()> {
+    let auditable = input_under_test("spdx");
+
+    let run = |format| -> anyhow::Result<Value> {
+        let execution = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--format", format, &auditable])
+            .output()?;
+        Ok(serde_json::from_slice(&execution.stdout)?)
+    };
+
+    let purls = |json: &Value, path| -> anyhow::Result<Vec<String>> {
+        Ok(JsonPath::parse(path)?
+            .query(json)
+            .all()
+            .into_iter()
+            .map(|purl| purl.as_str().unwrap().to_string())
+            .collect())
+    };
+
+    let cyclonedx = purls(&run("cyclonedx")?, "$.components[*].purl")?;
+    let spdx = purls(
+        &run("spdx")?,
+        "$.packages[*].externalRefs[*].referenceLocator",
+    )?;
+
+    assert_eq!(cyclonedx.len(), 5);
+    assert_eq!(cyclonedx, spdx);
+
+    Ok(())
+}
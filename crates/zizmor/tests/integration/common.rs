@@ -1,6 +1,7 @@
 use anyhow::{Context as _, Result};
 use regex::{Captures, Regex};
-use std::{env::current_dir, io::ErrorKind};
+use serde_json::Value;
+use std::{env::current_dir, io::ErrorKind, path::PathBuf, sync::OnceLock};
 
 use assert_cmd::Command;
 
@@ -25,7 +26,6 @@ pub fn input_under_test(name: &str) -> String {
 
 pub enum OutputMode {
     Stdout,
-    #[allow(dead_code, reason = "currently not used by any integration test")]
     Stderr,
     Both,
 }
@@ -71,6 +71,11 @@ impl Zizmor {
         self
     }
 
+    pub fn current_dir(mut self, dir: impl AsRef<std::path::Path>) -> Self {
+        self.cmd.current_dir(dir);
+        self
+    }
+
     pub fn input(mut self, input: impl Into<String>) -> Self {
         self.inputs.push(input.into());
         self
@@ -113,7 +118,7 @@ impl Zizmor {
             std::env::var("GH_TOKEN").context("online tests require GH_TOKEN to be set")?;
         }
 
-        if let Some(config) = self.config {
+        if let Some(config) = &self.config {
             self.cmd.arg("--config").arg(config);
         } else {
             self.cmd.arg("--no-config");
@@ -163,8 +168,9 @@ impl Zizmor {
 
         if let Some(exit_code) = output.status.code() {
             // There are other nonzero exit codes that don't indicate failure;
-            // 1 is our only failure code.
-            let is_failure = exit_code == 1;
+            // only 1 (unexpected errors), 3 (configuration errors), and
+            // 4 (input collection errors) do.
+            let is_failure = matches!(exit_code, 1 | 3 | 4);
             if is_failure != self.expects_failure {
                 anyhow::bail!("zizmor exited with unexpected code {exit_code}");
             }
@@ -175,6 +181,10 @@ impl Zizmor {
             raw = raw.replace(input, input_placeholder);
         }
 
+        if let Some(config) = &self.config {
+            raw = raw.replace(config, "@@CONFIG@@");
+        }
+
         // Normalize Windows '\' file paths to using '/', to get consistent snapshot test outputs
         if cfg!(windows) {
             let input_path_regex = Regex::new(&format!(r"{input_placeholder}[\\/\w.-]+"))?;
@@ -192,3 +202,199 @@ impl Zizmor {
 pub fn zizmor() -> Zizmor {
     Zizmor::new()
 }
+
+/// The directory of the given audit's fixtures, i.e. `tests/fixtures/<rule>/`.
+pub fn fixtures_dir(rule: &str) -> PathBuf {
+    current_dir()
+        .expect("Cannot figure out current directory")
+        .join("tests")
+        .join("fixtures")
+        .join(rule)
+}
+
+/// The idents of every audit, as listed by `--list-rules`.
+fn rules() -> &'static [String] {
+    static RULES: OnceLock<Vec<String>> = OnceLock::new();
+
+    RULES.get_or_init(|| {
+        let output = Command::cargo_bin("zizmor")
+            .unwrap()
+            .args(["--list-rules", "--format=json", "--no-config"])
+            .output()
+            .unwrap();
+        serde_json::from_slice::<Vec<Value>>(&output.stdout)
+            .unwrap()
+            .iter()
+            .filter_map(|rule| rule["ident"].as_str().map(Into::into))
+            .collect()
+    })
+}
+
+/// The names of the given audit's fixtures, i.e. the workflows and actions
+/// in its fixtures directory other than their configurations, sorted.
+pub fn fixtures(rule: &str) -> Result<Vec<String>> {
+    let dir = fixtures_dir(rule);
+    let mut names = vec![];
+    for entry in std::fs::read_dir(&dir).with_context(|| format!("no fixtures in {dir:?}"))? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if (name.ends_with(".yml") || name.ends_with(".yaml")) && !name.contains(".zizmor.") {
+            names.push(name);
+        }
+    }
+    names.sort();
+
+    Ok(names)
+}
+
+/// Renders a location's symbolic route, e.g. `jobs.build.steps[0].uses`.
+fn render_route(location: &Value) -> String {
+    let mut route = String::new();
+    for component in location["symbolic"]["route"]["components"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        if let Some(key) = component["Key"].as_str() {
+            if !route.is_empty() {
+                route.push('.');
+            }
+            route.push_str(key);
+        } else if let Some(idx) = component["Index"].as_u64() {
+            route.push_str(&format!("[{idx}]"));
+        }
+    }
+    route
+}
+
+/// Renders a finding (from the JSON output) as the lines of a fixture's
+/// snapshot: its determinations, and then each of its locations.
+fn render_fixture_finding(finding: &Value) -> String {
+    let determinations = &finding["determinations"];
+    let mut rendered = format!(
+        "{ident}: {severity} severity, {confidence} confidence, {persona} persona\n",
+        ident = finding["ident"].as_str().unwrap_or_default(),
+        severity = determinations["severity"].as_str().unwrap_or_default(),
+        confidence = determinations["confidence"].as_str().unwrap_or_default(),
+        persona = determinations["persona"].as_str().unwrap_or_default(),
+    );
+
+    for location in finding["locations"].as_array().into_iter().flatten() {
+        let point = &location["concrete"]["location"]["start_point"];
+        let kind = location["symbolic"]["role"]
+            .as_str()
+            .or(location["symbolic"]["kind"].as_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        rendered.push_str(&format!(
+            "  {line}:{column} {route} [{kind}]: {annotation}\n",
+            line = point["row"].as_u64().unwrap_or_default() + 1,
+            column = point["column"].as_u64().unwrap_or_default() + 1,
+            route = render_route(location),
+            annotation = location["symbolic"]["annotation"]
+                .as_str()
+                .unwrap_or_default(),
+        ));
+    }
+
+    for location in finding["external_locations"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        let position = match location["point"].as_object() {
+            Some(point) => format!(
+                ":{line}:{column}",
+                line = point["row"].as_u64().unwrap_or_default() + 1,
+                column = point["column"].as_u64().unwrap_or_default() + 1,
+            ),
+            None => String::new(),
+        };
+        rendered.push_str(&format!(
+            "  {path}{position} [external]: {annotation}\n",
+            path = location["path"].as_str().unwrap_or("<none>"),
+            annotation = location["annotation"].as_str().unwrap_or_default(),
+        ));
+    }
+
+    rendered
+}
+
+/// Runs the given audit (and only that audit) against one of its fixtures,
+/// and renders its findings (every persona's) for snapshotting.
+///
+/// A fixture can have its own configuration, as `<stem>.zizmor.yml`,
+/// and its own `--tpa-allowlist-file`, as `<stem>.allowlist.txt`; fixtures
+/// without a configuration are audited with `--no-config`. Fixtures whose
+/// names start with `negative-` must produce no findings.
+///
+/// Fails if the audit didn't run, e.g. because it requires online access,
+/// so that a misnamed or skipped audit can't pass as a negative fixture.
+pub fn audit_fixture(rule: &str, name: &str) -> Result<String> {
+    let dir = fixtures_dir(rule);
+    let fixture = dir.join(name);
+    anyhow::ensure!(fixture.exists(), "no such fixture: {fixture:?}");
+    let stem = name
+        .strip_suffix(".yml")
+        .or_else(|| name.strip_suffix(".yaml"))
+        .unwrap_or(name);
+
+    anyhow::ensure!(rules().iter().any(|r| r == rule), "no such audit: {rule}");
+    let disable = format!(
+        "--disable={}",
+        rules()
+            .iter()
+            .filter(|r| *r != rule)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+
+    let mut zizmor = zizmor()
+        .offline(true)
+        .input(fixture.to_string_lossy())
+        .args(["--format=json", "--summary", "--persona=auditor", &disable]);
+
+    let config = dir.join(format!("{stem}.zizmor.yml"));
+    if config.exists() {
+        zizmor = zizmor.config(config.to_string_lossy());
+    }
+
+    let allowlist = dir.join(format!("{stem}.allowlist.txt"));
+    if allowlist.exists() {
+        zizmor = zizmor.args(["--tpa-allowlist-file", &allowlist.to_string_lossy()]);
+    }
+
+    let output = serde_json::from_str::<Value>(&zizmor.run()?)?;
+
+    let ran = output["summary"]["rules"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|audit| audit["ident"] == rule);
+    anyhow::ensure!(ran, "{rule} didn't run against {name}");
+
+    let findings = output["findings"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|finding| finding["ident"] == rule)
+        .collect::<Vec<_>>();
+
+    if name.starts_with("negative-") {
+        anyhow::ensure!(
+            findings.is_empty(),
+            "negative fixture {name} has {n} {rule} finding(s)",
+            n = findings.len()
+        );
+    }
+
+    if findings.is_empty() {
+        return Ok("no findings\n".into());
+    }
+
+    Ok(findings
+        .into_iter()
+        .map(render_fixture_finding)
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
@@ -1,6 +1,7 @@
 //! End-to-end integration tests.
 
 use anyhow::Result;
+use assert_cmd::Command;
 
 use crate::common::{OutputMode, input_under_test, zizmor};
 
@@ -236,3 +237,2348 @@ fn invalid_inputs() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn tpa_fail_on() -> Result<()> {
+    // The TPA formats use a dedicated exit code, determined by `--tpa-fail-on`
+    // rather than by the severity of any findings.
+    for (fail_on, expected) in [
+        ("unpinned-third-party", 20),
+        ("any-third-party", 20),
+        ("never", 0),
+    ] {
+        let output = Command::cargo_bin("zizmor")?
+            .args([
+                "--offline",
+                "--no-config",
+                "--format=tpa-csv",
+                &format!("--tpa-fail-on={fail_on}"),
+                &input_under_test("tpa/tpa-menagerie.yml"),
+            ])
+            .output()?;
+
+        assert_eq!(
+            output.status.code(),
+            Some(expected),
+            "--tpa-fail-on={fail_on}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn score() -> Result<()> {
+    let run = |extra: &[&str]| -> Result<(Option<i32>, serde_json::Value)> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--format=json"])
+            .args(extra)
+            .arg(input_under_test("score/workflow.yml"))
+            .output()?;
+
+        Ok((
+            output.status.code(),
+            serde_json::from_slice(&output.stdout)?,
+        ))
+    };
+
+    // NOTE: These pin the formula; if they change, every score that's
+    // being tracked over time has changed too.
+    let (_, output) = run(&["--no-config", "--score"])?;
+    assert_eq!(output["score"]["value"], 65);
+    assert_eq!(
+        output["score"]["formula"],
+        "max(0, 100 - sum(count * weight))"
+    );
+    assert_eq!(
+        output["score"]["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| (c["name"].as_str().unwrap(), c["count"].as_u64().unwrap()))
+            .collect::<Vec<_>>(),
+        [
+            ("unpinned-third-party", 2),
+            ("secrets-to-third-party", 1),
+            ("dangerous-triggers", 1),
+            ("missing-permissions", 2),
+        ]
+    );
+
+    let config = input_under_test("score/zizmor.yml");
+    let (_, output) = run(&["--config", &config, "--score"])?;
+    assert_eq!(output["score"]["value"], 57);
+
+    // `--min-score` implies `--score`, and takes precedence over the
+    // findings' exit code when the score is below it.
+    for (min_score, expected) in [("57", 14), ("58", 23)] {
+        let (code, output) = run(&["--config", &config, "--min-score", min_score])?;
+        assert_eq!(output["score"]["value"], 57);
+        assert_eq!(code, Some(expected), "--min-score={min_score}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn rollup() -> Result<()> {
+    let run = |format: &str| -> Result<serde_json::Value> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-config", "--rollup=by-action"])
+            .arg(format!("--format={format}"))
+            .arg(input_under_test("rollup"))
+            .output()?;
+
+        Ok(serde_json::from_slice(&output.stdout)?)
+    };
+
+    // Identical findings for the same action (regardless of its case or
+    // quoting) are rolled up into the first, with the rest as occurrences.
+    let findings = run("json")?;
+    let findings = findings.as_array().unwrap();
+    assert_eq!(findings.len(), 2);
+    assert_eq!(findings[0]["occurrences"].as_array().unwrap().len(), 3);
+    assert!(findings[1].get("occurrences").is_none());
+
+    // SARIF has one result per rolled-up finding, located at each occurrence.
+    let sarif = run("sarif")?;
+    assert_eq!(
+        sarif["runs"][0]["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|result| result["locations"].as_array().unwrap().len())
+            .collect::<Vec<_>>(),
+        [4, 1]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn baseline() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let workflow = dir.path().join("workflow.yml");
+    let baseline = dir.path().join("baseline.json");
+
+    let original = std::fs::read_to_string(input_under_test("several-vulnerabilities.yml"))?;
+    std::fs::write(&workflow, &original)?;
+
+    let run = |extra: &[&str]| -> Result<(Option<i32>, serde_json::Value)> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-config", "--format=json", "--baseline"])
+            .arg(&baseline)
+            .args(extra)
+            .arg(&workflow)
+            .output()?;
+
+        Ok((
+            output.status.code(),
+            serde_json::from_slice(&output.stdout)?,
+        ))
+    };
+
+    // Generating the baseline marks every finding as baselined.
+    let (code, findings) = run(&["--update-baseline"])?;
+    assert_eq!(code, Some(0));
+    let baselined = findings.as_array().unwrap().len();
+    assert!(baselined > 0);
+    assert!(baseline.exists());
+
+    // Unrelated edits that shift every finding's line don't affect the baseline.
+    std::fs::write(&workflow, format!("# a new comment\n\n{original}"))?;
+    let (code, findings) = run(&[])?;
+    assert_eq!(code, Some(0));
+    assert_eq!(findings.as_array().unwrap().len(), baselined);
+    assert!(
+        findings
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|f| f["baselined"] == true)
+    );
+
+    // New findings aren't baselined, and determine the exit code.
+    std::fs::write(
+        &workflow,
+        format!("{original}      - run: echo \"${{{{ github.event.issue.title }}}}\"\n"),
+    )?;
+    let (code, findings) = run(&[])?;
+    assert_eq!(code, Some(14));
+    let new = findings
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|f| f["baselined"] == false)
+        .collect::<Vec<_>>();
+    assert_eq!(new.len(), 1);
+    assert_eq!(new[0]["ident"], "template-injection");
+
+    Ok(())
+}
+
+#[test]
+fn diff() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let workflow = dir.path().join("workflow.yml");
+    let previous = dir.path().join("previous.json");
+
+    let original = std::fs::read_to_string(input_under_test("several-vulnerabilities.yml"))?;
+    std::fs::write(&workflow, &original)?;
+
+    let run = |extra: &[&str]| -> Result<(Option<i32>, serde_json::Value, String)> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-config", "--format=json", "--quiet"])
+            .args(extra)
+            .arg(&workflow)
+            .output()?;
+
+        Ok((
+            output.status.code(),
+            serde_json::from_slice(&output.stdout)?,
+            String::from_utf8(output.stderr)?,
+        ))
+    };
+
+    let (code, findings, _) = run(&[])?;
+    assert_eq!(code, Some(14));
+    std::fs::write(&previous, serde_json::to_string(&findings)?)?;
+
+    let diff = format!("--diff={}", previous.display());
+
+    // Diffing against ourselves produces nothing new and nothing resolved.
+    std::fs::write(&workflow, format!("# a new comment\n\n{original}"))?;
+    let (code, findings, stderr) = run(&[&diff])?;
+    assert_eq!(code, Some(0));
+    assert_eq!(findings.as_array().unwrap().len(), 0);
+    assert!(!stderr.contains("resolved"));
+
+    // New findings are reported and determine the exit code, while
+    // removed ones are listed as resolved.
+    let head = original.replace("permissions: write-all", "permissions: {}")
+        + "      - run: echo \"${{ github.event.issue.title }}\"\n";
+    std::fs::write(&workflow, head)?;
+    let (code, findings, stderr) = run(&[&diff])?;
+    assert_eq!(code, Some(14));
+    let findings = findings.as_array().unwrap();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0]["ident"], "template-injection");
+    assert!(stderr.contains("1 finding resolved since"));
+    assert!(stderr.contains("excessive-permissions (High)"));
+
+    Ok(())
+}
+
+#[test]
+fn fail_on() -> Result<()> {
+    let run = |args: &[&str]| -> Result<Option<i32>> {
+        Ok(Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-config"])
+            .args(args)
+            .arg(input_under_test("use-trusted-publishing.yml"))
+            .output()?
+            .status
+            .code())
+    };
+
+    // By default, any finding fails the run.
+    assert_eq!(run(&[])?, Some(11));
+    assert_eq!(run(&["--fail-on=informational"])?, Some(11));
+    assert_eq!(run(&["--fail-on=never"])?, Some(0));
+
+    // The medium finding is pedantic, so it's suppressed by default
+    // and doesn't count towards failure.
+    assert_eq!(run(&["--fail-on=medium"])?, Some(0));
+    assert_eq!(run(&["--fail-on=medium", "--persona=pedantic"])?, Some(13));
+    assert_eq!(run(&["--fail-on=high", "--persona=pedantic"])?, Some(0));
+
+    Ok(())
+}
+
+#[test]
+fn output_to_file() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("results").join("findings.json");
+
+    let run = |extra: &[&str]| -> Result<std::process::Output> {
+        Ok(Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-config", "--format=json"])
+            .args(extra)
+            .arg(input_under_test("several-vulnerabilities.yml"))
+            .output()?)
+    };
+
+    let stdout = run(&[])?;
+
+    // `--output -` is the same as no `--output` at all.
+    let explicit = run(&["--output=-"])?;
+    assert_eq!(explicit.status.code(), stdout.status.code());
+    assert_eq!(explicit.stdout, stdout.stdout);
+
+    // With a file, the output moves there and a summary goes to stderr,
+    // but the exit code is unchanged.
+    let output = run(&["--output", path.to_str().unwrap()])?;
+    assert_eq!(output.status.code(), Some(14));
+    assert!(output.stdout.is_empty());
+    assert_eq!(std::fs::read(&path)?, stdout.stdout);
+
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("wrote json output to"));
+    assert!(stderr.contains("3 high"));
+    assert!(stderr.contains("failing: highest severity finding is high"));
+
+    // The TPA report can't be clobbered by the output.
+    let report = dir.path().join("report.json");
+    let report = report.to_str().unwrap();
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--no-config", "--format=tpa-list"])
+        .args(["--output", report, "--tpa-report", report])
+        .arg(input_under_test("several-vulnerabilities.yml"))
+        .output()?;
+    assert_eq!(output.status.code(), Some(3));
+    assert!(!dir.path().join("report.json").exists());
+
+    Ok(())
+}
+
+#[test]
+fn jsonl() -> Result<()> {
+    let run = |format: &str| -> Result<std::process::Output> {
+        Ok(Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-config", "--format", format])
+            .arg(input_under_test("several-vulnerabilities.yml"))
+            .arg(input_under_test("artipacked.yml"))
+            .output()?)
+    };
+
+    let json = run("json")?;
+    let jsonl = run("jsonl")?;
+    assert_eq!(jsonl.status.code(), json.status.code());
+
+    let mut lines = std::str::from_utf8(&jsonl.stdout)?
+        .lines()
+        .map(serde_json::from_str::<serde_json::Value>)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // The last line is the summary, and every other line is a finding
+    // in the same shape (and order) as the JSON output's.
+    let summary = lines.pop().unwrap();
+    assert_eq!(summary["type"], "summary");
+    assert_eq!(summary["findings"], lines.len());
+    assert_eq!(summary["severities"]["high"], 3);
+
+    let findings = serde_json::from_slice::<serde_json::Value>(&json.stdout)?;
+    assert_eq!(findings.as_array().unwrap(), &lines);
+
+    Ok(())
+}
+
+#[test]
+fn fingerprints_are_stable() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let workflow = dir.path().join("workflow.yml");
+    let original = std::fs::read_to_string(input_under_test("several-vulnerabilities.yml"))?;
+
+    let run = |format: &str| -> Result<serde_json::Value> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-config", "--format", format])
+            .arg(&workflow)
+            .output()?;
+        Ok(serde_json::from_slice(&output.stdout)?)
+    };
+    let fingerprints = || -> Result<Vec<String>> {
+        Ok(run("json")?
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["fingerprint"].as_str().unwrap().to_string())
+            .collect())
+    };
+
+    std::fs::write(&workflow, &original)?;
+    let before = fingerprints()?;
+    assert!(!before.is_empty());
+
+    // SARIF exposes the same fingerprints.
+    let sarif = run("sarif")?;
+    let partial = sarif["runs"][0]["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            r["partialFingerprints"]["zizmorFindingHash/v1"]
+                .as_str()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(partial, before);
+
+    // Renumbering every line doesn't change any fingerprint.
+    std::fs::write(
+        &workflow,
+        format!("# a comment\n# and another\n\n{original}"),
+    )?;
+    assert_eq!(fingerprints()?, before);
+
+    // ...but changing what a finding is about does.
+    std::fs::write(
+        &workflow,
+        original.replace("pull_request.title", "pull_request.body"),
+    )?;
+    let after = fingerprints()?;
+    assert_eq!(after.len(), before.len());
+    assert_ne!(after, before);
+
+    Ok(())
+}
+
+#[test]
+fn skipped_online_audits() -> Result<()> {
+    let run = |args: &[&str], envs: &[(&str, &str)]| -> Result<String> {
+        let output = Command::cargo_bin("zizmor")?
+            .env_remove("ZIZMOR_GH_TOKEN")
+            .env_remove("GH_TOKEN")
+            .env_remove("GITHUB_TOKEN")
+            .env_remove("ZIZMOR_OFFLINE")
+            .envs(envs.iter().copied())
+            .args(["--no-config", "--no-progress"])
+            .args(args)
+            .arg(input_under_test("several-vulnerabilities.yml"))
+            .output()?;
+        Ok(String::from_utf8(output.stderr)?)
+    };
+
+    // Online-only audits are skipped and summarized, while audits that
+    // are merely enhanced by the network (`known-vulnerable-actions`)
+    // degrade instead.
+    let stderr = run(&["--offline"], &[])?;
+    assert!(
+        stderr.contains("5 audits skipped: offline mode requested"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("known-vulnerable-actions"), "{stderr}");
+
+    // `ZIZMOR_OFFLINE` is a fallback for `--offline`.
+    let stderr = run(&[], &[("ZIZMOR_OFFLINE", "1")])?;
+    assert!(
+        stderr.contains("5 audits skipped: offline mode requested"),
+        "{stderr}"
+    );
+
+    let stderr = run(&["--no-online-audits"], &[])?;
+    assert!(
+        stderr.contains("5 audits skipped: offline mode requested"),
+        "{stderr}"
+    );
+
+    // Without credentials, online audits can't run either.
+    let stderr = run(&[], &[])?;
+    assert!(
+        stderr.contains("5 audits skipped: no credentials available"),
+        "{stderr}"
+    );
+
+    // Empty tokens don't count as credentials.
+    let stderr = run(&[], &[("GH_TOKEN", "")])?;
+    assert!(
+        stderr.contains("5 audits skipped: no credentials available"),
+        "{stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn parallel_output_is_deterministic() -> Result<()> {
+    let run = |jobs: &str, format: &str| -> Result<std::process::Output> {
+        Ok(Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-config", "--no-progress", "--jobs", jobs])
+            .args(["--format", format])
+            .arg(input_under_test("."))
+            .output()?)
+    };
+
+    for format in ["plain", "json", "jsonl"] {
+        let serial = run("1", format)?;
+        for jobs in ["2", "8"] {
+            let parallel = run(jobs, format)?;
+            assert_eq!(parallel.status.code(), serial.status.code());
+            assert_eq!(parallel.stdout, serial.stdout, "{format} with {jobs} jobs");
+            assert_eq!(parallel.stderr, serial.stderr, "{format} with {jobs} jobs");
+        }
+    }
+
+    // Zero jobs is nonsensical.
+    assert_eq!(run("0", "plain")?.status.code(), Some(2));
+
+    Ok(())
+}
+
+#[test]
+fn org_scan_arguments() -> Result<()> {
+    let run = |args: &[&str]| -> Result<std::process::Output> {
+        Ok(Command::cargo_bin("zizmor")?
+            .env_remove("GH_TOKEN")
+            .args(["--no-config", "--no-progress"])
+            .args(args)
+            .output()?)
+    };
+
+    // `--org` stands in for the usual inputs, but repository selection
+    // only makes sense with it.
+    assert_eq!(run(&[])?.status.code(), Some(2));
+    assert_eq!(
+        run(&["--repo-filter", "api-*", "."])?.status.code(),
+        Some(2)
+    );
+    assert_eq!(run(&["--repo-topic", "ci", "."])?.status.code(), Some(2));
+    assert_eq!(
+        run(&["--org", "acme", "--concurrency", "0"])?.status.code(),
+        Some(2)
+    );
+    assert_eq!(
+        run(&["--org", "acme", "--repo-filter", "api-[", "--offline"])?
+            .status
+            .code(),
+        Some(2)
+    );
+
+    // Listing an org's repositories requires the GitHub API.
+    let output = run(&["--org", "acme", "--offline"])?;
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8(output.stderr)?.contains("can't list repositories for"));
+
+    Ok(())
+}
+
+#[test]
+fn config_discovery() -> Result<()> {
+    let targets = ["github-config", "root-config", "no-config"]
+        .map(|target| input_under_test(&format!("config-discovery/{target}")));
+
+    // Returns the sorted targets with `unpinned-uses` findings, and stderr.
+    let run = |args: &[&str]| -> Result<(Vec<String>, String)> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-progress", "--format=json"])
+            .args(args)
+            .args(&targets)
+            .output()?;
+
+        let findings = serde_json::from_slice::<serde_json::Value>(&output.stdout)?;
+        let mut unpinned = findings
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|f| f["ident"] == "unpinned-uses")
+            .map(|f| {
+                let path = f["locations"][0]["symbolic"]["key"]["Local"]["given_path"]
+                    .as_str()
+                    .unwrap();
+                path.split('/').rev().nth(3).unwrap().to_string()
+            })
+            .collect::<Vec<_>>();
+        unpinned.sort();
+
+        Ok((unpinned, String::from_utf8(output.stderr)?))
+    };
+
+    // Each target uses its own configuration, with `.github/zizmor.yml`
+    // taking precedence over `zizmor.yml`.
+    let (unpinned, stderr) = run(&[])?;
+    assert_eq!(unpinned, ["github-config", "root-config"], "{stderr}");
+    assert!(
+        stderr.contains("github-config/.github/zizmor.yml")
+            && stderr.contains("root-config/zizmor.yml"),
+        "{stderr}"
+    );
+
+    // An explicit configuration always wins.
+    let explicit = input_under_test("config-discovery/explicit.yml");
+    let (unpinned, stderr) = run(&["--config", &explicit])?;
+    assert!(unpinned.is_empty(), "{stderr}");
+    assert!(!stderr.contains("using config for"), "{stderr}");
+
+    let (unpinned, stderr) = run(&["--no-config"])?;
+    assert!(unpinned.is_empty(), "{stderr}");
+
+    Ok(())
+}
+
+#[test]
+fn collect_exclude() -> Result<()> {
+    // Returns the sorted paths of each finding, relative to the input.
+    let run = |args: &[&str]| -> Result<Vec<String>> {
+        let output = Command::cargo_bin("zizmor")?
+            .args([
+                "--offline",
+                "--no-config",
+                "--no-progress",
+                "--format=sarif",
+            ])
+            .args(args)
+            .arg(input_under_test("collect-exclude"))
+            .output()?;
+        // Every collected input, including non-composite actions,
+        // should parse without warnings.
+        let stderr = String::from_utf8(output.stderr)?;
+        assert!(output.status.success(), "{stderr}");
+        assert!(!stderr.contains("WARN"), "{stderr}");
+
+        let sarif = serde_json::from_slice::<serde_json::Value>(&output.stdout)?;
+        let mut paths = sarif["runs"][0]["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|result| {
+                result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        Ok(paths)
+    };
+
+    // Action manifests are collected from anywhere in the input,
+    // and non-composite (node and docker) actions yield no findings.
+    assert_eq!(
+        run(&[])?,
+        [
+            ".github/actions/setup/action.yml",
+            ".github/workflows/ci.yml",
+            "tests/fixtures/bad-action/action.yml",
+        ]
+    );
+
+    assert_eq!(
+        run(&["--exclude=tests"])?,
+        [
+            ".github/actions/setup/action.yml",
+            ".github/workflows/ci.yml"
+        ]
+    );
+    assert_eq!(
+        run(&["--exclude=**/action.yml"])?,
+        [".github/workflows/ci.yml"]
+    );
+    assert_eq!(
+        run(&["--collect=workflows-only"])?,
+        [".github/workflows/ci.yml"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn stdin_input() -> Result<()> {
+    let workflow =
+        std::fs::read_to_string(input_under_test("collect-exclude/.github/workflows/ci.yml"))?;
+    let action = std::fs::read_to_string(input_under_test(
+        "collect-exclude/.github/actions/setup/action.yml",
+    ))?;
+
+    let run = |contents: &str, args: &[&str]| -> Result<(Option<i32>, String)> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-config", "--no-progress", "--stdin"])
+            .args(args)
+            .write_stdin(contents)
+            .output()?;
+
+        Ok((output.status.code(), String::from_utf8(output.stdout)?))
+    };
+
+    // The filename is used for locations, and exit codes match file inputs.
+    let (code, stdout) = run(&workflow, &["--stdin-filename=.github/workflows/ci.yml"])?;
+    assert_eq!(code, Some(14));
+    assert!(
+        stdout.contains(" --> .github/workflows/ci.yml:9:9"),
+        "{stdout}"
+    );
+
+    let (code, stdout) = run(
+        &workflow,
+        &[
+            "--stdin-filename=.github/workflows/ci.yml",
+            "--min-severity=high",
+        ],
+    )?;
+    assert_eq!(code, Some(14), "{stdout}");
+
+    let (code, stdout) = run(
+        &workflow,
+        &[
+            "--stdin-filename=.github/workflows/ci.yml",
+            "--fail-on=never",
+        ],
+    )?;
+    assert_eq!(code, Some(0), "{stdout}");
+
+    // The filename decides whether the input is an action definition.
+    let (code, stdout) = run(&action, &["--stdin-filename=action.yml"])?;
+    assert_eq!(code, Some(14));
+    assert!(stdout.contains(" --> action.yml:6:7"), "{stdout}");
+
+    let (code, _) = run(&action, &["--stdin-filename=.github/workflows/ci.yml"])?;
+    assert_eq!(code, Some(4));
+
+    // The TPA formats work from the in-memory input as well.
+    let (code, stdout) = run(
+        &workflow,
+        &[
+            "--stdin-filename=.github/workflows/ci.yml",
+            "--format=tpa-list",
+            "--tpa-report=-",
+        ],
+    )?;
+    assert_eq!(code, Some(20));
+    let report = serde_json::from_str::<serde_json::Value>(&stdout)?;
+    assert_eq!(
+        report["actions"][0]["file_path"],
+        ".github/workflows/ci.yml"
+    );
+    assert_eq!(
+        report["actions"][0]["line"],
+        "uses: some-org/some-action@v1"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn collect_overlapping_inputs() -> Result<()> {
+    let root = input_under_test("collect-exclude");
+    let workflow = input_under_test("collect-exclude/.github/workflows/ci.yml");
+    let github = input_under_test("collect-exclude/.github");
+
+    // Returns the number of findings and stderr.
+    let run = |args: &[&str]| -> Result<(usize, String)> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-progress", "--format=json"])
+            .args(args)
+            .env("RUST_LOG", "zizmor=debug")
+            .output()?;
+        let stderr = String::from_utf8(output.stderr)?;
+
+        let findings = serde_json::from_slice::<serde_json::Value>(&output.stdout)?;
+        Ok((findings.as_array().unwrap().len(), stderr))
+    };
+
+    // Each file is only audited once, no matter how many inputs contain it.
+    let (nfindings, stderr) = run(&["--no-config", &root, &workflow, &github])?;
+    assert_eq!(nfindings, 3, "{stderr}");
+    assert!(stderr.contains("already collected"), "{stderr}");
+
+    // Exclusions can also come from the configuration, and are
+    // listed along with the pattern that matched.
+    let config = input_under_test("collect-exclude.yml");
+    let (nfindings, stderr) = run(&["--config", &config, &root])?;
+    assert_eq!(nfindings, 2, "{stderr}");
+    assert!(
+        stderr.contains("bad-action/action.yml: matches **/fixtures/**"),
+        "{stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn unpinned_uses_policy_locations() -> Result<()> {
+    let config = input_under_test("unpinned-uses/configs/composite.yml");
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--no-progress", "--format=sarif"])
+        .args(["--config", &config])
+        .arg(input_under_test("unpinned-uses/menagerie-of-uses.yml"))
+        .output()?;
+
+    let sarif = serde_json::from_slice::<serde_json::Value>(&output.stdout)?;
+    let related = sarif["runs"][0]["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|result| result["relatedLocations"].as_array().unwrap())
+        .collect::<Vec<_>>();
+
+    // Configured policies point at their definitions...
+    let configured = related
+        .iter()
+        .find(|location| location["message"]["text"] == "actions/setup-python policy defined")
+        .unwrap();
+    let physical = &configured["physicalLocation"];
+    assert_eq!(physical["artifactLocation"]["uri"], config.as_str());
+    assert_eq!(physical["region"]["startLine"], 11);
+
+    // ...while built-in ones have no physical location at all.
+    let default = related
+        .iter()
+        .find(|location| location["message"]["text"] == "blanket policy is a built-in default")
+        .unwrap();
+    assert!(default.get("physicalLocation").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn expired_ignores_locations() -> Result<()> {
+    let config = input_under_test("config-ignores/config.yml");
+    let workflow = input_under_test("config-ignores/workflow.yml");
+    let run = |format: &str| {
+        Command::cargo_bin("zizmor")
+            .unwrap()
+            .args(["--offline", "--no-progress", format])
+            .args(["--config", &config])
+            .arg(&workflow)
+            .output()
+    };
+
+    let sarif = serde_json::from_slice::<serde_json::Value>(&run("--format=sarif")?.stdout)?;
+    let result = sarif["runs"][0]["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|result| result["ruleId"] == "zizmor/expired-ignores")
+        .unwrap();
+
+    // The expired entry is the result's location...
+    let physical = &result["locations"][0]["physicalLocation"];
+    assert_eq!(physical["artifactLocation"]["uri"], config.as_str());
+    assert_eq!(physical["region"]["startLine"], 6);
+
+    // ...and the finding that it no longer ignores is related to it.
+    let related = result["relatedLocations"].as_array().unwrap();
+    assert!(related.iter().any(|location| {
+        location["message"]["text"] == "this unpinned-uses finding is no longer ignored"
+    }));
+
+    let github = String::from_utf8(run("--format=github")?.stdout)?;
+    let annotation = github
+        .lines()
+        .find(|line| line.contains("title=expired-ignores"))
+        .unwrap();
+    assert!(annotation.contains("config.yml,line=6,endLine=6,"));
+
+    Ok(())
+}
+
+#[test]
+fn audit_summary() -> Result<()> {
+    let output = Command::cargo_bin("zizmor")?
+        .args([
+            "--offline",
+            "--no-config",
+            "--no-progress",
+            "--format=jsonl",
+            "--audit-summary",
+            "--disable=template-injection",
+        ])
+        .arg(input_under_test("artipacked.yml"))
+        .output()?;
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let summary = serde_json::from_str::<serde_json::Value>(stdout.lines().last().unwrap())?;
+    let audits = summary["audits"].as_array().unwrap();
+    let status = |ident: &str| {
+        audits
+            .iter()
+            .find(|audit| audit["ident"] == ident)
+            .unwrap()
+            .clone()
+    };
+
+    let artipacked = status("artipacked");
+    assert_eq!(artipacked["status"], "ran");
+    assert_eq!(artipacked["findings"], 1);
+    assert!(artipacked["wall_time_ms"].as_f64().unwrap() > 0.0);
+
+    assert_eq!(status("impostor-commit")["status"], "skipped");
+    assert_eq!(
+        status("impostor-commit")["reason"],
+        "offline mode requested"
+    );
+    assert_eq!(
+        status("template-injection")["reason"],
+        "disabled by --disable"
+    );
+
+    // Without --audit-summary, the summary line has no audits.
+    let output = Command::cargo_bin("zizmor")?
+        .args([
+            "--offline",
+            "--no-config",
+            "--no-progress",
+            "--format=jsonl",
+        ])
+        .arg(input_under_test("artipacked.yml"))
+        .output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    let summary = serde_json::from_str::<serde_json::Value>(stdout.lines().last().unwrap())?;
+    assert!(summary.get("audits").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn show_filtered() -> Result<()> {
+    let run = |extra: &[&str]| -> Result<Vec<serde_json::Value>> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-config", "--format=json"])
+            .args(extra)
+            .arg(input_under_test("unpinned-uses.yml"))
+            .output()?;
+
+        Ok(serde_json::from_slice::<serde_json::Value>(&output.stdout)?
+            .as_array()
+            .unwrap()
+            .clone())
+    };
+
+    let ids = |findings: &[serde_json::Value]| {
+        findings
+            .iter()
+            .map(|f| f["fingerprint"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let all = run(&[])?;
+    let high = run(&["--min-severity=high"])?;
+    assert!(!high.is_empty() && high.len() < all.len());
+    assert!(
+        high.iter()
+            .all(|f| f["determinations"]["severity"] == "High" && f.get("filtered").is_none())
+    );
+
+    // The remaining findings keep their original order.
+    let expected = all
+        .iter()
+        .filter(|f| f["determinations"]["severity"] == "High")
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(ids(&high), ids(&expected));
+
+    // Filtered findings are included (and marked) with `--show-filtered`.
+    let shown = run(&["--min-severity=high", "--show-filtered"])?;
+    assert_eq!(shown.len(), all.len());
+    assert_eq!(
+        shown.iter().filter(|f| f["filtered"] == true).count(),
+        all.len() - high.len()
+    );
+
+    // The remaining findings still fail the run, per `--fail-on`.
+    let output = Command::cargo_bin("zizmor")?
+        .args([
+            "--offline",
+            "--no-config",
+            "--min-severity=high",
+            "--fail-on=high",
+        ])
+        .arg(input_under_test("unpinned-uses.yml"))
+        .output()?;
+    assert_eq!(output.status.code(), Some(14));
+
+    Ok(())
+}
+
+#[test]
+fn fix_requires_github_api() -> Result<()> {
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--no-config", "--fix", "--dry-run"])
+        .arg(input_under_test("fix/unpinned-uses.yml"))
+        .output()?;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)?.contains("--fix needs the GitHub API"));
+
+    // `--dry-run` is only meaningful with `--fix`.
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--no-config", "--dry-run"])
+        .arg(input_under_test("fix/unpinned-uses.yml"))
+        .output()?;
+    assert_eq!(output.status.code(), Some(2));
+
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "gh-token-tests"), ignore)]
+#[test]
+fn fix_dry_run() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let workflow = dir.path().join("workflow.yml");
+    let original = std::fs::read_to_string(input_under_test("fix/unpinned-uses.yml"))?;
+    std::fs::write(&workflow, &original)?;
+
+    let run = |extra: &[&str]| -> Result<(Option<i32>, String)> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--no-config", "--no-online-audits", "--fix"])
+            .args(extra)
+            .arg(&workflow)
+            .output()?;
+
+        Ok((output.status.code(), String::from_utf8(output.stdout)?))
+    };
+
+    // The preview is a diff of exactly what `--fix` writes, and
+    // leaves the file alone.
+    let (code, diff) = run(&["--dry-run"])?;
+    assert_eq!(code, Some(21));
+    assert!(diff.starts_with("--- a/"), "{diff}");
+    assert!(
+        diff.contains(
+            "+      - uses: pypa/gh-action-pypi-publish@76f52bc884231f62b9a034ebfe128415bbaabdfc # v1.12.4"
+        ),
+        "{diff}"
+    );
+    assert!(!diff.contains("actions/checkout"), "{diff}");
+    assert_eq!(std::fs::read_to_string(&workflow)?, original);
+
+    let (code, _) = run(&[])?;
+    assert_eq!(code, Some(0));
+    let fixed = std::fs::read_to_string(&workflow)?;
+    assert_ne!(fixed, original);
+
+    // Once fixed, there's nothing left to preview.
+    let (code, diff) = run(&["--dry-run"])?;
+    assert_eq!(code, Some(0));
+    assert!(diff.is_empty(), "{diff}");
+
+    Ok(())
+}
+
+#[test]
+fn audit_transitive_requires_online() -> Result<()> {
+    // `--audit-transitive` fetches remote actions, so it can't be used offline.
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--no-config", "--audit-transitive"])
+        .arg(input_under_test("audit-transitive/uses-composite.yml"))
+        .output()?;
+    assert_eq!(output.status.code(), Some(2));
+
+    // The depth is only meaningful with `--audit-transitive`.
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--no-config", "--audit-transitive-depth=2"])
+        .arg(input_under_test("audit-transitive/uses-composite.yml"))
+        .output()?;
+    assert_eq!(output.status.code(), Some(2));
+
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "gh-token-tests"), ignore)]
+#[test]
+fn audit_transitive() -> Result<()> {
+    let output = zizmor()
+        .offline(false)
+        .output(OutputMode::Stderr)
+        .args(["--no-online-audits", "--audit-transitive"])
+        .input(input_under_test("audit-transitive/uses-composite.yml"))
+        .run()?;
+
+    // The composite action is audited under its synthetic path.
+    assert!(
+        output.contains("collected 1 composite action transitively"),
+        "{output}"
+    );
+    assert!(
+        output.contains(
+            "completed remote:woodruffw/gha-hazmat/ref-confusion@42064a9533f401a493c3599e56f144918f8eacfd/action.yml"
+        ),
+        "{output}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn lock_requires_github_api() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let lockfile = dir.path().join("gha.lock");
+
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--no-config", "lock", "--lockfile"])
+        .arg(&lockfile)
+        .arg(input_under_test("lock/lock.yml"))
+        .output()?;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)?.contains("can't resolve refs for the lockfile"));
+    assert!(!lockfile.exists());
+
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "gh-token-tests"), ignore)]
+#[test]
+fn lock() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let lockfile = dir.path().join("gha.lock");
+
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--no-config", "lock", "--lockfile"])
+        .arg(&lockfile)
+        .arg(input_under_test("lock/lock.yml"))
+        .output()?;
+    assert_eq!(output.status.code(), Some(0));
+
+    let lockfile = std::fs::read_to_string(&lockfile)?;
+    assert!(
+        lockfile.starts_with("# Generated by `zizmor lock`."),
+        "{lockfile}"
+    );
+    assert!(
+        lockfile.contains(
+            "actions:
+  actions/checkout:
+    11bd71901bbe5b1630ceea73d27597364c9af683: 11bd71901bbe5b1630ceea73d27597364c9af683
+  pypa/gh-action-pypi-publish:
+    v1.12.4: 76f52bc884231f62b9a034ebfe128415bbaabdfc
+images:
+  docker://library/alpine:
+    '3.20': null
+"
+        ),
+        "{lockfile}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn verify_lock_invalid_lockfile() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let lockfile = dir.path().join("gha.lock");
+
+    let verify = || -> Result<(Option<i32>, String)> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-config", "verify-lock", "--lockfile"])
+            .arg(&lockfile)
+            .arg(input_under_test("lock/lock.yml"))
+            .output()?;
+
+        Ok((output.status.code(), String::from_utf8(output.stderr)?))
+    };
+
+    let (code, stderr) = verify()?;
+    assert_eq!(code, Some(1));
+    assert!(stderr.contains("couldn't read lockfile"), "{stderr}");
+
+    std::fs::write(&lockfile, "version: 99\ngenerated: 2025-05-01T12:30:00Z\n")?;
+    let (code, stderr) = verify()?;
+    assert_eq!(code, Some(1));
+    assert!(
+        stderr.contains("unsupported lockfile version 99"),
+        "{stderr}"
+    );
+
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "gh-token-tests"), ignore)]
+#[test]
+fn verify_lock() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let lockfile = dir.path().join("gha.lock");
+
+    let run = |subcommand: &[&str]| -> Result<(Option<i32>, String)> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--no-config"])
+            .args(subcommand)
+            .arg("--lockfile")
+            .arg(&lockfile)
+            .arg(input_under_test("lock/lock.yml"))
+            .output()?;
+
+        Ok((output.status.code(), String::from_utf8(output.stdout)?))
+    };
+
+    assert_eq!(run(&["lock"])?.0, Some(0));
+    let original = std::fs::read_to_string(&lockfile)?;
+    assert_eq!(run(&["verify-lock"])?, (Some(0), "".into()));
+
+    // A moved ref is reported with both commits and its users.
+    let moved = original.replace(
+        "v1.12.4: 76f52bc884231f62b9a034ebfe128415bbaabdfc",
+        "v1.12.4: 0000000000000000000000000000000000000000",
+    );
+    assert_ne!(moved, original);
+    std::fs::write(&lockfile, &moved)?;
+
+    let (code, stdout) = run(&["verify-lock"])?;
+    assert_eq!(code, Some(22));
+    assert!(
+        stdout.contains(
+            "pypa/gh-action-pypi-publish@v1.12.4: moved from \
+             0000000000000000000000000000000000000000 to 76f52bc884231f62b9a034ebfe128415bbaabdfc"
+        ),
+        "{stdout}"
+    );
+    assert!(stdout.contains("used by "), "{stdout}");
+
+    // `--update` accepts the new commit.
+    assert_eq!(run(&["verify-lock", "--update"])?.0, Some(0));
+    assert_eq!(run(&["verify-lock"])?, (Some(0), "".into()));
+
+    Ok(())
+}
+
+#[test]
+fn changed_since() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let workflows = dir.path().join(".github/workflows");
+    std::fs::create_dir_all(&workflows)?;
+
+    let git = |args: &[&str]| -> Result<()> {
+        let status = std::process::Command::new("git")
+            .args([
+                "-c",
+                "user.name=zizmor",
+                "-c",
+                "user.email=zizmor@example.com",
+            ])
+            .args(args)
+            .current_dir(dir.path())
+            .status()?;
+        assert!(status.success(), "git {args:?}");
+        Ok(())
+    };
+    let workflow = |name: &str, version: &str| -> String {
+        format!(
+            "name: {name}\non: push\npermissions: {{}}\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - uses: example/action@{version}\n"
+        )
+    };
+
+    for name in ["renamed", "modified", "unchanged", "deleted"] {
+        std::fs::write(workflows.join(format!("{name}.yml")), workflow(name, "v1"))?;
+    }
+    git(&["init", "--quiet"])?;
+    git(&["add", "."])?;
+    git(&["commit", "--quiet", "-m", "initial"])?;
+
+    std::fs::write(workflows.join("modified.yml"), workflow("modified", "v2"))?;
+    std::fs::write(workflows.join("added.yml"), workflow("added", "v1"))?;
+    git(&["add", ".github/workflows/added.yml"])?;
+    git(&[
+        "mv",
+        ".github/workflows/renamed.yml",
+        ".github/workflows/moved.yml",
+    ])?;
+    git(&["rm", "--quiet", ".github/workflows/deleted.yml"])?;
+
+    let run = |since: &str| -> Result<(String, String)> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--no-config", "--offline", "--no-progress", "--color=never"])
+            .args(["--changed-since", since, "."])
+            .current_dir(dir.path())
+            .output()?;
+
+        Ok((
+            String::from_utf8(output.stdout)?,
+            String::from_utf8(output.stderr)?,
+        ))
+    };
+
+    // Only the changed files are audited, at their current paths.
+    let (stdout, _) = run("HEAD")?;
+    for name in ["added.yml", "modified.yml", "moved.yml"] {
+        assert!(stdout.contains(name), "{name}: {stdout}");
+    }
+    for name in ["unchanged.yml", "renamed.yml", "deleted.yml"] {
+        assert!(!stdout.contains(name), "{name}: {stdout}");
+    }
+    assert!(stdout.contains("1 unchanged file skipped"), "{stdout}");
+
+    // An unknown ref falls back to auditing everything.
+    let (stdout, stderr) = run("no-such-ref")?;
+    assert!(stdout.contains("unchanged.yml"), "{stdout}");
+    assert!(!stdout.contains("skipped"), "{stdout}");
+    assert!(
+        stderr.contains("couldn't determine which files changed"),
+        "{stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cache_results() -> Result<()> {
+    let cache_dir = tempfile::tempdir()?;
+    let cache_dir = cache_dir.path().to_str().unwrap();
+
+    let run = |args: &[&str]| -> Result<Vec<u8>> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--no-config", "--offline", "--no-progress", "--format=json"])
+            .args(["--cache-dir", cache_dir])
+            .args(args)
+            .arg(input_under_test("e2e-menagerie"))
+            .output()?;
+
+        Ok(output.stdout)
+    };
+
+    let uncached = run(&[])?;
+    assert!(!std::fs::exists(format!("{cache_dir}/results"))?);
+
+    // The first cached run populates the cache, and the second replays it.
+    let populated = run(&["--cache-results"])?;
+    assert!(std::fs::read_dir(format!("{cache_dir}/results"))?.count() > 0);
+    let replayed = run(&["--cache-results"])?;
+
+    assert!(!uncached.is_empty());
+    assert_eq!(uncached, populated);
+    assert_eq!(uncached, replayed);
+
+    Command::cargo_bin("zizmor")?
+        .args(["--cache-dir", cache_dir, "cache", "clear"])
+        .assert()
+        .success();
+    assert!(!std::fs::exists(format!("{cache_dir}/results"))?);
+
+    Ok(())
+}
+
+#[test]
+fn cache_results_flags() -> Result<()> {
+    let cache_dir = tempfile::tempdir()?;
+    let cache_dir = cache_dir.path().to_str().unwrap();
+
+    let dir = tempfile::tempdir()?;
+    let workflow = dir.path().join("ci.yml");
+    std::fs::write(
+        &workflow,
+        "on: push\npermissions: {}\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - uses: foo/bar@v1\n",
+    )?;
+
+    let run = |args: &[&str]| -> Result<String> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--no-config", "--offline", "--no-progress", "--format=json"])
+            .args(["--cache-dir", cache_dir])
+            .args(args)
+            .arg(&workflow)
+            .output()?;
+
+        Ok(String::from_utf8(output.stdout)?)
+    };
+
+    let strict = run(&["--cache-results"])?;
+    assert!(
+        strict.contains("third-party action is not pinned"),
+        "{strict}"
+    );
+
+    // Flags that change what an offline audit reports invalidate the cache.
+    for args in [
+        &["--tpa-allowed-org", "foo"][..],
+        &["--gh-hostname", "ghe.example.com"][..],
+    ] {
+        let uncached = run(args)?;
+        let cached = run(&[&["--cache-results"], args].concat())?;
+        assert_eq!(uncached, cached, "{args:?}");
+    }
+
+    let allowed = run(&["--cache-results", "--tpa-allowed-org", "foo"])?;
+    assert!(
+        !allowed.contains("third-party action is not pinned"),
+        "{allowed}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cache_results_local_actions() -> Result<()> {
+    let cache_dir = tempfile::tempdir()?;
+    let cache_dir = cache_dir.path().to_str().unwrap();
+
+    let dir = tempfile::tempdir()?;
+    let workflows = dir.path().join(".github/workflows");
+    std::fs::create_dir_all(&workflows)?;
+    let workflow = workflows.join("ci.yml");
+    std::fs::write(
+        &workflow,
+        "on: push\npermissions: {}\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - uses: ./.github/actions/build\n",
+    )?;
+
+    let run = |args: &[&str]| -> Result<String> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--no-config", "--offline", "--no-progress", "--format=json"])
+            .args(["--cache-dir", cache_dir])
+            .args(args)
+            .arg(&workflow)
+            .output()?;
+
+        Ok(String::from_utf8(output.stdout)?)
+    };
+
+    let missing = run(&["--cache-results"])?;
+    assert!(
+        missing.contains("no action.yml or action.yaml"),
+        "{missing}"
+    );
+
+    // Creating (or changing) the local action invalidates the cache.
+    let action = dir.path().join(".github/actions/build");
+    std::fs::create_dir_all(&action)?;
+    std::fs::write(
+        action.join("action.yml"),
+        "name: build\ndescription: build\nruns:\n  using: composite\n  steps:\n    - run: echo hi\n      shell: bash\n",
+    )?;
+
+    let uncached = run(&[])?;
+    let cached = run(&["--cache-results"])?;
+    assert_eq!(uncached, cached);
+    assert!(!cached.contains("no action.yml or action.yaml"), "{cached}");
+
+    Ok(())
+}
+
+#[test]
+fn summary() -> Result<()> {
+    let output = Command::cargo_bin("zizmor")?
+        .args([
+            "--offline",
+            "--no-config",
+            "--no-progress",
+            "--format=json",
+            "--summary",
+            "--disable=template-injection",
+        ])
+        .arg(input_under_test("artipacked.yml"))
+        .arg(input_under_test("unpinned-uses.yml"))
+        .output()?;
+
+    // With --summary, the JSON output is an object rather than an array.
+    let json = serde_json::from_slice::<serde_json::Value>(&output.stdout)?;
+    let findings = json["findings"].as_array().unwrap();
+    let summary = &json["summary"];
+
+    let rule = |ident: &str| {
+        summary["rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|rule| rule["ident"] == ident)
+            .cloned()
+    };
+
+    let artipacked = rule("artipacked").unwrap();
+    assert_eq!(artipacked["findings"], 1);
+    assert_eq!(artipacked["severities"]["medium"], 1);
+    assert_eq!(artipacked["confidences"]["low"], 1);
+    assert_eq!(artipacked["files"], 1);
+    assert!(artipacked["wall_time_ms"].as_f64().unwrap() > 0.0);
+
+    // Skipped and disabled audits are listed separately.
+    assert!(rule("template-injection").is_none());
+    let not_run = summary["not_run"].as_array().unwrap();
+    assert!(
+        not_run
+            .iter()
+            .any(|audit| audit["ident"] == "template-injection"
+                && audit["reason"] == "disabled by --disable")
+    );
+    assert!(
+        not_run
+            .iter()
+            .any(|audit| audit["ident"] == "impostor-commit" && audit["status"] == "skipped")
+    );
+
+    let totals = &summary["totals"];
+    assert_eq!(totals["inputs"], 2);
+    assert_eq!(totals["findings"].as_u64().unwrap(), findings.len() as u64);
+    assert_eq!(totals["files"], 2);
+    assert!(totals["suppressed"].as_u64().is_some());
+
+    let slowest = summary["slowest_inputs"].as_array().unwrap();
+    assert_eq!(slowest.len(), 2);
+    assert!(slowest[0]["wall_time_ms"].as_f64() >= slowest[1]["wall_time_ms"].as_f64());
+
+    // The plain output ends with the summary table.
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--no-config", "--no-progress", "--summary"])
+        .arg(input_under_test("artipacked.yml"))
+        .output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("run summary:"));
+    assert!(stdout.contains("by confidence:"));
+    assert!(stdout.contains("slowest inputs:"));
+
+    Ok(())
+}
+
+#[test]
+fn exit_codes() -> Result<()> {
+    let run = |args: &[&str]| -> Result<(Option<i32>, String)> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-progress"])
+            .args(args)
+            .output()?;
+        Ok((output.status.code(), String::from_utf8(output.stderr)?))
+    };
+
+    let dir = tempfile::tempdir()?;
+    let clean = dir.path().join("clean.yml");
+    std::fs::write(
+        &clean,
+        "name: clean\non: push\npermissions: {}\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - run: echo hello\n",
+    )?;
+    let clean = clean.to_str().unwrap();
+
+    // No findings.
+    let (code, stderr) = run(&["--no-config", clean])?;
+    assert_eq!(code, Some(0), "{stderr}");
+
+    // Findings, with the highest at high severity.
+    let findings = input_under_test("several-vulnerabilities.yml");
+    let (code, _) = run(&["--no-config", &findings])?;
+    assert_eq!(code, Some(14));
+
+    // Invalid usage.
+    let (code, _) = run(&["--no-config", "--not-a-flag", clean])?;
+    assert_eq!(code, Some(2));
+
+    // Configuration that can't be parsed, or that an audit can't load.
+    let bad = dir.path().join("bad.yml");
+    std::fs::write(&bad, "rules: [\n")?;
+    let (code, stderr) = run(&["--config", bad.to_str().unwrap(), clean])?;
+    assert_eq!(code, Some(3));
+    assert!(stderr.contains("failed to load config"), "{stderr}");
+
+    let config = input_under_test("check-config/invalid-pattern.yml");
+    let (code, stderr) = run(&["--config", &config, clean])?;
+    assert_eq!(code, Some(3));
+    assert!(
+        stderr.contains("failed to load audit: unpinned-uses"),
+        "{stderr}"
+    );
+
+    let (code, _) = run(&["--check-config", "--config", &config])?;
+    assert_eq!(code, Some(3));
+
+    let (code, _) = run(&[
+        "--no-config",
+        "--enable=artipacked",
+        "--disable=artipacked",
+        clean,
+    ])?;
+    assert_eq!(code, Some(3));
+
+    // Baselines that can't be loaded.
+    let missing = dir.path().join("missing-baseline.json");
+    let (code, stderr) = run(&[
+        "--no-config",
+        "--baseline",
+        missing.to_str().unwrap(),
+        clean,
+    ])?;
+    assert_eq!(code, Some(3), "{stderr}");
+
+    // Inputs that can't be collected.
+    let (code, stderr) = run(&["--no-config", "does-not-exist.yml"])?;
+    assert_eq!(code, Some(4));
+    assert!(stderr.contains("invalid input"), "{stderr}");
+
+    let invalid = dir.path().join("invalid.yml");
+    std::fs::write(&invalid, "this isn't a workflow\n")?;
+    let (code, _) = run(&["--no-config", invalid.to_str().unwrap()])?;
+    assert_eq!(code, Some(4));
+
+    // The exit codes are documented in `--help`.
+    let output = Command::cargo_bin("zizmor")?.arg("--help").output()?;
+    assert_eq!(output.status.code(), Some(0));
+    let help = String::from_utf8(output.stdout)?;
+    assert!(help.contains("Exit codes:"));
+    for code in ["3 ", "4 ", "10-14", "20 ", "21 ", "22 "] {
+        assert!(help.contains(&format!("  {code}")), "{help}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn sarif_severity_mapping() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let config = dir.path().join("zizmor.yml");
+    std::fs::write(
+        &config,
+        "sarif:\n  level:\n    high: warning\n  security-severity:\n    high: 8.5\n\
+         rules:\n  template-injection:\n    sarif:\n      security-severity:\n        high: 9.0\n",
+    )?;
+
+    let run = |config: Option<&str>| -> Result<serde_json::Value> {
+        let mut cmd = Command::cargo_bin("zizmor")?;
+        cmd.args(["--offline", "--no-progress", "--format=sarif"]);
+        match config {
+            Some(config) => cmd.args(["--config", config]),
+            None => cmd.arg("--no-config"),
+        };
+        let output = cmd
+            .arg(input_under_test("several-vulnerabilities.yml"))
+            .output()?;
+        Ok(serde_json::from_slice(&output.stdout)?)
+    };
+
+    let rule = |sarif: &serde_json::Value, id: &str| {
+        sarif["runs"][0]["tool"]["driver"]["rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|rule| rule["id"] == id)
+            .cloned()
+            .unwrap()
+    };
+
+    // By default, there's no `security-severity`.
+    let sarif = run(None)?;
+    let injection = rule(&sarif, "zizmor/template-injection");
+    assert_eq!(injection["defaultConfiguration"]["level"], "error");
+    assert!(injection["properties"].get("security-severity").is_none());
+    for result in sarif["runs"][0]["results"].as_array().unwrap() {
+        assert_eq!(result["level"], "error");
+        // Only the confidence, which every result has.
+        let properties = result["properties"].as_object().unwrap();
+        assert_eq!(properties.keys().collect::<Vec<_>>(), ["confidence"]);
+        assert!(properties["confidence"].is_string());
+    }
+
+    let sarif = run(Some(config.to_str().unwrap()))?;
+    let injection = rule(&sarif, "zizmor/template-injection");
+    assert_eq!(injection["defaultConfiguration"]["level"], "warning");
+    assert_eq!(injection["properties"]["security-severity"], "9.0");
+    assert_eq!(injection["properties"]["tags"][0], "security");
+    let permissions = rule(&sarif, "zizmor/excessive-permissions");
+    assert_eq!(permissions["properties"]["security-severity"], "8.5");
+
+    for result in sarif["runs"][0]["results"].as_array().unwrap() {
+        let expected = if result["ruleId"] == "zizmor/template-injection" {
+            "9.0"
+        } else {
+            "8.5"
+        };
+        assert_eq!(result["level"], "warning");
+        assert_eq!(result["properties"]["security-severity"], expected);
+    }
+
+    // Unknown severities in the mapping are configuration errors.
+    std::fs::write(
+        &config,
+        "sarif:\n  level:\n    critical: error\nrules: {}\n",
+    )?;
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--check-config", "--config", config.to_str().unwrap()])
+        .output()?;
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8(output.stderr)?.contains("unknown field `critical`"));
+
+    Ok(())
+}
+
+#[test]
+fn custom_rules() -> Result<()> {
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--list-rules", "--format=json", "--config"])
+        .arg(input_under_test("custom-rules/config.yml"))
+        .output()?;
+    assert!(output.status.success());
+
+    let rules: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout)?;
+    let custom = rules
+        .iter()
+        .find(|rule| rule["ident"] == "custom-rules/no-npm-publish")
+        .expect("custom rule should be listed");
+    assert_eq!(
+        custom["desc"],
+        "packages are published by the release workflow"
+    );
+    assert_eq!(custom["network"], "offline");
+
+    // The findings' JSON identifies each finding by its rule's ID.
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--no-progress", "--format=json", "--config"])
+        .arg(input_under_test("custom-rules/config.yml"))
+        .arg(input_under_test("custom-rules/action/action.yml"))
+        .output()?;
+    let findings: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout)?;
+    assert!(
+        findings
+            .iter()
+            .all(|finding| finding["ident"] == "custom-rules")
+    );
+    let mut subidents = findings
+        .iter()
+        .map(|finding| finding["subident"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    subidents.sort();
+    assert_eq!(subidents, ["no-legacy-deploy-key", "no-npm-publish"]);
+
+    // Invalid patterns are configuration errors.
+    let dir = tempfile::tempdir()?;
+    let config = dir.path().join("zizmor.yml");
+    std::fs::write(
+        &config,
+        "rules: {}\ncustom-rules:\n  - id: unclosed\n    description: oops\n    \
+         severity: low\n    target: run\n    regex: 'npm (publish'\n",
+    )?;
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--check-config", "--config", config.to_str().unwrap()])
+        .output()?;
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("invalid regex for custom rule unclosed: `npm (publish`"));
+
+    Ok(())
+}
+
+#[test]
+fn collect_extra_locations() -> Result<()> {
+    let findings = |mode: &str| -> Result<Vec<(String, serde_json::Value)>> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-progress", "--format=json"])
+            .arg(format!("--collect={mode}"))
+            .arg(input_under_test("collect-extra"))
+            .output()?;
+        let findings: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout)?;
+
+        let mut findings = findings
+            .into_iter()
+            .map(|finding| {
+                let path = finding["locations"][0]["symbolic"]["key"]["Local"]["given_path"]
+                    .as_str()
+                    .unwrap()
+                    .rsplit_once("collect-extra/")
+                    .unwrap()
+                    .1
+                    .to_string();
+                (path, finding["collection"].clone())
+            })
+            .collect::<Vec<_>>();
+        findings.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(findings)
+    };
+
+    // Only the standard workflow and workflow template are collected
+    // by default.
+    assert_eq!(
+        findings("default")?,
+        [
+            (".github/workflows/ci.yml".into(), serde_json::Value::Null),
+            (
+                "workflow-templates/starter.yml".into(),
+                serde_json::Value::Null
+            ),
+        ]
+    );
+
+    // `all` also collects the non-standard locations, and marks them.
+    assert_eq!(
+        findings("all")?,
+        [
+            (".github/workflows/ci.yml".into(), serde_json::Value::Null),
+            (".github/workflows/sub/nested.yml".into(), "extra".into()),
+            (
+                "workflow-templates/starter.yml".into(),
+                serde_json::Value::Null
+            ),
+        ]
+    );
+
+    // A glob replaces discovery entirely.
+    assert_eq!(
+        findings(".github/workflows-disabled/*.yml")?,
+        [(".github/workflows-disabled/old.yml".into(), "extra".into())]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tag_moved_requires_github_api() -> Result<()> {
+    // Without the GitHub API, `--lockfile` is inert: the lockfile isn't
+    // even read, so a missing one isn't an error.
+    let output = Command::cargo_bin("zizmor")?
+        .args([
+            "--offline",
+            "--no-config",
+            "--format=json",
+            "--lockfile=does-not-exist.lock",
+        ])
+        .arg(input_under_test("lock/lock.yml"))
+        .output()?;
+
+    let findings: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout)?;
+    assert!(findings.iter().all(|f| f["ident"] != "tag-moved"));
+    assert!(String::from_utf8(output.stderr)?.contains("tag-moved"));
+
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "gh-token-tests"), ignore)]
+#[test]
+fn tag_moved() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let lockfile = dir.path().join("gha.lock");
+    std::fs::write(
+        &lockfile,
+        "version: 1
+generated: 2025-05-01T12:30:00Z
+actions:
+  pypa/gh-action-pypi-publish:
+    v1.12.4: 0000000000000000000000000000000000000000
+",
+    )?;
+
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--no-config", "--format=json", "--lockfile"])
+        .arg(&lockfile)
+        .arg(input_under_test("lock/lock.yml"))
+        .output()?;
+
+    let findings: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout)?;
+    let moved = findings
+        .iter()
+        .filter(|f| f["ident"] == "tag-moved")
+        .map(|f| {
+            f["locations"][0]["symbolic"]["annotation"]
+                .as_str()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    // Both uses of the moved tag are reported.
+    assert_eq!(
+        moved,
+        ["v1.12.4 now points to 76f52bc884231f62b9a034ebfe128415bbaabdfc, \
+             but was locked to 0000000000000000000000000000000000000000"; 2]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn pin() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let workflow = dir.path().join("workflow.yml");
+    let original = std::fs::read_to_string(input_under_test("pin/pin.yml"))?;
+    std::fs::write(&workflow, &original)?;
+
+    let run = |extra: &[&str]| -> Result<(Option<i32>, String, String)> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-config", "pin", "--lockfile"])
+            .arg(input_under_test("pin/gha.lock"))
+            .args(extra)
+            .arg(&workflow)
+            .output()?;
+
+        Ok((
+            output.status.code(),
+            String::from_utf8(output.stdout)?,
+            String::from_utf8(output.stderr)?,
+        ))
+    };
+
+    // The preview is a diff of exactly what `zizmor pin` writes, and
+    // leaves the file alone. The pinned ref that disagrees with the
+    // lockfile takes precedence in the exit code.
+    let (code, diff, stderr) = run(&["--dry-run"])?;
+    assert_eq!(code, Some(22));
+    assert_eq!(std::fs::read_to_string(&workflow)?, original);
+    for pinned in [
+        "+      - uses: actions/checkout@11bd71901bbe5b1630ceea73d27597364c9af683 # v4",
+        "+      - uses: \"actions/setup-python@42375524e23c412d93fb67b49958b491fce71c38\" # keep this",
+        "+    uses: org/repo/.github/workflows/ci.yml@2d756ea4c53f7f6b397767d8723b3a10a9f35bf2 # main",
+    ] {
+        assert!(diff.contains(pinned), "{diff}");
+    }
+    assert!(
+        diff.lines()
+            .filter(|line| line.starts_with(['+', '-']))
+            .all(|line| !line.contains("foo/bar") && !line.contains("cache")),
+        "{diff}"
+    );
+    assert!(
+        stderr.contains("foo/bar@v1: not in the lockfile"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains(
+            "actions/cache@0c45773b623bea8c8e75f6c82b208c3cf94ea4f9: pinned to a different \
+             commit than v4 is locked to (5a3ec84eff668545956fd18022155c47e93e2684)"
+        ),
+        "{stderr}"
+    );
+    assert!(
+        !stderr.contains("actions/cache@5a3ec84eff668545956fd18022155c47e93e2684"),
+        "{stderr}"
+    );
+
+    // Only the pinned refs' values and comments change.
+    let (code, _, _) = run(&[])?;
+    assert_eq!(code, Some(22));
+    let pinned = std::fs::read_to_string(&workflow)?;
+    let changed = original
+        .lines()
+        .zip(pinned.lines())
+        .filter(|(before, after)| before != after)
+        .count();
+    assert_eq!(changed, 3);
+    assert_eq!(original.lines().count(), pinned.lines().count());
+
+    // Once pinned, there's nothing left to preview.
+    let (code, diff, _) = run(&["--dry-run"])?;
+    assert_eq!(code, Some(22));
+    assert!(diff.is_empty(), "{diff}");
+
+    Ok(())
+}
+
+#[test]
+fn gh_app_authentication() -> Result<()> {
+    let run = |args: &[&str]| -> Result<std::process::Output> {
+        Ok(Command::cargo_bin("zizmor")?
+            .env_remove("ZIZMOR_GH_TOKEN")
+            .env_remove("GH_TOKEN")
+            .env_remove("GITHUB_TOKEN")
+            .args(["--no-config", "--no-progress"])
+            .args(args)
+            .arg(input_under_test("several-vulnerabilities.yml"))
+            .output()?)
+    };
+
+    // The app ID and key only make sense together, and not with a token.
+    assert_eq!(run(&["--gh-app-id", "1234"])?.status.code(), Some(2));
+    assert_eq!(
+        run(&["--gh-app-key-path", "key.pem"])?.status.code(),
+        Some(2)
+    );
+    assert_eq!(
+        run(&[
+            "--gh-app-id",
+            "1234",
+            "--gh-app-key-path",
+            "key.pem",
+            "--gh-token",
+            "token"
+        ])?
+        .status
+        .code(),
+        Some(2)
+    );
+
+    // A key that can't be loaded is an error, rather than a silent
+    // fallback to unauthenticated audits.
+    let key = input_under_test("github-app/not-a-key.pem");
+    let output = run(&["--gh-app-id", "1234", "--gh-app-key-path", &key])?;
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(
+        stderr.contains("couldn't authenticate as GitHub App 1234"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("not a PEM-encoded RSA private key"),
+        "{stderr}"
+    );
+
+    // Offline mode never authenticates.
+    let output = run(&[
+        "--offline",
+        "--gh-app-id",
+        "1234",
+        "--gh-app-key-path",
+        &key,
+    ])?;
+    assert_ne!(output.status.code(), Some(1));
+
+    Ok(())
+}
+
+#[test]
+fn tpa_list_without_actions() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let workflow = dir.path().join("workflow.yml");
+    std::fs::copy(input_under_test("tpa/tpa-no-actions.yml"), &workflow)?;
+
+    let run = |extra: &[&str]| -> Result<std::process::Output> {
+        Ok(Command::cargo_bin("zizmor")?
+            .current_dir(dir.path())
+            .args([
+                "--offline",
+                "--no-config",
+                "--no-progress",
+                "--format=tpa-list",
+            ])
+            .args(extra)
+            .arg(&workflow)
+            .output()?)
+    };
+
+    // Nothing is written to the working directory when there's nothing
+    // to report, but the run still says so.
+    let output = run(&[])?;
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("no third-party actions found"), "{stderr}");
+    let entries = std::fs::read_dir(dir.path())?
+        .map(|entry| Ok(entry?.file_name()))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(entries, ["workflow.yml"]);
+
+    // An explicitly requested report is still written, and is valid.
+    let output = run(&["--tpa-report", "report.json"])?;
+    assert_eq!(output.status.code(), Some(0));
+    let report = serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(
+        dir.path().join("report.json"),
+    )?)?;
+    assert!(report["schema_version"].as_u64().is_some());
+    assert_eq!(report["actions"], serde_json::json!([]));
+    assert_eq!(report["summary"]["total_actions"], 0);
+    assert_eq!(report["summary"]["unpinned_third_party"], 0);
+    assert_eq!(report["summary"]["by_file"], serde_json::json!({}));
+
+    Ok(())
+}
+
+#[test]
+fn step_context_locations() -> Result<()> {
+    let output = Command::cargo_bin("zizmor")?
+        .args([
+            "--offline",
+            "--no-config",
+            "--format=json",
+            "--persona=auditor",
+        ])
+        .arg(input_under_test("unpinned-uses/step-context.yml"))
+        .output()?;
+
+    let findings = serde_json::from_slice::<serde_json::Value>(&output.stdout)?;
+    let finding = findings
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["ident"] == "unpinned-uses")
+        .unwrap();
+
+    // Context locations follow the step's own, in order, with roles.
+    let locations = finding["locations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| {
+            (
+                l["symbolic"]["kind"].as_str().unwrap(),
+                l["symbolic"]["role"].as_str(),
+                l["symbolic"]["annotation"].as_str().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        locations,
+        [
+            (
+                "Primary",
+                None,
+                "third-party action is not pinned to a commit SHA"
+            ),
+            ("Related", Some("job"), "in job build"),
+            (
+                "Related",
+                Some("trigger"),
+                "triggered by pull_request_target, push"
+            ),
+        ]
+    );
+    assert_eq!(finding["locations"][1]["concrete"]["feature"], "build");
+
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "gh-token-tests"), ignore)]
+#[test]
+fn stale_pin_comment() -> Result<()> {
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--no-config", "--format=json"])
+        .arg(input_under_test("stale-pin-comment.yml"))
+        .output()?;
+
+    let findings: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout)?;
+    let stale = findings
+        .iter()
+        .filter(|f| f["ident"] == "stale-pin-comment")
+        .map(|f| {
+            (
+                f["determinations"]["severity"].as_str().unwrap(),
+                f["locations"][0]["symbolic"]["annotation"]
+                    .as_str()
+                    .unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Only the comment that disagrees with its commit is reported, since
+    // the malformed one is pedantic.
+    assert_eq!(
+        stale,
+        [(
+            "Medium",
+            "comment says v4.2.2, but the pinned commit is v4.1.7"
+        )]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn workflow_templates() -> Result<()> {
+    let output = Command::cargo_bin("zizmor")?
+        .args(["--offline", "--no-config", "--no-progress", "--format=json"])
+        .arg(input_under_test("workflow-templates"))
+        .output()?;
+
+    // The `$default-branch` placeholders don't get in the way of auditing.
+    let findings: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout)?;
+    let unpinned = findings
+        .iter()
+        .filter(|f| f["ident"] == "unpinned-uses")
+        .collect::<Vec<_>>();
+    assert_eq!(unpinned.len(), 1);
+    assert!(findings.iter().all(|f| f["template"] == true));
+
+    // The TPA report includes the template's actions, and marks them.
+    let dir = tempfile::tempdir()?;
+    Command::cargo_bin("zizmor")?
+        .current_dir(dir.path())
+        .args([
+            "--offline",
+            "--no-config",
+            "--no-progress",
+            "--format=tpa-list",
+            "--tpa-report=report.json",
+        ])
+        .arg(input_under_test("workflow-templates"))
+        .output()?;
+    let report = serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(
+        dir.path().join("report.json"),
+    )?)?;
+    let actions = report["actions"].as_array().unwrap();
+    assert_eq!(
+        actions
+            .iter()
+            .map(|a| a["reference"].as_str().unwrap())
+            .collect::<Vec<_>>(),
+        ["actions/checkout@v4", "example/setup@$default-branch"]
+    );
+    assert!(actions.iter().all(|a| a["template"] == true));
+
+    Ok(())
+}
+
+#[test]
+fn collect_vendored() -> Result<()> {
+    /// The run's exit code, and each finding's path and `vendored` marker.
+    type Run = (Option<i32>, Vec<(String, serde_json::Value)>);
+
+    let run = |extra: &[&str]| -> Result<Run> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-progress", "--format=json"])
+            .args(extra)
+            .arg(input_under_test("collect-vendored"))
+            .output()?;
+        let findings: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout)?;
+
+        let mut findings = findings
+            .into_iter()
+            .map(|finding| {
+                let path = finding["locations"][0]["symbolic"]["key"]["Local"]["given_path"]
+                    .as_str()
+                    .unwrap()
+                    .rsplit_once("collect-vendored/")
+                    .unwrap()
+                    .1
+                    .to_string();
+                (path, finding["vendored"].clone())
+            })
+            .collect::<Vec<_>>();
+        findings.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok((output.status.code(), findings))
+    };
+
+    // Nested `.gitignore` files and negations are honored, and vendored
+    // inputs are still audited.
+    let (code, findings) = run(&[])?;
+    assert_eq!(code, Some(14));
+    assert_eq!(
+        findings,
+        [
+            ("actions/kept/action.yml".into(), serde_json::Value::Null),
+            (
+                "third_party/upstream/.github/workflows/ci.yml".into(),
+                true.into()
+            ),
+        ]
+    );
+
+    // `--no-ignore` collects the ignored inputs too.
+    let (_, findings) = run(&["--no-ignore"])?;
+    assert_eq!(
+        findings,
+        [
+            ("actions/dropped/action.yml".into(), serde_json::Value::Null),
+            ("actions/kept/action.yml".into(), serde_json::Value::Null),
+            (
+                "third_party/upstream/.github/workflows/ci.yml".into(),
+                true.into()
+            ),
+            (
+                "third_party/upstream/ignored-action/action.yml".into(),
+                true.into()
+            ),
+        ]
+    );
+
+    // Vendored findings alone don't fail the run.
+    let (code, findings) = run(&["--exclude=actions"])?;
+    assert_eq!(code, Some(0));
+    assert_eq!(findings.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn watch_rejects_unsupported_runs() -> Result<()> {
+    // Only the plain format is re-rendered on each run.
+    insta::assert_snapshot!(
+        zizmor()
+            .expects_failure(true)
+            .input(input_under_test("e2e-menagerie"))
+            .args(["--watch", "--format=json"])
+            .run()?
+    );
+
+    // Remote inputs can't be watched.
+    insta::assert_snapshot!(
+        zizmor()
+            .expects_failure(true)
+            .offline(true)
+            .input("woodruffw/zizmor")
+            .args(["--watch"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn lenient_allowlist() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let allowlist = dir.path().join("allowlist.txt");
+    std::fs::write(&allowlist, "trustedorg\nacme-corp/\nacme corp\n")?;
+    let allowlist = allowlist.to_str().unwrap();
+
+    let run = |args: &[&str]| -> Result<(Option<i32>, String)> {
+        let output = Command::cargo_bin("zizmor")?
+            .args(["--offline", "--no-progress", "--no-config"])
+            .args(["--tpa-allowlist-file", allowlist])
+            .args(args)
+            .arg(input_under_test("unpinned-uses.yml"))
+            .output()?;
+        Ok((output.status.code(), String::from_utf8(output.stderr)?))
+    };
+
+    // Invalid entries fail by default, with their line numbers...
+    let (code, stderr) = run(&[])?;
+    assert_eq!(code, Some(3));
+    assert!(
+        stderr.contains(&format!(
+            "{allowlist}:2: invalid allowlist entry `acme-corp/`"
+        )),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains(&format!(
+            "{allowlist}:3: invalid allowlist entry `acme corp`"
+        )),
+        "{stderr}"
+    );
+
+    // ...and are only warned about once, together, with --lenient-allowlist.
+    let (code, stderr) = run(&["--lenient-allowlist", "--tpa-allowed-org=-acme"])?;
+    assert_ne!(code, Some(3), "{stderr}");
+    assert_eq!(
+        stderr
+            .matches("ignoring 3 invalid allowlist entries")
+            .count(),
+        1,
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("--tpa-allowed-org: invalid allowlist entry `-acme`"),
+        "{stderr}"
+    );
+
+    Ok(())
+}
+
+/// Streamed output only holds onto each input's findings until they've
+/// been rendered, so a run's peak memory stays roughly flat as the number
+/// of inputs grows.
+#[cfg(target_os = "linux")]
+#[test]
+fn streaming_memory() -> Result<()> {
+    let workflow = "on: push\npermissions: {}\njobs:\n  test:\n    runs-on: ubuntu-latest\n    steps:\n      - uses: some-org/some-action@v1\n";
+
+    // The peak RSS (in kB) of a JSON Lines run over `count` synthetic
+    // workflows, each with a single finding.
+    let peak_rss = |count: usize| -> Result<u64> {
+        let dir = tempfile::tempdir()?;
+        let workflows = dir.path().join("repo/.github/workflows");
+        std::fs::create_dir_all(&workflows)?;
+        for n in 0..count {
+            std::fs::write(workflows.join(format!("ci-{n}.yml")), workflow)?;
+        }
+
+        let output = dir.path().join("output.jsonl");
+        let child = std::process::Command::new(assert_cmd::cargo::cargo_bin("zizmor"))
+            .args([
+                "--offline",
+                "--no-progress",
+                "--no-config",
+                "--format=jsonl",
+            ])
+            .arg(dir.path().join("repo"))
+            .stdout(std::fs::File::create(&output)?)
+            .stderr(std::process::Stdio::null())
+            .spawn()?;
+
+        // NOTE: wait4 reaps the child and reports its own resource usage,
+        // including its peak RSS (in kB on Linux), unlike RUSAGE_CHILDREN,
+        // which also covers the other tests' children.
+        let mut status = 0;
+        // SAFETY: rusage is plain old data, so all zeroes is a valid value.
+        let mut usage = unsafe { std::mem::zeroed::<libc::rusage>() };
+        // SAFETY: The child hasn't been waited on yet, and both pointers
+        // are valid for writes.
+        let pid = unsafe { libc::wait4(child.id() as libc::pid_t, &mut status, 0, &mut usage) };
+        assert_eq!(pid, child.id() as libc::pid_t, "couldn't wait on zizmor");
+        assert!(libc::WIFEXITED(status), "zizmor didn't exit normally");
+        let peak = usage.ru_maxrss as u64;
+
+        let output = std::fs::read_to_string(&output)?;
+        assert_eq!(output.lines().count(), count + 1);
+        assert!(
+            output
+                .lines()
+                .last()
+                .unwrap()
+                .contains(&format!(r#""findings":{count},"#)),
+            "{output}"
+        );
+        assert!(peak > 0, "couldn't get the peak RSS");
+
+        Ok(peak)
+    };
+
+    let (small, large) = (peak_rss(100)?, peak_rss(1000)?);
+    assert!(
+        large < small * 3 / 2,
+        "peak RSS grew from {small} kB (100 inputs) to {large} kB (1000 inputs)"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+//! Fixture-based snapshot tests for individual audits.
+//!
+//! Each audit's fixtures live in `tests/fixtures/<rule>/`, and each
+//! fixture gets its own snapshot of the audit's findings; see
+//! [`audit_fixture`] for how fixtures are configured. Adding a fixture to
+//! an audit's directory is enough to snapshot it, but each audit needs a
+//! test here that runs [`snapshot_fixtures`] on it.
+
+use anyhow::Result;
+
+use crate::common::{audit_fixture, fixtures};
+
+/// Snapshots every one of the given audit's fixtures.
+fn snapshot_fixtures(rule: &str) -> Result<()> {
+    let names = fixtures(rule)?;
+    anyhow::ensure!(!names.is_empty(), "{rule} has no fixtures");
+
+    for name in names {
+        let stem = name
+            .rsplit_once('.')
+            .map_or(name.as_str(), |(stem, _)| stem);
+        let findings = audit_fixture(rule, &name)?;
+        insta::with_settings!({
+            description => format!("{rule}/{name}"),
+            omit_expression => true,
+        }, {
+            insta::assert_snapshot!(format!("{rule}-{stem}"), findings);
+        });
+    }
+
+    Ok(())
+}
+
+#[test]
+fn unpinned_uses() -> Result<()> {
+    snapshot_fixtures("unpinned-uses")
+}
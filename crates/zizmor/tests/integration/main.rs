@@ -7,5 +7,7 @@ mod common;
 /// "Big picture" end-to-end tests, i.e. tests that typically exercise
 /// more than one audit or complex CLI functionality.
 mod e2e;
+/// Fixture-based snapshot tests for individual audits.
+mod fixtures;
 /// General snapshot tests, including repro cases for specific audits.
 mod snapshot;
@@ -3,7 +3,7 @@
 //! TODO: This file is too big; break it into multiple
 //! modules, one per audit/conceptual group.
 
-use crate::common::{input_under_test, zizmor};
+use crate::common::{OutputMode, input_under_test, zizmor};
 use anyhow::Result;
 
 #[test]
@@ -30,6 +30,25 @@ fn test_github_output() -> Result<()> {
             .run()?
     );
 
+    // More findings than GitHub will annotate in a single step.
+    insta::assert_snapshot!(
+        zizmor()
+            .offline(true)
+            .input(input_under_test("obfuscation.yml"))
+            .args(["--format=github"])
+            .run()?
+    );
+
+    // The most severe findings are annotated, even when they're found last.
+    insta::assert_snapshot!(
+        zizmor()
+            .offline(true)
+            .input(input_under_test("obfuscation.yml"))
+            .input(input_under_test("several-vulnerabilities.yml"))
+            .args(["--format=github"])
+            .run()?
+    );
+
     Ok(())
 }
 
@@ -121,6 +140,12 @@ fn self_hosted() -> Result<()> {
             .run()?
     );
 
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("self-hosted/self-hosted-pull-request.yml"))
+            .run()?
+    );
+
     Ok(())
 }
 
@@ -160,6 +185,13 @@ fn unpinned_uses() -> Result<()> {
             .run()?
     );
 
+    insta::assert_snapshot!(
+        "unpinned-uses-anchors",
+        zizmor()
+            .input(input_under_test("unpinned-uses/anchors.yml"))
+            .run()?
+    );
+
     // Config tests for `unpinned-uses`.
 
     // Default policies (no explicit config).
@@ -218,6 +250,122 @@ fn unpinned_uses() -> Result<()> {
             .run()?
     );
 
+    // Path-scoped overrides: release workflows hash-pin everything,
+    // while other workflows keep the top-level policies.
+    insta::assert_snapshot!(
+        "unpinned-uses-overrides-config",
+        zizmor()
+            .config(input_under_test("unpinned-uses/configs/overrides.yml"))
+            .input(input_under_test("unpinned-uses/overrides"))
+            .run()?
+    );
+
+    // Allowlist entries qualified with `ref-pin`.
+    insta::assert_snapshot!(
+        "unpinned-uses-ref-pin-allowlist-config",
+        zizmor()
+            .config(input_under_test(
+                "unpinned-uses/configs/ref-pin-allowlist.yml"
+            ))
+            .input(input_under_test("unpinned-uses/ref-pin-allowlist.yml"))
+            .run()?
+    );
+
+    // Allowlist entries that no audited input uses.
+    insta::assert_snapshot!(
+        "unpinned-uses-report-unused-allowlist-config",
+        zizmor()
+            .config(input_under_test(
+                "unpinned-uses/configs/report-unused-allowlist.yml"
+            ))
+            .input(input_under_test("unpinned-uses/ref-pin-allowlist.yml"))
+            .args(["--persona=auditor"])
+            .run()?
+    );
+
+    // Renamed owners, looked up under their canonical owner.
+    insta::assert_snapshot!(
+        "unpinned-uses-owner-aliases-config",
+        zizmor()
+            .config(input_under_test("unpinned-uses/configs/owner-aliases.yml"))
+            .input(input_under_test("unpinned-uses/owner-aliases.yml"))
+            .args(["--pedantic"])
+            .run()?
+    );
+
+    // Third-party actions evaluated against the policies.
+    insta::assert_snapshot!(
+        "unpinned-uses-third-party-policy-config",
+        zizmor()
+            .config(input_under_test(
+                "unpinned-uses/configs/third-party-policy.yml"
+            ))
+            .input(input_under_test("unpinned-uses/menagerie-of-uses.yml"))
+            .run()?
+    );
+
+    // Malformed `docker://` digests.
+    insta::assert_snapshot!(
+        "unpinned-uses-docker-digests",
+        zizmor()
+            .input(input_under_test("unpinned-uses/docker-digests.yml"))
+            .args(["--pedantic"])
+            .run()?
+    );
+
+    // Floating `docker://` tags, with the default and configured tags.
+    insta::assert_snapshot!(
+        "unpinned-uses-docker-floating-tags",
+        zizmor()
+            .input(input_under_test("unpinned-uses/docker-floating-tags.yml"))
+            .args(["--pedantic"])
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        "unpinned-uses-docker-floating-tags-config",
+        zizmor()
+            .config(input_under_test("unpinned-uses/configs/floating-tags.yml"))
+            .input(input_under_test("unpinned-uses/docker-floating-tags.yml"))
+            .run()?
+    );
+
+    // `uses:` built from expressions, including ones that are entirely
+    // an expression.
+    insta::assert_snapshot!(
+        "unpinned-uses-dynamic-uses",
+        zizmor()
+            .input(input_under_test("unpinned-uses/dynamic-uses.yml"))
+            .args(["--pedantic"])
+            .run()?
+    );
+
+    // Composite steps selected by the action's own inputs evaluate
+    // the inputs' defaults, when they're hash-pinned.
+    insta::assert_snapshot!(
+        "unpinned-uses-dynamic-uses-action",
+        zizmor()
+            .input(input_under_test("unpinned-uses/dynamic-uses-action"))
+            .args(["--pedantic"])
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        "unpinned-uses-dynamic-uses-action-no-pedantic",
+        zizmor()
+            .input(input_under_test("unpinned-uses/dynamic-uses-action"))
+            .run()?
+    );
+
+    // Findings are linked to their job and triggers, which don't
+    // affect inline ignores on neighbouring steps.
+    insta::assert_snapshot!(
+        "unpinned-uses-step-context",
+        zizmor()
+            .input(input_under_test("unpinned-uses/step-context.yml"))
+            .run()?
+    );
+
     // Invalid config: invalid policy syntax cases.
     for tc in [
         "invalid-wrong-policy-object",
@@ -227,6 +375,9 @@ fn unpinned_uses() -> Result<()> {
         "invalid-policy-syntax-4",
         "invalid-policy-syntax-5",
         "invalid-policy-syntax-6",
+        "invalid-policy-syntax-7",
+        "invalid-policy-syntax-8",
+        "invalid-override-paths",
     ] {
         insta::assert_snapshot!(
             zizmor()
@@ -339,6 +490,65 @@ fn template_injection() -> Result<()> {
             .run()?
     );
 
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("template-injection/context-severity.yml"))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("template-injection/context-severity.yml"))
+            .args(["--persona=pedantic"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn dangerous_triggers() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test(
+                "dangerous-triggers/privileged-checkout.yml"
+            ))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test(
+                "dangerous-triggers/privileged-checkout.yml"
+            ))
+            .args(["--persona=pedantic"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn artifact_poisoning() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("artifact-poisoning/workflow-run.yml"))
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn hardcoded_container_credentials() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test(
+                "hardcoded-container-credentials/registry-logins.yml"
+            ))
+            .run()?
+    );
+
     Ok(())
 }
 
@@ -357,6 +567,8 @@ fn cache_poisoning() -> Result<()> {
             .input(input_under_test(
                 "cache-poisoning/caching-enabled-by-default.yml"
             ))
+            // The publishing step is switched off with a literal `if: false`.
+            .args(["--disable=constant-conditions"])
             .run()?
     );
 
@@ -387,6 +599,7 @@ fn cache_poisoning() -> Result<()> {
     insta::assert_snapshot!(
         zizmor()
             .input(input_under_test("cache-poisoning/caching-opt-out.yml"))
+            .args(["--disable=constant-conditions"])
             .run()?
     );
 
@@ -399,6 +612,7 @@ fn cache_poisoning() -> Result<()> {
     insta::assert_snapshot!(
         zizmor()
             .input(input_under_test("cache-poisoning/workflow-tag-trigger.yml"))
+            .args(["--disable=constant-conditions"])
             .run()?
     );
 
@@ -450,6 +664,12 @@ fn cache_poisoning() -> Result<()> {
             .run()?
     );
 
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("cache-poisoning/privileged-trigger.yml"))
+            .run()?
+    );
+
     Ok(())
 }
 
@@ -553,6 +773,17 @@ fn excessive_permissions() -> Result<()> {
             .run()?
     );
 
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test(
+                "excessive-permissions/configs/allowed-permissions.yml"
+            ))
+            .input(input_under_test(
+                "excessive-permissions/workflow-write-explicit.yml"
+            ))
+            .run()?
+    );
+
     Ok(())
 }
 
@@ -576,6 +807,12 @@ fn github_env() -> Result<()> {
             .run()?
     );
 
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("github-env/attacker-input.yml"))
+            .run()?
+    );
+
     Ok(())
 }
 
@@ -587,6 +824,13 @@ fn secrets_inherit() -> Result<()> {
             .run()?
     );
 
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("secrets-inherit.yml"))
+            .args(["--persona=pedantic"])
+            .run()?
+    );
+
     Ok(())
 }
 
@@ -595,6 +839,8 @@ fn unsound_contains() -> Result<()> {
     insta::assert_snapshot!(
         zizmor()
             .input(input_under_test("unsound-contains.yml"))
+            // The fixture's placeholder contexts aren't real ones.
+            .args(["--disable=constant-conditions"])
             .run()?
     );
 
@@ -686,6 +932,96 @@ fn obfuscation() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn stale_pin_comment() -> Result<()> {
+    // Offline, only malformed version comments are flagged.
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("stale-pin-comment.yml"))
+            .args(["--persona=pedantic"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn matrix_injection() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("matrix-injection.yml"))
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn reusable_permissions() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("reusable-permissions"))
+            .run()?
+    );
+
+    // Calls to workflows that aren't audited are only flagged pedantically.
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("reusable-permissions"))
+            .args(["--pedantic"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn static_cloud_credentials() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("static-cloud-credentials.yml"))
+            .run()?
+    );
+
+    // Internal credential actions can be added with the configuration.
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("static-cloud-credentials/config.yml"))
+            .input(input_under_test("static-cloud-credentials.yml"))
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn dispatch_inputs() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("dispatch-inputs.yml"))
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn local_uses() -> Result<()> {
+    // The local actions that the workflow uses are audited with it,
+    // including the ones that they use in turn.
+    insta::assert_snapshot!(
+        zizmor()
+            .current_dir(input_under_test("local-uses"))
+            .input(".github/workflows/ci.yml")
+            .run()?
+    );
+
+    // Actions that are collected anyway aren't collected twice.
+    insta::assert_snapshot!(zizmor().input(input_under_test("local-uses")).run()?);
+
+    Ok(())
+}
+
 #[cfg_attr(not(feature = "gh-token-tests"), ignore)]
 #[test]
 fn stale_action_refs() -> Result<()> {
@@ -711,3 +1047,716 @@ fn unpinned_images() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn tpa_markdown() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("tpa/tpa-menagerie.yml"))
+            .args(["--format=tpa-markdown"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tpa_csv() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("tpa/tpa-menagerie.yml"))
+            .args(["--format=tpa-csv"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cyclonedx() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("tpa/tpa-menagerie.yml"))
+            .args(["--format=cyclonedx"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn spdx() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("spdx"))
+            .setenv("SOURCE_DATE_EPOCH", "0")
+            .args(["--format=spdx"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn html() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("html"))
+            .args(["--format=html"])
+            .run()?
+    );
+
+    // Without any actions, there's no third-party action tab.
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("tpa/tpa-no-actions.yml"))
+            .args(["--format=html"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn issue_md() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("html"))
+            .args(["--format=issue-md"])
+            .run()?
+    );
+
+    // Without any unpinned third-party actions, there's no checklist.
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("tpa/tpa-no-actions.yml"))
+            .args(["--format=issue-md"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tpa_suppressed() -> Result<()> {
+    // Only the action whose finding isn't ignored is reported, by default.
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("tpa/tpa-suppressed.yml"))
+            .args(["--format=tpa-csv"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tpa_badge() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("tpa/tpa-menagerie.yml"))
+            .args(["--format=tpa-badge"])
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("tpa/tpa-menagerie.yml"))
+            .config(input_under_test("tpa/configs/badge.yml"))
+            .args(["--format=tpa-badge"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tpa_report_stdout() -> Result<()> {
+    // With `--tpa-report -`, the JSON report is written to stdout.
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("tpa/tpa-menagerie.yml"))
+            .args(["--format=tpa-list", "--tpa-report=-"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn fail_on() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("use-trusted-publishing.yml"))
+            .args(["--fail-on=medium"])
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("use-trusted-publishing.yml"))
+            .args(["--fail-on=never"])
+            .run()?
+    );
+
+    // The configured threshold applies unless overridden on the command line.
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("use-trusted-publishing.yml"))
+            .config(input_under_test("fail-on/configs/medium.yml"))
+            .args(["--persona=pedantic"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn group_by() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("several-vulnerabilities.yml"))
+            .args(["--group-by=rule"])
+            .run()?
+    );
+
+    // Counts only include findings that survive the persona and severity filters.
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("use-trusted-publishing.yml"))
+            .args(["--group-by=rule", "--min-severity=informational"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn long_lines() -> Result<()> {
+    insta::assert_snapshot!(zizmor().input(input_under_test("long-lines.yml")).run()?);
+
+    Ok(())
+}
+
+#[test]
+fn secrets_to_third_party() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test(
+                "secrets-to-third-party/third-party-secrets.yml"
+            ))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test(
+                "secrets-to-third-party/third-party-secrets.yml"
+            ))
+            .args(["--persona=pedantic"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn curl_pipe_sh() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("curl-pipe-sh/remote-scripts.yml"))
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn known_vulnerable_actions() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test(
+                "known-vulnerable-actions/bundled-advisories.yml"
+            ))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test(
+                "known-vulnerable-actions/bundled-advisories.yml"
+            ))
+            .args([
+                "--advisories",
+                &input_under_test("known-vulnerable-actions/internal-advisories.json"),
+            ])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn typosquatting() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("typosquatting/typosquatted-actions.yml"))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("typosquatting/configs/protected.yml"))
+            .input(input_under_test("typosquatting/typosquatted-actions.yml"))
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn obfuscation_unusual_uses() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("obfuscation/unusual-uses.yml"))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("obfuscation/unusual-uses.yml"))
+            .args(["--persona=pedantic"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn secret_exposure() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("secret-exposure/exposed-secrets.yml"))
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn artifact_sensitive_paths() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test(
+                "artifact-sensitive-paths/sensitive-uploads.yml"
+            ))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test(
+                "artifact-sensitive-paths/pull-request.yml"
+            ))
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rules_config() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("rules-config/rules.yml"))
+            .input(input_under_test("rules-config/workflow.yml"))
+            .output(OutputMode::Both)
+            .run()?
+    );
+
+    // `--enable` and `--disable` override the configuration.
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("rules-config/rules.yml"))
+            .input(input_under_test("rules-config/workflow.yml"))
+            .args(["--enable=artipacked", "--disable=unpinned-uses"])
+            .output(OutputMode::Both)
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .expects_failure(true)
+            .config(input_under_test("rules-config/unknown-rule.yml"))
+            .input(input_under_test("rules-config/workflow.yml"))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .expects_failure(true)
+            .input(input_under_test("rules-config/workflow.yml"))
+            .args(["--disable=artipaked"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn persona_config() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("persona-config/persona.yml"))
+            .input(input_under_test("persona-config/workflow.yml"))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("persona-config/rule-persona.yml"))
+            .input(input_under_test("persona-config/workflow.yml"))
+            .run()?
+    );
+
+    // `--persona` overrides both the default and per-rule personas.
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("persona-config/rule-persona.yml"))
+            .input(input_under_test("persona-config/workflow.yml"))
+            .args(["--persona=regular"])
+            .output(OutputMode::Both)
+            .run()?
+    );
+
+    // Hidden findings are only included in JSON output with `--show-all`.
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("persona-config/workflow.yml"))
+            .args(["--format=json", "--show-all"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn config_ignores() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("config-ignores/config.yml"))
+            .input(input_under_test("config-ignores/workflow.yml"))
+            .run()?
+    );
+
+    // Ignored findings are included in JSON output, with their reasons.
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("config-ignores/config.yml"))
+            .input(input_under_test("config-ignores/workflow.yml"))
+            .args(["--format=json"])
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("config-ignores/config.yml"))
+            .input(input_under_test("config-ignores/workflow.yml"))
+            .args(["--no-ignores"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn check_config() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("check-config/valid.yml"))
+            .args(["--check-config"])
+            .run()?
+    );
+
+    insta::assert_snapshot!(zizmor().args(["--check-config"]).run()?);
+
+    for tc in [
+        "invalid-pattern",
+        "missing-allowlist",
+        "unknown-field",
+        "invalid-allowed-org",
+    ] {
+        insta::assert_snapshot!(
+            zizmor()
+                .expects_failure(true)
+                .config(input_under_test(&format!("check-config/{tc}.yml")))
+                .args(["--check-config"])
+                .run()?
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn list_rules() -> Result<()> {
+    insta::assert_snapshot!(zizmor().args(["--list-rules"]).run()?);
+
+    insta::assert_snapshot!(zizmor().args(["--list-rules", "--format=json"]).run()?);
+
+    insta::assert_snapshot!(
+        zizmor()
+            .expects_failure(true)
+            .args(["--list-rules", "--format=sarif"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn official_forks() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("official-forks/forked-actions.yml"))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("official-forks/forked-actions.yml"))
+            .args(["--pedantic"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn emit_allowlist() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("tpa/tpa-menagerie.yml"))
+            .args(["--emit-allowlist=-"])
+            .run()?
+    );
+
+    // No third-party actions still produce an allowlist, just an empty one.
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("artipacked.yml"))
+            .args(["--emit-allowlist=-"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn min_severity_config() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("min-severity/min-severity.yml"))
+            .input(input_under_test("unpinned-uses.yml"))
+            .output(OutputMode::Both)
+            .run()?
+    );
+
+    // The flags override the configuration.
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("min-severity/min-severity.yml"))
+            .input(input_under_test("unpinned-uses.yml"))
+            .args(["--min-severity=unknown", "--min-confidence=unknown"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn help_links() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("artipacked.yml"))
+            .args(["--no-help-links"])
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("several-vulnerabilities.yml"))
+            .args(["--group-by=rule", "--no-help-links"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn checkout_credentials() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test(
+                "checkout-credentials/pull-request-target.yml"
+            ))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test(
+                "checkout-credentials/pull-request-target.yml"
+            ))
+            .args(["--persona=auditor"])
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("checkout-credentials/third-party.yml"))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("checkout-credentials/third-party.yml"))
+            .args(["--pedantic"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn oidc_permissions() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("oidc-permissions/workflow-level.yml"))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("oidc-permissions/job-level.yml"))
+            .args(["--pedantic"])
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("oidc-permissions/config.yml"))
+            .input(input_under_test("oidc-permissions/job-level.yml"))
+            .args(["--pedantic"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn github_script_injection() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test(
+                "github-script-injection/github-script-injection.yml"
+            ))
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn deprecations() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("deprecations/deprecations.yml"))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("deprecations/composite-action/action.yml"))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("deprecations/js-action/action.yml"))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("deprecations/js-action/action.yml"))
+            .args(["--pedantic"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn constant_conditions() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test(
+                "constant-conditions/constant-conditions.yml"
+            ))
+            // `${{ false }}` is also obfuscation, which isn't under test here.
+            .args(["--disable=obfuscation"])
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn custom_rules() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("custom-rules/config.yml"))
+            .input(input_under_test("custom-rules/custom-rules.yml"))
+            .input(input_under_test("custom-rules/action/action.yml"))
+            .run()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rollup() -> Result<()> {
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("rollup"))
+            .args(["--rollup=by-action"])
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .input(input_under_test("rollup"))
+            .args(["--rollup=by-action", "--group-by=rule", "--fail-on=high"])
+            .run()?
+    );
+
+    // The configuration can count each occurrence instead, and the
+    // flags override it.
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("rollup/config.yml"))
+            .input(input_under_test("rollup"))
+            .run()?
+    );
+
+    insta::assert_snapshot!(
+        zizmor()
+            .config(input_under_test("rollup/config.yml"))
+            .input(input_under_test("rollup"))
+            .args(["--rollup=none"])
+            .run()?
+    );
+
+    Ok(())
+}